@@ -0,0 +1,131 @@
+use luhnsynth::{
+    config::Profile,
+    fraud::FraudInjector,
+    generator::TransactionStream,
+    output::{Columns, CsvWriter, NdjsonWriter, TransactionWriter},
+};
+use std::{
+    env, fs,
+    hint::black_box,
+    io::{self, BufWriter},
+    path::Path,
+    process::Command,
+    time::Instant,
+};
+
+// Rows generated for each measurement, enough to smooth out warm-up
+const ROWS: usize = 200_000;
+
+// Rows of each whole run compared against the baseline build
+const RUN_ROWS: usize = 1_000_000;
+
+// How many times faster than the baseline build whole runs must be
+const TARGET_SPEEDUP: f64 = 10.0;
+
+// Measures generation and CSV and JSON Lines writing throughput in rows per
+// second, with rows written through a buffer and thrown away, so neither
+// disk speed nor a growing buffer counts. With a baseline build of the tool
+// in LUHNSYNTH_BASELINE, whole runs of both are timed as well, and the
+// bench fails unless they're TARGET_SPEEDUP times faster than the
+// baseline's. Run with `cargo bench --bench throughput`.
+fn main() -> io::Result<()> {
+    let profile = Profile::default();
+
+    let start = Instant::now();
+    for tx in TransactionStream::new(&profile, ROWS) {
+        black_box(tx);
+    }
+    report("generate", ROWS, start.elapsed().as_secs_f64());
+
+    let start = Instant::now();
+    let stream = TransactionStream::new(&profile, ROWS);
+    for tx in FraudInjector::new(stream, &profile.fraud, profile.id_strategy).take(ROWS) {
+        black_box(tx);
+    }
+    report("generate with fraud", ROWS, start.elapsed().as_secs_f64());
+
+    let rows: Vec<_> = TransactionStream::new(&profile, ROWS).collect();
+    let start = Instant::now();
+    let mut writer = CsvWriter::new(
+        BufWriter::new(io::sink()),
+        &profile.csv,
+        profile.amount_format,
+        Columns::new(&profile),
+    )?;
+    for tx in &rows {
+        writer.write(tx)?;
+    }
+    writer.finish()?;
+    report("write csv", ROWS, start.elapsed().as_secs_f64());
+
+    let start = Instant::now();
+    let mut writer = NdjsonWriter::new(
+        BufWriter::new(io::sink()),
+        profile.amount_format,
+        Columns::new(&profile),
+    );
+    for tx in &rows {
+        writer.write(tx)?;
+    }
+    writer.finish()?;
+    report("write jsonl", ROWS, start.elapsed().as_secs_f64());
+
+    if let Some(baseline) = env::var_os("LUHNSYNTH_BASELINE") {
+        let current = env!("CARGO_BIN_EXE_luhnsynth");
+        let mut slow = Vec::new();
+        for format in ["csv", "jsonl"] {
+            let before = run(Path::new(&baseline), format)?;
+            let after = run(Path::new(current), format)?;
+            let speedup = compare(&format!("run to {}", format), RUN_ROWS, before, after);
+            if speedup < TARGET_SPEEDUP {
+                slow.push(format!("{} {:.2}x", format, speedup));
+            }
+        }
+        if !slow.is_empty() {
+            return Err(io::Error::other(format!(
+                "runs are short of {}x the baseline: {}",
+                TARGET_SPEEDUP,
+                slow.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Seconds a build of the tool takes to write a run of RUN_ROWS rows in a
+// format, to a scratch directory removed afterwards
+fn run(binary: &Path, format: &str) -> io::Result<f64> {
+    let dir = env::temp_dir().join(format!("luhnsynth-bench-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let start = Instant::now();
+    let status = Command::new(binary)
+        .arg("--quiet")
+        .arg("--output-dir")
+        .arg(&dir)
+        .args(["--sizes", &RUN_ROWS.to_string(), "--format", format])
+        .status()?;
+    let seconds = start.elapsed().as_secs_f64();
+    fs::remove_dir_all(&dir)?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} failed: {}", binary.display(), status)));
+    }
+    Ok(seconds)
+}
+
+fn report(name: &str, count: usize, seconds: f64) {
+    println!("{:<20} {:>12.0} /s", name, count as f64 / seconds);
+}
+
+// Print the rate of the baseline and current builds, returning how many
+// times faster the current one is
+fn compare(name: &str, count: usize, baseline: f64, current: f64) -> f64 {
+    let speedup = baseline / current;
+    println!(
+        "{:<20} {:>12.0} /s, baseline {:>12.0} /s, {:.2}x",
+        name,
+        count as f64 / current,
+        count as f64 / baseline,
+        speedup
+    );
+    speedup
+}
@@ -0,0 +1,477 @@
+use crate::{
+    geo::Country,
+    model::Transaction,
+    text::{Text, TextBuf},
+};
+use rand::{Rng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+
+// A postal address in one of the generated countries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Address {
+    // Street and house number, written in the country's order
+    pub street: Text,
+    pub city: Text,
+    // State, province or county of the city
+    pub region: Text,
+    pub postal_code: Text,
+    // ISO 3166-1 alpha-2 code
+    pub country: Text,
+}
+
+impl Address {
+    // A random address in one of the country's larger cities, with a postal
+    // code of that city in the country's format
+    pub fn random<R: Rng + ?Sized>(rng: &mut R, country: &Country) -> Self {
+        let format = FORMATS
+            .iter()
+            .find(|format| format.country == country.code)
+            .expect("every country has an address format");
+        let (city, region, postal_code) = format.cities.choose(rng).unwrap();
+        let street = format.streets.choose(rng).unwrap();
+        let number = rng.gen_range(1..200);
+        let mut text = TextBuf::new();
+        match format.style {
+            Style::NumberFirst => {
+                text.push_digits(number, 1);
+                text.push(' ');
+                text.push_str(street);
+            }
+            Style::NumberLast => {
+                text.push_str(street);
+                text.push(' ');
+                text.push_digits(number, 1);
+            }
+            Style::Block => {
+                for (n, max) in [6, 30, 20].into_iter().enumerate() {
+                    if n > 0 {
+                        text.push('-');
+                    }
+                    text.push_digits(rng.gen_range(1..max), 1);
+                }
+                text.push(' ');
+                text.push_str(street);
+            }
+        }
+        Self {
+            street: text.into(),
+            city: Text::from_static(city),
+            region: Text::from_static(region),
+            postal_code: postal_code_like(rng, postal_code).into(),
+            country: Text::from_static(country.code),
+        }
+    }
+
+    // The billing address written on a transaction
+    pub fn billing(tx: &Transaction) -> Self {
+        Self {
+            street: tx.billing_street.clone(),
+            city: tx.billing_city.clone(),
+            region: tx.billing_region.clone(),
+            postal_code: tx.billing_postal_code.clone(),
+            country: tx.billing_country.clone(),
+        }
+    }
+
+    // Ship the goods of a transaction to this address
+    pub fn assign_shipping(&self, tx: &mut Transaction) {
+        tx.shipping_street = Some(self.street.clone());
+        tx.shipping_city = Some(self.city.clone());
+        tx.shipping_region = Some(self.region.clone());
+        tx.shipping_postal_code = Some(self.postal_code.clone());
+        tx.shipping_country = Some(self.country.clone());
+    }
+}
+
+// Where the house number goes in a street address
+enum Style {
+    // `221 Baker Street`
+    NumberFirst,
+    // `Unter den Linden 12`
+    NumberLast,
+    // Japanese block numbering, `2-21-1 Jingumae`
+    Block,
+}
+
+struct Format {
+    country: &'static str,
+    style: Style,
+    streets: &'static [&'static str],
+    // City, region and postal code pattern, in which `#` stands for a digit
+    // and `@` for a letter
+    cities: &'static [(&'static str, &'static str, &'static str)],
+}
+
+// Postal code following a pattern, with its digits and letters drawn at
+// random. Letters leave out those easily mistaken for digits.
+fn postal_code_like<R: Rng + ?Sized>(rng: &mut R, pattern: &str) -> TextBuf {
+    let mut code = TextBuf::new();
+    for c in pattern.chars() {
+        code.push(match c {
+            '#' => char::from(b'0' + rng.gen_range(0..10)),
+            '@' => *b"ABDEFHJLNPRTUWXY".choose(rng).unwrap() as char,
+            c => c,
+        });
+    }
+    code
+}
+
+// Address formats of the countries in `geo::COUNTRIES`
+static FORMATS: [Format; 20] = [
+    Format {
+        country: "US",
+        style: Style::NumberFirst,
+        streets: &[
+            "Main Street",
+            "Oak Avenue",
+            "Maple Drive",
+            "Park Place",
+            "Washington Boulevard",
+            "Cedar Lane",
+            "Elm Street",
+            "Lakeview Road",
+        ],
+        cities: &[
+            ("New York", "NY", "100##"),
+            ("Chicago", "IL", "606##"),
+            ("San Francisco", "CA", "941##"),
+            ("Boston", "MA", "021##"),
+            ("Houston", "TX", "770##"),
+            ("Seattle", "WA", "981##"),
+            ("Miami", "FL", "331##"),
+            ("Atlanta", "GA", "303##"),
+        ],
+    },
+    Format {
+        country: "CA",
+        style: Style::NumberFirst,
+        streets: &[
+            "King Street West",
+            "Yonge Street",
+            "Robson Street",
+            "Rue Sainte-Catherine",
+            "Bank Street",
+            "Jasper Avenue",
+        ],
+        cities: &[
+            ("Toronto", "ON", "M5V #@#"),
+            ("Vancouver", "BC", "V6B #@#"),
+            ("Montreal", "QC", "H3B #@#"),
+            ("Calgary", "AB", "T2P #@#"),
+            ("Ottawa", "ON", "K1P #@#"),
+        ],
+    },
+    Format {
+        country: "MX",
+        style: Style::NumberLast,
+        streets: &[
+            "Avenida Paseo de la Reforma",
+            "Avenida Insurgentes Sur",
+            "Calle Madero",
+            "Avenida Chapultepec",
+            "Calle Hidalgo",
+        ],
+        cities: &[
+            ("Ciudad de Mexico", "CDMX", "06###"),
+            ("Guadalajara", "JAL", "44###"),
+            ("Monterrey", "NL", "64###"),
+            ("Puebla", "PUE", "72###"),
+        ],
+    },
+    Format {
+        country: "BR",
+        style: Style::NumberLast,
+        streets: &[
+            "Avenida Paulista",
+            "Rua Augusta",
+            "Rua Oscar Freire",
+            "Avenida Atlantica",
+            "Rua das Flores",
+            "Avenida Brasil",
+        ],
+        cities: &[
+            ("Sao Paulo", "SP", "01###-###"),
+            ("Rio de Janeiro", "RJ", "22###-###"),
+            ("Belo Horizonte", "MG", "30###-###"),
+            ("Curitiba", "PR", "80###-###"),
+            ("Porto Alegre", "RS", "90###-###"),
+        ],
+    },
+    Format {
+        country: "GB",
+        style: Style::NumberFirst,
+        streets: &[
+            "High Street",
+            "Station Road",
+            "Church Lane",
+            "Victoria Road",
+            "Green Lane",
+            "Park Road",
+        ],
+        cities: &[
+            ("London", "Greater London", "SW1A #@@"),
+            ("London", "Greater London", "EC1A #@@"),
+            ("Manchester", "Greater Manchester", "M1 #@@"),
+            ("Birmingham", "West Midlands", "B1 #@@"),
+            ("Leeds", "West Yorkshire", "LS1 #@@"),
+            ("Edinburgh", "City of Edinburgh", "EH1 #@@"),
+        ],
+    },
+    Format {
+        country: "IE",
+        style: Style::NumberFirst,
+        streets: &[
+            "O'Connell Street",
+            "Grafton Street",
+            "Patrick Street",
+            "Shop Street",
+            "Main Street",
+        ],
+        cities: &[
+            ("Dublin", "County Dublin", "D02 @###"),
+            ("Cork", "County Cork", "T12 @###"),
+            ("Galway", "County Galway", "H91 @###"),
+            ("Limerick", "County Limerick", "V94 @###"),
+        ],
+    },
+    Format {
+        country: "FR",
+        style: Style::NumberFirst,
+        streets: &[
+            "Rue de Rivoli",
+            "Rue de la Paix",
+            "Avenue Victor Hugo",
+            "Boulevard Saint-Germain",
+            "Rue de la Republique",
+            "Rue Pasteur",
+        ],
+        cities: &[
+            ("Paris", "Ile-de-France", "750##"),
+            ("Lyon", "Auvergne-Rhone-Alpes", "6900#"),
+            ("Marseille", "Provence-Alpes-Cote d'Azur", "130##"),
+            ("Toulouse", "Occitanie", "3100#"),
+            ("Bordeaux", "Nouvelle-Aquitaine", "3300#"),
+        ],
+    },
+    Format {
+        country: "DE",
+        style: Style::NumberLast,
+        streets: &[
+            "Hauptstrasse",
+            "Bahnhofstrasse",
+            "Gartenstrasse",
+            "Schillerstrasse",
+            "Goethestrasse",
+            "Lindenallee",
+        ],
+        cities: &[
+            ("Berlin", "Berlin", "10###"),
+            ("Hamburg", "Hamburg", "20###"),
+            ("Munchen", "Bayern", "80###"),
+            ("Koln", "Nordrhein-Westfalen", "50###"),
+            ("Frankfurt am Main", "Hessen", "60###"),
+        ],
+    },
+    Format {
+        country: "NL",
+        style: Style::NumberLast,
+        streets: &[
+            "Kalverstraat",
+            "Prinsengracht",
+            "Dorpsstraat",
+            "Kerkstraat",
+            "Stationsweg",
+        ],
+        cities: &[
+            ("Amsterdam", "Noord-Holland", "10## @@"),
+            ("Rotterdam", "Zuid-Holland", "30## @@"),
+            ("Den Haag", "Zuid-Holland", "25## @@"),
+            ("Utrecht", "Utrecht", "35## @@"),
+        ],
+    },
+    Format {
+        country: "BE",
+        style: Style::NumberLast,
+        streets: &[
+            "Rue Neuve",
+            "Meir",
+            "Avenue Louise",
+            "Veldstraat",
+            "Kerkstraat",
+        ],
+        cities: &[
+            ("Bruxelles", "Brussels-Capital", "10#0"),
+            ("Antwerpen", "Antwerpen", "20#0"),
+            ("Gent", "Oost-Vlaanderen", "90#0"),
+            ("Liege", "Liege", "40#0"),
+        ],
+    },
+    Format {
+        country: "ES",
+        style: Style::NumberLast,
+        streets: &[
+            "Calle Mayor",
+            "Gran Via",
+            "Calle de Alcala",
+            "Paseo de Gracia",
+            "Calle Real",
+            "Avenida de la Constitucion",
+        ],
+        cities: &[
+            ("Madrid", "Madrid", "280##"),
+            ("Barcelona", "Barcelona", "080##"),
+            ("Valencia", "Valencia", "460##"),
+            ("Sevilla", "Sevilla", "410##"),
+        ],
+    },
+    Format {
+        country: "LT",
+        style: Style::NumberLast,
+        streets: &[
+            "Gedimino prospektas",
+            "Vilniaus gatve",
+            "Laisves aleja",
+            "Pilies gatve",
+        ],
+        cities: &[
+            ("Vilnius", "Vilnius County", "LT-0####"),
+            ("Kaunas", "Kaunas County", "LT-4####"),
+            ("Klaipeda", "Klaipeda County", "LT-9####"),
+        ],
+    },
+    Format {
+        country: "RU",
+        style: Style::NumberLast,
+        streets: &[
+            "ulitsa Tverskaya",
+            "ulitsa Arbat",
+            "Nevsky prospekt",
+            "ulitsa Lenina",
+            "ulitsa Sadovaya",
+        ],
+        cities: &[
+            ("Moskva", "Moskva", "1#####"),
+            ("Sankt-Peterburg", "Sankt-Peterburg", "19####"),
+            ("Novosibirsk", "Novosibirskaya oblast", "630###"),
+            ("Yekaterinburg", "Sverdlovskaya oblast", "620###"),
+        ],
+    },
+    Format {
+        country: "NG",
+        style: Style::NumberFirst,
+        streets: &[
+            "Adeola Odeku Street",
+            "Allen Avenue",
+            "Awolowo Road",
+            "Ahmadu Bello Way",
+            "Broad Street",
+        ],
+        cities: &[
+            ("Lagos", "Lagos", "1012##"),
+            ("Abuja", "FCT", "9002##"),
+            ("Ibadan", "Oyo", "2002##"),
+            ("Port Harcourt", "Rivers", "5002##"),
+        ],
+    },
+    Format {
+        country: "ZA",
+        style: Style::NumberFirst,
+        streets: &[
+            "Jan Smuts Avenue",
+            "Long Street",
+            "Church Street",
+            "Main Road",
+            "Oxford Road",
+        ],
+        cities: &[
+            ("Johannesburg", "Gauteng", "20##"),
+            ("Cape Town", "Western Cape", "80##"),
+            ("Durban", "KwaZulu-Natal", "40##"),
+            ("Pretoria", "Gauteng", "00##"),
+        ],
+    },
+    Format {
+        country: "IN",
+        style: Style::NumberFirst,
+        streets: &[
+            "MG Road",
+            "Linking Road",
+            "Park Street",
+            "Brigade Road",
+            "Anna Salai",
+            "Nehru Place",
+        ],
+        cities: &[
+            ("Mumbai", "Maharashtra", "4000##"),
+            ("New Delhi", "Delhi", "1100##"),
+            ("Bengaluru", "Karnataka", "5600##"),
+            ("Chennai", "Tamil Nadu", "6000##"),
+            ("Kolkata", "West Bengal", "7000##"),
+        ],
+    },
+    Format {
+        country: "CN",
+        style: Style::NumberFirst,
+        streets: &[
+            "Nanjing Road",
+            "Huaihai Road",
+            "Changan Avenue",
+            "Wangfujing Street",
+            "Tianhe Road",
+        ],
+        cities: &[
+            ("Shanghai", "Shanghai", "200###"),
+            ("Beijing", "Beijing", "100###"),
+            ("Guangzhou", "Guangdong", "510###"),
+            ("Shenzhen", "Guangdong", "518###"),
+            ("Chengdu", "Sichuan", "610###"),
+        ],
+    },
+    Format {
+        country: "JP",
+        style: Style::Block,
+        streets: &["Jingumae", "Ginza", "Nishishinjuku", "Umeda", "Sakae"],
+        cities: &[
+            ("Shibuya-ku", "Tokyo", "150-00##"),
+            ("Chuo-ku", "Tokyo", "104-00##"),
+            ("Shinjuku-ku", "Tokyo", "160-00##"),
+            ("Osaka", "Osaka", "530-00##"),
+            ("Nagoya", "Aichi", "460-00##"),
+        ],
+    },
+    Format {
+        country: "SG",
+        style: Style::NumberFirst,
+        streets: &[
+            "Orchard Road",
+            "Tanjong Pagar Road",
+            "Serangoon Road",
+            "Holland Avenue",
+            "Tampines Avenue",
+        ],
+        cities: &[
+            ("Singapore", "Central Region", "23####"),
+            ("Singapore", "Central Region", "08####"),
+            ("Singapore", "East Region", "52####"),
+        ],
+    },
+    Format {
+        country: "AU",
+        style: Style::NumberFirst,
+        streets: &[
+            "George Street",
+            "Collins Street",
+            "Queen Street",
+            "Hay Street",
+            "King William Street",
+            "Pitt Street",
+        ],
+        cities: &[
+            ("Sydney", "NSW", "20##"),
+            ("Melbourne", "VIC", "30##"),
+            ("Brisbane", "QLD", "40##"),
+            ("Perth", "WA", "60##"),
+            ("Adelaide", "SA", "50##"),
+        ],
+    },
+];
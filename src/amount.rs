@@ -0,0 +1,166 @@
+use rand::Rng;
+use rand_distr::{Distribution, LogNormal, Pareto};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Shape of the amounts drawn for a merchant category, in major currency units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "distribution", rename_all = "snake_case")]
+pub enum AmountDistribution {
+    // Every amount between `min` and `max` is equally likely
+    Uniform {
+        min: f64,
+        max: f64,
+    },
+    // Most amounts cluster around `median`, with `sigma` controlling the
+    // spread on a log scale; optionally clamped to `min`/`max`
+    LogNormal {
+        median: f64,
+        sigma: f64,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    // Heavy-tailed amounts starting at `scale`; smaller `shape` values give
+    // more large outliers. Optionally capped at `max`.
+    Pareto {
+        scale: f64,
+        shape: f64,
+        max: Option<f64>,
+    },
+}
+
+impl AmountDistribution {
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        let (value, min, max) = match *self {
+            AmountDistribution::Uniform { min, max } => (rng.gen_range(min..=max), min, max),
+            AmountDistribution::LogNormal {
+                median,
+                sigma,
+                min,
+                max,
+            } => {
+                let value = LogNormal::new(median.ln(), sigma)
+                    .expect("amount distributions are validated on load")
+                    .sample(rng);
+                (value, min.unwrap_or(0.0), max.unwrap_or(f64::INFINITY))
+            }
+            AmountDistribution::Pareto { scale, shape, max } => {
+                let value = Pareto::new(scale, shape)
+                    .expect("amount distributions are validated on load")
+                    .sample(rng);
+                (value, scale, max.unwrap_or(f64::INFINITY))
+            }
+        };
+        value.clamp(min, max)
+    }
+
+    // Describe the first invalid parameter, if any
+    pub fn check(&self) -> Result<(), String> {
+        let positive = |name: &str, value: f64| {
+            if value.is_finite() && value > 0.0 {
+                Ok(())
+            } else {
+                Err(format!("{} must be a positive number", name))
+            }
+        };
+        let ordered = |min: Option<f64>, max: Option<f64>| match (min, max) {
+            (Some(min), Some(max)) if min > max => Err("min must not exceed max".to_string()),
+            _ => Ok(()),
+        };
+        match *self {
+            AmountDistribution::Uniform { min, max } => {
+                positive("min", min)?;
+                positive("max", max)?;
+                ordered(Some(min), Some(max))
+            }
+            AmountDistribution::LogNormal {
+                median,
+                sigma,
+                min,
+                max,
+            } => {
+                positive("median", median)?;
+                positive("sigma", sigma)?;
+                ordered(min, max)
+            }
+            AmountDistribution::Pareto { scale, shape, max } => {
+                positive("scale", scale)?;
+                positive("shape", shape)?;
+                ordered(Some(scale), max)
+            }
+        }
+    }
+}
+
+// Amount settings of a profile: a default distribution plus overrides keyed
+// by merchant category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AmountConfig {
+    pub default: AmountDistribution,
+    pub categories: BTreeMap<String, AmountDistribution>,
+}
+
+impl AmountConfig {
+    // Distribution to draw from for a merchant category
+    pub fn for_category(&self, category: &str) -> &AmountDistribution {
+        self.categories.get(category).unwrap_or(&self.default)
+    }
+}
+
+impl Default for AmountConfig {
+    fn default() -> Self {
+        let log_normal = |median, sigma, min, max| AmountDistribution::LogNormal {
+            median,
+            sigma,
+            min: Some(min),
+            max: Some(max),
+        };
+        Self {
+            default: AmountDistribution::Uniform {
+                min: 1.0,
+                max: 1000.0,
+            },
+            // Typical ticket sizes for the built-in merchant categories
+            categories: BTreeMap::from([
+                ("Retail".to_string(), log_normal(45.0, 0.8, 2.0, 1500.0)),
+                ("Grocery".to_string(), log_normal(55.0, 0.6, 3.0, 400.0)),
+                (
+                    "Electronics".to_string(),
+                    log_normal(180.0, 0.9, 10.0, 3000.0),
+                ),
+                (
+                    "Food & Beverage".to_string(),
+                    log_normal(6.5, 0.35, 3.0, 15.0),
+                ),
+                (
+                    "Health & Fitness".to_string(),
+                    log_normal(50.0, 0.5, 10.0, 300.0),
+                ),
+                (
+                    "Books & Media".to_string(),
+                    log_normal(20.0, 0.5, 5.0, 150.0),
+                ),
+                (
+                    "Convenience Store".to_string(),
+                    log_normal(12.0, 0.6, 1.0, 80.0),
+                ),
+                ("Clothing".to_string(), log_normal(70.0, 0.7, 10.0, 800.0)),
+                (
+                    "Travel".to_string(),
+                    AmountDistribution::Pareto {
+                        scale: 200.0,
+                        shape: 1.6,
+                        max: Some(3000.0),
+                    },
+                ),
+                ("Restaurant".to_string(), log_normal(60.0, 0.6, 10.0, 500.0)),
+                ("Lodging".to_string(), log_normal(180.0, 0.6, 50.0, 2500.0)),
+                (
+                    "Car Rental".to_string(),
+                    log_normal(120.0, 0.5, 30.0, 1500.0),
+                ),
+            ]),
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{
+    address::Address, brand::CardBrand, config::Profile, generator, geo, loader, luhn, names::Name,
+    track,
+};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+};
+
+// Replaces the personal data in a dataset of real transactions with made-up
+// values, leaving every other column as it is. A value is always replaced
+// by the same made-up one within a run, so cards, customers and devices can
+// still be followed across rows.
+pub struct Anonymizer<'a> {
+    profile: &'a Profile,
+    rng: StdRng,
+    pans: HashMap<String, String>,
+    // Keyed by card number and CVV, so cards sharing a CVV don't share the
+    // made-up one
+    cvvs: HashMap<(String, String), String>,
+    names: HashMap<String, Name>,
+    emails: HashMap<String, String>,
+    phones: HashMap<String, String>,
+    ips: HashMap<String, String>,
+    streets: HashMap<String, String>,
+    // Made-up track data by the original, which both tracks of a card share
+    track_data: HashMap<String, String>,
+    // Values replaced, by column
+    replaced: BTreeMap<String, u64>,
+    // Card numbers of no brand the profile knows, given random digits
+    unknown_brands: u64,
+}
+
+impl<'a> Anonymizer<'a> {
+    pub fn new(profile: &'a Profile) -> Self {
+        Self {
+            profile,
+            rng: StdRng::from_entropy(),
+            pans: HashMap::new(),
+            cvvs: HashMap::new(),
+            names: HashMap::new(),
+            emails: HashMap::new(),
+            phones: HashMap::new(),
+            ips: HashMap::new(),
+            streets: HashMap::new(),
+            track_data: HashMap::new(),
+            replaced: BTreeMap::new(),
+            unknown_brands: 0,
+        }
+    }
+
+    pub fn replaced(&self) -> &BTreeMap<String, u64> {
+        &self.replaced
+    }
+
+    pub fn unknown_brands(&self) -> u64 {
+        self.unknown_brands
+    }
+
+    // Anonymize a CSV file with a header row, or a JSON Lines file, as told
+    // by its extension, writing it to `out` in the same format. Returns the
+    // number of rows.
+    pub fn file(&mut self, input: &Path, out: impl Write) -> io::Result<u64> {
+        match input.extension().and_then(|e| e.to_str()) {
+            Some("csv") => self.csv(input, out),
+            Some("jsonl" | "ndjson") => self.jsonl(input, out),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "can't tell the format of {} from its extension; expected .csv or .jsonl",
+                    input.display()
+                ),
+            )),
+        }
+    }
+
+    fn csv(&mut self, input: &Path, out: impl Write) -> io::Result<u64> {
+        let mut reader = csv::Reader::from_path(input).map_err(|e| loader::invalid(input, e))?;
+        let headers = reader
+            .headers()
+            .map_err(|e| loader::invalid(input, e))?
+            .clone();
+        let mut writer = csv::Writer::from_writer(out);
+        writer.write_record(&headers)?;
+        let mut rows = 0;
+        for record in reader.records() {
+            let record = record.map_err(|e| loader::invalid(input, e))?;
+            let mut row: Vec<(String, String)> = headers
+                .iter()
+                .zip(record.iter())
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            self.row(&mut row);
+            writer.write_record(row.iter().map(|(_, value)| value))?;
+            rows += 1;
+        }
+        writer.flush()?;
+        Ok(rows)
+    }
+
+    // Rows are objects whose string fields are anonymized; fields keep their
+    // order and other values are written back untouched
+    fn jsonl(&mut self, input: &Path, mut out: impl Write) -> io::Result<u64> {
+        let reader = BufReader::new(File::open(input)?);
+        let mut rows = 0;
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut object: Map<String, Value> = serde_json::from_str(&line)
+                .map_err(|e| loader::invalid(input, format!("line {}: {}", i + 1, e)))?;
+            let mut row: Vec<(String, String)> = object
+                .iter()
+                .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+                .collect();
+            self.row(&mut row);
+            for (name, value) in row {
+                object.insert(name, Value::String(value));
+            }
+            serde_json::to_writer(&mut out, &object)?;
+            writeln!(out)?;
+            rows += 1;
+        }
+        out.flush()?;
+        Ok(rows)
+    }
+
+    // Replace the personal data of a row of named fields in place
+    fn row(&mut self, row: &mut [(String, String)]) {
+        let original: HashMap<String, String> = row.iter().cloned().collect();
+        let get = |name: &str| original.get(name).filter(|value| !value.is_empty());
+        // Made-up phones, addresses and IPs are from the country the row
+        // gives for them, where there is one
+        let country = |rng: &mut StdRng, name: &str| {
+            get(name)
+                .and_then(|code| geo::country(code))
+                .unwrap_or_else(|| geo::random_country(rng))
+        };
+        for (name, value) in row.iter_mut() {
+            if value.is_empty() {
+                continue;
+            }
+            let anonymized = match name.as_str() {
+                "card_number" | "token_pan" => self.pan(value),
+                "cvv" => {
+                    let pan = get("card_number").cloned().unwrap_or_default();
+                    self.cvvs
+                        .entry((pan, value.clone()))
+                        .or_insert_with(|| digits(&mut self.rng, value.len()))
+                        .clone()
+                }
+                "cardholder_name" => self.name(value).full.to_string(),
+                "email" => match self.emails.get(value.as_str()) {
+                    Some(email) => email.clone(),
+                    None => {
+                        // Addresses are made up from the row's made-up name
+                        let name = match get("cardholder_name") {
+                            Some(holder) => self.name(holder).clone(),
+                            None => self.random_name(),
+                        };
+                        let email = name.email(&mut self.rng).to_string();
+                        self.emails.insert(value.clone(), email.clone());
+                        email
+                    }
+                },
+                "phone" => {
+                    let home = country(&mut self.rng, "billing_country");
+                    self.phones
+                        .entry(value.clone())
+                        .or_insert_with(|| home.phone_number(&mut self.rng).into())
+                        .clone()
+                }
+                "ip_address" => {
+                    let (home, ipv6) = (country(&mut self.rng, "ip_country"), value.contains(':'));
+                    self.ips
+                        .entry(value.clone())
+                        .or_insert_with(|| home.ip_address(&mut self.rng, ipv6).into())
+                        .clone()
+                }
+                "billing_street" | "shipping_street" => {
+                    let home = country(&mut self.rng, &name.replace("street", "country"));
+                    self.streets
+                        .entry(value.clone())
+                        .or_insert_with(|| Address::random(&mut self.rng, home).street.into())
+                        .clone()
+                }
+                "track1" | "track2" => {
+                    let holder = get("cardholder_name").cloned();
+                    match self.track(value, holder.as_deref()) {
+                        Some(track) => track,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            *value = anonymized;
+            *self.replaced.entry(name.clone()).or_default() += 1;
+        }
+    }
+
+    // A card number of the same brand and length, valid under Luhn, with
+    // any spaces or dashes where the original had them. Values that aren't
+    // card numbers, such as masked ones, are left as they are.
+    fn pan(&mut self, value: &str) -> String {
+        if let Some(pan) = self.pans.get(value) {
+            return pan.clone();
+        }
+        let digits_only: String = value.chars().filter(|c| *c != ' ' && *c != '-').collect();
+        if !(12..=19).contains(&digits_only.len())
+            || !digits_only.chars().all(|c| c.is_ascii_digit())
+        {
+            return value.to_string();
+        }
+        let length = digits_only.len();
+        let made_up = match CardBrand::detect_in(&digits_only, &self.profile.card_brands) {
+            Some(brand) => {
+                let brand = CardBrand {
+                    lengths: vec![length],
+                    ..brand.clone()
+                };
+                generator::generate_card_number(&mut self.rng, &brand, &[]).to_string()
+            }
+            // Numbers of unknown brands keep only their major industry
+            // identifier
+            None => {
+                self.unknown_brands += 1;
+                luhn::complete(&format!(
+                    "{}{}",
+                    &digits_only[..1],
+                    digits(&mut self.rng, length - 2)
+                ))
+            }
+        };
+        let mut made_up = made_up.chars();
+        let pan: String = value
+            .chars()
+            .map(|c| match c {
+                ' ' | '-' => c,
+                _ => made_up.next().unwrap_or(c),
+            })
+            .collect();
+        self.pans.insert(value.to_string(), pan.clone());
+        pan
+    }
+
+    // A track rebuilt around the made-up card number and, on Track 1, name,
+    // keeping the expiry date and service code but not the discretionary
+    // data, which holds the card's verification values. Values that aren't
+    // tracks are left as they are.
+    fn track(&mut self, value: &str, holder: Option<&str>) -> Option<String> {
+        if let Some(track) = value.strip_prefix("%B") {
+            let (pan, rest) = track.split_once('^')?;
+            let (_, rest) = rest.split_once('^')?;
+            let (data, _) = rest.rsplit_once('?')?;
+            let name = match holder {
+                Some(holder) => track::name(&self.name(holder).full),
+                None => track::name(&self.random_name().full),
+            };
+            let (pan, data) = (self.pan(pan), self.track_data(data));
+            Some(track::track1(&pan, &name, &data))
+        } else if let Some(track) = value.strip_prefix(';') {
+            let (pan, rest) = track.split_once('=')?;
+            let (data, _) = rest.rsplit_once('?')?;
+            let (pan, data) = (self.pan(pan), self.track_data(data));
+            Some(track::track2(&pan, &data))
+        } else {
+            None
+        }
+    }
+
+    // Expiry date and service code, then random discretionary data
+    fn track_data(&mut self, data: &str) -> String {
+        self.track_data
+            .entry(data.to_string())
+            .or_insert_with(|| {
+                let kept: String = data.chars().take(7).collect();
+                let rest = data.chars().count() - kept.chars().count();
+                format!("{}{}", kept, digits(&mut self.rng, rest))
+            })
+            .clone()
+    }
+
+    fn name(&mut self, value: &str) -> &Name {
+        if !self.names.contains_key(value) {
+            let name = self.random_name();
+            self.names.insert(value.to_string(), name);
+        }
+        &self.names[value]
+    }
+
+    fn random_name(&mut self) -> Name {
+        Name::latin(
+            self.profile.first_names.choose(&mut self.rng).unwrap(),
+            self.profile.last_names.choose(&mut self.rng).unwrap(),
+        )
+    }
+}
+
+// Random decimal digits
+fn digits<R: Rng + ?Sized>(rng: &mut R, count: usize) -> String {
+    (0..count)
+        .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+        .collect()
+}
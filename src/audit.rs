@@ -0,0 +1,119 @@
+use crate::model::{LifecycleEvent, Transaction};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+};
+
+// Checks a dataset's rows for identifiers that are meant to be unique but
+// repeat. Every transaction (or lifecycle event of one) has its own ID, and a
+// card number or device always belongs to the same single customer, while
+// that customer only ever uses one card. Rows derived from another row, like
+// refunds and fraud patterns, keep the customer and card they were made with.
+// Fraud rows are exempt from device ownership, since fraud rings and card
+// sprays use one fraudster's device for many stolen cards.
+#[derive(Default)]
+pub struct UniquenessAudit {
+    transactions: HashSet<(String, Option<LifecycleEvent>)>,
+    // Owner of each card number and device, and card of each customer
+    card_owners: HashMap<String, String>,
+    device_owners: HashMap<String, String>,
+    customer_cards: HashMap<String, String>,
+    // Whether customers may share a card number, as they do when cards are
+    // drawn from the few published test cards
+    shared_cards: bool,
+    rows: usize,
+}
+
+impl UniquenessAudit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Allow a card number to belong to several customers
+    pub fn with_shared_cards(mut self) -> Self {
+        self.shared_cards = true;
+        self
+    }
+
+    // Record a row, failing on the first identifier that repeats
+    pub fn check(&mut self, tx: &Transaction) -> io::Result<()> {
+        self.rows += 1;
+        if !self
+            .transactions
+            .insert((tx.transaction_id.to_string(), tx.event_type))
+        {
+            return Err(self.duplicate("transaction_id", &tx.transaction_id));
+        }
+        if !self.shared_cards && !claim(&mut self.card_owners, &tx.card_number, &tx.customer_id) {
+            return Err(self.duplicate("card_number", &tx.card_number));
+        }
+        if let Some(device_id) = &tx.device_id
+            && !tx.is_fraud
+            && !claim(&mut self.device_owners, device_id, &tx.customer_id)
+        {
+            return Err(self.duplicate("device_id", device_id));
+        }
+        if !claim(&mut self.customer_cards, &tx.customer_id, &tx.card_number) {
+            return Err(self.duplicate("customer_id", &tx.customer_id));
+        }
+        Ok(())
+    }
+
+    fn duplicate(&self, field: &str, value: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "uniqueness check failed: {} {} repeats at row {}",
+                field, value, self.rows
+            ),
+        )
+    }
+}
+
+// Record that a key belongs to an owner, returning false when it already
+// belongs to another one
+fn claim(owners: &mut HashMap<String, String>, key: &str, owner: &str) -> bool {
+    match owners.get(key) {
+        Some(existing) => existing == owner,
+        None => {
+            owners.insert(key.to_string(), owner.to_string());
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Profile,
+        fraud::{FraudConfig, FraudInjector, FraudPattern},
+        generator::TransactionStream,
+    };
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn fraud_devices_pass_the_audit() {
+        for pattern in [FraudPattern::FraudRing, FraudPattern::CardSpray] {
+            let profile = Profile {
+                fraud: FraudConfig {
+                    rate: 0.1,
+                    patterns: BTreeMap::from([(pattern, 1.0)]),
+                    ..FraudConfig::default()
+                },
+                ..Profile::default()
+            };
+            let stream = TransactionStream::new(&profile, usize::MAX);
+            let rows = FraudInjector::new(stream, &profile.fraud, profile.id_strategy).take(5_000);
+            let mut audit = UniquenessAudit::new();
+            let mut fraud = 0;
+            for tx in rows {
+                audit
+                    .check(&tx)
+                    .unwrap_or_else(|e| panic!("{:?}: {}", pattern, e));
+                fraud += tx.is_fraud as usize;
+            }
+            assert!(fraud > 0, "{:?} made no fraud rows", pattern);
+        }
+    }
+}
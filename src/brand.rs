@@ -0,0 +1,353 @@
+use crate::config::to_strings;
+use serde::{Deserialize, Serialize};
+
+// Struct to store card brand information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardBrand {
+    pub name: String,
+    pub prefix: Vec<String>,
+    pub lengths: Vec<usize>,
+    pub cvv_length: usize,
+    // Published network and gateway test card numbers, used instead of
+    // generated ones with `test_cards_only`
+    #[serde(default)]
+    pub test_cards: Vec<String>,
+    // EMV application identifier (AID) of the brand's chip cards, in hex
+    #[serde(default)]
+    pub aid: Option<String>,
+}
+
+// A single prefix entry of a card brand: either a fixed IIN such as "6011"
+// or an inclusive range of equal-length IINs such as "3528-3589"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IinRange {
+    pub start: u64,
+    pub end: u64,
+    pub digits: usize,
+}
+
+impl IinRange {
+    pub fn parse(prefix: &str) -> Option<Self> {
+        let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+        let (start, end) = prefix.split_once('-').unwrap_or((prefix, prefix));
+        let (start, end) = (start.trim(), end.trim());
+        if !is_digits(start) || !is_digits(end) || start.len() != end.len() {
+            return None;
+        }
+        let range = Self {
+            start: start.parse().ok()?,
+            end: end.parse().ok()?,
+            digits: start.len(),
+        };
+        (range.start <= range.end).then_some(range)
+    }
+
+    // Whether the leading digits of a PAN fall inside the range
+    pub fn contains(&self, pan: &str) -> bool {
+        pan.get(..self.digits)
+            .and_then(|iin| iin.parse::<u64>().ok())
+            .is_some_and(|iin| (self.start..=self.end).contains(&iin))
+    }
+
+    // Render an IIN from the range with its leading zeros intact
+    pub fn format(&self, iin: u64) -> String {
+        format!("{:0width$}", iin, width = self.digits)
+    }
+}
+
+impl CardBrand {
+    // Detect the brand of a PAN among the built-in brands
+    pub fn detect(pan: &str) -> Option<CardBrand> {
+        Self::detect_in(pan, &builtin_brands()).cloned()
+    }
+
+    // Detect the brand of a PAN among the given brands. Overlapping ranges are
+    // resolved in favor of brands that issue the PAN's length, then the most
+    // specific (longest) matching IIN.
+    pub fn detect_in<'a>(pan: &str, brands: &'a [CardBrand]) -> Option<&'a CardBrand> {
+        let digits: String = pan.chars().filter(|c| *c != ' ' && *c != '-').collect();
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        brands
+            .iter()
+            .filter_map(|brand| {
+                let specificity = brand.matched_iin_digits(&digits)?;
+                Some(((brand.lengths.contains(&digits.len()), specificity), brand))
+            })
+            .max_by_key(|(rank, _)| *rank)
+            .map(|(_, brand)| brand)
+    }
+
+    // Test card numbers of this brand, falling back to those of the built-in
+    // brand with the same name when the profile lists none
+    pub fn test_cards(&self) -> Vec<String> {
+        if !self.test_cards.is_empty() {
+            return self.test_cards.clone();
+        }
+        builtin_brands()
+            .into_iter()
+            .find(|brand| brand.name == self.name)
+            .map(|brand| brand.test_cards)
+            .unwrap_or_default()
+    }
+
+    // EMV application identifier of this brand, falling back to that of the
+    // built-in brand with the same name when the profile gives none
+    pub fn aid(&self) -> Option<String> {
+        if self.aid.is_some() {
+            return self.aid.clone();
+        }
+        builtin_brands()
+            .into_iter()
+            .find(|brand| brand.name == self.name)
+            .and_then(|brand| brand.aid)
+    }
+
+    // Length of the longest IIN of this brand that the digits start with
+    fn matched_iin_digits(&self, digits: &str) -> Option<usize> {
+        self.prefix
+            .iter()
+            .filter_map(|p| IinRange::parse(p))
+            .filter(|range| range.contains(digits))
+            .map(|range| range.digits)
+            .max()
+    }
+}
+
+// Built-in card brands
+pub fn builtin_brands() -> Vec<CardBrand> {
+    vec![
+        CardBrand {
+            name: "Visa".to_string(),
+            prefix: to_strings(&["4"]),
+            lengths: vec![16],
+            cvv_length: 3,
+            test_cards: to_strings(&[
+                "4111111111111111",
+                "4012888888881881",
+                "4242424242424242",
+                "4000056655665556",
+                "4005519200000004",
+                "4009348888881881",
+                "4012000033330026",
+                "4012000077777777",
+                "4217651111111119",
+                "4500600000000061",
+            ]),
+            aid: Some("A0000000031010".to_string()),
+        },
+        CardBrand {
+            name: "Mastercard".to_string(),
+            prefix: to_strings(&["51-55", "2221-2720"]),
+            lengths: vec![16],
+            cvv_length: 3,
+            test_cards: to_strings(&[
+                "5555555555554444",
+                "5105105105105100",
+                "5200828282828210",
+                "5454545454545454",
+                "2223003122003222",
+                "2223000048400011",
+            ]),
+            aid: Some("A0000000041010".to_string()),
+        },
+        CardBrand {
+            name: "American Express".to_string(),
+            prefix: to_strings(&["34", "37"]),
+            lengths: vec![15],
+            cvv_length: 4,
+            test_cards: to_strings(&[
+                "378282246310005",
+                "371449635398431",
+                "378734493671000",
+                "370000000000002",
+            ]),
+            aid: Some("A00000002501".to_string()),
+        },
+        CardBrand {
+            name: "Discover".to_string(),
+            prefix: to_strings(&["6011", "644-649", "65"]),
+            lengths: vec![16],
+            cvv_length: 3,
+            test_cards: to_strings(&[
+                "6011111111111117",
+                "6011000990139424",
+                "6011981111111113",
+                "6445644564456445",
+            ]),
+            aid: Some("A0000001523010".to_string()),
+        },
+        CardBrand {
+            name: "JCB".to_string(),
+            prefix: to_strings(&["3528-3589"]),
+            lengths: vec![16, 17, 18, 19],
+            cvv_length: 3,
+            test_cards: to_strings(&["3530111333300000", "3566002020360505", "3566111111111113"]),
+            aid: Some("A0000000651010".to_string()),
+        },
+        CardBrand {
+            name: "UnionPay".to_string(),
+            prefix: to_strings(&["62"]),
+            lengths: vec![16, 17, 18, 19],
+            cvv_length: 3,
+            test_cards: to_strings(&["6200000000000005", "6205500000000000004"]),
+            aid: Some("A000000333010101".to_string()),
+        },
+        CardBrand {
+            name: "Diners Club".to_string(),
+            prefix: to_strings(&["300-305", "3095", "36", "38-39"]),
+            lengths: vec![14, 16],
+            cvv_length: 3,
+            test_cards: to_strings(&[
+                "30569309025904",
+                "38520000023237",
+                "36227206271667",
+                "3056930009020004",
+            ]),
+            aid: Some("A0000001523010".to_string()),
+        },
+        CardBrand {
+            name: "Maestro".to_string(),
+            prefix: to_strings(&["50", "56-58", "6304", "6759", "6761-6763"]),
+            lengths: vec![12, 13, 14, 15, 16, 17, 18, 19],
+            cvv_length: 3,
+            test_cards: to_strings(&["6759649826438453", "6304000000000000"]),
+            aid: Some("A0000000043060".to_string()),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detected(pan: &str) -> Option<String> {
+        CardBrand::detect(pan).map(|brand| brand.name)
+    }
+
+    #[test]
+    fn discover_ranges_are_told_apart() {
+        for pan in [
+            "6011111111111117",
+            "6445644564456445",
+            "6490000000000000",
+            "6500000000000002",
+        ] {
+            assert_eq!(detected(pan).as_deref(), Some("Discover"), "{}", pan);
+        }
+        // 643 and 640 sit between the 6011 and 644-649 ranges
+        assert_eq!(detected("6430000000000000"), None);
+        assert_eq!(detected("6400000000000000"), None);
+        // 62 is UnionPay, not Discover's 65
+        assert_eq!(detected("6200000000000005").as_deref(), Some("UnionPay"));
+    }
+
+    #[test]
+    fn maestro_and_mastercard_are_told_apart() {
+        assert_eq!(detected("5555555555554444").as_deref(), Some("Mastercard"));
+        assert_eq!(detected("2223003122003222").as_deref(), Some("Mastercard"));
+        assert_eq!(detected("5000000000000000").as_deref(), Some("Maestro"));
+        assert_eq!(detected("5600000000000000").as_deref(), Some("Maestro"));
+        assert_eq!(detected("6304000000000000").as_deref(), Some("Maestro"));
+        assert_eq!(detected("6759649826438453").as_deref(), Some("Maestro"));
+        // 2720 is the last Mastercard 2-series IIN
+        assert_eq!(detected("2720990000000000").as_deref(), Some("Mastercard"));
+        assert_eq!(detected("2721000000000000"), None);
+    }
+
+    #[test]
+    fn ranges_are_parsed() {
+        let jcb = IinRange::parse("3528-3589").unwrap();
+        assert_eq!(
+            jcb,
+            IinRange {
+                start: 3528,
+                end: 3589,
+                digits: 4
+            }
+        );
+        assert!(jcb.contains("3528000000000000"));
+        assert!(jcb.contains("3589999999999999"));
+        assert!(!jcb.contains("3527999999999999"));
+        assert!(!jcb.contains("3590000000000000"));
+        assert!(!jcb.contains("35"));
+        assert_eq!(detected("3530111333300000").as_deref(), Some("JCB"));
+        assert_eq!(detected("3589000000000000000").as_deref(), Some("JCB"));
+
+        assert_eq!(
+            IinRange::parse(" 6011 "),
+            Some(IinRange {
+                start: 6011,
+                end: 6011,
+                digits: 4
+            })
+        );
+        assert_eq!(IinRange::parse("0400-0499").unwrap().format(412), "0412");
+        assert_eq!(IinRange::parse("3589-3528"), None);
+        assert_eq!(IinRange::parse("352-3589"), None);
+        assert_eq!(IinRange::parse("35x8"), None);
+        assert_eq!(IinRange::parse(""), None);
+    }
+
+    #[test]
+    fn brands_issuing_the_length_win() {
+        let brand = |name: &str, prefix: &str, length: usize| CardBrand {
+            name: name.to_string(),
+            prefix: to_strings(&[prefix]),
+            lengths: vec![length],
+            cvv_length: 3,
+            test_cards: Vec::new(),
+            aid: None,
+        };
+        // The shorter IIN wins when only its brand issues the PAN's length
+        let brands = [brand("Long", "4000", 19), brand("Short", "4", 16)];
+        assert_eq!(
+            CardBrand::detect_in("4000000000000006", &brands)
+                .unwrap()
+                .name,
+            "Short"
+        );
+        assert_eq!(
+            CardBrand::detect_in("4000000000000000006", &brands)
+                .unwrap()
+                .name,
+            "Long"
+        );
+        // Otherwise the longer IIN does
+        let brands = [brand("Short", "4", 16), brand("Long", "4000", 16)];
+        assert_eq!(
+            CardBrand::detect_in("4000000000000006", &brands)
+                .unwrap()
+                .name,
+            "Long"
+        );
+        assert_eq!(
+            CardBrand::detect_in("4100000000000006", &brands)
+                .unwrap()
+                .name,
+            "Short"
+        );
+        // Diners Club issues 14 digits, Discover doesn't
+        assert_eq!(detected("36227206271667").as_deref(), Some("Diners Club"));
+    }
+
+    #[test]
+    fn profile_ranges_take_precedence() {
+        let mut brands = builtin_brands();
+        brands.push(CardBrand {
+            name: "Acme Bank".to_string(),
+            prefix: to_strings(&["411111-411119"]),
+            lengths: vec![16],
+            cvv_length: 3,
+            test_cards: Vec::new(),
+            aid: None,
+        });
+        let detected = |pan| CardBrand::detect_in(pan, &brands).map(|brand| brand.name.as_str());
+        assert_eq!(detected("4111 1111 1111 1111"), Some("Acme Bank"));
+        assert_eq!(detected("4111-1911-1111-1111"), Some("Acme Bank"));
+        assert_eq!(detected("4012888888881881"), Some("Visa"));
+        assert_eq!(detected("41111a1111111111"), None);
+        assert_eq!(detected(""), None);
+    }
+}
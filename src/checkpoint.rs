@@ -0,0 +1,76 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+// Where a long run had got to when it last finished a file, so an
+// interrupted run can carry on from there: the dataset being written, the
+// rows of it in complete files, and the seed and date range its rows are
+// drawn from. Checkpointed rows are seeded rows, which only depend on the
+// seed and where they are in the sequence, so the rows after the restart
+// are those the run would have gone on to write.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    // Command line of the run, which a resumed run has to repeat
+    pub args: Vec<String>,
+    // Index of the dataset in the profile's sizes; earlier ones are done
+    pub dataset: usize,
+    pub rows: usize,
+    pub files: usize,
+    // Seed of the dataset's rows: --seed, or one drawn for the dataset
+    pub seed: u64,
+    // Last day of the date range, fixed when the run started, so it doesn't
+    // move with the clock by the time the run resumes
+    pub date_to: NaiveDate,
+}
+
+impl Checkpoint {
+    // The start of a dataset drawn from `seed`
+    pub fn new(args: &[String], dataset: usize, seed: u64, date_to: NaiveDate) -> Self {
+        Self {
+            args: args.to_vec(),
+            dataset,
+            rows: 0,
+            files: 0,
+            seed,
+            date_to,
+        }
+    }
+
+    // Record that a dataset has reached a number of rows in complete files
+    pub fn advance(&mut self, rows: usize, files: usize) {
+        self.rows = rows;
+        self.files = files;
+    }
+
+    // Read a checkpoint, or nothing when the run hasn't made one
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let checkpoint = serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid checkpoint {}: {}", path.display(), e),
+            )
+        })?;
+        Ok(Some(checkpoint))
+    }
+
+    // Write the checkpoint next to its path first and move it into place,
+    // so a run interrupted while saving keeps the previous one
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        let mut out = BufWriter::new(File::create(&temporary)?);
+        serde_json::to_writer(&mut out, self)?;
+        out.flush()?;
+        drop(out);
+        fs::rename(&temporary, path)
+    }
+}
@@ -0,0 +1,1534 @@
+use crate::{
+    amount::AmountConfig,
+    bin::{self, BinEntry},
+    brand::{self, CardBrand, IinRange},
+    currency::{self, Region},
+    custom::{self, CustomField},
+    dirty::DirtyConfig,
+    dispute::DisputeConfig,
+    drift::DriftPoint,
+    fraud::{self, FraudConfig},
+    fx::DccConfig,
+    geo::GeoConfig,
+    id::IdStrategy,
+    installment::InstallmentConfig,
+    luhn,
+    masking::{MaskMode, MaskingConfig},
+    mcc,
+    ml::MlConfig,
+    model::{Channel, TransactionStatus},
+    money::AmountFormat,
+    names::NameConfig,
+    nulls,
+    output,
+    persona::Persona,
+    preauth::PreauthConfig,
+    scd::ScdConfig,
+    sequence::SequenceConfig,
+    subscription::SubscriptionConfig,
+    temporal::{DateRange, TemporalConfig, TemporalPattern, TimestampSampler},
+    three_ds::ThreeDsConfig,
+    user_agent,
+    wallet::WalletConfig,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// Struct to store merchant information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Merchant {
+    pub name: String,
+    pub id: String,
+    // Free-text category; defaults to the MCC description when left out
+    #[serde(default)]
+    pub category: String,
+    // ISO 18245 merchant category code
+    #[serde(default)]
+    pub mcc: Option<String>,
+    // Text on cardholders' statements; made up from the name when left out
+    #[serde(default)]
+    pub descriptor: Option<String>,
+}
+
+impl Merchant {
+    // Use the MCC description as the category when none was given
+    pub fn fill_category(&mut self) {
+        if self.category.is_empty()
+            && let Some(description) = self.mcc.as_deref().and_then(mcc::description)
+        {
+            self.category = description.to_string();
+        }
+    }
+}
+
+// Output file formats that can be requested from a profile or the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Csv,
+    Json,
+    // Newline-delimited JSON, one transaction per line
+    #[serde(alias = "ndjson")]
+    #[value(alias = "ndjson")]
+    Jsonl,
+    // Apache Parquet, only available with the `parquet` feature
+    Parquet,
+    // SQL script with the table DDL and the rows as INSERT or COPY statements
+    Sql,
+    // SQLite database file, only available with the `sqlite` feature
+    Sqlite,
+    // XML document with one element per transaction
+    Xml,
+    // Positional records laid out by the profile's `fixed_width` columns
+    #[serde(rename = "fixed-width")]
+    FixedWidth,
+    // Avro object container file with the schema embedded
+    Avro,
+    // Length-delimited protobuf messages, see `luhnsynth schema --proto`
+    #[serde(alias = "proto")]
+    #[value(alias = "proto")]
+    Protobuf,
+    // Length-framed ISO 8583 authorization request and response messages
+    Iso8583,
+    // ISO 20022 credit transfer initiation paying merchants their card sales
+    Pain001,
+    // ISO 20022 statement of the account those payouts are made from
+    Camt053,
+    // Excel workbook, only available with the `xlsx` feature
+    Xlsx,
+    // Customer, card, merchant and transaction CSV tables in a directory
+    Relational,
+    // Text rendered from each row by the profile's Handlebars `template`,
+    // only available with the `template` feature
+    Template,
+}
+
+impl OutputFormat {
+    // Whether files in this format can be wrapped in a compression encoder.
+    // Parquet, Avro and XLSX compress their own blocks, SQLite databases
+    // have to stay openable in place, and relational output compresses each
+    // table in its directory.
+    pub fn is_compressible(&self) -> bool {
+        !matches!(
+            self,
+            OutputFormat::Parquet
+                | OutputFormat::Avro
+                | OutputFormat::Xlsx
+                | OutputFormat::Sqlite
+                | OutputFormat::Relational
+        )
+    }
+
+    // Whether the format is written front to back as a single byte stream,
+    // so it can go to stdout. Parquet, XLSX and SQLite files are written out
+    // of order, and relational output is a directory of tables.
+    pub fn is_streamed(&self) -> bool {
+        !matches!(
+            self,
+            OutputFormat::Parquet
+                | OutputFormat::Xlsx
+                | OutputFormat::Sqlite
+                | OutputFormat::Relational
+        )
+    }
+
+    // Whether the format writes fields as the text they hold, so rows with
+    // malformed dates and card numbers can still be written
+    pub fn accepts_dirty(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Csv
+                | OutputFormat::Json
+                | OutputFormat::Jsonl
+                | OutputFormat::Sql
+                | OutputFormat::Sqlite
+                | OutputFormat::Xml
+                | OutputFormat::FixedWidth
+                | OutputFormat::Template
+        )
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "csv",
+            OutputFormat::Json => "json",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Sql => "sql",
+            OutputFormat::Sqlite => "db",
+            OutputFormat::Xml => "xml",
+            OutputFormat::FixedWidth => "dat",
+            OutputFormat::Avro => "avro",
+            OutputFormat::Protobuf => "pb",
+            OutputFormat::Iso8583 => "iso8583",
+            OutputFormat::Pain001 => "pain.001.xml",
+            OutputFormat::Camt053 => "camt.053.xml",
+            OutputFormat::Xlsx => "xlsx",
+            // A directory named after the dataset
+            OutputFormat::Relational => "",
+            OutputFormat::Template => "txt",
+        }
+    }
+
+    // Content type of the format's files, as served over HTTP
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "text/csv",
+            OutputFormat::Json => "application/json",
+            OutputFormat::Jsonl => "application/x-ndjson",
+            OutputFormat::Sql => "application/sql",
+            OutputFormat::Xml | OutputFormat::Pain001 | OutputFormat::Camt053 => "application/xml",
+            OutputFormat::FixedWidth | OutputFormat::Template => "text/plain",
+            OutputFormat::Avro => "application/avro",
+            OutputFormat::Protobuf => "application/x-protobuf",
+            _ => "application/octet-stream",
+        }
+    }
+}
+
+// Compression applied to text output files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    // Suffix added after the format's own extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+}
+
+// Period of transaction dates that output is partitioned by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PartitionBy {
+    Day,
+    Month,
+}
+
+impl PartitionBy {
+    // Format of the partition's date, as in `dt=2024-09-25` or `dt=2024-09`
+    pub fn date_format(&self) -> &'static str {
+        match self {
+            PartitionBy::Day => "%Y-%m-%d",
+            PartitionBy::Month => "%Y-%m",
+        }
+    }
+}
+
+// When CSV fields are wrapped in quotes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvQuoting {
+    // Only fields containing the delimiter, a quote or a line break
+    Necessary,
+    Always,
+    // Every field that isn't a number
+    NonNumeric,
+    // Never, even when the output can no longer be parsed back
+    Never,
+}
+
+// Layout of CSV output. The defaults produce RFC 4180 files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub header: bool,
+    pub quoting: CsvQuoting,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            header: true,
+            quoting: CsvQuoting::Necessary,
+        }
+    }
+}
+
+// How rows are loaded by SQL output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SqlStyle {
+    // Batched multi-row INSERT statements, accepted by most databases
+    Insert,
+    // A Postgres COPY ... FROM stdin block, much faster to load with psql
+    Copy,
+}
+
+// Layout of SQL output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SqlOptions {
+    pub table: String,
+    pub style: SqlStyle,
+    // Rows per INSERT statement
+    pub batch_size: usize,
+}
+
+impl Default for SqlOptions {
+    fn default() -> Self {
+        Self {
+            table: "transactions".to_string(),
+            style: SqlStyle::Insert,
+            batch_size: 500,
+        }
+    }
+}
+
+// Tables written by relational output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RelationalSchema {
+    // Customers, cards, merchants and transactions joined on their IDs
+    #[default]
+    Normalized,
+    // Customer, card, merchant and date dimensions around a fact table,
+    // joined on surrogate keys
+    Star,
+}
+
+// Layout of relational output
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelationalOptions {
+    pub schema: RelationalSchema,
+}
+
+// Element names of XML output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct XmlOptions {
+    // Document element wrapping every transaction
+    pub root: String,
+    // Element wrapping each transaction
+    pub row: String,
+    // Element names replacing field names, keyed by field name
+    pub fields: BTreeMap<String, String>,
+}
+
+impl Default for XmlOptions {
+    fn default() -> Self {
+        Self {
+            root: "transactions".to_string(),
+            row: "transaction".to_string(),
+            fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl XmlOptions {
+    // Element name of a column: the one given to its field, or else the name
+    // the column is written under
+    pub fn element<'a>(&'a self, field: &str, name: &'a str) -> &'a str {
+        self.fields.get(field).map_or(name, |element| element.as_str())
+    }
+}
+
+// Fields written by CSV, JSON, XML and SQL output, for target systems with a
+// fixed ingest schema
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColumnOptions {
+    // Fields to write, in this order; every field in its own order when empty
+    pub include: Vec<String>,
+    // Fields left out
+    pub exclude: Vec<String>,
+    // Names written in place of field names, keyed by field name
+    pub rename: BTreeMap<String, String>,
+}
+
+// Side of a fixed-width column that values are aligned to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Alignment {
+    #[default]
+    Left,
+    Right,
+}
+
+// One positional column of fixed-width output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixedWidthColumn {
+    // Transaction field written to the column, or `filler` for padding only
+    pub field: String,
+    // Width in bytes
+    pub width: usize,
+    #[serde(default)]
+    pub align: Alignment,
+    // Character that fills the rest of the column, one byte long
+    #[serde(default = "default_padding")]
+    pub padding: char,
+    // Cut longer values to the width instead of failing the run
+    #[serde(default)]
+    pub truncate: bool,
+}
+
+fn default_padding() -> char {
+    ' '
+}
+
+impl FixedWidthColumn {
+    fn new(field: &str, width: usize) -> Self {
+        Self {
+            field: field.to_string(),
+            width,
+            align: Alignment::Left,
+            padding: default_padding(),
+            truncate: false,
+        }
+    }
+}
+
+// Record layout of fixed-width output, in column order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FixedWidthOptions {
+    pub columns: Vec<FixedWidthColumn>,
+}
+
+impl Default for FixedWidthOptions {
+    // Every field, wide enough for generated values, with free text cut to fit
+    fn default() -> Self {
+        let text = |field: &str, width: usize| FixedWidthColumn {
+            truncate: true,
+            ..FixedWidthColumn::new(field, width)
+        };
+        Self {
+            columns: vec![
+                FixedWidthColumn::new("transaction_id", 36),
+                FixedWidthColumn::new("event_type", 13),
+                FixedWidthColumn::new("transaction_date", 35),
+                FixedWidthColumn::new("status", 8),
+                FixedWidthColumn::new("decline_reason", 19),
+                FixedWidthColumn::new("response_code", 2),
+                FixedWidthColumn::new("auth_code", 6),
+                FixedWidthColumn::new("original_transaction_id", 36),
+                FixedWidthColumn::new("subscription_id", 12),
+                FixedWidthColumn::new("initiator", 3),
+                FixedWidthColumn {
+                    align: Alignment::Right,
+                    ..FixedWidthColumn::new("installment_number", 2)
+                },
+                FixedWidthColumn {
+                    align: Alignment::Right,
+                    ..FixedWidthColumn::new("installment_count", 2)
+                },
+                FixedWidthColumn::new("order_reference", 12),
+                FixedWidthColumn::new("preauth_step", 11),
+                FixedWidthColumn::new("customer_id", 36),
+                text("cardholder_name", 40),
+                FixedWidthColumn::new("email", 64),
+                FixedWidthColumn::new("phone", 16),
+                FixedWidthColumn::new("card_number", 19),
+                text("card_brand", 20),
+                FixedWidthColumn::new("card_expiry", 5),
+                FixedWidthColumn::new("cvv", 4),
+                text("issuer_bank", 40),
+                FixedWidthColumn::new("card_country", 2),
+                FixedWidthColumn::new("card_type", 7),
+                text("billing_street", 40),
+                text("billing_city", 24),
+                text("billing_region", 28),
+                FixedWidthColumn::new("billing_postal_code", 10),
+                FixedWidthColumn::new("billing_country", 2),
+                text("shipping_street", 40),
+                text("shipping_city", 24),
+                text("shipping_region", 28),
+                FixedWidthColumn::new("shipping_postal_code", 10),
+                FixedWidthColumn::new("shipping_country", 2),
+                FixedWidthColumn {
+                    align: Alignment::Right,
+                    ..FixedWidthColumn::new("amount", 15)
+                },
+                FixedWidthColumn {
+                    align: Alignment::Right,
+                    ..FixedWidthColumn::new("refund_amount", 15)
+                },
+                FixedWidthColumn {
+                    align: Alignment::Right,
+                    ..FixedWidthColumn::new("remaining_amount", 15)
+                },
+                FixedWidthColumn::new("currency", 3),
+                FixedWidthColumn {
+                    align: Alignment::Right,
+                    ..FixedWidthColumn::new("original_amount", 15)
+                },
+                FixedWidthColumn::new("original_currency", 3),
+                FixedWidthColumn {
+                    align: Alignment::Right,
+                    ..FixedWidthColumn::new("billing_amount", 15)
+                },
+                FixedWidthColumn::new("billing_currency", 3),
+                FixedWidthColumn {
+                    align: Alignment::Right,
+                    ..FixedWidthColumn::new("fx_rate", 12)
+                },
+                FixedWidthColumn {
+                    align: Alignment::Right,
+                    ..FixedWidthColumn::new("fx_markup", 5)
+                },
+                text("merchant_name", 40),
+                text("merchant_id", 20),
+                text("statement_descriptor", 40),
+                text("merchant_category", 40),
+                FixedWidthColumn::new("mcc", 4),
+                FixedWidthColumn::new("acquirer_id", 6),
+                FixedWidthColumn::new("processor", 16),
+                FixedWidthColumn::new("batch_id", 24),
+                FixedWidthColumn::new("settlement_date", 10),
+                FixedWidthColumn::new("payment_method", 16),
+                FixedWidthColumn::new("channel", 11),
+                FixedWidthColumn::new("pos_entry_mode", 2),
+                FixedWidthColumn::new("terminal_id", 8),
+                FixedWidthColumn::new("track1", 79),
+                FixedWidthColumn::new("track2", 40),
+                FixedWidthColumn::new("emv_aid", 32),
+                FixedWidthColumn::new("emv_cryptogram", 16),
+                FixedWidthColumn::new("emv_tvr", 10),
+                FixedWidthColumn::new("emv_atc", 4),
+                FixedWidthColumn::new("wallet", 11),
+                FixedWidthColumn::new("wallet_id", 3),
+                FixedWidthColumn::new("token_pan", 19),
+                FixedWidthColumn::new("token_requestor_id", 11),
+                FixedWidthColumn::new("three_ds_version", 3),
+                FixedWidthColumn::new("eci", 2),
+                FixedWidthColumn::new("authentication_result", 12),
+                FixedWidthColumn::new("cavv", 28),
+                FixedWidthColumn::new("avs_result", 1),
+                FixedWidthColumn::new("cvv_result", 1),
+                FixedWidthColumn::new("ip_address", 39),
+                FixedWidthColumn::new("ip_country", 2),
+                text("device_id", 36),
+                FixedWidthColumn::new("device_platform", 16),
+                FixedWidthColumn::new("device_os", 32),
+                FixedWidthColumn::new("device_browser", 32),
+                FixedWidthColumn::new("screen_resolution", 11),
+                FixedWidthColumn::new("device_fingerprint", 32),
+                text("user_agent", 200),
+                FixedWidthColumn::new("is_fraud", 5),
+                FixedWidthColumn::new("fraud_pattern", 17),
+                FixedWidthColumn::new("persona", 20),
+            ],
+        }
+    }
+}
+
+// Relative weights for the categorical fields of a transaction. An empty
+// table means every value is equally likely; once a table is given, values
+// it leaves out are never generated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Weights {
+    pub status: BTreeMap<TransactionStatus, f64>,
+    // Keyed by card brand name
+    pub card_brands: BTreeMap<String, f64>,
+    // Keyed by currency code
+    pub currencies: BTreeMap<String, f64>,
+    pub channels: BTreeMap<Channel, f64>,
+    // Keyed by user agent string. Without a table, built-in user agents
+    // keep their market-share weight and others get a weight of 1.
+    pub user_agents: BTreeMap<String, f64>,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            status: BTreeMap::from([
+                (TransactionStatus::Approved, 0.85),
+                (TransactionStatus::Declined, 0.10),
+                (TransactionStatus::Pending, 0.03),
+                (TransactionStatus::Refunded, 0.02),
+            ]),
+            channels: BTreeMap::from([
+                (Channel::Ecommerce, 0.55),
+                (Channel::PosChip, 0.15),
+                (Channel::Contactless, 0.22),
+                (Channel::Magstripe, 0.03),
+                (Channel::Moto, 0.05),
+            ]),
+            card_brands: BTreeMap::new(),
+            currencies: BTreeMap::new(),
+            user_agents: BTreeMap::new(),
+        }
+    }
+}
+
+// Weight of each named item, in the order given
+fn resolve_weights<'a>(
+    table: &BTreeMap<String, f64>,
+    names: impl Iterator<Item = &'a str>,
+) -> Vec<f64> {
+    names
+        .map(|name| {
+            if table.is_empty() {
+                1.0
+            } else {
+                table.get(name).copied().unwrap_or(0.0)
+            }
+        })
+        .collect()
+}
+
+// Check a weight table against the names it may refer to
+fn check_weights<'a>(
+    field: &str,
+    table: &BTreeMap<String, f64>,
+    names: impl Iterator<Item = &'a str> + Clone,
+) -> io::Result<()> {
+    for (name, weight) in table {
+        if !names.clone().any(|n| n == name) {
+            return Err(invalid(format!(
+                "weights.{} refers to unknown `{}`",
+                field, name
+            )));
+        }
+        if !weight.is_finite() || *weight < 0.0 {
+            return Err(invalid(format!(
+                "weights.{}.{} must be a non-negative number",
+                field, name
+            )));
+        }
+    }
+    if resolve_weights(table, names).iter().sum::<f64>() <= 0.0 {
+        return Err(invalid(format!(
+            "weights.{} must have a positive total",
+            field
+        )));
+    }
+    Ok(())
+}
+
+// Generation profile: everything needed to produce a set of datasets.
+// Any field left out of a config file falls back to the built-in default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub sizes: Vec<usize>,
+    pub formats: Vec<OutputFormat>,
+    pub card_brands: Vec<CardBrand>,
+    // Issuer, country and card type of the BINs that cards are issued in
+    pub bins: Vec<BinEntry>,
+    pub merchants: Vec<Merchant>,
+    pub first_names: Vec<String>,
+    pub last_names: Vec<String>,
+    // Locale packs that cardholder names are drawn from instead of
+    // `first_names` and `last_names`
+    pub names: NameConfig,
+    pub currencies: Vec<String>,
+    // Draw currencies from the ISO 4217 catalogue of a region, weighted by
+    // their share of card payments, instead of from `currencies`
+    pub currency_region: Option<Region>,
+    pub user_agents: Vec<String>,
+    pub weights: Weights,
+    // Format of transaction IDs
+    pub id_strategy: IdStrategy,
+    // Fail generation when an identifier meant to be unique repeats
+    pub verify_unique: bool,
+    // Only use published test card numbers, never generated ones that could
+    // belong to a real card
+    pub test_cards_only: bool,
+    // Size of the persistent customer pool; when unset every transaction
+    // gets a brand new cardholder, card and device
+    pub customers: Option<usize>,
+    // Kinds of cardholder the customer pool is split between, each with its
+    // own merchant categories, amounts, frequency and devices
+    pub personas: Vec<Persona>,
+    // Markov chain that a pooled customer's consecutive purchases move
+    // between merchant categories by
+    pub sequences: SequenceConfig,
+    // Points partway through the date range where amounts, the merchant mix
+    // and fraud change
+    pub drift: Vec<DriftPoint>,
+    // Emit each transaction as its chain of lifecycle events instead of one row
+    pub lifecycle: bool,
+    // Give card-present transactions the Track 1 and Track 2 data read from
+    // the card
+    pub track_data: bool,
+    // Give chip and contactless transactions the EMV data of the card's
+    // application
+    pub emv: bool,
+    pub fraud: FraudConfig,
+    pub geo: GeoConfig,
+    pub wallets: WalletConfig,
+    pub three_ds: ThreeDsConfig,
+    pub subscriptions: SubscriptionConfig,
+    pub installments: InstallmentConfig,
+    pub preauths: PreauthConfig,
+    pub dcc: DccConfig,
+    pub scd: ScdConfig,
+    pub dirty: DirtyConfig,
+    // Share of rows each field is missing from, as in `cvv = 0.05`
+    pub nulls: BTreeMap<String, f64>,
+    // Extra columns appended to every row
+    pub custom_fields: Vec<CustomField>,
+    pub disputes: DisputeConfig,
+    // Masking or tokenization of card details in the output
+    pub masking: MaskingConfig,
+    pub ml: MlConfig,
+    pub amounts: AmountConfig,
+    pub temporal: TemporalConfig,
+    // First and last day (inclusive) of generated transactions; by default
+    // the last 3 years up to now
+    pub date_from: Option<NaiveDate>,
+    pub date_to: Option<NaiveDate>,
+    // End of a live run, whose transactions are stamped with the time
+    // they're generated at instead; only set from the command line
+    #[serde(skip)]
+    pub live_until: Option<DateTime<Utc>>,
+    // Timezone that timestamps are generated in and written with
+    pub timezone: Tz,
+    // Share of transactions attempted with an expired card, all declined
+    // with `card_expired`
+    pub expired_card_rate: f64,
+    // Share of refunds that return only part of what is left of the
+    // transaction, which can then be refunded again
+    pub partial_refund_rate: f64,
+    pub csv: CsvOptions,
+    pub sql: SqlOptions,
+    pub relational: RelationalOptions,
+    pub xml: XmlOptions,
+    pub fixed_width: FixedWidthOptions,
+    pub columns: ColumnOptions,
+    // Handlebars template that template output renders each row with
+    pub template: Option<PathBuf>,
+    // Compression for text output; Parquet is always written as is
+    pub compression: Option<Compression>,
+    // Write each dataset as a directory with a partition per day or month
+    pub partition_by: Option<PartitionBy>,
+    // Split each file into numbered files of at most this many rows
+    pub max_rows_per_file: Option<usize>,
+    // Whether text output writes amounts in major units or as whole numbers
+    // of minor units
+    pub amount_format: AmountFormat,
+}
+
+impl Profile {
+    // Names of the fields of every row: the built-in ones, then the custom
+    // fields
+    pub fn field_names(&self) -> Vec<&str> {
+        output::CSV_HEADER
+            .iter()
+            .copied()
+            .chain(self.custom_fields.iter().map(|field| field.name.as_str()))
+            .collect()
+    }
+
+    // Bounds that transaction timestamps are drawn between
+    pub fn date_range(&self) -> DateRange {
+        match self.live_until {
+            Some(until) => DateRange::live(until, self.timezone),
+            None => DateRange::new(self.date_from, self.date_to, self.timezone),
+        }
+    }
+
+    // Sampling weights for each status, in `TransactionStatus::ALL` order
+    pub fn status_weights(&self) -> Vec<f64> {
+        TransactionStatus::ALL
+            .iter()
+            .map(|status| self.weights.status.get(status).copied().unwrap_or(0.0))
+            .collect()
+    }
+
+    // Sampling weights for each channel, in `Channel::ALL` order
+    pub fn channel_weights(&self) -> Vec<f64> {
+        Channel::ALL
+            .iter()
+            .map(|channel| self.weights.channels.get(channel).copied().unwrap_or(0.0))
+            .collect()
+    }
+
+    // Sampling weights for each card brand, in profile order
+    pub fn brand_weights(&self) -> Vec<f64> {
+        resolve_weights(
+            &self.weights.card_brands,
+            self.card_brands.iter().map(|b| b.name.as_str()),
+        )
+    }
+
+    // Currencies transactions are drawn from: the catalogue of
+    // `currency_region` when one is set, or else `currencies`
+    pub fn currency_codes(&self) -> Vec<String> {
+        match self.currency_region {
+            Some(region) => currency::catalogue(region)
+                .map(|(code, _)| code.to_string())
+                .collect(),
+            None => self.currencies.clone(),
+        }
+    }
+
+    // Sampling weights for each currency, in `currency_codes` order. Catalogue
+    // currencies keep their share of card payments unless weights are given.
+    pub fn currency_weights(&self) -> Vec<f64> {
+        if let Some(region) = self.currency_region
+            && self.weights.currencies.is_empty()
+        {
+            return currency::catalogue(region).map(|(_, share)| share).collect();
+        }
+        resolve_weights(
+            &self.weights.currencies,
+            self.currency_codes().iter().map(|c| c.as_str()),
+        )
+    }
+
+    // Sampling weights for each user agent, in profile order
+    pub fn user_agent_weights(&self) -> Vec<f64> {
+        if !self.weights.user_agents.is_empty() {
+            return resolve_weights(
+                &self.weights.user_agents,
+                self.user_agents.iter().map(|a| a.as_str()),
+            );
+        }
+        let builtin = user_agent::builtin_weights();
+        self.user_agents
+            .iter()
+            .map(|agent| builtin.get(agent).copied().unwrap_or(1.0))
+            .collect()
+    }
+
+    // Load a profile from a TOML or YAML file, chosen by file extension
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let mut profile: Profile = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&contents)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            _ => toml::from_str(&contents)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        };
+        profile.fill_categories();
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    // Give merchants without a category the description of their MCC
+    fn fill_categories(&mut self) {
+        for merchant in &mut self.merchants {
+            merchant.fill_category();
+        }
+    }
+
+    // Reject profiles that would make generation panic or emit malformed cards
+    pub fn validate(&self) -> io::Result<()> {
+        let lists = [
+            ("card_brands", self.card_brands.is_empty()),
+            ("merchants", self.merchants.is_empty()),
+            ("first_names", self.first_names.is_empty()),
+            ("last_names", self.last_names.is_empty()),
+            ("currencies", self.currencies.is_empty()),
+            ("user_agents", self.user_agents.is_empty()),
+        ];
+        for (name, empty) in lists {
+            if empty {
+                return Err(invalid(format!(
+                    "profile list `{}` must not be empty",
+                    name
+                )));
+            }
+        }
+        for merchant in &self.merchants {
+            if let Some(code) = merchant.mcc.as_deref().filter(|c| !mcc::is_well_formed(c)) {
+                return Err(invalid(format!(
+                    "merchant `{}` has malformed MCC `{}`",
+                    merchant.name, code
+                )));
+            }
+            if merchant.category.is_empty() {
+                return Err(invalid(format!(
+                    "merchant `{}` needs a category or a known MCC",
+                    merchant.name
+                )));
+            }
+        }
+        for brand in &self.card_brands {
+            if brand.prefix.is_empty() || brand.lengths.is_empty() {
+                return Err(invalid(format!(
+                    "card brand `{}` needs at least one prefix and length",
+                    brand.name
+                )));
+            }
+            if let Some(prefix) = brand.prefix.iter().find(|p| IinRange::parse(p).is_none()) {
+                return Err(invalid(format!(
+                    "card brand `{}` has malformed prefix `{}`",
+                    brand.name, prefix
+                )));
+            }
+            // Every prefix must leave room for at least one random digit and the check digit
+            let longest_prefix = brand
+                .prefix
+                .iter()
+                .filter_map(|p| IinRange::parse(p))
+                .map(|range| range.digits)
+                .max()
+                .unwrap_or(0);
+            if let Some(length) = brand
+                .lengths
+                .iter()
+                .find(|&&l| l < longest_prefix + 2 || l > 19)
+            {
+                return Err(invalid(format!(
+                    "card brand `{}` has unsupported PAN length {}",
+                    brand.name, length
+                )));
+            }
+            if let Some(card) = brand
+                .test_cards
+                .iter()
+                .find(|c| !c.chars().all(|c| c.is_ascii_digit()) || !luhn::is_valid(c))
+            {
+                return Err(invalid(format!(
+                    "card brand `{}` has invalid test card `{}`",
+                    brand.name, card
+                )));
+            }
+            // An AID is a 5-byte registered application provider ID and up
+            // to 11 bytes of proprietary extension
+            if let Some(aid) = &brand.aid
+                && (!(10..=32).contains(&aid.len())
+                    || aid.len() % 2 != 0
+                    || !aid.chars().all(|c| c.is_ascii_hexdigit()))
+            {
+                return Err(invalid(format!(
+                    "card brand `{}` has AID `{}`; use 5 to 16 bytes in hex such as A0000000031010",
+                    brand.name, aid
+                )));
+            }
+        }
+        for entry in &self.bins {
+            if entry.prefix.is_empty()
+                || entry.prefix.len() > 8
+                || !entry.prefix.chars().all(|c| c.is_ascii_digit())
+            {
+                return Err(invalid(format!("BIN `{}` must be 1 to 8 digits", entry.prefix)));
+            }
+            if entry.issuer.is_empty() {
+                return Err(invalid(format!("BIN `{}` needs an issuer", entry.prefix)));
+            }
+            if entry.country.len() != 2
+                || !entry.country.chars().all(|c| c.is_ascii_uppercase())
+            {
+                return Err(invalid(format!(
+                    "BIN `{}` has country `{}`; use an ISO 3166 alpha-2 code such as US",
+                    entry.prefix, entry.country
+                )));
+            }
+        }
+        if self.test_cards_only
+            && let Some((brand, _)) = self
+                .card_brands
+                .iter()
+                .zip(self.brand_weights())
+                .find(|(brand, weight)| *weight > 0.0 && brand.test_cards().is_empty())
+        {
+            return Err(invalid(format!(
+                "card brand `{}` has no test cards to use with test_cards_only",
+                brand.name
+            )));
+        }
+
+        if let Some((status, _)) = self
+            .weights
+            .status
+            .iter()
+            .find(|(_, w)| !w.is_finite() || **w < 0.0)
+        {
+            return Err(invalid(format!(
+                "weights.status.{} must be a non-negative number",
+                status.as_str()
+            )));
+        }
+        if self.status_weights().iter().sum::<f64>() <= 0.0 {
+            return Err(invalid(
+                "weights.status must have a positive total".to_string(),
+            ));
+        }
+        if let Some((channel, _)) = self
+            .weights
+            .channels
+            .iter()
+            .find(|(_, w)| !w.is_finite() || **w < 0.0)
+        {
+            return Err(invalid(format!(
+                "weights.channels.{} must be a non-negative number",
+                channel.as_str()
+            )));
+        }
+        if self.channel_weights().iter().sum::<f64>() <= 0.0 {
+            return Err(invalid(
+                "weights.channels must have a positive total".to_string(),
+            ));
+        }
+        check_weights(
+            "card_brands",
+            &self.weights.card_brands,
+            self.card_brands.iter().map(|b| b.name.as_str()),
+        )?;
+        check_weights(
+            "currencies",
+            &self.weights.currencies,
+            self.currency_codes().iter().map(|c| c.as_str()),
+        )?;
+        check_weights(
+            "user_agents",
+            &self.weights.user_agents,
+            self.user_agents.iter().map(|a| a.as_str()),
+        )?;
+        if !(0.0..=1.0).contains(&self.fraud.rate) {
+            return Err(invalid("fraud.rate must be between 0 and 1".to_string()));
+        }
+        if self
+            .fraud
+            .patterns
+            .values()
+            .any(|w| !w.is_finite() || *w < 0.0)
+            || self.fraud.pattern_weights().iter().sum::<f64>() <= 0.0
+        {
+            return Err(invalid(
+                "fraud.patterns must be non-negative with a positive total".to_string(),
+            ));
+        }
+        if !(2..=fraud::MAX_RING_SIZE).contains(&self.fraud.ring_size) {
+            return Err(invalid(format!(
+                "fraud.ring_size must be between 2 and {}",
+                fraud::MAX_RING_SIZE
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.geo.foreign_ip_rate) {
+            return Err(invalid("geo.foreign_ip_rate must be between 0 and 1".to_string()));
+        }
+        if !(0.0..=1.0).contains(&self.geo.ipv6_share) {
+            return Err(invalid("geo.ipv6_share must be between 0 and 1".to_string()));
+        }
+        if !(0.0..=1.0).contains(&self.geo.shipping_mismatch_rate) {
+            return Err(invalid(
+                "geo.shipping_mismatch_rate must be between 0 and 1".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.fraud.address_mismatch_rate) {
+            return Err(invalid(
+                "fraud.address_mismatch_rate must be between 0 and 1".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.wallets.rate) {
+            return Err(invalid("wallets.rate must be between 0 and 1".to_string()));
+        }
+        if !(0.0..=1.0).contains(&self.three_ds.rate) {
+            return Err(invalid("three_ds.rate must be between 0 and 1".to_string()));
+        }
+        if self.names.locales.values().any(|w| !w.is_finite() || *w < 0.0)
+            || (!self.names.locales.is_empty()
+                && self.names.locale_weights().iter().sum::<f64>() <= 0.0)
+        {
+            return Err(invalid(
+                "names.locales must be non-negative with a positive total".to_string(),
+            ));
+        }
+        if self
+            .three_ds
+            .versions
+            .values()
+            .any(|w| !w.is_finite() || *w < 0.0)
+            || self.three_ds.version_weights().iter().sum::<f64>() <= 0.0
+        {
+            return Err(invalid(
+                "three_ds.versions must be non-negative with a positive total".to_string(),
+            ));
+        }
+        for (version, rate) in &self.three_ds.challenge_rates {
+            if !(0.0..=1.0).contains(rate) {
+                return Err(invalid(format!(
+                    "three_ds.challenge_rates.\"{}\" must be between 0 and 1",
+                    version.as_str()
+                )));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.three_ds.failure_rate) {
+            return Err(invalid("three_ds.failure_rate must be between 0 and 1".to_string()));
+        }
+        for (name, rate) in self.subscriptions.rates() {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(invalid(format!("subscriptions.{} must be between 0 and 1", name)));
+            }
+        }
+        if self.subscriptions.retry_days.contains(&0)
+            || !self.subscriptions.retry_days.is_sorted_by(|a, b| a < b)
+        {
+            return Err(invalid(
+                "subscriptions.retry_days must be increasing numbers of days above 0".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.installments.rate) {
+            return Err(invalid("installments.rate must be between 0 and 1".to_string()));
+        }
+        if self.installments.counts.is_empty()
+            || self.installments.counts.iter().any(|count| *count < 2)
+        {
+            return Err(invalid(
+                "installments.counts must list at least one plan of 2 or more installments"
+                    .to_string(),
+            ));
+        }
+        for (name, rate) in self.preauths.rates() {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(invalid(format!("preauths.{} must be between 0 and 1", name)));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.dcc.rate) {
+            return Err(invalid("dcc.rate must be between 0 and 1".to_string()));
+        }
+        if !(0.0 <= self.dcc.min_markup && self.dcc.min_markup <= self.dcc.max_markup) {
+            return Err(invalid(
+                "dcc.min_markup and dcc.max_markup must be a range of percentages from 0 up"
+                    .to_string(),
+            ));
+        }
+        for (name, rate) in self.scd.rates() {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(invalid(format!("scd.{} must be between 0 and 1", name)));
+            }
+        }
+        for (name, rate) in self.dirty.rates() {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(invalid(format!("dirty.{} must be between 0 and 1", name)));
+            }
+        }
+        for (name, rate) in self.disputes.rates() {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(invalid(format!("disputes.{} must be between 0 and 1", name)));
+            }
+        }
+        for (field, mode) in self.masking.modes() {
+            if mode == MaskMode::First6Last4 && field != "card_number" {
+                return Err(invalid(format!(
+                    "masking.{} can't be first6_last4; that only applies to card_number",
+                    field
+                )));
+            }
+        }
+        if self
+            .ml
+            .label_ratio
+            .is_some_and(|ratio| !(0.0..=1.0).contains(&ratio))
+        {
+            return Err(invalid("ml.label_ratio must be between 0 and 1".to_string()));
+        }
+        if self
+            .ml
+            .train_fraction
+            .is_some_and(|fraction| !(0.0..=1.0).contains(&fraction))
+        {
+            return Err(invalid("ml.train_fraction must be between 0 and 1".to_string()));
+        }
+        if !self.csv.delimiter.is_ascii() || matches!(self.csv.delimiter, '"' | '\n' | '\r') {
+            return Err(invalid(format!(
+                "csv.delimiter must be a single ASCII character other than a quote or line break, got {:?}",
+                self.csv.delimiter
+            )));
+        }
+        // Table names are written into SQL unquoted, optionally schema-qualified
+        let is_identifier = |part: &str| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        if !self.sql.table.split('.').all(is_identifier) || self.sql.table.split('.').count() > 2 {
+            return Err(invalid(format!(
+                "sql.table must be a plain or schema-qualified identifier, got {:?}",
+                self.sql.table
+            )));
+        }
+        if self.sql.batch_size == 0 {
+            return Err(invalid("sql.batch_size must be at least 1".to_string()));
+        }
+        custom::check(&self.custom_fields).map_err(|e| invalid(format!("custom_fields.{}", e)))?;
+        let fields = self.field_names();
+        // Element names are written as is, so they must be plain XML names
+        let is_xml_name = |name: &str| {
+            name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        };
+        for (option, name) in [("xml.root", &self.xml.root), ("xml.row", &self.xml.row)] {
+            if !is_xml_name(name) {
+                return Err(invalid(format!(
+                    "{} must be a valid XML element name, got {:?}",
+                    option, name
+                )));
+            }
+        }
+        for (field, name) in &self.xml.fields {
+            if !fields.contains(&field.as_str()) {
+                return Err(invalid(format!(
+                    "xml.fields refers to unknown field `{}`",
+                    field
+                )));
+            }
+            if !is_xml_name(name) {
+                return Err(invalid(format!(
+                    "xml.fields.{} must be a valid XML element name, got {:?}",
+                    field, name
+                )));
+            }
+        }
+        for (option, names) in [
+            ("columns.include", &self.columns.include),
+            ("columns.exclude", &self.columns.exclude),
+        ] {
+            for (i, field) in names.iter().enumerate() {
+                if !fields.contains(&field.as_str()) {
+                    return Err(invalid(format!(
+                        "{} refers to unknown field `{}`",
+                        option, field
+                    )));
+                }
+                if names[..i].contains(field) {
+                    return Err(invalid(format!("{} lists `{}` twice", option, field)));
+                }
+            }
+        }
+        // Column names are written into SQL unquoted and become XML elements
+        for (field, name) in &self.columns.rename {
+            if !fields.contains(&field.as_str()) {
+                return Err(invalid(format!(
+                    "columns.rename refers to unknown field `{}`",
+                    field
+                )));
+            }
+            if !is_identifier(name) {
+                return Err(invalid(format!(
+                    "columns.rename.{} must be letters, digits and underscores, got {:?}",
+                    field, name
+                )));
+            }
+        }
+        let columns = output::Columns::new(self);
+        let names: Vec<&str> = columns.names().collect();
+        if names.is_empty() {
+            return Err(invalid("columns leaves no field to write".to_string()));
+        }
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                return Err(invalid(format!(
+                    "columns writes two fields as `{}`",
+                    name
+                )));
+            }
+        }
+        if self.formats.contains(&OutputFormat::Template) && self.template.is_none() {
+            return Err(invalid(
+                "template output needs a template file, from `template` or --template".to_string(),
+            ));
+        }
+        if self.fixed_width.columns.is_empty() {
+            return Err(invalid("fixed_width.columns must not be empty".to_string()));
+        }
+        for column in &self.fixed_width.columns {
+            if column.field != "filler" && !fields.contains(&column.field.as_str()) {
+                return Err(invalid(format!(
+                    "fixed_width.columns refers to unknown field `{}`",
+                    column.field
+                )));
+            }
+            if column.width == 0 {
+                return Err(invalid(format!(
+                    "fixed_width column `{}` must be at least 1 byte wide",
+                    column.field
+                )));
+            }
+            if matches!(column.padding, '\n' | '\r') {
+                return Err(invalid(format!(
+                    "fixed_width column `{}` can't be padded with a line break",
+                    column.field
+                )));
+            }
+            if !column.padding.is_ascii() {
+                return Err(invalid(format!(
+                    "fixed_width column `{}` must be padded with an ASCII character",
+                    column.field
+                )));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.expired_card_rate) {
+            return Err(invalid(
+                "expired_card_rate must be between 0 and 1".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.partial_refund_rate) {
+            return Err(invalid(
+                "partial_refund_rate must be between 0 and 1".to_string(),
+            ));
+        }
+        self.amounts
+            .default
+            .check()
+            .map_err(|e| invalid(format!("amounts.default: {}", e)))?;
+        for (category, distribution) in &self.amounts.categories {
+            distribution
+                .check()
+                .map_err(|e| invalid(format!("amounts.categories.{}: {}", category, e)))?;
+        }
+        if let (Some(from), Some(to)) = (self.date_from, self.date_to)
+            && from > to
+        {
+            return Err(invalid(format!(
+                "date_from ({}) is after date_to ({})",
+                from, to
+            )));
+        }
+        let range = self.date_range();
+        let check_pattern = |pattern: &TemporalPattern| {
+            pattern.check()?;
+            TimestampSampler::new(pattern, &range).map(|_| ())
+        };
+        check_pattern(&self.temporal.default)
+            .map_err(|e| invalid(format!("temporal.default: {}", e)))?;
+        for (category, pattern) in &self.temporal.categories {
+            check_pattern(pattern)
+                .map_err(|e| invalid(format!("temporal.categories.{}: {}", category, e)))?;
+        }
+        if self.customers == Some(0) {
+            return Err(invalid("customers must be at least 1".to_string()));
+        }
+        if !self.personas.is_empty() {
+            for (i, persona) in self.personas.iter().enumerate() {
+                persona
+                    .check()
+                    .map_err(|e| invalid(format!("personas.{}: {}", persona.name, e)))?;
+                if self.personas[..i].iter().any(|p| p.name == persona.name) {
+                    return Err(invalid(format!("personas.{}: repeated", persona.name)));
+                }
+                let reachable = self
+                    .merchants
+                    .iter()
+                    .any(|m| persona.category_weight(&m.category) > 0.0);
+                if !reachable {
+                    return Err(invalid(format!(
+                        "personas.{}: no merchant has a category with weight",
+                        persona.name
+                    )));
+                }
+            }
+            if self.personas.iter().all(|p| p.weight == 0.0) {
+                return Err(invalid("personas need a weight above 0".to_string()));
+            }
+        }
+        self.sequences
+            .check(&self.merchants)
+            .map_err(|e| invalid(format!("sequences.{}", e)))?;
+        for point in &self.drift {
+            point
+                .check(&range, &self.merchants)
+                .map_err(|e| invalid(format!("drift.\"{}\": {}", point.at, e)))?;
+        }
+        for (field, rate) in &self.nulls {
+            nulls::check(field, *rate, &self.custom_fields)
+                .map_err(|e| invalid(format!("nulls.{} {}", field, e)))?;
+        }
+        if self.max_rows_per_file == Some(0) {
+            return Err(invalid("max_rows_per_file must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+
+    // Settings that generate fine but are likely mistakes: weights that don't
+    // add up to 1, card numbers unlike issued ones, and dates to come
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut tables = vec![
+            ("weights.status", self.status_weights()),
+            ("weights.channels", self.channel_weights()),
+            ("fraud.patterns", self.fraud.pattern_weights()),
+            ("three_ds.versions", self.three_ds.version_weights()),
+        ];
+        // Tables left empty give every entry the same share
+        if !self.names.locales.is_empty() {
+            tables.push(("names.locales", self.names.locale_weights()));
+        }
+        if !self.weights.card_brands.is_empty() {
+            tables.push(("weights.card_brands", self.brand_weights()));
+        }
+        if !self.weights.currencies.is_empty() {
+            tables.push(("weights.currencies", self.currency_weights()));
+        }
+        if !self.weights.user_agents.is_empty() {
+            tables.push(("weights.user_agents", self.user_agent_weights()));
+        }
+        // Weights below 1 read as shares, which should make up the whole;
+        // larger ones are taken as relative to each other
+        for (table, weights) in tables {
+            let total: f64 = weights.iter().sum();
+            if weights.iter().all(|w| *w < 1.0) && (total - 1.0).abs() > 1e-6 {
+                warnings.push(format!(
+                    "{} add up to {}, not 1; each is taken as a share of the total",
+                    table,
+                    (total * 1e6).round() / 1e6
+                ));
+            }
+        }
+        for (i, brand) in self.card_brands.iter().enumerate() {
+            if let Some(length) = brand.lengths.iter().find(|l| !(12..=19).contains(*l)) {
+                warnings.push(format!(
+                    "card brand `{}` has PAN length {}; issued cards have 12 to 19 digits",
+                    brand.name, length
+                ));
+            }
+            if !(3..=4).contains(&brand.cvv_length) {
+                warnings.push(format!(
+                    "card brand `{}` has CVV length {}; card security codes have 3 or 4 digits",
+                    brand.name, brand.cvv_length
+                ));
+            }
+            // Cards of overlapping prefixes can't be told apart by number
+            for other in &self.card_brands[i + 1..] {
+                let shared = brand.prefix.iter().find_map(|a| {
+                    other.prefix.iter().find(|b| prefixes_overlap(a, b)).map(|b| (a, b))
+                });
+                if let Some((a, b)) = shared {
+                    warnings.push(format!(
+                        "card brands `{}` and `{}` share numbers, through prefixes `{}` and `{}`",
+                        brand.name, other.name, a, b
+                    ));
+                }
+            }
+        }
+        let today = Utc::now().date_naive();
+        for (option, date) in [("date_from", self.date_from), ("date_to", self.date_to)] {
+            if let Some(date) = date.filter(|date| *date > today) {
+                warnings.push(format!("{} ({}) is in the future", option, date));
+            }
+        }
+        warnings
+    }
+}
+
+// Whether two card brand prefixes start any of the same card numbers
+fn prefixes_overlap(a: &str, b: &str) -> bool {
+    let (Some(a), Some(b)) = (IinRange::parse(a), IinRange::parse(b)) else {
+        return false;
+    };
+    let (short, long) = if a.digits <= b.digits { (a, b) } else { (b, a) };
+    let scale = 10u64.pow((long.digits - short.digits) as u32);
+    long.start / scale <= short.end && short.start <= long.end / scale
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            sizes: vec![100, 250, 500],
+            formats: vec![OutputFormat::Csv, OutputFormat::Json],
+            card_brands: brand::builtin_brands(),
+            bins: bin::builtin_bins(),
+            merchants: default_merchants(),
+            first_names: to_strings(&[
+                "John", "Jane", "Michael", "Emily", "David", "Sarah", "Robert", "Lisa", "William",
+                "Emma", "James", "Olivia", "Daniel", "Sophia", "Matthew", "Ava", "Christopher",
+                "Mia", "Andrew", "Isabella",
+            ]),
+            last_names: to_strings(&[
+                "Smith", "Johnson", "Williams", "Brown", "Jones", "Garcia", "Miller", "Davis",
+                "Rodriguez", "Martinez", "Hernandez", "Lopez", "Gonzalez", "Wilson", "Anderson",
+                "Thomas", "Taylor", "Moore", "Jackson", "Martin",
+            ]),
+            names: NameConfig::default(),
+            currencies: to_strings(&["USD", "EUR", "GBP", "CAD", "AUD", "JPY"]),
+            currency_region: None,
+            user_agents: user_agent::builtin_user_agents()
+                .into_iter()
+                .map(|(agent, _)| agent)
+                .collect(),
+            weights: Weights::default(),
+            id_strategy: IdStrategy::default(),
+            verify_unique: false,
+            test_cards_only: false,
+            customers: None,
+            personas: Vec::new(),
+            sequences: SequenceConfig::default(),
+            drift: Vec::new(),
+            lifecycle: false,
+            track_data: false,
+            emv: false,
+            fraud: FraudConfig::default(),
+            geo: GeoConfig::default(),
+            wallets: WalletConfig::default(),
+            three_ds: ThreeDsConfig::default(),
+            subscriptions: SubscriptionConfig::default(),
+            installments: InstallmentConfig::default(),
+            preauths: PreauthConfig::default(),
+            dcc: DccConfig::default(),
+            scd: ScdConfig::default(),
+            dirty: DirtyConfig::default(),
+            nulls: BTreeMap::new(),
+            custom_fields: Vec::new(),
+            disputes: DisputeConfig::default(),
+            masking: MaskingConfig::default(),
+            ml: MlConfig::default(),
+            amounts: AmountConfig::default(),
+            temporal: TemporalConfig::default(),
+            date_from: None,
+            date_to: None,
+            live_until: None,
+            timezone: Tz::UTC,
+            expired_card_rate: 0.0,
+            partial_refund_rate: 0.25,
+            csv: CsvOptions::default(),
+            sql: SqlOptions::default(),
+            relational: RelationalOptions::default(),
+            xml: XmlOptions::default(),
+            fixed_width: FixedWidthOptions::default(),
+            columns: ColumnOptions::default(),
+            template: None,
+            compression: None,
+            partition_by: None,
+            max_rows_per_file: None,
+            amount_format: AmountFormat::default(),
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub(crate) fn to_strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+// Built-in merchants
+fn default_merchants() -> Vec<Merchant> {
+    [
+        ("Acme Retail", "MER12345", "Retail", "5311"),
+        ("Sunshine Groceries", "MER22468", "Grocery", "5411"),
+        ("Tech Universe", "MER39521", "Electronics", "5732"),
+        ("Cozy Coffee Shop", "MER41327", "Food & Beverage", "5814"),
+        ("Fitness Plus", "MER57845", "Health & Fitness", "7997"),
+        ("BookWorld", "MER61234", "Books & Media", "5942"),
+        ("QuickMart", "MER78523", "Convenience Store", "5499"),
+        ("Urban Fashion", "MER84751", "Clothing", "5651"),
+        ("Travel Now", "MER92456", "Travel", "4722"),
+        ("Gourmet Dining", "MER10387", "Restaurant", "5812"),
+        ("Harbor View Hotel", "MER53190", "Lodging", "7011"),
+        ("Metro Car Rental", "MER66342", "Car Rental", "7512"),
+    ]
+    .iter()
+    .map(|(name, id, category, mcc)| Merchant {
+        name: name.to_string(),
+        id: id.to_string(),
+        category: category.to_string(),
+        mcc: Some(mcc.to_string()),
+        descriptor: None,
+    })
+    .collect()
+}
@@ -0,0 +1,375 @@
+use crate::{
+    brand::{CardBrand, IinRange},
+    config::Profile,
+    currency,
+    fit::same_name,
+    loader, luhn, mcc,
+    model::{DeclineReason, TransactionStatus},
+};
+use chrono::{DateTime, Datelike, NaiveDate};
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+// A rule a row of a card data file breaks
+#[derive(Debug)]
+pub struct Violation {
+    // Row of data, counting from 1 after any header
+    pub row: u64,
+    pub column: &'static str,
+    pub message: String,
+}
+
+// Rows checked and those that broke a rule
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub rows: u64,
+    pub violations: u64,
+    pub bad_rows: u64,
+}
+
+// Check every row of a CSV file with a header row, or a JSON Lines file, as
+// told by its extension, against the rules card data follows: card numbers
+// pass the Luhn check and fit the prefixes and lengths of their brand, CVVs
+// are as long as the brand's, expiry dates are MM/YY, currencies are active
+// ISO 4217 codes, MCCs are four digits and only declined rows have a
+// decline reason. Columns are named as in luhnsynth's output and any
+// the file lacks aren't checked. Each violation is passed to `report` as
+// it's found.
+pub fn check_file(
+    path: &Path,
+    profile: &Profile,
+    mut report: impl FnMut(Violation),
+) -> io::Result<Summary> {
+    let mut summary = Summary::default();
+    let mut check = |row: HashMap<String, String>| {
+        summary.rows += 1;
+        let violations = check_row(&row, &profile.card_brands);
+        summary.bad_rows += u64::from(!violations.is_empty());
+        for (column, message) in violations {
+            summary.violations += 1;
+            report(Violation {
+                row: summary.rows,
+                column,
+                message,
+            });
+        }
+    };
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("csv") => {
+            let mut reader = loader::open(path, &[])?;
+            let headers = reader
+                .headers()
+                .map_err(|e| loader::invalid(path, e))?
+                .clone();
+            for record in reader.records() {
+                let record = record.map_err(|e| loader::invalid(path, e))?;
+                let row = headers
+                    .iter()
+                    .zip(record.iter())
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect();
+                check(row);
+            }
+        }
+        Some("jsonl" | "ndjson") => {
+            let reader = BufReader::new(File::open(path)?);
+            for (i, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let object: Map<String, Value> = serde_json::from_str(&line)
+                    .map_err(|e| loader::invalid(path, format!("line {}: {}", i + 1, e)))?;
+                // Numbers are checked as written, as CVVs may be
+                let row = object
+                    .into_iter()
+                    .filter_map(|(name, value)| match value {
+                        Value::String(value) => Some((name, value)),
+                        Value::Number(value) => Some((name, value.to_string())),
+                        _ => None,
+                    })
+                    .collect();
+                check(row);
+            }
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "can't tell the format of {} from its extension; expected .csv or .jsonl",
+                    path.display()
+                ),
+            ));
+        }
+    }
+    Ok(summary)
+}
+
+// Rules a row breaks, with the column each is about
+fn check_row(row: &HashMap<String, String>, brands: &[CardBrand]) -> Vec<(&'static str, String)> {
+    let get = |name: &str| {
+        row.get(name)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    };
+    let mut violations = Vec::new();
+
+    // The brand a row names, or else the one its card number belongs to
+    let brand = match get("card_brand") {
+        Some(name) => {
+            let brand = brands.iter().find(|brand| same_name(&brand.name, name));
+            if brand.is_none() {
+                violations.push(("card_brand", format!("`{}` isn't a known card brand", name)));
+            }
+            brand
+        }
+        None => get("card_number").and_then(|pan| CardBrand::detect_in(pan, brands)),
+    };
+
+    if let Some(pan) = get("card_number") {
+        let digits: String = pan.chars().filter(|c| *c != ' ' && *c != '-').collect();
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            violations.push(("card_number", format!("`{}` isn't a card number", pan)));
+        } else {
+            if !luhn::is_valid(&digits) {
+                violations.push(("card_number", format!("{} fails the Luhn check", pan)));
+            }
+            match brand {
+                Some(brand) => {
+                    let prefixed = brand
+                        .prefix
+                        .iter()
+                        .filter_map(|prefix| IinRange::parse(prefix))
+                        .any(|range| range.contains(&digits));
+                    if !prefixed {
+                        violations.push((
+                            "card_number",
+                            format!("{} doesn't start with a {} prefix", pan, brand.name),
+                        ));
+                    }
+                    if !brand.lengths.contains(&digits.len()) {
+                        violations.push((
+                            "card_number",
+                            format!(
+                                "{} has {} digits, which {} cards don't",
+                                pan,
+                                digits.len(),
+                                brand.name
+                            ),
+                        ));
+                    }
+                }
+                None if get("card_brand").is_none() => {
+                    violations.push((
+                        "card_number",
+                        format!("{} has the prefix of no known card brand", pan),
+                    ));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Some(cvv), Some(brand)) = (get("cvv"), brand)
+        && (cvv.len() != brand.cvv_length || !cvv.chars().all(|c| c.is_ascii_digit()))
+    {
+        violations.push((
+            "cvv",
+            format!(
+                "`{}` isn't a {}-digit {} CVV",
+                cvv, brand.cvv_length, brand.name
+            ),
+        ));
+    }
+
+    let expiry = get("card_expiry").and_then(|expiry| {
+        let parsed = parse_expiry(expiry);
+        if parsed.is_none() {
+            violations.push(("card_expiry", format!("`{}` isn't an MM/YY date", expiry)));
+        }
+        parsed
+    });
+
+    if let Some(code) = get("currency")
+        && currency::numeric_code(code).is_none()
+    {
+        violations.push(("currency", format!("`{}` isn't an ISO 4217 currency", code)));
+    }
+
+    if let Some(code) = get("mcc")
+        && !mcc::is_well_formed(code)
+    {
+        violations.push(("mcc", format!("`{}` isn't a merchant category code", code)));
+    }
+
+    let status = get("status").and_then(|status| {
+        let parsed = TransactionStatus::ALL
+            .into_iter()
+            .find(|s| s.as_str() == status);
+        if parsed.is_none() {
+            violations.push(("status", format!("`{}` isn't a known status", status)));
+        }
+        parsed
+    });
+    let reason = get("decline_reason");
+    if let Some(reason) = reason
+        && !DeclineReason::ALL.iter().any(|r| r.as_str() == reason)
+    {
+        violations.push((
+            "decline_reason",
+            format!("`{}` isn't a known decline reason", reason),
+        ));
+    }
+    match (status, reason) {
+        (Some(TransactionStatus::Declined), None) => {
+            violations.push(("decline_reason", "declined without a reason".to_string()));
+        }
+        (Some(status), Some(reason)) if status != TransactionStatus::Declined => {
+            violations.push((
+                "decline_reason",
+                format!("`{}` given for a row that is {}", reason, status.as_str()),
+            ));
+        }
+        _ => {}
+    }
+
+    // Cards are good through the end of their expiry month, and one that
+    // had run out by the transaction can only be declined. Issuers decline
+    // as card_expired for a wrong expiry date too, so the reverse isn't
+    // checked.
+    let date = get("transaction_date")
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.date_naive());
+    if let (Some(expiry), Some(date), Some(status)) = (expiry, date, status)
+        && expiry < NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap()
+        && status != TransactionStatus::Declined
+    {
+        violations.push((
+            "card_expiry",
+            format!(
+                "card expired before the transaction but is {}",
+                status.as_str()
+            ),
+        ));
+    }
+    violations
+}
+
+// First day of the expiry month of an MM/YY date
+pub(crate) fn parse_expiry(expiry: &str) -> Option<NaiveDate> {
+    let (month, year) = expiry.split_once('/')?;
+    let digits = |s: &str| s.len() == 2 && s.chars().all(|c| c.is_ascii_digit());
+    if !digits(month) || !digits(year) {
+        return None;
+    }
+    let (month, year) = (month.parse::<u32>().ok()?, year.parse::<i32>().ok()?);
+    NaiveDate::from_ymd_opt(2000 + year, month, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const HEADER: &str = concat!(
+        "card_brand,card_number,cvv,card_expiry,currency,mcc,",
+        "status,decline_reason,transaction_date"
+    );
+    const VALID: &str = "Visa,4111111111111111,123,12/28,USD,5411,approved,,2026-03-05T14:07:09Z";
+
+    // Check a CSV file of the rows given, named after the test, returning
+    // the summary and the row and column of each violation
+    fn check(test: &str, rows: &[&str]) -> (Summary, Vec<(u64, &'static str)>) {
+        let path = std::env::temp_dir().join(format!(
+            "luhnsynth-{}-{}.csv",
+            test,
+            std::process::id()
+        ));
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "{}", HEADER).unwrap();
+        for row in rows {
+            writeln!(file, "{}", row).unwrap();
+        }
+        drop(file);
+        let mut violations = Vec::new();
+        let summary = check_file(&path, &Profile::default(), |violation| {
+            violations.push((violation.row, violation.column))
+        });
+        std::fs::remove_file(&path).unwrap();
+        (summary.unwrap(), violations)
+    }
+
+    #[test]
+    fn valid_rows_pass() {
+        let (summary, violations) = check("valid_rows_pass", &[
+            VALID,
+            "Mastercard,5555555555554444,737,01/27,EUR,5812,declined,insufficient_funds,\
+             2026-03-05T14:07:09Z",
+            "American Express,378282246310005,1234,06/30,JPY,4111,approved,,2026-03-05T14:07:09Z",
+        ]);
+        assert_eq!(violations, []);
+        assert_eq!((summary.rows, summary.violations, summary.bad_rows), (3, 0, 0));
+    }
+
+    #[test]
+    fn violations_are_reported_with_their_rows() {
+        let (summary, violations) = check("violations_are_reported_with_their_rows", &[
+            "Visa,4111111111111112,123,12/28,USD,5411,approved,,2026-03-05T14:07:09Z",
+            VALID,
+            "Visa,4111111111111111,123,13/28,USD,5411,approved,,2026-03-05T14:07:09Z",
+            "Visa,4111111111111111,123,12/28,XYZ,5411,approved,,2026-03-05T14:07:09Z",
+            "Visa,4111111111111111,123,12/28,USD,541,approved,,2026-03-05T14:07:09Z",
+            "Visa,4111111111111111,1234,12/28,USD,5411,declined,,2026-03-05T14:07:09Z",
+            "Visa,4111111111111111,123,01/26,USD,5411,approved,expired_card,\
+             2026-03-05T14:07:09Z",
+        ]);
+        assert_eq!(
+            violations,
+            [
+                (1, "card_number"),
+                (3, "card_expiry"),
+                (4, "currency"),
+                (5, "mcc"),
+                (6, "cvv"),
+                (6, "decline_reason"),
+                (7, "decline_reason"),
+                (7, "decline_reason"),
+                (7, "card_expiry"),
+            ]
+        );
+        assert_eq!((summary.rows, summary.violations, summary.bad_rows), (7, 9, 6));
+    }
+
+    #[test]
+    fn brands_are_checked() {
+        let (_, violations) = check("brands_are_checked", &[
+            // A Mastercard number given as Visa
+            "Visa,5555555555554444,123,12/28,USD,5411,approved,,2026-03-05T14:07:09Z",
+            "Visa,41111111111111111,123,12/28,USD,5411,approved,,2026-03-05T14:07:09Z",
+            "Dinosaur,4111111111111111,123,12/28,USD,5411,approved,,2026-03-05T14:07:09Z",
+        ]);
+        assert_eq!(
+            violations,
+            [
+                (1, "card_number"),
+                (2, "card_number"),
+                (2, "card_number"),
+                (3, "card_brand"),
+            ]
+        );
+    }
+
+    #[test]
+    fn expiry_dates_are_mm_yy() {
+        assert_eq!(parse_expiry("12/28"), NaiveDate::from_ymd_opt(2028, 12, 1));
+        assert_eq!(parse_expiry("00/28"), None);
+        assert_eq!(parse_expiry("1/28"), None);
+        assert_eq!(parse_expiry("12/2028"), None);
+        assert_eq!(parse_expiry("1228"), None);
+    }
+}
@@ -0,0 +1,272 @@
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+// Regions that profiles can draw currencies from. Each currency belongs to
+// one geographic region; the EU covers the euro and the currencies of EU
+// members that haven't adopted it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Region {
+    World,
+    Eu,
+    Europe,
+    NorthAmerica,
+    LatinAmerica,
+    MiddleEast,
+    Africa,
+    AsiaPacific,
+}
+
+impl Region {
+    // Whether a currency of the given geographic region is used in this one
+    fn contains(self, code: &str, region: Region) -> bool {
+        match self {
+            Region::World => true,
+            Region::Eu => EU_CURRENCIES.contains(&code),
+            _ => self == region,
+        }
+    }
+}
+
+const EU_CURRENCIES: [&str; 8] = ["EUR", "BGN", "CZK", "DKK", "HUF", "PLN", "RON", "SEK"];
+
+// Active ISO 4217 currencies with their numeric code, minor-unit exponent
+// (the number of decimals amounts in the currency are given with), region,
+// approximate units per US dollar and rough share of card payments
+// worldwide, in percent. Fund codes and currencies no longer in circulation
+// have no share, so they are never drawn for transactions.
+type Entry = (&'static str, &'static str, u32, Region, f64, f64);
+
+const CURRENCIES: [Entry; 165] = [
+    ("AED", "784", 2, Region::MiddleEast, 3.6725, 0.4),
+    ("AFN", "971", 2, Region::AsiaPacific, 70.0, 0.01),
+    ("ALL", "008", 2, Region::Europe, 92.0, 0.01),
+    ("AMD", "051", 2, Region::Europe, 390.0, 0.01),
+    ("ANG", "532", 2, Region::LatinAmerica, 1.79, 0.01),
+    ("AOA", "973", 2, Region::Africa, 910.0, 0.01),
+    ("ARS", "032", 2, Region::LatinAmerica, 1400.0, 0.2),
+    ("AUD", "036", 2, Region::AsiaPacific, 1.52, 2.0),
+    ("AWG", "533", 2, Region::LatinAmerica, 1.79, 0.01),
+    ("AZN", "944", 2, Region::Europe, 1.7, 0.02),
+    ("BAM", "977", 2, Region::Europe, 1.8, 0.02),
+    ("BBD", "052", 2, Region::LatinAmerica, 2.0, 0.01),
+    ("BDT", "050", 2, Region::AsiaPacific, 120.0, 0.05),
+    ("BGN", "975", 2, Region::Europe, 1.8, 0.05),
+    ("BHD", "048", 3, Region::MiddleEast, 0.376, 0.03),
+    ("BIF", "108", 0, Region::Africa, 2900.0, 0.01),
+    ("BMD", "060", 2, Region::NorthAmerica, 1.0, 0.01),
+    ("BND", "096", 2, Region::AsiaPacific, 1.34, 0.01),
+    ("BOB", "068", 2, Region::LatinAmerica, 6.9, 0.02),
+    ("BOV", "984", 2, Region::LatinAmerica, 6.9, 0.0),
+    ("BRL", "986", 2, Region::LatinAmerica, 5.0, 1.5),
+    ("BSD", "044", 2, Region::LatinAmerica, 1.0, 0.01),
+    ("BTN", "064", 2, Region::AsiaPacific, 83.3, 0.01),
+    ("BWP", "072", 2, Region::Africa, 13.6, 0.01),
+    ("BYN", "933", 2, Region::Europe, 3.3, 0.02),
+    ("BZD", "084", 2, Region::LatinAmerica, 2.0, 0.01),
+    ("CAD", "124", 2, Region::NorthAmerica, 1.36, 2.5),
+    ("CDF", "976", 2, Region::Africa, 2800.0, 0.01),
+    ("CHE", "947", 2, Region::Europe, 0.88, 0.0),
+    ("CHF", "756", 2, Region::Europe, 0.88, 0.8),
+    ("CHW", "948", 2, Region::Europe, 0.88, 0.0),
+    ("CLF", "990", 4, Region::LatinAmerica, 0.027, 0.0),
+    ("CLP", "152", 0, Region::LatinAmerica, 940.0, 0.2),
+    ("CNY", "156", 2, Region::AsiaPacific, 7.2, 6.0),
+    ("COP", "170", 2, Region::LatinAmerica, 4000.0, 0.2),
+    ("COU", "970", 2, Region::LatinAmerica, 4000.0, 0.0),
+    ("CRC", "188", 2, Region::LatinAmerica, 510.0, 0.03),
+    ("CUP", "192", 2, Region::LatinAmerica, 24.0, 0.01),
+    ("CVE", "132", 2, Region::Africa, 101.0, 0.01),
+    ("CZK", "203", 2, Region::Europe, 23.0, 0.2),
+    ("DJF", "262", 0, Region::Africa, 178.0, 0.01),
+    ("DKK", "208", 2, Region::Europe, 6.9, 0.3),
+    ("DOP", "214", 2, Region::LatinAmerica, 59.0, 0.03),
+    ("DZD", "012", 2, Region::Africa, 134.0, 0.02),
+    ("EGP", "818", 2, Region::Africa, 48.0, 0.1),
+    ("ERN", "232", 2, Region::Africa, 15.0, 0.01),
+    ("ETB", "230", 2, Region::Africa, 150.0, 0.02),
+    ("EUR", "978", 2, Region::Europe, 0.92, 20.0),
+    ("FJD", "242", 2, Region::AsiaPacific, 2.25, 0.01),
+    ("FKP", "238", 2, Region::LatinAmerica, 0.79, 0.01),
+    ("GBP", "826", 2, Region::Europe, 0.79, 6.0),
+    ("GEL", "981", 2, Region::Europe, 2.7, 0.02),
+    ("GHS", "936", 2, Region::Africa, 12.0, 0.03),
+    ("GIP", "292", 2, Region::Europe, 0.79, 0.01),
+    ("GMD", "270", 2, Region::Africa, 68.0, 0.01),
+    ("GNF", "324", 0, Region::Africa, 8600.0, 0.01),
+    ("GTQ", "320", 2, Region::LatinAmerica, 7.8, 0.03),
+    ("GYD", "328", 2, Region::LatinAmerica, 209.0, 0.01),
+    ("HKD", "344", 2, Region::AsiaPacific, 7.8, 0.6),
+    ("HNL", "340", 2, Region::LatinAmerica, 24.7, 0.02),
+    ("HTG", "332", 2, Region::LatinAmerica, 132.0, 0.01),
+    ("HUF", "348", 2, Region::Europe, 360.0, 0.15),
+    ("IDR", "360", 2, Region::AsiaPacific, 15700.0, 0.4),
+    ("ILS", "376", 2, Region::MiddleEast, 3.7, 0.2),
+    ("INR", "356", 2, Region::AsiaPacific, 83.3, 1.5),
+    ("IQD", "368", 3, Region::MiddleEast, 1310.0, 0.02),
+    ("IRR", "364", 2, Region::MiddleEast, 42000.0, 0.01),
+    ("ISK", "352", 0, Region::Europe, 138.0, 0.02),
+    ("JMD", "388", 2, Region::LatinAmerica, 156.0, 0.02),
+    ("JOD", "400", 3, Region::MiddleEast, 0.709, 0.02),
+    ("JPY", "392", 0, Region::AsiaPacific, 150.0, 4.0),
+    ("KES", "404", 2, Region::Africa, 129.0, 0.05),
+    ("KGS", "417", 2, Region::AsiaPacific, 87.0, 0.01),
+    ("KHR", "116", 2, Region::AsiaPacific, 4100.0, 0.01),
+    ("KMF", "174", 0, Region::Africa, 453.0, 0.01),
+    ("KPW", "408", 2, Region::AsiaPacific, 900.0, 0.0),
+    ("KRW", "410", 0, Region::AsiaPacific, 1350.0, 1.2),
+    ("KWD", "414", 3, Region::MiddleEast, 0.307, 0.08),
+    ("KYD", "136", 2, Region::LatinAmerica, 0.83, 0.01),
+    ("KZT", "398", 2, Region::AsiaPacific, 450.0, 0.05),
+    ("LAK", "418", 2, Region::AsiaPacific, 21000.0, 0.01),
+    ("LBP", "422", 2, Region::MiddleEast, 89500.0, 0.01),
+    ("LKR", "144", 2, Region::AsiaPacific, 300.0, 0.02),
+    ("LRD", "430", 2, Region::Africa, 190.0, 0.01),
+    ("LSL", "426", 2, Region::Africa, 18.6, 0.01),
+    ("LYD", "434", 3, Region::Africa, 4.8, 0.01),
+    ("MAD", "504", 2, Region::Africa, 10.0, 0.05),
+    ("MDL", "498", 2, Region::Europe, 17.7, 0.01),
+    ("MGA", "969", 2, Region::Africa, 4500.0, 0.01),
+    ("MKD", "807", 2, Region::Europe, 56.6, 0.01),
+    ("MMK", "104", 2, Region::AsiaPacific, 2100.0, 0.01),
+    ("MNT", "496", 2, Region::AsiaPacific, 3400.0, 0.01),
+    ("MOP", "446", 2, Region::AsiaPacific, 8.05, 0.02),
+    ("MRU", "929", 2, Region::Africa, 39.7, 0.01),
+    ("MUR", "480", 2, Region::Africa, 46.0, 0.01),
+    ("MVR", "462", 2, Region::AsiaPacific, 15.4, 0.01),
+    ("MWK", "454", 2, Region::Africa, 1730.0, 0.01),
+    ("MXN", "484", 2, Region::LatinAmerica, 17.1, 1.0),
+    ("MXV", "979", 2, Region::LatinAmerica, 2.1, 0.0),
+    ("MYR", "458", 2, Region::AsiaPacific, 4.7, 0.3),
+    ("MZN", "943", 2, Region::Africa, 63.9, 0.01),
+    ("NAD", "516", 2, Region::Africa, 18.6, 0.01),
+    ("NGN", "566", 2, Region::Africa, 1500.0, 0.15),
+    ("NIO", "558", 2, Region::LatinAmerica, 36.8, 0.01),
+    ("NOK", "578", 2, Region::Europe, 10.7, 0.4),
+    ("NPR", "524", 2, Region::AsiaPacific, 133.0, 0.02),
+    ("NZD", "554", 2, Region::AsiaPacific, 1.65, 0.3),
+    ("OMR", "512", 3, Region::MiddleEast, 0.385, 0.03),
+    ("PAB", "590", 2, Region::LatinAmerica, 1.0, 0.02),
+    ("PEN", "604", 2, Region::LatinAmerica, 3.75, 0.1),
+    ("PGK", "598", 2, Region::AsiaPacific, 3.9, 0.01),
+    ("PHP", "608", 2, Region::AsiaPacific, 56.0, 0.3),
+    ("PKR", "586", 2, Region::AsiaPacific, 280.0, 0.1),
+    ("PLN", "985", 2, Region::Europe, 4.0, 0.5),
+    ("PYG", "600", 0, Region::LatinAmerica, 7500.0, 0.02),
+    ("QAR", "634", 2, Region::MiddleEast, 3.64, 0.1),
+    ("RON", "946", 2, Region::Europe, 4.6, 0.15),
+    ("RSD", "941", 2, Region::Europe, 108.0, 0.03),
+    ("RUB", "643", 2, Region::Europe, 92.0, 0.6),
+    ("RWF", "646", 0, Region::Africa, 1300.0, 0.01),
+    ("SAR", "682", 2, Region::MiddleEast, 3.75, 0.4),
+    ("SBD", "090", 2, Region::AsiaPacific, 8.4, 0.01),
+    ("SCR", "690", 2, Region::Africa, 13.6, 0.01),
+    ("SDG", "938", 2, Region::Africa, 600.0, 0.01),
+    ("SEK", "752", 2, Region::Europe, 10.5, 0.5),
+    ("SGD", "702", 2, Region::AsiaPacific, 1.34, 0.5),
+    ("SHP", "654", 2, Region::Africa, 0.79, 0.01),
+    ("SLE", "925", 2, Region::Africa, 22.5, 0.01),
+    ("SOS", "706", 2, Region::Africa, 571.0, 0.01),
+    ("SRD", "968", 2, Region::LatinAmerica, 36.0, 0.01),
+    ("SSP", "728", 2, Region::Africa, 4500.0, 0.01),
+    ("STN", "930", 2, Region::Africa, 22.5, 0.01),
+    ("SVC", "222", 2, Region::LatinAmerica, 8.75, 0.0),
+    ("SYP", "760", 2, Region::MiddleEast, 13000.0, 0.01),
+    ("SZL", "748", 2, Region::Africa, 18.6, 0.01),
+    ("THB", "764", 2, Region::AsiaPacific, 36.0, 0.3),
+    ("TJS", "972", 2, Region::AsiaPacific, 10.9, 0.01),
+    ("TMT", "934", 2, Region::AsiaPacific, 3.5, 0.01),
+    ("TND", "788", 3, Region::Africa, 3.1, 0.02),
+    ("TOP", "776", 2, Region::AsiaPacific, 2.35, 0.01),
+    ("TRY", "949", 2, Region::Europe, 40.0, 0.5),
+    ("TTD", "780", 2, Region::LatinAmerica, 6.8, 0.02),
+    ("TWD", "901", 2, Region::AsiaPacific, 32.0, 0.4),
+    ("TZS", "834", 2, Region::Africa, 2600.0, 0.03),
+    ("UAH", "980", 2, Region::Europe, 41.0, 0.1),
+    ("UGX", "800", 0, Region::Africa, 3700.0, 0.02),
+    ("USD", "840", 2, Region::NorthAmerica, 1.0, 40.0),
+    ("USN", "997", 2, Region::NorthAmerica, 1.0, 0.0),
+    ("UYI", "940", 0, Region::LatinAmerica, 6.7, 0.0),
+    ("UYU", "858", 2, Region::LatinAmerica, 40.0, 0.03),
+    ("UYW", "927", 4, Region::LatinAmerica, 0.025, 0.0),
+    ("UZS", "860", 2, Region::AsiaPacific, 12700.0, 0.02),
+    ("VED", "926", 2, Region::LatinAmerica, 150.0, 0.01),
+    ("VES", "928", 2, Region::LatinAmerica, 150.0, 0.02),
+    ("VND", "704", 0, Region::AsiaPacific, 25000.0, 0.2),
+    ("VUV", "548", 0, Region::AsiaPacific, 119.0, 0.01),
+    ("WST", "882", 2, Region::AsiaPacific, 2.7, 0.01),
+    ("XAF", "950", 0, Region::Africa, 603.0, 0.03),
+    ("XCD", "951", 2, Region::LatinAmerica, 2.7, 0.01),
+    ("XOF", "952", 0, Region::Africa, 603.0, 0.03),
+    ("XPF", "953", 0, Region::AsiaPacific, 110.0, 0.01),
+    ("YER", "886", 2, Region::MiddleEast, 250.0, 0.01),
+    ("ZAR", "710", 2, Region::Africa, 18.6, 0.4),
+    ("ZMW", "967", 2, Region::Africa, 27.0, 0.02),
+    ("ZWG", "924", 2, Region::Africa, 27.0, 0.01),
+];
+
+// Exponent of currencies missing from the table, which like most currencies
+// are taken to have cents
+const DEFAULT_EXPONENT: u32 = 2;
+
+// The entry of a currency code, read from a table of where each possible
+// three-letter code is in CURRENCIES rather than searched for, since amounts
+// look their currency up several times a row
+fn find(currency: &str) -> Option<&'static Entry> {
+    static POSITIONS: OnceLock<Vec<u8>> = OnceLock::new();
+    let key = |code: &[u8]| -> Option<usize> {
+        code.iter().try_fold(0, |key, letter| {
+            letter
+                .is_ascii_uppercase()
+                .then(|| key * 26 + usize::from(letter - b'A'))
+        })
+    };
+    let positions = POSITIONS.get_or_init(|| {
+        let mut positions = vec![u8::MAX; 26 * 26 * 26];
+        for (i, (code, ..)) in CURRENCIES.iter().enumerate() {
+            positions[key(code.as_bytes()).expect("currency codes are three letters")] = i as u8;
+        }
+        positions
+    });
+    if currency.len() != 3 {
+        return None;
+    }
+    match positions[key(currency.as_bytes())?] {
+        u8::MAX => None,
+        i => Some(&CURRENCIES[usize::from(i)]),
+    }
+}
+
+// ISO 4217 numeric code of a currency, if it is an active one
+pub fn numeric_code(currency: &str) -> Option<&'static str> {
+    find(currency).map(|(_, numeric, ..)| *numeric)
+}
+
+// Number of decimals of a currency's minor unit: 0 for the yen, 2 for the
+// euro, 3 for the Bahraini dinar
+pub fn exponent(currency: &str) -> u32 {
+    find(currency).map_or(DEFAULT_EXPONENT, |(_, _, exponent, ..)| *exponent)
+}
+
+// Approximate mid-market units of a currency per US dollar
+pub fn usd_rate(currency: &str) -> Option<f64> {
+    find(currency).map(|(.., rate, _)| *rate)
+}
+
+// Factor that amounts drawn in dollar-like units are scaled by for a
+// currency: the power of ten nearest its dollar rate, so a coffee costs
+// around 5 dollars, 500 yen or 50,000 rupiah. Currencies without a rate are
+// left as they are.
+pub fn amount_scale(currency: &str) -> f64 {
+    usd_rate(currency).map_or(1.0, |rate| 10f64.powf(rate.log10().round()))
+}
+
+// Currencies of a region with their share of card payments, in code order
+pub fn catalogue(region: Region) -> impl Iterator<Item = (&'static str, f64)> {
+    CURRENCIES
+        .iter()
+        .filter(move |(code, _, _, currency_region, _, share)| {
+            *share > 0.0 && region.contains(code, *currency_region)
+        })
+        .map(|(code, .., share)| (*code, *share))
+}
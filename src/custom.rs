@@ -0,0 +1,204 @@
+use crate::{
+    model::Transaction,
+    money::AmountFormat,
+    output::{CSV_HEADER, csv_record},
+};
+use rand::{Rng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+// An extra column appended to every row, with the one generator its value
+// comes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomField {
+    pub name: String,
+    // The same string, number or boolean in every row
+    #[serde(default)]
+    pub constant: Option<Value>,
+    // A whole number between the two bounds, inclusive
+    #[serde(default)]
+    pub int_range: Option<[i64; 2]>,
+    // One of the values, each as likely as the others
+    #[serde(default)]
+    pub pick: Option<Vec<String>>,
+    // Text with `{field}` replaced by the value of a field of the row, or of
+    // a custom field listed before this one
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+// Type of a custom field's values, which typed formats declare its column as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomType {
+    Text,
+    Integer,
+    Float,
+    Boolean,
+}
+
+impl CustomField {
+    pub fn kind(&self) -> CustomType {
+        match (&self.constant, self.int_range) {
+            (Some(Value::Bool(_)), _) => CustomType::Boolean,
+            (Some(Value::Number(number)), _) if number.is_f64() => CustomType::Float,
+            (Some(Value::Number(_)), _) | (_, Some(_)) => CustomType::Integer,
+            _ => CustomType::Text,
+        }
+    }
+
+    // Reasons the field can't be generated, if any, given the names of the
+    // fields before it
+    pub fn check(&self, before: &[&str]) -> Result<(), String> {
+        if self.name.is_empty()
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            || self.name.starts_with(|c: char| c.is_ascii_digit())
+        {
+            return Err(
+                "name must be letters, digits and underscores, not starting with a digit"
+                    .to_string(),
+            );
+        }
+        if before.contains(&self.name.as_str()) {
+            return Err("is already a field".to_string());
+        }
+        let generators = [
+            self.constant.is_some(),
+            self.int_range.is_some(),
+            self.pick.is_some(),
+            self.template.is_some(),
+        ];
+        if generators.iter().filter(|set| **set).count() != 1 {
+            return Err("needs exactly one of constant, int_range, pick or template".to_string());
+        }
+        if let Some(constant) = &self.constant
+            && !matches!(
+                constant,
+                Value::String(_) | Value::Number(_) | Value::Bool(_)
+            )
+        {
+            return Err("constant must be a string, number or boolean".to_string());
+        }
+        if let Some([min, max]) = self.int_range
+            && min > max
+        {
+            return Err(format!("int_range starts above its end, at {}", min));
+        }
+        if self.pick.as_ref().is_some_and(Vec::is_empty) {
+            return Err("pick needs at least one value".to_string());
+        }
+        if let Some(template) = &self.template {
+            for part in placeholders(template)? {
+                if !before.contains(&part) {
+                    return Err(format!("template: no field {:?} before this one", part));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn value<R: Rng + ?Sized>(&self, rng: &mut R, fields: &dyn Fn(&str) -> String) -> Value {
+        if let Some(constant) = &self.constant {
+            constant.clone()
+        } else if let Some([min, max]) = self.int_range {
+            Value::from(rng.gen_range(min..=max))
+        } else if let Some(values) = &self.pick {
+            Value::from(
+                values
+                    .choose(rng)
+                    .expect("pick lists are validated")
+                    .clone(),
+            )
+        } else {
+            let template = self.template.as_deref().unwrap_or_default();
+            let mut text = String::new();
+            let mut rest = template;
+            while let Some(open) = rest.find('{') {
+                let close = rest[open..].find('}').expect("templates are validated") + open;
+                text.push_str(&rest[..open]);
+                text.push_str(&fields(&rest[open + 1..close]));
+                rest = &rest[close + 1..];
+            }
+            text.push_str(rest);
+            Value::from(text)
+        }
+    }
+}
+
+// Names of the fields a template refers to
+fn placeholders(template: &str) -> Result<Vec<&str>, String> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find(['{', '}']) {
+        let close = match rest[open..].find('}') {
+            Some(close) if rest[open..].starts_with('{') => close + open,
+            _ => return Err("template has an unmatched brace".to_string()),
+        };
+        names.push(&rest[open + 1..close]);
+        rest = &rest[close + 1..];
+    }
+    Ok(names)
+}
+
+// Reasons the custom fields can't be generated, if any, prefixed with the
+// field at fault
+pub fn check(fields: &[CustomField]) -> Result<(), String> {
+    let mut names: Vec<&str> = CSV_HEADER.to_vec();
+    for field in fields {
+        field
+            .check(&names)
+            .map_err(|e| format!("\"{}\": {}", field.name, e))?;
+        names.push(&field.name);
+    }
+    Ok(())
+}
+
+// The text a custom value is written as in text formats: strings as they
+// are, numbers and booleans as in JSON, and nothing for a missing value
+pub fn text(value: Option<&Value>) -> Cow<'_, str> {
+    match value {
+        None | Some(Value::Null) => Cow::Borrowed(""),
+        Some(Value::String(text)) => Cow::Borrowed(text),
+        Some(value) => Cow::Owned(value.to_string()),
+    }
+}
+
+// Appends the profile's custom fields to rows
+pub struct CustomFields {
+    fields: Vec<CustomField>,
+    amount_format: AmountFormat,
+    // Whether a template needs the row's fields as text
+    templated: bool,
+}
+
+impl CustomFields {
+    // The profile's custom fields, if it has any
+    pub fn new(fields: &[CustomField], amount_format: AmountFormat) -> Option<Self> {
+        (!fields.is_empty()).then(|| Self {
+            fields: fields.to_vec(),
+            amount_format,
+            templated: fields.iter().any(|field| field.template.is_some()),
+        })
+    }
+
+    pub fn apply<R: Rng + ?Sized>(&self, rng: &mut R, tx: &mut Transaction) {
+        // Templates see the row's fields as they are written to CSV
+        let mut record = csv::StringRecord::new();
+        if self.templated {
+            csv_record(tx, self.amount_format, &mut record, &mut String::new());
+        }
+        let mut values = Map::new();
+        for field in &self.fields {
+            let lookup = |name: &str| match CSV_HEADER.iter().position(|column| *column == name) {
+                Some(i) => record[i].to_string(),
+                None => text(values.get(name)).into_owned(),
+            };
+            let value = field.value(rng, &lookup);
+            values.insert(field.name.clone(), value);
+        }
+        tx.custom = values;
+    }
+}
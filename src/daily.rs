@@ -0,0 +1,64 @@
+use crate::{config::Merchant, model::Customer};
+use chrono::{Days, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+// Size of the customer pool of a feed whose profile doesn't set one, as
+// customers have to persist from one day to the next
+pub const DEFAULT_CUSTOMERS: usize = 1000;
+
+// What a daily feed keeps between runs: the last day it generated, and the
+// customers and merchants as they stood at the end of it, so cardholders
+// return with the same cards, devices and addresses, and moves and
+// rebrands carry over to the following days
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyState {
+    pub last_day: NaiveDate,
+    pub customers: Vec<Customer>,
+    pub merchants: Vec<Merchant>,
+}
+
+impl DailyState {
+    // Read the state of a feed, or nothing when it hasn't run yet
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let state = serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid state file {}: {}", path.display(), e),
+            )
+        })?;
+        Ok(Some(state))
+    }
+
+    // Write the state next to its path first and move it into place, so an
+    // interrupted run leaves the previous state intact
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        let mut out = BufWriter::new(File::create(&temporary)?);
+        serde_json::to_writer(&mut out, self)?;
+        out.flush()?;
+        drop(out);
+        fs::rename(&temporary, path)
+    }
+
+    // The day a run generates: the one after the last
+    pub fn next_day(&self) -> NaiveDate {
+        self.last_day + Days::new(1)
+    }
+}
+
+// The last day that has ended in a timezone, which a feed catches up to
+pub fn yesterday(tz: Tz) -> NaiveDate {
+    Utc::now().with_timezone(&tz).date_naive() - Days::new(1)
+}
@@ -0,0 +1,108 @@
+use crate::config::Merchant;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use sha2::{Digest, Sha256};
+
+// Prefixes payment facilitators put before the names of the merchants they
+// sign up
+const FACILITATORS: [&str; 6] = ["SQ *", "TST* ", "PAYPAL *", "SP ", "PY *", "CKO*"];
+
+// Toll-free area codes of customer service numbers
+const TOLL_FREE: [&str; 5] = ["800", "833", "855", "866", "888"];
+
+// Cities merchants are located in, shortened to the 13 characters a
+// descriptor has for them, with their state
+const CITIES: [(&str, &str); 10] = [
+    ("NEW YORK", "NY"),
+    ("LOS ANGELES", "CA"),
+    ("SAN FRANCISCO", "CA"),
+    ("CHICAGO", "IL"),
+    ("HOUSTON", "TX"),
+    ("SEATTLE", "WA"),
+    ("MIAMI", "FL"),
+    ("BOSTON", "MA"),
+    ("ATLANTA", "GA"),
+    ("SALT LAKE CTY", "UT"),
+];
+
+// The text cardholders see for the merchant on their statement, unless the
+// merchant has its own. Descriptors are as messy as real ones: upper case,
+// abbreviated, cut short, with store numbers and facilitator prefixes, and a
+// phone number, city or website before the state. They are drawn from a
+// generator seeded with the merchant ID, so a merchant keeps its descriptor
+// across rows and runs.
+pub fn descriptor(merchant: &Merchant) -> String {
+    if let Some(descriptor) = &merchant.descriptor {
+        return descriptor.clone();
+    }
+    let digest = Sha256::digest(merchant.id.as_bytes());
+    let mut rng = StdRng::seed_from_u64(u64::from_be_bytes(digest[..8].try_into().unwrap()));
+
+    let words: Vec<String> = merchant
+        .name
+        .to_uppercase()
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '&')
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+    let words = if words.is_empty() {
+        vec!["MERCHANT".to_string()]
+    } else {
+        words
+    };
+    let words: Vec<String> = if rng.gen_bool(0.4) {
+        words.iter().map(|word| without_vowels(word)).collect()
+    } else {
+        words
+    };
+    let separator = match rng.gen_range(0..10) {
+        0..=5 => " ",
+        6..=7 => "",
+        _ => "*",
+    };
+    let mut name = words.join(separator);
+    if rng.gen_bool(0.3) {
+        name.truncate(rng.gen_range(8..=14));
+    }
+    if rng.gen_bool(0.25) {
+        name.push_str(&format!(" #{}", rng.gen_range(100..10000)));
+    }
+    if rng.gen_bool(0.3) {
+        name.insert_str(0, FACILITATORS.choose(&mut rng).unwrap());
+    }
+    name.truncate(25);
+
+    let (city, state) = *CITIES.choose(&mut rng).unwrap();
+    let location = match rng.gen_range(0..3) {
+        // Numbers in the 555-01xx range set aside for fiction
+        0 => format!(
+            "{}-5550{}",
+            TOLL_FREE.choose(&mut rng).unwrap(),
+            rng.gen_range(100..200)
+        ),
+        1 => city.to_string(),
+        _ => {
+            let mut site = words.concat();
+            site.truncate(9);
+            format!("{}.COM", site)
+        }
+    };
+    // Some acquirers pad the fields to their fixed positions
+    if rng.gen_bool(0.5) {
+        format!("{:<25}{:<13}{}", name, location, state)
+    } else {
+        format!("{} {} {}", name, location, state)
+    }
+}
+
+// A word with the vowels after its first letter left out, as in `FSHN`
+fn without_vowels(word: &str) -> String {
+    let mut chars = word.chars();
+    let first = chars.next().into_iter();
+    first
+        .chain(chars.filter(|c| !matches!(c, 'A' | 'E' | 'I' | 'O' | 'U')))
+        .collect()
+}
@@ -0,0 +1,212 @@
+use crate::{generator::gen_device_id, manifest::hex, model::Transaction, text::Text};
+use rand::{Rng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// A customer's device. The platform, OS and browser are read from the user
+// agent, and the screen resolution suits the kind of device, so the
+// attributes never contradict each other.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Device {
+    pub device_id: Text,
+    pub user_agent: Text,
+    // Windows, macOS, iOS, Android, Linux, ChromeOS or Other
+    pub platform: Text,
+    // Operating system and version, e.g. "iOS 14.6"
+    pub os: Text,
+    // Browser family and major version, e.g. "Chrome 91"
+    pub browser: Text,
+    // Width and height in CSS pixels, e.g. "1920x1080"
+    pub screen_resolution: Text,
+    // Hash of the attributes above, stable for the device
+    pub fingerprint: Text,
+}
+
+// A user agent with the platform, OS, browser and screens it names, read
+// once for all the devices that run it
+#[derive(Debug, Clone)]
+pub struct UserAgent {
+    user_agent: Text,
+    platform: &'static str,
+    os: Text,
+    browser: Text,
+    screens: &'static [&'static str],
+}
+
+impl UserAgent {
+    pub fn parse(user_agent: &str) -> Self {
+        let (platform, os) = operating_system(user_agent);
+        Self {
+            user_agent: user_agent.into(),
+            platform,
+            os: os.into(),
+            browser: browser(user_agent).into(),
+            screens: screens(user_agent, platform),
+        }
+    }
+}
+
+impl Device {
+    // A new device running the given user agent
+    pub fn new<R: Rng + ?Sized>(rng: &mut R, user_agent: &str) -> Self {
+        Self::running(rng, &UserAgent::parse(user_agent))
+    }
+
+    // A new device running a user agent already read
+    pub fn running<R: Rng + ?Sized>(rng: &mut R, agent: &UserAgent) -> Self {
+        let device_id = gen_device_id();
+        let screen_resolution = *agent.screens.choose(rng).unwrap();
+
+        let mut hasher = Sha256::new();
+        for attribute in [&*device_id, &*agent.user_agent, screen_resolution] {
+            hasher.update(attribute.as_bytes());
+            hasher.update([0]);
+        }
+        let fingerprint = hex(&hasher.finalize()[..16]);
+
+        Self {
+            device_id,
+            user_agent: agent.user_agent.clone(),
+            platform: Text::from_static(agent.platform),
+            os: agent.os.clone(),
+            browser: agent.browser.clone(),
+            screen_resolution: Text::from_static(screen_resolution),
+            fingerprint: fingerprint.into(),
+        }
+    }
+
+    // Put the device's attributes on a transaction made from it
+    pub fn assign(&self, tx: &mut Transaction) {
+        tx.device_id = Some(self.device_id.clone());
+        tx.device_platform = Some(self.platform.clone());
+        tx.device_os = Some(self.os.clone());
+        tx.device_browser = Some(self.browser.clone());
+        tx.screen_resolution = Some(self.screen_resolution.clone());
+        tx.device_fingerprint = Some(self.fingerprint.clone());
+        tx.user_agent = Some(self.user_agent.clone());
+    }
+}
+
+// Platform and operating system named by a user agent
+fn operating_system(user_agent: &str) -> (&'static str, String) {
+    if let Some(version) = ["iPhone OS ", "CPU OS ", "; iOS "]
+        .iter()
+        .find_map(|marker| version_after(user_agent, marker))
+    {
+        return ("iOS", format!("iOS {}", version.replace('_', ".")));
+    }
+    if let Some(version) = version_after(user_agent, "Android ") {
+        return ("Android", format!("Android {}", version));
+    }
+    if user_agent.contains("Android") {
+        return ("Android", "Android".to_string());
+    }
+    if let Some(version) = version_after(user_agent, "Windows NT ") {
+        let name = match version.as_str() {
+            "10.0" => "Windows 10",
+            "6.3" => "Windows 8.1",
+            "6.2" => "Windows 8",
+            "6.1" => "Windows 7",
+            _ => "Windows",
+        };
+        return ("Windows", name.to_string());
+    }
+    if let Some(version) = version_after(user_agent, "Mac OS X ") {
+        return ("macOS", format!("macOS {}", version.replace('_', ".")));
+    }
+    if user_agent.contains("CrOS") {
+        return ("ChromeOS", "ChromeOS".to_string());
+    }
+    if user_agent.contains("Linux") {
+        return ("Linux", "Linux".to_string());
+    }
+    ("Other", "Other".to_string())
+}
+
+// Browser family and major version named by a user agent. Browsers built on
+// Chrome or Safari name those too, so they are checked first. Apps name
+// themselves in place of `Mozilla/5.0`.
+fn browser(user_agent: &str) -> String {
+    const BROWSERS: [(&str, &str); 8] = [
+        ("Edg/", "Edge"),
+        ("EdgiOS/", "Edge"),
+        ("OPR/", "Opera"),
+        ("SamsungBrowser/", "Samsung Internet"),
+        ("Firefox/", "Firefox"),
+        ("FxiOS/", "Firefox"),
+        ("CriOS/", "Chrome"),
+        ("Chrome/", "Chrome"),
+    ];
+    for (marker, name) in BROWSERS {
+        if let Some(version) = version_after(user_agent, marker) {
+            return format!("{} {}", name, major(&version));
+        }
+    }
+    if let Some(version) = version_after(user_agent, "Version/")
+        && user_agent.contains("Safari/")
+    {
+        return format!("Safari {}", major(&version));
+    }
+    match user_agent.split_once('/') {
+        Some((app, version)) if !app.is_empty() && !app.contains(' ') && app != "Mozilla" => {
+            format!(
+                "{} {}",
+                app,
+                major(version.split(' ').next().unwrap_or(version))
+            )
+        }
+        _ => "Other".to_string(),
+    }
+}
+
+// The version number that follows a marker in a user agent
+fn version_after(user_agent: &str, marker: &str) -> Option<String> {
+    let start = user_agent.find(marker)? + marker.len();
+    let version: String = user_agent[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.' || *c == '_')
+        .collect();
+    (!version.is_empty()).then_some(version)
+}
+
+fn major(version: &str) -> &str {
+    version.split(['.', '_']).next().unwrap_or(version)
+}
+
+// Common screen resolutions in CSS pixels, by kind of device
+const PHONE_SCREENS: [&str; 6] = [
+    "375x667", "390x844", "393x852", "414x896", "428x926", "430x932",
+];
+const ANDROID_SCREENS: [&str; 6] = [
+    "360x780", "360x800", "384x854", "393x873", "412x892", "412x915",
+];
+const TABLET_SCREENS: [&str; 4] = ["810x1080", "820x1180", "834x1194", "1024x1366"];
+const ANDROID_TABLET_SCREENS: [&str; 3] = ["800x1280", "1200x1920", "1600x2560"];
+const MAC_SCREENS: [&str; 6] = [
+    "1440x900",
+    "1512x982",
+    "1680x1050",
+    "1728x1117",
+    "1920x1080",
+    "2560x1440",
+];
+const DESKTOP_SCREENS: [&str; 6] = [
+    "1280x720",
+    "1366x768",
+    "1440x900",
+    "1536x864",
+    "1920x1080",
+    "2560x1440",
+];
+
+// Screen resolutions of the kind of device a user agent runs on
+fn screens(user_agent: &str, platform: &str) -> &'static [&'static str] {
+    match platform {
+        "iOS" if user_agent.contains("iPad") => &TABLET_SCREENS,
+        "iOS" => &PHONE_SCREENS,
+        "Android" if !user_agent.contains("Mobile") => &ANDROID_TABLET_SCREENS,
+        "Android" => &ANDROID_SCREENS,
+        "macOS" => &MAC_SCREENS,
+        _ => &DESKTOP_SCREENS,
+    }
+}
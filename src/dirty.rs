@@ -0,0 +1,330 @@
+use crate::{
+    model::{LifecycleEvent, Transaction},
+    temporal,
+    text::Text,
+};
+use chrono::{DateTime, Duration, FixedOffset, Timelike};
+use chrono_tz::Tz;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+
+// Rates of deliberate data-quality problems, each the share of rows that get
+// it. A row can get several of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirtyConfig {
+    pub enabled: bool,
+    // A field blanked, or set to null where the field is optional
+    pub nulls: f64,
+    // `transaction_date` in a broken or non-RFC 3339 form
+    pub malformed_dates: f64,
+    // Card numbers missing their last digits
+    pub truncated_pans: f64,
+    // Invisible, replacement, bidi-control or mis-decoded characters in a
+    // text field
+    pub bad_characters: f64,
+    // Rows written twice in a row
+    pub duplicates: f64,
+    // Timestamps moved before the event they follow
+    pub out_of_order: f64,
+}
+
+impl Default for DirtyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            nulls: 0.01,
+            malformed_dates: 0.01,
+            truncated_pans: 0.01,
+            bad_characters: 0.01,
+            duplicates: 0.01,
+            out_of_order: 0.01,
+        }
+    }
+}
+
+impl DirtyConfig {
+    // Each rate with the name it is configured under
+    pub fn rates(&self) -> [(&'static str, f64); 6] {
+        [
+            ("nulls", self.nulls),
+            ("malformed_dates", self.malformed_dates),
+            ("truncated_pans", self.truncated_pans),
+            ("bad_characters", self.bad_characters),
+            ("duplicates", self.duplicates),
+            ("out_of_order", self.out_of_order),
+        ]
+    }
+}
+
+// A row of the manifest: one problem injected into one output row
+#[derive(Debug, Clone, Serialize)]
+pub struct Corruption {
+    // 1-based position of the row in the dataset, before any train/test split
+    pub row: usize,
+    pub transaction_id: String,
+    pub kind: &'static str,
+    // Field that was changed, empty for duplicated rows
+    pub field: &'static str,
+    // Value before the change; for duplicates, the row that was duplicated
+    pub original: String,
+}
+
+// Text fields that bad characters are slipped into
+const TEXT_FIELDS: [&str; 7] = [
+    "cardholder_name",
+    "billing_street",
+    "billing_city",
+    "merchant_name",
+    "statement_descriptor",
+    "merchant_category",
+    "user_agent",
+];
+
+// Fields that nulls are injected into
+const NULLABLE_FIELDS: [&str; 19] = [
+    "customer_id",
+    "cardholder_name",
+    "email",
+    "phone",
+    "card_number",
+    "card_brand",
+    "card_expiry",
+    "cvv",
+    "billing_street",
+    "billing_postal_code",
+    "shipping_street",
+    "currency",
+    "merchant_name",
+    "merchant_id",
+    "merchant_category",
+    "mcc",
+    "ip_address",
+    "device_id",
+    "user_agent",
+];
+
+// Characters that look harmless or invisible but break naive parsing and
+// comparisons: zero-width space, no-break space, byte order mark, the
+// replacement character, a right-to-left override and a combining accent
+const BAD_CHARACTERS: [char; 6] = [
+    '\u{200B}', '\u{00A0}', '\u{FEFF}', '\u{FFFD}', '\u{202E}', '\u{0301}',
+];
+
+// Corrupts a dataset's rows at the configured rates, keeping a record of
+// every change for the manifest
+pub struct Corruptor {
+    config: DirtyConfig,
+    rng: StdRng,
+    // Rows before the range start are out of order even without a
+    // preceding event
+    range_start: DateTime<Tz>,
+    rows: usize,
+    corruptions: Vec<Corruption>,
+}
+
+impl Corruptor {
+    pub fn new(config: &DirtyConfig, range_start: DateTime<Tz>) -> Self {
+        Self {
+            config: config.clone(),
+            rng: StdRng::from_entropy(),
+            range_start,
+            rows: 0,
+            corruptions: Vec::new(),
+        }
+    }
+
+    // Corrupt a row, returning it along with its duplicate when it gets one
+    pub fn corrupt(&mut self, mut tx: Transaction) -> (Transaction, Option<Transaction>) {
+        self.rows += 1;
+        let row = self.rows;
+        let (config, rng) = (&self.config, &mut self.rng);
+        let mut changes = Vec::new();
+
+        // Reorder before mangling the date, while it still parses
+        if rng.gen_bool(config.out_of_order) {
+            let original = tx.transaction_date.clone();
+            tx.transaction_date = temporal::rfc3339(&out_of_order(rng, &tx, self.range_start));
+            changes.push(("out_of_order", "transaction_date", original));
+        }
+        if rng.gen_bool(config.malformed_dates) {
+            let original = tx.transaction_date.clone();
+            tx.transaction_date = malformed_date(rng, &original).into();
+            changes.push(("malformed_date", "transaction_date", original));
+        }
+        if rng.gen_bool(config.truncated_pans) {
+            let original = tx.card_number.clone();
+            let cut = rng.gen_range(1..=4).min(original.len() - 1);
+            tx.card_number = original[..original.len() - cut].into();
+            changes.push(("truncated_pan", "card_number", original));
+        }
+        if rng.gen_bool(config.bad_characters)
+            && let Some(field) = present_field(rng, &mut tx, &TEXT_FIELDS)
+        {
+            let value = text_field(&mut tx, field).unwrap();
+            let original = value.clone();
+            *value = with_bad_characters(rng, value).into();
+            changes.push(("bad_characters", field, original));
+        }
+        if rng.gen_bool(config.nulls)
+            && let Some(field) = present_field(rng, &mut tx, &NULLABLE_FIELDS)
+        {
+            let original = match optional_field(&mut tx, field) {
+                Some(value) => value.take().unwrap_or_default(),
+                None => std::mem::take(text_field(&mut tx, field).unwrap()),
+            };
+            changes.push(("null", field, original));
+        }
+        for (kind, field, original) in changes {
+            self.record(row, &tx, kind, field, original.into());
+        }
+
+        if self.rng.gen_bool(self.config.duplicates) {
+            self.rows += 1;
+            let duplicate = self.rows;
+            self.record(duplicate, &tx, "duplicate", "", row.to_string());
+            (tx.clone(), Some(tx))
+        } else {
+            (tx, None)
+        }
+    }
+
+    // Take the manifest entries of the rows corrupted since the last call
+    pub fn take_corruptions(&mut self) -> Vec<Corruption> {
+        std::mem::take(&mut self.corruptions)
+    }
+
+    fn record(
+        &mut self,
+        row: usize,
+        tx: &Transaction,
+        kind: &'static str,
+        field: &'static str,
+        original: String,
+    ) {
+        self.corruptions.push(Corruption {
+            row,
+            transaction_id: tx.transaction_id.to_string(),
+            kind,
+            field,
+            original,
+        });
+    }
+}
+
+// A timestamp before the event the row follows. Lifecycle events after the
+// authorization, and refunds, are moved back further than their longest
+// delay, so they precede the authorization or purchase; other rows are moved
+// before the start of the date range.
+fn out_of_order<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: &Transaction,
+    range_start: DateTime<Tz>,
+) -> DateTime<FixedOffset> {
+    let date =
+        DateTime::parse_from_rfc3339(&tx.transaction_date).expect("generated dates are RFC 3339");
+    let follows_event = tx.original_transaction_id.is_some()
+        || tx
+            .event_type
+            .is_some_and(|event| event != LifecycleEvent::Authorization);
+    let before = if follows_event {
+        date
+    } else {
+        range_start.fixed_offset()
+    };
+    let moved = before - Duration::seconds(rng.gen_range(31 * 86_400..=90 * 86_400));
+    moved.with_nanosecond(0).unwrap_or(moved)
+}
+
+// A random one of the fields that the row has a value for
+fn present_field<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: &mut Transaction,
+    fields: &[&'static str],
+) -> Option<&'static str> {
+    let present: Vec<&'static str> = fields
+        .iter()
+        .copied()
+        .filter(|field| text_field(tx, field).is_some())
+        .collect();
+    present.choose(rng).copied()
+}
+
+// The text field of a transaction with the given name, unless it is an
+// optional field that is unset
+fn text_field<'a>(tx: &'a mut Transaction, field: &str) -> Option<&'a mut Text> {
+    match field {
+        "customer_id" => Some(&mut tx.customer_id),
+        "cardholder_name" => Some(&mut tx.cardholder_name),
+        "email" => Some(&mut tx.email),
+        "phone" => Some(&mut tx.phone),
+        "card_number" => Some(&mut tx.card_number),
+        "card_brand" => Some(&mut tx.card_brand),
+        "card_expiry" => Some(&mut tx.card_expiry),
+        "billing_street" => Some(&mut tx.billing_street),
+        "billing_city" => Some(&mut tx.billing_city),
+        "billing_postal_code" => Some(&mut tx.billing_postal_code),
+        "currency" => Some(&mut tx.currency),
+        "merchant_name" => Some(&mut tx.merchant_name),
+        "merchant_id" => Some(&mut tx.merchant_id),
+        "statement_descriptor" => Some(&mut tx.statement_descriptor),
+        "merchant_category" => Some(&mut tx.merchant_category),
+        field => optional_field(tx, field)
+            .unwrap_or_else(|| unreachable!("not a text field: {}", field))
+            .as_mut(),
+    }
+}
+
+// The optional text field of a transaction with the given name
+fn optional_field<'a>(tx: &'a mut Transaction, field: &str) -> Option<&'a mut Option<Text>> {
+    match field {
+        "cvv" => Some(&mut tx.cvv),
+        "shipping_street" => Some(&mut tx.shipping_street),
+        "mcc" => Some(&mut tx.mcc),
+        "ip_address" => Some(&mut tx.ip_address),
+        "device_id" => Some(&mut tx.device_id),
+        "user_agent" => Some(&mut tx.user_agent),
+        _ => None,
+    }
+}
+
+// A broken rendering of an RFC 3339 timestamp, in one of the shapes bad
+// upstream systems produce
+fn malformed_date<R: Rng + ?Sized>(rng: &mut R, date: &str) -> String {
+    let Ok(parsed) = DateTime::parse_from_rfc3339(date) else {
+        return date.to_string();
+    };
+    match rng.gen_range(0..6) {
+        // Day-first local format
+        0 => parsed.format("%d/%m/%Y %H:%M:%S").to_string(),
+        // No offset, space separated
+        1 => parsed.format("%Y-%m-%d %H:%M:%S").to_string(),
+        // Unix seconds as text
+        2 => parsed.timestamp().to_string(),
+        // Impossible day and hour
+        3 => parsed.format("%Y-02-30T25:%M:%S%:z").to_string(),
+        // Cut off mid-time
+        4 => date[..date.len().min(13)].to_string(),
+        _ => ["N/A", "0000-00-00 00:00:00", "null"]
+            .choose(rng)
+            .unwrap()
+            .to_string(),
+    }
+}
+
+// Text with a bad character slipped in at a random character boundary, or
+// with a letter mis-decoded as UTF-8 read as Latin-1
+fn with_bad_characters<R: Rng + ?Sized>(rng: &mut R, text: &str) -> String {
+    let boundaries: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([text.len()])
+        .collect();
+    let at = *boundaries.choose(rng).unwrap();
+    let inserted = if rng.gen_bool(0.2) {
+        "Ã©".to_string()
+    } else {
+        BAD_CHARACTERS.choose(rng).unwrap().to_string()
+    };
+    format!("{}{}{}", &text[..at], inserted, &text[at..])
+}
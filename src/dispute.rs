@@ -0,0 +1,341 @@
+use crate::{
+    id,
+    model::{LifecycleEvent, Transaction, TransactionStatus},
+    preauth::PreauthStep,
+    three_ds::AuthenticationResult,
+};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use rand::{Rng, distributions::WeightedIndex, prelude::Distribution};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Chargebacks raised against approved transactions. Disputes are only
+// generated when `rate` is above 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisputeConfig {
+    // Share of genuine approved transactions the cardholder disputes
+    pub rate: f64,
+    // Share of approved fraud rows the real cardholder disputes
+    pub fraud_rate: f64,
+    // Share of disputes the merchant fights with a representment rather
+    // than accepting
+    pub representment_rate: f64,
+    // Share of representments the merchant wins
+    pub win_rate: f64,
+}
+
+impl Default for DisputeConfig {
+    fn default() -> Self {
+        Self {
+            rate: 0.0,
+            fraud_rate: 0.8,
+            representment_rate: 0.6,
+            win_rate: 0.4,
+        }
+    }
+}
+
+impl DisputeConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.rate > 0.0
+    }
+
+    // Each rate with the name it is configured under
+    pub fn rates(&self) -> [(&'static str, f64); 4] {
+        [
+            ("rate", self.rate),
+            ("fraud_rate", self.fraud_rate),
+            ("representment_rate", self.representment_rate),
+            ("win_rate", self.win_rate),
+        ]
+    }
+}
+
+// Stages a dispute goes through: opened by the issuer's chargeback, then
+// either accepted by the merchant, or represented and then won or lost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeStatus {
+    Opened,
+    Accepted,
+    Represented,
+    Won,
+    Lost,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeCategory {
+    Fraud,
+    ProcessingError,
+    ConsumerDispute,
+}
+
+// A chargeback reason with its Visa and Mastercard reason codes
+struct Reason {
+    visa: &'static str,
+    mastercard: &'static str,
+    description: &'static str,
+    category: DisputeCategory,
+}
+
+const CARD_ABSENT_FRAUD: Reason = Reason {
+    visa: "10.4",
+    mastercard: "4837",
+    description: "Other Fraud - Card-Absent Environment",
+    category: DisputeCategory::Fraud,
+};
+const CARD_PRESENT_FRAUD: Reason = Reason {
+    visa: "10.3",
+    mastercard: "4837",
+    description: "Other Fraud - Card-Present Environment",
+    category: DisputeCategory::Fraud,
+};
+
+// Reasons genuine transactions are disputed for, with their weights.
+// Fraud claims on genuine transactions are friendly fraud.
+const GENUINE_REASONS: [(Reason, f64); 4] = [
+    (
+        Reason {
+            visa: "12.6.1",
+            mastercard: "4834",
+            description: "Duplicate Processing",
+            category: DisputeCategory::ProcessingError,
+        },
+        0.1,
+    ),
+    (
+        Reason {
+            visa: "13.1",
+            mastercard: "4853",
+            description: "Merchandise/Services Not Received",
+            category: DisputeCategory::ConsumerDispute,
+        },
+        0.25,
+    ),
+    (
+        Reason {
+            visa: "13.3",
+            mastercard: "4853",
+            description: "Not as Described or Defective Merchandise/Services",
+            category: DisputeCategory::ConsumerDispute,
+        },
+        0.2,
+    ),
+    (
+        Reason {
+            visa: "13.6",
+            mastercard: "4853",
+            description: "Credit Not Processed",
+            category: DisputeCategory::ConsumerDispute,
+        },
+        0.1,
+    ),
+];
+// Weight of friendly fraud claims among genuine disputes
+const FRIENDLY_FRAUD: f64 = 0.35;
+
+// Reasons a scripted chargeback can be raised for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChargebackReason {
+    #[default]
+    Fraud,
+    Duplicate,
+    NotReceived,
+    NotAsDescribed,
+    CreditNotProcessed,
+}
+
+impl ChargebackReason {
+    fn reason(self, tx: &Transaction) -> &'static Reason {
+        match self {
+            ChargebackReason::Fraud => fraud_reason(tx),
+            ChargebackReason::Duplicate => &GENUINE_REASONS[0].0,
+            ChargebackReason::NotReceived => &GENUINE_REASONS[1].0,
+            ChargebackReason::NotAsDescribed => &GENUINE_REASONS[2].0,
+            ChargebackReason::CreditNotProcessed => &GENUINE_REASONS[3].0,
+        }
+    }
+}
+
+fn fraud_reason(tx: &Transaction) -> &'static Reason {
+    if tx.channel.is_card_present() {
+        &CARD_PRESENT_FRAUD
+    } else {
+        &CARD_ABSENT_FRAUD
+    }
+}
+
+// A row of the disputes file: one status a dispute reached
+#[derive(Debug, Clone, Serialize)]
+pub struct DisputeEvent {
+    pub dispute_id: String,
+    pub transaction_id: String,
+    pub status: DisputeStatus,
+    // RFC 3339 timestamp, in the offset of the disputed transaction
+    pub status_date: String,
+    pub reason_code: &'static str,
+    pub reason: &'static str,
+    pub category: DisputeCategory,
+    pub amount: f64,
+    pub currency: String,
+    pub card_brand: String,
+    pub merchant_id: String,
+}
+
+// Number of disputes opened so far in this run, scrambled into their IDs
+static DISPUTES: AtomicU64 = AtomicU64::new(0);
+
+fn gen_dispute_id() -> String {
+    let sequence = DISPUTES.fetch_add(1, Ordering::Relaxed);
+    format!("DSP{:09}", id::scramble(sequence, 1_000_000_000, 3))
+}
+
+// The dispute of a transaction, if it gets one, as the statuses it has
+// reached so far. Only approved purchases are disputed, and in lifecycle
+// mode only their authorization; pre-authorized orders are disputed on
+// their capture. Merchants always win fraud disputes on payments
+// authenticated with 3-D Secure, where liability shifted to the issuer.
+pub fn dispute<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: &Transaction,
+    config: &DisputeConfig,
+) -> Vec<DisputeEvent> {
+    let rate = if tx.is_fraud {
+        config.fraud_rate
+    } else {
+        config.rate
+    };
+    let disputed_event = match tx.preauth_step {
+        Some(PreauthStep::Capture) => LifecycleEvent::Capture,
+        _ => LifecycleEvent::Authorization,
+    };
+    if tx.status != TransactionStatus::Approved
+        || tx.is_hold()
+        || tx.event_type.is_some_and(|e| e != disputed_event)
+        || !rng.gen_bool(rate)
+    {
+        return Vec::new();
+    }
+
+    let fraud_reason = fraud_reason(tx);
+    let reason = if tx.is_fraud {
+        fraud_reason
+    } else {
+        let weights = GENUINE_REASONS
+            .iter()
+            .map(|(_, weight)| *weight)
+            .chain([FRIENDLY_FRAUD]);
+        let index = WeightedIndex::new(weights).expect("reason weights are positive");
+        GENUINE_REASONS
+            .get(index.sample(rng))
+            .map_or(fraud_reason, |(reason, _)| reason)
+    };
+    let liability_shift = reason.category == DisputeCategory::Fraud
+        && matches!(
+            tx.authentication_result,
+            Some(AuthenticationResult::Frictionless | AuthenticationResult::Challenge)
+        );
+
+    // Fraud is noticed and charged back sooner than other problems
+    let opened = match reason.category {
+        DisputeCategory::Fraud => Duration::days(rng.gen_range(2..=45)),
+        _ => Duration::days(rng.gen_range(7..=90)),
+    };
+    let mut stages = vec![(DisputeStatus::Opened, opened)];
+    if liability_shift || rng.gen_bool(config.representment_rate) {
+        stages.push((
+            DisputeStatus::Represented,
+            Duration::days(rng.gen_range(3..=25)),
+        ));
+        let won = liability_shift || rng.gen_bool(config.win_rate);
+        stages.push((
+            if won {
+                DisputeStatus::Won
+            } else {
+                DisputeStatus::Lost
+            },
+            Duration::days(rng.gen_range(10..=45)),
+        ));
+    } else {
+        stages.push((
+            DisputeStatus::Accepted,
+            Duration::days(rng.gen_range(1..=10)),
+        ));
+    }
+
+    // Statuses that would be reached after the current time haven't been yet
+    let now = Utc::now();
+    let mut at =
+        DateTime::parse_from_rfc3339(&tx.transaction_date).expect("generated dates are RFC 3339");
+    let mut reached = Vec::with_capacity(stages.len());
+    for (status, delay) in stages {
+        at += delay + Duration::seconds(rng.gen_range(0..86_400));
+        if at > now {
+            break;
+        }
+        reached.push((status, at));
+    }
+    events(tx, reason, reached)
+}
+
+// A chargeback raised against a transaction at a given time, as a scenario
+// scripts it, that goes on to the outcome given: opened and no further,
+// accepted, represented, or represented and then won or lost
+pub fn chargeback<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: &Transaction,
+    reason: ChargebackReason,
+    outcome: DisputeStatus,
+    opened: DateTime<FixedOffset>,
+) -> Vec<DisputeEvent> {
+    let mut stages = vec![DisputeStatus::Opened];
+    match outcome {
+        DisputeStatus::Opened => {}
+        DisputeStatus::Accepted | DisputeStatus::Represented => stages.push(outcome),
+        DisputeStatus::Won | DisputeStatus::Lost => {
+            stages.extend([DisputeStatus::Represented, outcome])
+        }
+    }
+    let mut at = opened;
+    let mut reached = Vec::with_capacity(stages.len());
+    for status in stages {
+        if status != DisputeStatus::Opened {
+            at +=
+                Duration::days(rng.gen_range(3..=25)) + Duration::seconds(rng.gen_range(0..86_400));
+        }
+        reached.push((status, at));
+    }
+    events(tx, reason.reason(tx), reached)
+}
+
+// The rows of a dispute that reached each status at the time given
+fn events(
+    tx: &Transaction,
+    reason: &Reason,
+    reached: Vec<(DisputeStatus, DateTime<FixedOffset>)>,
+) -> Vec<DisputeEvent> {
+    let reason_code = match tx.card_brand.as_str() {
+        "Mastercard" | "Maestro" => reason.mastercard,
+        _ => reason.visa,
+    };
+    let dispute_id = gen_dispute_id();
+    reached
+        .into_iter()
+        .map(|(status, at)| DisputeEvent {
+            dispute_id: dispute_id.clone(),
+            transaction_id: tx.transaction_id.to_string(),
+            status,
+            status_date: at.to_rfc3339(),
+            reason_code,
+            reason: reason.description,
+            category: reason.category,
+            amount: tx.amount,
+            currency: tx.currency.to_string(),
+            card_brand: tx.card_brand.to_string(),
+            merchant_id: tx.merchant_id.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,305 @@
+use crate::{
+    config::{Merchant, Profile},
+    fraud::FraudPattern,
+    model::Transaction,
+    temporal::{self, DateRange},
+};
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+// Concept drift: a point partway through the date range where the
+// distributions rows are drawn from change, for validating the drift
+// detection of model-monitoring tools. What a point leaves out carries on
+// from the point before it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftPoint {
+    // When the change takes effect: a date, an RFC 3339 time, or a share of
+    // the date range such as "50%"
+    pub at: String,
+    // Factor amounts are scaled by from this point on
+    #[serde(default)]
+    pub amount_scale: Option<f64>,
+    // Weights of merchant categories from this point on, relative to 1 for
+    // categories left out
+    #[serde(default)]
+    pub categories: Option<BTreeMap<String, f64>>,
+    // Fraud patterns first injected at this point, and never before it
+    #[serde(default)]
+    pub fraud_patterns: Vec<FraudPattern>,
+    // Share of transactions that start a fraud pattern from this point on
+    #[serde(default)]
+    pub fraud_rate: Option<f64>,
+}
+
+// The drift used by `--drift-at`: amounts inflate by a quarter, spending
+// moves from groceries to electronics and travel, and account takeovers
+// appear
+pub fn builtin_point(at: &str) -> DriftPoint {
+    DriftPoint {
+        at: at.to_string(),
+        amount_scale: Some(1.25),
+        categories: Some(
+            [("Electronics", 2.0), ("Travel", 2.0), ("Grocery", 0.5)]
+                .iter()
+                .map(|(category, weight)| (category.to_string(), *weight))
+                .collect(),
+        ),
+        fraud_patterns: vec![FraudPattern::AccountTakeover],
+        fraud_rate: None,
+    }
+}
+
+impl DriftPoint {
+    // Time the point takes effect within the date range
+    pub fn time(&self, range: &DateRange) -> Result<DateTime<Tz>, String> {
+        let tz = range.end.timezone();
+        if let Some(percent) = self.at.strip_suffix('%') {
+            let share = percent
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|share| (0.0..=100.0).contains(share))
+                .ok_or_else(|| format!("invalid share {:?}, expected 0% to 100%", self.at))?;
+            let span = (range.end - range.start).num_seconds() as f64;
+            return Ok(range.start + chrono::Duration::seconds((span * share / 100.0) as i64));
+        }
+        let time = match NaiveDate::parse_from_str(&self.at, "%Y-%m-%d") {
+            Ok(day) => temporal::local(tz, day.and_time(NaiveTime::MIN)),
+            Err(_) => DateTime::parse_from_rfc3339(&self.at)
+                .map_err(|_| format!("invalid time {:?}, expected a date, time or share", self.at))?
+                .with_timezone(&tz),
+        };
+        if time < range.start || time > range.end {
+            return Err(format!("{} is outside the date range", self.at));
+        }
+        Ok(time)
+    }
+
+    // Reasons the point can't be generated from, if any
+    pub fn check(&self, range: &DateRange, merchants: &[Merchant]) -> Result<(), String> {
+        self.time(range)?;
+        if self
+            .amount_scale
+            .is_some_and(|scale| !scale.is_finite() || scale <= 0.0)
+        {
+            return Err("amount_scale must be above 0".to_string());
+        }
+        if let Some(categories) = &self.categories {
+            if let Some((category, _)) = categories
+                .iter()
+                .find(|(_, weight)| !weight.is_finite() || **weight < 0.0)
+            {
+                return Err(format!(
+                    "categories.\"{}\" must be a non-negative number",
+                    category
+                ));
+            }
+            let weight = |m: &Merchant| categories.get(&m.category).copied().unwrap_or(1.0);
+            if !merchants.iter().any(|m| weight(m) > 0.0) {
+                return Err("categories leave no merchant with weight".to_string());
+            }
+        }
+        if self
+            .fraud_rate
+            .is_some_and(|rate| !(0.0..=1.0).contains(&rate))
+        {
+            return Err("fraud_rate must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+// The stretches of the date range between drift points, each with the
+// distributions in effect
+#[derive(Debug, Clone)]
+pub struct Drift {
+    phases: Vec<Phase>,
+    // Highest weight each merchant has in any phase, which merchants drawn
+    // as usual are accepted against
+    ceilings: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Phase {
+    // Time the phase starts at; the first starts with the date range
+    pub start: DateTime<Tz>,
+    pub amount_scale: f64,
+    // Weight of each merchant in profile order, all 1 when the mix hasn't
+    // drifted
+    #[serde(skip)]
+    pub merchant_weights: Vec<f64>,
+    pub categories: BTreeMap<String, f64>,
+    // Patterns injected in the phase, and the fraud rate unless it's the
+    // profile's
+    pub fraud_patterns: Vec<FraudPattern>,
+    pub fraud_rate: Option<f64>,
+}
+
+impl Drift {
+    // The profile's drift over its date range, if it has any
+    pub fn of(profile: &Profile) -> Option<Self> {
+        (!profile.drift.is_empty())
+            .then(|| Self::new(&profile.drift, &profile.date_range(), &profile.merchants))
+    }
+
+    // Phases of validated drift points
+    pub fn new(points: &[DriftPoint], range: &DateRange, merchants: &[Merchant]) -> Self {
+        let mut points: Vec<(DateTime<Tz>, &DriftPoint)> = points
+            .iter()
+            .map(|point| {
+                (
+                    point.time(range).expect("drift points are validated"),
+                    point,
+                )
+            })
+            .collect();
+        points.sort_by_key(|(time, _)| *time);
+        let late: Vec<FraudPattern> = points
+            .iter()
+            .flat_map(|(_, point)| point.fraud_patterns.iter().copied())
+            .collect();
+        let mut phase = Phase {
+            start: range.start,
+            amount_scale: 1.0,
+            merchant_weights: vec![1.0; merchants.len()],
+            categories: BTreeMap::new(),
+            fraud_patterns: FraudPattern::ALL
+                .into_iter()
+                .filter(|pattern| !late.contains(pattern))
+                .collect(),
+            fraud_rate: None,
+        };
+        let mut phases = Vec::new();
+        for (time, point) in points {
+            phases.push(phase.clone());
+            phase.start = time;
+            if let Some(scale) = point.amount_scale {
+                phase.amount_scale = scale;
+            }
+            if let Some(categories) = &point.categories {
+                phase.categories = categories.clone();
+                phase.merchant_weights = merchants
+                    .iter()
+                    .map(|m| categories.get(&m.category).copied().unwrap_or(1.0))
+                    .collect();
+            }
+            for pattern in &point.fraud_patterns {
+                if !phase.fraud_patterns.contains(pattern) {
+                    phase.fraud_patterns.push(*pattern);
+                }
+            }
+            if point.fraud_rate.is_some() {
+                phase.fraud_rate = point.fraud_rate;
+            }
+        }
+        phases.push(phase);
+        let ceilings = (0..merchants.len())
+            .map(|i| {
+                phases
+                    .iter()
+                    .map(|phase| phase.merchant_weights[i])
+                    .fold(0.0, f64::max)
+            })
+            .collect();
+        Self { phases, ceilings }
+    }
+
+    pub fn phases(&self) -> &[Phase] {
+        &self.phases
+    }
+
+    // Index of the phase a time falls in
+    pub fn phase_index<T: chrono::TimeZone>(&self, time: &DateTime<T>) -> usize {
+        self.phases
+            .partition_point(|phase| phase.start <= *time)
+            .saturating_sub(1)
+    }
+
+    pub fn phase<T: chrono::TimeZone>(&self, time: &DateTime<T>) -> &Phase {
+        &self.phases[self.phase_index(time)]
+    }
+
+    // Whether any phase changes the merchant mix, which takes redrawing
+    // merchants
+    pub fn shifts_merchants(&self) -> bool {
+        self.phases.iter().any(|phase| !phase.categories.is_empty())
+    }
+
+    // Whether a merchant drawn as usual for a time is kept, so merchants
+    // end up drawn by the weights of the phase the time falls in
+    pub fn accepts<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        merchant_index: usize,
+        time: &DateTime<Tz>,
+    ) -> bool {
+        let ceiling = self.ceilings[merchant_index];
+        let weight = self.phase(time).merchant_weights[merchant_index];
+        ceiling > 0.0 && rng.gen_bool((weight / ceiling).min(1.0))
+    }
+}
+
+// Tally of the rows written in each phase, written next to a dataset as a
+// manifest of its drift points
+pub struct DriftManifest {
+    drift: Drift,
+    end: DateTime<Tz>,
+    rows: Vec<usize>,
+    fraud_rows: Vec<usize>,
+}
+
+#[derive(Serialize)]
+struct PhaseEntry<'a> {
+    #[serde(flatten)]
+    phase: &'a Phase,
+    end: DateTime<Tz>,
+    rows: usize,
+    fraud_rows: usize,
+}
+
+impl DriftManifest {
+    pub fn new(drift: Drift, range: &DateRange) -> Self {
+        let phases = drift.phases.len();
+        Self {
+            drift,
+            end: range.end,
+            rows: vec![0; phases],
+            fraud_rows: vec![0; phases],
+        }
+    }
+
+    pub fn record(&mut self, tx: &Transaction) {
+        if let Ok(time) = DateTime::parse_from_rfc3339(&tx.transaction_date) {
+            let i = self.drift.phase_index(&time);
+            self.rows[i] += 1;
+            self.fraud_rows[i] += usize::from(tx.is_fraud);
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let phases = &self.drift.phases;
+        let entries: Vec<PhaseEntry> = phases
+            .iter()
+            .enumerate()
+            .map(|(i, phase)| PhaseEntry {
+                phase,
+                end: phases.get(i + 1).map_or(self.end, |next| next.start),
+                rows: self.rows[i],
+                fraud_rows: self.fraud_rows[i],
+            })
+            .collect();
+        let mut file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut file, &json!({ "phases": entries }))?;
+        file.write_all(b"\n")?;
+        file.flush()
+    }
+}
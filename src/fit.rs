@@ -0,0 +1,460 @@
+use crate::{
+    amount::{AmountConfig, AmountDistribution},
+    brand::CardBrand,
+    config::Profile,
+    currency,
+    fraud::FraudInjector,
+    generator::TransactionStream,
+    loader,
+    model::{Channel, TransactionStatus},
+    temporal::TemporalPattern,
+};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike};
+use serde::Serialize;
+use std::{collections::BTreeMap, io, path::Path};
+
+// Columns a sample is fitted from; settings of any the sample lacks are left
+// at their defaults
+const COLUMNS: [&str; 7] = [
+    "card_brand",
+    "status",
+    "amount",
+    "currency",
+    "channel",
+    "is_fraud",
+    "transaction_date",
+];
+
+// Rows a merchant category needs in the sample for its amounts to be
+// fitted apart from the rest
+const MIN_CATEGORY_ROWS: usize = 30;
+
+// Transactions generated to check the shares a fitted profile makes, and
+// the times it's corrected; fraud rows don't grow in step with the fraud
+// rate, so one correction falls short
+const CALIBRATION_ROWS: usize = 20_000;
+const CALIBRATION_PASSES: usize = 3;
+
+// Statistics of a sample dataset, such as an export of real transactions
+// with the card numbers and names stripped, that a generation profile is
+// fitted to
+pub struct Fit {
+    rows: usize,
+    brands: BTreeMap<String, u64>,
+    statuses: BTreeMap<TransactionStatus, u64>,
+    currencies: BTreeMap<String, u64>,
+    channels: BTreeMap<Channel, u64>,
+    // Fraud rows, and rows with a fraud label at all
+    fraud: (u64, u64),
+    amounts: Vec<f64>,
+    category_amounts: BTreeMap<String, Vec<f64>>,
+    hours: [u64; 24],
+    days: BTreeMap<NaiveDate, u64>,
+    // Values the profile has nothing to fit them to, skipped with a warning
+    warnings: Vec<String>,
+}
+
+// The parts of a profile a fit sets; everything else keeps its default
+#[derive(Serialize)]
+struct Fitted {
+    sizes: Vec<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    card_brands: Vec<CardBrand>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_from: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_to: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    currencies: Vec<String>,
+    weights: FittedWeights,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fraud: Option<FittedFraud>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amounts: Option<AmountConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temporal: Option<FittedTemporal>,
+}
+
+impl Fitted {
+    // The profile this makes, checked as any loaded profile is
+    fn load(&self) -> io::Result<Profile> {
+        let text = toml::to_string(self).map_err(io::Error::other)?;
+        let profile: Profile =
+            toml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        profile.validate()?;
+        Ok(profile)
+    }
+}
+
+#[derive(Serialize)]
+struct FittedWeights {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    status: BTreeMap<TransactionStatus, f64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    card_brands: BTreeMap<String, f64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    currencies: BTreeMap<String, f64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    channels: BTreeMap<Channel, f64>,
+}
+
+#[derive(Serialize)]
+struct FittedFraud {
+    rate: f64,
+}
+
+#[derive(Serialize)]
+struct FittedTemporal {
+    default: TemporalPattern,
+}
+
+impl Fit {
+    // Read a CSV sample with a header row, matching its card brands to
+    // those of the profile
+    pub fn read(path: &Path, profile: &Profile) -> io::Result<Self> {
+        let mut reader = loader::open(path, &[])?;
+        let headers = reader
+            .headers()
+            .map_err(|e| loader::invalid(path, e))?
+            .clone();
+        let column = |name: &str| headers.iter().position(|h| h == name);
+        let columns: Vec<Option<usize>> = COLUMNS.iter().map(|name| column(name)).collect();
+        if columns.iter().all(Option::is_none) {
+            return Err(loader::invalid(
+                path,
+                format!("no column to fit; expected some of {}", COLUMNS.join(", ")),
+            ));
+        }
+        let category = column("merchant_category");
+        let mut fit = Fit {
+            rows: 0,
+            brands: BTreeMap::new(),
+            statuses: BTreeMap::new(),
+            currencies: BTreeMap::new(),
+            channels: BTreeMap::new(),
+            fraud: (0, 0),
+            amounts: Vec::new(),
+            category_amounts: BTreeMap::new(),
+            hours: [0; 24],
+            days: BTreeMap::new(),
+            warnings: Vec::new(),
+        };
+        let mut unknown: BTreeMap<(&str, String), u64> = BTreeMap::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| loader::invalid(path, e))?;
+            fit.rows += 1;
+            let value = |i: usize| {
+                columns[i]
+                    .and_then(|column| record.get(column))
+                    .filter(|value| !value.is_empty())
+            };
+            if let Some(brand) = value(0) {
+                match profile
+                    .card_brands
+                    .iter()
+                    .find(|b| same_name(&b.name, brand))
+                {
+                    Some(known) => *fit.brands.entry(known.name.clone()).or_default() += 1,
+                    None => {
+                        *unknown
+                            .entry(("card brand", brand.to_string()))
+                            .or_default() += 1
+                    }
+                }
+            }
+            if let Some(status) = value(1) {
+                match TransactionStatus::ALL
+                    .iter()
+                    .find(|s| same_name(s.as_str(), status))
+                {
+                    Some(known) => *fit.statuses.entry(*known).or_default() += 1,
+                    None => *unknown.entry(("status", status.to_string())).or_default() += 1,
+                }
+            }
+            // Amounts are fitted in dollar-like units, as profiles give them,
+            // so yen and dollar amounts share a distribution
+            let scale = value(3).map_or(1.0, |c| currency::amount_scale(&c.to_ascii_uppercase()));
+            let amount = value(2).and_then(|amount| amount.parse::<f64>().ok());
+            if let Some(amount) = amount.filter(|amount| *amount > 0.0 && amount.is_finite()) {
+                let amount = amount / scale;
+                fit.amounts.push(amount);
+                if let Some(category) = category.and_then(|column| record.get(column)) {
+                    let amounts = fit
+                        .category_amounts
+                        .entry(category.to_string())
+                        .or_default();
+                    amounts.push(amount);
+                }
+            }
+            if let Some(currency) = value(3) {
+                let code = currency.to_ascii_uppercase();
+                if code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase()) {
+                    *fit.currencies.entry(code).or_default() += 1;
+                } else {
+                    *unknown
+                        .entry(("currency", currency.to_string()))
+                        .or_default() += 1;
+                }
+            }
+            if let Some(channel) = value(4) {
+                match Channel::ALL.iter().find(|c| same_name(c.as_str(), channel)) {
+                    Some(known) => *fit.channels.entry(*known).or_default() += 1,
+                    None => *unknown.entry(("channel", channel.to_string())).or_default() += 1,
+                }
+            }
+            if let Some(label) = value(5) {
+                fit.fraud.1 += 1;
+                fit.fraud.0 += u64::from(matches!(label, "true" | "1" | "TRUE" | "True"));
+            }
+            if let Some(time) = value(6).and_then(parse_time) {
+                fit.hours[time.hour() as usize] += 1;
+                *fit.days.entry(time.date()).or_default() += 1;
+            }
+        }
+        for ((kind, value), rows) in unknown {
+            fit.warnings.push(format!(
+                "skipped {} rows with unknown {} `{}`",
+                rows, kind, value
+            ));
+        }
+        Ok(fit)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    // Values of the sample that couldn't be fitted
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    // The fitted profile as TOML, checked to load as a profile. Temporal
+    // weights the sample is too short to show keep the profile's.
+    pub fn to_toml(&self, profile: &Profile) -> io::Result<String> {
+        let (date_from, date_to) = match (self.days.keys().next(), self.days.keys().last()) {
+            (Some(from), Some(to)) => (Some(*from), Some(*to)),
+            _ => (None, None),
+        };
+        let currencies = shares(&self.currencies);
+        let amounts = log_normal(&self.amounts).map(|default| AmountConfig {
+            default,
+            categories: self
+                .category_amounts
+                .iter()
+                .filter(|(_, amounts)| amounts.len() >= MIN_CATEGORY_ROWS)
+                .filter_map(|(category, amounts)| Some((category.clone(), log_normal(amounts)?)))
+                .collect(),
+        });
+        // Brands of a profile of its own are written out, as the fitted
+        // profile's weights refer to them
+        let default_brands = serde_json::to_value(&Profile::default().card_brands)?;
+        let card_brands = if serde_json::to_value(&profile.card_brands)? == default_brands {
+            Vec::new()
+        } else {
+            profile.card_brands.clone()
+        };
+        let mut fitted = Fitted {
+            sizes: vec![self.rows],
+            card_brands,
+            date_from,
+            date_to,
+            currencies: currencies.keys().cloned().collect(),
+            weights: FittedWeights {
+                status: shares(&self.statuses),
+                card_brands: shares(&self.brands),
+                currencies,
+                channels: shares(&self.channels),
+            },
+            fraud: (self.fraud.1 > 0).then(|| FittedFraud {
+                rate: round(self.fraud.0 as f64 / self.fraud.1 as f64),
+            }),
+            amounts,
+            temporal: self
+                .temporal(&profile.temporal.default)
+                .map(|default| FittedTemporal { default }),
+        };
+        // Expired cards, fraud and its declines shift the shares a profile's
+        // weights make, so the weights are corrected by how far samples
+        // generated with them are off
+        let statuses = shares(&self.statuses);
+        let channels = shares(&self.channels);
+        for pass in 0..CALIBRATION_PASSES {
+            let sample = Sample::generate(&fitted.load()?);
+            // The first fraud rate is far too high, and the statuses and
+            // channels fraud brings with it would throw the weights off
+            if pass > 0 {
+                correct(
+                    &mut fitted.weights.status,
+                    &statuses,
+                    &sample.statuses,
+                    sample.rows,
+                );
+                correct(
+                    &mut fitted.weights.channels,
+                    &channels,
+                    &sample.channels,
+                    sample.rows,
+                );
+            }
+            if let Some(fraud) = fitted.fraud.as_mut()
+                && sample.fraud > 0
+            {
+                let observed = self.fraud.0 as f64 / self.fraud.1 as f64;
+                let generated = sample.fraud as f64 / sample.rows as f64;
+                fraud.rate = round(fraud.rate * observed / generated);
+            }
+        }
+        fitted.load()?;
+        toml::to_string(&fitted).map_err(io::Error::other)
+    }
+
+    // Hourly weights, and weekday and monthly ones when the sample covers
+    // every weekday or month, each relative to the number of days of it in
+    // the sample's range
+    fn temporal(&self, base: &TemporalPattern) -> Option<TemporalPattern> {
+        let (first, last) = (*self.days.keys().next()?, *self.days.keys().last()?);
+        let span = (last - first).num_days() + 1;
+        let mut pattern = base.clone();
+        pattern.hourly = relative(self.hours.map(|rows| rows as f64));
+        let (mut weekdays, mut weekday_days) = ([0.0; 7], [0.0; 7]);
+        let (mut months, mut month_days) = ([0.0; 12], [0.0; 12]);
+        for day in first.iter_days().take(span as usize) {
+            let rows = self.days.get(&day).copied().unwrap_or(0) as f64;
+            let weekday = day.weekday().num_days_from_monday() as usize;
+            let month = day.month0() as usize;
+            weekdays[weekday] += rows;
+            weekday_days[weekday] += 1.0;
+            months[month] += rows;
+            month_days[month] += 1.0;
+        }
+        if span >= 7 {
+            pattern.weekday = relative(per_day(weekdays, weekday_days));
+        }
+        if span >= 365 {
+            pattern.monthly = relative(per_day(months, month_days));
+        }
+        Some(pattern)
+    }
+}
+
+// Statuses, channels and fraud of transactions generated with a profile
+struct Sample {
+    rows: u64,
+    statuses: BTreeMap<TransactionStatus, u64>,
+    channels: BTreeMap<Channel, u64>,
+    fraud: u64,
+}
+
+impl Sample {
+    fn generate(profile: &Profile) -> Self {
+        let mut sample = Sample {
+            rows: 0,
+            statuses: BTreeMap::new(),
+            channels: BTreeMap::new(),
+            fraud: 0,
+        };
+        let stream = TransactionStream::new(profile, CALIBRATION_ROWS);
+        for tx in FraudInjector::new(stream, &profile.fraud, profile.id_strategy) {
+            sample.rows += 1;
+            *sample.statuses.entry(tx.status).or_default() += 1;
+            *sample.channels.entry(tx.channel).or_default() += 1;
+            sample.fraud += u64::from(tx.is_fraud);
+        }
+        sample
+    }
+}
+
+// Scale weights by how far the shares generated with them fall short of or
+// overshoot those observed
+fn correct<K: Ord>(
+    weights: &mut BTreeMap<K, f64>,
+    observed: &BTreeMap<K, f64>,
+    generated: &BTreeMap<K, u64>,
+    rows: u64,
+) {
+    for (key, weight) in weights.iter_mut() {
+        if let Some(&count) = generated.get(key).filter(|count| **count > 0) {
+            *weight *= observed[key] / (count as f64 / rows as f64);
+        }
+    }
+    let total: f64 = weights.values().sum();
+    for weight in weights.values_mut() {
+        *weight = round(*weight / total);
+    }
+}
+
+// Whether a sample's value names the same thing, ignoring case, spaces and
+// punctuation, as `American Express` and `AMERICAN_EXPRESS` do
+pub(crate) fn same_name(name: &str, value: &str) -> bool {
+    let letters = |s: &str| {
+        s.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>()
+    };
+    letters(name) == letters(value)
+}
+
+// A timestamp as written, in the timezone it was written in
+fn parse_time(value: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.naive_local())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+}
+
+// Counts as shares of their total
+fn shares<K: Ord + Clone>(counts: &BTreeMap<K, u64>) -> BTreeMap<K, f64> {
+    let total: u64 = counts.values().sum();
+    counts
+        .iter()
+        .map(|(key, count)| (key.clone(), round(*count as f64 / total as f64)))
+        .collect()
+}
+
+// A log-normal distribution with the median and spread of the amounts,
+// clamped to the smallest and largest of them
+fn log_normal(amounts: &[f64]) -> Option<AmountDistribution> {
+    if amounts.len() < 2 {
+        return None;
+    }
+    let logs: Vec<f64> = amounts.iter().map(|amount| amount.ln()).collect();
+    let mean = logs.iter().sum::<f64>() / logs.len() as f64;
+    let variance =
+        logs.iter().map(|log| (log - mean).powi(2)).sum::<f64>() / (logs.len() - 1) as f64;
+    let min = amounts.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = amounts.iter().copied().fold(0.0, f64::max);
+    Some(AmountDistribution::LogNormal {
+        median: round(mean.exp()),
+        sigma: round(variance.sqrt().max(0.01)),
+        min: Some(min),
+        max: Some(max),
+    })
+}
+
+// Rows per day of each weekday or month, for those the range has
+fn per_day<const N: usize>(rows: [f64; N], days: [f64; N]) -> [f64; N] {
+    let mut per_day = [0.0; N];
+    for i in 0..N {
+        if days[i] > 0.0 {
+            per_day[i] = rows[i] / days[i];
+        }
+    }
+    per_day
+}
+
+// Weights scaled so they average 1, as the built-in patterns are written
+fn relative<const N: usize>(weights: [f64; N]) -> [f64; N] {
+    let mean = weights.iter().sum::<f64>() / N as f64;
+    if mean <= 0.0 {
+        return [1.0; N];
+    }
+    weights.map(|weight| round(weight / mean))
+}
+
+// Four decimals are plenty for weights and rates, and keep the file readable
+fn round(value: f64) -> f64 {
+    (value * 10_000.0).round() / 10_000.0
+}
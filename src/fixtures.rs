@@ -0,0 +1,377 @@
+use crate::{
+    bin::CardType,
+    model::{DeclineReason, Transaction, TransactionStatus},
+    money::Money,
+    three_ds::AuthenticationResult,
+};
+use chrono::DateTime;
+use clap::ValueEnum;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::io;
+
+// Payment gateways whose API responses fixtures are shaped like
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Provider {
+    Stripe,
+    Adyen,
+}
+
+impl Provider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Provider::Stripe => "stripe",
+            Provider::Adyen => "adyen",
+        }
+    }
+
+    // The response the gateway's API would give for the transaction
+    pub fn fixture(self, tx: &Transaction) -> io::Result<Fixture> {
+        match self {
+            Provider::Stripe => stripe(tx),
+            Provider::Adyen => adyen(tx),
+        }
+    }
+}
+
+// A mocked API response: the HTTP status it's served with and its body
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixture {
+    pub status: u16,
+    pub body: Value,
+}
+
+// A Stripe charge for payments, with a card error in a 402 for declines,
+// and a refund object for refunds
+fn stripe(tx: &Transaction) -> io::Result<Fixture> {
+    let created = created(tx)?;
+    let charge_id = gateway_id("ch_", &tx.transaction_id);
+    if let Some(refund_amount) = tx.refund_amount {
+        let original = tx.original_transaction_id.as_deref().unwrap_or_default();
+        return Ok(Fixture {
+            status: 200,
+            body: json!({
+                "id": gateway_id("re_", &tx.transaction_id),
+                "object": "refund",
+                "amount": minor_units(refund_amount, &tx.currency),
+                "balance_transaction": gateway_id("txn_", &tx.transaction_id),
+                "charge": gateway_id("ch_", original),
+                "created": created,
+                "currency": tx.currency.to_lowercase(),
+                "metadata": { "transaction_id": tx.transaction_id },
+                "reason": null,
+                "status": "succeeded",
+            }),
+        });
+    }
+    let amount = minor_units(tx.amount, &tx.currency);
+    let (exp_month, exp_year) = expiry(&tx.card_expiry);
+    let three_d_secure = tx.authentication_result.map(|result| {
+        json!({
+            "authentication_flow": match result {
+                AuthenticationResult::Challenge => "challenge",
+                _ => "frictionless",
+            },
+            "result": match result {
+                AuthenticationResult::Failed => "failed",
+                _ => "authenticated",
+            },
+            "version": format!("{}.0", tx.three_ds_version.as_deref().unwrap_or("2.2")),
+        })
+    });
+    if tx.status == TransactionStatus::Declined {
+        let (code, decline_code, message) = stripe_decline(tx);
+        return Ok(Fixture {
+            status: 402,
+            body: json!({
+                "error": {
+                    "charge": charge_id,
+                    "code": code,
+                    "decline_code": decline_code,
+                    "doc_url": format!(
+                        "https://stripe.com/docs/error-codes/{}",
+                        code.replace('_', "-")
+                    ),
+                    "message": message,
+                    "type": "card_error",
+                },
+            }),
+        });
+    }
+    let approved = tx.status == TransactionStatus::Approved;
+    let brand = stripe_brand(&tx.card_brand);
+    let avs = tx.avs_result.as_deref();
+    Ok(Fixture {
+        status: 200,
+        body: json!({
+            "id": charge_id,
+            "object": "charge",
+            "amount": amount,
+            "amount_captured": if approved { amount } else { 0 },
+            "amount_refunded": 0,
+            "balance_transaction": approved.then(|| gateway_id("txn_", &tx.transaction_id)),
+            "billing_details": {
+                "address": {
+                    "city": tx.billing_city,
+                    "country": tx.billing_country,
+                    "line1": tx.billing_street,
+                    "line2": null,
+                    "postal_code": tx.billing_postal_code,
+                    "state": tx.billing_region,
+                },
+                "email": tx.email,
+                "name": tx.cardholder_name,
+                "phone": tx.phone,
+            },
+            "captured": approved,
+            "created": created,
+            "currency": tx.currency.to_lowercase(),
+            "customer": gateway_id("cus_", &tx.customer_id),
+            "description": null,
+            "livemode": false,
+            "metadata": { "transaction_id": tx.transaction_id },
+            // Pending charges are still waiting on the network
+            "outcome": approved.then(|| json!({
+                "network_status": "approved_by_network",
+                "reason": null,
+                "risk_level": if tx.is_fraud { "elevated" } else { "normal" },
+                "seller_message": "Payment complete.",
+                "type": "authorized",
+            })),
+            "paid": approved,
+            "payment_intent": gateway_id("pi_", &tx.transaction_id),
+            "payment_method": gateway_id("pm_", &tx.card_number),
+            "payment_method_details": {
+                "card": {
+                    "brand": brand,
+                    "checks": {
+                        "address_line1_check": check(avs, &["Y", "A"], &["N", "Z"]),
+                        "address_postal_code_check": check(avs, &["Y", "Z"], &["N", "A"]),
+                        "cvc_check": check(tx.cvv_result.as_deref(), &["M"], &["N"]),
+                    },
+                    "country": tx.card_country,
+                    "exp_month": exp_month,
+                    "exp_year": exp_year,
+                    "fingerprint": gateway_id("", &tx.card_number)[..16],
+                    "funding": tx.card_type.map_or("unknown", |card_type| card_type.as_str()),
+                    "last4": last4(&tx.card_number),
+                    "network": brand,
+                    "three_d_secure": three_d_secure,
+                    "wallet": tx.wallet.map(|wallet| json!({ "type": wallet.as_str() })),
+                },
+                "type": "card",
+            },
+            "receipt_email": tx.email,
+            "refunded": false,
+            "statement_descriptor": tx.statement_descriptor,
+            "status": if approved { "succeeded" } else { "pending" },
+        }),
+    })
+}
+
+// Error code, decline code and message of a declined charge
+fn stripe_decline(tx: &Transaction) -> (&'static str, &'static str, &'static str) {
+    match tx.decline_reason {
+        Some(DeclineReason::InsufficientFunds) => (
+            "card_declined",
+            "insufficient_funds",
+            "Your card has insufficient funds.",
+        ),
+        Some(DeclineReason::CardExpired) => {
+            ("expired_card", "expired_card", "Your card has expired.")
+        }
+        Some(DeclineReason::InvalidCard) => (
+            "incorrect_number",
+            "incorrect_number",
+            "Your card number is incorrect.",
+        ),
+        Some(DeclineReason::SuspiciousActivity) => {
+            ("card_declined", "fraudulent", "Your card was declined.")
+        }
+        None => (
+            "card_declined",
+            "generic_decline",
+            "Your card was declined.",
+        ),
+    }
+}
+
+fn stripe_brand(brand: &str) -> &'static str {
+    match brand {
+        "Visa" => "visa",
+        "Mastercard" | "Maestro" => "mastercard",
+        "American Express" => "amex",
+        "Discover" => "discover",
+        "JCB" => "jcb",
+        "UnionPay" => "unionpay",
+        "Diners Club" => "diners",
+        _ => "unknown",
+    }
+}
+
+// A result of Adyen's /payments for payments, a validation error in a 422
+// for card numbers that aren't valid, and an acknowledgement of the
+// request from /refunds for refunds
+fn adyen(tx: &Transaction) -> io::Result<Fixture> {
+    let reference = psp_reference(&tx.transaction_id);
+    let merchant_account = format!("{}ECOM", tx.merchant_id);
+    if let Some(refund_amount) = tx.refund_amount {
+        let original = tx.original_transaction_id.as_deref().unwrap_or_default();
+        return Ok(Fixture {
+            status: 201,
+            body: json!({
+                "merchantAccount": merchant_account,
+                "paymentPspReference": psp_reference(original),
+                "pspReference": reference,
+                "reference": tx.transaction_id,
+                "status": "received",
+                "amount": {
+                    "currency": tx.currency,
+                    "value": minor_units(refund_amount, &tx.currency),
+                },
+            }),
+        });
+    }
+    if matches!(tx.decline_reason, Some(DeclineReason::InvalidCard)) {
+        return Ok(Fixture {
+            status: 422,
+            body: json!({
+                "status": 422,
+                "errorCode": "101",
+                "message": "Invalid card number",
+                "errorType": "validation",
+                "pspReference": reference,
+            }),
+        });
+    }
+    let (exp_month, exp_year) = expiry(&tx.card_expiry);
+    let mut additional_data = json!({
+        "cardHolderName": tx.cardholder_name,
+        "cardSummary": last4(&tx.card_number),
+        "expiryDate": format!("{}/{}", exp_month, exp_year),
+        "paymentMethod": adyen_brand(&tx.card_brand),
+        "fundingSource": match tx.card_type {
+            Some(CardType::Debit) => "DEBIT",
+            Some(CardType::Prepaid) => "PREPAID",
+            _ => "CREDIT",
+        },
+        "merchantReference": tx.transaction_id,
+    });
+    let extra = [
+        ("authCode", tx.auth_code.as_deref()),
+        ("issuerCountry", tx.card_country.as_deref()),
+        ("avsResultRaw", tx.avs_result.as_deref()),
+        ("cvcResultRaw", tx.cvv_result.as_deref()),
+        ("threeDSVersion", tx.three_ds_version.as_deref()),
+        ("cavv", tx.cavv.as_deref()),
+        ("eci", tx.eci.as_deref()),
+    ];
+    if let Some(data) = additional_data.as_object_mut() {
+        for (name, value) in extra {
+            if let Some(value) = value {
+                data.insert(name.to_string(), json!(value));
+            }
+        }
+    }
+    let mut body = json!({
+        "additionalData": additional_data,
+        "pspReference": reference,
+        "resultCode": match tx.status {
+            TransactionStatus::Declined => "Refused",
+            TransactionStatus::Pending => "Pending",
+            _ => "Authorised",
+        },
+        "amount": {
+            "currency": tx.currency,
+            "value": minor_units(tx.amount, &tx.currency),
+        },
+        "merchantReference": tx.transaction_id,
+        "paymentMethod": {
+            "brand": adyen_brand(&tx.card_brand),
+            "type": "scheme",
+        },
+    });
+    if tx.status == TransactionStatus::Declined
+        && let Some(body) = body.as_object_mut()
+    {
+        let (code, reason) = match tx.decline_reason {
+            Some(DeclineReason::InsufficientFunds) => ("12", "Not enough balance"),
+            Some(DeclineReason::CardExpired) => ("6", "Expired Card"),
+            Some(DeclineReason::SuspiciousActivity) => ("31", "Issuer Suspected Fraud"),
+            _ => ("2", "Refused"),
+        };
+        body.insert("refusalReason".to_string(), json!(reason));
+        body.insert("refusalReasonCode".to_string(), json!(code));
+    }
+    Ok(Fixture { status: 200, body })
+}
+
+fn adyen_brand(brand: &str) -> String {
+    match brand {
+        "Visa" => "visa".to_string(),
+        "Mastercard" => "mc".to_string(),
+        "American Express" => "amex".to_string(),
+        "UnionPay" => "cup".to_string(),
+        "Diners Club" => "diners".to_string(),
+        brand => brand.to_lowercase().replace(' ', ""),
+    }
+}
+
+fn created(tx: &Transaction) -> io::Result<i64> {
+    Ok(DateTime::parse_from_rfc3339(&tx.transaction_date)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        .timestamp())
+}
+
+// Gateways take amounts in minor units, and refunds as positive amounts
+fn minor_units(amount: f64, currency: &str) -> i64 {
+    Money::from_major(amount.abs(), currency).minor_units
+}
+
+// Month and four-digit year of an `MM/YY` expiry
+fn expiry(expiry: &str) -> (u32, u32) {
+    let (month, year) = expiry.split_once('/').unwrap_or((expiry, ""));
+    (
+        month.parse().unwrap_or(1),
+        2000 + year.parse::<u32>().unwrap_or(0),
+    )
+}
+
+fn last4(card_number: &str) -> &str {
+    &card_number[card_number.len().saturating_sub(4)..]
+}
+
+// Stripe's pass, fail or unavailable for a verification result code
+fn check(result: Option<&str>, pass: &[&str], fail: &[&str]) -> Option<&'static str> {
+    let result = result?;
+    Some(if pass.contains(&result) {
+        "pass"
+    } else if fail.contains(&result) {
+        "fail"
+    } else {
+        "unavailable"
+    })
+}
+
+// An object ID in Stripe's style, derived from what the object stands for so
+// the same transaction, card or customer always gets the same one
+fn gateway_id(prefix: &str, source: &str) -> String {
+    const ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    let digest = Sha256::digest(format!("{}{}", prefix, source));
+    let id: String = digest
+        .iter()
+        .take(24)
+        .map(|byte| ALPHABET[*byte as usize % ALPHABET.len()] as char)
+        .collect();
+    format!("{}{}", prefix, id)
+}
+
+// Adyen's 16-character PSP reference, derived the same way
+fn psp_reference(source: &str) -> String {
+    const ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    Sha256::digest(format!("psp{}", source))
+        .iter()
+        .take(16)
+        .map(|byte| ALPHABET[*byte as usize % ALPHABET.len()] as char)
+        .collect()
+}
@@ -0,0 +1,870 @@
+use crate::{
+    address::Address,
+    bin::{self, CardType},
+    drift::Drift,
+    currency,
+    device::{Device, UserAgent},
+    fx,
+    geo::{self, Country},
+    id::IdStrategy,
+    model::{Channel, DeclineReason, Transaction, TransactionStatus},
+    money::Money,
+    settlement,
+    temporal,
+    text::Text,
+    verification,
+};
+use chrono::{DateTime, Duration, FixedOffset};
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    rngs::StdRng,
+    seq::{IteratorRandom, SliceRandom},
+    Rng, SeedableRng,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    ops::RangeInclusive,
+};
+
+// Kinds of anomalous behavior the injector can produce
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum FraudPattern {
+    // A burst of small authorizations on one card, mostly declined
+    CardTesting,
+    // A second purchase on the same card minutes later from a far-away IP
+    ImpossibleTravel,
+    // A purchase many times larger than usual
+    HighValue,
+    // Several stolen cards spending at the same merchant within an hour,
+    // from the few devices the ring shares
+    FraudRing,
+    // 0.50 authorizations on many stolen cards in a row from one device,
+    // finding out which cards work
+    CardSpray,
+    // A burst of purchases on the card from a device and a country the
+    // cardholder has never used
+    AccountTakeover,
+}
+
+impl FraudPattern {
+    pub const ALL: [FraudPattern; 6] = [
+        FraudPattern::CardTesting,
+        FraudPattern::ImpossibleTravel,
+        FraudPattern::HighValue,
+        FraudPattern::FraudRing,
+        FraudPattern::CardSpray,
+        FraudPattern::AccountTakeover,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FraudPattern::CardTesting => "card_testing",
+            FraudPattern::ImpossibleTravel => "impossible_travel",
+            FraudPattern::HighValue => "high_value",
+            FraudPattern::FraudRing => "fraud_ring",
+            FraudPattern::CardSpray => "card_spray",
+            FraudPattern::AccountTakeover => "account_takeover",
+        }
+    }
+}
+
+// Fraud injection settings of a profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FraudConfig {
+    // Share of generated transactions that are fraud, made up of whole
+    // patterns
+    pub rate: f64,
+    // Relative weights of the patterns
+    pub patterns: BTreeMap<FraudPattern, f64>,
+    // Share of fraudulent online purchases shipped to an address near the
+    // fraudster rather than the cardholder's billing address
+    pub address_mismatch_rate: f64,
+    // Number of stolen cards a fraud ring spends with, at most
+    // `MAX_RING_SIZE`
+    pub ring_size: usize,
+}
+
+impl Default for FraudConfig {
+    fn default() -> Self {
+        Self {
+            rate: 0.0,
+            patterns: FraudPattern::ALL.iter().map(|p| (*p, 1.0)).collect(),
+            address_mismatch_rate: 0.7,
+            ring_size: 6,
+        }
+    }
+}
+
+impl FraudConfig {
+    // Sampling weights for each pattern, in `FraudPattern::ALL` order
+    pub fn pattern_weights(&self) -> Vec<f64> {
+        FraudPattern::ALL
+            .iter()
+            .map(|p| self.patterns.get(p).copied().unwrap_or(0.0))
+            .collect()
+    }
+}
+
+// Number of genuine cards kept for rings and sprays to spend with
+const MAX_STOLEN_CARDS: usize = 500;
+
+// Largest fraud ring, so no one pattern floods the rows around it
+pub const MAX_RING_SIZE: usize = 30;
+
+// Rows of the patterns that make a burst of them: card-testing attempts,
+// cards a spray tries and purchases of an account takeover
+const TESTING_ATTEMPTS: RangeInclusive<usize> = 5..=15;
+const SPRAY_CARDS: RangeInclusive<usize> = 10..=30;
+const TAKEOVER_PURCHASES: RangeInclusive<usize> = 3..=6;
+
+// Fraud rows a pattern makes on average, and all the rows it makes including
+// the genuine purchase it follows, if it keeps it
+fn burst(pattern: FraudPattern, ring_size: usize) -> (f64, f64) {
+    let mean = |range: RangeInclusive<usize>| (range.start() + range.end()) as f64 / 2.0;
+    match pattern {
+        FraudPattern::CardTesting => (mean(TESTING_ATTEMPTS), mean(TESTING_ATTEMPTS)),
+        FraudPattern::ImpossibleTravel => (1.0, 2.0),
+        FraudPattern::HighValue => (1.0, 1.0),
+        FraudPattern::FraudRing => (ring_size.saturating_sub(1) as f64, ring_size as f64),
+        FraudPattern::CardSpray => (mean(SPRAY_CARDS), mean(SPRAY_CARDS) + 1.0),
+        FraudPattern::AccountTakeover => (mean(TAKEOVER_PURCHASES), mean(TAKEOVER_PURCHASES) + 1.0),
+    }
+}
+
+// Patterns to inject with their weights, and the fraud rows and all rows a
+// pattern drawn from them makes on average
+struct Patterns {
+    index: WeightedIndex<f64>,
+    fraud_rows: f64,
+    rows: f64,
+}
+
+impl Patterns {
+    // None when no pattern has weight
+    fn new(weights: &[f64], ring_size: usize) -> Option<Self> {
+        let index = WeightedIndex::new(weights).ok()?;
+        let total: f64 = weights.iter().sum();
+        let (mut fraud_rows, mut rows) = (0.0, 0.0);
+        for (pattern, weight) in FraudPattern::ALL.iter().zip(weights) {
+            let (fraud, all) = burst(*pattern, ring_size);
+            fraud_rows += weight / total * fraud;
+            rows += weight / total * all;
+        }
+        Some(Self { index, fraud_rows, rows })
+    }
+
+    // Chance an eligible purchase starts a pattern for `rate` of the rows to
+    // be fraud. Each start turns one row into `rows`, `fraud_rows` of them
+    // fraud, so with a chance p the share is p * fraud_rows over
+    // 1 + p * (rows - 1).
+    fn start_probability(&self, rate: f64) -> f64 {
+        (rate / (self.fraud_rows - rate * (self.rows - 1.0))).clamp(0.0, 1.0)
+    }
+}
+
+// Fraud rate and patterns of a drift phase
+type DriftPhase = (Option<f64>, Option<Patterns>);
+
+// Wraps a transaction stream and replaces a share of its transactions with
+// labelled fraud patterns. Patterns can expand into several rows, so pair it
+// with `take` when an exact row count is needed.
+pub struct FraudInjector<I> {
+    inner: I,
+    rng: StdRng,
+    rate: f64,
+    // Share of fraud rows to hold, overriding `rate` when set
+    target_ratio: Option<f64>,
+    patterns: Patterns,
+    // Fraud rate and patterns of each drift phase, unset where none of the
+    // phase's patterns has weight
+    drift: Option<(Drift, Vec<DriftPhase>)>,
+    weights: Vec<f64>,
+    address_mismatch_rate: f64,
+    // Strategy for the IDs of the rows a pattern adds
+    ids: IdStrategy,
+    // User agents of the devices fraudsters use, with their weights
+    user_agents: Vec<(UserAgent, f64)>,
+    ring_size: usize,
+    // Cards of recent genuine purchases, which rings and sprays spend with
+    // as stolen cards
+    stolen: VecDeque<StolenCard>,
+    pending: VecDeque<Transaction>,
+    emitted: usize,
+    fraudulent: usize,
+    // Rows taken from the stream, and those of them that could have started
+    // a pattern, since the rest dilute the share of fraud
+    seen: usize,
+    eligible: usize,
+}
+
+impl<I: Iterator<Item = Transaction>> FraudInjector<I> {
+    pub fn new(inner: I, config: &FraudConfig, ids: IdStrategy) -> Self {
+        Self {
+            inner,
+            rng: StdRng::from_entropy(),
+            rate: config.rate,
+            target_ratio: None,
+            patterns: Patterns::new(&config.pattern_weights(), config.ring_size)
+                .expect("fraud pattern weights are validated on load"),
+            drift: None,
+            weights: config.pattern_weights(),
+            address_mismatch_rate: config.address_mismatch_rate,
+            ids,
+            user_agents: Vec::new(),
+            ring_size: config.ring_size,
+            stolen: VecDeque::new(),
+            pending: VecDeque::new(),
+            emitted: 0,
+            fraudulent: 0,
+            seen: 0,
+            eligible: 0,
+        }
+    }
+
+    // Draw the patterns and their rows from this generator, so a seeded run
+    // injects the same fraud
+    pub fn with_rng(mut self, rng: StdRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    // Inject fraud whenever the share of fraud rows so far falls below `ratio`,
+    // so the output holds that class balance regardless of pattern sizes
+    pub fn with_target_ratio(mut self, ratio: Option<f64>) -> Self {
+        self.target_ratio = ratio;
+        self
+    }
+
+    // Inject fraud at the rate and with the patterns of the drift phase each
+    // purchase falls in
+    pub fn with_drift(mut self, drift: Option<Drift>) -> Self {
+        self.drift = drift.map(|drift| {
+            let phases = drift
+                .phases()
+                .iter()
+                .map(|phase| {
+                    let weights = FraudPattern::ALL.iter().zip(&self.weights).map(|(p, w)| {
+                        if phase.fraud_patterns.contains(p) {
+                            *w
+                        } else {
+                            0.0
+                        }
+                    });
+                    let weights: Vec<f64> = weights.collect();
+                    (phase.fraud_rate, Patterns::new(&weights, self.ring_size))
+                })
+                .collect();
+            (drift, phases)
+        });
+        self
+    }
+
+    // Give fraudsters' devices one of these user agents. Without any, they
+    // run the same user agent as the victim's device, if any.
+    pub fn with_user_agents(mut self, user_agents: &[String], weights: &[f64]) -> Self {
+        self.user_agents = user_agents
+            .iter()
+            .map(|user_agent| UserAgent::parse(user_agent))
+            .zip(weights.iter().copied())
+            .collect();
+        self
+    }
+
+    // Whether fraud can be injected at all, so genuine cards are only kept
+    // for rings and sprays when they may be used
+    fn is_enabled(&self) -> bool {
+        let drifted = self.drift.as_ref().is_some_and(|(_, phases)| {
+            phases.iter().any(|(rate, _)| rate.is_some_and(|rate| rate > 0.0))
+        });
+        self.rate > 0.0 || self.target_ratio.is_some_and(|ratio| ratio > 0.0) || drifted
+    }
+
+    // Keep the card of a genuine purchase for later rings and sprays,
+    // forgetting the oldest once enough are kept
+    fn steal(&mut self, tx: &Transaction) {
+        if self.stolen.len() >= MAX_STOLEN_CARDS {
+            self.stolen.pop_front();
+        }
+        self.stolen.push_back(StolenCard::from(tx));
+    }
+
+    // Chance an eligible purchase starts a pattern. Rows that can't start
+    // one, such as refunds, are made up for by starting patterns more often
+    // on the rest.
+    fn start_probability(&self, rate: f64, patterns: &Patterns) -> f64 {
+        match self.target_ratio {
+            Some(ratio) => f64::from(u8::from(
+                (self.fraudulent as f64) < ratio * (self.emitted + 1) as f64,
+            )),
+            None => {
+                let eligible = self.eligible as f64 / self.seen as f64;
+                (patterns.start_probability(rate) / eligible).min(1.0)
+            }
+        }
+    }
+
+    fn next_row(&mut self) -> Option<Transaction> {
+        if let Some(tx) = self.pending.pop_front() {
+            return Some(tx);
+        }
+
+        let tx = self.inner.next()?;
+        self.seen += 1;
+        // Attempts with an expired card are declined for it, and its details
+        // are of no use to a fraudster
+        let expired = matches!(tx.decline_reason, Some(DeclineReason::CardExpired));
+        if tx.status != TransactionStatus::Refunded && !expired && self.is_enabled() {
+            self.steal(&tx);
+        }
+        // Only one-off purchases are turned into fraud; refunds, subscription
+        // charges, installments and pre-authorized orders keep their linkage
+        if tx.status == TransactionStatus::Refunded
+            || tx.subscription_id.is_some()
+            || tx.installment_count.is_some()
+            || tx.preauth_step.is_some()
+            || expired
+        {
+            return Some(tx);
+        }
+        self.eligible += 1;
+        // The fraud rate and patterns in effect at the time of the purchase
+        let (rate, patterns) = match &self.drift {
+            Some((drift, phases)) => {
+                let (rate, patterns) = &phases[drift.phase_index(&parse_date(&tx))];
+                (rate.unwrap_or(self.rate), patterns.as_ref())
+            }
+            None => (self.rate, Some(&self.patterns)),
+        };
+        let Some(patterns) = patterns else {
+            return Some(tx);
+        };
+        let probability = self.start_probability(rate, patterns);
+        if probability < 1.0 && !self.rng.gen_bool(probability) {
+            return Some(tx);
+        }
+
+        let pattern = FraudPattern::ALL[patterns.index.sample(&mut self.rng)];
+        let rng = &mut self.rng;
+        let (user_agents, mismatch_rate, ids) = (&self.user_agents, self.address_mismatch_rate, self.ids);
+        self.pending = match pattern {
+            FraudPattern::CardTesting => {
+                let device = fraud_device(rng, user_agents, &tx);
+                card_testing(rng, &tx, device, mismatch_rate, ids)
+            }
+            FraudPattern::ImpossibleTravel => {
+                let device = fraud_device(rng, user_agents, &tx);
+                impossible_travel(rng, tx, device, mismatch_rate, ids)
+            }
+            FraudPattern::HighValue => high_value(rng, tx),
+            FraudPattern::FraudRing => {
+                let cards = stolen_cards(rng, &self.stolen, &tx, self.ring_size.saturating_sub(1));
+                let devices = (0..rng.gen_range(1..=3))
+                    .map(|_| fraud_device(rng, user_agents, &tx))
+                    .collect();
+                fraud_ring(rng, tx, &cards, devices, ids)
+            }
+            FraudPattern::CardSpray => {
+                let count = rng.gen_range(SPRAY_CARDS);
+                let cards = stolen_cards(rng, &self.stolen, &tx, count);
+                let device = fraud_device(rng, user_agents, &tx);
+                card_spray(rng, tx, &cards, device, ids)
+            }
+            FraudPattern::AccountTakeover => {
+                let device = fraud_device(rng, user_agents, &tx);
+                account_takeover(rng, tx, device, mismatch_rate, ids)
+            }
+        }
+        .into();
+        self.pending.pop_front()
+    }
+}
+
+impl<I: Iterator<Item = Transaction>> Iterator for FraudInjector<I> {
+    type Item = Transaction;
+
+    fn next(&mut self) -> Option<Transaction> {
+        let tx = self.next_row()?;
+        self.emitted += 1;
+        self.fraudulent += usize::from(tx.is_fraud);
+        Some(tx)
+    }
+}
+
+// A device other than the cardholder's, for patterns where the card is
+// used by someone else. Without user agents for fraudsters, it runs the
+// victim's.
+fn fraud_device<R: Rng + ?Sized>(
+    rng: &mut R,
+    user_agents: &[(UserAgent, f64)],
+    tx: &Transaction,
+) -> Device {
+    match user_agents.choose_weighted(rng, |(_, weight)| *weight) {
+        Ok((user_agent, _)) => Device::running(rng, user_agent),
+        Err(_) => Device::new(rng, tx.user_agent.as_deref().unwrap_or_default()),
+    }
+}
+
+// Up to `count` distinct kept cards other than the purchase's own
+fn stolen_cards<R: Rng + ?Sized>(
+    rng: &mut R,
+    stolen: &VecDeque<StolenCard>,
+    tx: &Transaction,
+    count: usize,
+) -> Vec<StolenCard> {
+    let mut cards: Vec<StolenCard> = Vec::new();
+    let shuffled = stolen
+        .iter()
+        .filter(|card| card.card_number != tx.card_number)
+        .choose_multiple(rng, count * 2);
+    for card in shuffled {
+        if cards.len() < count && !cards.iter().any(|c| c.card_number == card.card_number) {
+            cards.push(card.clone());
+        }
+    }
+    cards
+}
+
+// Copy of a transaction made at the given time with a new ID, labelled as
+// fraud
+fn fraudulent<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: &Transaction,
+    at: DateTime<FixedOffset>,
+    pattern: FraudPattern,
+    ids: IdStrategy,
+) -> Transaction {
+    Transaction {
+        transaction_id: ids.generate(rng, at.timestamp_millis()),
+        transaction_date: temporal::rfc3339(&at),
+        is_fraud: true,
+        fraud_pattern: Some(pattern),
+        ..tx.clone()
+    }
+}
+
+// Transaction date, keeping its UTC offset so derived rows are written in
+// the same zone
+fn parse_date(tx: &Transaction) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(&tx.transaction_date).expect("generated dates are RFC 3339")
+}
+
+// A burst of 5-15 tiny authorizations from one device and an IP address
+// abroad, seconds apart. All but possibly the last are declined.
+fn card_testing<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: &Transaction,
+    device: Device,
+    address_mismatch_rate: f64,
+    ids: IdStrategy,
+) -> Vec<Transaction> {
+    let attempts = rng.gen_range(TESTING_ATTEMPTS);
+    let country = foreign_country(rng, tx);
+    let ip_address = country.ip_address(rng, is_ipv6(tx));
+    let shipping = shipping_address(rng, tx, country, address_mismatch_rate);
+    // Attempts are for 0.50-2.00 dollars, or about as much in other currencies
+    let scale = currency::amount_scale(&tx.currency);
+    let mut at = parse_date(tx);
+
+    (0..attempts)
+        .map(|i| {
+            at += Duration::seconds(rng.gen_range(5..=60));
+            let approved = i == attempts - 1 && rng.gen_bool(0.3);
+            let decline_reason = match rng.gen_range(0..3) {
+                0 => DeclineReason::InvalidCard,
+                1 => DeclineReason::InsufficientFunds,
+                _ => DeclineReason::SuspiciousActivity,
+            };
+            let mut attempt = Transaction {
+                status: if approved {
+                    TransactionStatus::Approved
+                } else {
+                    TransactionStatus::Declined
+                },
+                decline_reason: (!approved).then_some(decline_reason),
+                amount: Money::from_major(rng.gen_range(0.5..2.0) * scale, &tx.currency).to_major(),
+                ..fraudulent(rng, tx, at, FraudPattern::CardTesting, ids)
+            };
+            online(rng, &mut attempt, &device, &ip_address, country.code);
+            shipping.assign_shipping(&mut attempt);
+            fx::rebill(&mut attempt);
+            attempt.respond(rng);
+            settlement::settle(&mut attempt);
+            attempt
+        })
+        .collect()
+}
+
+// The genuine purchase followed minutes later by a fraudulent online one on
+// the same card from a different device and an IP address on another
+// continent. In-store purchases are taken to be made in the billing country.
+fn impossible_travel<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: Transaction,
+    device: Device,
+    address_mismatch_rate: f64,
+    ids: IdStrategy,
+) -> Vec<Transaction> {
+    let at = parse_date(&tx) + Duration::minutes(rng.gen_range(5..=30));
+    let origin = tx.ip_country.as_deref().unwrap_or(&tx.billing_country);
+    let country = match geo::country(origin) {
+        Some(home) => geo::distant_country(rng, home),
+        None => geo::random_country(rng),
+    };
+    let mut fraud = Transaction {
+        status: TransactionStatus::Approved,
+        decline_reason: None,
+        ..fraudulent(rng, &tx, at, FraudPattern::ImpossibleTravel, ids)
+    };
+    let ip_address = country.ip_address(rng, is_ipv6(&tx));
+    online(rng, &mut fraud, &device, &ip_address, country.code);
+    shipping_address(rng, &tx, country, address_mismatch_rate).assign_shipping(&mut fraud);
+    fraud.respond(rng);
+    settlement::settle(&mut fraud);
+    vec![tx, fraud]
+}
+
+// Card details and cardholder of a genuine purchase, as a fraudster who has
+// stolen them would present them
+#[derive(Debug, Clone)]
+struct StolenCard {
+    customer_id: Text,
+    cardholder_name: Text,
+    email: Text,
+    phone: Text,
+    card_number: Text,
+    card_brand: Text,
+    card_expiry: Text,
+    cvv: Option<Text>,
+    issuer_bank: Option<Text>,
+    card_country: Option<Text>,
+    card_type: Option<CardType>,
+    billing: Address,
+}
+
+impl From<&Transaction> for StolenCard {
+    fn from(tx: &Transaction) -> Self {
+        Self {
+            customer_id: tx.customer_id.clone(),
+            cardholder_name: tx.cardholder_name.clone(),
+            email: tx.email.clone(),
+            phone: tx.phone.clone(),
+            card_number: tx.card_number.clone(),
+            card_brand: tx.card_brand.clone(),
+            card_expiry: tx.card_expiry.clone(),
+            cvv: tx.cvv.clone(),
+            issuer_bank: tx.issuer_bank.clone(),
+            card_country: tx.card_country.clone(),
+            card_type: tx.card_type,
+            billing: Address::billing(tx),
+        }
+    }
+}
+
+impl StolenCard {
+    // Make a row a payment with this card, as its cardholder
+    fn assign(&self, tx: &mut Transaction) {
+        tx.customer_id = self.customer_id.clone();
+        tx.cardholder_name = self.cardholder_name.clone();
+        tx.email = self.email.clone();
+        tx.phone = self.phone.clone();
+        tx.card_number = self.card_number.clone();
+        tx.card_brand = self.card_brand.clone();
+        tx.card_expiry = self.card_expiry.clone();
+        tx.cvv = self.cvv.clone();
+        tx.issuer_bank = self.issuer_bank.clone();
+        tx.card_country = self.card_country.clone();
+        tx.card_type = self.card_type;
+        tx.billing_street = self.billing.street.clone();
+        tx.billing_city = self.billing.city.clone();
+        tx.billing_region = self.billing.region.clone();
+        tx.billing_postal_code = self.billing.postal_code.clone();
+        tx.billing_country = self.billing.country.clone();
+    }
+}
+
+// The genuine purchase followed by a ring spending with stolen cards at the
+// same merchant within the hour. Members share one to three devices and
+// connect from one country, and order around what the purchase cost, each
+// shipping to an address of their own there. Most cards still work.
+fn fraud_ring<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: Transaction,
+    cards: &[StolenCard],
+    devices: Vec<Device>,
+    ids: IdStrategy,
+) -> Vec<Transaction> {
+    let country = foreign_country(rng, &tx);
+    let ipv6 = is_ipv6(&tx);
+    let ip_addresses: Vec<Text> =
+        devices.iter().map(|_| country.ip_address(rng, ipv6)).collect();
+    let start = parse_date(&tx);
+    let mut rows: Vec<Transaction> = cards
+        .iter()
+        .map(|card| {
+            let at = start + Duration::seconds(rng.gen_range(60..=3600));
+            let approved = rng.gen_bool(0.8);
+            let mut member = Transaction {
+                status: if approved {
+                    TransactionStatus::Approved
+                } else {
+                    TransactionStatus::Declined
+                },
+                decline_reason: (!approved).then_some(DeclineReason::SuspiciousActivity),
+                amount: Money::from_major(tx.amount, &tx.currency)
+                    .scale(rng.gen_range(0.5..1.5))
+                    .to_major(),
+                ..fraudulent(rng, &tx, at, FraudPattern::FraudRing, ids)
+            };
+            card.assign(&mut member);
+            let shared = rng.gen_range(0..devices.len());
+            online(rng, &mut member, &devices[shared], &ip_addresses[shared], country.code);
+            Address::random(rng, country).assign_shipping(&mut member);
+            fx::rebill(&mut member);
+            member.respond(rng);
+            settlement::settle(&mut member);
+            member
+        })
+        .collect();
+    rows.sort_by_key(parse_date);
+    rows.insert(0, tx);
+    rows
+}
+
+// The genuine purchase followed by 0.50 authorizations (or about as much in
+// other currencies) on stolen cards one after another, seconds apart, from
+// one device and IP address abroad. Most cards are declined.
+fn card_spray<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: Transaction,
+    cards: &[StolenCard],
+    device: Device,
+    ids: IdStrategy,
+) -> Vec<Transaction> {
+    let country = foreign_country(rng, &tx);
+    let ip_address = country.ip_address(rng, is_ipv6(&tx));
+    let amount = Money::from_major(0.5 * currency::amount_scale(&tx.currency), &tx.currency);
+    let mut at = parse_date(&tx);
+    let mut rows = vec![tx.clone()];
+    rows.extend(cards.iter().map(|card| {
+        at += Duration::seconds(rng.gen_range(1..=10));
+        let approved = rng.gen_bool(0.2);
+        let decline_reason = match rng.gen_range(0..3) {
+            0 => DeclineReason::InvalidCard,
+            1 => DeclineReason::CardExpired,
+            _ => DeclineReason::SuspiciousActivity,
+        };
+        let mut attempt = Transaction {
+            status: if approved {
+                TransactionStatus::Approved
+            } else {
+                TransactionStatus::Declined
+            },
+            decline_reason: (!approved).then_some(decline_reason),
+            amount: amount.to_major(),
+            ..fraudulent(rng, &tx, at, FraudPattern::CardSpray, ids)
+        };
+        card.assign(&mut attempt);
+        online(rng, &mut attempt, &device, &ip_address, country.code);
+        Address::billing(&attempt).assign_shipping(&mut attempt);
+        fx::rebill(&mut attempt);
+        attempt.respond(rng);
+        settlement::settle(&mut attempt);
+        attempt
+    }));
+    rows
+}
+
+// The genuine purchase followed by 3-6 online purchases on the card minutes
+// apart, from a new device in another country, each larger than the last
+fn account_takeover<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: Transaction,
+    device: Device,
+    address_mismatch_rate: f64,
+    ids: IdStrategy,
+) -> Vec<Transaction> {
+    let country = foreign_country(rng, &tx);
+    let ip_address = country.ip_address(rng, is_ipv6(&tx));
+    let shipping = shipping_address(rng, &tx, country, address_mismatch_rate);
+    let original = Money::from_major(tx.amount, &tx.currency);
+    let mut at = parse_date(&tx) + Duration::hours(rng.gen_range(1..=48));
+    let mut factor = 1.0;
+    let mut rows = vec![tx.clone()];
+    for _ in 0..rng.gen_range(TAKEOVER_PURCHASES) {
+        at += Duration::minutes(rng.gen_range(2..=20));
+        factor *= rng.gen_range(1.2..2.0);
+        let mut purchase = Transaction {
+            status: TransactionStatus::Approved,
+            decline_reason: None,
+            amount: original.scale(factor).to_major(),
+            ..fraudulent(rng, &tx, at, FraudPattern::AccountTakeover, ids)
+        };
+        online(rng, &mut purchase, &device, &ip_address, country.code);
+        shipping.assign_shipping(&mut purchase);
+        fx::rebill(&mut purchase);
+        purchase.respond(rng);
+        settlement::settle(&mut purchase);
+        rows.push(purchase);
+    }
+    rows
+}
+
+// Make a row an online purchase from the given device and IP address. Cards
+// copied in store carry no CVV, so rows derived from an in-store purchase go
+// without one. Stolen card details are paid with directly, never through the
+// cardholder's wallet, at merchants that don't ask for 3-D Secure, and get
+// their own AVS and CVV results.
+fn online<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: &mut Transaction,
+    device: &Device,
+    ip_address: &str,
+    ip_country: &str,
+) {
+    tx.channel = Channel::Ecommerce;
+    tx.pos_entry_mode = Text::from_static(Channel::Ecommerce.pos_entry_mode());
+    tx.terminal_id = None;
+    tx.track1 = None;
+    tx.track2 = None;
+    tx.emv_aid = None;
+    tx.emv_cryptogram = None;
+    tx.emv_tvr = None;
+    tx.emv_atc = None;
+    tx.payment_method = Text::from_static(bin::payment_method(tx.card_type));
+    tx.wallet = None;
+    tx.wallet_id = None;
+    tx.token_pan = None;
+    tx.token_requestor_id = None;
+    tx.three_ds_version = None;
+    tx.eci = None;
+    tx.authentication_result = None;
+    tx.cavv = None;
+    tx.ip_address = Some(ip_address.into());
+    tx.ip_country = Some(ip_country.into());
+    device.assign(tx);
+    verification::verify(rng, tx);
+}
+
+// Where a fraudster has goods bought online sent: mostly an address in the
+// country they connect from, otherwise the cardholder's billing address so
+// the order looks genuine
+fn shipping_address<R: Rng + ?Sized>(
+    rng: &mut R,
+    tx: &Transaction,
+    country: &Country,
+    mismatch_rate: f64,
+) -> Address {
+    if rng.gen_bool(mismatch_rate) {
+        Address::random(rng, country)
+    } else {
+        Address::billing(tx)
+    }
+}
+
+// A country other than the cardholder's billing country, where fraudsters
+// connect from
+fn foreign_country<R: Rng + ?Sized>(rng: &mut R, tx: &Transaction) -> &'static Country {
+    match geo::country(&tx.billing_country) {
+        Some(home) => geo::foreign_country(rng, home),
+        None => geo::random_country(rng),
+    }
+}
+
+// Whether a row's IP address is IPv6, so addresses derived from it can keep
+// its family
+fn is_ipv6(tx: &Transaction) -> bool {
+    tx.ip_address.as_ref().is_some_and(|ip| ip.contains(':'))
+}
+
+// The purchase inflated to 20-100 times its amount
+fn high_value<R: Rng + ?Sized>(rng: &mut R, tx: Transaction) -> Vec<Transaction> {
+    let amount = Money::from_major(tx.amount, &tx.currency)
+        .scale(rng.gen_range(20.0..100.0))
+        .to_major();
+    let mut fraud = Transaction {
+        amount,
+        is_fraud: true,
+        fraud_pattern: Some(FraudPattern::HighValue),
+        ..tx
+    };
+    fx::rebill(&mut fraud);
+    vec![fraud]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Profile, generator::TransactionStream, seed};
+
+    // Share of fraud rows among `rows` seeded ones generated with the fraud
+    // settings
+    fn fraud_share(fraud: FraudConfig, rows: usize) -> f64 {
+        let profile = Profile {
+            fraud,
+            ..Profile::default()
+        };
+        let fraud_rows = seed::with_seed(840, |rng| {
+            let stream_rng = StdRng::from_rng(&mut *rng).unwrap();
+            let stream = TransactionStream::with_rng(&profile, usize::MAX, None, stream_rng);
+            FraudInjector::new(stream, &profile.fraud, profile.id_strategy)
+                .with_rng(StdRng::from_rng(rng).unwrap())
+                .take(rows)
+                .filter(|tx| tx.is_fraud)
+                .count()
+        });
+        fraud_rows as f64 / rows as f64
+    }
+
+    #[test]
+    fn rate_is_the_share_of_fraud_rows() {
+        for rate in [0.05, 0.1, 0.3] {
+            let share = fraud_share(FraudConfig { rate, ..FraudConfig::default() }, 60_000);
+            assert!((share - rate).abs() < 0.01, "{} of rows for a rate of {}", share, rate);
+        }
+        // Patterns of one row and of long bursts alike
+        for (pattern, tolerance) in [
+            (FraudPattern::HighValue, 0.002),
+            (FraudPattern::FraudRing, 0.005),
+            (FraudPattern::CardTesting, 0.01),
+            (FraudPattern::CardSpray, 0.01),
+        ] {
+            let fraud = FraudConfig {
+                rate: 0.1,
+                patterns: BTreeMap::from([(pattern, 1.0)]),
+                ..FraudConfig::default()
+            };
+            let share = fraud_share(fraud, 60_000);
+            assert!((share - 0.1).abs() < tolerance, "{} of rows with {:?}", share, pattern);
+        }
+        assert_eq!(fraud_share(FraudConfig::default(), 1_000), 0.0);
+    }
+
+    #[test]
+    fn start_probability_makes_up_for_bursts() {
+        let weights = |pattern: FraudPattern| FraudPattern::ALL.map(|p| (p == pattern) as u8 as f64);
+        let single = Patterns::new(&weights(FraudPattern::HighValue), 6).unwrap();
+        assert_eq!(single.start_probability(0.1), 0.1);
+        // A spray makes 20 fraud rows and keeps the purchase: p * 20 over
+        // 1 + p * 20 is 0.1 for p = 1/180
+        let spray = Patterns::new(&weights(FraudPattern::CardSpray), 6).unwrap();
+        assert!((spray.start_probability(0.1) - 1.0 / 180.0).abs() < 1e-12);
+        assert_eq!(spray.start_probability(1.0), 1.0);
+        assert_eq!(spray.start_probability(0.0), 0.0);
+        assert!(Patterns::new(&[0.0; 6], 6).is_none());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{currency, geo, model::Transaction, money::Money, text::Text};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Dynamic currency conversion: cardholders paying in a currency other than
+// their card's are offered to pay in their card's currency instead, at a
+// rate the acquirer marks up. Conversions are only generated when `rate` is
+// above 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DccConfig {
+    // Share of purchases in a foreign currency that the cardholder pays in
+    // their card's currency
+    pub rate: f64,
+    // Smallest and largest markup over the mid-market rate, in percent
+    pub min_markup: f64,
+    pub max_markup: f64,
+}
+
+impl Default for DccConfig {
+    fn default() -> Self {
+        Self {
+            rate: 0.0,
+            min_markup: 2.5,
+            max_markup: 7.0,
+        }
+    }
+}
+
+// Mid-market rate converting one unit of `from` into `to`, to 6 decimals;
+// unknown when either currency isn't in the ISO 4217 catalogue
+pub fn rate(from: &str, to: &str) -> Option<f64> {
+    let rate = currency::usd_rate(to)? / currency::usd_rate(from)?;
+    Some((rate * 1e6).round() / 1e6)
+}
+
+// Offer the cardholder to pay in their card's currency, that of the country
+// the card was issued in (or, when the BIN isn't known, the billing
+// country). Cardholders who accept are billed the amount converted at the
+// mid-market rate plus a markup, while the merchant is still paid in its own
+// currency.
+pub fn offer<R: Rng + ?Sized>(rng: &mut R, tx: &mut Transaction, config: &DccConfig) {
+    let country = tx.card_country.as_deref().unwrap_or(&tx.billing_country);
+    let Some(home) = geo::country(country).map(|country| country.currency) else {
+        return;
+    };
+    if home == tx.currency || !rng.gen_bool(config.rate) {
+        return;
+    }
+    let Some(rate) = rate(&tx.currency, home) else {
+        return;
+    };
+    let markup = rng.gen_range(config.min_markup..=config.max_markup);
+    tx.original_currency = Some(tx.currency.clone());
+    tx.billing_currency = Some(Text::from_static(home));
+    tx.fx_rate = Some(rate);
+    tx.fx_markup = Some((markup * 100.0).round() / 100.0);
+    rebill(tx);
+}
+
+// Work out both legs of a converted transaction again from its amount, after
+// the amount changed for a refund, an installment or another step of an
+// order. Transactions that weren't converted are left as they are.
+pub fn rebill(tx: &mut Transaction) {
+    let (Some(rate), Some(markup), Some(currency)) =
+        (tx.fx_rate, tx.fx_markup, tx.billing_currency.as_deref())
+    else {
+        return;
+    };
+    let billed = tx.amount * rate * (1.0 + markup / 100.0);
+    tx.original_amount = Some(tx.amount);
+    tx.billing_amount = Some(Money::from_major(billed, currency).to_major());
+}
@@ -0,0 +1,1345 @@
+use crate::{
+    address::Address,
+    bin::{self, BinEntry},
+    brand::{CardBrand, IinRange},
+    config::{Merchant, Profile},
+    currency, descriptor,
+    device::{Device, UserAgent},
+    drift::Drift,
+    emv, fx, geo, id, installment,
+    luhn,
+    model::{CardExpiry, Channel, Customer, DeclineReason, Transaction, TransactionStatus},
+    money::Money,
+    names::{Locale, Name},
+    preauth::{self, PreauthStep},
+    scd, settlement,
+    subscription::{self, Initiator},
+    temporal::{self, DateRange, TimestampSampler},
+    text::{Text, TextBuf},
+    three_ds::{AuthenticationResult, ThreeDsVersion},
+    track,
+    verification,
+    wallet::{Wallet, WalletToken},
+};
+use chrono::{DateTime, Datelike, Duration, TimeZone};
+use chrono_tz::Tz;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    prelude::SliceRandom,
+    rngs::StdRng,
+    Rng, SeedableRng,
+};
+use rand_distr::Exp;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+// Helper function to generate random data
+fn gen_random_element<'a, T, R: Rng + ?Sized>(rng: &mut R, vec: &'a [T]) -> &'a T {
+    vec.choose(rng).unwrap()
+}
+
+// Generate a random expiry date 1-5 years after the given date, so the card
+// stays valid throughout the generated date range
+fn gen_random_expiry_date<Tz: TimeZone, R: Rng + ?Sized>(
+    rng: &mut R,
+    after: &DateTime<Tz>,
+) -> CardExpiry {
+    let future_years = rng.gen_range(1..=5);
+    let future_month = rng.gen_range(1..=12);
+    CardExpiry::new(future_month, (after.year() + future_years) as u16)
+}
+
+// Generate an expiry date 1-24 months before the month of the given date, so
+// the card had already expired by then
+fn gen_expired_date<Tz: TimeZone, R: Rng + ?Sized>(rng: &mut R, at: &DateTime<Tz>) -> CardExpiry {
+    let months = at.year() * 12 + at.month0() as i32 - rng.gen_range(1..=24);
+    CardExpiry::new((months % 12 + 1) as u8, (months / 12) as u16)
+}
+
+// Generate how long after a purchase its refund happens (1 hour to 30 days)
+fn gen_refund_delay<R: Rng + ?Sized>(rng: &mut R) -> Duration {
+    Duration::minutes(rng.gen_range(60..=30 * 24 * 60))
+}
+
+// Number of customers, devices and cards generated so far in this run,
+// scrambled into their IDs so no two of them share one. Cards are counted
+// by the salt of their IIN and length.
+static CUSTOMERS: AtomicU64 = AtomicU64::new(0);
+static DEVICES: AtomicU64 = AtomicU64::new(0);
+static CARDS: Mutex<BTreeMap<u64, u64>> = Mutex::new(BTreeMap::new());
+
+// Number the first card of an IIN not counted yet is given, which seeded
+// blocks move on so theirs don't meet
+static CARDS_FROM: AtomicU64 = AtomicU64::new(0);
+
+// Customers, devices, cards, subscriptions and orders numbered so far in
+// this run, which seeded blocks and server requests set before generating
+#[derive(Debug, Clone, Default)]
+pub struct Counters {
+    pub customers: u64,
+    pub devices: u64,
+    pub cards: BTreeMap<u64, u64>,
+    pub cards_from: u64,
+    pub subscriptions: u64,
+    pub orders: u64,
+}
+
+impl Counters {
+    pub fn current() -> Self {
+        Self {
+            customers: CUSTOMERS.load(Ordering::Relaxed),
+            devices: DEVICES.load(Ordering::Relaxed),
+            cards: CARDS.lock().unwrap().clone(),
+            cards_from: CARDS_FROM.load(Ordering::Relaxed),
+            subscriptions: subscription::SUBSCRIPTIONS.load(Ordering::Relaxed),
+            orders: preauth::ORDERS.load(Ordering::Relaxed),
+        }
+    }
+
+    // The counts moved on by `offset`, leaving room for whatever is
+    // numbered in between
+    pub fn offset(&self, offset: u64) -> Self {
+        Self {
+            customers: self.customers + offset,
+            devices: self.devices + offset,
+            cards: self.cards.iter().map(|(salt, count)| (*salt, count + offset)).collect(),
+            cards_from: self.cards_from + offset,
+            subscriptions: self.subscriptions + offset,
+            orders: self.orders + offset,
+        }
+    }
+
+    // Carry on numbering from an earlier run's counts
+    pub fn restore(&self) {
+        CUSTOMERS.store(self.customers, Ordering::Relaxed);
+        DEVICES.store(self.devices, Ordering::Relaxed);
+        *CARDS.lock().unwrap() = self.cards.clone();
+        CARDS_FROM.store(self.cards_from, Ordering::Relaxed);
+        subscription::SUBSCRIPTIONS.store(self.subscriptions, Ordering::Relaxed);
+        preauth::ORDERS.store(self.orders, Ordering::Relaxed);
+    }
+}
+
+// Generate a customer ID, distinct for the first billion customers of a run
+fn gen_customer_id() -> Text {
+    let sequence = CUSTOMERS.fetch_add(1, Ordering::Relaxed);
+    let mut id = TextBuf::new();
+    id.push_str("CUS");
+    id.push_digits(id::scramble(sequence, 1_000_000_000, 1), 9);
+    id.into()
+}
+
+// Generate a device ID, distinct for the first 100 million devices of a run
+pub(crate) fn gen_device_id() -> Text {
+    let sequence = DEVICES.fetch_add(1, Ordering::Relaxed);
+    let mut id = TextBuf::new();
+    id.push_str("DEV");
+    id.push_digits(id::scramble(sequence, 100_000_000, 2), 8);
+    id.into()
+}
+
+// Terminal of a merchant that a card-present transaction is made at: the
+// digits of the merchant ID and one of its four lanes, as the 8 characters
+// of ISO 8583 field 41
+fn gen_terminal_id<R: Rng + ?Sized>(rng: &mut R, merchant_id: &str) -> Text {
+    // The last five digits of the merchant ID, then a two-digit lane
+    let digits = merchant_id
+        .bytes()
+        .filter(u8::is_ascii_digit)
+        .fold(0, |n, digit| (n * 10 + u64::from(digit - b'0')) % 100_000);
+    let lane = rng.gen_range(1..=4);
+    let mut id = TextBuf::new();
+    id.push('T');
+    id.push_digits(digits, 5);
+    id.push_digits(lane, 2);
+    id.into()
+}
+
+// Generate a valid credit card number for a specific brand, in one of the
+// brand's BINs when it has any that fit the length. Cards are numbered per
+// IIN and length, and the account digits are that number scrambled, so no PAN
+// repeats until an IIN runs out of account numbers.
+pub(crate) fn generate_card_number<R: Rng + ?Sized>(
+    rng: &mut R,
+    brand: &CardBrand,
+    bins: &[&BinEntry],
+) -> Text {
+    // Choose a random length, then a BIN or prefix; ranged prefixes pick an
+    // IIN inside the range
+    let length = *gen_random_element(rng, &brand.lengths);
+    let fits = |entry: &&&BinEntry| entry.prefix.len() + 2 <= length;
+    let mut number = TextBuf::new();
+    match bins.iter().filter(fits).count() {
+        0 => {
+            let prefix = gen_random_element(rng, &brand.prefix);
+            let range = IinRange::parse(prefix).expect("profile prefixes are validated on load");
+            number.push_digits(rng.gen_range(range.start..=range.end), range.digits);
+        }
+        fitting => {
+            let n = rng.gen_range(0..fitting);
+            let entry = bins.iter().filter(fits).nth(n).expect("counted above");
+            number.push_str(&entry.prefix);
+        }
+    }
+
+    // Fill everything but the final position with the account digits
+    let iin = number.as_str();
+    let digits = length - 1 - iin.len();
+    let salt = iin.parse::<u64>().unwrap_or(0) * 20 + length as u64;
+    let sequence = {
+        let mut cards = CARDS.lock().unwrap_or_else(|e| e.into_inner());
+        let count = cards
+            .entry(salt)
+            .or_insert_with(|| CARDS_FROM.load(Ordering::Relaxed));
+        *count += 1;
+        *count - 1
+    };
+    let account = id::scramble(sequence, 10u64.pow(digits as u32), salt);
+    number.push_digits(account, digits);
+
+    // Append the Luhn check digit
+    let check = luhn::check_digit(number.as_str()).expect("card numbers are digits");
+    number.push_digits(check.into(), 1);
+
+    debug_assert!(number.as_str().len() == length && luhn::is_valid(number.as_str()));
+    number.into()
+}
+
+// Generate a CVV code, drawn as one number and zero-padded to its length
+fn generate_cvv<R: Rng + ?Sized>(rng: &mut R, length: usize) -> Text {
+    let code = rng.gen_range(0..10u64.pow(length as u32));
+    let mut cvv = TextBuf::new();
+    cvv.push_digits(code, length);
+    cvv.into()
+}
+
+// Produces transactions from a profile, with its weighted distributions
+// prepared once up front
+pub struct Generator<'a> {
+    profile: &'a Profile,
+    status_index: WeightedIndex<f64>,
+    brand_index: WeightedIndex<f64>,
+    // Test card numbers of each brand, when cards are only drawn from those
+    test_cards: Vec<Vec<Text>>,
+    // BIN table entries inside each brand's prefixes
+    brand_bins: Vec<Vec<&'a BinEntry>>,
+    // EMV application ID of each brand that has one, by name
+    aids: HashMap<String, String>,
+    // Currencies in profile order, from the list or the region's catalogue
+    currencies: Vec<Text>,
+    currency_index: WeightedIndex<f64>,
+    // Locale of each new cardholder's name, unless names come from the
+    // profile's lists
+    locale_index: Option<WeightedIndex<f64>>,
+    // Text of each merchant, in profile order, held once for every row
+    // made there
+    merchants: Vec<MerchantText>,
+    channel_index: WeightedIndex<f64>,
+    user_agent_index: WeightedIndex<f64>,
+    // The profile's user agents, read once for the devices running them
+    user_agents: Vec<UserAgent>,
+    three_ds_version_index: WeightedIndex<f64>,
+    // Persona of each new customer, and the merchants each persona's
+    // customers shop at, when the pool is split between personas
+    persona_index: Option<WeightedIndex<f64>>,
+    persona_merchants: Vec<WeightedIndex<f64>>,
+    customers: Vec<Customer>,
+    // Returning customer of each purchase, weighted by how often their
+    // persona transacts
+    customer_index: Option<WeightedIndex<f64>>,
+    // Merchants that follow a purchase in each category of the transition
+    // matrix, for customers without a persona and then for each persona;
+    // unset where the persona never shops at the categories that follow
+    sequence_merchants: HashMap<String, Vec<Option<WeightedIndex<f64>>>>,
+    // Mean seconds between consecutive purchases, and the last purchase of
+    // each pooled customer, when purchases follow merchant sequences
+    sequence_gap: Exp<f64>,
+    links: HashMap<Text, Link>,
+    // Changes partway through the date range, when the profile has any
+    drift: Option<Drift>,
+    // Billing addresses that pooled customers move to, and names and
+    // statement descriptors that merchants rebrand to, each with the time of
+    // the change, by customer and merchant ID
+    moves: HashMap<Text, scd::Changes<Address>>,
+    renames: HashMap<Text, scd::Changes<(Text, Text)>>,
+    // Timestamp samplers for the default pattern and each configured category
+    default_timestamps: TimestampSampler,
+    category_timestamps: HashMap<String, TimestampSampler>,
+    date_range: DateRange,
+    // Approved transactions that a later refund may point back to
+    refundable: Vec<Refundable>,
+    // Renewals of subscriptions signed up to, later installments of purchases
+    // and later steps of pre-authorized orders, emitted before anything else
+    scheduled: VecDeque<Transaction>,
+}
+
+// A merchant's name, ID, category, MCC and statement descriptor as rows
+// carry them
+struct MerchantText {
+    name: Text,
+    id: Text,
+    category: Text,
+    mcc: Option<Text>,
+    descriptor: Text,
+}
+
+impl MerchantText {
+    fn new(merchant: &Merchant) -> Self {
+        Self {
+            name: merchant.name.as_str().into(),
+            id: merchant.id.as_str().into(),
+            category: merchant.category.as_str().into(),
+            mcc: merchant.mcc.as_deref().map(Text::from),
+            descriptor: descriptor::descriptor(merchant).into(),
+        }
+    }
+}
+
+// Category and time of a customer's last purchase, which their next one
+// follows on from
+struct Link {
+    category: Text,
+    time: DateTime<Tz>,
+}
+
+// An approved transaction that hasn't been refunded in full yet
+struct Refundable {
+    original: Transaction,
+    // Amount left to refund
+    remaining: Money,
+    // Time of the transaction or its latest partial refund, which the next
+    // refund comes after
+    last: DateTime<Tz>,
+}
+
+// Share of a returning customer's transactions made from their usual IP address
+const HOME_IP_SHARE: f64 = 0.9;
+
+// Most draws of a purchase before one is kept whatever the drift, for
+// customers who only shop where drift leaves little weight
+const MAX_DRIFT_ATTEMPTS: usize = 100;
+
+// Number of approved transactions kept around as refund candidates
+const MAX_REFUNDABLE: usize = 10_000;
+
+impl<'a> Generator<'a> {
+    pub fn new<R: Rng + ?Sized>(profile: &'a Profile, rng: &mut R) -> Self {
+        Self::with_customers(profile, None, rng)
+    }
+
+    // A generator drawing from the given pool of customers, or a new pool
+    // of the profile's size when there is none. The pool, moves and
+    // rebrands are drawn from `rng`.
+    pub fn with_customers<R: Rng + ?Sized>(
+        profile: &'a Profile,
+        customers: Option<Vec<Customer>>,
+        rng: &mut R,
+    ) -> Self {
+        // Weights are validated when the profile is loaded
+        let index = |weights: Vec<f64>| WeightedIndex::new(weights).expect("invalid profile weights");
+        let date_range = profile.date_range();
+        let timestamps = |pattern| {
+            TimestampSampler::new(pattern, &date_range).expect("invalid temporal pattern")
+        };
+        let mut generator = Self {
+            profile,
+            status_index: index(profile.status_weights()),
+            brand_index: index(profile.brand_weights()),
+            test_cards: if profile.test_cards_only {
+                profile
+                    .card_brands
+                    .iter()
+                    .map(|b| b.test_cards().into_iter().map(Text::from).collect())
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            brand_bins: profile
+                .card_brands
+                .iter()
+                .map(|brand| {
+                    let ranges: Vec<IinRange> =
+                        brand.prefix.iter().filter_map(|p| IinRange::parse(p)).collect();
+                    profile
+                        .bins
+                        .iter()
+                        .filter(|entry| ranges.iter().any(|range| range.contains(&entry.prefix)))
+                        .collect()
+                })
+                .collect(),
+            aids: profile
+                .card_brands
+                .iter()
+                .filter_map(|brand| Some((brand.name.clone(), brand.aid()?)))
+                .collect(),
+            currencies: profile
+                .currency_codes()
+                .into_iter()
+                .map(Text::from)
+                .collect(),
+            currency_index: index(profile.currency_weights()),
+            locale_index: (!profile.names.locales.is_empty())
+                .then(|| index(profile.names.locale_weights())),
+            merchants: profile.merchants.iter().map(MerchantText::new).collect(),
+            channel_index: index(profile.channel_weights()),
+            user_agent_index: index(profile.user_agent_weights()),
+            user_agents: profile.user_agents.iter().map(|ua| UserAgent::parse(ua)).collect(),
+            three_ds_version_index: index(profile.three_ds.version_weights()),
+            persona_index: (!profile.personas.is_empty())
+                .then(|| index(profile.personas.iter().map(|p| p.weight).collect())),
+            persona_merchants: profile
+                .personas
+                .iter()
+                .map(|persona| {
+                    index(
+                        profile
+                            .merchants
+                            .iter()
+                            .map(|m| persona.category_weight(&m.category))
+                            .collect(),
+                    )
+                })
+                .collect(),
+            customers: Vec::new(),
+            customer_index: None,
+            sequence_merchants: profile
+                .sequences
+                .transitions
+                .iter()
+                .map(|(category, row)| {
+                    let transition = |merchant: &Merchant| {
+                        row.get(&merchant.category).copied().unwrap_or(0.0)
+                    };
+                    let mut indexes = vec![
+                        WeightedIndex::new(profile.merchants.iter().map(transition)).ok(),
+                    ];
+                    indexes.extend(profile.personas.iter().map(|persona| {
+                        WeightedIndex::new(profile.merchants.iter().map(|m| {
+                            transition(m) * persona.category_weight(&m.category)
+                        }))
+                        .ok()
+                    }));
+                    (category.clone(), indexes)
+                })
+                .collect(),
+            sequence_gap: Exp::new(1.0 / profile.sequences.mean_gap().as_secs_f64())
+                .expect("sequence gap is validated"),
+            links: HashMap::new(),
+            drift: Drift::of(profile),
+            moves: HashMap::new(),
+            renames: HashMap::new(),
+            default_timestamps: timestamps(&profile.temporal.default),
+            category_timestamps: profile
+                .temporal
+                .categories
+                .iter()
+                .map(|(category, pattern)| (category.clone(), timestamps(pattern)))
+                .collect(),
+            date_range,
+            refundable: Vec::new(),
+            scheduled: VecDeque::new(),
+        };
+        generator.customers = match customers {
+            Some(customers) => customers,
+            None => {
+                let pool_size = profile.customers.unwrap_or(0);
+                (0..pool_size).map(|_| generator.new_customer(rng)).collect()
+            }
+        };
+        if !profile.personas.is_empty() && !generator.customers.is_empty() {
+            let frequencies = generator
+                .customers
+                .iter()
+                .map(|customer| {
+                    generator
+                        .persona(customer)
+                        .map_or(1.0, |i| profile.personas[i].frequency)
+                })
+                .collect();
+            generator.customer_index = Some(index(frequencies));
+        }
+
+        // Customers move within their own country
+        let date_range = &generator.date_range;
+        generator.moves = generator
+            .customers
+            .iter()
+            .filter_map(|customer| {
+                let home = geo::country(&customer.billing_address.country)?;
+                let moves: scd::Changes<Address> =
+                    scd::change_times(rng, profile.scd.address_change_rate, date_range)
+                        .into_iter()
+                        .map(|at| (at, Address::random(rng, home)))
+                        .collect();
+                (!moves.is_empty()).then(|| (customer.customer_id.clone(), moves))
+            })
+            .collect();
+        generator.renames = profile
+            .merchants
+            .iter()
+            .filter_map(|merchant| {
+                let mut name = merchant.name.clone();
+                let renames: scd::Changes<(Text, Text)> =
+                    scd::change_times(rng, profile.scd.merchant_rename_rate, date_range)
+                        .into_iter()
+                        .map(|at| {
+                            name = scd::rename(rng, &name);
+                            let renamed = Merchant {
+                                name: name.clone(),
+                                ..merchant.clone()
+                            };
+                            let descriptor: Text = descriptor::descriptor(&renamed).into();
+                            (at, (name.as_str().into(), descriptor))
+                        })
+                        .collect();
+                (!renames.is_empty()).then(|| (merchant.id.as_str().into(), renames))
+            })
+            .collect();
+        generator
+    }
+
+    // Generate a new customer with their own card, device and usual IP address
+    pub fn customer<R: Rng + ?Sized>(&self, rng: &mut R) -> Customer {
+        self.new_customer(rng)
+    }
+
+    fn new_customer<R: Rng + ?Sized>(&self, rng: &mut R) -> Customer {
+        let profile = self.profile;
+        let index = self.brand_index.sample(rng);
+        let brand = &profile.card_brands[index];
+        let name = match &self.locale_index {
+            Some(locale_index) => Locale::ALL[locale_index.sample(rng)].full_name(rng),
+            None => Name::latin(
+                gen_random_element(rng, &profile.first_names).as_str(),
+                gen_random_element(rng, &profile.last_names).as_str(),
+            ),
+        };
+        let user_agent = &self.user_agents[self.user_agent_index.sample(rng)];
+        let device = Device::running(rng, user_agent);
+        let persona = self
+            .persona_index
+            .as_ref()
+            .map(|persona_index| &profile.personas[persona_index.sample(rng)]);
+        let other_devices = persona.map_or_else(Vec::new, |persona| {
+            (1..persona.devices)
+                .map(|_| {
+                    let user_agent = &self.user_agents[self.user_agent_index.sample(rng)];
+                    Device::running(rng, user_agent)
+                })
+                .collect()
+        });
+        let card_number = match self.test_cards.get(index) {
+            Some(test_cards) => gen_random_element(rng, test_cards).clone(),
+            None => generate_card_number(rng, brand, &self.brand_bins[index]),
+        };
+        // Customers live in the country their card was issued in, when it
+        // is known
+        let home = bin::lookup(&card_number, &profile.bins)
+            .and_then(|entry| geo::country(&entry.country))
+            .unwrap_or_else(|| geo::random_country(rng));
+        let ipv6 = rng.gen_bool(profile.geo.ipv6_share);
+
+        Customer {
+            customer_id: gen_customer_id(),
+            email: name.email(rng),
+            phone: home.phone_number(rng),
+            name: name.full,
+            card_brand: brand.name.as_str().into(),
+            card_number,
+            card_expiry: gen_random_expiry_date(rng, &self.date_range.end).into(),
+            cvv: generate_cvv(rng, brand.cvv_length),
+            billing_address: Address::random(rng, home),
+            ip_address: home.ip_address(rng, ipv6),
+            wallet: (!profile.test_cards_only).then(|| WalletToken {
+                wallet: Wallet::for_device(rng, &device),
+                token_pan: generate_card_number(rng, brand, &[]),
+            }),
+            device,
+            persona: persona.map(|persona| persona.name.as_str().into()),
+            other_devices,
+        }
+    }
+
+    // Index of the customer's persona in the profile
+    fn persona(&self, customer: &Customer) -> Option<usize> {
+        let name = customer.persona.as_ref()?;
+        self.profile.personas.iter().position(|p| &p.name == name)
+    }
+
+    // A channel and currency drawn by the profile's weights, for purchases
+    // that are scripted without one
+    pub fn channel<R: Rng + ?Sized>(&self, rng: &mut R) -> Channel {
+        Channel::ALL[self.channel_index.sample(rng)]
+    }
+
+    pub fn currency<R: Rng + ?Sized>(&self, rng: &mut R) -> Text {
+        self.currencies[self.currency_index.sample(rng)].clone()
+    }
+
+    // The customers drawn from by `transaction`; empty when every
+    // transaction gets a brand new customer
+    pub fn customers(&self) -> &[Customer] {
+        &self.customers
+    }
+
+    // The customers as they stand at the end of the date range, each at the
+    // address they last moved to
+    pub fn settled_customers(&self) -> Vec<Customer> {
+        let mut customers = self.customers.clone();
+        for customer in &mut customers {
+            if let Some((_, address)) =
+                self.moves.get(&customer.customer_id).and_then(|m| m.last())
+            {
+                customer.billing_address = address.clone();
+            }
+        }
+        customers
+    }
+
+    // The profile's merchants as they stand at the end of the date range,
+    // each under the name and statement descriptor it last rebranded to
+    pub fn settled_merchants(&self) -> Vec<Merchant> {
+        let mut merchants = self.profile.merchants.clone();
+        for merchant in &mut merchants {
+            if let Some((_, (name, descriptor))) =
+                self.renames.get(&*merchant.id).and_then(|r| r.last())
+            {
+                merchant.name = name.to_string();
+                merchant.descriptor = Some(descriptor.to_string());
+            }
+        }
+        merchants
+    }
+
+    // Forget refundable purchases, scheduled rows and links for a new seeded block
+    pub(crate) fn reset(&mut self) {
+        self.refundable.clear();
+        self.scheduled.clear();
+        self.links.clear();
+    }
+
+    // Generate a single transaction. Refunds always point back to an earlier
+    // approved transaction; until one exists, an approval is generated instead.
+    // Subscription sign-ups are followed by their renewals, purchases paid in
+    // installments by their later installments, and pre-authorizations at
+    // hotels and car rentals by their incremental authorizations and capture.
+    // Every row carries the billing address and merchant name in effect at
+    // its time.
+    pub fn transaction<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Transaction {
+        let mut tx = self.next_row(rng);
+        self.revise(&mut tx);
+        tx
+    }
+
+    // Apply the moves and rebrands made by the time of a transaction, which
+    // may be a later step of one generated before them
+    fn revise(&self, tx: &mut Transaction) {
+        if self.moves.is_empty() && self.renames.is_empty() {
+            return;
+        }
+        let time =
+            DateTime::parse_from_rfc3339(&tx.transaction_date).expect("generated dates are RFC 3339");
+        if let Some(moves) = self.moves.get(&tx.customer_id)
+            && let Some(address) = scd::latest(moves, &time)
+        {
+            scd::relocate(tx, address);
+        }
+        if let Some(renames) = self.renames.get(&tx.merchant_id)
+            && let Some((name, descriptor)) = scd::latest(renames, &time)
+        {
+            tx.merchant_name = name.clone();
+            tx.statement_descriptor = descriptor.clone();
+        }
+    }
+
+    fn next_row<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Transaction {
+        if let Some(tx) = self.scheduled.pop_front() {
+            if tx.preauth_step == Some(PreauthStep::Capture) {
+                self.remember_refundable(rng, &tx);
+            }
+            return tx;
+        }
+        let status = TransactionStatus::ALL[self.status_index.sample(rng)];
+
+        if status == TransactionStatus::Refunded
+            && let Some(refund) = self.refund(rng)
+        {
+            return refund;
+        }
+        let status = match status {
+            TransactionStatus::Refunded => TransactionStatus::Approved,
+            status => status,
+        };
+
+        let tx = if rng.gen_bool(self.profile.subscriptions.rate) {
+            self.subscribe(rng, status)
+        } else {
+            let channel = Channel::ALL[self.channel_index.sample(rng)];
+            let mut tx = self.purchase(rng, status, channel);
+            if preauth::is_lodging_or_rental(tx.mcc.as_deref())
+                && rng.gen_bool(self.profile.preauths.rate)
+            {
+                self.scheduled.extend(preauth::authorize(
+                    rng,
+                    &mut tx,
+                    &self.profile.preauths,
+                    &self.date_range.end,
+                    self.profile.id_strategy,
+                ));
+            } else if rng.gen_bool(self.profile.installments.rate) {
+                let count = *self
+                    .profile
+                    .installments
+                    .counts
+                    .choose(rng)
+                    .expect("installment counts are validated on load");
+                self.scheduled.extend(installment::plan(
+                    rng,
+                    &mut tx,
+                    count,
+                    &self.date_range.end,
+                    self.profile.id_strategy,
+                ));
+            }
+            tx
+        };
+        if tx.status == TransactionStatus::Approved && !tx.is_hold() {
+            self.remember_refundable(rng, &tx);
+        }
+        tx
+    }
+
+    // Keep an approved transaction as a refund candidate, replacing a random
+    // older candidate once the history is full
+    fn remember_refundable<R: Rng + ?Sized>(&mut self, rng: &mut R, tx: &Transaction) {
+        let candidate = Refundable {
+            original: tx.clone(),
+            remaining: Money::from_major(tx.amount, &tx.currency),
+            last: DateTime::parse_from_rfc3339(&tx.transaction_date)
+                .expect("generated dates are RFC 3339")
+                .with_timezone(&self.date_range.end.timezone()),
+        };
+        if self.refundable.len() < MAX_REFUNDABLE {
+            self.refundable.push(candidate);
+        } else {
+            let slot = rng.gen_range(0..MAX_REFUNDABLE);
+            self.refundable[slot] = candidate;
+        }
+    }
+
+    // Refund an earlier approved transaction some time after it happened or
+    // was last refunded. Partial refunds return part of what is left, keeping
+    // the transaction around for further refunds, so refunds never add up to
+    // more than the original amount. Refunds of a capture keep its order
+    // reference but aren't a step of the pre-authorization themselves.
+    fn refund<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<Transaction> {
+        if self.refundable.is_empty() {
+            return None;
+        }
+        let index = rng.gen_range(0..self.refundable.len());
+        let candidate = &mut self.refundable[index];
+        // Live refunds happen now, which is always after the purchase
+        let refund_date = if self.date_range.live {
+            self.date_range.now().max(candidate.last)
+        } else {
+            (candidate.last + gen_refund_delay(rng)).min(self.date_range.end)
+        };
+
+        // Partial refunds are whole minor units, leaving at least one behind
+        let remaining = candidate.remaining;
+        let refunded = if remaining.minor_units >= 2
+            && rng.gen_bool(self.profile.partial_refund_rate)
+        {
+            let share = remaining.scale(rng.gen_range(0.1..0.9));
+            Money {
+                minor_units: share.minor_units.clamp(1, remaining.minor_units - 1),
+                ..share
+            }
+        } else {
+            remaining
+        };
+        candidate.remaining = remaining - refunded;
+        candidate.last = refund_date;
+        let remaining = candidate.remaining;
+        let original = if remaining.minor_units > 0 {
+            candidate.original.clone()
+        } else {
+            self.refundable.swap_remove(index).original
+        };
+        Some(self.refund_of(rng, &original, refunded, remaining, refund_date))
+    }
+
+    // A refund of part of an approved transaction at a given time, leaving
+    // `remaining` of it to refund
+    pub fn refund_of<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        original: &Transaction,
+        refunded: Money,
+        remaining: Money,
+        refund_date: DateTime<Tz>,
+    ) -> Transaction {
+        let refund_amount = refunded.to_major();
+        let mut refund = Transaction {
+            transaction_id: self
+                .profile
+                .id_strategy
+                .generate(rng, refund_date.timestamp_millis()),
+            transaction_date: temporal::rfc3339(&refund_date),
+            status: TransactionStatus::Refunded,
+            decline_reason: None,
+            original_transaction_id: Some(original.transaction_id.clone()),
+            amount: -refund_amount,
+            refund_amount: Some(refund_amount),
+            remaining_amount: Some(remaining.to_major()),
+            preauth_step: None,
+            ..original.clone()
+        };
+        fx::rebill(&mut refund);
+        refund.respond(rng);
+        settlement::settle(&mut refund);
+        refund
+    }
+
+    // Sign a customer up to a subscription online, scheduling its renewals
+    // when the sign-up is approved
+    fn subscribe<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        status: TransactionStatus,
+    ) -> Transaction {
+        let mut signup = self.purchase(rng, status, Channel::Ecommerce);
+        signup.subscription_id = Some(subscription::gen_subscription_id().into());
+        signup.initiator = Some(Initiator::Cit);
+        if signup.status == TransactionStatus::Approved {
+            self.scheduled.extend(subscription::renewals(
+                rng,
+                &signup,
+                &self.profile.subscriptions,
+                &self.date_range.end,
+                self.profile.id_strategy,
+            ));
+        }
+        signup
+    }
+
+    // Generate a purchase with the given (non-refund) status over a channel
+    fn purchase<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        status: TransactionStatus,
+        channel: Channel,
+    ) -> Transaction {
+        let profile = self.profile;
+
+        // Draw a returning customer, or invent one when there is no pool
+        let fresh;
+        let returning = match &self.customer_index {
+            Some(customer_index) => Some(&self.customers[customer_index.sample(rng)]),
+            None => self.customers.choose(rng),
+        };
+        let customer = match returning {
+            Some(customer) => customer,
+            None => {
+                fresh = self.new_customer(rng);
+                &fresh
+            }
+        };
+        let persona = self.persona(customer);
+
+        // A pooled customer's purchase follows on from their last one, unless
+        // that would run past the end of the date range. Live purchases are
+        // made now, whenever that is.
+        let chained = returning.is_some() && profile.sequences.is_enabled();
+        // Purchases are drawn again until drift keeps them, so the merchant
+        // mix is that of the stretch of the date range they fall in
+        let mut attempts = 0;
+        let (merchant_index, currency, transaction_date) = loop {
+            let link = chained
+                .then(|| self.links.get(&customer.customer_id))
+                .flatten();
+            let next = link.and_then(|link| {
+                if self.date_range.live {
+                    return Some((link, None));
+                }
+                let gap =
+                    Duration::milliseconds((self.sequence_gap.sample(rng) * 1000.0) as i64);
+                let time = link
+                    .time
+                    .checked_add_signed(gap)
+                    .filter(|time| *time <= self.date_range.end)?;
+                Some((link, Some(time)))
+            });
+
+            // Select random elements, with the merchant from the categories
+            // that follow the customer's last purchase, or else those the
+            // customer's persona shops at
+            let following = next
+                .and_then(|(link, _)| self.sequence_merchants.get(link.category.as_str()))
+                .and_then(|indexes| indexes[persona.map_or(0, |i| i + 1)].as_ref());
+            let merchant_index = match (following, persona) {
+                (Some(index), _) => index.sample(rng),
+                (None, Some(i)) => self.persona_merchants[i].sample(rng),
+                (None, None) => rng.gen_range(0..profile.merchants.len()),
+            };
+            let merchant = &profile.merchants[merchant_index];
+            let currency = &self.currencies[self.currency_index.sample(rng)];
+
+            // Generate transaction date within the profile's date range,
+            // following the merchant category's temporal pattern
+            let transaction_date = match next.and_then(|(_, time)| time) {
+                Some(time) => time,
+                None => self
+                    .category_timestamps
+                    .get(&merchant.category)
+                    .unwrap_or(&self.default_timestamps)
+                    .sample(rng),
+            };
+            attempts += 1;
+            match &self.drift {
+                Some(drift)
+                    if drift.shifts_merchants()
+                        && attempts < MAX_DRIFT_ATTEMPTS
+                        && !drift.accepts(rng, merchant_index, &transaction_date) => {}
+                _ => break (merchant_index, currency, transaction_date),
+            }
+        };
+        let merchant = &profile.merchants[merchant_index];
+
+        // Generate amount from the merchant category's distribution, scaled
+        // to the currency's magnitude and rounded to its minor units
+        let amount = profile
+            .amounts
+            .for_category(&merchant.category)
+            .sample(rng);
+        let amount = amount * persona.map_or(1.0, |i| profile.personas[i].amount_scale);
+        let amount = match &self.drift {
+            Some(drift) => amount * drift.phase(&transaction_date).amount_scale,
+            None => amount,
+        };
+        let amount = Money::from_major(amount * currency::amount_scale(currency), currency);
+        let amount = amount.to_major();
+
+        // Some attempts are made with an expired card, and are declined for it
+        let (status, decline_reason) = if rng.gen_bool(profile.expired_card_rate) {
+            (TransactionStatus::Declined, Some(DeclineReason::CardExpired))
+        } else {
+            match status {
+                TransactionStatus::Declined => (status, Some(rng.r#gen())),
+                _ => (status, None),
+            }
+        };
+        let order = Order {
+            customer,
+            merchant_index,
+            channel,
+            currency: currency.clone(),
+            amount,
+            transaction_date,
+            status,
+            decline_reason,
+        };
+        let tx = self.fill(rng, order, false);
+        if chained {
+            let link = Link {
+                category: self.merchants[merchant_index].category.clone(),
+                time: transaction_date,
+            };
+            self.links.insert(tx.customer_id.clone(), link);
+        }
+        tx
+    }
+
+    // A purchase as a scenario scripts it, with the rest of its details
+    // filled in. Its status is kept as scripted.
+    pub fn scripted<R: Rng + ?Sized>(&self, rng: &mut R, order: Order) -> Transaction {
+        let mut tx = self.fill(rng, order, true);
+        self.revise(&mut tx);
+        tx
+    }
+
+    // Fill in the details of a purchase around what has been decided for it
+    fn fill<R: Rng + ?Sized>(&self, rng: &mut R, order: Order, scripted: bool) -> Transaction {
+        let profile = self.profile;
+        let Order {
+            customer,
+            merchant_index,
+            channel,
+            currency,
+            amount,
+            transaction_date,
+            status,
+            decline_reason,
+        } = order;
+        let merchant = &self.merchants[merchant_index];
+
+        let bin = bin::lookup(&customer.card_number, &profile.bins);
+
+        // Cards declined as expired carry an expiry before the transaction date
+        let card_expiry = match decline_reason {
+            Some(DeclineReason::CardExpired) => {
+                gen_expired_date(rng, &transaction_date).into()
+            }
+            _ => customer.card_expiry.clone(),
+        };
+
+        let mut tx = Transaction {
+            transaction_id: profile
+                .id_strategy
+                .generate(rng, transaction_date.timestamp_millis()),
+            event_type: None,
+            transaction_date: temporal::rfc3339(&transaction_date),
+            status,
+            decline_reason,
+            response_code: Text::default(),
+            auth_code: None,
+            original_transaction_id: None,
+            subscription_id: None,
+            initiator: None,
+            installment_number: None,
+            installment_count: None,
+            order_reference: None,
+            preauth_step: None,
+            customer_id: customer.customer_id.clone(),
+            cardholder_name: customer.name.clone(),
+            email: customer.email.clone(),
+            phone: customer.phone.clone(),
+            card_number: customer.card_number.clone(),
+            card_brand: customer.card_brand.clone(),
+            card_expiry,
+            cvv: (!channel.is_card_present()).then(|| customer.cvv.clone()),
+            issuer_bank: bin.map(|entry| entry.issuer.clone()),
+            card_country: bin.map(|entry| entry.country.clone()),
+            card_type: bin.map(|entry| entry.kind()),
+            billing_street: customer.billing_address.street.clone(),
+            billing_city: customer.billing_address.city.clone(),
+            billing_region: customer.billing_address.region.clone(),
+            billing_postal_code: customer.billing_address.postal_code.clone(),
+            billing_country: customer.billing_address.country.clone(),
+            shipping_street: None,
+            shipping_city: None,
+            shipping_region: None,
+            shipping_postal_code: None,
+            shipping_country: None,
+            amount,
+            refund_amount: None,
+            remaining_amount: None,
+            currency,
+            original_amount: None,
+            original_currency: None,
+            billing_amount: None,
+            billing_currency: None,
+            fx_rate: None,
+            fx_markup: None,
+            merchant_name: merchant.name.clone(),
+            merchant_id: merchant.id.clone(),
+            statement_descriptor: merchant.descriptor.clone(),
+            merchant_category: merchant.category.clone(),
+            mcc: merchant.mcc.clone(),
+            acquirer_id: Text::default(),
+            processor: Text::default(),
+            batch_id: None,
+            settlement_date: None,
+            payment_method: Text::from_static(bin::payment_method(bin.map(|entry| entry.kind()))),
+            channel,
+            pos_entry_mode: Text::from_static(channel.pos_entry_mode()),
+            terminal_id: channel
+                .is_card_present()
+                .then(|| gen_terminal_id(rng, &merchant.id)),
+            track1: None,
+            track2: None,
+            emv_aid: None,
+            emv_cryptogram: None,
+            emv_tvr: None,
+            emv_atc: None,
+            wallet: None,
+            wallet_id: None,
+            token_pan: None,
+            token_requestor_id: None,
+            three_ds_version: None,
+            eci: None,
+            authentication_result: None,
+            cavv: None,
+            avs_result: None,
+            cvv_result: None,
+            ip_address: None,
+            ip_country: None,
+            device_id: None,
+            device_platform: None,
+            device_os: None,
+            device_browser: None,
+            screen_resolution: None,
+            device_fingerprint: None,
+            user_agent: None,
+            is_fraud: false,
+            fraud_pattern: None,
+            persona: customer.persona.clone(),
+            custom: serde_json::Map::new(),
+        };
+
+        // Customers with a wallet pay online and by tapping their phone with
+        // it some of the time, presenting the device PAN instead of the card
+        // number
+        if matches!(channel, Channel::Ecommerce | Channel::Contactless)
+            && let Some(token) = &customer.wallet
+            && rng.gen_bool(profile.wallets.rate)
+        {
+            tx.payment_method = Text::from_static("digital_wallet");
+            tx.wallet = Some(token.wallet);
+            tx.wallet_id = Some(Text::from_static(token.wallet.wallet_id()));
+            tx.token_pan = Some(token.token_pan.clone());
+            tx.token_requestor_id = Some(Text::from_static(token.wallet.token_requestor_id()));
+        }
+        if profile.track_data && channel.is_card_present() {
+            track::assign(&mut tx);
+        }
+        if profile.emv
+            && matches!(channel, Channel::PosChip | Channel::Contactless)
+            && let Some(aid) = self.aids.get(tx.card_brand.as_str())
+        {
+            emv::assign(&mut tx, aid);
+        }
+
+        // Online purchases come from the customer's device. Returning
+        // customers mostly transact from their usual IP address, otherwise
+        // from elsewhere in their billing country, and now and then from
+        // abroad. Goods are shipped to the billing address, except now and
+        // then to another address in the same country, such as a gift.
+        if channel == Channel::Ecommerce {
+            let home = geo::country(&customer.billing_address.country)
+                .unwrap_or_else(|| geo::random_country(rng));
+            let ipv6 = rng.gen_bool(profile.geo.ipv6_share);
+            let (ip_address, ip_country) = if rng.gen_bool(profile.geo.foreign_ip_rate) {
+                let country = geo::foreign_country(rng, home);
+                (country.ip_address(rng, ipv6), country.code)
+            } else if rng.gen_bool(HOME_IP_SHARE) {
+                (customer.ip_address.clone(), home.code)
+            } else {
+                (home.ip_address(rng, ipv6), home.code)
+            };
+            tx.ip_address = Some(ip_address);
+            tx.ip_country = Some(Text::from_static(ip_country));
+            // Customers with more than one device shop from any of them
+            let device = match customer.other_devices.as_slice() {
+                [] => &customer.device,
+                others => {
+                    let i = rng.gen_range(0..=others.len());
+                    others.get(i).unwrap_or(&customer.device)
+                }
+            };
+            device.assign(&mut tx);
+            if rng.gen_bool(profile.geo.shipping_mismatch_rate) {
+                Address::random(rng, home).assign_shipping(&mut tx);
+            } else {
+                customer.billing_address.assign_shipping(&mut tx);
+            }
+        }
+
+        // Most online card payments are authenticated with 3-D Secure first.
+        // Cardholders who fail the challenge are declined.
+        if channel == Channel::Ecommerce
+            && tx.wallet.is_none()
+            && rng.gen_bool(profile.three_ds.rate)
+        {
+            let version = ThreeDsVersion::ALL[self.three_ds_version_index.sample(rng)];
+            let authentication = profile.three_ds.authenticate(rng, version, &tx.card_brand);
+            let failed = authentication.result == AuthenticationResult::Failed
+                && tx.status != TransactionStatus::Declined;
+            // Scripted payments that would fail go without 3-D Secure instead
+            if !(failed && scripted) {
+                authentication.assign(&mut tx);
+            }
+            if failed && !scripted {
+                tx.status = TransactionStatus::Declined;
+                tx.decline_reason = Some(DeclineReason::SuspiciousActivity);
+            }
+        }
+        fx::offer(rng, &mut tx, &profile.dcc);
+        verification::verify(rng, &mut tx);
+        tx.respond(rng);
+        settlement::settle(&mut tx);
+        tx
+    }
+}
+
+// What a purchase is made of before the rest of its details are filled in:
+// drawn from the profile, or scripted by a scenario
+pub struct Order<'c> {
+    pub customer: &'c Customer,
+    // Index of the merchant in the profile
+    pub merchant_index: usize,
+    pub channel: Channel,
+    pub currency: Text,
+    // In major units, rounded to the currency's minor units
+    pub amount: f64,
+    pub transaction_date: DateTime<Tz>,
+    pub status: TransactionStatus,
+    pub decline_reason: Option<DeclineReason>,
+}
+
+// Lazily generated sequence of transactions, so arbitrarily large datasets
+// can be written without holding them in memory
+pub struct TransactionStream<'a> {
+    generator: Generator<'a>,
+    rng: StdRng,
+    remaining: usize,
+}
+
+impl<'a> TransactionStream<'a> {
+    pub fn new(profile: &'a Profile, count: usize) -> Self {
+        Self::with_customers(profile, count, None)
+    }
+
+    // A stream drawing from the given pool of customers, as a daily feed
+    // does from the pool of its earlier runs
+    pub fn with_customers(
+        profile: &'a Profile,
+        count: usize,
+        customers: Option<Vec<Customer>>,
+    ) -> Self {
+        Self::with_rng(profile, count, customers, StdRng::from_entropy())
+    }
+
+    // A stream drawing every row and its customer pool from `rng`, so a
+    // seeded one repeats its rows
+    pub fn with_rng(
+        profile: &'a Profile,
+        count: usize,
+        customers: Option<Vec<Customer>>,
+        mut rng: StdRng,
+    ) -> Self {
+        Self {
+            generator: Generator::with_customers(profile, customers, &mut rng),
+            rng,
+            remaining: count,
+        }
+    }
+
+    pub fn generator(&self) -> &Generator<'a> {
+        &self.generator
+    }
+}
+
+impl Iterator for TransactionStream<'_> {
+    type Item = Transaction;
+
+    fn next(&mut self) -> Option<Transaction> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(self.generator.transaction(&mut self.rng))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for TransactionStream<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brand::builtin_brands;
+    use rand::{SeedableRng, rngs::StdRng};
+    use std::collections::HashSet;
+
+    // PANs drawn per brand and length
+    const PANS: usize = 2000;
+
+    // Check a PAN starts inside one of the brand's prefixes, has the
+    // length asked for and passes the Luhn check
+    fn assert_valid(pan: &str, brand: &CardBrand, length: usize) {
+        assert_eq!(pan.len(), length, "{} PAN {} has the wrong length", brand.name, pan);
+        assert!(pan.chars().all(|c| c.is_ascii_digit()), "{} isn't all digits", pan);
+        assert!(
+            brand
+                .prefix
+                .iter()
+                .filter_map(|prefix| IinRange::parse(prefix))
+                .any(|range| range.contains(pan)),
+            "{} PAN {} is outside the brand's prefixes",
+            brand.name,
+            pan
+        );
+        assert!(luhn::is_valid(pan), "{} PAN {} fails the Luhn check", brand.name, pan);
+    }
+
+    // Every built-in brand at each of its own lengths and at 13, 15, 16
+    // and 19 digits, as a profile may give it
+    #[test]
+    fn card_numbers_are_valid_for_every_brand_and_length() {
+        let mut rng = StdRng::seed_from_u64(759);
+        for brand in builtin_brands() {
+            let mut lengths = brand.lengths.clone();
+            lengths.extend([13, 15, 16, 19]);
+            lengths.sort_unstable();
+            lengths.dedup();
+            for length in lengths {
+                let brand = CardBrand {
+                    lengths: vec![length],
+                    ..brand.clone()
+                };
+                for _ in 0..PANS {
+                    let pan = generate_card_number(&mut rng, &brand, &[]);
+                    assert_valid(&pan, &brand, length);
+                }
+            }
+        }
+    }
+
+    // Card numbers issued in the built-in BINs stay valid and in the BIN
+    #[test]
+    fn card_numbers_in_bins_are_valid() {
+        let mut rng = StdRng::seed_from_u64(760);
+        let bins = bin::builtin_bins();
+        for brand in builtin_brands() {
+            let in_brand: Vec<&BinEntry> = bins
+                .iter()
+                .filter(|entry| {
+                    brand
+                        .prefix
+                        .iter()
+                        .filter_map(|prefix| IinRange::parse(prefix))
+                        .any(|range| range.contains(&entry.prefix))
+                })
+                .collect();
+            for &length in &brand.lengths {
+                let brand = CardBrand {
+                    lengths: vec![length],
+                    ..brand.clone()
+                };
+                for _ in 0..PANS {
+                    let pan = generate_card_number(&mut rng, &brand, &in_brand);
+                    assert_valid(&pan, &brand, length);
+                    assert!(
+                        in_brand.is_empty()
+                            || in_brand.iter().any(|entry| pan.starts_with(&entry.prefix)),
+                        "{} PAN {} isn't in one of the brand's BINs",
+                        brand.name,
+                        pan
+                    );
+                }
+            }
+        }
+    }
+
+    // A brand's cards don't repeat while its IINs have account numbers left
+    #[test]
+    fn card_numbers_do_not_repeat() {
+        let mut rng = StdRng::seed_from_u64(761);
+        for brand in builtin_brands() {
+            let mut seen = HashSet::new();
+            for _ in 0..PANS {
+                let pan = generate_card_number(&mut rng, &brand, &[]);
+                assert!(seen.insert(pan.clone()), "{} PAN {} repeated", brand.name, pan);
+            }
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use crate::text::{Text, TextBuf};
+use rand::{Rng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, net::Ipv6Addr};
+
+// Where transactions are made from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeoConfig {
+    // Share of genuine purchases made from an IP address in a country other
+    // than the billing country, as when travelling or behind a VPN
+    pub foreign_ip_rate: f64,
+    // Share of IP addresses that are IPv6 rather than IPv4
+    pub ipv6_share: f64,
+    // Share of genuine online purchases shipped to an address other than the
+    // billing address, such as gifts
+    pub shipping_mismatch_rate: f64,
+}
+
+impl Default for GeoConfig {
+    fn default() -> Self {
+        Self {
+            foreign_ip_rate: 0.02,
+            ipv6_share: 0.0,
+            shipping_mismatch_rate: 0.05,
+        }
+    }
+}
+
+// A country with the IPv4 blocks its addresses are drawn from
+#[derive(Debug)]
+pub struct Country {
+    // ISO 3166-1 alpha-2 code
+    pub code: &'static str,
+    pub region: &'static str,
+    // ISO 4217 code of the country's currency, which cards issued there are
+    // billed in
+    pub currency: &'static str,
+    // International calling code, and the leading digits of mobile numbers
+    // with the number of digits that follow them
+    calling_code: &'static str,
+    mobile_prefixes: &'static [&'static str],
+    mobile_digits: u32,
+    // Network address and prefix length of each IPv4 block
+    blocks: &'static [([u8; 4], u8)],
+    // Top 32 bits of the network address and prefix length of each IPv6
+    // block, all of them global unicast
+    ipv6_blocks: &'static [(u32, u8)],
+}
+
+impl Country {
+    // A random address in the country, IPv6 or IPv4
+    pub fn ip_address<R: Rng + ?Sized>(&self, rng: &mut R, ipv6: bool) -> Text {
+        if ipv6 {
+            self.ipv6_address(rng)
+        } else {
+            self.ipv4_address(rng)
+        }
+    }
+
+    // A random mobile number in the country in E.164 format. US and Canadian
+    // numbers are in the 555-01xx range and British ones in the 07700 900xxx
+    // range, which are set aside for fiction.
+    pub fn phone_number<R: Rng + ?Sized>(&self, rng: &mut R) -> Text {
+        let prefix = self.mobile_prefixes.choose(rng).unwrap();
+        let subscriber = rng.gen_range(0..10u64.pow(self.mobile_digits));
+        let mut number = TextBuf::new();
+        number.push('+');
+        number.push_str(self.calling_code);
+        number.push_str(prefix);
+        number.push_digits(subscriber, self.mobile_digits as usize);
+        number.into()
+    }
+
+    // A random host address from one of the country's IPv4 blocks
+    fn ipv4_address<R: Rng + ?Sized>(&self, rng: &mut R) -> Text {
+        let (network, prefix) = *self.blocks.choose(rng).unwrap();
+        let host_bits = 32 - prefix as u32;
+        let base = u32::from_be_bytes(network);
+        // Skip addresses ending in .0 and .255, which look like network and
+        // broadcast addresses
+        let address = loop {
+            let address = base | rng.gen_range(0..1u32 << host_bits);
+            if !matches!(address & 0xFF, 0 | 255) {
+                break address;
+            }
+        };
+        let mut text = TextBuf::new();
+        for (i, byte) in address.to_be_bytes().into_iter().enumerate() {
+            if i > 0 {
+                text.push('.');
+            }
+            text.push_digits(byte.into(), 1);
+        }
+        text.into()
+    }
+
+    // A random address from one of the country's IPv6 blocks, in its
+    // compressed text form
+    fn ipv6_address<R: Rng + ?Sized>(&self, rng: &mut R) -> Text {
+        let (network, prefix) = *self.ipv6_blocks.choose(rng).unwrap();
+        let network = (network as u128) << 96;
+        let host_mask = u128::MAX >> prefix;
+        // A zero interface identifier would be the subnet-router anycast
+        // address
+        let address = loop {
+            let address = network | (rng.r#gen::<u128>() & host_mask);
+            if address as u64 != 0 {
+                break address;
+            }
+        };
+        let mut text = TextBuf::new();
+        let _ = write!(text, "{}", Ipv6Addr::from(address));
+        text.into()
+    }
+}
+
+// Countries addresses are generated in. The blocks are ranges allocated to
+// large consumer ISPs and mobile carriers of each country, so addresses look local
+// to geolocation lookups, though any single address may be unassigned.
+pub const COUNTRIES: &[Country] = &[
+    Country {
+        code: "US",
+        region: "North America",
+        currency: "USD",
+        calling_code: "1",
+        mobile_prefixes: &["21255501", "31255501", "41555501", "61755501", "71355501"],
+        mobile_digits: 2,
+        blocks: &[
+            ([24, 0, 0, 0], 12),
+            ([68, 32, 0, 0], 11),
+            ([73, 0, 0, 0], 8),
+            ([98, 192, 0, 0], 10),
+        ],
+        ipv6_blocks: &[(0x2601_0000, 20), (0x2600_1000, 24)],
+    },
+    Country {
+        code: "CA",
+        region: "North America",
+        currency: "CAD",
+        calling_code: "1",
+        mobile_prefixes: &["41655501", "60455501", "51455501", "40355501", "61355501"],
+        mobile_digits: 2,
+        blocks: &[
+            ([70, 24, 0, 0], 13),
+            ([99, 224, 0, 0], 11),
+            ([142, 112, 0, 0], 12),
+        ],
+        ipv6_blocks: &[(0x2607_fea8, 32), (0x2001_056a, 32)],
+    },
+    Country {
+        code: "MX",
+        region: "Latin America",
+        currency: "MXN",
+        calling_code: "52",
+        mobile_prefixes: &["55", "33", "81"],
+        mobile_digits: 8,
+        blocks: &[([187, 128, 0, 0], 10), ([189, 128, 0, 0], 11)],
+        ipv6_blocks: &[(0x2806_02f0, 32), (0x2806_1000, 24)],
+    },
+    Country {
+        code: "BR",
+        region: "Latin America",
+        currency: "BRL",
+        calling_code: "55",
+        mobile_prefixes: &["119", "219", "319", "419", "519", "619", "719", "819"],
+        mobile_digits: 8,
+        blocks: &[
+            ([177, 0, 0, 0], 11),
+            ([179, 96, 0, 0], 11),
+            ([189, 0, 0, 0], 11),
+        ],
+        ipv6_blocks: &[(0x2804_014c, 32), (0x2804_07f0, 32)],
+    },
+    Country {
+        code: "GB",
+        region: "Europe",
+        currency: "GBP",
+        calling_code: "44",
+        mobile_prefixes: &["7700900"],
+        mobile_digits: 3,
+        blocks: &[
+            ([81, 128, 0, 0], 11),
+            ([86, 128, 0, 0], 10),
+            ([109, 144, 0, 0], 12),
+        ],
+        ipv6_blocks: &[(0x2a00_23c0, 28), (0x2a02_0c7c, 32)],
+    },
+    Country {
+        code: "IE",
+        region: "Europe",
+        currency: "EUR",
+        calling_code: "353",
+        mobile_prefixes: &["83", "85", "86", "87", "89"],
+        mobile_digits: 7,
+        blocks: &[([86, 40, 0, 0], 13), ([89, 100, 0, 0], 14)],
+        ipv6_blocks: &[(0x2a02_8084, 32), (0x2001_0bb6, 32)],
+    },
+    Country {
+        code: "FR",
+        region: "Europe",
+        currency: "EUR",
+        calling_code: "33",
+        mobile_prefixes: &["6", "7"],
+        mobile_digits: 8,
+        blocks: &[
+            ([78, 192, 0, 0], 10),
+            ([86, 192, 0, 0], 10),
+            ([90, 0, 0, 0], 9),
+        ],
+        ipv6_blocks: &[(0x2a01_cb00, 24), (0x2a01_0e00, 26)],
+    },
+    Country {
+        code: "DE",
+        region: "Europe",
+        currency: "EUR",
+        calling_code: "49",
+        mobile_prefixes: &["151", "152", "157", "159", "176", "177", "178", "179"],
+        mobile_digits: 8,
+        blocks: &[
+            ([84, 128, 0, 0], 10),
+            ([91, 0, 0, 0], 10),
+            ([93, 192, 0, 0], 10),
+        ],
+        ipv6_blocks: &[(0x2003_0000, 19), (0x2a02_8100, 27)],
+    },
+    Country {
+        code: "NL",
+        region: "Europe",
+        currency: "EUR",
+        calling_code: "31",
+        mobile_prefixes: &["6"],
+        mobile_digits: 8,
+        blocks: &[([77, 160, 0, 0], 12), ([84, 80, 0, 0], 12)],
+        ipv6_blocks: &[(0x2001_1c00, 24), (0x2a02_a440, 30)],
+    },
+    Country {
+        code: "BE",
+        region: "Europe",
+        currency: "EUR",
+        calling_code: "32",
+        mobile_prefixes: &["47", "48", "49"],
+        mobile_digits: 7,
+        blocks: &[([81, 240, 0, 0], 12), ([91, 176, 0, 0], 12)],
+        ipv6_blocks: &[(0x2a02_a03f, 32), (0x2a02_1810, 28)],
+    },
+    Country {
+        code: "ES",
+        region: "Europe",
+        currency: "EUR",
+        calling_code: "34",
+        mobile_prefixes: &["6", "7"],
+        mobile_digits: 8,
+        blocks: &[([83, 32, 0, 0], 11), ([88, 0, 0, 0], 11)],
+        ipv6_blocks: &[(0x2a0c_5a80, 29), (0x2a01_c500, 24)],
+    },
+    Country {
+        code: "LT",
+        region: "Europe",
+        currency: "EUR",
+        calling_code: "370",
+        mobile_prefixes: &["6"],
+        mobile_digits: 7,
+        blocks: &[([78, 56, 0, 0], 13), ([88, 118, 0, 0], 15)],
+        ipv6_blocks: &[(0x2001_07f8, 32), (0x2a02_0ae8, 32)],
+    },
+    Country {
+        code: "RU",
+        region: "Europe",
+        currency: "RUB",
+        calling_code: "7",
+        mobile_prefixes: &["9"],
+        mobile_digits: 9,
+        blocks: &[([95, 24, 0, 0], 13), ([178, 64, 0, 0], 10)],
+        ipv6_blocks: &[(0x2a00_1fa0, 29), (0x2a03_d000, 26)],
+    },
+    Country {
+        code: "NG",
+        region: "Africa",
+        currency: "NGN",
+        calling_code: "234",
+        mobile_prefixes: &["70", "80", "81", "90", "91"],
+        mobile_digits: 8,
+        blocks: &[([105, 112, 0, 0], 12), ([197, 210, 0, 0], 15)],
+        ipv6_blocks: &[(0x2c0f_f738, 32), (0x2c0f_2a80, 32)],
+    },
+    Country {
+        code: "ZA",
+        region: "Africa",
+        currency: "ZAR",
+        calling_code: "27",
+        mobile_prefixes: &["71", "72", "73", "74", "76", "78", "79", "82", "83", "84"],
+        mobile_digits: 7,
+        blocks: &[([41, 0, 0, 0], 11), ([105, 224, 0, 0], 11)],
+        ipv6_blocks: &[(0x2c0f_f720, 32), (0x2001_4200, 32)],
+    },
+    Country {
+        code: "IN",
+        region: "Asia",
+        currency: "INR",
+        calling_code: "91",
+        mobile_prefixes: &["6", "7", "8", "9"],
+        mobile_digits: 9,
+        blocks: &[
+            ([49, 32, 0, 0], 11),
+            ([117, 192, 0, 0], 10),
+            ([182, 64, 0, 0], 12),
+        ],
+        ipv6_blocks: &[(0x2405_0201, 32), (0x2401_4900, 32)],
+    },
+    Country {
+        code: "CN",
+        region: "Asia",
+        currency: "CNY",
+        calling_code: "86",
+        mobile_prefixes: &["13", "15", "18"],
+        mobile_digits: 9,
+        blocks: &[
+            ([36, 96, 0, 0], 11),
+            ([112, 0, 0, 0], 10),
+            ([223, 64, 0, 0], 11),
+        ],
+        ipv6_blocks: &[(0x240e_0000, 20), (0x2409_8000, 20), (0x2408_8000, 20)],
+    },
+    Country {
+        code: "JP",
+        region: "Asia",
+        currency: "JPY",
+        calling_code: "81",
+        mobile_prefixes: &["70", "80", "90"],
+        mobile_digits: 8,
+        blocks: &[([126, 0, 0, 0], 8), ([153, 128, 0, 0], 9)],
+        ipv6_blocks: &[(0x240b_0010, 28), (0x2400_4050, 32)],
+    },
+    Country {
+        code: "SG",
+        region: "Asia",
+        currency: "SGD",
+        calling_code: "65",
+        mobile_prefixes: &["8", "9"],
+        mobile_digits: 7,
+        blocks: &[([116, 86, 0, 0], 15), ([175, 156, 0, 0], 15)],
+        ipv6_blocks: &[(0x2406_3003, 32), (0x2001_0d08, 32)],
+    },
+    Country {
+        code: "AU",
+        region: "Oceania",
+        currency: "AUD",
+        calling_code: "61",
+        mobile_prefixes: &["4"],
+        mobile_digits: 8,
+        blocks: &[
+            ([1, 120, 0, 0], 13),
+            ([49, 176, 0, 0], 12),
+            ([101, 160, 0, 0], 11),
+        ],
+        ipv6_blocks: &[(0x2001_8003, 32), (0x2001_4479, 32)],
+    },
+];
+
+// The country with the given ISO 3166-1 alpha-2 code
+pub fn country(code: &str) -> Option<&'static Country> {
+    COUNTRIES.iter().find(|country| country.code == code)
+}
+
+// A random country
+pub fn random_country<R: Rng + ?Sized>(rng: &mut R) -> &'static Country {
+    COUNTRIES.choose(rng).unwrap()
+}
+
+// A random country other than the given one
+pub fn foreign_country<R: Rng + ?Sized>(rng: &mut R, home: &Country) -> &'static Country {
+    let others: Vec<&Country> = COUNTRIES.iter().filter(|c| c.code != home.code).collect();
+    others.choose(rng).unwrap()
+}
+
+// A random country in another region than the given one, too far away to
+// reach within hours
+pub fn distant_country<R: Rng + ?Sized>(rng: &mut R, home: &Country) -> &'static Country {
+    let others: Vec<&Country> = COUNTRIES
+        .iter()
+        .filter(|c| c.region != home.region)
+        .collect();
+    others.choose(rng).unwrap()
+}
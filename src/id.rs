@@ -0,0 +1,171 @@
+use crate::text::{Text, TextBuf};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::{
+    Once,
+    atomic::{AtomicU64, Ordering},
+};
+
+// Format of generated transaction IDs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum IdStrategy {
+    // `TXN` followed by 9 base-36 characters
+    #[default]
+    Txn,
+    // Random UUID, version 4
+    #[serde(alias = "uuidv4")]
+    #[value(alias = "uuidv4")]
+    Uuid4,
+    // UUID version 7, ordered by the transaction's timestamp
+    #[serde(alias = "uuidv7")]
+    #[value(alias = "uuidv7")]
+    Uuid7,
+    // ULID, ordered by the transaction's timestamp
+    Ulid,
+}
+
+// Number of rows that got an ID so far in this run. Every strategy encodes
+// the sequence number losslessly, so IDs never repeat within a run.
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+// Random per-run value the sequence is offset by, so separate runs don't
+// produce the same IDs. It's drawn once, unless a run sets its own.
+static RUN_KEY: AtomicU64 = AtomicU64::new(0);
+static KEYED: Once = Once::new();
+
+fn run_key() -> u64 {
+    KEYED.call_once(|| RUN_KEY.store(rand::thread_rng().r#gen(), Ordering::Relaxed));
+    RUN_KEY.load(Ordering::Relaxed)
+}
+
+// The run key and the number of rows given an ID so far, which checkpoints
+// save so a resumed run carries on where it stopped
+pub fn sequence() -> (u64, u64) {
+    (run_key(), SEQUENCE.load(Ordering::Relaxed))
+}
+
+// Carry on the IDs of an earlier run, or number them as a seeded run does
+pub fn restore_sequence(key: u64, sequence: u64) {
+    KEYED.call_once(|| {});
+    RUN_KEY.store(key, Ordering::Relaxed);
+    SEQUENCE.store(sequence, Ordering::Relaxed);
+}
+
+// Number of distinct `TXN` suffixes: 9 characters from a 36-character set
+const TXN_SPACE: u64 = 36u64.pow(9);
+const TXN_CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+// Width of the sequence counter in time-ordered IDs, enough for trillions of
+// rows per run
+const COUNTER_BITS: u32 = 42;
+
+const CROCKFORD: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+impl IdStrategy {
+    // Generate the ID of a transaction made at the given Unix time in
+    // milliseconds, which time-ordered strategies embed
+    pub fn generate<R: Rng + ?Sized>(&self, rng: &mut R, timestamp_millis: i64) -> Text {
+        let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        match self {
+            // The sequence is scrambled within the suffix space, so IDs still
+            // look random while each one is distinct
+            IdStrategy::Txn => {
+                let mut n = scramble(sequence, TXN_SPACE, 0);
+                let mut suffix = [0u8; 9];
+                for c in suffix.iter_mut().rev() {
+                    *c = TXN_CHARSET[(n % 36) as usize];
+                    n /= 36;
+                }
+                let mut id = TextBuf::new();
+                id.push_str("TXN");
+                id.push_str(std::str::from_utf8(&suffix).expect("the charset is ASCII"));
+                id.into()
+            }
+            // 58 random bits around the version nibble, and the scrambled
+            // sequence in the 62 bits after the variant
+            IdStrategy::Uuid4 => {
+                let high = (rng.r#gen::<u64>() & !0xF000) | 0x4000;
+                let low = mix(sequence ^ run_key(), 62) | 0x8000_0000_0000_0000;
+                uuid(high, low).into()
+            }
+            // 48-bit timestamp, the version, then a 42-bit counter spanning
+            // rand_a and the top of rand_b (RFC 9562 method 1), so IDs sort by
+            // time and then by generation order
+            IdStrategy::Uuid7 => {
+                let counter = counter(sequence);
+                let high = (timestamp(timestamp_millis) << 16) | 0x7000 | (counter >> 30);
+                let low = 0x8000_0000_0000_0000
+                    | ((counter & 0x3FFF_FFFF) << 32)
+                    | rng.r#gen::<u32>() as u64;
+                uuid(high, low).into()
+            }
+            // 48-bit timestamp, then a 42-bit counter and 38 random bits in
+            // place of the 80 random bits, in Crockford base 32
+            IdStrategy::Ulid => {
+                let value = ((timestamp(timestamp_millis) as u128) << 80)
+                    | ((counter(sequence) as u128) << 38)
+                    | (rng.r#gen::<u64>() as u128 & ((1 << 38) - 1));
+                let mut id = TextBuf::new();
+                for i in (0..26).rev() {
+                    id.push(CROCKFORD[(value >> (i * 5)) as usize & 0x1F] as char);
+                }
+                id.into()
+            }
+        }
+    }
+}
+
+// Unix time in milliseconds, clamped to the 48 bits time-ordered IDs hold
+fn timestamp(millis: i64) -> u64 {
+    (millis.max(0) as u64).min((1 << 48) - 1)
+}
+
+// Counter of a time-ordered ID: the sequence from a random start below
+// 2^41, so it can't wrap around within a run
+fn counter(sequence: u64) -> u64 {
+    let start = run_key() & ((1 << (COUNTER_BITS - 1)) - 1);
+    (start + sequence) & ((1 << COUNTER_BITS) - 1)
+}
+
+// Map a sequence number to a random-looking number below `space`, different
+// for every sequence number below `space` and repeating after that. `salt`
+// keeps sequences scrambled into the same space apart.
+pub(crate) fn scramble(sequence: u64, space: u64, salt: u64) -> u64 {
+    let bits = (64 - (space - 1).leading_zeros()).max(1);
+    let start = (run_key() ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15)) % space;
+    let mut n = (start + sequence % space) % space;
+    // Walking the permutation of all `bits`-bit numbers until it lands back
+    // below `space` keeps it a permutation of the numbers below `space`
+    loop {
+        n = mix(n, bits);
+        if n < space {
+            return n;
+        }
+    }
+}
+
+// Scramble a value of the given bit width with a bijection, so distinct
+// inputs always give distinct outputs
+fn mix(mut x: u64, bits: u32) -> u64 {
+    let mask = (1 << bits) - 1;
+    let shift = bits.div_ceil(2);
+    x &= mask;
+    for multiplier in [0x9E37_79B9_7F4A_7C15u64, 0xBF58_476D_1CE4_E5B9] {
+        x ^= x >> shift;
+        x = x.wrapping_mul(multiplier) & mask;
+    }
+    x ^ (x >> shift)
+}
+
+// Hyphenated hex form of a UUID from its two 64-bit halves
+fn uuid(high: u64, low: u64) -> String {
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xFFFF,
+        high & 0xFFFF,
+        low >> 48,
+        low & 0xFFFF_FFFF_FFFF
+    )
+}
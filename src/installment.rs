@@ -0,0 +1,89 @@
+use crate::{
+    fx,
+    id::IdStrategy,
+    model::{Transaction, TransactionStatus},
+    money::Money,
+    settlement, temporal,
+};
+use chrono::{DateTime, Months};
+use chrono_tz::Tz;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Plans that purchases are paid in monthly installments with, as is common
+// with cards issued in Latin America. Plans are only generated when `rate`
+// is above 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstallmentConfig {
+    // Share of purchases paid in installments
+    pub rate: f64,
+    // Numbers of installments that plans are offered with, picked at random
+    pub counts: Vec<u32>,
+}
+
+impl Default for InstallmentConfig {
+    fn default() -> Self {
+        Self {
+            rate: 0.0,
+            counts: vec![3, 6, 10, 12],
+        }
+    }
+}
+
+// Turn a purchase into the first of `count` monthly installments, returning
+// the later ones up to `end`. The purchase is authorized once, so later
+// installments keep its authorization and only settle separately, each in
+// the merchant's batch of its day. The amount is split evenly in minor
+// units, with the first installment taking the remainder, so the plan adds
+// up to the purchase amount. Purchases that weren't approved end at the
+// first installment.
+pub fn plan<R: Rng + ?Sized>(
+    rng: &mut R,
+    purchase: &mut Transaction,
+    count: u32,
+    end: &DateTime<Tz>,
+    ids: IdStrategy,
+) -> Vec<Transaction> {
+    let total = Money::from_major(purchase.amount, &purchase.currency);
+    let share = Money {
+        minor_units: total.minor_units / count as i64,
+        ..total
+    };
+    let first = Money {
+        minor_units: total.minor_units - share.minor_units * (count as i64 - 1),
+        ..total
+    };
+    purchase.amount = first.to_major();
+    fx::rebill(purchase);
+    purchase.installment_number = Some(1);
+    purchase.installment_count = Some(count);
+    if purchase.status != TransactionStatus::Approved {
+        return Vec::new();
+    }
+
+    let start = DateTime::parse_from_rfc3339(&purchase.transaction_date)
+        .expect("generated dates are RFC 3339")
+        .with_timezone(&end.timezone());
+    let mut installments = Vec::new();
+    for number in 2..=count {
+        let Some(due) = start.checked_add_months(Months::new(number - 1)) else {
+            break;
+        };
+        if due > *end {
+            break;
+        }
+        let mut installment = Transaction {
+            transaction_id: ids.generate(rng, due.timestamp_millis()),
+            transaction_date: temporal::rfc3339(&due),
+            original_transaction_id: Some(purchase.transaction_id.clone()),
+            installment_number: Some(number),
+            amount: share.to_major(),
+            ..purchase.clone()
+        };
+        fx::rebill(&mut installment);
+        settlement::settle(&mut installment);
+        installments.push(installment);
+    }
+    installments
+}
@@ -0,0 +1,61 @@
+pub mod address;
+pub mod amount;
+pub mod anonymize;
+pub mod audit;
+pub mod bin;
+pub mod brand;
+pub mod checkpoint;
+pub mod config;
+pub mod conformance;
+pub mod currency;
+pub mod custom;
+pub mod daily;
+pub mod descriptor;
+pub mod device;
+pub mod dirty;
+pub mod dispute;
+pub mod drift;
+pub mod emv;
+pub mod fit;
+pub mod fixtures;
+pub mod fraud;
+pub mod fx;
+pub mod generator;
+pub mod geo;
+pub mod id;
+pub mod installment;
+pub mod lifecycle;
+pub mod live;
+pub mod loader;
+pub mod logging;
+pub mod luhn;
+pub mod manifest;
+pub mod masking;
+pub mod mcc;
+pub mod metrics;
+pub mod ml;
+pub mod model;
+pub mod money;
+pub mod names;
+pub mod nulls;
+pub mod output;
+pub mod persona;
+pub mod plan;
+pub mod preauth;
+pub mod report;
+pub mod scd;
+pub mod scenario;
+pub mod seed;
+pub mod sequence;
+pub mod serve;
+pub mod settlement;
+pub mod subscription;
+pub mod temporal;
+pub mod text;
+pub mod three_ds;
+pub mod throttle;
+pub mod track;
+pub mod user_agent;
+pub mod verification;
+pub mod wallet;
+pub mod webhook;
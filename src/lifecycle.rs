@@ -0,0 +1,65 @@
+use crate::{
+    model::{LifecycleEvent, Transaction, TransactionStatus},
+    preauth::PreauthStep,
+    temporal,
+};
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+
+// Expand a transaction into the chain of events a payment goes through, all
+// sharing its transaction ID:
+// - approved: authorization, capture, clearing, settlement
+// - declined/pending: the authorization only
+// - refunded: the refund, then its clearing and settlement
+// Holds of pre-authorized orders are only authorized, and their captures
+// go on from the capture.
+// Events that would fall after the current time haven't happened yet and are left out.
+pub fn events<R: Rng + ?Sized>(rng: &mut R, tx: &Transaction) -> Vec<Transaction> {
+    // Events keep the UTC offset of the transaction they expand
+    let start =
+        DateTime::parse_from_rfc3339(&tx.transaction_date).expect("generated dates are RFC 3339");
+
+    // Each stage follows the previous one after a realistic delay
+    let capture = Duration::minutes(rng.gen_range(1..=24 * 60));
+    let clearing = Duration::hours(rng.gen_range(12..=36));
+    let settlement = Duration::hours(rng.gen_range(24..=72));
+
+    let stages = match tx.status {
+        _ if tx.is_hold() => vec![(LifecycleEvent::Authorization, Duration::zero())],
+        TransactionStatus::Approved if tx.preauth_step == Some(PreauthStep::Capture) => vec![
+            (LifecycleEvent::Capture, Duration::zero()),
+            (LifecycleEvent::Clearing, clearing),
+            (LifecycleEvent::Settlement, settlement),
+        ],
+        TransactionStatus::Approved => vec![
+            (LifecycleEvent::Authorization, Duration::zero()),
+            (LifecycleEvent::Capture, capture),
+            (LifecycleEvent::Clearing, clearing),
+            (LifecycleEvent::Settlement, settlement),
+        ],
+        TransactionStatus::Declined | TransactionStatus::Pending => {
+            vec![(LifecycleEvent::Authorization, Duration::zero())]
+        }
+        TransactionStatus::Refunded => vec![
+            (LifecycleEvent::Refund, Duration::zero()),
+            (LifecycleEvent::Clearing, clearing),
+            (LifecycleEvent::Settlement, settlement),
+        ],
+    };
+
+    let now = Utc::now();
+    let mut at = start;
+    let mut events = Vec::with_capacity(stages.len());
+    for (event, delay) in stages {
+        at += delay;
+        if at > now {
+            break;
+        }
+        events.push(Transaction {
+            event_type: Some(event),
+            transaction_date: temporal::rfc3339(&at),
+            ..tx.clone()
+        });
+    }
+    events
+}
@@ -0,0 +1,67 @@
+use crate::{model::Transaction, throttle::Throttle};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+// Rows of a live run, each let out once its time has come and no faster
+// than the throttle allows, if it's given one, until the run ends. Purchases and refunds are
+// stamped with the time they're generated at, but renewals, installments,
+// later steps of pre-authorizations and the rest of a fraud burst are due
+// later, so they're held until then, and dropped if that's after the end.
+pub struct LiveStream<I> {
+    inner: I,
+    throttle: Option<Throttle>,
+    end: DateTime<Utc>,
+    // Rows due later, by their time and the order they came in
+    held: BTreeMap<(DateTime<Utc>, u64), Transaction>,
+    arrived: u64,
+}
+
+impl<I: Iterator<Item = Transaction>> LiveStream<I> {
+    pub fn new(inner: I, end: DateTime<Utc>) -> Self {
+        Self {
+            inner,
+            throttle: None,
+            end,
+            held: BTreeMap::new(),
+            arrived: 0,
+        }
+    }
+
+    pub fn with_throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+}
+
+impl<I: Iterator<Item = Transaction>> Iterator for LiveStream<I> {
+    type Item = Transaction;
+
+    fn next(&mut self) -> Option<Transaction> {
+        if let Some(throttle) = self.throttle.as_mut() {
+            throttle.wait();
+        }
+        loop {
+            let now = Utc::now();
+            if now >= self.end {
+                return None;
+            }
+            if let Some(entry) = self.held.first_entry()
+                && entry.key().0 <= now
+            {
+                return Some(entry.remove());
+            }
+            let tx = self.inner.next()?;
+            let Ok(at) = DateTime::parse_from_rfc3339(&tx.transaction_date) else {
+                return Some(tx);
+            };
+            let at = at.with_timezone(&Utc);
+            if at <= now {
+                return Some(tx);
+            }
+            if at < self.end {
+                self.held.insert((at, self.arrived), tx);
+                self.arrived += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{
+    bin::{BinEntry, CardType},
+    config::Merchant,
+};
+use serde::Deserialize;
+use std::{io, path::Path};
+
+// Load merchants from a CSV file with `name`, `id`, `category` and `mcc`
+// columns, and an optional `descriptor` column. `name` and `id` are
+// required; each merchant needs a category, a known MCC, or both.
+pub fn load_merchants(path: &Path) -> io::Result<Vec<Merchant>> {
+    let mut reader = open(path, &["name", "id"])?;
+    let mut merchants = Vec::new();
+    for (row, record) in reader.deserialize::<Merchant>().enumerate() {
+        let mut merchant = record.map_err(|e| invalid(path, e))?;
+        merchant.fill_category();
+        if merchant.name.is_empty() || merchant.id.is_empty() {
+            return Err(invalid(
+                path,
+                format!("row {} is missing a merchant name or id", row + 2),
+            ));
+        }
+        merchants.push(merchant);
+    }
+    Ok(merchants)
+}
+
+// Load first and last names from a CSV file with `first_name` and
+// `last_name` columns. Empty cells are skipped, so the two lists can have
+// different lengths.
+pub fn load_names(path: &Path) -> io::Result<(Vec<String>, Vec<String>)> {
+    let mut reader = open(path, &["first_name", "last_name"])?;
+    let headers = reader.headers().map_err(|e| invalid(path, e))?.clone();
+    let first = headers.iter().position(|h| h == "first_name").unwrap();
+    let last = headers.iter().position(|h| h == "last_name").unwrap();
+
+    let mut first_names = Vec::new();
+    let mut last_names = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| invalid(path, e))?;
+        for (column, names) in [(first, &mut first_names), (last, &mut last_names)] {
+            match record.get(column).map(str::trim) {
+                Some(name) if !name.is_empty() => names.push(name.to_string()),
+                _ => {}
+            }
+        }
+    }
+    Ok((first_names, last_names))
+}
+
+// Load a BIN table from a CSV file with `prefix`, `issuer`, `country` and
+// `card_type` columns, and an optional `prepaid` column where empty cells
+// mean false
+pub fn load_bins(path: &Path) -> io::Result<Vec<BinEntry>> {
+    #[derive(Deserialize)]
+    struct Row {
+        prefix: String,
+        issuer: String,
+        country: String,
+        card_type: CardType,
+        prepaid: Option<bool>,
+    }
+
+    let mut reader = open(path, &["prefix", "issuer", "country", "card_type"])?;
+    let mut bins = Vec::new();
+    for record in reader.deserialize::<Row>() {
+        let row = record.map_err(|e| invalid(path, e))?;
+        bins.push(BinEntry {
+            prefix: row.prefix,
+            issuer: row.issuer.into(),
+            country: row.country.into(),
+            card_type: row.card_type,
+            prepaid: row.prepaid.unwrap_or(false),
+        });
+    }
+    Ok(bins)
+}
+
+// Open a CSV file and check that its header has the required columns
+pub(crate) fn open(path: &Path, required: &[&str]) -> io::Result<csv::Reader<std::fs::File>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| invalid(path, e))?;
+    let headers = reader.headers().map_err(|e| invalid(path, e))?;
+    let missing: Vec<&str> = required
+        .iter()
+        .copied()
+        .filter(|column| !headers.iter().any(|h| h == *column))
+        .collect();
+    if !missing.is_empty() {
+        return Err(invalid(
+            path,
+            format!("missing required column(s): {}", missing.join(", ")),
+        ));
+    }
+    Ok(reader)
+}
+
+pub(crate) fn invalid(path: &Path, error: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), error),
+    )
+}
@@ -0,0 +1,266 @@
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    io::{self, Write},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+use tracing::{
+    Event, Level, Metadata, Subscriber,
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    span,
+    subscriber::{Interest, SetGlobalDefaultError},
+};
+
+// How log lines are written to stderr
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    // Plain messages, with levels, times and spans once verbose
+    #[default]
+    Text,
+    // One JSON object per line, for CI and log collectors
+    Json,
+}
+
+// Install the logger for the rest of the run. Debug and trace events only
+// come from this crate, as dependencies can be chatty at those levels.
+pub fn init(format: LogFormat, level: LevelFilter) -> Result<(), SetGlobalDefaultError> {
+    tracing::subscriber::set_global_default(Logger {
+        format,
+        level,
+        spans: Mutex::new(HashMap::new()),
+        next_id: AtomicU64::new(1),
+    })
+}
+
+// The fields of a span or event, in the order they were recorded, with the
+// message of an event kept apart
+#[derive(Debug, Default)]
+struct Fields {
+    message: Option<String>,
+    values: Vec<(&'static str, Value)>,
+}
+
+impl Fields {
+    fn put(&mut self, field: &Field, value: Value) {
+        if field.name() == "message" {
+            self.message = Some(match value {
+                Value::String(message) => message,
+                value => value.to_string(),
+            });
+        } else {
+            self.values.push((field.name(), value));
+        }
+    }
+
+    // Fields as `key=value` pairs, after a space
+    fn text(&self) -> String {
+        self.values
+            .iter()
+            .map(|(name, value)| match value.as_str() {
+                Some(value) => format!(" {}={}", name, value),
+                None => format!(" {}={}", name, value),
+            })
+            .collect()
+    }
+
+    fn json(&self) -> Map<String, Value> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.put(field, Value::String(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.put(field, Value::String(value.to_string()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.put(field, json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.put(field, json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.put(field, json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.put(field, json!(value));
+    }
+}
+
+// A span that is open, with the handles still held on it
+struct SpanData {
+    name: &'static str,
+    fields: Fields,
+    handles: usize,
+}
+
+thread_local! {
+    // Spans entered on this thread, innermost last
+    static ENTERED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Logger {
+    format: LogFormat,
+    level: LevelFilter,
+    spans: Mutex<HashMap<u64, SpanData>>,
+    next_id: AtomicU64,
+}
+
+impl Logger {
+    fn line(&self, event: &Event<'_>, fields: &Fields) -> String {
+        let spans = self.spans.lock().unwrap();
+        let entered: Vec<&SpanData> = ENTERED.with(|entered| {
+            entered
+                .borrow()
+                .iter()
+                .filter_map(|id| spans.get(id))
+                .collect::<Vec<_>>()
+        });
+        let level = *event.metadata().level();
+        let message = fields.message.as_deref().unwrap_or_default();
+        match self.format {
+            LogFormat::Json => {
+                let mut line = Map::new();
+                let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+                line.insert("timestamp".into(), timestamp.into());
+                line.insert("level".into(), level.as_str().into());
+                line.insert("target".into(), event.metadata().target().into());
+                line.insert("message".into(), message.into());
+                line.insert("fields".into(), fields.json().into());
+                let entered = entered
+                    .iter()
+                    .map(|span| {
+                        let mut fields = Map::new();
+                        fields.insert("name".into(), span.name.into());
+                        fields.extend(span.fields.json());
+                        Value::Object(fields)
+                    })
+                    .collect();
+                line.insert("spans".into(), Value::Array(entered));
+                Value::Object(line).to_string()
+            }
+            // Plain messages until the run is verbose, apart from warnings
+            // and errors being marked as such
+            LogFormat::Text if self.level < LevelFilter::DEBUG => match level {
+                Level::INFO => format!("{}{}", message, fields.text()),
+                level => format!("{} {}{}", level, message, fields.text()),
+            },
+            LogFormat::Text => {
+                let spans: String = entered
+                    .iter()
+                    .map(|span| format!("{}{{{}}}: ", span.name, span.fields.text().trim_start()))
+                    .collect();
+                format!(
+                    "{} {:>5} {}{}{}",
+                    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                    level,
+                    spans,
+                    message,
+                    fields.text()
+                )
+            }
+        }
+    }
+}
+
+impl Subscriber for Logger {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        if self.enabled(metadata) {
+            Interest::always()
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= self.level
+            && (*metadata.level() <= Level::INFO || metadata.target().starts_with("luhnsynth"))
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.level)
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut fields = Fields::default();
+        span.record(&mut fields);
+        let data = SpanData {
+            name: span.metadata().name(),
+            fields,
+            handles: 1,
+        };
+        self.spans.lock().unwrap().insert(id, data);
+        span::Id::from_u64(id)
+    }
+
+    fn record(&self, span: &span::Id, values: &span::Record<'_>) {
+        if let Some(data) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            values.record(&mut data.fields);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let line = self.line(event, &fields);
+        // Nowhere is left to report a failure to write to stderr
+        let _ = writeln!(io::stderr().lock(), "{}", line);
+    }
+
+    fn enter(&self, span: &span::Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &span::Id) {
+        ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(at) = entered.iter().rposition(|id| *id == span.into_u64()) {
+                entered.remove(at);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &span::Id) -> span::Id {
+        if let Some(data) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            data.handles += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: span::Id) -> bool {
+        let mut spans = self.spans.lock().unwrap();
+        let id = span.into_u64();
+        match spans.get_mut(&id) {
+            Some(data) if data.handles > 1 => {
+                data.handles -= 1;
+                false
+            }
+            Some(_) => {
+                spans.remove(&id);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,138 @@
+// Luhn (mod 10) checksum helpers for validating and completing PANs.
+// Spaces and dashes are ignored so formatted numbers like "4111 1111 1111 1111"
+// can be passed straight in.
+
+// Luhn sum of a PAN's digits read right to left, doubling every other digit
+// starting with the first when `double_first` is set, along with the number
+// of digits. None if the PAN holds anything but digits and separators.
+fn luhn_sum(pan: &str, double_first: bool) -> Option<(u32, usize)> {
+    let mut sum = 0;
+    let mut count = 0;
+    for byte in pan.bytes().rev().filter(|b| *b != b' ' && *b != b'-') {
+        if !byte.is_ascii_digit() {
+            return None;
+        }
+        let digit = u32::from(byte - b'0');
+        sum += if (count % 2 == 0) == double_first {
+            let doubled = digit * 2;
+            if doubled > 9 { doubled - 9 } else { doubled }
+        } else {
+            digit
+        };
+        count += 1;
+    }
+    Some((sum, count))
+}
+
+// Luhn checksum of a complete PAN (check digit included): 0 for a valid number.
+// Numbers containing characters other than digits, spaces and dashes return 10,
+// which no valid sum can produce.
+pub fn checksum(pan: &str) -> u32 {
+    match luhn_sum(pan, false) {
+        Some((sum, _)) => sum % 10,
+        None => 10,
+    }
+}
+
+// Whether a PAN of at least two digits passes the Luhn check
+pub fn is_valid(pan: &str) -> bool {
+    luhn_sum(pan, false).is_some_and(|(sum, count)| count >= 2 && sum.is_multiple_of(10))
+}
+
+// Check digit that makes `partial` valid once appended
+pub fn check_digit(partial: &str) -> Option<u32> {
+    let (sum, _) = luhn_sum(partial, true)?;
+    Some((10 - sum % 10) % 10)
+}
+
+// Append the Luhn check digit to a partial PAN, dropping any separators.
+// Panics if `partial` contains characters other than digits, spaces and dashes.
+pub fn complete(partial: &str) -> String {
+    let check = check_digit(partial).expect("partial PAN must contain only digits");
+    let mut pan = String::with_capacity(partial.len() + 1);
+    pan.extend(partial.chars().filter(|c| c.is_ascii_digit()));
+    pan.push(char::from_digit(check, 10).unwrap());
+    pan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_are_checked() {
+        for pan in [
+            "4111111111111111",
+            "378282246310005",
+            "6011000990139424",
+            "79927398713",
+        ] {
+            assert_eq!(checksum(pan), 0, "{}", pan);
+            assert!(is_valid(pan), "{}", pan);
+        }
+        assert_eq!(checksum("4111111111111112"), 1);
+        assert!(!is_valid("4111111111111112"));
+        assert!(!is_valid("79927398710"));
+    }
+
+    #[test]
+    fn separators_are_ignored() {
+        assert!(is_valid("4111 1111 1111 1111"));
+        assert!(is_valid("4111-1111-1111-1111"));
+        assert!(is_valid("3782-822463-10005"));
+        assert_eq!(check_digit("7992 7398-71"), Some(3));
+        assert_eq!(complete("4111 1111-1111 111"), "4111111111111111");
+    }
+
+    #[test]
+    fn empty_and_short_numbers_are_invalid() {
+        // An empty sum is 0, but no PAN has fewer than two digits
+        assert_eq!(checksum(""), 0);
+        assert!(!is_valid(""));
+        assert!(!is_valid("0"));
+        assert!(!is_valid(" - "));
+        assert!(is_valid("00"));
+        assert_eq!(check_digit(""), Some(0));
+    }
+
+    #[test]
+    fn other_characters_are_rejected() {
+        assert_eq!(checksum("4111a11111111111"), 10);
+        assert_eq!(checksum("4111.1111.1111.1111"), 10);
+        assert!(!is_valid("4111a11111111111"));
+        assert!(!is_valid("４１１１１１１１１１１１１１１１"));
+        assert_eq!(check_digit("411x"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "partial PAN must contain only digits")]
+    fn completing_other_characters_panics() {
+        complete("4111/1111");
+    }
+
+    #[test]
+    fn completed_numbers_are_valid() {
+        assert_eq!(check_digit("7992739871"), Some(3));
+        assert_eq!(complete("411111111111111"), "4111111111111111");
+        for partial in [
+            "",
+            "0",
+            "4",
+            "37828224631000",
+            "601100099013942",
+            "123456789012345678",
+        ] {
+            let pan = complete(partial);
+            assert_eq!(pan.len(), partial.len() + 1);
+            assert_eq!(checksum(&pan), 0, "{}", pan);
+            // Any other final digit fails the check
+            let stem = &pan[..partial.len()];
+            for digit in (0..10).filter(|d| Some(*d) != check_digit(partial)) {
+                assert!(checksum(&format!("{}{}", stem, digit)) != 0);
+            }
+            if pan.len() >= 2 {
+                assert!(is_valid(&pan), "{}", pan);
+            }
+        }
+    }
+}
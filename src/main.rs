@@ -1,609 +1,1968 @@
-use chrono::{DateTime, Duration, Datelike, Utc};
-use rand::{
-    distributions::{Distribution, Standard},
-    prelude::SliceRandom,
-    Rng,
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
+use clap::{Parser, Subcommand, ValueEnum};
+use luhnsynth::{
+    anonymize::Anonymizer,
+    audit::UniquenessAudit,
+    checkpoint::Checkpoint,
+    config::{
+        Compression, CsvQuoting, OutputFormat, PartitionBy, Profile, RelationalSchema, SqlStyle,
+    },
+    conformance,
+    currency::Region,
+    custom::CustomFields,
+    daily::{self, DailyState},
+    dirty::Corruptor,
+    dispute,
+    drift::{self, Drift, DriftManifest},
+    fit::Fit,
+    fixtures::Provider,
+    fraud::{FraudInjector, FraudPattern},
+    generator::TransactionStream,
+    id::IdStrategy,
+    lifecycle,
+    live::LiveStream,
+    loader,
+    logging::{self, LogFormat},
+    manifest::{self, Manifest},
+    masking::MaskMode,
+    metrics::{Metrics, Progress},
+    ml::{Split, StratifiedSplitter},
+    model::{Customer, Transaction},
+    money::AmountFormat,
+    names::Locale,
+    nulls::{self, Nulls},
+    output::{self, TransactionWriter},
+    persona,
+    plan::{Plan, RowSizes},
+    report::{DataProfile, ReportFormat},
+    scenario::{Scenario, Script},
+    seed::{self, SeededRows},
+    sequence, serve,
+    throttle::{self, Burst, Throttle},
 };
-use serde::{Deserialize, Serialize};
+use rand::{SeedableRng, rngs::StdRng};
 use std::{
-    fs::File,
-    io::{self, Write},
+    env,
+    fs::{self, File},
+    io::{self, BufWriter},
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
 };
+use tracing::{debug, info, info_span, level_filters::LevelFilter, trace, warn};
 
-// Struct to store card brand information
-#[derive(Debug, Clone)]
-struct CardBrand {
-    name: String,
-    prefix: Vec<String>,
-    lengths: Vec<usize>,
-    cvv_length: usize,
-}
+/// A tool to generate synthetic credit card data for testing
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-// Struct to store merchant information
-#[derive(Debug, Clone)]
-struct Merchant {
-    name: String,
-    id: String,
-    category: String,
-}
+    /// Generation profile (TOML or YAML) overriding the built-in lists
+    #[arg(short, long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Directory to save the generated files
+    #[arg(short, long, value_name = "DIR", default_value = ".")]
+    output_dir: PathBuf,
+
+    /// Load rows straight into a database (postgres://... or mysql://...), or stream them to
+    /// stdout with `-`; files are only written as well when --format is given. With
+    /// s3://bucket/prefix/, gs://bucket/prefix/ or az://container/prefix/, upload the files
+    /// written to object storage
+    #[arg(long, value_name = "URL")]
+    output: Option<String>,
+
+    /// Server-side encryption of uploaded objects: aes256 or kms
+    #[arg(long, value_name = "MODE")]
+    sse: Option<output::Sse>,
+
+    /// KMS key (S3), Cloud KMS key name (GCS) or encryption scope (Azure) that uploaded objects
+    /// are encrypted with; implies --sse kms
+    #[arg(long, value_name = "KEY")]
+    sse_kms_key_id: Option<String>,
+
+    /// Stream rows to a message broker or HTTP endpoint; files are only written as well when
+    /// --format is given
+    #[arg(long, value_name = "SINK")]
+    sink: Option<Sink>,
+
+    /// Comma-separated list of Kafka bootstrap brokers (host:port)
+    #[arg(long, value_name = "BROKERS", value_delimiter = ',', required_if_eq("sink", "kafka"))]
+    brokers: Vec<String>,
+
+    /// Kafka topic that transactions are published to
+    #[arg(long, value_name = "TOPIC", default_value = "transactions")]
+    topic: String,
+
+    /// Endpoint that transactions are POSTed to as JSON
+    #[arg(long, global = true, value_name = "URL")]
+    url: Option<String>,
+
+    /// Number of HTTP requests kept in flight at once
+    #[arg(long, global = true, value_name = "N", default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+    concurrency: u16,
+
+    /// Times a failed HTTP request is retried, with exponential backoff
+    #[arg(long, global = true, value_name = "N", default_value_t = 3)]
+    retries: u32,
+
+    /// Emit at most this many transactions per second, or per minute or hour as in 500/s, 3000/m
+    /// or 100/h
+    #[arg(long, global = true, value_name = "RATE", value_parser = throttle::parse_rate)]
+    rate: Option<f64>,
+
+    /// Emit transactions in real time for this long, as in 90s, 10m or 1h30m, each stamped with
+    /// the time it's emitted at; needs --rate
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        value_parser = throttle::parse_duration,
+        requires = "rate"
+    )]
+    duration: Option<Duration>,
+
+    /// Space transactions as a Poisson process averaging --rate, rather than evenly
+    #[arg(long, global = true, requires = "rate")]
+    jitter: bool,
+
+    /// Multiply --rate by FACTOR for the last LENGTH of every PERIOD, as in 10x30s/5m; repeat for
+    /// several bursts
+    #[arg(
+        long,
+        global = true,
+        value_name = "FACTORxLENGTH/PERIOD",
+        value_parser = Burst::parse,
+        requires = "rate"
+    )]
+    burst: Vec<Burst>,
+
+    /// Serialize and write each output on tasks of their own with up to this many rows queued
+    /// for it, so slow sinks don't hold up generation [default: 4096 when given without a value]
+    #[arg(
+        long,
+        global = true,
+        value_name = "ROWS",
+        num_args = 0..=1,
+        default_missing_value = "4096",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pipeline: Option<u32>,
+
+    /// Print nothing but errors: no progress bar, output list or stats
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log more of what happens: -v for debug events such as the time spent on each output,
+    /// -vv for trace events down to every row written
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// How log lines are written to stderr: plain text, or one JSON object per line [default: text]
+    #[arg(long, global = true, value_name = "FORMAT")]
+    log_format: Option<LogFormat>,
+
+    /// Write the stats of the run (rows, bytes, elapsed time, counts by brand and status) to a
+    /// JSON file
+    #[arg(long, global = true, value_name = "FILE")]
+    json_metrics: Option<PathBuf>,
+
+    /// Write manifest.json to the output directory, listing every file written with its SHA-256,
+    /// row count and size, and the seed and profile hash of the run
+    #[arg(long, global = true)]
+    manifest: bool,
+
+    /// Write a profile of each dataset next to it (counts by brand and status beside the shares
+    /// asked for, amount histograms by currency, rows per day) as json, html or both
+    #[arg(long, value_name = "FORMATS", value_delimiter = ',')]
+    profile_report: Vec<ReportFormat>,
+
+    /// Comma-separated list of dataset sizes to generate
+    #[arg(short, long, value_name = "SIZES", value_delimiter = ',')]
+    sizes: Option<Vec<usize>>,
+
+    /// Comma-separated list of output formats
+    #[arg(short, long, value_name = "FORMAT", value_delimiter = ',')]
+    format: Option<Vec<OutputFormat>>,
+
+    /// Render each row with this Handlebars template as template output, the only format unless
+    /// --format is given too
+    #[arg(long, value_name = "FILE")]
+    template: Option<PathBuf>,
+
+    /// Format of transaction IDs [default: txn]
+    #[arg(long, value_name = "STRATEGY")]
+    id_strategy: Option<IdStrategy>,
+
+    /// Fail if a transaction ID repeats, or a card number, device or customer ID is shared between customers
+    #[arg(long)]
+    verify_unique: bool,
+
+    /// Only use published network and gateway test card numbers, never generated ones
+    #[arg(long)]
+    test_cards_only: bool,
+
+    /// Share of IP addresses that are IPv6 rather than IPv4 (0-1) [default: 0]
+    #[arg(long, value_name = "SHARE")]
+    ipv6_share: Option<f64>,
+
+    /// Share of online and contactless purchases paid with a digital wallet (0-1) [default: 0.3]
+    #[arg(long, value_name = "RATE")]
+    wallet_rate: Option<f64>,
+
+    /// Share of online card payments authenticated with 3-D Secure (0-1) [default: 0.8]
+    #[arg(long, value_name = "RATE")]
+    three_ds_rate: Option<f64>,
+
+    /// Share of purchases that sign up to a monthly subscription, renewed with recurring charges
+    /// (0-1) [default: 0]
+    #[arg(long, value_name = "RATE")]
+    subscription_rate: Option<f64>,
+
+    /// Share of purchases paid in monthly installments (0-1) [default: 0]
+    #[arg(long, value_name = "RATE")]
+    installment_rate: Option<f64>,
+
+    /// Share of purchases at hotels and car rentals pre-authorized, topped up with incremental
+    /// authorizations and captured for a different amount (0-1) [default: 0]
+    #[arg(long, value_name = "RATE")]
+    preauth_rate: Option<f64>,
+
+    /// Draw currencies from the ISO 4217 catalogue of a region, weighted by their share of card
+    /// payments, instead of the profile's list
+    #[arg(long, value_name = "REGION")]
+    currency_region: Option<Region>,
+
+    /// Share of purchases in a foreign currency that the cardholder pays in their card's currency
+    /// with dynamic currency conversion (0-1) [default: 0]
+    #[arg(long, value_name = "RATE")]
+    dcc_rate: Option<f64>,
+
+    /// Share of pooled customers who move to a new billing address during the date range, kept as
+    /// versions of the customer in relational output (0-1) [default: 0]
+    #[arg(long, value_name = "RATE")]
+    address_change_rate: Option<f64>,
+
+    /// Share of merchants that rebrand under a new name during the date range, kept as versions of
+    /// the merchant in relational output (0-1) [default: 0]
+    #[arg(long, value_name = "RATE")]
+    merchant_rename_rate: Option<f64>,
+
+    /// Inject nulls, malformed dates, truncated PANs, bad characters, duplicates and out-of-order
+    /// timestamps, listed in a manifest next to each dataset
+    #[arg(long)]
+    dirty: bool,
+
+    /// Leave a field missing from a share of rows, as in cvv=5% or user_agent=0.2: unset where it
+    /// is optional, which each format writes as its null, and empty otherwise; repeat for several
+    /// fields
+    #[arg(long, value_name = "FIELD=RATE", value_parser = nulls::parse)]
+    null: Vec<(String, f64)>,
+
+    /// Share of approved transactions later disputed with a chargeback (0-1), listed in a disputes
+    /// file next to each dataset
+    #[arg(long, value_name = "RATE")]
+    dispute_rate: Option<f64>,
+
+    /// How card numbers are written: clear, masked, first6-last4, sha256 or token
+    #[arg(long, value_name = "MODE")]
+    mask_pan: Option<MaskMode>,
 
-// Transaction status enum
-#[derive(Debug, Clone, Serialize, Deserialize)]
-enum TransactionStatus {
-    #[serde(rename = "approved")]
-    Approved,
-    #[serde(rename = "declined")]
-    Declined,
-    #[serde(rename = "pending")]
-    Pending,
-    #[serde(rename = "refunded")]
-    Refunded,
+    /// Number of persistent customers that transactions are drawn from
+    #[arg(long, value_name = "COUNT")]
+    customers: Option<usize>,
+
+    /// Split customers between the built-in student, frequent traveler and small business personas,
+    /// unless the profile configures its own
+    #[arg(long)]
+    personas: bool,
+
+    /// Chain each pooled customer's purchases through merchant categories with the built-in
+    /// transition matrix, unless the profile configures its own
+    #[arg(long)]
+    merchant_sequences: bool,
+
+    /// Emit authorization, capture, clearing and settlement events per transaction
+    #[arg(long)]
+    lifecycle: bool,
+
+    /// Write the Track 1 and Track 2 magnetic stripe data of card-present transactions
+    #[arg(long)]
+    track_data: bool,
+
+    /// Write the EMV application ID, cryptogram, TVR and ATC of chip and contactless transactions
+    #[arg(long)]
+    emv: bool,
+
+    /// Shift amounts, the merchant mix and fraud patterns at a date, time or share of the date
+    /// range such as 50%, recorded in a drift manifest next to each dataset
+    #[arg(long, value_name = "WHEN")]
+    drift_at: Option<String>,
+
+    /// Share of rows labelled as fraud, injected as whole fraud patterns (0-1)
+    #[arg(long, value_name = "RATE")]
+    fraud_rate: Option<f64>,
+
+    /// Target share of rows labelled as fraud (0-1), overriding --fraud-rate
+    #[arg(long, value_name = "RATIO")]
+    label_ratio: Option<f64>,
+
+    /// Share of fraudulent online purchases shipped to an address in the fraudster's country rather than the billing address (0-1) [default: 0.7]
+    #[arg(long, value_name = "RATE")]
+    address_mismatch_rate: Option<f64>,
+
+    /// Only inject these fraud patterns, equally weighted (card-testing, impossible-travel,
+    /// high-value, fraud-ring, card-spray, account-takeover)
+    #[arg(long, value_name = "PATTERNS", value_delimiter = ',')]
+    fraud_patterns: Vec<FraudPattern>,
+
+    /// Number of stolen cards a fraud ring spends with (2-30) [default: 6]
+    #[arg(long, value_name = "CARDS")]
+    ring_size: Option<usize>,
+
+    /// Write stratified train/test files, putting this share of rows in train (0-1)
+    #[arg(long, value_name = "FRACTION")]
+    split: Option<f64>,
+
+    /// CSV file of merchants (name, id, category, mcc) replacing the built-in list
+    #[arg(long, value_name = "FILE")]
+    merchants: Option<PathBuf>,
+
+    /// CSV file of BINs (prefix, issuer, country, card_type, prepaid) replacing the built-in table
+    #[arg(long, value_name = "FILE")]
+    bins: Option<PathBuf>,
+
+    /// CSV file of cardholder names (first_name, last_name) replacing the built-in lists
+    #[arg(long, value_name = "FILE")]
+    names: Option<PathBuf>,
+
+    /// Comma-separated list of locales cardholder names are drawn from in equal shares, in their
+    /// own script, instead of the name lists
+    #[arg(long, value_name = "LOCALES", value_delimiter = ',')]
+    name_locales: Option<Vec<Locale>>,
+
+    /// First day of generated transactions (YYYY-MM-DD) [default: 3 years before --date-to]
+    #[arg(long, value_name = "DATE")]
+    date_from: Option<NaiveDate>,
+
+    /// Last day of generated transactions (YYYY-MM-DD) [default: now]
+    #[arg(long, value_name = "DATE")]
+    date_to: Option<NaiveDate>,
+
+    /// IANA timezone that timestamps are generated in, e.g. Europe/Berlin [default: UTC]
+    #[arg(long, value_name = "ZONE")]
+    timezone: Option<Tz>,
+
+    /// Share of transactions attempted with an expired card and declined as card_expired (0-1)
+    #[arg(long, value_name = "RATE")]
+    expired_card_rate: Option<f64>,
+
+    /// Share of refunds that return only part of what is left of the transaction (0-1) [default: 0.25]
+    #[arg(long, value_name = "RATE")]
+    partial_refund_rate: Option<f64>,
+
+    /// Field delimiter for CSV output, e.g. '|' or 'tab' [default: ,]
+    #[arg(long, value_name = "CHAR", value_parser = parse_delimiter)]
+    csv_delimiter: Option<char>,
+
+    /// When to quote CSV fields [default: necessary]
+    #[arg(long, value_name = "POLICY")]
+    csv_quoting: Option<CsvQuoting>,
+
+    /// Leave the header row out of CSV output
+    #[arg(long)]
+    no_header: bool,
+
+    /// How SQL output loads rows: batched INSERT statements or a Postgres COPY block [default: insert]
+    #[arg(long, value_name = "STYLE")]
+    sql_style: Option<SqlStyle>,
+
+    /// Table created and loaded by SQL and SQLite output [default: transactions]
+    #[arg(long, value_name = "NAME")]
+    sql_table: Option<String>,
+
+    /// Tables written by relational output: normalized tables, or dimensions around a fact table [default: normalized]
+    #[arg(long, value_name = "SCHEMA")]
+    schema: Option<RelationalSchema>,
+
+    /// Fields written by CSV, JSON, XML and SQL output, in this order (comma-separated)
+    /// [default: every field]
+    #[arg(long, value_name = "FIELDS", value_delimiter = ',')]
+    columns: Vec<String>,
+
+    /// Fields left out of CSV, JSON, XML and SQL output (comma-separated)
+    #[arg(long, value_name = "FIELDS", value_delimiter = ',')]
+    exclude_columns: Vec<String>,
+
+    /// Write a field under another name in CSV, JSON, XML and SQL output, as in card_number=pan;
+    /// repeat for several fields
+    #[arg(long, value_name = "FIELD=NAME", value_parser = parse_rename)]
+    rename: Vec<(String, String)>,
+
+    /// Document element of XML output [default: transactions]
+    #[arg(long, value_name = "NAME")]
+    xml_root: Option<String>,
+
+    /// Element wrapping each transaction in XML output [default: transaction]
+    #[arg(long, value_name = "NAME")]
+    xml_row: Option<String>,
+
+    /// Compress output files, except Parquet, Avro, XLSX and SQLite ones
+    #[arg(long, value_name = "CODEC")]
+    compress: Option<Compression>,
+
+    /// Write each dataset as a Hive-style directory with a partition per day or month of
+    /// transaction date, as in dt=2024-09-25/part-0000.csv
+    #[arg(long, value_name = "PERIOD")]
+    partition_by: Option<PartitionBy>,
+
+    /// Split output into numbered files of at most this many rows, as in transactions_100-00001.csv
+    #[arg(long, value_name = "ROWS")]
+    max_rows_per_file: Option<usize>,
+
+    /// Record progress in this file each time a --max-rows-per-file file is complete, and resume
+    /// from it when the run is started again with the same options
+    #[arg(long, value_name = "FILE")]
+    checkpoint: Option<PathBuf>,
+
+    /// Check the options and print what would be written (datasets, files, rows and estimated
+    /// sizes) without writing anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Generate the same rows from this seed every time, in blocks of 1000 that can be generated
+    /// apart; needs a fixed --date-to
+    #[arg(long, value_name = "SEED")]
+    seed: Option<u64>,
+
+    /// Start each dataset at this row of the seeded sequence, so separate jobs can each write a
+    /// range of rows that join up into those of a single run [default: 0]
+    #[arg(long, value_name = "ROW", requires = "seed")]
+    start_row: Option<u64>,
+
+    /// Play out a YAML scenario scripting what named customers do, step by step, as the only
+    /// dataset instead of drawing rows at random
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["sizes", "start_row"])]
+    scenario: Option<PathBuf>,
+
+    /// Write amounts in CSV, JSON, XML and fixed-width output in major units or as whole minor units [default: decimal]
+    #[arg(long, value_name = "FORMAT")]
+    amount_format: Option<AmountFormat>,
 }
 
-// Helper function to implement random distribution for TransactionStatus
-impl Distribution<TransactionStatus> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> TransactionStatus {
-        match rng.gen_range(0..4) {
-            0 => TransactionStatus::Approved,
-            1 => TransactionStatus::Declined,
-            2 => TransactionStatus::Pending,
-            _ => TransactionStatus::Refunded,
+// Accept a literal delimiter character, or `tab`/`\t` for TSV output
+fn parse_delimiter(value: &str) -> Result<char, String> {
+    match value {
+        "tab" | "\\t" => Ok('\t'),
+        _ => {
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(format!("expected a single character, got {:?}", value)),
+            }
         }
     }
 }
 
-// Decline reason enum (Option to handle null cases)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-enum DeclineReason {
-    #[serde(rename = "insufficient_funds")]
-    InsufficientFunds,
-    #[serde(rename = "card_expired")]
-    CardExpired,
-    #[serde(rename = "invalid_card")]
-    InvalidCard,
-    #[serde(rename = "suspicious_activity")]
-    SuspiciousActivity,
+// A `--rename` argument: a field and the name it is written under
+fn parse_rename(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((field, name)) => Ok((field.trim().to_string(), name.trim().to_string())),
+        None => Err(format!("expected FIELD=NAME, as in card_number=pan, got {:?}", value)),
+    }
 }
 
-// Helper function to implement random distribution for DeclineReason
-impl Distribution<DeclineReason> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> DeclineReason {
-        match rng.gen_range(0..4) {
-            0 => DeclineReason::InsufficientFunds,
-            1 => DeclineReason::CardExpired,
-            2 => DeclineReason::InvalidCard,
-            _ => DeclineReason::SuspiciousActivity,
-        }
-    }
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// POST transactions to --url as payment webhook events, signed with an HMAC-SHA256
+    /// `Signature` header
+    Webhooks {
+        /// Shared secret that event payloads are signed with
+        #[arg(long, value_name = "SECRET")]
+        secret: String,
+
+        /// Number of events to deliver
+        #[arg(long, value_name = "COUNT", default_value_t = 100)]
+        count: usize,
+    },
+
+    /// Write each transaction as the JSON response of a payment gateway's API, one file per
+    /// transaction: charges, refunds and card errors for declines, for mocking the gateway in tests
+    Fixtures {
+        /// Gateway whose responses the fixtures are shaped like
+        #[arg(long, value_enum, value_name = "PROVIDER")]
+        provider: Provider,
+
+        /// Number of fixtures to write
+        #[arg(long, value_name = "COUNT", default_value_t = 100)]
+        count: usize,
+    },
+
+    /// Generate the day after the last one generated (yesterday on the first run) into a
+    /// dt=YYYY-MM-DD directory, keeping the customer pool in a state file between runs
+    Daily {
+        /// JSON file that the last generated day, customers and merchants are kept in
+        #[arg(long, value_name = "FILE")]
+        state: PathBuf,
+
+        /// Number of transactions generated for the day
+        #[arg(long, value_name = "COUNT", default_value_t = 1000)]
+        count: usize,
+
+        /// First day of a new feed (YYYY-MM-DD) [default: yesterday]
+        #[arg(long, value_name = "DATE")]
+        start: Option<NaiveDate>,
+    },
+
+    /// Print the schema of output formats, for generating consumer code and validating files
+    #[command(group(clap::ArgGroup::new("kind").required(true)))]
+    Schema {
+        /// Print the .proto definition of protobuf output
+        #[arg(long, group = "kind")]
+        proto: bool,
+
+        /// Print the Avro schema embedded in Avro output
+        #[arg(long, group = "kind")]
+        avro: bool,
+
+        /// Schema to print: proto, avro, jsonschema (JSON and JSON Lines rows), arrow (Parquet)
+        /// or ddl (the SQL CREATE TABLE statement)
+        #[arg(long, group = "kind", value_name = "FORMAT")]
+        format: Option<output::SchemaFormat>,
+    },
+
+    /// Check a profile, with any options given, and report settings that are likely mistakes,
+    /// without generating anything
+    Validate,
+
+    /// Fit a profile to a CSV sample of real transactions, with card numbers and names stripped,
+    /// that generates data with its brand mix, statuses, amounts and timing
+    Fit {
+        /// CSV sample with a header row, in the columns of CSV output
+        #[arg(long, value_name = "FILE")]
+        input: PathBuf,
+
+        /// File to write the fitted profile to [default: stdout]
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+
+    /// Check every row of a CSV or JSON Lines file of card data, such as third-party fixtures:
+    /// Luhn validity, brand prefixes and lengths, CVV lengths, expiry dates, currencies, MCCs
+    /// and decline reasons
+    ValidateData {
+        /// CSV file with a header row (.csv) or JSON Lines file (.jsonl) to check
+        file: PathBuf,
+    },
+
+    /// Replace the card numbers, tracks, CVVs, names, emails, phones, IPs and streets of a CSV
+    /// or JSON Lines file of real transactions with made-up ones, keeping every other column
+    Anonymize {
+        /// CSV file with a header row (.csv) or JSON Lines file (.jsonl) to anonymize
+        #[arg(long, value_name = "FILE")]
+        input: PathBuf,
+
+        /// File to write the anonymized rows to, in the same format [default: stdout]
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+
+    /// Serve transactions on demand: REST requests such as GET /transactions?count=100&brand=visa,
+    /// a live feed over a WebSocket on /stream, and a gRPC service streaming them, on the same port
+    Serve {
+        /// Port to listen on
+        #[arg(long, value_name = "PORT", default_value_t = 8080)]
+        port: u16,
+
+        /// Address to listen on; 0.0.0.0 serves other hosts as well
+        #[arg(long, value_name = "ADDR", default_value = "127.0.0.1")]
+        bind: IpAddr,
+    },
 }
 
-// Card expiry struct
-#[derive(Debug, Clone)]
-struct CardExpiry {
-    month: u8,
-    year: u16,
+// Streaming destinations for generated rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Sink {
+    // Publish each transaction as a JSON message to a Kafka topic
+    Kafka,
+    // POST each transaction as JSON to the --url endpoint
+    Http,
 }
 
-impl CardExpiry {
-    fn new(month: u8, year: u16) -> Self {
-        Self { month, year }
-    }
+// A writer for one output, with the time spent writing to it
+struct Output {
+    split: Option<Split>,
+    name: String,
+    writer: Box<dyn TransactionWriter>,
+    busy: Duration,
+    // Rows written to it
+    rows: u64,
+}
 
-    fn to_string(&self) -> String {
-        format!("{:02}/{}", self.month, self.year % 100)
+impl Output {
+    fn new(split: Option<Split>, name: &str, writer: Box<dyn TransactionWriter>) -> Self {
+        Self {
+            split,
+            name: name.to_string(),
+            writer,
+            busy: Duration::ZERO,
+            rows: 0,
+        }
     }
 }
 
-// Main transaction struct
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Transaction {
-    transaction_id: String,
-    transaction_date: String,
-    status: TransactionStatus,
-    decline_reason: Option<DeclineReason>,
-    cardholder_name: String,
-    card_number: String,
-    card_brand: String,
-    card_expiry: String,
-    cvv: String,
-    amount: f64,
-    currency: String,
-    merchant_name: String,
-    merchant_id: String,
-    merchant_category: String,
-    payment_method: String,
-    ip_address: String,
-    device_id: String,
-    user_agent: String,
+// Directory and file name stem of a dataset. Daily feeds write each day to
+// a partition directory of its own.
+fn dataset_path(
+    output_dir: &Path,
+    size: usize,
+    day: Option<NaiveDate>,
+    scripted: bool,
+    live: bool,
+) -> (PathBuf, String) {
+    match day {
+        Some(day) => (
+            output_dir.join(format!("dt={}", day)),
+            "transactions".to_string(),
+        ),
+        None if scripted => (output_dir.to_path_buf(), "transactions_scenario".to_string()),
+        None if live => (output_dir.to_path_buf(), "transactions_live".to_string()),
+        None => (output_dir.to_path_buf(), format!("transactions_{}", size)),
+    }
 }
 
-// Helper function to generate random data
-fn gen_random_element<T>(vec: &[T]) -> &T {
-    let mut rng = rand::thread_rng();
-    vec.choose(&mut rng).unwrap()
+// Path a writer of a format is created at, and the path its files are
+// listed as. Partitioned datasets share a directory, named like the files.
+fn output_paths(
+    dir: &Path,
+    name: &str,
+    format: OutputFormat,
+    profile: &Profile,
+) -> (PathBuf, PathBuf) {
+    match profile.partition_by {
+        Some(_) => {
+            let path = dir.join(name);
+            let parts = output::file_name("part-*", format, profile);
+            (path.clone(), path.join("dt=*").join(parts))
+        }
+        None => {
+            let path = dir.join(output::file_name(name, format, profile));
+            let listed = match profile.max_rows_per_file {
+                Some(_) => {
+                    let shards = format!("{}-*", name);
+                    dir.join(output::file_name(&shards, format, profile))
+                }
+                None => path.clone(),
+            };
+            (path, listed)
+        }
+    }
 }
 
-// Generate a random date within the last 3 years
-fn gen_random_date() -> DateTime<Utc> {
-    let mut rng = rand::thread_rng();
-    let now = Utc::now();
-    let days_ago = rng.gen_range(0..365 * 3);
-    now - Duration::days(days_ago)
-}
+// Daily feed state file, the day being generated, and the customers the
+// feed left off with, until they're handed to the generator
+type DailyRun = (PathBuf, NaiveDate, Option<Vec<Customer>>);
 
-// Generate a random future expiry date (1-5 years in the future)
-fn gen_random_expiry_date() -> CardExpiry {
-    let mut rng = rand::thread_rng();
-    let now = Utc::now();
-    let future_years = rng.gen_range(1..=5);
-    let future_month = rng.gen_range(1..=12);
-    CardExpiry::new(future_month, (now.year() + future_years) as u16)
+// A run generating datasets: its profile, where rows go besides files, and
+// what it has written so far
+struct Run<'a> {
+    cli: &'a Cli,
+    profile: Profile,
+    to_stdout: bool,
+    bucket: Option<output::ObjectUrl>,
+    encryption: output::Encryption,
+    database: Option<&'a str>,
+    // Webhook secret
+    secret: Option<&'a str>,
+    provider: Option<Provider>,
+    daily: Option<DailyRun>,
+    scenario: Option<Scenario>,
+    script: Option<Script>,
+    // Command line, which a checkpoint has to be resumed with
+    args: Vec<String>,
+    resumed: Option<Checkpoint>,
+    metrics: Metrics,
+    written: Vec<String>,
+    // Rows written to each output, for the manifest
+    counts: Vec<(String, u64)>,
+    // Whether the reader of rows streamed to stdout closed it, which ends
+    // the dataset early
+    stdout_closed: bool,
 }
 
-// Generate a random transaction ID
-fn gen_transaction_id() -> String {
-    let mut rng = rand::thread_rng();
-    let mut id = String::from("TXN");
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
-    for _ in 0..9 {
-        let idx = rng.gen_range(0..CHARSET.len());
-        id.push(CHARSET[idx] as char);
+fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    let level = match (cli.quiet, cli.verbose) {
+        (true, _) => LevelFilter::ERROR,
+        (false, 0) => LevelFilter::INFO,
+        (false, 1) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    };
+    logging::init(cli.log_format.unwrap_or_default(), level).map_err(io::Error::other)?;
+
+    let config = cli.config.as_deref();
+    match &cli.command {
+        Some(Command::Schema {
+            proto,
+            avro,
+            format,
+        }) => schema(config, *proto, *avro, *format),
+        Some(Command::Fit { input, out }) => fit(config, input, out.as_deref()),
+        Some(Command::ValidateData { file }) => validate_data(config, file),
+        Some(Command::Anonymize { input, out }) => anonymize(config, input, out.as_deref()),
+        _ => generate(&cli),
     }
-    id
 }
 
-// Generate a random IPv4 address
-fn gen_ip_address() -> String {
-    let mut rng = rand::thread_rng();
-    format!(
-        "{}.{}.{}.{}",
-        rng.gen_range(1..255),
-        rng.gen_range(0..255),
-        rng.gen_range(0..255),
-        rng.gen_range(0..255)
-    )
+// The profile at `path`, or the built-in one
+fn load_profile(path: Option<&Path>) -> io::Result<Profile> {
+    match path {
+        Some(path) => {
+            debug!(path = %path.display(), "loading profile");
+            Profile::load(path)
+        }
+        None => Ok(Profile::default()),
+    }
 }
 
-// Generate a random device ID
-fn gen_device_id() -> String {
-    let mut rng = rand::thread_rng();
-    format!("DEV{}", rng.gen_range(10000..99999))
+// Schemas follow the custom fields, columns and amount format of the
+// profile given, if any
+fn schema(
+    config: Option<&Path>,
+    proto: bool,
+    avro: bool,
+    format: Option<output::SchemaFormat>,
+) -> io::Result<()> {
+    let profile = load_profile(config)?;
+    let format = match (proto, avro, format) {
+        (true, _, _) => output::SchemaFormat::Proto,
+        (_, true, _) => output::SchemaFormat::Avro,
+        (_, _, Some(format)) => format,
+        (false, false, None) => unreachable!("clap requires one schema kind"),
+    };
+    print!("{}", output::schema(format, &profile)?);
+    Ok(())
 }
 
-// Apply Luhn algorithm to generate valid credit card numbers
-fn apply_luhn_algorithm(partial: &str) -> String {
-    let mut rng = rand::thread_rng();
-    let mut number = partial.to_string();
-    
-    // Complete the number with random digits if needed
-    while number.len() < 15 {
-        number.push_str(&rng.gen_range(0..=9).to_string());
-    }
-    
-    // Remove the last digit if it exists to calculate the check digit
-    let without_check_digit = if number.len() < 16 {
-        number.clone()
-    } else {
-        number[0..number.len()-1].to_string()
-    };
-    
-    // Calculate Luhn sum
-    let mut sum = 0;
-    let mut double = false;
-    
-    for c in without_check_digit.chars().rev() {
-        if let Some(digit) = c.to_digit(10) {
-            let mut value = digit;
-            if double {
-                value *= 2;
-                if value > 9 {
-                    value -= 9;
-                }
-            }
-            sum += value;
-            double = !double;
+// Brands in the sample are matched to those of the profile given, if any
+fn fit(config: Option<&Path>, input: &Path, out: Option<&Path>) -> io::Result<()> {
+    let profile = load_profile(config)?;
+    let fit = Fit::read(input, &profile)?;
+    for warning in fit.warnings() {
+        warn!(path = %input.display(), "{}", warning);
+    }
+    let toml = format!(
+        "# Fitted by luhnsynth from {} rows of {}\n{}",
+        fit.rows(),
+        input.display(),
+        fit.to_toml(&profile)?
+    );
+    match out {
+        Some(path) => {
+            fs::write(path, toml)?;
+            info!(path = %path.display(), rows = fit.rows(), "wrote fitted profile");
         }
+        None => print!("{}", toml),
+    }
+    Ok(())
+}
+
+// Brands are those of the profile given, if any
+fn validate_data(config: Option<&Path>, file: &Path) -> io::Result<()> {
+    let profile = load_profile(config)?;
+    let summary = conformance::check_file(file, &profile, |violation| {
+        println!(
+            "row {}: {}: {}",
+            violation.row, violation.column, violation.message
+        );
+    })?;
+    if summary.violations > 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{}: {} violations in {} of {} rows",
+                file.display(),
+                summary.violations,
+                summary.bad_rows,
+                summary.rows
+            ),
+        ));
     }
-    
-    // Calculate check digit
-    let check_digit = (10 - (sum % 10)) % 10;
-    
-    format!("{}{}", without_check_digit, check_digit)
+    println!("{}: all {} rows are valid", file.display(), summary.rows);
+    Ok(())
 }
 
-// Generate a valid credit card number for a specific brand
-fn generate_card_number(brand: &CardBrand) -> String {
-    // Choose a random prefix
-    let prefix = gen_random_element(&brand.prefix);
-    
-    // Choose a random length
-    let length = *gen_random_element(&brand.lengths);
-    
-    // Generate a partial number with the prefix
-    let partial = prefix.clone();
-    
-    // Apply Luhn algorithm to generate a valid number
-    let full_number = apply_luhn_algorithm(&partial);
-    
-    // Ensure the number has the correct length
-    full_number[0..length].to_string()
+// Card numbers keep the brands of the profile given, if any, and names are
+// drawn from its lists
+fn anonymize(config: Option<&Path>, input: &Path, out: Option<&Path>) -> io::Result<()> {
+    let profile = load_profile(config)?;
+    let mut anonymizer = Anonymizer::new(&profile);
+    let rows = match out {
+        Some(path) => anonymizer.file(input, BufWriter::new(File::create(path)?))?,
+        None => anonymizer.file(input, io::stdout().lock())?,
+    };
+    if anonymizer.unknown_brands() > 0 {
+        warn!(
+            count = anonymizer.unknown_brands(),
+            "card numbers of unknown brands replaced with random digits"
+        );
+    }
+    for (column, count) in anonymizer.replaced() {
+        debug!(column, count, "values replaced");
+    }
+    info!(path = %input.display(), rows, "anonymized");
+    Ok(())
 }
 
-// Generate a CVV code
-fn generate_cvv(length: usize) -> String {
-    let mut rng = rand::thread_rng();
-    let mut cvv = String::new();
-    for _ in 0..length {
-        cvv.push_str(&rng.gen_range(0..=9).to_string());
+// Checking a profile ends once it has passed, with whatever looks amiss
+fn validate(config: Option<&Path>, profile: &Profile) -> io::Result<()> {
+    let warnings = profile.warnings();
+    for warning in &warnings {
+        println!("warning: {}", warning);
+    }
+    let name = config.map_or("built-in profile".to_string(), |path| path.display().to_string());
+    match warnings.len() {
+        0 => println!("{} is valid", name),
+        1 => println!("{} is valid, with 1 warning", name),
+        n => println!("{} is valid, with {} warnings", name, n),
     }
-    cvv
+    Ok(())
 }
 
-// Generate a single transaction
-fn generate_transaction(
-    card_brands: &[CardBrand],
-    merchants: &[Merchant],
-    first_names: &[String],
-    last_names: &[String],
-    currencies: &[String],
-    user_agents: &[String],
-) -> Transaction {
-    let mut rng = rand::thread_rng();
-    
-    // Select random elements
-    let brand = gen_random_element(card_brands);
-    let merchant = gen_random_element(merchants);
-    let status: TransactionStatus = rand::random();
-    let first_name = gen_random_element(first_names);
-    let last_name = gen_random_element(last_names);
-    let currency = gen_random_element(currencies);
-    let user_agent = gen_random_element(user_agents);
-    
-    // Generate card number and expiry
-    let card_number = generate_card_number(brand);
-    let expiry_date = gen_random_expiry_date();
-    
-    // Generate transaction date
-    let transaction_date = gen_random_date();
-    
-    // Generate amount based on currency
-    let amount = if currency == "JPY" {
-        rng.gen_range(100..=50000) as f64
-    } else {
-        (rng.gen_range(1..=1000) as f64) + (rng.gen_range::<f64, _>(0.0..1.0) * 100.0).round() / 100.0
+// Generate datasets, or serve or check the profile, with the options given
+fn generate(cli: &Cli) -> io::Result<()> {
+    // Load the generation profile, letting command line flags take precedence
+    let mut profile = load_profile(cli.config.as_deref())?;
+    let sized = cli.sizes.is_some();
+    if let Some(sizes) = &cli.sizes {
+        profile.sizes = sizes.clone();
+    }
+    // Webhook mode delivers one batch of events instead of writing datasets
+    let secret = match &cli.command {
+        Some(Command::Webhooks { secret, count }) => {
+            profile.sizes = vec![*count];
+            Some(secret.as_str())
+        }
+        _ => None,
+    };
+    // Fixture mode writes one batch of responses, in place of the formats
+    // unless some are asked for as well
+    let provider = match cli.command {
+        Some(Command::Fixtures { provider, count }) => {
+            profile.sizes = vec![count];
+            Some(provider)
+        }
+        _ => None,
+    };
+    // A scenario is a single dataset, of as many rows as it plays out to
+    let scenario = match &cli.scenario {
+        Some(path) => Some(Scenario::load(path)?),
+        None => None,
     };
-    
-    // Generate decline reason if status is declined
-    let decline_reason = match status {
-        TransactionStatus::Declined => Some(rand::random()),
+    if scenario.is_some() {
+        profile.sizes = vec![0];
+    }
+    // A live run is one dataset that goes on until --duration is up, unless
+    // a size or count given stops it sooner
+    if cli.duration.is_some() {
+        if !sized && secret.is_none() {
+            profile.sizes = vec![usize::MAX];
+        } else if profile.sizes.len() != 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--duration runs a single dataset; pass one size at most",
+            ));
+        }
+    }
+    // `--output -` streams rows to stdout, as CSV unless a format is given,
+    // an object URL is where the files written are uploaded to, and any
+    // other value is a database to load rows into
+    let to_stdout = cli.output.as_deref() == Some("-");
+    let bucket = cli.output.as_deref().and_then(output::ObjectUrl::parse);
+    let database = cli
+        .output
+        .as_deref()
+        .filter(|url| *url != "-" && bucket.is_none());
+    let encryption = output::Encryption::new(cli.sse, cli.sse_kms_key_id.clone())?;
+    match &bucket {
+        Some(bucket) => {
+            bucket.validate()?;
+            encryption.validate(bucket.provider)?;
+        }
+        None if encryption.sse.is_some() => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--sse applies to uploads to s3://, gs:// or az:// --output URLs",
+            ));
+        }
+        None => {}
+    }
+    if let Some(formats) = &cli.format {
+        profile.formats = formats.clone();
+    } else if cli.template.is_some() {
+        profile.formats = vec![OutputFormat::Template];
+    } else if to_stdout {
+        profile.formats = vec![OutputFormat::Csv];
+    } else if database.is_some() || cli.sink.is_some() || secret.is_some() || provider.is_some() {
+        profile.formats.clear();
+    }
+    if cli.template.is_some() {
+        profile.template = cli.template.clone();
+        if !profile.formats.contains(&OutputFormat::Template) {
+            profile.formats.push(OutputFormat::Template);
+        }
+    }
+    apply_options(&mut profile, cli)?;
+    // A daily feed generates the day after its last run, from the customers
+    // and merchants it left off with
+    let daily = match &cli.command {
+        Some(Command::Daily { state, count, start }) => {
+            let saved = DailyState::load(state)?;
+            let yesterday = daily::yesterday(profile.timezone);
+            let day = match &saved {
+                Some(saved) => saved.next_day(),
+                None => start.unwrap_or(yesterday),
+            };
+            if day > yesterday {
+                info!("Nothing to generate: {} hasn't ended yet", day);
+                return Ok(());
+            }
+            profile.sizes = vec![*count];
+            profile.date_from = Some(day);
+            profile.date_to = Some(day);
+            let customers = match saved {
+                Some(saved) => {
+                    profile.merchants = saved.merchants;
+                    Some(saved.customers)
+                }
+                None => {
+                    profile.customers.get_or_insert(daily::DEFAULT_CUSTOMERS);
+                    None
+                }
+            };
+            Some((state.clone(), day, customers))
+        }
         _ => None,
     };
+    profile.validate()?;
+    // Checked here as the pool may be given on the command line after the
+    // profile is loaded
+    if profile.sequences.is_enabled() && profile.customers.is_none() && daily.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "merchant sequences need a customer pool (--customers)",
+        ));
+    }
+    if let Some(Command::Validate) = cli.command {
+        return validate(cli.config.as_deref(), &profile);
+    }
+    // Server mode generates rows for each request instead of datasets
+    if let Some(Command::Serve { port, bind }) = cli.command {
+        return serve::run(profile, SocketAddr::new(bind, port));
+    }
+
+    let mut run = Run {
+        cli,
+        profile,
+        to_stdout,
+        bucket,
+        encryption,
+        database,
+        secret,
+        provider,
+        daily,
+        scenario,
+        script: None,
+        args: env::args().skip(1).collect(),
+        resumed: None,
+        metrics: Metrics::default(),
+        written: Vec::new(),
+        counts: Vec::new(),
+        stdout_closed: false,
+    };
+    run.check()?;
+    // Scenarios are played out up front, as they're short; with a seed,
+    // what they leave open is drawn the same way every time
+    if let Some(scenario) = &run.scenario {
+        scenario.validate(&run.profile)?;
+        let script = match cli.seed {
+            Some(seed) => seed::with_seed(seed, |rng| scenario.run(rng, &run.profile))?,
+            None => scenario.run(&mut StdRng::from_entropy(), &run.profile)?,
+        };
+        run.profile.sizes = vec![script.transactions.len()];
+        run.script = Some(script);
+    }
+    run.resume()?;
+
+    // A dry run stops short of writing, with the plan of what it would have
+    if cli.dry_run {
+        return run.dry_run();
+    }
+
+    fs::create_dir_all(&cli.output_dir)?;
+
+    // A live run's date range is from now until its time is up
+    if let Some(duration) = cli.duration {
+        let duration = chrono::Duration::from_std(duration)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "--duration is too long"))?;
+        run.profile.live_until = Some(Utc::now() + duration);
+    }
 
-    Transaction {
-        transaction_id: gen_transaction_id(),
-        transaction_date: transaction_date.to_rfc3339(),
-        status,
-        decline_reason,
-        cardholder_name: format!("{} {}", first_name, last_name),
-        card_number,
-        card_brand: brand.name.clone(),
-        card_expiry: expiry_date.to_string(),
-        cvv: generate_cvv(brand.cvv_length),
-        amount,
-        currency: currency.clone(),
-        merchant_name: merchant.name.clone(),
-        merchant_id: merchant.id.clone(),
-        merchant_category: merchant.category.clone(),
-        payment_method: "credit_card".to_string(),
-        ip_address: gen_ip_address(),
-        device_id: gen_device_id(),
-        user_agent: user_agent.clone(),
+    // Generate datasets with the requested sizes
+    info!("Generating test datasets...");
+    let start = Instant::now();
+    for (dataset, size) in run.profile.sizes.clone().into_iter().enumerate() {
+        run.dataset(dataset, size)?;
     }
+    run.finish(start.elapsed())
 }
 
-// Generate multiple transactions
-fn generate_transactions(
-    count: usize,
-    card_brands: &[CardBrand],
-    merchants: &[Merchant],
-    first_names: &[String],
-    last_names: &[String],
-    currencies: &[String],
-    user_agents: &[String],
-) -> Vec<Transaction> {
-    (0..count)
-        .map(|_| {
-            generate_transaction(
-                card_brands,
-                merchants,
-                first_names,
-                last_names,
-                currencies,
-                user_agents,
-            )
-        })
-        .collect()
+// Let command line flags take precedence over the profile
+fn apply_options(profile: &mut Profile, cli: &Cli) -> io::Result<()> {
+    if let Some(strategy) = cli.id_strategy {
+        profile.id_strategy = strategy;
+    }
+    if cli.verify_unique {
+        profile.verify_unique = true;
+    }
+    if cli.test_cards_only {
+        profile.test_cards_only = true;
+    }
+    if let Some(share) = cli.ipv6_share {
+        profile.geo.ipv6_share = share;
+    }
+    if let Some(rate) = cli.wallet_rate {
+        profile.wallets.rate = rate;
+    }
+    if let Some(rate) = cli.three_ds_rate {
+        profile.three_ds.rate = rate;
+    }
+    if let Some(rate) = cli.subscription_rate {
+        profile.subscriptions.rate = rate;
+    }
+    if let Some(rate) = cli.installment_rate {
+        profile.installments.rate = rate;
+    }
+    if let Some(rate) = cli.preauth_rate {
+        profile.preauths.rate = rate;
+    }
+    if cli.currency_region.is_some() {
+        profile.currency_region = cli.currency_region;
+    }
+    if let Some(rate) = cli.dcc_rate {
+        profile.dcc.rate = rate;
+    }
+    if let Some(rate) = cli.address_change_rate {
+        profile.scd.address_change_rate = rate;
+    }
+    if let Some(rate) = cli.merchant_rename_rate {
+        profile.scd.merchant_rename_rate = rate;
+    }
+    if cli.dirty {
+        profile.dirty.enabled = true;
+    }
+    profile.nulls.extend(cli.null.iter().cloned());
+    if let Some(rate) = cli.dispute_rate {
+        profile.disputes.rate = rate;
+    }
+    if let Some(mode) = cli.mask_pan {
+        profile.masking.card_number = mode;
+    }
+    if cli.customers.is_some() {
+        profile.customers = cli.customers;
+    }
+    if cli.personas && profile.personas.is_empty() {
+        profile.personas = persona::builtin_personas();
+    }
+    if cli.merchant_sequences && !profile.sequences.is_enabled() {
+        profile.sequences.transitions = sequence::builtin_transitions(&profile.merchants);
+    }
+    if cli.lifecycle {
+        profile.lifecycle = true;
+    }
+    if cli.track_data {
+        profile.track_data = true;
+    }
+    if cli.emv {
+        profile.emv = true;
+    }
+    if let Some(rate) = cli.fraud_rate {
+        profile.fraud.rate = rate;
+    }
+    if cli.label_ratio.is_some() {
+        profile.ml.label_ratio = cli.label_ratio;
+    }
+    if let Some(rate) = cli.address_mismatch_rate {
+        profile.fraud.address_mismatch_rate = rate;
+    }
+    if let Some(at) = &cli.drift_at {
+        profile.drift.push(drift::builtin_point(at));
+    }
+    if !cli.fraud_patterns.is_empty() {
+        profile.fraud.patterns = cli.fraud_patterns.iter().map(|p| (*p, 1.0)).collect();
+    }
+    if let Some(size) = cli.ring_size {
+        profile.fraud.ring_size = size;
+    }
+    if cli.split.is_some() {
+        profile.ml.train_fraction = cli.split;
+    }
+    if let Some(path) = &cli.merchants {
+        profile.merchants = loader::load_merchants(path)?;
+    }
+    if let Some(path) = &cli.bins {
+        profile.bins = loader::load_bins(path)?;
+    }
+    if let Some(path) = &cli.names {
+        (profile.first_names, profile.last_names) = loader::load_names(path)?;
+    }
+    if let Some(locales) = &cli.name_locales {
+        profile.names.locales = locales.iter().map(|locale| (*locale, 1.0)).collect();
+    }
+    if cli.date_from.is_some() {
+        profile.date_from = cli.date_from;
+    }
+    if cli.date_to.is_some() {
+        profile.date_to = cli.date_to;
+    }
+    if let Some(timezone) = cli.timezone {
+        profile.timezone = timezone;
+    }
+    if let Some(rate) = cli.expired_card_rate {
+        profile.expired_card_rate = rate;
+    }
+    if let Some(rate) = cli.partial_refund_rate {
+        profile.partial_refund_rate = rate;
+    }
+    if let Some(delimiter) = cli.csv_delimiter {
+        profile.csv.delimiter = delimiter;
+    }
+    if let Some(quoting) = cli.csv_quoting {
+        profile.csv.quoting = quoting;
+    }
+    if cli.no_header {
+        profile.csv.header = false;
+    }
+    if let Some(style) = cli.sql_style {
+        profile.sql.style = style;
+    }
+    if let Some(table) = &cli.sql_table {
+        profile.sql.table = table.clone();
+    }
+    if let Some(schema) = cli.schema {
+        profile.relational.schema = schema;
+    }
+    if !cli.columns.is_empty() {
+        profile.columns.include = cli.columns.clone();
+    }
+    profile.columns.exclude.extend(cli.exclude_columns.iter().cloned());
+    profile.columns.rename.extend(cli.rename.iter().cloned());
+    if let Some(root) = &cli.xml_root {
+        profile.xml.root = root.clone();
+    }
+    if let Some(row) = &cli.xml_row {
+        profile.xml.row = row.clone();
+    }
+    if cli.compress.is_some() {
+        profile.compression = cli.compress;
+    }
+    if cli.partition_by.is_some() {
+        profile.partition_by = cli.partition_by;
+    }
+    if cli.max_rows_per_file.is_some() {
+        profile.max_rows_per_file = cli.max_rows_per_file;
+    }
+    if let Some(format) = cli.amount_format {
+        profile.amount_format = format;
+    }
+    Ok(())
 }
 
-// Write transactions to a CSV file
-fn write_transactions_to_csv(transactions: &[Transaction], filename: &str) -> io::Result<()> {
-    let mut file = File::create(filename)?;
-    
-    // Write headers
-    writeln!(
-        file,
-        "transaction_id,transaction_date,status,decline_reason,cardholder_name,card_number,card_brand,card_expiry,cvv,amount,currency,merchant_name,merchant_id,merchant_category,payment_method,ip_address,device_id,user_agent"
-    )?;
-    
-    // Write data rows
-    for tx in transactions {
-        let decline_reason = match &tx.decline_reason {
-            Some(reason) => match reason {
-                DeclineReason::InsufficientFunds => "insufficient_funds",
-                DeclineReason::CardExpired => "card_expired",
-                DeclineReason::InvalidCard => "invalid_card",
-                DeclineReason::SuspiciousActivity => "suspicious_activity",
-            },
-            None => "",
+impl Run<'_> {
+    // Reject options that can't be combined, before anything is written
+    fn check(&self) -> io::Result<()> {
+        let (cli, profile) = (self.cli, &self.profile);
+        // Checked here as clap can't require a global flag from the subcommand
+        if (cli.sink == Some(Sink::Http) || self.secret.is_some()) && cli.url.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--sink http and webhooks require --url",
+            ));
+        }
+
+        // Relational tables and daily feeds are laid out in directories of their own
+        if profile.max_rows_per_file.is_some()
+            && profile.formats.contains(&OutputFormat::Relational)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "relational output can't be split into files of limited rows",
+            ));
+        }
+        if profile.partition_by.is_some() {
+            if profile.formats.contains(&OutputFormat::Relational) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "relational output can't be partitioned",
+                ));
+            }
+            if self.daily.is_some() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "daily feeds are already partitioned by day",
+                ));
+            }
+        }
+
+        // Typed and protocol formats need well-formed values to encode
+        if profile.dirty.enabled {
+            if let Some(format) = profile.formats.iter().find(|f| !f.accepts_dirty()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "dirty rows can't be written as {}; use csv, json, jsonl, sql, sqlite, \
+                         xml, fixed-width or template",
+                        format.to_possible_value().expect("no skipped formats").get_name()
+                    ),
+                ));
+            }
+            if self.database.is_some() || self.secret.is_some() || self.provider.is_some() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "dirty rows can't be loaded into a database, sent as webhooks or written as \
+                     fixtures",
+                ));
+            }
+        }
+
+        // Fixtures are named by transaction ID, which lifecycle events share
+        // with the payment they belong to
+        if self.provider.is_some() && profile.lifecycle {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fixtures can't be written for lifecycle events",
+            ));
+        }
+
+        // Rows streamed to stdout make up a single dataset in a single format
+        if self.to_stdout {
+            if profile.sizes.len() != 1
+                || profile.formats.len() != 1
+                || profile.ml.train_fraction.is_some()
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "--output - writes a single dataset; pass one size and one format, without \
+                     a train/test split",
+                ));
+            }
+            if let Some(format) = profile.formats.iter().find(|f| !f.is_streamed()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} output can't be written to stdout",
+                        format.to_possible_value().expect("no skipped formats").get_name()
+                    ),
+                ));
+            }
+            if profile.partition_by.is_some() || profile.max_rows_per_file.is_some() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "rows written to stdout can't be partitioned or split into files",
+                ));
+            }
+            if profile.compression.is_some() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "rows written to stdout aren't compressed; pipe them through gzip or zstd \
+                     instead",
+                ));
+            }
+        }
+
+        if cli.manifest && self.to_stdout {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--manifest lists files written to --output-dir, and rows written to stdout \
+                 aren't",
+            ));
+        }
+
+        // Checkpoints only roll back files, and only when every row of the
+        // dataset lands in the next file in turn
+        if cli.checkpoint.is_some() {
+            if profile.max_rows_per_file.is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "--checkpoint needs --max-rows-per-file, as checkpoints are taken as files \
+                     complete",
+                ));
+            }
+            if self.to_stdout
+                || self.database.is_some()
+                || cli.sink.is_some()
+                || self.secret.is_some()
+                || self.provider.is_some()
+                || self.daily.is_some()
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "--checkpoint only applies to files written to --output-dir",
+                ));
+            }
+            if profile.partition_by.is_some()
+                || profile.ml.train_fraction.is_some()
+                || profile.lifecycle
+                || profile.dirty.enabled
+                || profile.disputes.is_enabled()
+                || cli.pipeline.is_some()
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "--checkpoint can't be combined with partitions, train/test splits, \
+                     lifecycle events, dirty rows, disputes or --pipeline",
+                ));
+            }
+        }
+        // Live rows are stamped with the clock, so they can't be replayed or
+        // resumed, and daily feeds generate a day that has already ended
+        if cli.duration.is_some()
+            && (cli.seed.is_some() || cli.checkpoint.is_some() || self.daily.is_some())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--duration can't be combined with --seed, --checkpoint or daily feeds",
+            ));
+        }
+        // Seeded rows only depend on the seed and where they are in the
+        // sequence, so the date range can't run up to the current time, and
+        // steps that draw randomness of their own after generation are left
+        // out
+        if cli.seed.is_some() {
+            if profile.date_to.is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "--seed needs --date-to (or date_to in the config), so the date range \
+                     doesn't move with the clock",
+                ));
+            }
+            if self.daily.is_some()
+                || profile.ml.train_fraction.is_some()
+                || profile.lifecycle
+                || profile.dirty.enabled
+                || profile.disputes.is_enabled()
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "--seed can't be combined with daily feeds, train/test splits, lifecycle \
+                     events, dirty rows or disputes",
+                ));
+            }
+        }
+        if self.scenario.is_some()
+            && (cli.duration.is_some() || cli.checkpoint.is_some() || self.daily.is_some())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--scenario can't be combined with --duration, --checkpoint or daily feeds",
+            ));
+        }
+        Ok(())
+    }
+
+    // A run started again with its checkpoint carries on where it stopped,
+    // with the seed and date range it had
+    fn resume(&mut self) -> io::Result<()> {
+        let Some(path) = &self.cli.checkpoint else {
+            return Ok(());
         };
-        
-        let status = match tx.status {
-            TransactionStatus::Approved => "approved",
-            TransactionStatus::Declined => "declined",
-            TransactionStatus::Pending => "pending",
-            TransactionStatus::Refunded => "refunded",
+        let Some(resumed) = Checkpoint::load(path)? else {
+            // A new run's rows are drawn up to a day fixed now, which the
+            // checkpoint keeps
+            let today = Utc::now().with_timezone(&self.profile.timezone).date_naive();
+            self.profile.date_to.get_or_insert(today);
+            return Ok(());
         };
-        
-        writeln!(
-            file,
-            "{},{},{},{},\"{}\",{},{},{},{},{:.2},{},{},{},{},{},{},{},\"{}\"",
-            tx.transaction_id,
-            tx.transaction_date,
-            status,
-            decline_reason,
-            tx.cardholder_name,
-            tx.card_number,
-            tx.card_brand,
-            tx.card_expiry,
-            tx.cvv,
-            tx.amount,
-            tx.currency,
-            tx.merchant_name,
-            tx.merchant_id,
-            tx.merchant_category,
-            tx.payment_method,
-            tx.ip_address,
-            tx.device_id,
-            tx.user_agent
-        )?;
-    }
-    
-    Ok(())
-}
+        if resumed.args != self.args {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "checkpoint {} was written by a run with other options; start it again with \
+                     the same ones, or remove the checkpoint to start over",
+                    path.display()
+                ),
+            ));
+        }
+        self.profile.date_to = Some(resumed.date_to);
+        info!(
+            "Resuming from {}: {} rows of dataset {} in {} complete files",
+            path.display(),
+            resumed.rows,
+            resumed.dataset + 1,
+            resumed.files
+        );
+        self.resumed = Some(resumed);
+        Ok(())
+    }
 
-// Write transactions to a JSON file
-fn write_transactions_to_json(transactions: &[Transaction], filename: &str) -> io::Result<()> {
-    let json = serde_json::to_string_pretty(transactions)?;
-    let mut file = File::create(filename)?;
-    file.write_all(json.as_bytes())?;
-    Ok(())
+    // Print the plan of what the run would write
+    fn dry_run(&self) -> io::Result<()> {
+        let (cli, profile) = (self.cli, &self.profile);
+        for warning in profile.warnings() {
+            warn!("{}", warning);
+        }
+        let sizes = RowSizes::measure(profile)?;
+        let day = self.daily.as_ref().map(|(_, day, _)| *day);
+        let splits = match profile.ml.train_fraction {
+            Some(fraction) => {
+                vec![(Some(Split::Train), fraction), (Some(Split::Test), 1.0 - fraction)]
+            }
+            None => vec![(None, 1.0)],
+        };
+        let mut plan = Plan::default();
+        for &size in &profile.sizes {
+            // Live runs without a size go on until their time is up
+            let transactions = Some(size).filter(|size| *size != usize::MAX);
+            plan.dataset(transactions.map(|size| sizes.rows(size as f64)));
+            let live = cli.duration.is_some();
+            let scripted = self.script.is_some();
+            let (dir, stem) = dataset_path(&cli.output_dir, size, day, scripted, live);
+            for &(split, fraction) in &splits {
+                let suffix = split.map_or(String::new(), |s| format!("_{}", s.as_str()));
+                let rows = transactions.map(|size| sizes.rows(size as f64 * fraction));
+                for &format in &profile.formats {
+                    if self.to_stdout {
+                        plan.output("stdout", rows, None, None);
+                        continue;
+                    }
+                    let name = format!("{}{}", stem, suffix);
+                    let (_, listed) = output_paths(&dir, &name, format, profile);
+                    let files = match (profile.partition_by, profile.max_rows_per_file, rows) {
+                        (Some(_), _, _) | (_, _, None) => None,
+                        (None, Some(max_rows), Some(rows)) => {
+                            Some(rows.div_ceil(max_rows as u64).max(1) * sizes.files(format))
+                        }
+                        (None, None, Some(_)) => Some(sizes.files(format)),
+                    };
+                    let bytes = rows.map(|rows| sizes.bytes(format, rows));
+                    plan.output(&listed.to_string_lossy(), rows, files, bytes);
+                }
+                if self.database.is_some() {
+                    let table = match day {
+                        Some(_) => format!("{}{}", profile.sql.table, suffix),
+                        None => format!("{}_{}{}", profile.sql.table, size, suffix),
+                    };
+                    plan.output(&format!("table {}", table), rows, None, None);
+                }
+                if cli.sink == Some(Sink::Kafka) {
+                    plan.output(&format!("topic {}{}", cli.topic, suffix), rows, None, None);
+                }
+                if (cli.sink == Some(Sink::Http) || self.secret.is_some())
+                    && let Some(url) = &cli.url
+                {
+                    plan.output(&format!("requests to {}", url), rows, None, None);
+                }
+                // Fixtures are a file per transaction
+                if let Some(provider) = self.provider {
+                    let path = dir.join(format!("{}_{}{}", stem, provider.as_str(), suffix));
+                    plan.output(&path.to_string_lossy(), rows, rows, None);
+                }
+            }
+        }
+        print!("{}", plan.report());
+        Ok(())
+    }
+
+    // Open a writer for every requested format, and every split when
+    // train/test files were asked for. Sharded files of a resumed dataset
+    // are numbered on from `first`.
+    fn outputs(
+        &mut self,
+        dir: &Path,
+        stem: &str,
+        size: usize,
+        first: usize,
+    ) -> io::Result<Vec<Output>> {
+        let (cli, profile) = (self.cli, &self.profile);
+        let splits = match profile.ml.train_fraction {
+            Some(_) => vec![Some(Split::Train), Some(Split::Test)],
+            None => vec![None],
+        };
+        // Files and stdout of pipelined outputs are written by a task of
+        // their own, started as they're opened
+        if cli.pipeline.is_some() {
+            output::start_pipeline()?;
+        }
+        let mut outputs = Vec::new();
+        for split in splits {
+            let suffix = split.map_or(String::new(), |s| format!("_{}", s.as_str()));
+            for &format in &profile.formats {
+                if self.to_stdout {
+                    let writer = output::create_stdout_writer(format, profile)?;
+                    outputs.push(Output::new(split, "stdout", writer));
+                    self.written.push("stdout".to_string());
+                    continue;
+                }
+                let name = format!("{}{}", stem, suffix);
+                let (path, listed) = output_paths(dir, &name, format, profile);
+                let mut writer = match profile.max_rows_per_file {
+                    Some(max_rows) if first > 0 => {
+                        output::create_sharded_writer(format, &path, max_rows, first, profile)?
+                    }
+                    _ => output::create_writer(format, &path, profile)?,
+                };
+                if let Some(bucket) = &self.bucket {
+                    writer = output::create_upload_writer(
+                        writer,
+                        &listed,
+                        &cli.output_dir,
+                        bucket,
+                        &self.encryption,
+                        cli.retries,
+                    )?;
+                }
+                let listed = listed.to_string_lossy().into_owned();
+                outputs.push(Output::new(split, &listed, writer));
+                self.written.push(listed);
+            }
+            // Database tables are named like the files, without an extension,
+            // except that daily feeds load every day into the same table
+            if let Some(url) = self.database {
+                let table = match &self.daily {
+                    Some(_) => format!("{}{}", profile.sql.table, suffix),
+                    None => format!("{}_{}{}", profile.sql.table, size, suffix),
+                };
+                let name = format!("table {}", table);
+                let writer = output::create_database_writer(
+                    url,
+                    &table,
+                    &profile.custom_fields,
+                    &profile.masking,
+                    output::Columns::new(profile),
+                )?;
+                outputs.push(Output::new(split, &name, writer));
+                self.written.push(name);
+            }
+            if cli.sink == Some(Sink::Kafka) {
+                let topic = format!("{}{}", cli.topic, suffix);
+                let name = format!("topic {}", topic);
+                let writer = output::create_kafka_writer(&cli.brokers, &topic)?;
+                outputs.push(Output::new(split, &name, writer));
+                self.written.push(name);
+            }
+            if (cli.sink == Some(Sink::Http) || self.secret.is_some())
+                && let Some(url) = &cli.url
+            {
+                let concurrency = cli.concurrency as usize;
+                let writer =
+                    output::create_http_writer(url, concurrency, cli.retries, self.secret)?;
+                let name = match split {
+                    Some(split) => format!("{} requests to {}", split.as_str(), url),
+                    None => format!("requests to {}", url),
+                };
+                outputs.push(Output::new(split, &name, writer));
+                self.written.push(name);
+            }
+            if let Some(provider) = self.provider {
+                let path = dir.join(format!("{}_{}{}", stem, provider.as_str(), suffix));
+                let writer = output::FixtureWriter::new(&path, provider)?;
+                let name = path.to_string_lossy().into_owned();
+                outputs.push(Output::new(split, &name, Box::new(writer)));
+                self.written.push(name);
+            }
+        }
+        // Pipelined outputs are serialized and written by tasks of their own
+        // while rows are generated
+        if let Some(depth) = cli.pipeline {
+            outputs = outputs
+                .into_iter()
+                .map(|output| {
+                    let writer = output::PipelinedWriter::new(output.writer, depth as usize)?;
+                    Ok(Output { writer: Box::new(writer), ..output })
+                })
+                .collect::<io::Result<_>>()?;
+        }
+        Ok(outputs)
+    }
+
+    // Generate the dataset at index `dataset` of the profile's sizes and
+    // write it to every output
+    fn dataset(&mut self, dataset: usize, size: usize) -> io::Result<()> {
+        let _dataset = info_span!("dataset", size).entered();
+        // Datasets an interrupted run finished are left as they are, and the
+        // one it stopped in goes on after its last complete file
+        if self.resumed.as_ref().is_some_and(|resumed| resumed.dataset > dataset) {
+            debug!("skipping dataset finished before the checkpoint");
+            return Ok(());
+        }
+        let resume = self.resumed.take().filter(|resumed| resumed.dataset == dataset);
+        let (done, first) = resume.as_ref().map_or((0, 0), |resume| (resume.rows, resume.files));
+        let day = self.daily.as_ref().map(|(_, day, _)| *day);
+        let live = self.profile.live_until.is_some();
+        let scripted = self.script.is_some();
+        let (dir, stem) = dataset_path(&self.cli.output_dir, size, day, scripted, live);
+        fs::create_dir_all(&dir)?;
+        let mut outputs = self.outputs(&dir, &stem, size, first)?;
+
+        let (cli, profile) = (self.cli, &self.profile);
+        let mut splitter = profile.ml.train_fraction.map(StratifiedSplitter::new);
+
+        // Stream each transaction to all writers so formats share the same data.
+        // The progress bar stays out of the way of verbose and JSON logs, and
+        // live runs have no total to show progress towards.
+        let mut progress = if cli.quiet
+            || cli.verbose > 0
+            || cli.log_format.unwrap_or_default() == LogFormat::Json
+            || profile.live_until.is_some()
+        {
+            Progress::hidden()
+        } else {
+            Progress::new(size - done)
+        };
+        let customers = self.daily.as_mut().and_then(|(_, _, customers)| customers.take());
+        // Scenarios were played out already
+        let (mut scripted_rows, mut scripted_disputes) = match self.script.take() {
+            Some(script) => (Some(script.transactions), script.disputes),
+            None => (None, Vec::new()),
+        };
+        // Checkpointed rows are seeded too, from a seed drawn for each
+        // dataset unless the run has one, so a resumed run draws the rows the
+        // interrupted one would have
+        let seed = match (cli.seed, &cli.checkpoint, &resume) {
+            (Some(seed), ..) => Some(seed),
+            (None, Some(_), Some(resume)) => Some(resume.seed),
+            (None, Some(_), None) => Some(rand::random()),
+            (None, None, _) => None,
+        };
+        // Seeded rows are generated block by block from where the dataset
+        // starts, instead of from a stream of their own
+        let mut stream = match seed {
+            _ if scripted_rows.is_some() => None,
+            Some(_) => None,
+            None => Some(TransactionStream::with_customers(profile, size - done, customers)),
+        };
+        // The checkpoint is saved as the dataset starts, with the seed it
+        // was given
+        let mut checkpoint = match (&cli.checkpoint, profile.max_rows_per_file, seed) {
+            (Some(path), Some(max_rows), Some(seed)) => {
+                let date_to = profile.date_to.expect("checkpointed runs have an end date");
+                let mut checkpoint = Checkpoint::new(&self.args, dataset, seed, date_to);
+                checkpoint.advance(done, first);
+                checkpoint.save(path)?;
+                Some((checkpoint, path, max_rows, done))
+            }
+            _ => None,
+        };
+        let generated: Box<dyn Iterator<Item = Transaction>> = match (&mut stream, seed) {
+            _ if scripted_rows.is_some() => {
+                Box::new(scripted_rows.take().into_iter().flatten())
+            }
+            (Some(stream), _) => Box::new(
+                FraudInjector::new(stream.by_ref(), &profile.fraud, profile.id_strategy)
+                    .with_target_ratio(profile.ml.label_ratio)
+                    .with_drift(Drift::of(profile))
+                    .with_user_agents(&profile.user_agents, &profile.user_agent_weights()),
+            ),
+            (None, seed) => {
+                let seed = seed.expect("datasets without a stream are seeded");
+                let first = cli.start_row.unwrap_or(0) + done as u64;
+                Box::new(SeededRows::new(profile, seed, first))
+            }
+        };
+        let transactions = generated.take(size - done).inspect(|_| progress.tick());
+        let rows: Box<dyn Iterator<Item = Transaction>> = if profile.lifecycle {
+            let mut rng = StdRng::from_entropy();
+            Box::new(transactions.flat_map(move |tx| lifecycle::events(&mut rng, &tx)))
+        } else {
+            Box::new(transactions)
+        };
+        let throttle = cli.rate.map(|rate| {
+            Throttle::new(rate)
+                .with_jitter(cli.jitter)
+                .with_bursts(cli.burst.clone())
+        });
+        // Live rows are paced before they're generated, so they're stamped
+        // with the time they go out at
+        let (rows, mut throttle): (Box<dyn Iterator<Item = Transaction>>, _) =
+            match (profile.live_until, throttle) {
+                (Some(end), Some(throttle)) => {
+                    (Box::new(LiveStream::new(rows, end).with_throttle(throttle)), None)
+                }
+                (_, throttle) => (rows, throttle),
+            };
+        let mut audit = profile.verify_unique.then(|| {
+            let audit = UniquenessAudit::new();
+            // The few test cards are necessarily shared between customers
+            if profile.test_cards_only {
+                audit.with_shared_cards()
+            } else {
+                audit
+            }
+        });
+        // Dirty rows come with a manifest of what was done to which row
+        let mut dirty = if profile.dirty.enabled {
+            let path = dir.join(format!("{}.manifest.csv", stem));
+            let manifest = csv::Writer::from_path(&path)?;
+            self.written.push(path.to_string_lossy().into_owned());
+            let start = profile.date_range().start;
+            Some((Corruptor::new(&profile.dirty, start), manifest))
+        } else {
+            None
+        };
+        // Drift comes with a manifest of when the distributions change, and
+        // how many rows and fraud rows each stretch got
+        let mut drift = Drift::of(profile)
+            .map(|drift| DriftManifest::new(drift, &profile.date_range()));
+        // Chargebacks go to their own file, linked by transaction ID, with
+        // those a scenario scripts first
+        let scripted = self.scenario.as_ref().is_some_and(Scenario::has_chargebacks);
+        let mut disputes = if profile.disputes.is_enabled() || scripted {
+            let path = dir.join(format!("{}.disputes.csv", stem));
+            let mut file = csv::Writer::from_path(&path)?;
+            for event in scripted_disputes.drain(..) {
+                file.serialize(event)?;
+            }
+            self.written.push(path.to_string_lossy().into_owned());
+            Some(file)
+        } else {
+            None
+        };
+        // The profile report summarizes rows as they're written
+        let mut report = (!cli.profile_report.is_empty()).then(|| DataProfile::new(profile));
+        let masking = profile.masking.is_enabled().then_some(&profile.masking);
+        let custom = CustomFields::new(&profile.custom_fields, profile.amount_format);
+        let nulls = Nulls::new(&profile.nulls);
+        // Disputes, custom fields and nulls are drawn as rows are written
+        let mut rng = StdRng::from_entropy();
+        'rows: for mut tx in rows {
+            // Uniqueness is checked on the rows as generated, before any are
+            // deliberately duplicated or masked
+            if let Some(audit) = audit.as_mut() {
+                audit.check(&tx)?;
+            }
+            if let Some(file) = disputes.as_mut()
+                && profile.disputes.is_enabled()
+            {
+                for event in dispute::dispute(&mut rng, &tx, &profile.disputes) {
+                    file.serialize(event)?;
+                }
+            }
+            if let Some(drift) = drift.as_mut() {
+                drift.record(&tx);
+            }
+            // Masking comes before corruption so the manifest doesn't hold
+            // clear card numbers either
+            if let Some(masking) = masking {
+                masking.apply(&mut tx);
+            }
+            if let Some(custom) = custom.as_ref() {
+                custom.apply(&mut rng, &mut tx);
+            }
+            if let Some(nulls) = nulls.as_ref() {
+                nulls.apply(&mut rng, &mut tx);
+            }
+            let (tx, duplicate) = match dirty.as_mut() {
+                Some((corruptor, manifest)) => {
+                    let batch = corruptor.corrupt(tx);
+                    for corruption in corruptor.take_corruptions() {
+                        manifest.serialize(corruption)?;
+                    }
+                    batch
+                }
+                None => (tx, None),
+            };
+            for tx in std::iter::once(tx).chain(duplicate) {
+                if let Some(throttle) = throttle.as_mut() {
+                    throttle.wait();
+                }
+                let split = splitter.as_mut().map(|s| s.assign(&tx));
+                let tx = Arc::new(tx);
+                for output in outputs.iter_mut().filter(|o| o.split == split) {
+                    let started = Instant::now();
+                    match output.writer.write_shared(&tx) {
+                        Err(e) if output::is_stdout_closed(&e) => {
+                            self.stdout_closed = true;
+                            continue;
+                        }
+                        result => result?,
+                    }
+                    output.busy += started.elapsed();
+                    output.rows += 1;
+                }
+                self.metrics.record(&tx);
+                if let Some(report) = report.as_mut() {
+                    report.record(&tx);
+                }
+                trace!(transaction_id = %tx.transaction_id, "wrote row");
+                // Each file completed moves the checkpoint on
+                if let Some((checkpoint, path, max_rows, rows)) = checkpoint.as_mut() {
+                    *rows += 1;
+                    if *rows % *max_rows == 0 && *rows < size {
+                        checkpoint.advance(*rows, *rows / *max_rows);
+                        checkpoint.save(path)?;
+                        debug!(rows = *rows, "saved checkpoint");
+                    }
+                }
+                // The row goes to the other outputs, and is the last
+                if self.stdout_closed {
+                    break 'rows;
+                }
+            }
+        }
+        progress.finish();
+        if self.stdout_closed {
+            info!("stdout was closed; stopped generating");
+        }
+        if let Some((_, mut manifest)) = dirty {
+            manifest.flush()?;
+        }
+        if let Some(mut file) = disputes {
+            file.flush()?;
+        }
+        if let Some(drift) = drift {
+            let path = dir.join(format!("{}.drift.json", stem));
+            drift.write(&path)?;
+            self.written.push(path.to_string_lossy().into_owned());
+        }
+        if let Some(report) = report {
+            for &format in &cli.profile_report {
+                let path = dir.join(format!("{}.profile.{}", stem, format.extension()));
+                report.write(&path, format)?;
+                self.written.push(path.to_string_lossy().into_owned());
+            }
+        }
+        for output in &mut outputs {
+            let started = Instant::now();
+            match output.writer.finish() {
+                Err(e) if output::is_stdout_closed(&e) => self.stdout_closed = true,
+                result => result?,
+            }
+            output.busy += started.elapsed();
+            self.metrics.wrote(output.writer.bytes());
+            debug!(
+                output = %output.name,
+                seconds = output.busy.as_secs_f64(),
+                "finished writing"
+            );
+            if let Some(summary) = output.writer.summary() {
+                info!("{}", summary.trim_end());
+            }
+            self.counts.push((output.name.clone(), done as u64 + output.rows));
+        }
+
+        // The feed's state only moves on once the day has been written
+        if let (Some((path, day, _)), Some(stream), false) =
+            (&self.daily, &stream, self.stdout_closed)
+        {
+            let generator = stream.generator();
+            let state = DailyState {
+                last_day: *day,
+                customers: generator.settled_customers(),
+                merchants: generator.settled_merchants(),
+            };
+            state.save(path)?;
+            debug!(path = %path.display(), "saved daily state");
+            self.written.push(path.to_string_lossy().into_owned());
+        }
+        Ok(())
+    }
+
+    // Wrap up a run that wrote every dataset: its manifest and summary
+    fn finish(mut self, elapsed: Duration) -> io::Result<()> {
+        let (cli, profile) = (self.cli, &self.profile);
+        // A finished run has nothing to resume
+        if let Some(path) = &cli.checkpoint {
+            fs::remove_file(path)?;
+        }
+
+        // The manifest lists every file written, with the rows of those holding
+        // whole datasets or shards of them
+        if cli.manifest {
+            let mut manifest = Manifest::new(cli.seed, profile)?;
+            for name in &self.written {
+                let rows = self
+                    .counts
+                    .iter()
+                    .find(|(output, _)| output == name)
+                    .filter(|_| profile.partition_by.is_none())
+                    .map(|(_, rows)| *rows);
+                manifest.add(&cli.output_dir, name, rows, profile.max_rows_per_file)?;
+            }
+            let path = cli.output_dir.join(manifest::FILE_NAME);
+            manifest.save(&path)?;
+            self.written.push(path.to_string_lossy().into_owned());
+        }
+
+        info!("Done! Generated {} outputs:", self.written.len());
+        for filename in &self.written {
+            info!("- {}", filename);
+        }
+        self.metrics.finish(elapsed, &self.written);
+        info!("{}", self.metrics.report().trim_end());
+        if let Some(path) = &cli.json_metrics {
+            self.metrics.save(path)?;
+        }
+
+        Ok(())
+    }
 }
 
-fn main() -> io::Result<()> {
-    // Define card brands
-    let card_brands = vec![
-        CardBrand {
-            name: "Visa".to_string(),
-            prefix: vec!["4".to_string()],
-            lengths: vec![16],
-            cvv_length: 3,
-        },
-        CardBrand {
-            name: "Mastercard".to_string(),
-            prefix: vec![
-                "51".to_string(),
-                "52".to_string(),
-                "53".to_string(),
-                "54".to_string(),
-                "55".to_string(),
-            ],
-            lengths: vec![16],
-            cvv_length: 3,
-        },
-        CardBrand {
-            name: "American Express".to_string(),
-            prefix: vec!["34".to_string(), "37".to_string()],
-            lengths: vec![15],
-            cvv_length: 4,
-        },
-        CardBrand {
-            name: "Discover".to_string(),
-            prefix: vec![
-                "6011".to_string(),
-                "644".to_string(),
-                "645".to_string(),
-                "646".to_string(),
-                "647".to_string(),
-                "648".to_string(),
-                "649".to_string(),
-                "65".to_string(),
-            ],
-            lengths: vec![16],
-            cvv_length: 3,
-        },
-    ];
-
-    // Define merchants
-    let merchants = vec![
-        Merchant {
-            name: "Acme Retail".to_string(),
-            id: "MER12345".to_string(),
-            category: "Retail".to_string(),
-        },
-        Merchant {
-            name: "Sunshine Groceries".to_string(),
-            id: "MER22468".to_string(),
-            category: "Grocery".to_string(),
-        },
-        Merchant {
-            name: "Tech Universe".to_string(),
-            id: "MER39521".to_string(),
-            category: "Electronics".to_string(),
-        },
-        Merchant {
-            name: "Cozy Coffee Shop".to_string(),
-            id: "MER41327".to_string(),
-            category: "Food & Beverage".to_string(),
-        },
-        Merchant {
-            name: "Fitness Plus".to_string(),
-            id: "MER57845".to_string(),
-            category: "Health & Fitness".to_string(),
-        },
-        Merchant {
-            name: "BookWorld".to_string(),
-            id: "MER61234".to_string(),
-            category: "Books & Media".to_string(),
-        },
-        Merchant {
-            name: "QuickMart".to_string(),
-            id: "MER78523".to_string(),
-            category: "Convenience Store".to_string(),
-        },
-        Merchant {
-            name: "Urban Fashion".to_string(),
-            id: "MER84751".to_string(),
-            category: "Clothing".to_string(),
-        },
-        Merchant {
-            name: "Travel Now".to_string(),
-            id: "MER92456".to_string(),
-            category: "Travel".to_string(),
-        },
-        Merchant {
-            name: "Gourmet Dining".to_string(),
-            id: "MER10387".to_string(),
-            category: "Restaurant".to_string(),
-        },
-    ];
-
-    // Define first names
-    let first_names = vec![
-        "John".to_string(),
-        "Jane".to_string(),
-        "Michael".to_string(),
-        "Emily".to_string(),
-        "David".to_string(),
-        "Sarah".to_string(),
-        "Robert".to_string(),
-        "Lisa".to_string(),
-        "William".to_string(),
-        "Emma".to_string(),
-        "James".to_string(),
-        "Olivia".to_string(),
-        "Daniel".to_string(),
-        "Sophia".to_string(),
-        "Matthew".to_string(),
-        "Ava".to_string(),
-        "Christopher".to_string(),
-        "Mia".to_string(),
-        "Andrew".to_string(),
-        "Isabella".to_string(),
-    ];
-
-    // Define last names
-    let last_names = vec![
-        "Smith".to_string(),
-        "Johnson".to_string(),
-        "Williams".to_string(),
-        "Brown".to_string(),
-        "Jones".to_string(),
-        "Garcia".to_string(),
-        "Miller".to_string(),
-        "Davis".to_string(),
-        "Rodriguez".to_string(),
-        "Martinez".to_string(),
-        "Hernandez".to_string(),
-        "Lopez".to_string(),
-        "Gonzalez".to_string(),
-        "Wilson".to_string(),
-        "Anderson".to_string(),
-        "Thomas".to_string(),
-        "Taylor".to_string(),
-        "Moore".to_string(),
-        "Jackson".to_string(),
-        "Martin".to_string(),
-    ];
-
-    // Define currencies
-    let currencies = vec![
-        "USD".to_string(),
-        "EUR".to_string(),
-        "GBP".to_string(),
-        "CAD".to_string(),
-        "AUD".to_string(),
-        "JPY".to_string(),
-    ];
-
-    // Define user agents
-    let user_agents = vec![
-        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36".to_string(),
-        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/14.1.1 Safari/605.1.15".to_string(),
-        "Mozilla/5.0 (iPhone; CPU iPhone OS 14_6 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/14.0 Mobile/15E148 Safari/604.1".to_string(),
-    ];
-
-    // Generate datasets with different sizes
-    println!("Generating test datasets...");
-    
-    // Small dataset (100 records)
-    let small_dataset = generate_transactions(
-        100,
-        &card_brands,
-        &merchants,
-        &first_names,
-        &last_names,
-        &currencies,
-        &user_agents,
-    );
-    
-    // Medium dataset (250 records)
-    let medium_dataset = generate_transactions(
-        250,
-        &card_brands,
-        &merchants,
-        &first_names,
-        &last_names,
-        &currencies,
-        &user_agents,
-    );
-    
-    // Large dataset (500 records)
-    let large_dataset = generate_transactions(
-        500,
-        &card_brands,
-        &merchants,
-        &first_names,
-        &last_names,
-        &currencies,
-        &user_agents,
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Write the datasets to files
-    println!("Writing datasets to files...");
-    
-    // CSV Files
-    write_transactions_to_csv(&small_dataset, "transactions_100.csv")?;
-    write_transactions_to_csv(&medium_dataset, "transactions_250.csv")?;
-    write_transactions_to_csv(&large_dataset, "transactions_500.csv")?;
-    
-    // JSON Files
-    write_transactions_to_json(&small_dataset, "transactions_100.json")?;
-    write_transactions_to_json(&medium_dataset, "transactions_250.json")?;
-    write_transactions_to_json(&large_dataset, "transactions_500.json")?;
-
-    println!("Done! Generated 6 files:");
-    println!("- CSV files: transactions_100.csv, transactions_250.csv, transactions_500.csv");
-    println!("- JSON files: transactions_100.json, transactions_250.json, transactions_500.json");
+    // Generate into `dir` with the given options
+    fn run(dir: &Path, options: &[&str]) -> io::Result<()> {
+        let dir = dir.to_string_lossy();
+        let args = ["luhnsynth", "-q", "-o", &dir].into_iter().chain(options.iter().copied());
+        generate(&Cli::try_parse_from(args).map_err(io::Error::other)?)
+    }
 
-    Ok(())
+    // Names and contents of the files in a directory
+    fn files(dir: &Path) -> Vec<(PathBuf, Vec<u8>)> {
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                (PathBuf::from(path.file_name().unwrap()), fs::read(&path).unwrap())
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn resumed_runs_write_what_uninterrupted_ones_do() {
+        let dir = env::temp_dir().join(format!("luhnsynth-resume-{}", std::process::id()));
+        let (interrupted, uninterrupted) = (dir.join("interrupted"), dir.join("uninterrupted"));
+        fs::create_dir_all(&interrupted).unwrap();
+        fs::create_dir_all(&uninterrupted).unwrap();
+        let checkpoint = dir.join("ckpt.json");
+        let path = checkpoint.to_string_lossy();
+        let sizes = ["-s", "2500", "-f", "csv,jsonl", "--max-rows-per-file", "1000"];
+        let checkpointed = [&sizes[..], &["--checkpoint", &path]].concat();
+
+        // A directory in the way of the third file stops the run after two
+        let blocker = interrupted.join("transactions_2500-00003.csv");
+        fs::create_dir(&blocker).unwrap();
+        assert!(run(&interrupted, &checkpointed).is_err());
+        let saved = Checkpoint::load(&checkpoint).unwrap().unwrap();
+        assert_eq!((saved.dataset, saved.rows, saved.files), (0, 2000, 2));
+        fs::remove_dir(&blocker).unwrap();
+        run(&interrupted, &checkpointed).unwrap();
+        assert!(!checkpoint.exists());
+
+        // The rows of the seed and date range the checkpoint recorded
+        let (seed, date_to) = (saved.seed.to_string(), saved.date_to.to_string());
+        let seeded = [&sizes[..], &["--seed", &seed, "--date-to", &date_to]].concat();
+        run(&uninterrupted, &seeded).unwrap();
+
+        let written = files(&interrupted);
+        assert_eq!(written.len(), 6);
+        assert!(written == files(&uninterrupted), "resumed files differ");
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,117 @@
+use crate::{config::Profile, metrics};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+};
+
+// Name of the manifest, in the output directory
+pub const FILE_NAME: &str = "manifest.json";
+
+// Provenance of a run for data-delivery pipelines: every file it wrote with
+// its checksum, size and row count, and the seed and profile it was
+// generated from
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    seed: Option<u64>,
+    // SHA-256 of the profile the run used, command line flags included, so
+    // deliveries made with the same settings share it
+    config_sha256: String,
+    files: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize)]
+struct Entry {
+    // Path relative to the output directory, where the manifest is
+    path: String,
+    sha256: String,
+    bytes: u64,
+    // Rows of generated data in the file; empty for files such as dirty-row
+    // manifests and partitions, whose rows aren't counted
+    rows: Option<u64>,
+}
+
+impl Manifest {
+    pub fn new(seed: Option<u64>, profile: &Profile) -> io::Result<Self> {
+        let config = serde_json::to_vec(profile)?;
+        Ok(Self {
+            seed,
+            config_sha256: hex(&Sha256::digest(&config)),
+            files: Vec::new(),
+        })
+    }
+
+    // Add the files of an output, as listed in the log, given the rows
+    // written to it if they're known. Outputs split into shards of
+    // `max_rows` rows share them out in file order, and outputs that aren't
+    // files add nothing.
+    pub fn add(
+        &mut self,
+        dir: &Path,
+        output: &str,
+        rows: Option<u64>,
+        max_rows: Option<usize>,
+    ) -> io::Result<()> {
+        let files = metrics::files(Path::new(output));
+        let single = files.len() == 1 && files[0] == Path::new(output);
+        for (i, path) in files.iter().enumerate() {
+            let rows = match (rows, max_rows) {
+                (Some(rows), _) if single => Some(rows),
+                (Some(rows), Some(max_rows)) => {
+                    let max_rows = max_rows as u64;
+                    Some(rows.saturating_sub(i as u64 * max_rows).min(max_rows))
+                }
+                _ => None,
+            };
+            let (sha256, bytes) = checksum(path)?;
+            self.files.push(Entry {
+                path: path
+                    .strip_prefix(dir)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .into_owned(),
+                sha256,
+                bytes,
+                rows,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut out, self)?;
+        writeln!(out)?;
+        out.flush()
+    }
+}
+
+// SHA-256 and size of a file, read a block at a time
+fn checksum(path: &Path) -> io::Result<(String, u64)> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut bytes = 0;
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        bytes += n as u64;
+    }
+    Ok((hex(&hasher.finalize()), bytes))
+}
+
+// Lowercase hex of a digest or key, as checksums and signatures are written
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        hex.push(DIGITS[usize::from(byte >> 4)] as char);
+        hex.push(DIGITS[usize::from(byte & 0xF)] as char);
+    }
+    hex
+}
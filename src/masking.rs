@@ -0,0 +1,161 @@
+use crate::{luhn, manifest::hex, model::Transaction};
+use hmac::{Hmac, KeyInit, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// How a sensitive field is written out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum MaskMode {
+    // As generated
+    #[default]
+    Clear,
+    // Card numbers keep their first 6 and last 4 digits, with the rest
+    // starred out; other fields are starred out entirely
+    Masked,
+    // Only the first 6 and last 4 digits of the card number
+    First6Last4,
+    // Hex SHA-256 digest of the value
+    Sha256,
+    // Deterministic pseudo-token of the same shape. Card number tokens keep
+    // their first 6 and last 4 digits and always fail the Luhn check, so
+    // they can't be mistaken for real card numbers.
+    Token,
+}
+
+// Masking applied to the sensitive fields of every row written
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaskingConfig {
+    pub card_number: MaskMode,
+    pub cvv: MaskMode,
+    pub card_expiry: MaskMode,
+    pub cardholder_name: MaskMode,
+    pub email: MaskMode,
+    pub phone: MaskMode,
+    // Key that tokens are derived from; the same key and value always give
+    // the same token, so masked datasets can still be joined
+    pub token_key: String,
+}
+
+impl MaskingConfig {
+    // Each field's mode with its name
+    pub fn modes(&self) -> [(&'static str, MaskMode); 6] {
+        [
+            ("card_number", self.card_number),
+            ("cvv", self.cvv),
+            ("card_expiry", self.card_expiry),
+            ("cardholder_name", self.cardholder_name),
+            ("email", self.email),
+            ("phone", self.phone),
+        ]
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.modes()
+            .iter()
+            .any(|(_, mode)| *mode != MaskMode::Clear)
+    }
+
+    // Mask the sensitive fields of a row in place
+    pub fn apply(&self, tx: &mut Transaction) {
+        let fields = [
+            ("card_number", self.card_number, Some(&mut tx.card_number)),
+            // Network tokens are card numbers too and are masked the same way
+            ("card_number", self.card_number, tx.token_pan.as_mut()),
+            ("cvv", self.cvv, tx.cvv.as_mut()),
+            ("card_expiry", self.card_expiry, Some(&mut tx.card_expiry)),
+            (
+                "cardholder_name",
+                self.cardholder_name,
+                Some(&mut tx.cardholder_name),
+            ),
+            ("email", self.email, Some(&mut tx.email)),
+            ("phone", self.phone, Some(&mut tx.phone)),
+        ];
+        for (field, mode, value) in fields {
+            if let Some(value) = value
+                && mode != MaskMode::Clear
+            {
+                *value = self.mask(field, mode, value).into();
+            }
+        }
+        // Tracks hold the card number in clear, so they go whenever it's
+        // masked
+        if self.card_number != MaskMode::Clear {
+            tx.track1 = None;
+            tx.track2 = None;
+        }
+    }
+
+    fn mask(&self, field: &str, mode: MaskMode, value: &str) -> String {
+        // Card numbers long enough to keep 6 and 4 digits with some hidden
+        // between them; shorter ones are hidden entirely
+        let pan = field == "card_number" && value.len() > 10;
+        match mode {
+            MaskMode::Clear => value.to_string(),
+            MaskMode::Masked if pan => format!(
+                "{}{}{}",
+                &value[..6],
+                "*".repeat(value.len() - 10),
+                &value[value.len() - 4..]
+            ),
+            MaskMode::Masked => value
+                .chars()
+                .map(|c| if c.is_alphanumeric() { '*' } else { c })
+                .collect(),
+            MaskMode::First6Last4 if pan => format!("{}{}", &value[..6], &value[value.len() - 4..]),
+            MaskMode::First6Last4 => "*".repeat(value.chars().count()),
+            MaskMode::Sha256 => hex(&Sha256::digest(value.as_bytes())),
+            MaskMode::Token => {
+                let replaced = if pan {
+                    6..value.len() - 4
+                } else {
+                    0..value.len()
+                };
+                let mut token = self.token(field, value, replaced);
+                // Nudging a single digit always changes the Luhn checksum
+                if pan && luhn::is_valid(&token) {
+                    let i = value.len() - 5;
+                    let digit = (token.as_bytes()[i] - b'0' + 1) % 10;
+                    token.replace_range(i..=i, &digit.to_string());
+                }
+                token
+            }
+        }
+    }
+
+    // Replace the digits and letters of `value` within `range` with ones
+    // derived from the value, keeping case, separators and length. Letters
+    // of other scripts become Latin ones, so no name is left readable.
+    fn token(&self, field: &str, value: &str, range: std::ops::Range<usize>) -> String {
+        let mut stream = Vec::new();
+        let mut block = 0u32;
+        value
+            .char_indices()
+            .map(|(i, c)| {
+                if !range.contains(&i) || !c.is_alphanumeric() {
+                    return c;
+                }
+                if stream.is_empty() {
+                    let mut mac = Hmac::<Sha256>::new_from_slice(self.token_key.as_bytes())
+                        .expect("HMAC accepts keys of any length");
+                    mac.update(field.as_bytes());
+                    mac.update(b":");
+                    mac.update(value.as_bytes());
+                    mac.update(&block.to_be_bytes());
+                    stream = mac.finalize().into_bytes().to_vec();
+                    block += 1;
+                }
+                let byte = stream.pop().unwrap();
+                if c.is_ascii_digit() {
+                    (b'0' + byte % 10) as char
+                } else if c.is_uppercase() {
+                    (b'A' + byte % 26) as char
+                } else {
+                    (b'a' + byte % 26) as char
+                }
+            })
+            .collect()
+    }
+}
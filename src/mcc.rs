@@ -0,0 +1,187 @@
+// Bundled ISO 18245 merchant category codes with their descriptions,
+// covering the codes most commonly seen in card acquiring
+pub const MCC_TABLE: &[(&str, &str)] = &[
+    ("0742", "Veterinary Services"),
+    ("0763", "Agricultural Cooperatives"),
+    ("0780", "Landscaping and Horticultural Services"),
+    ("1520", "General Contractors - Residential and Commercial"),
+    ("1711", "Heating, Plumbing, and Air Conditioning Contractors"),
+    ("1731", "Electrical Contractors"),
+    ("1799", "Special Trade Contractors"),
+    ("2741", "Miscellaneous Publishing and Printing"),
+    ("2842", "Specialty Cleaning, Polishing, and Sanitation Preparations"),
+    ("3000", "United Airlines"),
+    ("3351", "Affiliated Auto Rental"),
+    ("3501", "Holiday Inns"),
+    ("4011", "Railroads"),
+    ("4111", "Local and Suburban Commuter Passenger Transportation"),
+    ("4112", "Passenger Railways"),
+    ("4121", "Taxicabs and Limousines"),
+    ("4131", "Bus Lines"),
+    ("4214", "Motor Freight Carriers and Trucking"),
+    ("4215", "Courier Services"),
+    ("4225", "Public Warehousing and Storage"),
+    ("4411", "Steamship and Cruise Lines"),
+    ("4457", "Boat Rentals and Leases"),
+    ("4468", "Marinas, Marine Service, and Supplies"),
+    ("4511", "Airlines and Air Carriers"),
+    ("4582", "Airports, Flying Fields, and Airport Terminals"),
+    ("4722", "Travel Agencies and Tour Operators"),
+    ("4784", "Tolls and Bridge Fees"),
+    ("4789", "Transportation Services"),
+    ("4812", "Telecommunication Equipment and Telephone Sales"),
+    ("4814", "Telecommunication Services"),
+    ("4816", "Computer Network Services"),
+    ("4829", "Wire Transfers and Money Orders"),
+    ("4899", "Cable, Satellite, and Other Pay Television and Radio"),
+    ("4900", "Utilities"),
+    ("5013", "Motor Vehicle Supplies and New Parts"),
+    ("5021", "Office and Commercial Furniture"),
+    ("5045", "Computers, Peripherals, and Software"),
+    ("5047", "Medical, Dental, Ophthalmic, and Hospital Equipment"),
+    ("5065", "Electrical Parts and Equipment"),
+    ("5072", "Hardware, Equipment, and Supplies"),
+    ("5094", "Precious Stones and Metals, Watches and Jewelry"),
+    ("5111", "Stationery, Office Supplies, Printing and Writing Paper"),
+    ("5122", "Drugs, Drug Proprietaries, and Druggist Sundries"),
+    ("5137", "Men's, Women's, and Children's Uniforms"),
+    ("5192", "Books, Periodicals, and Newspapers"),
+    ("5200", "Home Supply Warehouse Stores"),
+    ("5211", "Lumber and Building Materials Stores"),
+    ("5251", "Hardware Stores"),
+    ("5261", "Nurseries and Lawn and Garden Supply Stores"),
+    ("5300", "Wholesale Clubs"),
+    ("5309", "Duty Free Stores"),
+    ("5310", "Discount Stores"),
+    ("5311", "Department Stores"),
+    ("5331", "Variety Stores"),
+    ("5399", "Miscellaneous General Merchandise"),
+    ("5411", "Grocery Stores and Supermarkets"),
+    ("5422", "Freezer and Locker Meat Provisioners"),
+    ("5441", "Candy, Nut, and Confectionery Stores"),
+    ("5451", "Dairy Products Stores"),
+    ("5462", "Bakeries"),
+    ("5499", "Miscellaneous Food Stores - Convenience Stores and Specialty Markets"),
+    ("5511", "Car and Truck Dealers (New and Used)"),
+    ("5521", "Car and Truck Dealers (Used Only)"),
+    ("5532", "Automotive Tire Stores"),
+    ("5533", "Automotive Parts and Accessories Stores"),
+    ("5541", "Service Stations"),
+    ("5542", "Automated Fuel Dispensers"),
+    ("5571", "Motorcycle Shops and Dealers"),
+    ("5611", "Men's and Boys' Clothing and Accessories Stores"),
+    ("5621", "Women's Ready-to-Wear Stores"),
+    ("5631", "Women's Accessory and Specialty Shops"),
+    ("5641", "Children's and Infants' Wear Stores"),
+    ("5651", "Family Clothing Stores"),
+    ("5655", "Sports and Riding Apparel Stores"),
+    ("5661", "Shoe Stores"),
+    ("5691", "Men's and Women's Clothing Stores"),
+    ("5712", "Furniture, Home Furnishings, and Equipment Stores"),
+    ("5722", "Household Appliance Stores"),
+    ("5732", "Electronics Stores"),
+    ("5733", "Music Stores - Musical Instruments, Pianos, and Sheet Music"),
+    ("5734", "Computer Software Stores"),
+    ("5735", "Record Stores"),
+    ("5811", "Caterers"),
+    ("5812", "Eating Places and Restaurants"),
+    ("5813", "Drinking Places (Alcoholic Beverages)"),
+    ("5814", "Fast Food Restaurants"),
+    ("5815", "Digital Goods - Media, Books, Movies, Music"),
+    ("5816", "Digital Goods - Games"),
+    ("5817", "Digital Goods - Applications"),
+    ("5818", "Digital Goods - Large Digital Goods Merchant"),
+    ("5912", "Drug Stores and Pharmacies"),
+    ("5921", "Package Stores - Beer, Wine, and Liquor"),
+    ("5932", "Antique Shops"),
+    ("5941", "Sporting Goods Stores"),
+    ("5942", "Book Stores"),
+    ("5943", "Stationery, Office, and School Supply Stores"),
+    ("5944", "Jewelry, Watch, Clock, and Silverware Stores"),
+    ("5945", "Hobby, Toy, and Game Shops"),
+    ("5947", "Gift, Card, Novelty, and Souvenir Shops"),
+    ("5949", "Sewing, Needlework, Fabric, and Piece Goods Stores"),
+    ("5960", "Direct Marketing - Insurance Services"),
+    ("5961", "Mail Order Houses"),
+    ("5964", "Direct Marketing - Catalog Merchant"),
+    ("5965", "Direct Marketing - Combination Catalog and Retail Merchant"),
+    ("5967", "Direct Marketing - Inbound Teleservices Merchant"),
+    ("5968", "Direct Marketing - Continuity/Subscription Merchant"),
+    ("5969", "Direct Marketing - Other Direct Marketers"),
+    ("5977", "Cosmetic Stores"),
+    ("5992", "Florists"),
+    ("5993", "Cigar Stores and Stands"),
+    ("5994", "News Dealers and Newsstands"),
+    ("5995", "Pet Shops, Pet Food, and Supplies"),
+    ("5999", "Miscellaneous and Specialty Retail Stores"),
+    ("6010", "Financial Institutions - Manual Cash Disbursements"),
+    ("6011", "Financial Institutions - Automated Cash Disbursements"),
+    ("6012", "Financial Institutions - Merchandise and Services"),
+    ("6051", "Non-Financial Institutions - Foreign Currency, Money Orders, Stored Value"),
+    ("6211", "Security Brokers and Dealers"),
+    ("6300", "Insurance Sales, Underwriting, and Premiums"),
+    ("6513", "Real Estate Agents and Managers - Rentals"),
+    ("7011", "Lodging - Hotels, Motels, and Resorts"),
+    ("7012", "Timeshares"),
+    ("7032", "Sporting and Recreational Camps"),
+    ("7210", "Laundry, Cleaning, and Garment Services"),
+    ("7221", "Photographic Studios"),
+    ("7230", "Beauty and Barber Shops"),
+    ("7261", "Funeral Services and Crematories"),
+    ("7298", "Health and Beauty Spas"),
+    ("7299", "Miscellaneous Personal Services"),
+    ("7311", "Advertising Services"),
+    ("7349", "Cleaning, Maintenance, and Janitorial Services"),
+    ("7372", "Computer Programming, Data Processing, and Integrated Systems Design"),
+    ("7399", "Business Services"),
+    ("7512", "Automobile Rental Agency"),
+    ("7523", "Parking Lots and Garages"),
+    ("7538", "Automotive Service Shops"),
+    ("7542", "Car Washes"),
+    ("7549", "Towing Services"),
+    ("7622", "Electronics Repair Shops"),
+    ("7832", "Motion Picture Theaters"),
+    ("7841", "Video Tape Rental Stores"),
+    ("7922", "Theatrical Producers and Ticket Agencies"),
+    ("7941", "Commercial Sports, Professional Sports Clubs, and Promoters"),
+    ("7991", "Tourist Attractions and Exhibits"),
+    ("7992", "Public Golf Courses"),
+    ("7994", "Video Game Arcades"),
+    ("7995", "Betting, Including Lottery Tickets and Casino Gaming Chips"),
+    ("7996", "Amusement Parks, Carnivals, and Circuses"),
+    ("7997", "Membership Clubs (Sports, Recreation, Athletic), Country Clubs"),
+    ("7999", "Recreation Services"),
+    ("8011", "Doctors and Physicians"),
+    ("8021", "Dentists and Orthodontists"),
+    ("8043", "Opticians, Optical Goods, and Eyeglasses"),
+    ("8062", "Hospitals"),
+    ("8099", "Medical Services and Health Practitioners"),
+    ("8111", "Legal Services and Attorneys"),
+    ("8211", "Elementary and Secondary Schools"),
+    ("8220", "Colleges, Universities, and Professional Schools"),
+    ("8299", "Schools and Educational Services"),
+    ("8398", "Charitable and Social Service Organizations"),
+    ("8641", "Civic, Social, and Fraternal Associations"),
+    ("8651", "Political Organizations"),
+    ("8699", "Membership Organizations"),
+    ("8931", "Accounting, Auditing, and Bookkeeping Services"),
+    ("8999", "Professional Services"),
+    ("9211", "Court Costs, Including Alimony and Child Support"),
+    ("9222", "Fines"),
+    ("9311", "Tax Payments"),
+    ("9399", "Government Services"),
+    ("9402", "Postal Services - Government Only"),
+];
+
+// Description of a merchant category code, if it is in the bundled table
+pub fn description(code: &str) -> Option<&'static str> {
+    MCC_TABLE
+        .binary_search_by(|(mcc, _)| (*mcc).cmp(code))
+        .ok()
+        .map(|i| MCC_TABLE[i].1)
+}
+
+// Whether a string has the shape of a merchant category code
+pub fn is_well_formed(code: &str) -> bool {
+    code.len() == 4 && code.chars().all(|c| c.is_ascii_digit())
+}
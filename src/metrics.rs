@@ -0,0 +1,242 @@
+use crate::model::Transaction;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+// How often the progress bar is redrawn
+const REDRAW: Duration = Duration::from_millis(100);
+
+// Width of the bar itself, in characters
+const BAR_WIDTH: usize = 30;
+
+// A progress bar on stderr with the rate and time left, for datasets that
+// take a while. It's only drawn when stderr is a terminal, so logs and
+// pipes don't fill up with redraws.
+pub struct Progress {
+    total: usize,
+    done: usize,
+    start: Instant,
+    drawn: Option<Instant>,
+    visible: bool,
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            done: 0,
+            start: Instant::now(),
+            drawn: None,
+            visible: io::stderr().is_terminal(),
+        }
+    }
+
+    // A bar that is never drawn
+    pub fn hidden() -> Self {
+        Self {
+            visible: false,
+            ..Self::new(0)
+        }
+    }
+
+    // Count a transaction, redrawing the bar every so often
+    pub fn tick(&mut self) {
+        self.done += 1;
+        if self.visible && self.drawn.is_none_or(|at| at.elapsed() >= REDRAW) {
+            self.draw();
+        }
+    }
+
+    // Draw the bar a last time and move past it
+    pub fn finish(&mut self) {
+        if self.visible {
+            self.draw();
+            eprintln!();
+        }
+    }
+
+    fn draw(&mut self) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let fraction = (self.done as f64 / self.total.max(1) as f64).min(1.0);
+        let filled = (fraction * BAR_WIDTH as f64) as usize;
+        let rate = if elapsed > 0.0 {
+            self.done as f64 / elapsed
+        } else {
+            0.0
+        };
+        let eta = if rate > 0.0 {
+            self.total.saturating_sub(self.done) as f64 / rate
+        } else {
+            0.0
+        };
+        eprint!(
+            "\r[{}{}] {}/{} {:.0} rows/s ETA {}",
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            self.done,
+            self.total,
+            rate,
+            clock(eta),
+        );
+        let _ = io::stderr().flush();
+        self.drawn = Some(Instant::now());
+    }
+}
+
+// Seconds as minutes and seconds, or hours, minutes and seconds
+fn clock(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+// What a run generated: the rows written with their card brands and
+// statuses, the bytes written to the files, stdout and sinks they went to,
+// and how long it took
+#[derive(Debug, Default, Serialize)]
+pub struct Metrics {
+    pub rows: u64,
+    pub bytes: u64,
+    pub elapsed_seconds: f64,
+    pub rows_per_second: f64,
+    pub brands: BTreeMap<String, u64>,
+    pub statuses: BTreeMap<String, u64>,
+    pub outputs: Vec<String>,
+}
+
+impl Metrics {
+    // Count a row as it's written
+    pub fn record(&mut self, tx: &Transaction) {
+        self.rows += 1;
+        count(&mut self.brands, &tx.card_brand);
+        count(&mut self.statuses, tx.status.as_str());
+    }
+
+    // Count the bytes an output wrote once it's finished
+    pub fn wrote(&mut self, bytes: u64) {
+        self.bytes += bytes;
+    }
+
+    // Close the run after its outputs have been written
+    pub fn finish(&mut self, elapsed: Duration, outputs: &[String]) {
+        self.elapsed_seconds = elapsed.as_secs_f64();
+        self.rows_per_second = if self.elapsed_seconds > 0.0 {
+            self.rows as f64 / self.elapsed_seconds
+        } else {
+            0.0
+        };
+        self.outputs = outputs.to_vec();
+    }
+
+    // The stats block printed at the end of a run
+    pub fn report(&self) -> String {
+        let mut report = String::from("Stats:\n");
+        let _ = writeln!(report, "  rows written: {}", self.rows);
+        let _ = writeln!(report, "  bytes written: {}", self.bytes);
+        let _ = writeln!(
+            report,
+            "  elapsed: {:.2}s ({:.0} rows/s)",
+            self.elapsed_seconds, self.rows_per_second
+        );
+        for (title, counts) in [("brand", &self.brands), ("status", &self.statuses)] {
+            let _ = writeln!(report, "  by {}:", title);
+            for (name, count) in counts {
+                let _ = writeln!(report, "    {}: {}", name, count);
+            }
+        }
+        report
+    }
+
+    // Write the metrics as JSON, for automation to pick up
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = io::BufWriter::new(fs::File::create(path)?);
+        serde_json::to_writer_pretty(&mut out, self)?;
+        writeln!(out)?;
+        out.flush()
+    }
+}
+
+// Add one to a count, only allocating its key the first time it's seen
+fn count(counts: &mut BTreeMap<String, u64>, key: &str) {
+    match counts.get_mut(key) {
+        Some(count) => *count += 1,
+        None => {
+            counts.insert(key.to_string(), 1);
+        }
+    }
+}
+
+// Bytes on disk under an output. Outputs that aren't files, like database
+// tables, count for nothing.
+pub fn disk_usage(output: &Path) -> u64 {
+    files(output)
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+// Files making up an output: a file, every file below a directory, or every
+// file matching a path with `*` in its components, as shards and
+// partitions are listed
+pub fn files(output: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::new()];
+    for component in output.components() {
+        let name = component.as_os_str().to_string_lossy();
+        let Some((prefix, suffix)) = name.split_once('*') else {
+            for path in &mut paths {
+                path.push(component);
+            }
+            continue;
+        };
+        paths = paths
+            .iter()
+            .flat_map(fs::read_dir)
+            .flatten()
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                name.len() >= prefix.len() + suffix.len()
+                    && name.starts_with(prefix)
+                    && name.ends_with(suffix)
+            })
+            .map(|entry| entry.path())
+            .collect();
+    }
+    paths.sort();
+    let mut files = Vec::new();
+    for path in paths {
+        tree(path, &mut files);
+    }
+    files
+}
+
+// Add a file, or every file below a directory, in name order
+fn tree(path: PathBuf, files: &mut Vec<PathBuf>) {
+    match fs::metadata(&path) {
+        Ok(meta) if meta.is_dir() => {
+            let mut entries: Vec<PathBuf> = fs::read_dir(&path)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|entry| entry.path())
+                .collect();
+            entries.sort();
+            for entry in entries {
+                tree(entry, files);
+            }
+        }
+        Ok(_) => files.push(path),
+        Err(_) => {}
+    }
+}
@@ -0,0 +1,60 @@
+use crate::model::Transaction;
+use serde::{Deserialize, Serialize};
+
+// Settings for producing model training datasets
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MlConfig {
+    // Target share of rows labelled as fraud. When set, fraud injection
+    // adapts to hold this class balance instead of using `fraud.rate`.
+    pub label_ratio: Option<f64>,
+    // Share of rows written to the train split, the rest going to test.
+    // When unset, a single unsplit file is written.
+    pub train_fraction: Option<f64>,
+}
+
+// Half of a train/test split
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    Train,
+    Test,
+}
+
+impl Split {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Split::Train => "train",
+            Split::Test => "test",
+        }
+    }
+}
+
+// Assigns streamed rows to train or test so that fraud and non-fraud rows
+// each keep the requested train fraction
+pub struct StratifiedSplitter {
+    train_fraction: f64,
+    seen: [usize; 2],
+    train: [usize; 2],
+}
+
+impl StratifiedSplitter {
+    pub fn new(train_fraction: f64) -> Self {
+        Self {
+            train_fraction,
+            seen: [0; 2],
+            train: [0; 2],
+        }
+    }
+
+    pub fn assign(&mut self, tx: &Transaction) -> Split {
+        let class = usize::from(tx.is_fraud);
+        self.seen[class] += 1;
+        // A row goes to train whenever its class has fallen behind its quota
+        if (self.train[class] as f64) < self.train_fraction * self.seen[class] as f64 {
+            self.train[class] += 1;
+            Split::Train
+        } else {
+            Split::Test
+        }
+    }
+}
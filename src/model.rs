@@ -0,0 +1,427 @@
+use crate::{
+    address::Address,
+    bin::CardType,
+    device::Device,
+    fraud::FraudPattern,
+    preauth::PreauthStep,
+    subscription::Initiator,
+    text::{Text, TextBuf},
+    three_ds::AuthenticationResult,
+    wallet::{Wallet, WalletToken},
+};
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Transaction status enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum TransactionStatus {
+    #[serde(rename = "approved")]
+    Approved,
+    #[serde(rename = "declined")]
+    Declined,
+    #[serde(rename = "pending")]
+    Pending,
+    #[serde(rename = "refunded")]
+    Refunded,
+}
+
+// Helper function to implement random distribution for TransactionStatus
+impl Distribution<TransactionStatus> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> TransactionStatus {
+        match rng.gen_range(0..4) {
+            0 => TransactionStatus::Approved,
+            1 => TransactionStatus::Declined,
+            2 => TransactionStatus::Pending,
+            _ => TransactionStatus::Refunded,
+        }
+    }
+}
+
+impl TransactionStatus {
+    pub const ALL: [TransactionStatus; 4] = [
+        TransactionStatus::Approved,
+        TransactionStatus::Declined,
+        TransactionStatus::Pending,
+        TransactionStatus::Refunded,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionStatus::Approved => "approved",
+            TransactionStatus::Declined => "declined",
+            TransactionStatus::Pending => "pending",
+            TransactionStatus::Refunded => "refunded",
+        }
+    }
+}
+
+// Decline reason enum (Option to handle null cases)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DeclineReason {
+    #[serde(rename = "insufficient_funds")]
+    InsufficientFunds,
+    #[serde(rename = "card_expired")]
+    CardExpired,
+    #[serde(rename = "invalid_card")]
+    InvalidCard,
+    #[serde(rename = "suspicious_activity")]
+    SuspiciousActivity,
+}
+
+// Helper function to implement random distribution for DeclineReason
+impl Distribution<DeclineReason> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> DeclineReason {
+        match rng.gen_range(0..4) {
+            0 => DeclineReason::InsufficientFunds,
+            1 => DeclineReason::CardExpired,
+            2 => DeclineReason::InvalidCard,
+            _ => DeclineReason::SuspiciousActivity,
+        }
+    }
+}
+
+impl DeclineReason {
+    pub const ALL: [DeclineReason; 4] = [
+        DeclineReason::InsufficientFunds,
+        DeclineReason::CardExpired,
+        DeclineReason::InvalidCard,
+        DeclineReason::SuspiciousActivity,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeclineReason::InsufficientFunds => "insufficient_funds",
+            DeclineReason::CardExpired => "card_expired",
+            DeclineReason::InvalidCard => "invalid_card",
+            DeclineReason::SuspiciousActivity => "suspicious_activity",
+        }
+    }
+}
+
+// Stage of a payment in lifecycle mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleEvent {
+    Authorization,
+    Capture,
+    Clearing,
+    Settlement,
+    Refund,
+}
+
+impl LifecycleEvent {
+    pub const ALL: [LifecycleEvent; 5] = [
+        LifecycleEvent::Authorization,
+        LifecycleEvent::Capture,
+        LifecycleEvent::Clearing,
+        LifecycleEvent::Settlement,
+        LifecycleEvent::Refund,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LifecycleEvent::Authorization => "authorization",
+            LifecycleEvent::Capture => "capture",
+            LifecycleEvent::Clearing => "clearing",
+            LifecycleEvent::Settlement => "settlement",
+            LifecycleEvent::Refund => "refund",
+        }
+    }
+}
+
+// How the card was presented to the merchant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    // Online purchase
+    Ecommerce,
+    // Chip card inserted at a terminal
+    PosChip,
+    // Card or phone tapped on a terminal
+    Contactless,
+    // Magnetic stripe swiped at a terminal
+    Magstripe,
+    // Mail or telephone order, keyed in by the merchant
+    Moto,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 5] = [
+        Channel::Ecommerce,
+        Channel::PosChip,
+        Channel::Contactless,
+        Channel::Magstripe,
+        Channel::Moto,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Ecommerce => "ecommerce",
+            Channel::PosChip => "pos_chip",
+            Channel::Contactless => "contactless",
+            Channel::Magstripe => "magstripe",
+            Channel::Moto => "moto",
+        }
+    }
+
+    // PAN entry mode, the first two digits of ISO 8583 field 22
+    pub fn pos_entry_mode(&self) -> &'static str {
+        match self {
+            Channel::Ecommerce => "81",
+            Channel::PosChip => "05",
+            Channel::Contactless => "07",
+            Channel::Magstripe => "90",
+            Channel::Moto => "01",
+        }
+    }
+
+    // Whether the card is at a terminal in the store. Card-present
+    // transactions have a terminal but no CVV, IP address or device.
+    pub fn is_card_present(&self) -> bool {
+        matches!(
+            self,
+            Channel::PosChip | Channel::Contactless | Channel::Magstripe
+        )
+    }
+}
+
+// Card expiry struct
+#[derive(Debug, Clone)]
+pub struct CardExpiry {
+    pub month: u8,
+    pub year: u16,
+}
+
+impl CardExpiry {
+    pub fn new(month: u8, year: u16) -> Self {
+        Self { month, year }
+    }
+}
+
+impl fmt::Display for CardExpiry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}/{:02}", self.month, self.year % 100)
+    }
+}
+
+impl From<CardExpiry> for Text {
+    fn from(expiry: CardExpiry) -> Self {
+        let mut text = TextBuf::new();
+        text.push_digits(expiry.month.into(), 2);
+        text.push('/');
+        text.push_digits((expiry.year % 100).into(), 2);
+        text.into()
+    }
+}
+
+// A cardholder with a stable card, device and usual IP address, so repeat
+// customers can be followed across transactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Customer {
+    pub customer_id: Text,
+    pub name: Text,
+    // Email address made up from the name, and mobile number in E.164
+    // format with the calling code of the billing country
+    pub email: Text,
+    pub phone: Text,
+    pub card_brand: Text,
+    pub card_number: Text,
+    pub card_expiry: Text,
+    pub cvv: Text,
+    // Billing address, in the country the usual IP address is located in
+    pub billing_address: Address,
+    pub device: Device,
+    pub ip_address: Text,
+    // The card as provisioned into the wallet on the customer's device;
+    // unset when only test cards are used, since a device PAN would be a
+    // generated card number
+    pub wallet: Option<WalletToken>,
+    // Persona the customer was given, and the devices they shop online from
+    // besides their main one
+    #[serde(default)]
+    pub persona: Option<Text>,
+    #[serde(default)]
+    pub other_devices: Vec<Device>,
+}
+
+// Main transaction struct
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transaction {
+    pub transaction_id: Text,
+    // Lifecycle stage of this row; only set in lifecycle mode
+    pub event_type: Option<LifecycleEvent>,
+    pub transaction_date: Text,
+    pub status: TransactionStatus,
+    pub decline_reason: Option<DeclineReason>,
+    // ISO 8583 response code of the authorization, and the authorization
+    // code the issuer gave when it was approved
+    pub response_code: Text,
+    pub auth_code: Option<Text>,
+    // For refunds, the approved transaction being refunded, for retries of a
+    // declined subscription renewal, that renewal, for later installments,
+    // the first installment of the plan, and for incremental authorizations
+    // and captures, the pre-authorization
+    pub original_transaction_id: Option<Text>,
+    // Subscription the row charges, and whether it was the cardholder's
+    // sign-up or a charge the merchant made later
+    pub subscription_id: Option<Text>,
+    pub initiator: Option<Initiator>,
+    // Position of the row in the installment plan the purchase is paid in,
+    // and the number of installments in the plan
+    pub installment_number: Option<u32>,
+    pub installment_count: Option<u32>,
+    // Order of a pre-authorized hotel stay or car rental, and the step of it
+    // the row is
+    pub order_reference: Option<Text>,
+    pub preauth_step: Option<PreauthStep>,
+    pub customer_id: Text,
+    pub cardholder_name: Text,
+    pub email: Text,
+    pub phone: Text,
+    pub card_number: Text,
+    pub card_brand: Text,
+    pub card_expiry: Text,
+    // Unset in store, where the CVV isn't entered
+    pub cvv: Option<Text>,
+    // Issuer attributes from the BIN table; unset when the card's BIN isn't
+    // in it
+    pub issuer_bank: Option<Text>,
+    pub card_country: Option<Text>,
+    pub card_type: Option<CardType>,
+    pub billing_street: Text,
+    pub billing_city: Text,
+    pub billing_region: Text,
+    pub billing_postal_code: Text,
+    pub billing_country: Text,
+    // Where the goods of an online purchase are sent; unset in store
+    pub shipping_street: Option<Text>,
+    pub shipping_city: Option<Text>,
+    pub shipping_region: Option<Text>,
+    pub shipping_postal_code: Option<Text>,
+    pub shipping_country: Option<Text>,
+    pub amount: f64,
+    // For refunds, the amount returned, and what is left of the original
+    // transaction to refund afterwards
+    pub refund_amount: Option<f64>,
+    pub remaining_amount: Option<f64>,
+    pub currency: Text,
+    // Dynamic currency conversion of a purchase the cardholder paid in their
+    // card's currency: the amount and currency the merchant priced it in and
+    // is paid in, the amount and currency the cardholder is billed, the
+    // mid-market rate between them and the markup on it in percent
+    pub original_amount: Option<f64>,
+    pub original_currency: Option<Text>,
+    pub billing_amount: Option<f64>,
+    pub billing_currency: Option<Text>,
+    pub fx_rate: Option<f64>,
+    pub fx_markup: Option<f64>,
+    pub merchant_name: Text,
+    pub merchant_id: Text,
+    // Text the cardholder sees for the merchant on their statement
+    pub statement_descriptor: Text,
+    pub merchant_category: Text,
+    pub mcc: Option<Text>,
+    // Acquirer and processor of the merchant, and for approvals and refunds
+    // the merchant's daily batch and the day it settled (YYYY-MM-DD)
+    pub acquirer_id: Text,
+    pub processor: Text,
+    pub batch_id: Option<Text>,
+    pub settlement_date: Option<Text>,
+    pub payment_method: Text,
+    pub channel: Channel,
+    // PAN entry mode code of the channel
+    pub pos_entry_mode: Text,
+    // Terminal the card was presented at; only set for card-present
+    // transactions
+    pub terminal_id: Option<Text>,
+    // Track 1 and Track 2 data the terminal read from the card, with their
+    // sentinels and LRC; only set for card-present transactions of profiles
+    // with `track_data`
+    pub track1: Option<Text>,
+    pub track2: Option<Text>,
+    // EMV data of the card's chip application, in hex: the application ID
+    // (tag 4F), the authorization request cryptogram (9F26), terminal
+    // verification results (95) and application transaction counter (9F36);
+    // only set for chip and contactless transactions of profiles with `emv`
+    pub emv_aid: Option<Text>,
+    pub emv_cryptogram: Option<Text>,
+    pub emv_tvr: Option<Text>,
+    pub emv_atc: Option<Text>,
+    // Wallet of a tokenized payment, with the wallet identifier, the device
+    // PAN used in place of the card number and the token requestor ID
+    pub wallet: Option<Wallet>,
+    pub wallet_id: Option<Text>,
+    pub token_pan: Option<Text>,
+    pub token_requestor_id: Option<Text>,
+    // 3-D Secure authentication of an online card payment: the protocol
+    // version, ECI, result and, when authenticated, the CAVV
+    pub three_ds_version: Option<Text>,
+    pub eci: Option<Text>,
+    pub authentication_result: Option<AuthenticationResult>,
+    pub cavv: Option<Text>,
+    // Address and CVV verification results of a card-not-present transaction
+    pub avs_result: Option<Text>,
+    pub cvv_result: Option<Text>,
+    // The IP address and device are only known for online purchases
+    pub ip_address: Option<Text>,
+    // Country the IP address is located in
+    pub ip_country: Option<Text>,
+    pub device_id: Option<Text>,
+    // Attributes of the device, consistent with its user agent
+    pub device_platform: Option<Text>,
+    pub device_os: Option<Text>,
+    pub device_browser: Option<Text>,
+    pub screen_resolution: Option<Text>,
+    pub device_fingerprint: Option<Text>,
+    pub user_agent: Option<Text>,
+    // Ground-truth fraud label and the pattern that produced it
+    pub is_fraud: bool,
+    pub fraud_pattern: Option<FraudPattern>,
+    // Persona of the customer, when the pool is split between personas
+    pub persona: Option<Text>,
+    // Values of the profile's custom fields, by name in profile order
+    #[serde(flatten)]
+    pub custom: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Transaction {
+    // Whether the row only holds funds on the card: a pre-authorization or
+    // incremental authorization, which the final capture settles instead
+    pub fn is_hold(&self) -> bool {
+        matches!(
+            self.preauth_step,
+            Some(PreauthStep::Preauth | PreauthStep::Incremental)
+        )
+    }
+
+    // Set the response code for the row's status and decline reason, with a
+    // new authorization code when it was approved
+    pub fn respond<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let code = match (self.status, &self.decline_reason) {
+            (TransactionStatus::Declined, Some(DeclineReason::InsufficientFunds)) => "51",
+            (TransactionStatus::Declined, Some(DeclineReason::CardExpired)) => "54",
+            (TransactionStatus::Declined, Some(DeclineReason::InvalidCard)) => "14",
+            (TransactionStatus::Declined, Some(DeclineReason::SuspiciousActivity)) => "59",
+            (TransactionStatus::Declined, None) => "05",
+            (TransactionStatus::Pending, _) => "09",
+            // Refunds, and payments refunded later, were approved when authorized
+            (TransactionStatus::Approved | TransactionStatus::Refunded, _) => "00",
+        };
+        self.response_code = Text::from_static(code);
+        self.auth_code = (code == "00").then(|| gen_auth_code(rng));
+    }
+}
+
+// Six uppercase letters and digits, as issuers hand out authorization codes
+fn gen_auth_code<R: Rng + ?Sized>(rng: &mut R) -> Text {
+    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    let mut code = TextBuf::new();
+    for _ in 0..6 {
+        code.push(CHARS[rng.gen_range(0..CHARS.len())] as char);
+    }
+    code.into()
+}
@@ -0,0 +1,116 @@
+use crate::currency;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Write as _},
+    ops::{Add, Sub},
+};
+
+// How amounts are written to text output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum AmountFormat {
+    // Major units with as many decimals as the currency has (`12.34`,
+    // `4339`, `1.250`)
+    #[default]
+    Decimal,
+    // Whole minor units (`1234`, `4339`, `1250`)
+    MinorUnits,
+}
+
+// An amount of money as a whole number of the currency's minor units, so
+// splitting and summing amounts never loses or gains a cent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Money {
+    pub minor_units: i64,
+    pub exponent: u32,
+}
+
+impl Money {
+    pub fn new(minor_units: i64, currency: &str) -> Self {
+        Self {
+            minor_units,
+            exponent: currency::exponent(currency),
+        }
+    }
+
+    // The amount in the currency nearest to one in major units
+    pub fn from_major(amount: f64, currency: &str) -> Self {
+        let exponent = currency::exponent(currency);
+        Self {
+            minor_units: (amount * 10f64.powi(exponent as i32)).round() as i64,
+            exponent,
+        }
+    }
+
+    pub fn to_major(self) -> f64 {
+        self.minor_units as f64 / 10f64.powi(self.exponent as i32)
+    }
+
+    // The amount multiplied by a factor, rounded to the nearest minor unit
+    pub fn scale(self, factor: f64) -> Self {
+        Self {
+            minor_units: (self.minor_units as f64 * factor).round() as i64,
+            ..self
+        }
+    }
+
+    // The amount as text in the given format
+    pub fn format(self, format: AmountFormat) -> String {
+        let mut text = String::new();
+        self.write_to(&mut text, format);
+        text
+    }
+
+    // Append the amount as text in the given format
+    pub fn write_to(self, out: &mut String, format: AmountFormat) {
+        let _ = match format {
+            AmountFormat::Decimal => write!(out, "{}", self),
+            AmountFormat::MinorUnits => write!(out, "{}", self.minor_units),
+        };
+    }
+}
+
+// Amounts are only added up and subtracted within one currency
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money {
+            minor_units: self.minor_units + other.minor_units,
+            ..self
+        }
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money {
+            minor_units: self.minor_units - other.minor_units,
+            ..self
+        }
+    }
+}
+
+// Major units with exactly as many decimals as the currency has, worked out
+// from the minor units so no floating-point rounding creeps in
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.minor_units < 0 { "-" } else { "" };
+        let scale = 10u64.pow(self.exponent);
+        let units = self.minor_units.unsigned_abs();
+        if self.exponent == 0 {
+            write!(f, "{}{}", sign, units)
+        } else {
+            write!(
+                f,
+                "{}{}.{:0width$}",
+                sign,
+                units / scale,
+                units % scale,
+                width = self.exponent as usize
+            )
+        }
+    }
+}
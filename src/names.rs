@@ -0,0 +1,899 @@
+use crate::text::{Text, TextBuf};
+use rand::{Rng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Mix of languages cardholder names are drawn in, by relative weight. An
+// empty table keeps drawing names from the profile's `first_names` and
+// `last_names`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NameConfig {
+    pub locales: BTreeMap<Locale, f64>,
+}
+
+impl NameConfig {
+    // Sampling weights for each locale, in `Locale::ALL` order
+    pub fn locale_weights(&self) -> Vec<f64> {
+        Locale::ALL
+            .iter()
+            .map(|locale| self.locales.get(locale).copied().unwrap_or(0.0))
+            .collect()
+    }
+}
+
+// Languages with a pack of given and family names in their own script. Each
+// name comes with its Latin spelling, without diacritics.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    En,
+    Es,
+    Pt,
+    De,
+    Fr,
+    It,
+    Ru,
+    Ar,
+    Hi,
+    Zh,
+    Ja,
+    Ko,
+}
+
+// A cardholder name, with the given and family names also spelled in plain
+// ASCII for use in email addresses
+#[derive(Clone)]
+pub struct Name {
+    pub full: Text,
+    pub given: Text,
+    pub family: Text,
+}
+
+impl Name {
+    // A name from a given and a family name in the Latin alphabet, as in
+    // the profile's name lists
+    pub fn latin(given: &str, family: &str) -> Self {
+        let mut full = TextBuf::new();
+        full.push_str(given);
+        full.push(' ');
+        full.push_str(family);
+        Self {
+            full: full.into(),
+            given: ascii(given),
+            family: ascii(family),
+        }
+    }
+
+    // A random email address made up from the name, at one of the domains
+    // reserved for documentation so no real mailbox is ever addressed
+    pub fn email<R: Rng + ?Sized>(&self, rng: &mut R) -> Text {
+        let given = local_part(&self.given);
+        let family = local_part(&self.family);
+        let (given, family) = (given.as_str(), family.as_str());
+        let mut email = TextBuf::new();
+        match (given.is_empty(), family.is_empty()) {
+            (true, true) => email.push_str("cardholder"),
+            (false, true) => email.push_str(given),
+            (true, false) => email.push_str(family),
+            (false, false) => {
+                let style = rng.gen_range(0..5);
+                email.push_str(if style == 2 { &given[..1] } else { given });
+                match style {
+                    0 | 2 | 4 => email.push('.'),
+                    3 => email.push('_'),
+                    _ => {}
+                }
+                email.push_str(family);
+                match style {
+                    1 => email.push_digits(rng.gen_range(1..100), 1),
+                    4 => email.push_digits(rng.gen_range(1950..2010), 1),
+                    _ => {}
+                }
+            }
+        }
+        // Some addresses carry a subaddress tag
+        if rng.gen_bool(0.1) {
+            email.push('+');
+            email.push_str(TAGS.choose(rng).unwrap());
+        }
+        email.push('@');
+        email.push_str(DOMAINS.choose(rng).unwrap());
+        email.into()
+    }
+}
+
+const DOMAINS: [&str; 3] = ["example.com", "example.net", "example.org"];
+
+const TAGS: [&str; 4] = ["test", "shop", "news", "cards"];
+
+// Lowercase letters and digits of a name, as used in an email address
+fn local_part(name: &str) -> TextBuf {
+    let mut local = TextBuf::new();
+    for c in name.chars().filter(char::is_ascii_alphanumeric) {
+        local.push(c.to_ascii_lowercase());
+    }
+    local
+}
+
+// The name with common Latin diacritics and ligatures folded to ASCII, and
+// any other characters dropped
+fn ascii(name: &str) -> Text {
+    if name.is_ascii() {
+        return name.into();
+    }
+    let mut folded = TextBuf::new();
+    for c in name.chars() {
+        match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ą' => folded.push('a'),
+            'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ą' => folded.push('A'),
+            'ç' | 'ć' | 'č' => folded.push('c'),
+            'Ç' | 'Ć' | 'Č' => folded.push('C'),
+            'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ė' => folded.push('e'),
+            'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ę' | 'Ė' => folded.push('E'),
+            'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' => folded.push('i'),
+            'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' | 'Į' => folded.push('I'),
+            'ñ' | 'ń' => folded.push('n'),
+            'Ñ' | 'Ń' => folded.push('N'),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => folded.push('o'),
+            'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' => folded.push('O'),
+            'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ų' => folded.push('u'),
+            'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ū' | 'Ų' => folded.push('U'),
+            'ý' | 'ÿ' => folded.push('y'),
+            'Ý' => folded.push('Y'),
+            'š' | 'ś' => folded.push('s'),
+            'Š' | 'Ś' => folded.push('S'),
+            'ž' | 'ź' | 'ż' => folded.push('z'),
+            'Ž' | 'Ź' | 'Ż' => folded.push('Z'),
+            'ł' => folded.push('l'),
+            'Ł' => folded.push('L'),
+            'ß' => folded.push_str("ss"),
+            'æ' => folded.push_str("ae"),
+            'Æ' => folded.push_str("Ae"),
+            'œ' => folded.push_str("oe"),
+            'Œ' => folded.push_str("Oe"),
+            c if c.is_ascii() => folded.push(c),
+            _ => {}
+        }
+    }
+    folded.into()
+}
+
+struct Pack {
+    male: &'static [(&'static str, &'static str)],
+    female: &'static [(&'static str, &'static str)],
+    family: &'static [(&'static str, &'static str)],
+}
+
+impl Locale {
+    pub const ALL: [Locale; 12] = [
+        Locale::En,
+        Locale::Es,
+        Locale::Pt,
+        Locale::De,
+        Locale::Fr,
+        Locale::It,
+        Locale::Ru,
+        Locale::Ar,
+        Locale::Hi,
+        Locale::Zh,
+        Locale::Ja,
+        Locale::Ko,
+    ];
+
+    // A random full name, written the way the language writes it: family
+    // name first and unspaced in Chinese, Japanese and Korean, two family
+    // names in Spanish and Portuguese, and Russian family names agreeing
+    // with the gender of the given name
+    pub fn full_name<R: Rng + ?Sized>(self, rng: &mut R) -> Name {
+        let pack = self.pack();
+        let female = rng.gen_bool(0.5);
+        let given = if female { pack.female } else { pack.male };
+        let (given, given_latin) = given.choose(rng).unwrap();
+        let (family, family_latin) = pack.family.choose(rng).unwrap();
+        let mut full = TextBuf::new();
+        match self {
+            Locale::Zh | Locale::Ja | Locale::Ko => {
+                full.push_str(family);
+                full.push_str(given);
+            }
+            Locale::Es | Locale::Pt => {
+                let (second, _) = pack.family.choose(rng).unwrap();
+                full.push_str(given);
+                full.push(' ');
+                full.push_str(family);
+                full.push(' ');
+                full.push_str(second);
+            }
+            // Listed family names end in -ов, -ев or -ин, whose feminine
+            // form adds -а
+            _ => {
+                full.push_str(given);
+                full.push(' ');
+                full.push_str(family);
+                if self == Locale::Ru && female {
+                    full.push('а');
+                }
+            }
+        }
+        let family_latin = match self {
+            Locale::Ru if female => {
+                let mut feminine = TextBuf::new();
+                feminine.push_str(family_latin);
+                feminine.push('a');
+                feminine.into()
+            }
+            _ => Text::from_static(family_latin),
+        };
+        Name {
+            full: full.into(),
+            given: Text::from_static(given_latin),
+            family: family_latin,
+        }
+    }
+
+    fn pack(self) -> &'static Pack {
+        match self {
+            Locale::En => &EN,
+            Locale::Es => &ES,
+            Locale::Pt => &PT,
+            Locale::De => &DE,
+            Locale::Fr => &FR,
+            Locale::It => &IT,
+            Locale::Ru => &RU,
+            Locale::Ar => &AR,
+            Locale::Hi => &HI,
+            Locale::Zh => &ZH,
+            Locale::Ja => &JA,
+            Locale::Ko => &KO,
+        }
+    }
+}
+
+static EN: Pack = Pack {
+    male: &[
+        ("James", "James"),
+        ("John", "John"),
+        ("Robert", "Robert"),
+        ("Michael", "Michael"),
+        ("William", "William"),
+        ("David", "David"),
+        ("Richard", "Richard"),
+        ("Joseph", "Joseph"),
+        ("Thomas", "Thomas"),
+        ("Charles", "Charles"),
+        ("Daniel", "Daniel"),
+        ("Matthew", "Matthew"),
+        ("Christopher", "Christopher"),
+        ("Andrew", "Andrew"),
+        ("Noah", "Noah"),
+        ("Liam", "Liam"),
+        ("Ethan", "Ethan"),
+        ("Mason", "Mason"),
+    ],
+    female: &[
+        ("Mary", "Mary"),
+        ("Patricia", "Patricia"),
+        ("Jennifer", "Jennifer"),
+        ("Linda", "Linda"),
+        ("Elizabeth", "Elizabeth"),
+        ("Barbara", "Barbara"),
+        ("Susan", "Susan"),
+        ("Jessica", "Jessica"),
+        ("Sarah", "Sarah"),
+        ("Karen", "Karen"),
+        ("Emily", "Emily"),
+        ("Emma", "Emma"),
+        ("Olivia", "Olivia"),
+        ("Ava", "Ava"),
+        ("Sophia", "Sophia"),
+        ("Isabella", "Isabella"),
+        ("Chloe", "Chloe"),
+        ("Grace", "Grace"),
+    ],
+    family: &[
+        ("Smith", "Smith"),
+        ("Johnson", "Johnson"),
+        ("Williams", "Williams"),
+        ("Brown", "Brown"),
+        ("Jones", "Jones"),
+        ("Miller", "Miller"),
+        ("Davis", "Davis"),
+        ("Wilson", "Wilson"),
+        ("Anderson", "Anderson"),
+        ("Taylor", "Taylor"),
+        ("Thomas", "Thomas"),
+        ("Moore", "Moore"),
+        ("Jackson", "Jackson"),
+        ("Martin", "Martin"),
+        ("Lee", "Lee"),
+        ("Thompson", "Thompson"),
+        ("White", "White"),
+        ("Harris", "Harris"),
+        ("Clark", "Clark"),
+        ("Lewis", "Lewis"),
+        ("Robinson", "Robinson"),
+        ("Walker", "Walker"),
+        ("Young", "Young"),
+        ("Allen", "Allen"),
+        ("King", "King"),
+        ("Wright", "Wright"),
+        ("Scott", "Scott"),
+        ("Hill", "Hill"),
+        ("Green", "Green"),
+        ("Baker", "Baker"),
+    ],
+};
+
+static ES: Pack = Pack {
+    male: &[
+        ("José", "Jose"),
+        ("Juan", "Juan"),
+        ("Luis", "Luis"),
+        ("Carlos", "Carlos"),
+        ("Javier", "Javier"),
+        ("Miguel", "Miguel"),
+        ("Alejandro", "Alejandro"),
+        ("Diego", "Diego"),
+        ("Andrés", "Andres"),
+        ("Fernando", "Fernando"),
+        ("Pablo", "Pablo"),
+        ("Sergio", "Sergio"),
+        ("Raúl", "Raul"),
+        ("Jesús", "Jesus"),
+    ],
+    female: &[
+        ("María", "Maria"),
+        ("Carmen", "Carmen"),
+        ("Ana", "Ana"),
+        ("Lucía", "Lucia"),
+        ("Isabel", "Isabel"),
+        ("Sofía", "Sofia"),
+        ("Valentina", "Valentina"),
+        ("Camila", "Camila"),
+        ("Paula", "Paula"),
+        ("Elena", "Elena"),
+        ("Marta", "Marta"),
+        ("Gabriela", "Gabriela"),
+        ("Inés", "Ines"),
+        ("Begoña", "Begona"),
+    ],
+    family: &[
+        ("García", "Garcia"),
+        ("Rodríguez", "Rodriguez"),
+        ("Martínez", "Martinez"),
+        ("López", "Lopez"),
+        ("González", "Gonzalez"),
+        ("Pérez", "Perez"),
+        ("Sánchez", "Sanchez"),
+        ("Ramírez", "Ramirez"),
+        ("Torres", "Torres"),
+        ("Flores", "Flores"),
+        ("Rivera", "Rivera"),
+        ("Gómez", "Gomez"),
+        ("Díaz", "Diaz"),
+        ("Hernández", "Hernandez"),
+        ("Moreno", "Moreno"),
+        ("Muñoz", "Munoz"),
+        ("Álvarez", "Alvarez"),
+        ("Romero", "Romero"),
+        ("Jiménez", "Jimenez"),
+        ("Ruiz", "Ruiz"),
+        ("Castillo", "Castillo"),
+        ("Vargas", "Vargas"),
+        ("Ortiz", "Ortiz"),
+        ("Núñez", "Nunez"),
+        ("Herrera", "Herrera"),
+    ],
+};
+
+static PT: Pack = Pack {
+    male: &[
+        ("João", "Joao"),
+        ("Pedro", "Pedro"),
+        ("Lucas", "Lucas"),
+        ("Gabriel", "Gabriel"),
+        ("Rafael", "Rafael"),
+        ("Mateus", "Mateus"),
+        ("Thiago", "Thiago"),
+        ("Gustavo", "Gustavo"),
+        ("Felipe", "Felipe"),
+        ("Bruno", "Bruno"),
+        ("António", "Antonio"),
+        ("Vinícius", "Vinicius"),
+    ],
+    female: &[
+        ("Maria", "Maria"),
+        ("Ana", "Ana"),
+        ("Beatriz", "Beatriz"),
+        ("Mariana", "Mariana"),
+        ("Júlia", "Julia"),
+        ("Larissa", "Larissa"),
+        ("Fernanda", "Fernanda"),
+        ("Letícia", "Leticia"),
+        ("Camila", "Camila"),
+        ("Inês", "Ines"),
+        ("Conceição", "Conceicao"),
+        ("Débora", "Debora"),
+    ],
+    family: &[
+        ("Silva", "Silva"),
+        ("Santos", "Santos"),
+        ("Oliveira", "Oliveira"),
+        ("Souza", "Souza"),
+        ("Rodrigues", "Rodrigues"),
+        ("Ferreira", "Ferreira"),
+        ("Alves", "Alves"),
+        ("Pereira", "Pereira"),
+        ("Lima", "Lima"),
+        ("Gomes", "Gomes"),
+        ("Costa", "Costa"),
+        ("Ribeiro", "Ribeiro"),
+        ("Martins", "Martins"),
+        ("Carvalho", "Carvalho"),
+        ("Araújo", "Araujo"),
+        ("Melo", "Melo"),
+        ("Barbosa", "Barbosa"),
+        ("Rocha", "Rocha"),
+        ("Gonçalves", "Goncalves"),
+        ("Magalhães", "Magalhaes"),
+    ],
+};
+
+static DE: Pack = Pack {
+    male: &[
+        ("Lukas", "Lukas"),
+        ("Jonas", "Jonas"),
+        ("Leon", "Leon"),
+        ("Finn", "Finn"),
+        ("Paul", "Paul"),
+        ("Felix", "Felix"),
+        ("Maximilian", "Maximilian"),
+        ("Jürgen", "Juergen"),
+        ("Tobias", "Tobias"),
+        ("Stefan", "Stefan"),
+        ("Jörg", "Joerg"),
+        ("Björn", "Bjoern"),
+    ],
+    female: &[
+        ("Anna", "Anna"),
+        ("Lea", "Lea"),
+        ("Hannah", "Hannah"),
+        ("Mia", "Mia"),
+        ("Emma", "Emma"),
+        ("Sophie", "Sophie"),
+        ("Marie", "Marie"),
+        ("Lena", "Lena"),
+        ("Johanna", "Johanna"),
+        ("Katharina", "Katharina"),
+        ("Jana", "Jana"),
+        ("Ursula", "Ursula"),
+    ],
+    family: &[
+        ("Müller", "Mueller"),
+        ("Schmidt", "Schmidt"),
+        ("Schneider", "Schneider"),
+        ("Fischer", "Fischer"),
+        ("Weber", "Weber"),
+        ("Meyer", "Meyer"),
+        ("Wagner", "Wagner"),
+        ("Becker", "Becker"),
+        ("Schulz", "Schulz"),
+        ("Hoffmann", "Hoffmann"),
+        ("Schäfer", "Schaefer"),
+        ("Koch", "Koch"),
+        ("Bauer", "Bauer"),
+        ("Richter", "Richter"),
+        ("Klein", "Klein"),
+        ("Wolf", "Wolf"),
+        ("Schröder", "Schroeder"),
+        ("Neumann", "Neumann"),
+        ("Schwarz", "Schwarz"),
+        ("Zimmermann", "Zimmermann"),
+        ("Braun", "Braun"),
+        ("Krüger", "Krueger"),
+        ("Hartmann", "Hartmann"),
+        ("Weiß", "Weiss"),
+    ],
+};
+
+static FR: Pack = Pack {
+    male: &[
+        ("Jean", "Jean"),
+        ("Louis", "Louis"),
+        ("Hugo", "Hugo"),
+        ("Gabriel", "Gabriel"),
+        ("Arthur", "Arthur"),
+        ("Jules", "Jules"),
+        ("Raphaël", "Raphael"),
+        ("Théo", "Theo"),
+        ("François", "Francois"),
+        ("Mathieu", "Mathieu"),
+        ("Antoine", "Antoine"),
+        ("Jérôme", "Jerome"),
+    ],
+    female: &[
+        ("Marie", "Marie"),
+        ("Camille", "Camille"),
+        ("Léa", "Lea"),
+        ("Chloé", "Chloe"),
+        ("Manon", "Manon"),
+        ("Inès", "Ines"),
+        ("Élodie", "Elodie"),
+        ("Zoé", "Zoe"),
+        ("Hélène", "Helene"),
+        ("Céline", "Celine"),
+        ("Océane", "Oceane"),
+        ("Anaïs", "Anais"),
+    ],
+    family: &[
+        ("Martin", "Martin"),
+        ("Bernard", "Bernard"),
+        ("Dubois", "Dubois"),
+        ("Thomas", "Thomas"),
+        ("Robert", "Robert"),
+        ("Richard", "Richard"),
+        ("Petit", "Petit"),
+        ("Durand", "Durand"),
+        ("Leroy", "Leroy"),
+        ("Moreau", "Moreau"),
+        ("Simon", "Simon"),
+        ("Laurent", "Laurent"),
+        ("Lefèvre", "Lefevre"),
+        ("Michel", "Michel"),
+        ("Garnier", "Garnier"),
+        ("Faure", "Faure"),
+        ("Rousseau", "Rousseau"),
+        ("Blanc", "Blanc"),
+        ("Guérin", "Guerin"),
+        ("Mercier", "Mercier"),
+        ("Bonnet", "Bonnet"),
+        ("Fontaine", "Fontaine"),
+        ("Chevalier", "Chevalier"),
+        ("Gauthier", "Gauthier"),
+    ],
+};
+
+static IT: Pack = Pack {
+    male: &[
+        ("Marco", "Marco"),
+        ("Alessandro", "Alessandro"),
+        ("Luca", "Luca"),
+        ("Matteo", "Matteo"),
+        ("Lorenzo", "Lorenzo"),
+        ("Andrea", "Andrea"),
+        ("Davide", "Davide"),
+        ("Niccolò", "Niccolo"),
+        ("Simone", "Simone"),
+        ("Riccardo", "Riccardo"),
+    ],
+    female: &[
+        ("Giulia", "Giulia"),
+        ("Francesca", "Francesca"),
+        ("Chiara", "Chiara"),
+        ("Sara", "Sara"),
+        ("Martina", "Martina"),
+        ("Alessia", "Alessia"),
+        ("Federica", "Federica"),
+        ("Aurora", "Aurora"),
+        ("Elisa", "Elisa"),
+        ("Giorgia", "Giorgia"),
+    ],
+    family: &[
+        ("Rossi", "Rossi"),
+        ("Russo", "Russo"),
+        ("Ferrari", "Ferrari"),
+        ("Esposito", "Esposito"),
+        ("Bianchi", "Bianchi"),
+        ("Romano", "Romano"),
+        ("Colombo", "Colombo"),
+        ("Ricci", "Ricci"),
+        ("Marino", "Marino"),
+        ("Greco", "Greco"),
+        ("Bruno", "Bruno"),
+        ("Gallo", "Gallo"),
+        ("Conti", "Conti"),
+        ("De Luca", "De Luca"),
+        ("Mancini", "Mancini"),
+        ("Giordano", "Giordano"),
+        ("Rizzo", "Rizzo"),
+        ("Lombardi", "Lombardi"),
+        ("Moretti", "Moretti"),
+        ("Santoro", "Santoro"),
+    ],
+};
+
+static RU: Pack = Pack {
+    male: &[
+        ("Александр", "Aleksandr"),
+        ("Дмитрий", "Dmitry"),
+        ("Сергей", "Sergey"),
+        ("Андрей", "Andrey"),
+        ("Алексей", "Aleksey"),
+        ("Иван", "Ivan"),
+        ("Михаил", "Mikhail"),
+        ("Никита", "Nikita"),
+        ("Артём", "Artyom"),
+        ("Максим", "Maksim"),
+    ],
+    female: &[
+        ("Мария", "Mariya"),
+        ("Анна", "Anna"),
+        ("Елена", "Elena"),
+        ("Ольга", "Olga"),
+        ("Наталья", "Natalya"),
+        ("Татьяна", "Tatyana"),
+        ("Екатерина", "Ekaterina"),
+        ("Анастасия", "Anastasia"),
+        ("Ирина", "Irina"),
+        ("Дарья", "Darya"),
+    ],
+    family: &[
+        ("Иванов", "Ivanov"),
+        ("Смирнов", "Smirnov"),
+        ("Кузнецов", "Kuznetsov"),
+        ("Попов", "Popov"),
+        ("Васильев", "Vasilyev"),
+        ("Петров", "Petrov"),
+        ("Соколов", "Sokolov"),
+        ("Михайлов", "Mikhaylov"),
+        ("Новиков", "Novikov"),
+        ("Фёдоров", "Fyodorov"),
+        ("Морозов", "Morozov"),
+        ("Волков", "Volkov"),
+        ("Алексеев", "Alekseyev"),
+        ("Лебедев", "Lebedev"),
+        ("Семёнов", "Semyonov"),
+        ("Егоров", "Yegorov"),
+        ("Павлов", "Pavlov"),
+        ("Козлов", "Kozlov"),
+        ("Степанов", "Stepanov"),
+        ("Никитин", "Nikitin"),
+    ],
+};
+
+static AR: Pack = Pack {
+    male: &[
+        ("محمد", "Mohammed"),
+        ("أحمد", "Ahmed"),
+        ("علي", "Ali"),
+        ("عمر", "Omar"),
+        ("يوسف", "Youssef"),
+        ("خالد", "Khaled"),
+        ("حسن", "Hassan"),
+        ("إبراهيم", "Ibrahim"),
+        ("عبدالله", "Abdullah"),
+        ("مصطفى", "Mustafa"),
+    ],
+    female: &[
+        ("فاطمة", "Fatima"),
+        ("مريم", "Maryam"),
+        ("عائشة", "Aisha"),
+        ("نور", "Nour"),
+        ("سارة", "Sara"),
+        ("ليلى", "Layla"),
+        ("زينب", "Zainab"),
+        ("هدى", "Huda"),
+        ("رنا", "Rana"),
+        ("ياسمين", "Yasmin"),
+    ],
+    family: &[
+        ("العلي", "Al-Ali"),
+        ("الحسن", "Al-Hassan"),
+        ("المصري", "Al-Masri"),
+        ("الخطيب", "Al-Khatib"),
+        ("حداد", "Haddad"),
+        ("منصور", "Mansour"),
+        ("سعيد", "Saeed"),
+        ("العمري", "Al-Omari"),
+        ("الشمري", "Al-Shammari"),
+        ("القحطاني", "Al-Qahtani"),
+        ("الزهراني", "Al-Zahrani"),
+        ("الهاشمي", "Al-Hashimi"),
+        ("النجار", "Al-Najjar"),
+        ("عبدالرحمن", "Abdelrahman"),
+        ("الأنصاري", "Al-Ansari"),
+    ],
+};
+
+static HI: Pack = Pack {
+    male: &[
+        ("आरव", "Aarav"),
+        ("विवान", "Vivaan"),
+        ("अर्जुन", "Arjun"),
+        ("राहुल", "Rahul"),
+        ("अमित", "Amit"),
+        ("रोहन", "Rohan"),
+        ("विक्रम", "Vikram"),
+        ("सुरेश", "Suresh"),
+        ("आदित्य", "Aditya"),
+        ("करण", "Karan"),
+    ],
+    female: &[
+        ("प्रिया", "Priya"),
+        ("अनन्या", "Ananya"),
+        ("दीपिका", "Deepika"),
+        ("पूजा", "Pooja"),
+        ("नेहा", "Neha"),
+        ("कविता", "Kavita"),
+        ("अंजलि", "Anjali"),
+        ("स्नेहा", "Sneha"),
+        ("ईशा", "Isha"),
+        ("मीरा", "Meera"),
+    ],
+    family: &[
+        ("शर्मा", "Sharma"),
+        ("वर्मा", "Verma"),
+        ("गुप्ता", "Gupta"),
+        ("सिंह", "Singh"),
+        ("कुमार", "Kumar"),
+        ("पटेल", "Patel"),
+        ("मेहता", "Mehta"),
+        ("जोशी", "Joshi"),
+        ("अग्रवाल", "Agarwal"),
+        ("रेड्डी", "Reddy"),
+        ("नायर", "Nair"),
+        ("मिश्रा", "Mishra"),
+        ("यादव", "Yadav"),
+        ("चौधरी", "Chaudhary"),
+        ("कपूर", "Kapoor"),
+    ],
+};
+
+static ZH: Pack = Pack {
+    male: &[
+        ("伟", "Wei"),
+        ("强", "Qiang"),
+        ("磊", "Lei"),
+        ("军", "Jun"),
+        ("洋", "Yang"),
+        ("勇", "Yong"),
+        ("杰", "Jie"),
+        ("涛", "Tao"),
+        ("明", "Ming"),
+        ("浩然", "Haoran"),
+        ("子轩", "Zixuan"),
+        ("俊杰", "Junjie"),
+    ],
+    female: &[
+        ("芳", "Fang"),
+        ("娜", "Na"),
+        ("敏", "Min"),
+        ("静", "Jing"),
+        ("丽", "Li"),
+        ("婷", "Ting"),
+        ("雪", "Xue"),
+        ("欣怡", "Xinyi"),
+        ("梓涵", "Zihan"),
+        ("诗涵", "Shihan"),
+        ("雨桐", "Yutong"),
+        ("佳怡", "Jiayi"),
+    ],
+    family: &[
+        ("王", "Wang"),
+        ("李", "Li"),
+        ("张", "Zhang"),
+        ("刘", "Liu"),
+        ("陈", "Chen"),
+        ("杨", "Yang"),
+        ("黄", "Huang"),
+        ("赵", "Zhao"),
+        ("吴", "Wu"),
+        ("周", "Zhou"),
+        ("徐", "Xu"),
+        ("孙", "Sun"),
+        ("马", "Ma"),
+        ("朱", "Zhu"),
+        ("胡", "Hu"),
+        ("郭", "Guo"),
+        ("何", "He"),
+        ("林", "Lin"),
+        ("高", "Gao"),
+        ("罗", "Luo"),
+    ],
+};
+
+static JA: Pack = Pack {
+    male: &[
+        ("翔太", "Shota"),
+        ("大輝", "Daiki"),
+        ("健太", "Kenta"),
+        ("拓海", "Takumi"),
+        ("蓮", "Ren"),
+        ("悠真", "Yuma"),
+        ("陽翔", "Haruto"),
+        ("大和", "Yamato"),
+        ("和也", "Kazuya"),
+        ("直樹", "Naoki"),
+    ],
+    female: &[
+        ("陽菜", "Hina"),
+        ("結衣", "Yui"),
+        ("美咲", "Misaki"),
+        ("さくら", "Sakura"),
+        ("葵", "Aoi"),
+        ("花子", "Hanako"),
+        ("愛", "Ai"),
+        ("真由美", "Mayumi"),
+        ("優子", "Yuko"),
+        ("彩", "Aya"),
+    ],
+    family: &[
+        ("佐藤", "Sato"),
+        ("鈴木", "Suzuki"),
+        ("高橋", "Takahashi"),
+        ("田中", "Tanaka"),
+        ("伊藤", "Ito"),
+        ("渡辺", "Watanabe"),
+        ("山本", "Yamamoto"),
+        ("中村", "Nakamura"),
+        ("小林", "Kobayashi"),
+        ("加藤", "Kato"),
+        ("吉田", "Yoshida"),
+        ("山田", "Yamada"),
+        ("佐々木", "Sasaki"),
+        ("山口", "Yamaguchi"),
+        ("松本", "Matsumoto"),
+        ("井上", "Inoue"),
+        ("木村", "Kimura"),
+        ("林", "Hayashi"),
+        ("清水", "Shimizu"),
+        ("森", "Mori"),
+    ],
+};
+
+static KO: Pack = Pack {
+    male: &[
+        ("민준", "Minjun"),
+        ("서준", "Seojun"),
+        ("도윤", "Doyun"),
+        ("예준", "Yejun"),
+        ("시우", "Siwoo"),
+        ("지호", "Jiho"),
+        ("현우", "Hyunwoo"),
+        ("준호", "Junho"),
+        ("성민", "Sungmin"),
+        ("동현", "Donghyun"),
+    ],
+    female: &[
+        ("서연", "Seoyeon"),
+        ("지우", "Jiwoo"),
+        ("서윤", "Seoyun"),
+        ("하은", "Haeun"),
+        ("민서", "Minseo"),
+        ("지민", "Jimin"),
+        ("수빈", "Subin"),
+        ("예린", "Yerin"),
+        ("유진", "Yujin"),
+        ("은지", "Eunji"),
+    ],
+    family: &[
+        ("김", "Kim"),
+        ("이", "Lee"),
+        ("박", "Park"),
+        ("최", "Choi"),
+        ("정", "Jung"),
+        ("강", "Kang"),
+        ("조", "Cho"),
+        ("윤", "Yoon"),
+        ("장", "Jang"),
+        ("임", "Lim"),
+        ("한", "Han"),
+        ("오", "Oh"),
+        ("서", "Seo"),
+        ("신", "Shin"),
+        ("권", "Kwon"),
+    ],
+};
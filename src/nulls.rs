@@ -0,0 +1,242 @@
+use crate::{custom::CustomField, model::Transaction};
+use rand::Rng;
+use std::collections::BTreeMap;
+
+// Fields that can be left missing. Optional fields are unset, which every
+// format writes as its null; the rest are written empty.
+const NULLABLE_FIELDS: [&str; 80] = [
+    "event_type",
+    "decline_reason",
+    "response_code",
+    "auth_code",
+    "original_transaction_id",
+    "subscription_id",
+    "initiator",
+    "installment_number",
+    "installment_count",
+    "order_reference",
+    "preauth_step",
+    "customer_id",
+    "cardholder_name",
+    "email",
+    "phone",
+    "card_number",
+    "card_brand",
+    "card_expiry",
+    "cvv",
+    "issuer_bank",
+    "card_country",
+    "card_type",
+    "billing_street",
+    "billing_city",
+    "billing_region",
+    "billing_postal_code",
+    "billing_country",
+    "shipping_street",
+    "shipping_city",
+    "shipping_region",
+    "shipping_postal_code",
+    "shipping_country",
+    "refund_amount",
+    "remaining_amount",
+    "currency",
+    "original_amount",
+    "original_currency",
+    "billing_amount",
+    "billing_currency",
+    "fx_rate",
+    "fx_markup",
+    "merchant_name",
+    "merchant_id",
+    "statement_descriptor",
+    "merchant_category",
+    "mcc",
+    "acquirer_id",
+    "processor",
+    "batch_id",
+    "settlement_date",
+    "payment_method",
+    "pos_entry_mode",
+    "terminal_id",
+    "track1",
+    "track2",
+    "emv_aid",
+    "emv_cryptogram",
+    "emv_tvr",
+    "emv_atc",
+    "wallet",
+    "wallet_id",
+    "token_pan",
+    "token_requestor_id",
+    "three_ds_version",
+    "eci",
+    "authentication_result",
+    "cavv",
+    "avs_result",
+    "cvv_result",
+    "ip_address",
+    "ip_country",
+    "device_id",
+    "device_platform",
+    "device_os",
+    "device_browser",
+    "screen_resolution",
+    "device_fingerprint",
+    "user_agent",
+    "fraud_pattern",
+    "persona",
+];
+
+// Fields every row needs, which are never left missing
+const REQUIRED_FIELDS: [&str; 6] = [
+    "transaction_id",
+    "transaction_date",
+    "status",
+    "amount",
+    "channel",
+    "is_fraud",
+];
+
+// A `--null` argument: a field and the share of rows it is missing from,
+// as in `cvv=5%` or `user_agent=0.2`
+pub fn parse(value: &str) -> Result<(String, f64), String> {
+    let invalid = || format!("expected FIELD=RATE, as in cvv=5%, got {:?}", value);
+    let (field, rate) = value.split_once('=').ok_or_else(invalid)?;
+    let rate = match rate.trim().strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => rate.trim().parse::<f64>(),
+    }
+    .map_err(|_| invalid())?;
+    Ok((field.trim().to_string(), rate))
+}
+
+// Reasons a field can't be left missing at a rate, if any. Custom fields
+// can be missing too.
+pub fn check(field: &str, rate: f64, custom_fields: &[CustomField]) -> Result<(), String> {
+    if REQUIRED_FIELDS.contains(&field) {
+        return Err("is needed by every row and can't be null".to_string());
+    }
+    if !NULLABLE_FIELDS.contains(&field) && !custom_fields.iter().any(|f| f.name == field) {
+        return Err("is not a field".to_string());
+    }
+    if !(0.0..=1.0).contains(&rate) {
+        return Err("must be between 0 and 1".to_string());
+    }
+    Ok(())
+}
+
+// Leaves the configured fields of rows missing at their rates
+pub struct Nulls {
+    rates: Vec<(String, f64)>,
+}
+
+impl Nulls {
+    // The profile's null rates, if any field has one above 0
+    pub fn new(rates: &BTreeMap<String, f64>) -> Option<Self> {
+        let rates: Vec<(String, f64)> = rates
+            .iter()
+            .filter(|(_, rate)| **rate > 0.0)
+            .map(|(field, rate)| (field.clone(), *rate))
+            .collect();
+        (!rates.is_empty()).then_some(Self { rates })
+    }
+
+    pub fn apply<R: Rng + ?Sized>(&self, rng: &mut R, tx: &mut Transaction) {
+        for (field, rate) in &self.rates {
+            if rng.gen_bool(*rate) {
+                clear(tx, field);
+            }
+        }
+    }
+}
+
+// Unset or empty a field of a row, or null a custom field
+fn clear(tx: &mut Transaction, field: &str) {
+    match field {
+        "event_type" => tx.event_type = None,
+        "decline_reason" => tx.decline_reason = None,
+        "response_code" => tx.response_code.clear(),
+        "auth_code" => tx.auth_code = None,
+        "original_transaction_id" => tx.original_transaction_id = None,
+        "subscription_id" => tx.subscription_id = None,
+        "initiator" => tx.initiator = None,
+        "installment_number" => tx.installment_number = None,
+        "installment_count" => tx.installment_count = None,
+        "order_reference" => tx.order_reference = None,
+        "preauth_step" => tx.preauth_step = None,
+        "customer_id" => tx.customer_id.clear(),
+        "cardholder_name" => tx.cardholder_name.clear(),
+        "email" => tx.email.clear(),
+        "phone" => tx.phone.clear(),
+        "card_number" => tx.card_number.clear(),
+        "card_brand" => tx.card_brand.clear(),
+        "card_expiry" => tx.card_expiry.clear(),
+        "cvv" => tx.cvv = None,
+        "issuer_bank" => tx.issuer_bank = None,
+        "card_country" => tx.card_country = None,
+        "card_type" => tx.card_type = None,
+        "billing_street" => tx.billing_street.clear(),
+        "billing_city" => tx.billing_city.clear(),
+        "billing_region" => tx.billing_region.clear(),
+        "billing_postal_code" => tx.billing_postal_code.clear(),
+        "billing_country" => tx.billing_country.clear(),
+        "shipping_street" => tx.shipping_street = None,
+        "shipping_city" => tx.shipping_city = None,
+        "shipping_region" => tx.shipping_region = None,
+        "shipping_postal_code" => tx.shipping_postal_code = None,
+        "shipping_country" => tx.shipping_country = None,
+        "refund_amount" => tx.refund_amount = None,
+        "remaining_amount" => tx.remaining_amount = None,
+        "currency" => tx.currency.clear(),
+        "original_amount" => tx.original_amount = None,
+        "original_currency" => tx.original_currency = None,
+        "billing_amount" => tx.billing_amount = None,
+        "billing_currency" => tx.billing_currency = None,
+        "fx_rate" => tx.fx_rate = None,
+        "fx_markup" => tx.fx_markup = None,
+        "merchant_name" => tx.merchant_name.clear(),
+        "merchant_id" => tx.merchant_id.clear(),
+        "statement_descriptor" => tx.statement_descriptor.clear(),
+        "merchant_category" => tx.merchant_category.clear(),
+        "mcc" => tx.mcc = None,
+        "acquirer_id" => tx.acquirer_id.clear(),
+        "processor" => tx.processor.clear(),
+        "batch_id" => tx.batch_id = None,
+        "settlement_date" => tx.settlement_date = None,
+        "payment_method" => tx.payment_method.clear(),
+        "pos_entry_mode" => tx.pos_entry_mode.clear(),
+        "terminal_id" => tx.terminal_id = None,
+        "track1" => tx.track1 = None,
+        "track2" => tx.track2 = None,
+        "emv_aid" => tx.emv_aid = None,
+        "emv_cryptogram" => tx.emv_cryptogram = None,
+        "emv_tvr" => tx.emv_tvr = None,
+        "emv_atc" => tx.emv_atc = None,
+        "wallet" => tx.wallet = None,
+        "wallet_id" => tx.wallet_id = None,
+        "token_pan" => tx.token_pan = None,
+        "token_requestor_id" => tx.token_requestor_id = None,
+        "three_ds_version" => tx.three_ds_version = None,
+        "eci" => tx.eci = None,
+        "authentication_result" => tx.authentication_result = None,
+        "cavv" => tx.cavv = None,
+        "avs_result" => tx.avs_result = None,
+        "cvv_result" => tx.cvv_result = None,
+        "ip_address" => tx.ip_address = None,
+        "ip_country" => tx.ip_country = None,
+        "device_id" => tx.device_id = None,
+        "device_platform" => tx.device_platform = None,
+        "device_os" => tx.device_os = None,
+        "device_browser" => tx.device_browser = None,
+        "screen_resolution" => tx.screen_resolution = None,
+        "device_fingerprint" => tx.device_fingerprint = None,
+        "user_agent" => tx.user_agent = None,
+        "fraud_pattern" => tx.fraud_pattern = None,
+        "persona" => tx.persona = None,
+        field => {
+            if let Some(value) = tx.custom.get_mut(field) {
+                *value = serde_json::Value::Null;
+            }
+        }
+    }
+}
@@ -0,0 +1,615 @@
+use super::{Finish, TransactionWriter};
+use crate::{
+    bin::CardType,
+    custom::{CustomField, CustomType},
+    fraud::FraudPattern,
+    model::{Channel, DeclineReason, LifecycleEvent, Transaction, TransactionStatus},
+    preauth::PreauthStep,
+    subscription::Initiator,
+    three_ds::AuthenticationResult,
+    wallet::Wallet,
+};
+use chrono::DateTime;
+use flate2::write::DeflateEncoder;
+use rand::Rng;
+use serde_json::{Value, json};
+use std::io::{self, Write};
+
+// Number of transactions encoded into each container block
+const BLOCK_SIZE: usize = 4096;
+
+// Avro schema of a transaction, in `Transaction` field order with the custom
+// fields last. Enumerated fields become Avro enums, and timestamps are
+// stored in UTC like Parquet output.
+pub fn transaction_schema(custom_fields: &[CustomField]) -> Value {
+    fn nullable(schema: Value) -> Value {
+        json!(["null", schema])
+    }
+    let field = |name: &str, schema: Value| json!({ "name": name, "type": schema });
+    let optional = |name: &str, schema: Value| json!({ "name": name, "type": nullable(schema), "default": null });
+
+    let custom = custom_fields.iter().map(|custom| {
+        let schema = match custom.kind() {
+            CustomType::Text => "string",
+            CustomType::Integer => "long",
+            CustomType::Float => "double",
+            CustomType::Boolean => "boolean",
+        };
+        optional(&custom.name, json!(schema))
+    });
+    let mut schema = json!({
+        "type": "record",
+        "name": "Transaction",
+        "namespace": "luhnsynth",
+        "fields": [
+            field("transaction_id", json!("string")),
+            optional("event_type", json!({
+                "type": "enum",
+                "name": "LifecycleEvent",
+                "symbols": LifecycleEvent::ALL.map(|e| e.as_str()),
+            })),
+            field("transaction_date", json!({ "type": "long", "logicalType": "timestamp-micros" })),
+            field("status", json!({
+                "type": "enum",
+                "name": "TransactionStatus",
+                "symbols": TransactionStatus::ALL.map(|s| s.as_str()),
+            })),
+            optional("decline_reason", json!({
+                "type": "enum",
+                "name": "DeclineReason",
+                "symbols": DeclineReason::ALL.map(|r| r.as_str()),
+            })),
+            field("response_code", json!("string")),
+            optional("auth_code", json!("string")),
+            optional("original_transaction_id", json!("string")),
+            optional("subscription_id", json!("string")),
+            optional("initiator", json!({
+                "type": "enum",
+                "name": "Initiator",
+                "symbols": Initiator::ALL.map(|i| i.as_str()),
+            })),
+            optional("installment_number", json!("int")),
+            optional("installment_count", json!("int")),
+            optional("order_reference", json!("string")),
+            optional("preauth_step", json!({
+                "type": "enum",
+                "name": "PreauthStep",
+                "symbols": PreauthStep::ALL.map(|s| s.as_str()),
+            })),
+            field("customer_id", json!("string")),
+            field("cardholder_name", json!("string")),
+        field("email", json!("string")),
+        field("phone", json!("string")),
+            field("card_number", json!("string")),
+            field("card_brand", json!("string")),
+            field("card_expiry", json!("string")),
+            optional("cvv", json!("string")),
+            optional("issuer_bank", json!("string")),
+            optional("card_country", json!("string")),
+            optional("card_type", json!({
+                "type": "enum",
+                "name": "CardType",
+                "symbols": CardType::ALL.map(|t| t.as_str()),
+            })),
+            field("billing_street", json!("string")),
+            field("billing_city", json!("string")),
+            field("billing_region", json!("string")),
+            field("billing_postal_code", json!("string")),
+            field("billing_country", json!("string")),
+            optional("shipping_street", json!("string")),
+            optional("shipping_city", json!("string")),
+            optional("shipping_region", json!("string")),
+            optional("shipping_postal_code", json!("string")),
+            optional("shipping_country", json!("string")),
+            field("amount", json!("double")),
+            optional("refund_amount", json!("double")),
+            optional("remaining_amount", json!("double")),
+            field("currency", json!("string")),
+            optional("original_amount", json!("double")),
+            optional("original_currency", json!("string")),
+            optional("billing_amount", json!("double")),
+            optional("billing_currency", json!("string")),
+            optional("fx_rate", json!("double")),
+            optional("fx_markup", json!("double")),
+            field("merchant_name", json!("string")),
+            field("merchant_id", json!("string")),
+            field("statement_descriptor", json!("string")),
+            field("merchant_category", json!("string")),
+            optional("mcc", json!("string")),
+            field("acquirer_id", json!("string")),
+            field("processor", json!("string")),
+            optional("batch_id", json!("string")),
+            optional("settlement_date", json!("string")),
+            field("payment_method", json!("string")),
+            field("channel", json!({
+                "type": "enum",
+                "name": "Channel",
+                "symbols": Channel::ALL.map(|c| c.as_str()),
+            })),
+            field("pos_entry_mode", json!("string")),
+            optional("terminal_id", json!("string")),
+            optional("track1", json!("string")),
+            optional("track2", json!("string")),
+            optional("emv_aid", json!("string")),
+            optional("emv_cryptogram", json!("string")),
+            optional("emv_tvr", json!("string")),
+            optional("emv_atc", json!("string")),
+            optional("wallet", json!({
+                "type": "enum",
+                "name": "Wallet",
+                "symbols": Wallet::ALL.map(|w| w.as_str()),
+            })),
+            optional("wallet_id", json!("string")),
+            optional("token_pan", json!("string")),
+            optional("token_requestor_id", json!("string")),
+            optional("three_ds_version", json!("string")),
+            optional("eci", json!("string")),
+            optional("authentication_result", json!({
+                "type": "enum",
+                "name": "AuthenticationResult",
+                "symbols": AuthenticationResult::ALL.map(|r| r.as_str()),
+            })),
+            optional("cavv", json!("string")),
+            optional("avs_result", json!("string")),
+            optional("cvv_result", json!("string")),
+            optional("ip_address", json!("string")),
+            optional("ip_country", json!("string")),
+            optional("device_id", json!("string")),
+            optional("device_platform", json!("string")),
+            optional("device_os", json!("string")),
+            optional("device_browser", json!("string")),
+            optional("screen_resolution", json!("string")),
+            optional("device_fingerprint", json!("string")),
+            optional("user_agent", json!("string")),
+            field("is_fraud", json!("boolean")),
+            optional("fraud_pattern", json!({
+                "type": "enum",
+                "name": "FraudPattern",
+                "symbols": FraudPattern::ALL.map(|p| p.as_str()),
+            })),
+            optional("persona", json!("string")),
+        ],
+    });
+    if let Some(fields) = schema["fields"].as_array_mut() {
+        fields.extend(custom);
+    }
+    schema
+}
+
+// Writes transactions as an Avro object container file: a header embedding
+// the schema, then deflate-compressed blocks of binary-encoded records, each
+// followed by the file's sync marker
+pub struct AvroWriter<W: Finish> {
+    out: W,
+    sync: [u8; 16],
+    // Encoded records of the block being filled
+    block: Vec<u8>,
+    count: usize,
+    // Custom fields, written after the built-in ones
+    custom: Vec<(String, CustomType)>,
+}
+
+impl<W: Finish> AvroWriter<W> {
+    pub fn new(mut out: W, custom_fields: &[CustomField]) -> io::Result<Self> {
+        let sync: [u8; 16] = rand::thread_rng().r#gen();
+        let mut header = b"Obj\x01".to_vec();
+        // File metadata, as a map of one block holding both entries
+        write_long(&mut header, 2);
+        write_bytes(&mut header, b"avro.schema");
+        let schema = transaction_schema(custom_fields);
+        write_bytes(&mut header, schema.to_string().as_bytes());
+        write_bytes(&mut header, b"avro.codec");
+        write_bytes(&mut header, b"deflate");
+        write_long(&mut header, 0);
+        header.extend_from_slice(&sync);
+        out.write_all(&header)?;
+        Ok(Self {
+            out,
+            sync,
+            block: Vec::new(),
+            count: 0,
+            custom: custom_fields
+                .iter()
+                .map(|field| (field.name.clone(), field.kind()))
+                .collect(),
+        })
+    }
+
+    fn flush_block(&mut self) -> io::Result<()> {
+        if self.count == 0 {
+            return Ok(());
+        }
+        let mut deflate = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        deflate.write_all(&self.block)?;
+        let data = deflate.finish()?;
+
+        let mut prefix = Vec::new();
+        write_long(&mut prefix, self.count as i64);
+        write_long(&mut prefix, data.len() as i64);
+        self.out.write_all(&prefix)?;
+        self.out.write_all(&data)?;
+        self.out.write_all(&self.sync)?;
+        self.block.clear();
+        self.count = 0;
+        Ok(())
+    }
+}
+
+impl<W: Finish> TransactionWriter for AvroWriter<W> {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        let timestamp = DateTime::parse_from_rfc3339(&tx.transaction_date)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            .timestamp_micros();
+        let out = &mut self.block;
+        write_string(out, &tx.transaction_id);
+        write_optional(out, tx.event_type.as_ref(), |out, event| {
+            write_enum(
+                out,
+                &LifecycleEvent::ALL.map(|e| e.as_str()),
+                event.as_str(),
+            )
+        });
+        write_long(out, timestamp);
+        write_enum(
+            out,
+            &TransactionStatus::ALL.map(|s| s.as_str()),
+            tx.status.as_str(),
+        );
+        write_optional(out, tx.decline_reason.as_ref(), |out, reason| {
+            write_enum(
+                out,
+                &DeclineReason::ALL.map(|r| r.as_str()),
+                reason.as_str(),
+            )
+        });
+        write_string(out, &tx.response_code);
+        write_optional(out, tx.auth_code.as_ref(), |out, code| write_string(out, code));
+        write_optional(out, tx.original_transaction_id.as_ref(), |out, id| {
+            write_string(out, id)
+        });
+        write_optional(out, tx.subscription_id.as_ref(), |out, id| write_string(out, id));
+        write_optional(out, tx.initiator.as_ref(), |out, initiator| {
+            write_enum(out, &Initiator::ALL.map(|i| i.as_str()), initiator.as_str())
+        });
+        write_optional(out, tx.installment_number.as_ref(), |out, number| write_long(out, *number as i64));
+        write_optional(out, tx.installment_count.as_ref(), |out, count| write_long(out, *count as i64));
+        write_optional(out, tx.order_reference.as_ref(), |out, reference| write_string(out, reference));
+        write_optional(out, tx.preauth_step.as_ref(), |out, step| {
+            write_enum(out, &PreauthStep::ALL.map(|s| s.as_str()), step.as_str())
+        });
+        write_string(out, &tx.customer_id);
+        write_string(out, &tx.cardholder_name);
+        write_string(out, &tx.email);
+        write_string(out, &tx.phone);
+        write_string(out, &tx.card_number);
+        write_string(out, &tx.card_brand);
+        write_string(out, &tx.card_expiry);
+        write_optional(out, tx.cvv.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.issuer_bank.as_ref(), |out, issuer| {
+            write_string(out, issuer)
+        });
+        write_optional(out, tx.card_country.as_ref(), |out, country| {
+            write_string(out, country)
+        });
+        write_optional(out, tx.card_type.as_ref(), |out, card_type| {
+            write_enum(out, &CardType::ALL.map(|t| t.as_str()), card_type.as_str())
+        });
+        write_string(out, &tx.billing_street);
+        write_string(out, &tx.billing_city);
+        write_string(out, &tx.billing_region);
+        write_string(out, &tx.billing_postal_code);
+        write_string(out, &tx.billing_country);
+        write_optional(out, tx.shipping_street.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.shipping_city.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.shipping_region.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.shipping_postal_code.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.shipping_country.as_ref(), |out, value| write_string(out, value));
+        out.extend_from_slice(&tx.amount.to_le_bytes());
+        write_optional(out, tx.refund_amount.as_ref(), |out, amount| out.extend_from_slice(&amount.to_le_bytes()));
+        write_optional(out, tx.remaining_amount.as_ref(), |out, amount| out.extend_from_slice(&amount.to_le_bytes()));
+        write_string(out, &tx.currency);
+        write_optional(out, tx.original_amount.as_ref(), |out, amount| out.extend_from_slice(&amount.to_le_bytes()));
+        write_optional(out, tx.original_currency.as_ref(), |out, currency| write_string(out, currency));
+        write_optional(out, tx.billing_amount.as_ref(), |out, amount| out.extend_from_slice(&amount.to_le_bytes()));
+        write_optional(out, tx.billing_currency.as_ref(), |out, currency| write_string(out, currency));
+        write_optional(out, tx.fx_rate.as_ref(), |out, rate| out.extend_from_slice(&rate.to_le_bytes()));
+        write_optional(out, tx.fx_markup.as_ref(), |out, markup| out.extend_from_slice(&markup.to_le_bytes()));
+        write_string(out, &tx.merchant_name);
+        write_string(out, &tx.merchant_id);
+        write_string(out, &tx.statement_descriptor);
+        write_string(out, &tx.merchant_category);
+        write_optional(out, tx.mcc.as_ref(), |out, mcc| write_string(out, mcc));
+        write_string(out, &tx.acquirer_id);
+        write_string(out, &tx.processor);
+        write_optional(out, tx.batch_id.as_ref(), |out, id| write_string(out, id));
+        write_optional(out, tx.settlement_date.as_ref(), |out, date| write_string(out, date));
+        write_string(out, &tx.payment_method);
+        write_enum(out, &Channel::ALL.map(|c| c.as_str()), tx.channel.as_str());
+        write_string(out, &tx.pos_entry_mode);
+        write_optional(out, tx.terminal_id.as_ref(), |out, id| write_string(out, id));
+        write_optional(out, tx.track1.as_ref(), |out, track| write_string(out, track));
+        write_optional(out, tx.track2.as_ref(), |out, track| write_string(out, track));
+        write_optional(out, tx.emv_aid.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.emv_cryptogram.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.emv_tvr.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.emv_atc.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.wallet.as_ref(), |out, wallet| {
+            write_enum(out, &Wallet::ALL.map(|w| w.as_str()), wallet.as_str())
+        });
+        write_optional(out, tx.wallet_id.as_ref(), |out, id| write_string(out, id));
+        write_optional(out, tx.token_pan.as_ref(), |out, pan| write_string(out, pan));
+        write_optional(out, tx.token_requestor_id.as_ref(), |out, id| write_string(out, id));
+        write_optional(out, tx.three_ds_version.as_ref(), |out, version| write_string(out, version));
+        write_optional(out, tx.eci.as_ref(), |out, eci| write_string(out, eci));
+        write_optional(out, tx.authentication_result.as_ref(), |out, result| {
+            write_enum(out, &AuthenticationResult::ALL.map(|r| r.as_str()), result.as_str())
+        });
+        write_optional(out, tx.cavv.as_ref(), |out, cavv| write_string(out, cavv));
+        write_optional(out, tx.avs_result.as_ref(), |out, result| write_string(out, result));
+        write_optional(out, tx.cvv_result.as_ref(), |out, result| write_string(out, result));
+        write_optional(out, tx.ip_address.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.ip_country.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.device_id.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.device_platform.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.device_os.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.device_browser.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.screen_resolution.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.device_fingerprint.as_ref(), |out, value| write_string(out, value));
+        write_optional(out, tx.user_agent.as_ref(), |out, value| write_string(out, value));
+        out.push(tx.is_fraud as u8);
+        write_optional(out, tx.fraud_pattern.as_ref(), |out, pattern| {
+            write_enum(
+                out,
+                &FraudPattern::ALL.map(|p| p.as_str()),
+                pattern.as_str(),
+            )
+        });
+        write_optional(out, tx.persona.as_ref(), |out, value| write_string(out, value));
+        for (name, kind) in &self.custom {
+            let value = tx.custom.get(name).filter(|value| !value.is_null());
+            write_optional(out, value, |out, value| match kind {
+                CustomType::Text => write_string(out, value.as_str().unwrap_or_default()),
+                CustomType::Integer => write_long(out, value.as_i64().unwrap_or_default()),
+                CustomType::Float => {
+                    out.extend_from_slice(&value.as_f64().unwrap_or_default().to_le_bytes())
+                }
+                CustomType::Boolean => out.push(value.as_bool().unwrap_or_default() as u8),
+            });
+        }
+
+        self.count += 1;
+        if self.count >= BLOCK_SIZE {
+            self.flush_block()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.flush_block()?;
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.out.bytes()
+    }
+}
+
+// Zigzag-encoded variable-length integer, as Avro encodes int and long
+fn write_long(out: &mut Vec<u8>, value: i64) {
+    let mut n = ((value << 1) ^ (value >> 63)) as u64;
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_long(out, bytes.len() as i64);
+    out.extend_from_slice(bytes);
+}
+
+fn write_string(out: &mut Vec<u8>, value: &str) {
+    write_bytes(out, value.as_bytes());
+}
+
+// Enum symbol, written as its index in the schema's symbol list
+fn write_enum(out: &mut Vec<u8>, symbols: &[&str], value: &str) {
+    let index = symbols
+        .iter()
+        .position(|symbol| *symbol == value)
+        .unwrap_or(0);
+    write_long(out, index as i64);
+}
+
+// Value of a `["null", T]` union: the branch index, then the value if any
+fn write_optional<T>(out: &mut Vec<u8>, value: Option<T>, write: impl FnOnce(&mut Vec<u8>, T)) {
+    match value {
+        None => write_long(out, 0),
+        Some(value) => {
+            write_long(out, 1);
+            write(out, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Profile, custom::CustomFields, seed};
+    use flate2::read::DeflateDecoder;
+    use rand::{SeedableRng, rngs::StdRng};
+    use std::io::Read;
+
+    fn read_long(data: &mut &[u8]) -> i64 {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = data.split_first().expect("truncated long");
+            *data = rest;
+            n |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        (n >> 1) as i64 ^ -((n & 1) as i64)
+    }
+
+    fn read_bytes<'a>(data: &mut &'a [u8]) -> &'a [u8] {
+        let length = read_long(data) as usize;
+        let (bytes, rest) = data.split_at(length);
+        *data = rest;
+        bytes
+    }
+
+    // A value of the given schema, as JSON would hold it. Timestamps stay
+    // microseconds.
+    fn read_value(schema: &Value, data: &mut &[u8]) -> Value {
+        match schema {
+            Value::Array(branches) => {
+                let branch = read_long(data) as usize;
+                read_value(&branches[branch], data)
+            }
+            Value::Object(named) => match named["type"].as_str().unwrap() {
+                "enum" => {
+                    let index = read_long(data) as usize;
+                    named["symbols"][index].clone()
+                }
+                _ => read_value(&named["type"], data),
+            },
+            Value::String(kind) => match kind.as_str() {
+                "null" => Value::Null,
+                "boolean" => {
+                    let (&byte, rest) = data.split_first().unwrap();
+                    *data = rest;
+                    assert!(byte <= 1, "boolean byte {}", byte);
+                    json!(byte == 1)
+                }
+                "int" | "long" => json!(read_long(data)),
+                "double" => {
+                    let (bytes, rest) = data.split_at(8);
+                    *data = rest;
+                    json!(f64::from_le_bytes(bytes.try_into().unwrap()))
+                }
+                "string" => json!(std::str::from_utf8(read_bytes(data)).unwrap()),
+                other => panic!("unexpected type {}", other),
+            },
+            other => panic!("unexpected schema {}", other),
+        }
+    }
+
+    // The schema a file embeds and its records, read block by block
+    fn decode(file: &[u8]) -> (Value, Vec<Vec<(String, Value)>>) {
+        let mut data = file.strip_prefix(b"Obj\x01").expect("no magic");
+        let mut metadata = std::collections::HashMap::new();
+        loop {
+            let entries = read_long(&mut data);
+            if entries == 0 {
+                break;
+            }
+            for _ in 0..entries {
+                let key = std::str::from_utf8(read_bytes(&mut data))
+                    .unwrap()
+                    .to_string();
+                metadata.insert(key, read_bytes(&mut data).to_vec());
+            }
+        }
+        assert_eq!(metadata["avro.codec"], b"deflate");
+        let schema: Value = serde_json::from_slice(&metadata["avro.schema"]).unwrap();
+        let (sync, mut data) = data.split_at(16);
+
+        let mut records = Vec::new();
+        while !data.is_empty() {
+            let count = read_long(&mut data) as usize;
+            let compressed = read_bytes(&mut data);
+            let (marker, rest) = data.split_at(16);
+            assert_eq!(marker, sync);
+            data = rest;
+            let mut block = Vec::new();
+            DeflateDecoder::new(compressed)
+                .read_to_end(&mut block)
+                .unwrap();
+            let mut block = &block[..];
+            for _ in 0..count {
+                let fields = schema["fields"].as_array().unwrap();
+                let record = fields
+                    .iter()
+                    .map(|field| {
+                        let name = field["name"].as_str().unwrap().to_string();
+                        (name, read_value(&field["type"], &mut block))
+                    })
+                    .collect();
+                records.push(record);
+            }
+            assert!(block.is_empty(), "{} bytes left in a block", block.len());
+        }
+        (schema, records)
+    }
+
+    #[test]
+    fn records_decode_against_the_schema() {
+        let profile = Profile {
+            custom_fields: vec![
+                serde_json::from_value(json!({ "name": "region", "pick": ["eu", "us"] })).unwrap(),
+                serde_json::from_value(json!({ "name": "batch", "int_range": [-5, 5] })).unwrap(),
+                serde_json::from_value(json!({ "name": "score", "constant": 0.5 })).unwrap(),
+                serde_json::from_value(json!({ "name": "flagged", "constant": true })).unwrap(),
+            ],
+            ..Profile::default()
+        };
+        // More rows than a block holds, so the file has two
+        let mut rows: Vec<Transaction> = seed::SeededRows::new(&profile, 785, 0)
+            .take(BLOCK_SIZE + 10)
+            .collect();
+        let custom = CustomFields::new(&profile.custom_fields, profile.amount_format).unwrap();
+        let mut rng = StdRng::seed_from_u64(785);
+        for tx in &mut rows {
+            custom.apply(&mut rng, tx);
+        }
+        rows[0].installment_number = Some(2);
+        rows[0].installment_count = Some(3);
+        rows[0].refund_amount = Some(0.0);
+        rows[1].custom.insert("region".to_string(), Value::Null);
+        rows[1].custom.remove("batch");
+        rows[2].transaction_date = "1969-12-31T23:59:59.5-02:00".into();
+
+        let mut writer = AvroWriter::new(Vec::new(), &profile.custom_fields).unwrap();
+        for tx in &rows {
+            writer.write(tx).unwrap();
+        }
+        writer.finish().unwrap();
+        let (schema, records) = decode(&writer.out);
+
+        assert_eq!(schema, transaction_schema(&profile.custom_fields));
+        assert_eq!(records.len(), rows.len());
+        let mut nulls = 0;
+        for (tx, record) in rows.iter().zip(&records) {
+            let expected = serde_json::to_value(tx).unwrap();
+            for (name, value) in record {
+                let expected = match name.as_str() {
+                    "transaction_date" => json!(
+                        DateTime::parse_from_rfc3339(&tx.transaction_date)
+                            .unwrap()
+                            .timestamp_micros()
+                    ),
+                    _ => expected.get(name).cloned().unwrap_or(Value::Null),
+                };
+                assert_eq!(*value, expected, "{} of {}", name, tx.transaction_id);
+                nulls += value.is_null() as usize;
+            }
+        }
+        assert!(nulls > 0);
+        assert_eq!(
+            records[1][records[1].len() - 4],
+            ("region".to_string(), Value::Null)
+        );
+        assert_eq!(
+            records[1][records[1].len() - 3],
+            ("batch".to_string(), Value::Null)
+        );
+        let date = records[2]
+            .iter()
+            .find(|(name, _)| name == "transaction_date")
+            .unwrap();
+        assert_eq!(date.1, json!(7_199_500_000i64));
+    }
+}
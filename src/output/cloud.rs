@@ -0,0 +1,127 @@
+use clap::ValueEnum;
+use std::{fmt, io};
+
+// Object storage services that files can be uploaded to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    S3,
+    Gcs,
+    Azure,
+}
+
+impl Provider {
+    pub fn scheme(self) -> &'static str {
+        match self {
+            Provider::S3 => "s3",
+            Provider::Gcs => "gs",
+            Provider::Azure => "az",
+        }
+    }
+}
+
+// Where `--output s3://bucket/prefix/` (or `gs://` or `az://container/`)
+// uploads files to: a bucket or container, and a prefix every key starts
+// with, empty or ending in `/`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectUrl {
+    pub provider: Provider,
+    pub bucket: String,
+    pub prefix: String,
+}
+
+impl ObjectUrl {
+    // The object URL an output names, if it names one
+    pub fn parse(url: &str) -> Option<Self> {
+        let (scheme, rest) = url.split_once("://")?;
+        let provider = [Provider::S3, Provider::Gcs, Provider::Azure]
+            .into_iter()
+            .find(|provider| provider.scheme() == scheme)?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        let mut prefix = prefix.trim_matches('/').to_string();
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+        Some(Self {
+            provider,
+            bucket: bucket.to_string(),
+            prefix,
+        })
+    }
+
+    // Check the bucket is a name the services could accept
+    pub fn validate(&self) -> io::Result<()> {
+        let valid = !self.bucket.is_empty()
+            && self
+                .bucket
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-._".contains(c));
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid bucket or container name in {}", self),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ObjectUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}://{}/{}",
+            self.provider.scheme(),
+            self.bucket,
+            self.prefix
+        )
+    }
+}
+
+// Server-side encryption of uploaded objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Sse {
+    // Keys managed by the service (SSE-S3); GCS and Azure always do this
+    Aes256,
+    // A key held in the provider's key management service
+    Kms,
+}
+
+// How uploaded objects are encrypted: nothing asked for beyond the
+// service's default, or a mode with the KMS key to use, if not the default
+#[derive(Debug, Clone, Default)]
+pub struct Encryption {
+    pub sse: Option<Sse>,
+    pub kms_key_id: Option<String>,
+}
+
+impl Encryption {
+    // A KMS key on its own means KMS encryption
+    pub fn new(sse: Option<Sse>, kms_key_id: Option<String>) -> io::Result<Self> {
+        let sse = match (sse, &kms_key_id) {
+            (Some(Sse::Aes256), Some(_)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "--sse-kms-key-id only applies to --sse kms",
+                ));
+            }
+            (None, Some(_)) => Some(Sse::Kms),
+            (sse, _) => sse,
+        };
+        Ok(Self { sse, kms_key_id })
+    }
+
+    // Check the service can encrypt with what was asked for. Only S3 has a
+    // default KMS key; GCS and Azure need one named.
+    pub fn validate(&self, provider: Provider) -> io::Result<()> {
+        if self.sse == Some(Sse::Kms) && self.kms_key_id.is_none() && provider != Provider::S3 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "--sse kms on {}:// needs --sse-kms-key-id with the key to encrypt with",
+                    provider.scheme()
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use super::CSV_HEADER;
+use crate::config::Profile;
+use serde_json::{Map, Value};
+
+// Fields written by CSV, JSON, XML and SQL output, in the order and under
+// the names the profile's `[columns]` gives them
+#[derive(Debug, Clone)]
+pub struct Columns {
+    // Names of every field of a row: the built-in ones, then the custom fields
+    fields: Vec<String>,
+    // Index into `fields` and name written of each column, in column order
+    selected: Vec<(usize, String)>,
+    // Whether every field is written under its own name and in its own order
+    all: bool,
+}
+
+impl Columns {
+    pub fn new(profile: &Profile) -> Self {
+        let fields: Vec<String> = profile
+            .field_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let options = &profile.columns;
+        let included: Vec<&String> = if options.include.is_empty() {
+            fields.iter().collect()
+        } else {
+            options.include.iter().collect()
+        };
+        let selected: Vec<(usize, String)> = included
+            .into_iter()
+            .filter(|field| !options.exclude.contains(field))
+            .filter_map(|field| {
+                let i = fields.iter().position(|f| f == field)?;
+                let name = options.rename.get(field).unwrap_or(field);
+                Some((i, name.clone()))
+            })
+            .collect();
+        let all = selected.len() == fields.len()
+            && selected
+                .iter()
+                .enumerate()
+                .all(|(n, (i, name))| n == *i && *name == fields[*i]);
+        Self {
+            fields,
+            selected,
+            all,
+        }
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.all
+    }
+
+    // Names of the custom fields, which follow the built-in ones in a row
+    pub fn custom(&self) -> &[String] {
+        &self.fields[CSV_HEADER.len()..]
+    }
+
+    // Name of a field, by its index in a row
+    pub fn field(&self, i: usize) -> &str {
+        &self.fields[i]
+    }
+
+    // Name a field is written under, unless it is left out
+    pub fn name_of(&self, field: &str) -> Option<&str> {
+        self.iter()
+            .find(|(i, _)| self.fields[*i] == field)
+            .map(|(_, name)| name)
+    }
+
+    // Names written for the columns, in column order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.selected.iter().map(|(_, name)| name.as_str())
+    }
+
+    // Field written to each column and the name it is written under
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.selected.iter().map(|(i, name)| (*i, name.as_str()))
+    }
+
+    // The values of the columns, from those of every field of a row
+    pub fn pick<'a, T>(&'a self, row: &'a [T]) -> impl Iterator<Item = &'a T> {
+        self.selected.iter().map(move |(i, _)| &row[*i])
+    }
+
+    // A JSON row cut down to the columns, in their order and under their names
+    pub fn project(&self, mut row: Map<String, Value>) -> Map<String, Value> {
+        self.selected
+            .iter()
+            .map(|(i, name)| {
+                let value = row.remove(&self.fields[*i]).unwrap_or(Value::Null);
+                (name.clone(), value)
+            })
+            .collect()
+    }
+}
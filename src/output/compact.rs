@@ -0,0 +1,393 @@
+use super::{CSV_HEADER, Columns, Field, fields};
+use crate::{
+    config::{CsvOptions, CsvQuoting},
+    custom,
+    model::Transaction,
+    money::{AmountFormat, Money},
+};
+use std::io::Write;
+
+// CSV and JSON rows are written straight from the transaction, whose text
+// the generator already holds compactly, into a buffer the writer reuses
+// from row to row. The output is byte for byte what the csv crate and
+// serde_json write.
+
+// How CSV fields are delimited and quoted, following the csv crate's rules
+struct Quoting {
+    delimiter: u8,
+    style: CsvQuoting,
+    // Bytes that make a field need quotes: the delimiter, quotes and line
+    // breaks
+    special: [bool; 256],
+}
+
+impl Quoting {
+    fn new(delimiter: u8, style: CsvQuoting) -> Self {
+        let mut special = [false; 256];
+        for b in [delimiter, b'"', b'\r', b'\n'] {
+            special[b as usize] = true;
+        }
+        Self {
+            delimiter,
+            style,
+            special,
+        }
+    }
+
+    fn field(&self, text: &str, out: &mut Vec<u8>) {
+        let quoted = match self.style {
+            CsvQuoting::Necessary => text.bytes().any(|b| self.special[b as usize]),
+            CsvQuoting::Always => true,
+            CsvQuoting::NonNumeric => {
+                text.parse::<f64>().is_err() && text.parse::<i128>().is_err()
+            }
+            CsvQuoting::Never => false,
+        };
+        if !quoted {
+            out.extend_from_slice(text.as_bytes());
+            return;
+        }
+        out.push(b'"');
+        for part in text.split_inclusive('"') {
+            out.extend_from_slice(part.as_bytes());
+            if part.ends_with('"') {
+                out.push(b'"');
+            }
+        }
+        out.push(b'"');
+    }
+
+    // End a record. One with nothing in it is written as an empty quoted
+    // field, as the csv crate writes it.
+    fn end(&self, start: usize, out: &mut Vec<u8>) {
+        if out.len() == start {
+            out.extend_from_slice(b"\"\"");
+        }
+        out.push(b'\n');
+    }
+}
+
+// Writes transactions as CSV records
+pub(super) struct CsvRows {
+    columns: Columns,
+    amount_format: AmountFormat,
+    quoting: Quoting,
+    // Text of the row's numbers, reused from field to field
+    scratch: String,
+}
+
+impl CsvRows {
+    pub(super) fn new(options: &CsvOptions, amount_format: AmountFormat, columns: Columns) -> Self {
+        Self {
+            columns,
+            amount_format,
+            quoting: Quoting::new(options.delimiter as u8, options.quoting),
+            scratch: String::new(),
+        }
+    }
+
+    pub(super) fn header(&self, out: &mut Vec<u8>) {
+        let start = out.len();
+        for (n, name) in self.columns.names().enumerate() {
+            if n > 0 {
+                out.push(self.quoting.delimiter);
+            }
+            self.quoting.field(name, out);
+        }
+        self.quoting.end(start, out);
+    }
+
+    pub(super) fn row(&mut self, tx: &Transaction, out: &mut Vec<u8>) {
+        let quoting = &self.quoting;
+        let fields = fields(tx);
+        let start = out.len();
+        for (n, (i, _)) in self.columns.iter().enumerate() {
+            if n > 0 {
+                out.push(quoting.delimiter);
+            }
+            if i >= CSV_HEADER.len() {
+                quoting.field(&custom::text(tx.custom.get(self.columns.field(i))), out);
+                continue;
+            }
+            quoting.field(fields[i].csv_text(self.amount_format, &mut self.scratch), out);
+        }
+        quoting.end(start, out);
+    }
+}
+
+// Writes transactions as JSON objects, on one line each or pretty-printed
+// as elements of an array
+pub(super) struct JsonRows {
+    columns: Columns,
+    amount_format: AmountFormat,
+    pretty: bool,
+    // `"name":` written before the value of each column, in column order
+    keys: Vec<Box<[u8]>>,
+}
+
+impl JsonRows {
+    pub(super) fn new(amount_format: AmountFormat, columns: Columns, pretty: bool) -> Self {
+        let keys = columns.names().map(|name| key(name, pretty).into()).collect();
+        Self {
+            columns,
+            amount_format,
+            pretty,
+            keys,
+        }
+    }
+
+    pub(super) fn row(&mut self, tx: &Transaction, out: &mut Vec<u8>) {
+        let fields = fields(tx);
+        out.push(b'{');
+        let mut empty = true;
+        if self.columns.is_all() {
+            for (i, key) in self.keys[..CSV_HEADER.len()].iter().enumerate() {
+                self.entry(&mut empty, key, out);
+                self.value(fields[i], out);
+            }
+            // Custom fields follow in the row's order, as when it is serialized
+            for (name, value) in &tx.custom {
+                self.entry(&mut empty, &key(name, self.pretty), out);
+                let _ = serde_json::to_writer(&mut *out, value);
+            }
+        } else {
+            for (key, (i, _)) in self.keys.iter().zip(self.columns.iter()) {
+                self.entry(&mut empty, key, out);
+                if i < CSV_HEADER.len() {
+                    self.value(fields[i], out);
+                } else {
+                    let value = tx.custom.get(self.columns.field(i));
+                    let _ = serde_json::to_writer(&mut *out, &value);
+                }
+            }
+        }
+        if self.pretty && !empty {
+            out.extend_from_slice(b"\n  ");
+        }
+        out.push(b'}');
+    }
+
+    // Start an entry with its key. Pretty-printed rows are indented one
+    // level, as elements of the array.
+    fn entry(&self, empty: &mut bool, key: &[u8], out: &mut Vec<u8>) {
+        if !*empty {
+            out.push(b',');
+        }
+        *empty = false;
+        if self.pretty {
+            out.extend_from_slice(b"\n    ");
+        }
+        out.extend_from_slice(key);
+    }
+
+    fn value(&self, field: Field, out: &mut Vec<u8>) {
+        match field {
+            Field::Text(text) => string(text, out),
+            Field::Missing => out.extend_from_slice(b"null"),
+            Field::Amount(amount, currency) => match self.amount_format {
+                AmountFormat::MinorUnits if amount.is_finite() => {
+                    let _ = write!(out, "{}", Money::from_major(amount, currency).minor_units);
+                }
+                _ => number(amount, out),
+            },
+            Field::Rate(rate, _) => number(rate, out),
+            Field::Count(count) => {
+                let _ = write!(out, "{}", count);
+            }
+            Field::Flag(flag) => {
+                out.extend_from_slice(if flag { b"true" } else { b"false" })
+            }
+        }
+    }
+}
+
+// `"name":` before a value, with a space after the colon when pretty-printed
+fn key(name: &str, pretty: bool) -> Vec<u8> {
+    let mut key = Vec::new();
+    string(name, &mut key);
+    key.extend_from_slice(if pretty { b": " } else { b":" });
+    key
+}
+
+// Bytes JSON strings escape: control characters, quotes and backslashes
+const ESCAPED: [bool; 256] = {
+    let mut escaped = [false; 256];
+    let mut b = 0;
+    while b < 0x20 {
+        escaped[b] = true;
+        b += 1;
+    }
+    escaped[b'"' as usize] = true;
+    escaped[b'\\' as usize] = true;
+    escaped
+};
+
+// Text as a JSON string. Most text has nothing to escape and is copied as
+// is; the rest is escaped by serde_json.
+fn string(text: &str, out: &mut Vec<u8>) {
+    if text.bytes().any(|b| ESCAPED[b as usize]) {
+        let _ = serde_json::to_writer(&mut *out, text);
+    } else {
+        out.push(b'"');
+        out.extend_from_slice(text.as_bytes());
+        out.push(b'"');
+    }
+}
+
+fn number(value: f64, out: &mut Vec<u8>) {
+    let _ = serde_json::to_writer(&mut *out, &value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Profile,
+        custom::CustomField,
+        output::{csv_record, csv_writer, push_custom, to_json},
+        seed,
+        text::Text,
+    };
+    use serde_json::{Value, json};
+
+    // Seeded rows, and rows with text the encoders must quote or escape
+    fn rows() -> Vec<Transaction> {
+        let profile = Profile::default();
+        let mut rows: Vec<Transaction> =
+            seed::SeededRows::new(&profile, 828, 0).take(100).collect();
+        for (n, tx) in rows.iter_mut().enumerate() {
+            tx.custom.insert("region".to_string(), json!("eu"));
+            tx.custom.insert("batch".to_string(), json!(n));
+        }
+        let odd = [
+            ("O'Brien, \"Jo\"", "Shop; \"Deli\"\r\n", "Mozilla/5.0 (X11, \"a\\b\")\t\u{1}"),
+            ("Zoë 12", "1.5", "-3"),
+            ("", "", ""),
+        ];
+        for (n, (name, merchant, user_agent)) in odd.into_iter().enumerate() {
+            let mut tx = rows[n].clone();
+            tx.cardholder_name = name.into();
+            tx.merchant_name = merchant.into();
+            tx.statement_descriptor = merchant.to_uppercase().into();
+            tx.merchant_category = merchant.into();
+            tx.user_agent = Some(user_agent.into());
+            tx.card_number = ["411111******1111", "", "4|5,6"][n].into();
+            tx.cvv = [Some("12a"), Some(""), None][n].map(Text::from);
+            tx.auth_code = None;
+            tx.custom.remove("batch");
+            rows.push(tx);
+        }
+        rows
+    }
+
+    fn profiles() -> Vec<Profile> {
+        let mut profiles = Vec::new();
+        for delimiter in [',', ';', '\t', '1'] {
+            for quoting in [
+                CsvQuoting::Necessary,
+                CsvQuoting::Always,
+                CsvQuoting::NonNumeric,
+                CsvQuoting::Never,
+            ] {
+                for format in [AmountFormat::Decimal, AmountFormat::MinorUnits] {
+                    let mut profile = Profile::default();
+                    profile.csv.delimiter = delimiter;
+                    profile.csv.quoting = quoting;
+                    profile.amount_format = format;
+                    profile.custom_fields = ["region", "batch"]
+                        .map(|name| CustomField {
+                            name: name.to_string(),
+                            constant: Some(json!(0)),
+                            int_range: None,
+                            pick: None,
+                            template: None,
+                        })
+                        .to_vec();
+                    profiles.push(profile.clone());
+                    profile.columns.include = ["batch", "amount", "user_agent", "card_number"]
+                        .map(str::to_string)
+                        .to_vec();
+                    profile.columns.rename.insert("amount".to_string(), "total".to_string());
+                    profiles.push(profile.clone());
+                    profile.columns.include = vec!["auth_code".to_string()];
+                    profiles.push(profile);
+                }
+            }
+        }
+        profiles
+    }
+
+    #[test]
+    fn csv_is_what_the_csv_crate_writes() {
+        let rows = rows();
+        for profile in profiles() {
+            let columns = Columns::new(&profile);
+            let mut expected = csv_writer(Vec::new(), &profile.csv);
+            expected.write_record(columns.names()).unwrap();
+            let (mut record, mut scratch) = (csv::StringRecord::new(), String::new());
+            for tx in &rows {
+                csv_record(tx, profile.amount_format, &mut record, &mut scratch);
+                push_custom(tx, columns.custom(), &mut record);
+                let fields: Vec<&str> = record.iter().collect();
+                expected.write_record(columns.pick(&fields)).unwrap();
+            }
+            let expected = String::from_utf8(expected.into_inner().unwrap()).unwrap();
+
+            let mut encoder = CsvRows::new(&profile.csv, profile.amount_format, columns);
+            let mut written = Vec::new();
+            encoder.header(&mut written);
+            for tx in &rows {
+                encoder.row(tx, &mut written);
+            }
+            assert_eq!(String::from_utf8(written).unwrap(), expected);
+        }
+    }
+
+    // A row as serde writes it: the row itself in major units with every
+    // field, otherwise a JSON value
+    fn serialized(
+        tx: &Transaction,
+        format: AmountFormat,
+        columns: &Columns,
+        pretty: bool,
+    ) -> String {
+        let text = if format == AmountFormat::Decimal && columns.is_all() {
+            match pretty {
+                false => serde_json::to_string(tx),
+                true => serde_json::to_string_pretty(tx),
+            }
+        } else {
+            let Value::Object(row) = to_json(tx, format).unwrap() else {
+                unreachable!()
+            };
+            let row = match columns.is_all() {
+                true => row,
+                false => columns.project(row),
+            };
+            match pretty {
+                false => serde_json::to_string(&row),
+                true => serde_json::to_string_pretty(&row),
+            }
+        };
+        text.unwrap().replace('\n', "\n  ")
+    }
+
+    #[test]
+    fn json_is_what_serde_writes() {
+        let rows = rows();
+        for profile in profiles() {
+            let columns = Columns::new(&profile);
+            for pretty in [false, true] {
+                let mut encoder = JsonRows::new(profile.amount_format, columns.clone(), pretty);
+                for tx in &rows {
+                    let mut written = Vec::new();
+                    encoder.row(tx, &mut written);
+                    assert_eq!(
+                        String::from_utf8(written).unwrap(),
+                        serialized(tx, profile.amount_format, &columns, pretty)
+                    );
+                }
+            }
+        }
+    }
+}
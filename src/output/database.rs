@@ -0,0 +1,347 @@
+use super::{Columns, TransactionWriter};
+use crate::{custom::CustomField, masking::MaskingConfig};
+use std::io;
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+use {
+    crate::model::Transaction,
+    std::{
+        sync::{
+            Arc, Mutex,
+            mpsc::{self, Receiver, SyncSender},
+        },
+        thread::JoinHandle,
+    },
+};
+
+// Rows handed to a loader thread at a time
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+const BATCH_SIZE: usize = 5_000;
+
+// Pooled connections, each driven by its own loader thread
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+const CONNECTIONS: u32 = 4;
+
+// Create a writer that loads transactions into the given table of the
+// database at `url`, creating the table if it doesn't exist yet
+#[cfg_attr(
+    not(all(feature = "postgres", feature = "mysql")),
+    allow(unused_variables)
+)]
+pub fn create_database_writer(
+    url: &str,
+    table: &str,
+    custom_fields: &[CustomField],
+    masking: &MaskingConfig,
+    columns: Columns,
+) -> io::Result<Box<dyn TransactionWriter>> {
+    let scheme = url.split_once("://").map_or("", |(scheme, _)| scheme);
+    match scheme {
+        #[cfg(feature = "postgres")]
+        "postgres" | "postgresql" => Ok(Box::new(PooledWriter::new(postgres::Loader::connect(
+            url,
+            table,
+            custom_fields,
+            masking,
+            columns,
+        )?))),
+        #[cfg(not(feature = "postgres"))]
+        "postgres" | "postgresql" => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "postgres output requires building with `--features postgres`",
+        )),
+        #[cfg(feature = "mysql")]
+        "mysql" => Ok(Box::new(PooledWriter::new(mysql::Loader::connect(
+            url,
+            table,
+            custom_fields,
+            masking,
+            columns,
+        )?))),
+        #[cfg(not(feature = "mysql"))]
+        "mysql" => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "mysql output requires building with `--features mysql`",
+        )),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "unsupported database URL {:?}, expected postgres:// or mysql://",
+                url
+            ),
+        )),
+    }
+}
+
+// Bulk-loads a batch of rows over one of the pool's connections
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+trait Load: Send + Sync + 'static {
+    fn load(&self, rows: &[Transaction]) -> io::Result<()>;
+}
+
+// Buffers transactions into batches and loads them on several pooled
+// connections at once. The first error from a loader thread is reported by
+// the next call to `write` or `finish`.
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+struct PooledWriter {
+    buffer: Vec<Transaction>,
+    batches: Option<SyncSender<Vec<Transaction>>>,
+    workers: Vec<JoinHandle<()>>,
+    error: Arc<Mutex<Option<io::Error>>>,
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+impl PooledWriter {
+    fn new<L: Load>(loader: L) -> Self {
+        let loader = Arc::new(loader);
+        let (sender, receiver) = mpsc::sync_channel(CONNECTIONS as usize);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let error = Arc::new(Mutex::new(None));
+        let workers = (0..CONNECTIONS)
+            .map(|_| {
+                let loader = loader.clone();
+                let receiver = receiver.clone();
+                let error = error.clone();
+                std::thread::spawn(move || load_batches(&*loader, &receiver, &error))
+            })
+            .collect();
+        Self {
+            buffer: Vec::with_capacity(BATCH_SIZE),
+            batches: Some(sender),
+            workers,
+            error,
+        }
+    }
+
+    fn check_error(&self) -> io::Result<()> {
+        match self.error.lock().unwrap().take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn send_batch(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let batch = std::mem::replace(&mut self.buffer, Vec::with_capacity(BATCH_SIZE));
+        if let Some(batches) = &self.batches
+            && batches.send(batch).is_err()
+        {
+            // Every loader thread has stopped, so one of them failed
+            self.check_error()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+impl TransactionWriter for PooledWriter {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        self.check_error()?;
+        self.buffer.push(tx.clone());
+        if self.buffer.len() >= BATCH_SIZE {
+            self.send_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.send_batch()?;
+        // Closing the channel lets the loader threads drain it and exit
+        self.batches = None;
+        for worker in self.workers.drain(..) {
+            worker
+                .join()
+                .map_err(|_| io::Error::other("database loader thread panicked"))?;
+        }
+        self.check_error()
+    }
+}
+
+// Loader thread body: load batches until the channel closes or a load fails
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+fn load_batches<L: Load>(
+    loader: &L,
+    batches: &Mutex<Receiver<Vec<Transaction>>>,
+    error: &Mutex<Option<io::Error>>,
+) {
+    loop {
+        let batch = match batches.lock().unwrap().recv() {
+            Ok(batch) => batch,
+            Err(_) => return,
+        };
+        if let Err(e) = loader.load(&batch) {
+            error.lock().unwrap().get_or_insert(e);
+            return;
+        }
+    }
+}
+
+// Postgres, loading each batch with COPY
+#[cfg(feature = "postgres")]
+mod postgres {
+    use super::{CONNECTIONS, Load};
+    use crate::{
+        custom::CustomField,
+        masking::MaskingConfig,
+        model::Transaction,
+        output::{Columns, sql, to_io},
+    };
+    use postgres::{Config, NoTls};
+    use r2d2_postgres::PostgresConnectionManager;
+    use std::io;
+
+    pub struct Loader {
+        pool: r2d2::Pool<PostgresConnectionManager<NoTls>>,
+        copy: String,
+        columns: Columns,
+    }
+
+    impl Loader {
+        pub fn connect(
+            url: &str,
+            table: &str,
+            custom_fields: &[CustomField],
+            masking: &MaskingConfig,
+            columns: Columns,
+        ) -> io::Result<Self> {
+            let config: Config = url
+                .parse()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            let pool = r2d2::Pool::builder()
+                .max_size(CONNECTIONS)
+                .build(PostgresConnectionManager::new(config, NoTls))
+                .map_err(to_io)?;
+            pool.get()
+                .map_err(to_io)?
+                .batch_execute(&sql::create_table(
+                    table,
+                    &sql::masked_types(masking),
+                    custom_fields,
+                    &columns,
+                ))
+                .map_err(to_io)?;
+
+            let names: Vec<&str> = columns.names().collect();
+            Ok(Self {
+                pool,
+                copy: format!("COPY {} ({}) FROM STDIN", table, names.join(", ")),
+                columns,
+            })
+        }
+    }
+
+    impl Load for Loader {
+        fn load(&self, rows: &[Transaction]) -> io::Result<()> {
+            let mut conn = self.pool.get().map_err(to_io)?;
+            let mut copy = conn.copy_in(&self.copy).map_err(to_io)?;
+            for tx in rows {
+                sql::write_copy_row(&mut copy, tx, &self.columns)?;
+            }
+            copy.finish().map_err(to_io)?;
+            Ok(())
+        }
+    }
+}
+
+// MySQL, loading each batch as multi-row INSERT statements
+#[cfg(feature = "mysql")]
+mod mysql {
+    use super::Load;
+    use crate::{
+        custom::CustomField,
+        masking::MaskingConfig,
+        model::Transaction,
+        output::{
+            Columns,
+            sql::{self, Value},
+            to_io,
+        },
+    };
+    use chrono::DateTime;
+    use mysql::{Opts, Params, Pool, prelude::Queryable};
+    use std::io;
+
+    // Rows per INSERT, keeping statements well below the placeholder limit
+    const ROWS_PER_INSERT: usize = 1_000;
+
+    pub struct Loader {
+        pool: Pool,
+        table: String,
+        columns: Columns,
+    }
+
+    impl Loader {
+        pub fn connect(
+            url: &str,
+            table: &str,
+            custom_fields: &[CustomField],
+            masking: &MaskingConfig,
+            columns: Columns,
+        ) -> io::Result<Self> {
+            let opts =
+                Opts::from_url(url).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            let pool = Pool::new(opts).map_err(to_io)?;
+            // MySQL has no zoned timestamp type, so dates are stored in UTC
+            let mut types = sql::masked_types(masking);
+            types.push(("transaction_date", "DATETIME NOT NULL"));
+            pool.get_conn()
+                .and_then(|mut conn| {
+                    conn.query_drop(sql::create_table(table, &types, custom_fields, &columns))
+                })
+                .map_err(to_io)?;
+            Ok(Self {
+                pool,
+                table: table.to_string(),
+                columns,
+            })
+        }
+
+        fn insert(&self, rows: usize) -> String {
+            let names: Vec<&str> = self.columns.names().collect();
+            let row = format!("({})", vec!["?"; names.len()].join(", "));
+            format!(
+                "INSERT INTO {} ({}) VALUES {}",
+                self.table,
+                names.join(", "),
+                vec![row; rows].join(", ")
+            )
+        }
+    }
+
+    impl Load for Loader {
+        fn load(&self, rows: &[Transaction]) -> io::Result<()> {
+            let mut conn = self.pool.get_conn().map_err(to_io)?;
+            let mut tx = conn.start_transaction(Default::default()).map_err(to_io)?;
+            for chunk in rows.chunks(ROWS_PER_INSERT) {
+                let columns = self.columns.names().count();
+                let mut params = Vec::with_capacity(chunk.len() * columns);
+                for row in chunk {
+                    for value in sql::row_values(row, &self.columns) {
+                        params.push(match value {
+                            Value::Null => mysql::Value::NULL,
+                            Value::Text(text) => mysql::Value::from(text),
+                            Value::Timestamp(text) => {
+                                let utc = DateTime::parse_from_rfc3339(text)
+                                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+                                    .naive_utc();
+                                mysql::Value::from(utc.format("%Y-%m-%d %H:%M:%S").to_string())
+                            }
+                            Value::Integer(number) => mysql::Value::from(number),
+                            Value::Long(number) => mysql::Value::from(number),
+                            Value::Float(number) => mysql::Value::from(number),
+                            Value::Amount(amount) => mysql::Value::from(amount.to_major()),
+                            Value::Rate(number) | Value::Percent(number) => {
+                                mysql::Value::from(number)
+                            }
+                            Value::Bool(flag) => mysql::Value::from(flag),
+                        });
+                    }
+                }
+                tx.exec_drop(self.insert(chunk.len()), Params::Positional(params))
+                    .map_err(to_io)?;
+            }
+            tx.commit().map_err(to_io)
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use super::{AmountFields, CSV_HEADER, Finish, TransactionWriter};
+use crate::{
+    config::{Alignment, FixedWidthColumn, FixedWidthOptions},
+    custom,
+    model::Transaction,
+    money::AmountFormat,
+};
+use std::io;
+
+// Writes transactions as fixed-width records, one per line, with every value
+// padded to its column's width. Absent optional fields and filler columns are
+// written as padding only.
+pub struct FixedWidthWriter<W: Finish> {
+    out: W,
+    // Each column with the index of its field in `Transaction` field order,
+    // or None for filler and custom fields, which are looked up by name
+    columns: Vec<(FixedWidthColumn, Option<usize>)>,
+    record: String,
+    amount_format: AmountFormat,
+}
+
+impl<W: Finish> FixedWidthWriter<W> {
+    pub fn new(out: W, options: &FixedWidthOptions, amount_format: AmountFormat) -> Self {
+        let columns = options
+            .columns
+            .iter()
+            .map(|column| {
+                let index = CSV_HEADER.iter().position(|field| *field == column.field);
+                (column.clone(), index)
+            })
+            .collect();
+        Self {
+            out,
+            columns,
+            record: String::new(),
+            amount_format,
+        }
+    }
+}
+
+impl<W: Finish> TransactionWriter for FixedWidthWriter<W> {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        let amounts = AmountFields::new(tx, self.amount_format);
+        let fx_rate = tx.fx_rate.map(|r| format!("{:.6}", r));
+        let fx_markup = tx.fx_markup.map(|m| format!("{:.2}", m));
+        let installment_number = tx.installment_number.map(|n| n.to_string());
+        let installment_count = tx.installment_count.map(|n| n.to_string());
+        let is_fraud = tx.is_fraud.to_string();
+        let values = [
+            tx.transaction_id.as_str(),
+            tx.event_type.as_ref().map_or("", |e| e.as_str()),
+            &tx.transaction_date,
+            tx.status.as_str(),
+            tx.decline_reason.as_ref().map_or("", |r| r.as_str()),
+            &tx.response_code,
+            tx.auth_code.as_deref().unwrap_or(""),
+            tx.original_transaction_id.as_deref().unwrap_or(""),
+            tx.subscription_id.as_deref().unwrap_or(""),
+            tx.initiator.as_ref().map_or("", |i| i.as_str()),
+            installment_number.as_deref().unwrap_or(""),
+            installment_count.as_deref().unwrap_or(""),
+            tx.order_reference.as_deref().unwrap_or(""),
+            tx.preauth_step.as_ref().map_or("", |s| s.as_str()),
+            &tx.customer_id,
+            &tx.cardholder_name,
+            &tx.email,
+            &tx.phone,
+            &tx.card_number,
+            &tx.card_brand,
+            &tx.card_expiry,
+            tx.cvv.as_deref().unwrap_or(""),
+            tx.issuer_bank.as_deref().unwrap_or(""),
+            tx.card_country.as_deref().unwrap_or(""),
+            tx.card_type.as_ref().map_or("", |t| t.as_str()),
+            &tx.billing_street,
+            &tx.billing_city,
+            &tx.billing_region,
+            &tx.billing_postal_code,
+            &tx.billing_country,
+            tx.shipping_street.as_deref().unwrap_or(""),
+            tx.shipping_city.as_deref().unwrap_or(""),
+            tx.shipping_region.as_deref().unwrap_or(""),
+            tx.shipping_postal_code.as_deref().unwrap_or(""),
+            tx.shipping_country.as_deref().unwrap_or(""),
+            &amounts.amount,
+            amounts.refund_amount.as_deref().unwrap_or(""),
+            amounts.remaining_amount.as_deref().unwrap_or(""),
+            &tx.currency,
+            amounts.original_amount.as_deref().unwrap_or(""),
+            tx.original_currency.as_deref().unwrap_or(""),
+            amounts.billing_amount.as_deref().unwrap_or(""),
+            tx.billing_currency.as_deref().unwrap_or(""),
+            fx_rate.as_deref().unwrap_or(""),
+            fx_markup.as_deref().unwrap_or(""),
+            &tx.merchant_name,
+            &tx.merchant_id,
+            &tx.statement_descriptor,
+            &tx.merchant_category,
+            tx.mcc.as_deref().unwrap_or(""),
+            &tx.acquirer_id,
+            &tx.processor,
+            tx.batch_id.as_deref().unwrap_or(""),
+            tx.settlement_date.as_deref().unwrap_or(""),
+            &tx.payment_method,
+            tx.channel.as_str(),
+            &tx.pos_entry_mode,
+            tx.terminal_id.as_deref().unwrap_or(""),
+            tx.track1.as_deref().unwrap_or(""),
+            tx.track2.as_deref().unwrap_or(""),
+            tx.emv_aid.as_deref().unwrap_or(""),
+            tx.emv_cryptogram.as_deref().unwrap_or(""),
+            tx.emv_tvr.as_deref().unwrap_or(""),
+            tx.emv_atc.as_deref().unwrap_or(""),
+            tx.wallet.as_ref().map_or("", |w| w.as_str()),
+            tx.wallet_id.as_deref().unwrap_or(""),
+            tx.token_pan.as_deref().unwrap_or(""),
+            tx.token_requestor_id.as_deref().unwrap_or(""),
+            tx.three_ds_version.as_deref().unwrap_or(""),
+            tx.eci.as_deref().unwrap_or(""),
+            tx.authentication_result.as_ref().map_or("", |r| r.as_str()),
+            tx.cavv.as_deref().unwrap_or(""),
+            tx.avs_result.as_deref().unwrap_or(""),
+            tx.cvv_result.as_deref().unwrap_or(""),
+            tx.ip_address.as_deref().unwrap_or(""),
+            tx.ip_country.as_deref().unwrap_or(""),
+            tx.device_id.as_deref().unwrap_or(""),
+            tx.device_platform.as_deref().unwrap_or(""),
+            tx.device_os.as_deref().unwrap_or(""),
+            tx.device_browser.as_deref().unwrap_or(""),
+            tx.screen_resolution.as_deref().unwrap_or(""),
+            tx.device_fingerprint.as_deref().unwrap_or(""),
+            tx.user_agent.as_deref().unwrap_or(""),
+            &is_fraud,
+            tx.fraud_pattern.as_ref().map_or("", |p| p.as_str()),
+            tx.persona.as_deref().unwrap_or(""),
+        ];
+
+        self.record.clear();
+        for (column, index) in &self.columns {
+            let custom = index
+                .is_none()
+                .then(|| custom::text(tx.custom.get(&column.field)));
+            let value = index.map_or_else(|| custom.as_deref().unwrap_or(""), |i| values[i]);
+            // Widths count bytes, so every record is as long as every other
+            // whatever its characters take. Values are cut between
+            // characters, and padded to make up any byte left over.
+            let value = if value.len() <= column.width {
+                value
+            } else if column.truncate {
+                &value[..value.floor_char_boundary(column.width)]
+            } else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{} {:?} doesn't fit its {}-byte fixed-width column",
+                        column.field, value, column.width
+                    ),
+                ));
+            };
+            let padding = column.width - value.len();
+            let pad = |record: &mut String| record.extend((0..padding).map(|_| column.padding));
+            match column.align {
+                Alignment::Left => {
+                    self.record.push_str(value);
+                    pad(&mut self.record);
+                }
+                // Zero padding goes after the sign, so refunds read -0000012.50
+                Alignment::Right if column.padding == '0' && value.starts_with('-') => {
+                    self.record.push('-');
+                    pad(&mut self.record);
+                    self.record.push_str(&value[1..]);
+                }
+                Alignment::Right => {
+                    pad(&mut self.record);
+                    self.record.push_str(value);
+                }
+            }
+        }
+        self.record.push('\n');
+        self.out.write_all(self.record.as_bytes())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.out.bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Profile, generator::TransactionStream, names::Locale};
+
+    #[test]
+    fn records_have_the_same_byte_length() {
+        let mut profile = Profile::default();
+        profile.names.locales = Locale::ALL.iter().map(|locale| (*locale, 1.0)).collect();
+        let options = FixedWidthOptions::default();
+        let mut writer = FixedWidthWriter::new(Vec::new(), &options, AmountFormat::Decimal);
+        for tx in TransactionStream::new(&profile, 5_000) {
+            writer.write(&tx).unwrap();
+        }
+        writer.finish().unwrap();
+        let width: usize = options.columns.iter().map(|column| column.width).sum();
+        // Names in other scripts take several bytes per character, and are
+        // cut between characters rather than through them
+        let output = String::from_utf8(writer.out).unwrap();
+        assert!(!output.is_ascii());
+        for record in output.lines() {
+            assert_eq!(record.len(), width, "{:?}", record);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use super::{Counted, Finish, TransactionWriter};
+use crate::{fixtures::Provider, model::Transaction};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+// Writes the response a payment gateway would give for each transaction as
+// `<transaction_id>.json` in a directory of its own, for mocking the
+// gateway's API in tests. `index.csv` lists each file with the HTTP status
+// it's served with, so a mock server can return declines as errors.
+pub struct FixtureWriter {
+    dir: PathBuf,
+    provider: Provider,
+    index: csv::Writer<Counted<BufWriter<File>>>,
+    // Bytes of the fixture files written
+    bytes: u64,
+}
+
+impl FixtureWriter {
+    pub fn new(dir: &Path, provider: Provider) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let index = Counted::new(BufWriter::new(File::create(dir.join("index.csv"))?));
+        let mut index = csv::Writer::from_writer(index);
+        index.write_record(["file", "http_status", "transaction_id", "status"])?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            provider,
+            index,
+            bytes: 0,
+        })
+    }
+}
+
+impl TransactionWriter for FixtureWriter {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        let fixture = self.provider.fixture(tx)?;
+        let name = format!("{}.json", tx.transaction_id);
+        let mut file = Counted::new(BufWriter::new(File::create(self.dir.join(&name))?));
+        serde_json::to_writer_pretty(&mut file, &fixture.body)?;
+        file.write_all(b"\n")?;
+        file.flush()?;
+        self.bytes += file.bytes();
+        self.index.write_record([
+            name.as_str(),
+            &fixture.status.to_string(),
+            &tx.transaction_id,
+            tx.status.as_str(),
+        ])?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.index.flush()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.bytes + self.index.get_ref().bytes()
+    }
+}
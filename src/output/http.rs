@@ -0,0 +1,264 @@
+use super::TransactionWriter;
+use crate::{
+    model::Transaction,
+    webhook::{Event, Signer},
+};
+use chrono::Utc;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    io,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, SyncSender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use ureq::Agent;
+
+// Delay before the first retry; each later retry waits twice as long
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+// Longest a single request may take before it counts as failed
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Results of a replay run, filled in by the request threads
+#[derive(Debug, Default)]
+struct Stats {
+    // Round-trip time of every answered request, including retried attempts
+    latencies: Vec<Duration>,
+    // Final response status of each transaction
+    statuses: BTreeMap<u16, usize>,
+    succeeded: usize,
+    failed: usize,
+    retries: usize,
+    // Transactions that never got a response, with the last transport error
+    unreachable: usize,
+    last_error: Option<String>,
+}
+
+impl Stats {
+    // Request counts, status codes and latency percentiles of a run that
+    // took `elapsed`
+    fn summary(&self, elapsed: Duration) -> Result<String, fmt::Error> {
+        let mut out = String::new();
+        let total = self.succeeded + self.failed + self.unreachable;
+        let elapsed = elapsed.as_secs_f64();
+        writeln!(
+            out,
+            "{} requests in {:.1}s ({:.1}/s): {} succeeded, {} failed, {} unreachable, {} retries",
+            total,
+            elapsed,
+            total as f64 / elapsed.max(f64::EPSILON),
+            self.succeeded,
+            self.failed,
+            self.unreachable,
+            self.retries
+        )?;
+        if !self.statuses.is_empty() {
+            let statuses: Vec<String> = self
+                .statuses
+                .iter()
+                .map(|(status, count)| format!("{}: {}", status, count))
+                .collect();
+            writeln!(out, "Status codes: {}", statuses.join(", "))?;
+        }
+        if !self.latencies.is_empty() {
+            let mut latencies = self.latencies.clone();
+            latencies.sort();
+            let percentile = |p: f64| {
+                let rank = ((latencies.len() - 1) as f64 * p).round() as usize;
+                latencies[rank].as_secs_f64() * 1000.0
+            };
+            writeln!(
+                out,
+                "Latency (ms): p50 {:.1}, p90 {:.1}, p99 {:.1}, max {:.1}",
+                percentile(0.5),
+                percentile(0.9),
+                percentile(0.99),
+                percentile(1.0)
+            )?;
+        }
+        if let Some(error) = &self.last_error {
+            writeln!(out, "Last error: {}", error)?;
+        }
+        Ok(out)
+    }
+}
+
+// A request body, with its webhook signature when deliveries are signed
+struct Request {
+    body: String,
+    signature: Option<String>,
+}
+
+// POSTs each transaction as JSON to an endpoint from several request threads
+// at once, retrying throttled and failed requests with exponential backoff.
+// Failed requests are counted in the report rather than stopping the run.
+// With a signer, transactions are sent as signed webhook events instead.
+pub struct HttpWriter {
+    requests: Option<SyncSender<Request>>,
+    signer: Option<Signer>,
+    // Draws the IDs of signed events
+    rng: StdRng,
+    workers: Vec<JoinHandle<()>>,
+    stats: Arc<Mutex<Stats>>,
+    started: Instant,
+    // Bytes of the request bodies sent
+    bytes: u64,
+}
+
+impl HttpWriter {
+    pub fn new(
+        url: &str,
+        concurrency: usize,
+        retries: u32,
+        signer: Option<Signer>,
+    ) -> io::Result<Self> {
+        let agent: Agent = Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_global(Some(REQUEST_TIMEOUT))
+            .max_idle_connections_per_host(concurrency)
+            .build()
+            .into();
+        let stats = Arc::new(Mutex::new(Stats::default()));
+        let (sender, receiver) = mpsc::sync_channel(concurrency);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..concurrency)
+            .map(|_| {
+                let agent = agent.clone();
+                let url = url.to_string();
+                let receiver = receiver.clone();
+                let stats = stats.clone();
+                thread::spawn(move || post_requests(&agent, &url, retries, &receiver, &stats))
+            })
+            .collect();
+        Ok(Self {
+            requests: Some(sender),
+            signer,
+            rng: StdRng::from_entropy(),
+            workers,
+            stats,
+            started: Instant::now(),
+            bytes: 0,
+        })
+    }
+}
+
+impl TransactionWriter for HttpWriter {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        let request = match &self.signer {
+            Some(signer) => {
+                let body = serde_json::to_string(&Event::new(&mut self.rng, tx)?)?;
+                let signature = signer.sign(Utc::now().timestamp(), &body);
+                Request {
+                    body,
+                    signature: Some(signature),
+                }
+            }
+            None => Request {
+                body: serde_json::to_string(tx)?,
+                signature: None,
+            },
+        };
+        self.bytes += request.body.len() as u64;
+        if let Some(requests) = &self.requests {
+            requests
+                .send(request)
+                .map_err(|_| io::Error::other("HTTP request threads stopped"))?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        // Closing the channel lets the request threads drain it and exit
+        self.requests = None;
+        for worker in self.workers.drain(..) {
+            worker
+                .join()
+                .map_err(|_| io::Error::other("HTTP request thread panicked"))?;
+        }
+        Ok(())
+    }
+
+    fn summary(&self) -> Option<String> {
+        self.stats
+            .lock()
+            .unwrap()
+            .summary(self.started.elapsed())
+            .ok()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+// Request thread body: POST requests until the channel closes
+fn post_requests(
+    agent: &Agent,
+    url: &str,
+    retries: u32,
+    requests: &Mutex<Receiver<Request>>,
+    stats: &Mutex<Stats>,
+) {
+    let mut rng = rand::thread_rng();
+    loop {
+        let request = match requests.lock().unwrap().recv() {
+            Ok(request) => request,
+            Err(_) => return,
+        };
+
+        let mut attempt = 0;
+        loop {
+            let started = Instant::now();
+            let mut post = agent.post(url).header("Content-Type", "application/json");
+            if let Some(signature) = &request.signature {
+                post = post.header(Signer::HEADER, signature);
+            }
+            let result = post.send(request.body.as_str());
+            let latency = started.elapsed();
+
+            // Throttling, server errors and transport errors are worth retrying
+            let retry = match &result {
+                Ok(response) => {
+                    let status = response.status();
+                    status.as_u16() == 429 || status.is_server_error()
+                }
+                Err(_) => true,
+            };
+            if retry && attempt < retries {
+                stats.lock().unwrap().retries += 1;
+                if result.is_ok() {
+                    stats.lock().unwrap().latencies.push(latency);
+                }
+                // Full jitter keeps retrying threads from hitting the endpoint in step
+                let backoff = INITIAL_BACKOFF * 2u32.pow(attempt);
+                thread::sleep(backoff.mul_f64(rng.gen_range(0.5..=1.0)));
+                attempt += 1;
+                continue;
+            }
+
+            let mut stats = stats.lock().unwrap();
+            match result {
+                Ok(response) => {
+                    let status = response.status();
+                    stats.latencies.push(latency);
+                    *stats.statuses.entry(status.as_u16()).or_default() += 1;
+                    if status.is_success() {
+                        stats.succeeded += 1;
+                    } else {
+                        stats.failed += 1;
+                    }
+                }
+                Err(e) => {
+                    stats.unreachable += 1;
+                    stats.last_error = Some(e.to_string());
+                }
+            }
+            break;
+        }
+    }
+}
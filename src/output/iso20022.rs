@@ -0,0 +1,437 @@
+use super::{Finish, TransactionWriter};
+use crate::{
+    model::{LifecycleEvent, Transaction, TransactionStatus},
+    money::Money,
+};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+// The acquirer whose settlement account pays merchants out
+const ACQUIRER_NAME: &str = "LuhnSynth Acquiring";
+const ACQUIRER_BIC: &str = "LUHNGB2LXXX";
+const ACQUIRER_ACCOUNT: u64 = 1;
+
+// ISO 20022 documents that settlements can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Document {
+    // pain.001 customer credit transfer initiation: the acquirer's
+    // instructions to pay each merchant its net card sales
+    PaymentInitiation,
+    // camt.053 bank-to-customer statement of the acquirer's settlement
+    // account, with an entry for each of those payouts
+    Statement,
+}
+
+// A merchant's net card sales in one currency on one day, paid out the
+// following day
+struct Payout {
+    merchant_name: String,
+    // Net amount in minor units; refunds count against sales
+    amount: i64,
+    transactions: usize,
+}
+
+// A payout with the end-to-end reference it is paid under
+struct Transfer<'a> {
+    reference: String,
+    merchant_id: &'a str,
+    payout: &'a Payout,
+}
+
+// Aggregates settled transactions into merchant payouts and writes them as
+// an ISO 20022 document once the last transaction is in. Both documents are
+// derived from the payouts alone, so the pain.001 and camt.053 files of one
+// dataset share message IDs and end-to-end references that reconciliation
+// can match on.
+pub struct Iso20022Writer<W: Finish> {
+    out: W,
+    document: Document,
+    payouts: Payouts,
+}
+
+impl<W: Finish> Iso20022Writer<W> {
+    pub fn new(out: W, document: Document) -> Self {
+        Self {
+            out,
+            document,
+            payouts: BTreeMap::new(),
+        }
+    }
+
+    // Message ID derived from the payouts themselves, so the documents of
+    // one dataset agree on it without sharing state
+    fn message_id(&self) -> String {
+        let mut hash = FNV_OFFSET;
+        for ((date, currency, merchant_id), payout) in &self.payouts {
+            let key = format!("{}{}{}{}", date, currency, merchant_id, payout.amount);
+            hash = fnv1a(hash, key.as_bytes());
+        }
+        format!("LS{:016X}", hash)
+    }
+}
+
+impl<W: Finish> TransactionWriter for Iso20022Writer<W> {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        // Only money that actually moved is paid out: approved sales and
+        // refunds other than holds, counted once at settlement in lifecycle
+        // mode
+        if !matches!(
+            tx.status,
+            TransactionStatus::Approved | TransactionStatus::Refunded
+        ) || tx.is_hold()
+            || tx
+                .event_type
+                .is_some_and(|event| event != LifecycleEvent::Settlement)
+        {
+            return Ok(());
+        }
+        let date = DateTime::parse_from_rfc3339(&tx.transaction_date)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            .date_naive();
+        let minor_units = Money::from_major(tx.amount, &tx.currency).minor_units;
+        let payout = self
+            .payouts
+            .entry((date, tx.currency.to_string(), tx.merchant_id.to_string()))
+            .or_insert_with(|| Payout {
+                merchant_name: tx.merchant_name.to_string(),
+                amount: 0,
+                transactions: 0,
+            });
+        payout.amount += minor_units;
+        payout.transactions += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let message_id = self.message_id();
+        match self.document {
+            Document::PaymentInitiation => {
+                write_payment_initiation(&mut self.out, &self.payouts, &message_id)?
+            }
+            Document::Statement => write_statement(&mut self.out, &self.payouts, &message_id)?,
+        }
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.out.bytes()
+    }
+}
+
+// Keyed by sales date, currency and merchant ID
+type Payouts = BTreeMap<(NaiveDate, String, String), Payout>;
+
+// Payouts with a positive net amount, grouped by currency and execution
+// date, each with its end-to-end reference
+fn batches(payouts: &Payouts) -> BTreeMap<(String, NaiveDate), Vec<Transfer<'_>>> {
+    let mut batches: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for ((date, currency, merchant_id), payout) in payouts {
+        if payout.amount <= 0 {
+            continue;
+        }
+        let executed = *date + Days::new(1);
+        let reference: String = format!("STL{}{}{}", date.format("%Y%m%d"), currency, merchant_id)
+            .chars()
+            .take(35)
+            .collect();
+        batches
+            .entry((currency.clone(), executed))
+            .or_default()
+            .push(Transfer {
+                reference,
+                merchant_id,
+                payout,
+            });
+    }
+    batches
+}
+
+fn write_payment_initiation<W: Write>(
+    out: &mut W,
+    payouts: &Payouts,
+    message_id: &str,
+) -> io::Result<()> {
+    let batches = batches(payouts);
+    let transfers = || {
+        batches
+            .iter()
+            .flat_map(|((currency, _), transfers)| transfers.iter().map(move |t| (currency, t)))
+    };
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:pain.001.001.09">"#
+    )?;
+    writeln!(out, "  <CstmrCdtTrfInitn>")?;
+    writeln!(out, "    <GrpHdr>")?;
+    writeln!(out, "      <MsgId>{}</MsgId>", message_id)?;
+    writeln!(out, "      <CreDtTm>{}</CreDtTm>", created())?;
+    writeln!(out, "      <NbOfTxs>{}</NbOfTxs>", transfers().count())?;
+    writeln!(out, "      <CtrlSum>{}</CtrlSum>", control_sum(transfers()))?;
+    writeln!(
+        out,
+        "      <InitgPty><Nm>{}</Nm></InitgPty>",
+        escape(ACQUIRER_NAME)
+    )?;
+    writeln!(out, "    </GrpHdr>")?;
+    for ((currency, executed), transfers) in &batches {
+        writeln!(out, "    <PmtInf>")?;
+        writeln!(
+            out,
+            "      <PmtInfId>{}</PmtInfId>",
+            payment_information_id(message_id, currency, *executed)
+        )?;
+        writeln!(out, "      <PmtMtd>TRF</PmtMtd>")?;
+        writeln!(out, "      <NbOfTxs>{}</NbOfTxs>", transfers.len())?;
+        let sum = control_sum(transfers.iter().map(|t| (currency, t)));
+        writeln!(out, "      <CtrlSum>{}</CtrlSum>", sum)?;
+        writeln!(
+            out,
+            "      <ReqdExctnDt><Dt>{}</Dt></ReqdExctnDt>",
+            executed
+        )?;
+        writeln!(out, "      <Dbtr><Nm>{}</Nm></Dbtr>", escape(ACQUIRER_NAME))?;
+        writeln!(
+            out,
+            "      <DbtrAcct><Id><IBAN>{}</IBAN></Id><Ccy>{}</Ccy></DbtrAcct>",
+            iban(ACQUIRER_ACCOUNT),
+            currency
+        )?;
+        writeln!(
+            out,
+            "      <DbtrAgt><FinInstnId><BICFI>{}</BICFI></FinInstnId></DbtrAgt>",
+            ACQUIRER_BIC
+        )?;
+        for Transfer {
+            reference,
+            merchant_id,
+            payout,
+        } in transfers
+        {
+            writeln!(out, "      <CdtTrfTxInf>")?;
+            writeln!(
+                out,
+                "        <PmtId><EndToEndId>{}</EndToEndId></PmtId>",
+                reference
+            )?;
+            writeln!(
+                out,
+                r#"        <Amt><InstdAmt Ccy="{}">{}</InstdAmt></Amt>"#,
+                currency,
+                amount(payout.amount, currency)
+            )?;
+            writeln!(
+                out,
+                "        <Cdtr><Nm>{}</Nm></Cdtr>",
+                escape(&payout.merchant_name)
+            )?;
+            writeln!(
+                out,
+                "        <CdtrAcct><Id><IBAN>{}</IBAN></Id></CdtrAcct>",
+                iban(merchant_account(merchant_id))
+            )?;
+            writeln!(
+                out,
+                "        <RmtInf><Ustrd>{}</Ustrd></RmtInf>",
+                remittance(*executed, payout)
+            )?;
+            writeln!(out, "      </CdtTrfTxInf>")?;
+        }
+        writeln!(out, "    </PmtInf>")?;
+    }
+    writeln!(out, "  </CstmrCdtTrfInitn>")?;
+    writeln!(out, "</Document>")
+}
+
+fn write_statement<W: Write>(out: &mut W, payouts: &Payouts, message_id: &str) -> io::Result<()> {
+    let batches = batches(payouts);
+    let created = created();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.08">"#
+    )?;
+    writeln!(out, "  <BkToCstmrStmt>")?;
+    writeln!(out, "    <GrpHdr>")?;
+    writeln!(out, "      <MsgId>{}</MsgId>", message_id)?;
+    writeln!(out, "      <CreDtTm>{}</CreDtTm>", created)?;
+    writeln!(out, "    </GrpHdr>")?;
+
+    // One statement per currency account, starting out funded with
+    // exactly what is paid out over the period
+    let mut currencies: BTreeMap<&str, Vec<_>> = BTreeMap::new();
+    for ((currency, executed), transfers) in &batches {
+        let entries = currencies.entry(currency.as_str()).or_default();
+        entries.extend(transfers.iter().map(|transfer| (*executed, transfer)));
+    }
+    for (sequence, (currency, entries)) in currencies.iter().enumerate() {
+        let total: i64 = entries.iter().map(|(_, t)| t.payout.amount).sum();
+        let (first, last) = (entries[0].0, entries[entries.len() - 1].0);
+        writeln!(out, "    <Stmt>")?;
+        writeln!(out, "      <Id>{}-{}</Id>", message_id, currency)?;
+        writeln!(out, "      <ElctrncSeqNb>{}</ElctrncSeqNb>", sequence + 1)?;
+        writeln!(out, "      <CreDtTm>{}</CreDtTm>", created)?;
+        writeln!(
+            out,
+            "      <FrToDt><FrDtTm>{}T00:00:00</FrDtTm><ToDtTm>{}T23:59:59</ToDtTm></FrToDt>",
+            first, last
+        )?;
+        writeln!(
+            out,
+            "      <Acct><Id><IBAN>{}</IBAN></Id><Ccy>{}</Ccy><Ownr><Nm>{}</Nm></Ownr></Acct>",
+            iban(ACQUIRER_ACCOUNT),
+            currency,
+            escape(ACQUIRER_NAME)
+        )?;
+        for (code, balance, date) in [("OPBD", total, first), ("CLBD", 0, last)] {
+            writeln!(
+                out,
+                r#"      <Bal><Tp><CdOrPrtry><Cd>{}</Cd></CdOrPrtry></Tp><Amt Ccy="{}">{}</Amt><CdtDbtInd>CRDT</CdtDbtInd><Dt><Dt>{}</Dt></Dt></Bal>"#,
+                code,
+                currency,
+                amount(balance, currency),
+                date
+            )?;
+        }
+        writeln!(
+            out,
+            "      <TxsSummry><TtlDbtNtries><NbOfNtries>{}</NbOfNtries><Sum>{}</Sum></TtlDbtNtries></TxsSummry>",
+            entries.len(),
+            amount(total, currency)
+        )?;
+        for (
+            executed,
+            Transfer {
+                reference,
+                merchant_id,
+                payout,
+            },
+        ) in entries
+        {
+            let payout_amount = amount(payout.amount, currency);
+            writeln!(out, "      <Ntry>")?;
+            writeln!(out, "        <NtryRef>{}</NtryRef>", reference)?;
+            writeln!(
+                out,
+                r#"        <Amt Ccy="{}">{}</Amt>"#,
+                currency, payout_amount
+            )?;
+            writeln!(out, "        <CdtDbtInd>DBIT</CdtDbtInd>")?;
+            writeln!(out, "        <Sts><Cd>BOOK</Cd></Sts>")?;
+            writeln!(out, "        <BookgDt><Dt>{}</Dt></BookgDt>", executed)?;
+            writeln!(out, "        <ValDt><Dt>{}</Dt></ValDt>", executed)?;
+            writeln!(
+                out,
+                "        <BkTxCd><Domn><Cd>PMNT</Cd><Fmly><Cd>ICDT</Cd><SubFmlyCd>ESCT</SubFmlyCd></Fmly></Domn></BkTxCd>"
+            )?;
+            writeln!(out, "        <NtryDtls><TxDtls>")?;
+            writeln!(
+                out,
+                "          <Refs><MsgId>{}</MsgId><PmtInfId>{}</PmtInfId><EndToEndId>{}</EndToEndId></Refs>",
+                message_id,
+                payment_information_id(message_id, currency, *executed),
+                reference
+            )?;
+            writeln!(
+                out,
+                r#"          <Amt Ccy="{}">{}</Amt><CdtDbtInd>DBIT</CdtDbtInd>"#,
+                currency, payout_amount
+            )?;
+            writeln!(
+                out,
+                "          <RltdPties><Cdtr><Pty><Nm>{}</Nm></Pty></Cdtr><CdtrAcct><Id><IBAN>{}</IBAN></Id></CdtrAcct></RltdPties>",
+                escape(&payout.merchant_name),
+                iban(merchant_account(merchant_id))
+            )?;
+            writeln!(
+                out,
+                "          <RmtInf><Ustrd>{}</Ustrd></RmtInf>",
+                remittance(*executed, payout)
+            )?;
+            writeln!(out, "        </TxDtls></NtryDtls>")?;
+            writeln!(out, "      </Ntry>")?;
+        }
+        writeln!(out, "    </Stmt>")?;
+    }
+    writeln!(out, "  </BkToCstmrStmt>")?;
+    writeln!(out, "</Document>")
+}
+
+fn payment_information_id(message_id: &str, currency: &str, executed: NaiveDate) -> String {
+    format!("{}-{}-{}", message_id, executed.format("%Y%m%d"), currency)
+}
+
+fn remittance(executed: NaiveDate, payout: &Payout) -> String {
+    format!(
+        "Card settlement {}, {} transaction{}",
+        executed - Days::new(1),
+        payout.transactions,
+        if payout.transactions == 1 { "" } else { "s" }
+    )
+}
+
+fn created() -> String {
+    Utc::now().format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+// Sum of the amounts of payouts, which may be in different currencies, with
+// as many decimals as the currency with the most has
+fn control_sum<'a>(payouts: impl IntoIterator<Item = (&'a String, &'a Transfer<'a>)>) -> String {
+    let mut sum = 0.0;
+    let mut decimals = 2;
+    for (currency, transfer) in payouts {
+        let payout = Money::new(transfer.payout.amount, currency);
+        sum += payout.to_major();
+        decimals = decimals.max(payout.exponent as usize);
+    }
+    format!("{:.*}", decimals, sum)
+}
+
+// Format minor units as a decimal amount with the currency's minor digits
+fn amount(minor_units: i64, currency: &str) -> String {
+    Money::new(minor_units, currency).to_string()
+}
+
+// Account number a merchant is paid out to, derived from its ID
+fn merchant_account(merchant_id: &str) -> u64 {
+    fnv1a(FNV_OFFSET, merchant_id.as_bytes()) % 100_000_000
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+// Continue an FNV-1a hash over more bytes, for identifiers that have to be
+// stable across runs
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash = (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+// UK-format IBAN at the acquirer's bank, with valid check digits
+fn iban(account: u64) -> String {
+    let bban = format!("LUHN400000{:08}", account);
+    // Check digits make the rearranged IBAN, with letters as numbers,
+    // congruent to 1 mod 97
+    let rearranged = format!("{}GB00", bban);
+    let mut remainder = 0u32;
+    for c in rearranged.chars() {
+        let value = c.to_digit(36).unwrap();
+        remainder = if value >= 10 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+    format!("GB{:02}{}", 98 - remainder, bban)
+}
+
+// Escape text for XML element content
+pub(super) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,465 @@
+use super::{Finish, TransactionWriter};
+use crate::{
+    currency,
+    model::{LifecycleEvent, Transaction},
+    money::Money,
+    text::Text,
+};
+use chrono::{DateTime, Datelike, Utc};
+use std::io;
+
+// Look up the ISO 4217 numeric code of a currency, which payment messages
+// can only be rendered in when it is known
+fn numeric_code(currency: &str) -> io::Result<&'static str> {
+    currency::numeric_code(currency).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("no ISO 4217 numeric code known for currency {}", currency),
+        )
+    })
+}
+
+// An ISO 8583 message under construction: the MTI, then data elements in
+// ascending order, with the primary bitmap worked out from the elements set
+struct Message {
+    mti: &'static str,
+    fields: Vec<(usize, String)>,
+}
+
+impl Message {
+    fn new(mti: &'static str) -> Self {
+        Self {
+            mti,
+            fields: Vec::new(),
+        }
+    }
+
+    // Set a fixed-length element, which must already be the exact length
+    fn fixed(&mut self, number: usize, value: String) -> &mut Self {
+        self.fields.push((number, value));
+        self
+    }
+
+    // Set an element unless the field it is made from was left empty, in
+    // which case it is left out of the bitmap
+    fn optional(&mut self, number: usize, value: Option<String>) -> &mut Self {
+        if let Some(value) = value {
+            self.fields.push((number, value));
+        }
+        self
+    }
+
+    // Set an LLVAR element, prefixed with its two-digit length, unless it
+    // is empty
+    fn llvar(&mut self, number: usize, value: &str) -> &mut Self {
+        if !value.is_empty() {
+            self.fields
+                .push((number, format!("{:02}{}", value.len(), value)));
+        }
+        self
+    }
+
+    // Set an LLLVAR element, prefixed with its three-digit length, unless
+    // it is empty
+    fn lllvar(&mut self, number: usize, value: &str) -> &mut Self {
+        if !value.is_empty() {
+            self.fields
+                .push((number, format!("{:03}{}", value.len(), value)));
+        }
+        self
+    }
+
+    // MTI, binary primary bitmap and ASCII data elements
+    fn encode(&self) -> Vec<u8> {
+        let mut bitmap = [0u8; 8];
+        for (number, _) in &self.fields {
+            bitmap[(number - 1) / 8] |= 0x80 >> ((number - 1) % 8);
+        }
+        let mut message = self.mti.as_bytes().to_vec();
+        message.extend_from_slice(&bitmap);
+        let mut fields: Vec<&(usize, String)> = self.fields.iter().collect();
+        fields.sort_by_key(|(number, _)| *number);
+        for (_, value) in fields {
+            message.extend_from_slice(value.as_bytes());
+        }
+        message
+    }
+}
+
+// Writes each transaction as a 0100 authorization request followed by its
+// 0110 response. Every message is framed with a two-byte big-endian length,
+// as on a switch's TCP link, and carries a binary primary bitmap followed by
+// ASCII data elements. In lifecycle mode only the authorization events are
+// written, since captures, clearing and refunds travel as other messages.
+pub struct Iso8583Writer<W: Finish> {
+    out: W,
+    // System trace audit number (DE11) of the next request
+    stan: u32,
+}
+
+impl<W: Finish> Iso8583Writer<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, stan: 0 }
+    }
+
+    fn write_message(&mut self, message: &Message) -> io::Result<()> {
+        let bytes = message.encode();
+        self.out.write_all(&(bytes.len() as u16).to_be_bytes())?;
+        self.out.write_all(&bytes)
+    }
+}
+
+impl<W: Finish> TransactionWriter for Iso8583Writer<W> {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        if tx
+            .event_type
+            .is_some_and(|event| event != LifecycleEvent::Authorization)
+        {
+            return Ok(());
+        }
+
+        let date = DateTime::parse_from_rfc3339(&tx.transaction_date)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        // Fields left empty, as with --null, leave their elements out
+        let present = |value: &str| (!value.is_empty()).then(|| value.to_string());
+        let currency = match tx.currency.as_str() {
+            "" => None,
+            currency => Some(numeric_code(currency)?.to_string()),
+        };
+        // Refunds carry negative amounts, but DE4 is unsigned and the
+        // processing code marks the message as a return instead
+        let minor_units = Money::from_major(tx.amount.abs(), &tx.currency).minor_units;
+        if minor_units > 999_999_999_999 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("amount {} {} does not fit in DE4", tx.amount, tx.currency),
+            ));
+        }
+        // Wallet payments carry the network token in DE2 in place of the
+        // card number
+        let pan = tx.token_pan.as_deref().unwrap_or(&tx.card_number);
+        let processing_code = if tx.amount < 0.0 { "200000" } else { "000000" };
+        // Card expiry is MM/YY, while DE14 is YYMM
+        let expiry = match tx.card_expiry.split_once('/') {
+            Some((month, year)) => Some(format!("{}{}", year, month)),
+            None => present(&tx.card_expiry),
+        };
+        // DE22: PAN entry mode, then whether a PIN can be entered, which
+        // only terminals in store allow
+        let pin_capability = if tx.channel.is_card_present() {
+            "1"
+        } else {
+            "2"
+        };
+        let pos_entry_mode =
+            present(&tx.pos_entry_mode).map(|mode| format!("{}{}", mode, pin_capability));
+        // DE35 and DE45 carry the tracks as read, without their start and
+        // end sentinels and LRC
+        let unframed = |track: &Option<Text>| {
+            track
+                .as_deref()
+                .and_then(|track| track.get(1..track.len().saturating_sub(2)))
+                .unwrap_or_default()
+                .to_string()
+        };
+        let (track1, track2) = (unframed(&tx.track1), unframed(&tx.track2));
+        let icc = icc_data(tx, minor_units, currency.as_deref(), &date.format("%y%m%d"));
+        // DE41 names the card acceptor terminal, or the device of an
+        // online purchase
+        let terminal = text(
+            tx.terminal_id
+                .as_deref()
+                .or(tx.device_id.as_deref())
+                .unwrap_or_default(),
+            8,
+        );
+        // Transmission date and time is always in UTC
+        let transmitted = date.with_timezone(&Utc).format("%m%d%H%M%S").to_string();
+        self.stan = self.stan % 999_999 + 1;
+        let stan = format!("{:06}", self.stan);
+        // Retrieval reference number: last digit of the year, day of the
+        // year, hour and trace number
+        let rrn = format!(
+            "{}{:03}{}{}",
+            date.year() % 10,
+            date.ordinal(),
+            date.format("%H"),
+            stan
+        );
+
+        let mut request = Message::new("0100");
+        request
+            .llvar(2, pan)
+            .fixed(3, processing_code.to_string())
+            .fixed(4, format!("{:012}", minor_units))
+            .fixed(7, transmitted.clone())
+            .fixed(11, stan.clone())
+            .fixed(12, date.format("%H%M%S").to_string())
+            .fixed(13, date.format("%m%d").to_string())
+            .optional(14, expiry);
+        if let Some(mcc) = &tx.mcc {
+            request.fixed(18, mcc.to_string());
+        }
+        request
+            .optional(22, pos_entry_mode)
+            .llvar(32, &tx.acquirer_id)
+            .llvar(35, &track2)
+            .fixed(37, rrn.clone())
+            .fixed(41, terminal.clone())
+            .optional(42, present(&tx.merchant_id).map(|id| text(&id, 15)))
+            .optional(43, present(&tx.statement_descriptor).map(|d| text(&d, 40)))
+            .llvar(45, &track1)
+            .optional(49, currency.clone())
+            .lllvar(55, &icc);
+        self.write_message(&request)?;
+
+        let mut response = Message::new("0110");
+        response
+            .llvar(2, pan)
+            .fixed(3, processing_code.to_string())
+            .fixed(4, format!("{:012}", minor_units))
+            .fixed(7, transmitted)
+            .fixed(11, stan)
+            .fixed(37, rrn);
+        if let Some(auth_code) = &tx.auth_code {
+            response.fixed(38, text(auth_code, 6));
+        }
+        response
+            .optional(39, present(&tx.response_code))
+            .fixed(41, terminal)
+            .optional(42, present(&tx.merchant_id).map(|id| text(&id, 15)))
+            .optional(49, currency);
+        self.write_message(&response)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.out.bytes()
+    }
+}
+
+// DE55 of a row with EMV data: the chip's tags for the authorization
+// request as BER-TLV, written in hex like the rest of the message's ASCII
+// elements. Rows without EMV data leave it out.
+fn icc_data(
+    tx: &Transaction,
+    minor_units: i64,
+    currency: Option<&str>,
+    date: &impl std::fmt::Display,
+) -> String {
+    let (Some(aid), Some(cryptogram), Some(tvr), Some(atc)) =
+        (&tx.emv_aid, &tx.emv_cryptogram, &tx.emv_tvr, &tx.emv_atc)
+    else {
+        return String::new();
+    };
+    let transaction_type = if tx.amount < 0.0 { "20" } else { "00" };
+    let tags = [
+        // Application cryptogram, and its type: an authorization request
+        ("9F26", cryptogram.to_string()),
+        ("9F27", "80".to_string()),
+        ("9F36", atc.to_string()),
+        ("95", tvr.to_string()),
+        ("9A", date.to_string()),
+        ("9C", transaction_type.to_string()),
+        ("9F02", format!("{:012}", minor_units)),
+        ("5F2A", format!("{:0>4}", currency.unwrap_or_default())),
+        // Application interchange profile: offline data authentication,
+        // cardholder verification and terminal risk management supported
+        ("82", "1980".to_string()),
+        ("84", aid.to_string()),
+    ];
+    tags.iter()
+        .map(|(tag, value)| format!("{}{:02X}{}", tag, value.len() / 2, value))
+        .collect()
+}
+
+// Fit text into a fixed-length alphanumeric element, left-justified and
+// space-padded, with characters outside printable ASCII replaced
+fn text(value: &str, len: usize) -> String {
+    let mut field: String = value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() || c == ' ' {
+                c
+            } else {
+                '?'
+            }
+        })
+        .take(len)
+        .collect();
+    while field.len() < len {
+        field.push(' ');
+    }
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Profile, model::Channel, seed, track};
+
+    // Whether an element is fixed-length, or LLVAR or LLLVAR, with the
+    // length of the fixed ones, for the elements the writer sets
+    fn format(number: usize) -> (usize, usize) {
+        match number {
+            2 | 32 | 35 | 45 => (2, 0),
+            55 => (3, 0),
+            3 | 11 | 12 | 38 => (0, 6),
+            4 | 37 => (0, 12),
+            7 => (0, 10),
+            13 | 14 | 18 => (0, 4),
+            22 | 49 => (0, 3),
+            39 => (0, 2),
+            41 => (0, 8),
+            42 => (0, 15),
+            43 => (0, 40),
+            _ => panic!("unexpected element {}", number),
+        }
+    }
+
+    // A message's MTI, bitmap and elements in order, and the rest of the
+    // stream after it
+    type Decoded<'a> = (&'a str, [u8; 8], Vec<(usize, &'a str)>, &'a [u8]);
+
+    // Split the first framed message off a stream
+    fn decode(stream: &[u8]) -> Decoded<'_> {
+        let length = u16::from_be_bytes([stream[0], stream[1]]) as usize;
+        let (message, rest) = stream[2..].split_at(length);
+        let mti = std::str::from_utf8(&message[..4]).unwrap();
+        let bitmap: [u8; 8] = message[4..12].try_into().unwrap();
+        let mut data = std::str::from_utf8(&message[12..]).unwrap();
+        let mut fields = Vec::new();
+        for number in 1..=64 {
+            if bitmap[(number - 1) / 8] & (0x80 >> ((number - 1) % 8)) == 0 {
+                continue;
+            }
+            let (prefix, fixed) = format(number);
+            let len = match prefix {
+                0 => fixed,
+                _ => prefix + data[..prefix].parse::<usize>().unwrap(),
+            };
+            let (value, remaining) = data.split_at(len);
+            fields.push((number, value));
+            data = remaining;
+        }
+        assert!(data.is_empty(), "{} left over after the elements", data);
+        (mti, bitmap, fields, rest)
+    }
+
+    fn transaction() -> Transaction {
+        let mut tx = seed::generate_nth(&Profile::default(), 783, 0);
+        tx.event_type = None;
+        tx.card_number = "4111111111111111".into();
+        tx.token_pan = None;
+        tx.card_expiry = "12/28".into();
+        tx.cardholder_name = "Jane Q. Doe".into();
+        tx.amount = 12.34;
+        tx.currency = "USD".into();
+        tx.transaction_date = "2026-03-05T14:07:09+00:00".into();
+        tx.channel = Channel::PosChip;
+        tx.pos_entry_mode = "05".into();
+        tx.mcc = Some("5411".into());
+        tx.acquirer_id = "123456".into();
+        tx.terminal_id = Some("TERM0001".into());
+        tx.merchant_id = "MERCHANT0000001".into();
+        tx.statement_descriptor = "ACME STORE".into();
+        tx.auth_code = Some("A1B2C3".into());
+        tx.response_code = "00".into();
+        tx.emv_aid = Some("A0000000031010".into());
+        tx.emv_cryptogram = Some("0123456789ABCDEF".into());
+        tx.emv_tvr = Some("0000000000".into());
+        tx.emv_atc = Some("0001".into());
+        track::assign(&mut tx);
+        tx
+    }
+
+    #[test]
+    fn authorization_pair_is_encoded() {
+        let mut writer = Iso8583Writer::new(Vec::new());
+        writer.write(&transaction()).unwrap();
+
+        let (mti, bitmap, fields, rest) = decode(&writer.out);
+        assert_eq!(mti, "0100");
+        assert_eq!(bitmap, [0x72, 0x3c, 0x44, 0x01, 0x28, 0xe8, 0x82, 0x00]);
+        // DE55 tags, each with its length in bytes
+        let icc = [
+            "9F26080123456789ABCDEF",
+            "9F270180",
+            "9F36020001",
+            "95050000000000",
+            "9A03260305",
+            "9C0100",
+            "9F0206000000001234",
+            "5F2A020840",
+            "82021980",
+            "8407A0000000031010",
+        ]
+        .concat();
+        assert_eq!(
+            fields,
+            [
+                (2, "164111111111111111"),
+                (3, "000000"),
+                (4, "000000001234"),
+                (7, "0305140709"),
+                (11, "000001"),
+                (12, "140709"),
+                (13, "0305"),
+                (14, "2812"),
+                (18, "5411"),
+                (22, "051"),
+                (32, "06123456"),
+                (35, "374111111111111111=28122011155473100000"),
+                (37, "606414000001"),
+                (41, "TERM0001"),
+                (42, "MERCHANT0000001"),
+                (43, "ACME STORE                              "),
+                (45, "49B4111111111111111^DOE/JANE Q^28122011155473100000"),
+                (49, "840"),
+                (55, &format!("{:03}{}", icc.len(), icc)),
+            ]
+        );
+
+        let (mti, bitmap, fields, rest) = decode(rest);
+        assert_eq!(mti, "0110");
+        assert_eq!(bitmap, [0x72, 0x20, 0x00, 0x00, 0x0e, 0xc0, 0x80, 0x00]);
+        assert_eq!(
+            fields,
+            [
+                (2, "164111111111111111"),
+                (3, "000000"),
+                (4, "000000001234"),
+                (7, "0305140709"),
+                (11, "000001"),
+                (37, "606414000001"),
+                (38, "A1B2C3"),
+                (39, "00"),
+                (41, "TERM0001"),
+                (42, "MERCHANT0000001"),
+                (49, "840"),
+            ]
+        );
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn refunds_and_empty_fields() {
+        let mut tx = transaction();
+        tx.amount = -5.0;
+        tx.card_expiry = "".into();
+        tx.track1 = None;
+        tx.track2 = None;
+        tx.emv_aid = None;
+        let mut writer = Iso8583Writer::new(Vec::new());
+        writer.write(&tx).unwrap();
+
+        let (_, _, fields, _) = decode(&writer.out);
+        let numbers: Vec<usize> = fields.iter().map(|(number, _)| *number).collect();
+        assert_eq!(numbers, [2, 3, 4, 7, 11, 12, 13, 18, 22, 32, 37, 41, 42, 43, 49]);
+        assert_eq!(fields[1], (3, "200000"));
+        assert_eq!(fields[2], (4, "000000000500"));
+    }
+}
@@ -0,0 +1,93 @@
+use super::{TransactionWriter, to_io};
+use crate::model::Transaction;
+use rdkafka::{
+    ClientConfig, ClientContext,
+    error::{KafkaError, RDKafkaErrorCode},
+    message::DeliveryResult,
+    producer::{BaseProducer, BaseRecord, Producer, ProducerContext},
+};
+use std::{io, sync::Mutex, time::Duration};
+
+// How long `finish` waits for outstanding messages to be acknowledged
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Remembers the first message the brokers failed to accept
+#[derive(Default)]
+struct Deliveries {
+    failed: Mutex<Option<KafkaError>>,
+}
+
+impl ClientContext for Deliveries {}
+
+impl ProducerContext for Deliveries {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: ()) {
+        if let Err((e, _)) = result {
+            self.failed.lock().unwrap().get_or_insert_with(|| e.clone());
+        }
+    }
+}
+
+// Publishes each transaction as a JSON message, keyed by customer ID so a
+// customer's transactions land on one partition in order
+pub struct KafkaWriter {
+    producer: BaseProducer<Deliveries>,
+    topic: String,
+    // Bytes of the keys and payloads sent
+    bytes: u64,
+}
+
+impl KafkaWriter {
+    pub fn new(brokers: &[String], topic: &str) -> io::Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers.join(","))
+            .set("linger.ms", "50")
+            .create_with_context(Deliveries::default())
+            .map_err(to_io)?;
+        Ok(Self {
+            producer,
+            topic: topic.to_string(),
+            bytes: 0,
+        })
+    }
+
+    fn check_deliveries(&self) -> io::Result<()> {
+        match self.producer.context().failed.lock().unwrap().take() {
+            Some(e) => Err(to_io(e)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl TransactionWriter for KafkaWriter {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        let payload = serde_json::to_string(tx)?;
+        let mut record = BaseRecord::to(&self.topic)
+            .key(tx.customer_id.as_str())
+            .payload(&payload);
+        // Wait for room in the producer queue when the brokers fall behind
+        loop {
+            match self.producer.send(record) {
+                Ok(()) => break,
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), rejected)) => {
+                    record = rejected;
+                    self.producer.poll(Duration::from_millis(100));
+                }
+                Err((e, _)) => return Err(to_io(e)),
+            }
+        }
+        self.bytes += (tx.customer_id.len() + payload.len()) as u64;
+        self.producer.poll(Duration::ZERO);
+        self.check_deliveries()
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.producer.flush(FLUSH_TIMEOUT).map_err(to_io)?;
+        self.check_deliveries()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
@@ -0,0 +1,1038 @@
+mod avro;
+mod cloud;
+mod columns;
+mod compact;
+mod database;
+mod fixed_width;
+mod fixtures;
+#[cfg(feature = "http")]
+mod http;
+mod iso20022;
+mod iso8583;
+#[cfg(feature = "cloud")]
+mod object_store;
+mod partition;
+mod pipeline;
+#[cfg(feature = "kafka")]
+mod kafka;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod protobuf;
+mod relational;
+mod schema;
+mod shard;
+mod sql;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "template")]
+mod template;
+#[cfg(feature = "xlsx")]
+mod xlsx;
+mod xml;
+
+pub use avro::{AvroWriter, transaction_schema};
+pub use cloud::{Encryption, ObjectUrl, Provider, Sse};
+pub use columns::Columns;
+pub use database::create_database_writer;
+pub use fixed_width::FixedWidthWriter;
+pub use fixtures::FixtureWriter;
+#[cfg(feature = "http")]
+pub use http::HttpWriter;
+pub use iso20022::{Document, Iso20022Writer};
+pub use iso8583::Iso8583Writer;
+#[cfg(feature = "cloud")]
+pub use object_store::{ObjectStore, UploadWriter};
+pub use partition::PartitionedWriter;
+pub use pipeline::{PipelinedWriter, start_pipeline};
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaWriter;
+pub use protobuf::{ProtobufWriter, encode_transaction, proto_schema};
+pub use relational::RelationalWriter;
+pub use schema::{SchemaFormat, schema};
+pub use shard::ShardedWriter;
+pub use sql::SqlWriter;
+#[cfg(feature = "template")]
+pub use template::TemplateWriter;
+pub use xml::XmlWriter;
+
+use compact::{CsvRows, JsonRows};
+use pipeline::Destination;
+use crate::{
+    config::{Compression, CsvOptions, CsvQuoting, OutputFormat, Profile},
+    custom,
+    model::Transaction,
+    money::{AmountFormat, Money},
+};
+use flate2::write::GzEncoder;
+use std::{
+    error::Error,
+    fmt::{self, Write as _},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::Arc,
+};
+
+// A sink that consumes transactions one at a time, so datasets never need
+// to be fully materialized before they are written. Sinks can be moved to a
+// task of their own when outputs are pipelined.
+pub trait TransactionWriter: Send {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()>;
+
+    // Write a row that other writers are given as well. Writers that hand
+    // rows to another thread keep a handle on it rather than a copy.
+    fn write_shared(&mut self, tx: &Arc<Transaction>) -> io::Result<()> {
+        self.write(tx)
+    }
+
+    // Flush any trailing output once the last transaction has been written
+    fn finish(&mut self) -> io::Result<()>;
+
+    // A report to print once the writer has finished, for sinks that track
+    // more than the rows they were given
+    fn summary(&self) -> Option<String> {
+        None
+    }
+
+    // Bytes written so far to the files, stream or sink rows go to. Writers
+    // loading a database write none of their own.
+    fn bytes(&self) -> u64 {
+        0
+    }
+}
+
+// A byte stream that writers end once the last transaction is in. Beyond
+// flushing, compressed streams write their trailer here.
+pub trait Finish: Write + Send {
+    fn finish(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+
+    // Bytes that have reached the file or stream so far, for streams that
+    // count them
+    fn bytes(&self) -> u64 {
+        0
+    }
+}
+
+impl Finish for File {}
+impl<W: Write + Send> Finish for BufWriter<W> {}
+
+impl Finish for Vec<u8> {
+    fn bytes(&self) -> u64 {
+        self.len() as u64
+    }
+}
+
+// A stream counting the bytes written to it
+pub struct Counted<W> {
+    out: W,
+    bytes: u64,
+}
+
+impl<W: Write> Counted<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, bytes: 0 }
+    }
+}
+
+impl<W: Write> Write for Counted<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.out.write(buf)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl<W: Finish> Finish for Counted<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+// An output file, optionally written through a compression encoder. Bytes
+// are counted as they reach the file, after compression.
+pub enum Sink {
+    Plain(Counted<Destination<File>>),
+    Gzip(GzEncoder<Counted<Destination<File>>>),
+    Zstd(zstd::Encoder<'static, Counted<Destination<File>>>),
+}
+
+impl Sink {
+    pub fn new(file: File, compression: Option<Compression>) -> io::Result<Self> {
+        let file = Counted::new(Destination::file(file));
+        Ok(match compression {
+            None => Sink::Plain(file),
+            Some(Compression::Gzip) => {
+                Sink::Gzip(GzEncoder::new(file, flate2::Compression::default()))
+            }
+            Some(Compression::Zstd) => Sink::Zstd(zstd::Encoder::new(file, 0)?),
+        })
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::Plain(out) => out.write(buf),
+            Sink::Gzip(out) => out.write(buf),
+            Sink::Zstd(out) => out.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Plain(out) => out.flush(),
+            Sink::Gzip(out) => out.flush(),
+            Sink::Zstd(out) => out.flush(),
+        }
+    }
+}
+
+impl Finish for Sink {
+    fn finish(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Plain(out) => out.finish(),
+            Sink::Gzip(out) => {
+                out.try_finish()?;
+                out.get_mut().finish()
+            }
+            Sink::Zstd(out) => {
+                out.do_finish()?;
+                out.get_mut().finish()
+            }
+        }
+    }
+
+    fn bytes(&self) -> u64 {
+        match self {
+            Sink::Plain(out) => out.bytes(),
+            Sink::Gzip(out) => out.get_ref().bytes(),
+            Sink::Zstd(out) => out.get_ref().bytes(),
+        }
+    }
+}
+
+// Create a writer publishing to a Kafka topic
+#[cfg(feature = "kafka")]
+pub fn create_kafka_writer(brokers: &[String], topic: &str) -> io::Result<Box<dyn TransactionWriter>> {
+    Ok(Box::new(KafkaWriter::new(brokers, topic)?))
+}
+
+#[cfg(not(feature = "kafka"))]
+pub fn create_kafka_writer(_brokers: &[String], _topic: &str) -> io::Result<Box<dyn TransactionWriter>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "kafka output requires building with `--features kafka`",
+    ))
+}
+
+// Create a writer POSTing each transaction to an HTTP endpoint, as a signed
+// webhook event when a signing secret is given
+#[cfg(feature = "http")]
+pub fn create_http_writer(
+    url: &str,
+    concurrency: usize,
+    retries: u32,
+    secret: Option<&str>,
+) -> io::Result<Box<dyn TransactionWriter>> {
+    let signer = secret.map(crate::webhook::Signer::new);
+    Ok(Box::new(HttpWriter::new(url, concurrency, retries, signer)?))
+}
+
+#[cfg(not(feature = "http"))]
+pub fn create_http_writer(
+    _url: &str,
+    _concurrency: usize,
+    _retries: u32,
+    _secret: Option<&str>,
+) -> io::Result<Box<dyn TransactionWriter>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "http output requires building with `--features http`",
+    ))
+}
+
+// Wrap a file writer so its files are uploaded to object storage once
+// written, keyed by their path below `root`
+#[cfg(feature = "cloud")]
+pub fn create_upload_writer(
+    writer: Box<dyn TransactionWriter>,
+    files: &Path,
+    root: &Path,
+    url: &ObjectUrl,
+    encryption: &Encryption,
+    retries: u32,
+) -> io::Result<Box<dyn TransactionWriter>> {
+    let store = ObjectStore::new(url, encryption, retries)?;
+    Ok(Box::new(UploadWriter::new(writer, store, files, root)))
+}
+
+#[cfg(not(feature = "cloud"))]
+pub fn create_upload_writer(
+    _writer: Box<dyn TransactionWriter>,
+    _files: &Path,
+    _root: &Path,
+    _url: &ObjectUrl,
+    _encryption: &Encryption,
+    _retries: u32,
+) -> io::Result<Box<dyn TransactionWriter>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "uploading to object storage requires building with `--features cloud`",
+    ))
+}
+
+// Name of a file of the given format, with the extension of the profile's
+// compression when the format is compressed
+pub fn file_name(stem: &str, format: OutputFormat, profile: &Profile) -> String {
+    let mut name = stem.to_string();
+    if !format.extension().is_empty() {
+        name = format!("{}.{}", name, format.extension());
+    }
+    if let Some(compression) = profile.compression
+        && format.is_compressible()
+    {
+        name = format!("{}.{}", name, compression.extension());
+    }
+    name
+}
+
+// Create a file writer for the requested output format, laid out according
+// to the profile's output options
+pub fn create_writer(
+    format: OutputFormat,
+    path: &Path,
+    profile: &Profile,
+) -> io::Result<Box<dyn TransactionWriter>> {
+    #[cfg(not(feature = "parquet"))]
+    if format == OutputFormat::Parquet {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "parquet output requires building with `--features parquet`",
+        ));
+    }
+    #[cfg(not(feature = "sqlite"))]
+    if format == OutputFormat::Sqlite {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "sqlite output requires building with `--features sqlite`",
+        ));
+    }
+    #[cfg(not(feature = "xlsx"))]
+    if format == OutputFormat::Xlsx {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "xlsx output requires building with `--features xlsx`",
+        ));
+    }
+
+    // Relational output is a directory of tables, each compressed on its own
+    if format == OutputFormat::Relational {
+        return Ok(Box::new(RelationalWriter::new(path, profile)?));
+    }
+    // Partitioned output is a directory of partitions with files of their own
+    if let Some(by) = profile.partition_by {
+        return Ok(Box::new(PartitionedWriter::new(format, path, by, profile)?));
+    }
+    if let Some(max_rows) = profile.max_rows_per_file {
+        return create_sharded_writer(format, path, max_rows, 0, profile);
+    }
+
+    let file = File::create(path)?;
+    let compression = profile.compression.filter(|_| format.is_compressible());
+    match format {
+        #[cfg(feature = "parquet")]
+        OutputFormat::Parquet => Ok(Box::new(parquet::ParquetWriter::new(
+            file,
+            &profile.custom_fields,
+        )?)),
+        // SQLite opens the freshly truncated file as a new, empty database
+        #[cfg(feature = "sqlite")]
+        OutputFormat::Sqlite => {
+            drop(file);
+            Ok(Box::new(sqlite::SqliteWriter::new(
+                path,
+                &profile.sql.table,
+                &profile.custom_fields,
+                &profile.masking,
+                Columns::new(profile),
+            )?))
+        }
+        #[cfg(feature = "xlsx")]
+        OutputFormat::Xlsx => Ok(Box::new(xlsx::XlsxWriter::new(file, &profile.custom_fields)?)),
+        _ => stream_writer(format, Sink::new(file, compression)?, profile),
+    }
+}
+
+// Sharded output is a series of files numbered after the name given,
+// `transactions_100-00001.csv` and on, starting at the file with the given
+// index when an earlier run completed the ones before it
+pub fn create_sharded_writer(
+    format: OutputFormat,
+    path: &Path,
+    max_rows: usize,
+    first: usize,
+    profile: &Profile,
+) -> io::Result<Box<dyn TransactionWriter>> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = name.split('.').next().unwrap_or_default().to_string();
+    let shard = move |index| format!("{}-{:05}", stem, index + 1);
+    Ok(Box::new(ShardedWriter::new(format, dir, shard, max_rows, first, profile)?))
+}
+
+// Build a writer that streams rows to stdout, for piping into other tools.
+// Only formats that are streamed can go there, and rows aren't compressed,
+// as that's what the pipe is for.
+pub fn create_stdout_writer(
+    format: OutputFormat,
+    profile: &Profile,
+) -> io::Result<Box<dyn TransactionWriter>> {
+    stream_writer(format, Pipe(Counted::new(Destination::stdout())), profile)
+}
+
+// What writing to stdout fails with once a reader that stops early, like
+// `head`, has closed the pipe. The run then ends quietly as other tools do,
+// after finishing its other outputs.
+#[derive(Debug)]
+struct StdoutClosed;
+
+impl fmt::Display for StdoutClosed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("stdout was closed")
+    }
+}
+
+impl Error for StdoutClosed {}
+
+// Whether an error is stdout being closed, as it is or wrapped by the
+// writer of a format
+pub fn is_stdout_closed(error: &io::Error) -> bool {
+    let mut cause: Option<&(dyn Error + 'static)> = error.get_ref().map(|e| e as _);
+    while let Some(error) = cause {
+        if error.is::<StdoutClosed>() {
+            return true;
+        }
+        // I/O errors don't give the errors they wrap as their source, and
+        // neither do CSV errors their I/O errors
+        cause = if let Some(error) = error.downcast_ref::<io::Error>() {
+            error.get_ref().map(|e| e as _)
+        } else if let Some(csv::ErrorKind::Io(error)) =
+            error.downcast_ref::<csv::Error>().map(csv::Error::kind)
+        {
+            Some(error)
+        } else {
+            error.source()
+        };
+    }
+    false
+}
+
+// Stdout for streamed rows
+struct Pipe(Counted<Destination<io::Stdout>>);
+
+impl Pipe {
+    fn check<T>(result: io::Result<T>) -> io::Result<T> {
+        match result {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Err(io::Error::other(StdoutClosed)),
+            result => result,
+        }
+    }
+}
+
+impl Write for Pipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Self::check(self.0.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Self::check(self.0.flush())
+    }
+}
+
+impl Finish for Pipe {
+    fn finish(&mut self) -> io::Result<()> {
+        Self::check(self.0.finish())
+    }
+
+    fn bytes(&self) -> u64 {
+        self.0.bytes()
+    }
+}
+
+// A writer for a format written front to back into a byte stream
+pub fn stream_writer<W: Finish + 'static>(
+    format: OutputFormat,
+    out: W,
+    profile: &Profile,
+) -> io::Result<Box<dyn TransactionWriter>> {
+    Ok(match format {
+        OutputFormat::Csv => Box::new(CsvWriter::new(
+            out,
+            &profile.csv,
+            profile.amount_format,
+            Columns::new(profile),
+        )?),
+        OutputFormat::Json => Box::new(JsonWriter::new(
+            out,
+            profile.amount_format,
+            Columns::new(profile),
+        )),
+        OutputFormat::Jsonl => Box::new(NdjsonWriter::new(
+            out,
+            profile.amount_format,
+            Columns::new(profile),
+        )),
+        OutputFormat::Sql => Box::new(SqlWriter::new(
+            out,
+            &profile.sql,
+            &profile.custom_fields,
+            &profile.masking,
+            Columns::new(profile),
+        )?),
+        OutputFormat::Xml => Box::new(XmlWriter::new(
+            out,
+            &profile.xml,
+            profile.amount_format,
+            Columns::new(profile),
+        )?),
+        OutputFormat::FixedWidth => Box::new(FixedWidthWriter::new(
+            out,
+            &profile.fixed_width,
+            profile.amount_format,
+        )),
+        OutputFormat::Avro => Box::new(AvroWriter::new(out, &profile.custom_fields)?),
+        OutputFormat::Protobuf => Box::new(ProtobufWriter::new(out)),
+        OutputFormat::Iso8583 => Box::new(Iso8583Writer::new(out)),
+        OutputFormat::Pain001 => {
+            Box::new(Iso20022Writer::new(out, Document::PaymentInitiation))
+        }
+        OutputFormat::Camt053 => Box::new(Iso20022Writer::new(out, Document::Statement)),
+        #[cfg(feature = "template")]
+        OutputFormat::Template => {
+            let path = profile.template.as_deref().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "template output needs --template")
+            })?;
+            Box::new(TemplateWriter::new(out, path, profile.amount_format)?)
+        }
+        #[cfg(not(feature = "template"))]
+        OutputFormat::Template => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "template output requires building with `--features template`",
+            ));
+        }
+        OutputFormat::Parquet
+        | OutputFormat::Sqlite
+        | OutputFormat::Xlsx
+        | OutputFormat::Relational => unreachable!("not written as a stream"),
+    })
+}
+
+// Column names of the CSV header, in `Transaction` field order
+pub(crate) const CSV_HEADER: [&str; 86] = [
+    "transaction_id",
+    "event_type",
+    "transaction_date",
+    "status",
+    "decline_reason",
+    "response_code",
+    "auth_code",
+    "original_transaction_id",
+    "subscription_id",
+    "initiator",
+    "installment_number",
+    "installment_count",
+    "order_reference",
+    "preauth_step",
+    "customer_id",
+    "cardholder_name",
+    "email",
+    "phone",
+    "card_number",
+    "card_brand",
+    "card_expiry",
+    "cvv",
+    "issuer_bank",
+    "card_country",
+    "card_type",
+    "billing_street",
+    "billing_city",
+    "billing_region",
+    "billing_postal_code",
+    "billing_country",
+    "shipping_street",
+    "shipping_city",
+    "shipping_region",
+    "shipping_postal_code",
+    "shipping_country",
+    "amount",
+    "refund_amount",
+    "remaining_amount",
+    "currency",
+    "original_amount",
+    "original_currency",
+    "billing_amount",
+    "billing_currency",
+    "fx_rate",
+    "fx_markup",
+    "merchant_name",
+    "merchant_id",
+    "statement_descriptor",
+    "merchant_category",
+    "mcc",
+    "acquirer_id",
+    "processor",
+    "batch_id",
+    "settlement_date",
+    "payment_method",
+    "channel",
+    "pos_entry_mode",
+    "terminal_id",
+    "track1",
+    "track2",
+    "emv_aid",
+    "emv_cryptogram",
+    "emv_tvr",
+    "emv_atc",
+    "wallet",
+    "wallet_id",
+    "token_pan",
+    "token_requestor_id",
+    "three_ds_version",
+    "eci",
+    "authentication_result",
+    "cavv",
+    "avs_result",
+    "cvv_result",
+    "ip_address",
+    "ip_country",
+    "device_id",
+    "device_platform",
+    "device_os",
+    "device_browser",
+    "screen_resolution",
+    "device_fingerprint",
+    "user_agent",
+    "is_fraud",
+    "fraud_pattern",
+    "persona",
+];
+
+// Text of the amount fields of a row, each in major units with as many
+// decimals as its currency has, or as a whole number of minor units
+pub(crate) struct AmountFields {
+    pub amount: String,
+    pub refund_amount: Option<String>,
+    pub remaining_amount: Option<String>,
+    pub original_amount: Option<String>,
+    pub billing_amount: Option<String>,
+}
+
+impl AmountFields {
+    pub fn new(tx: &Transaction, format: AmountFormat) -> Self {
+        let text = |amount: f64, currency: &str| Money::from_major(amount, currency).format(format);
+        let billing_currency = tx.billing_currency.as_deref().unwrap_or(&tx.currency);
+        Self {
+            amount: text(tx.amount, &tx.currency),
+            refund_amount: tx.refund_amount.map(|a| text(a, &tx.currency)),
+            remaining_amount: tx.remaining_amount.map(|a| text(a, &tx.currency)),
+            original_amount: tx.original_amount.map(|a| text(a, &tx.currency)),
+            billing_amount: tx.billing_amount.map(|a| text(a, billing_currency)),
+        }
+    }
+}
+
+// A row as the text of a message, the same JSON as a line of JSON Lines.
+// In major units the row is serialized as it is; otherwise its amount fields
+// are first rewritten as integers in a JSON value.
+pub fn json_message(tx: &Transaction, format: AmountFormat) -> io::Result<String> {
+    Ok(match format {
+        AmountFormat::Decimal => serde_json::to_string(tx)?,
+        AmountFormat::MinorUnits => to_json(tx, format)?.to_string(),
+    })
+}
+
+// A row as a JSON object. In minor units, the amount fields are written as
+// integers instead of numbers in major units.
+fn to_json(tx: &Transaction, format: AmountFormat) -> io::Result<serde_json::Value> {
+    let mut value = serde_json::to_value(tx)?;
+    if format == AmountFormat::MinorUnits
+        && let Some(row) = value.as_object_mut()
+    {
+        let billing_currency = tx.billing_currency.as_deref().unwrap_or(&tx.currency);
+        let fields = [
+            ("amount", tx.currency.as_str()),
+            ("refund_amount", &tx.currency),
+            ("remaining_amount", &tx.currency),
+            ("original_amount", &tx.currency),
+            ("billing_amount", billing_currency),
+        ];
+        for (field, currency) in fields {
+            if let Some(amount) = row.get(field).and_then(|amount| amount.as_f64()) {
+                let minor_units = Money::from_major(amount, currency).minor_units;
+                row.insert(field.to_string(), minor_units.into());
+            }
+        }
+    }
+    Ok(value)
+}
+
+// Writes transactions as CSV rows, quoting fields as needed so merchant
+// names and user agents containing delimiters or quotes survive a round trip
+pub struct CsvWriter<W: Finish> {
+    out: W,
+    rows: CsvRows,
+    // Text of the row being written, reused across rows
+    buffer: Vec<u8>,
+}
+
+impl<W: Finish> CsvWriter<W> {
+    pub fn new(
+        mut out: W,
+        options: &CsvOptions,
+        amount_format: AmountFormat,
+        columns: Columns,
+    ) -> io::Result<Self> {
+        let rows = CsvRows::new(options, amount_format, columns);
+        let mut buffer = Vec::new();
+        if options.header {
+            rows.header(&mut buffer);
+            out.write_all(&buffer)?;
+        }
+        Ok(Self { out, rows, buffer })
+    }
+}
+
+impl<W: Finish> TransactionWriter for CsvWriter<W> {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        self.buffer.clear();
+        self.rows.row(tx, &mut self.buffer);
+        self.out.write_all(&self.buffer)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.out.bytes()
+    }
+}
+
+// A CSV writer with the profile's delimiter and quoting
+pub(crate) fn csv_writer<W: Write>(out: W, options: &CsvOptions) -> csv::Writer<W> {
+    let quote_style = match options.quoting {
+        CsvQuoting::Necessary => csv::QuoteStyle::Necessary,
+        CsvQuoting::Always => csv::QuoteStyle::Always,
+        CsvQuoting::NonNumeric => csv::QuoteStyle::NonNumeric,
+        CsvQuoting::Never => csv::QuoteStyle::Never,
+    };
+    csv::WriterBuilder::new()
+        .delimiter(options.delimiter as u8)
+        .quote_style(quote_style)
+        .from_writer(out)
+}
+
+// Errors of the libraries behind optional outputs, as I/O errors
+#[cfg(any(
+    feature = "parquet",
+    feature = "sqlite",
+    feature = "postgres",
+    feature = "mysql",
+    feature = "kafka",
+    feature = "xlsx"
+))]
+pub(crate) fn to_io<E: std::error::Error + Send + Sync + 'static>(e: E) -> io::Error {
+    io::Error::other(e)
+}
+
+// A field of a row, before it is written out as text
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Field<'a> {
+    Text(&'a str),
+    // No value: empty in CSV, null in JSON
+    Missing,
+    // An amount in major units of the currency
+    Amount(f64, &'a str),
+    // A rate, written to CSV with this many decimals
+    Rate(f64, usize),
+    Count(u32),
+    Flag(bool),
+}
+
+impl<'a> Field<'a> {
+    // The field as CSV text. Numbers are formatted into `scratch`, which is
+    // reused from field to field, rather than into strings of their own.
+    pub(crate) fn csv_text<'b>(
+        self,
+        amount_format: AmountFormat,
+        scratch: &'b mut String,
+    ) -> &'b str
+    where
+        'a: 'b,
+    {
+        scratch.clear();
+        match self {
+            Field::Text(text) => return text,
+            Field::Missing => return "",
+            Field::Flag(flag) => return if flag { "true" } else { "false" },
+            Field::Amount(amount, currency) => {
+                Money::from_major(amount, currency).write_to(scratch, amount_format)
+            }
+            Field::Rate(rate, decimals) => {
+                let _ = write!(scratch, "{:.*}", decimals, rate);
+            }
+            Field::Count(count) => {
+                let _ = write!(scratch, "{}", count);
+            }
+        }
+        scratch
+    }
+}
+
+// A field that may have no value
+fn optional(text: Option<&str>) -> Field<'_> {
+    text.map_or(Field::Missing, Field::Text)
+}
+
+// The fields of a row, in `CSV_HEADER` order
+pub(crate) fn fields(tx: &Transaction) -> [Field<'_>; CSV_HEADER.len()] {
+    use Field::{Amount, Count, Flag, Missing, Rate, Text};
+    let billing_currency = tx.billing_currency.as_deref().unwrap_or(&tx.currency);
+    [
+        Text(&tx.transaction_id),
+        optional(tx.event_type.as_ref().map(|e| e.as_str())),
+        Text(&tx.transaction_date),
+        Text(tx.status.as_str()),
+        optional(tx.decline_reason.as_ref().map(|r| r.as_str())),
+        Text(&tx.response_code),
+        optional(tx.auth_code.as_deref()),
+        optional(tx.original_transaction_id.as_deref()),
+        optional(tx.subscription_id.as_deref()),
+        optional(tx.initiator.as_ref().map(|i| i.as_str())),
+        tx.installment_number.map_or(Missing, Count),
+        tx.installment_count.map_or(Missing, Count),
+        optional(tx.order_reference.as_deref()),
+        optional(tx.preauth_step.as_ref().map(|s| s.as_str())),
+        Text(&tx.customer_id),
+        Text(&tx.cardholder_name),
+        Text(&tx.email),
+        Text(&tx.phone),
+        Text(&tx.card_number),
+        Text(&tx.card_brand),
+        Text(&tx.card_expiry),
+        optional(tx.cvv.as_deref()),
+        optional(tx.issuer_bank.as_deref()),
+        optional(tx.card_country.as_deref()),
+        optional(tx.card_type.as_ref().map(|t| t.as_str())),
+        Text(&tx.billing_street),
+        Text(&tx.billing_city),
+        Text(&tx.billing_region),
+        Text(&tx.billing_postal_code),
+        Text(&tx.billing_country),
+        optional(tx.shipping_street.as_deref()),
+        optional(tx.shipping_city.as_deref()),
+        optional(tx.shipping_region.as_deref()),
+        optional(tx.shipping_postal_code.as_deref()),
+        optional(tx.shipping_country.as_deref()),
+        Amount(tx.amount, &tx.currency),
+        tx.refund_amount.map_or(Missing, |amount| Amount(amount, &tx.currency)),
+        tx.remaining_amount.map_or(Missing, |amount| Amount(amount, &tx.currency)),
+        Text(&tx.currency),
+        tx.original_amount.map_or(Missing, |amount| Amount(amount, &tx.currency)),
+        optional(tx.original_currency.as_deref()),
+        tx.billing_amount.map_or(Missing, |amount| Amount(amount, billing_currency)),
+        optional(tx.billing_currency.as_deref()),
+        tx.fx_rate.map_or(Missing, |rate| Rate(rate, 6)),
+        tx.fx_markup.map_or(Missing, |markup| Rate(markup, 2)),
+        Text(&tx.merchant_name),
+        Text(&tx.merchant_id),
+        Text(&tx.statement_descriptor),
+        Text(&tx.merchant_category),
+        optional(tx.mcc.as_deref()),
+        Text(&tx.acquirer_id),
+        Text(&tx.processor),
+        optional(tx.batch_id.as_deref()),
+        optional(tx.settlement_date.as_deref()),
+        Text(&tx.payment_method),
+        Text(tx.channel.as_str()),
+        Text(&tx.pos_entry_mode),
+        optional(tx.terminal_id.as_deref()),
+        optional(tx.track1.as_deref()),
+        optional(tx.track2.as_deref()),
+        optional(tx.emv_aid.as_deref()),
+        optional(tx.emv_cryptogram.as_deref()),
+        optional(tx.emv_tvr.as_deref()),
+        optional(tx.emv_atc.as_deref()),
+        optional(tx.wallet.as_ref().map(|w| w.as_str())),
+        optional(tx.wallet_id.as_deref()),
+        optional(tx.token_pan.as_deref()),
+        optional(tx.token_requestor_id.as_deref()),
+        optional(tx.three_ds_version.as_deref()),
+        optional(tx.eci.as_deref()),
+        optional(tx.authentication_result.as_ref().map(|r| r.as_str())),
+        optional(tx.cavv.as_deref()),
+        optional(tx.avs_result.as_deref()),
+        optional(tx.cvv_result.as_deref()),
+        optional(tx.ip_address.as_deref()),
+        optional(tx.ip_country.as_deref()),
+        optional(tx.device_id.as_deref()),
+        optional(tx.device_platform.as_deref()),
+        optional(tx.device_os.as_deref()),
+        optional(tx.device_browser.as_deref()),
+        optional(tx.screen_resolution.as_deref()),
+        optional(tx.device_fingerprint.as_deref()),
+        optional(tx.user_agent.as_deref()),
+        Flag(tx.is_fraud),
+        optional(tx.fraud_pattern.as_ref().map(|p| p.as_str())),
+        optional(tx.persona.as_deref()),
+    ]
+}
+
+// Fill `record` with the CSV fields of a row, in `CSV_HEADER` order
+pub(crate) fn csv_record(
+    tx: &Transaction,
+    amount_format: AmountFormat,
+    record: &mut csv::StringRecord,
+    scratch: &mut String,
+) {
+    record.clear();
+    for field in fields(tx) {
+        record.push_field(field.csv_text(amount_format, scratch));
+    }
+}
+
+// Append the text of a row's custom fields to `record`, in the order of
+// their names
+pub(crate) fn push_custom(tx: &Transaction, names: &[String], record: &mut csv::StringRecord) {
+    for name in names {
+        record.push_field(&custom::text(tx.custom.get(name)));
+    }
+}
+
+// Writes transactions as a pretty-printed JSON array, one element at a time
+pub struct JsonWriter<W: Finish> {
+    out: W,
+    count: usize,
+    rows: JsonRows,
+    // Text of the element being written, reused across rows
+    buffer: Vec<u8>,
+}
+
+impl<W: Finish> JsonWriter<W> {
+    pub fn new(out: W, amount_format: AmountFormat, columns: Columns) -> Self {
+        Self {
+            out,
+            count: 0,
+            rows: JsonRows::new(amount_format, columns, true),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<W: Finish> TransactionWriter for JsonWriter<W> {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        let separator = if self.count == 0 { "[\n  " } else { ",\n  " };
+        self.buffer.clear();
+        self.buffer.extend_from_slice(separator.as_bytes());
+        self.rows.row(tx, &mut self.buffer);
+        self.out.write_all(&self.buffer)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let closing = if self.count == 0 { "[]" } else { "\n]" };
+        self.out.write_all(closing.as_bytes())?;
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.out.bytes()
+    }
+}
+
+// Writes transactions as newline-delimited JSON (JSON Lines), the shape
+// expected by Spark, BigQuery and most bulk loaders
+pub struct NdjsonWriter<W: Finish> {
+    out: W,
+    rows: JsonRows,
+    // Text of the line being written, reused across rows
+    buffer: Vec<u8>,
+}
+
+impl<W: Finish> NdjsonWriter<W> {
+    pub fn new(out: W, amount_format: AmountFormat, columns: Columns) -> Self {
+        Self {
+            out,
+            rows: JsonRows::new(amount_format, columns, false),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<W: Finish> TransactionWriter for NdjsonWriter<W> {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        self.buffer.clear();
+        self.rows.row(tx, &mut self.buffer);
+        self.buffer.push(b'\n');
+        self.out.write_all(&self.buffer)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.out.bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generator::TransactionStream, metrics};
+    use std::{env, fs, process};
+
+    #[test]
+    fn writers_count_the_bytes_of_their_files() {
+        let dir = env::temp_dir().join(format!("luhnsynth-bytes-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for compression in [None, Some(Compression::Gzip), Some(Compression::Zstd)] {
+            for max_rows in [None, Some(1_000)] {
+                let profile = Profile {
+                    compression,
+                    max_rows_per_file: max_rows,
+                    ..Profile::default()
+                };
+                let path = dir.join(file_name("transactions", OutputFormat::Csv, &profile));
+                let mut writer = create_writer(OutputFormat::Csv, &path, &profile).unwrap();
+                for tx in TransactionStream::new(&profile, 2_500) {
+                    writer.write(&tx).unwrap();
+                }
+                writer.finish().unwrap();
+                // Compressed files count what reached the disk
+                let files = dir.join(match max_rows {
+                    Some(_) => file_name("transactions-*", OutputFormat::Csv, &profile),
+                    None => file_name("transactions", OutputFormat::Csv, &profile),
+                });
+                assert!(writer.bytes() > 0);
+                assert_eq!(writer.bytes(), metrics::disk_usage(&files));
+                for file in metrics::files(&files) {
+                    fs::remove_file(file).unwrap();
+                }
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
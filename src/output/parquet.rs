@@ -0,0 +1,317 @@
+use super::{TransactionWriter, to_io};
+use crate::{
+    custom::{CustomField, CustomType},
+    model::Transaction,
+};
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int32Array, Int64Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::DateTime;
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use std::{fs::File, io, sync::Arc};
+
+// Number of transactions buffered into each Arrow record batch
+const BATCH_SIZE: usize = 8192;
+
+// Writes transactions as an Apache Parquet file with a typed Arrow schema
+pub struct ParquetWriter {
+    writer: Option<ArrowWriter<File>>,
+    schema: SchemaRef,
+    // Custom fields, in columns after the built-in ones
+    custom: Vec<(String, CustomType)>,
+    buffer: Vec<Transaction>,
+    // Bytes of the file, once it's been closed
+    bytes: u64,
+}
+
+impl ParquetWriter {
+    pub fn new(file: File, custom_fields: &[CustomField]) -> io::Result<Self> {
+        let custom: Vec<(String, CustomType)> = custom_fields
+            .iter()
+            .map(|field| (field.name.clone(), field.kind()))
+            .collect();
+        let schema = Arc::new(transaction_schema(custom_fields));
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(file, schema.clone(), Some(props)).map_err(to_io)?;
+        Ok(Self {
+            writer: Some(writer),
+            schema,
+            custom,
+            buffer: Vec::with_capacity(BATCH_SIZE),
+            bytes: 0,
+        })
+    }
+
+    // Convert the buffered transactions into a record batch and write it out
+    fn flush_batch(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let rows = &self.buffer;
+        let timestamps = rows
+            .iter()
+            .map(|tx| {
+                DateTime::parse_from_rfc3339(&tx.transaction_date)
+                    .map(|date| date.timestamp_micros())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut columns: Vec<ArrayRef> = vec![
+            strings(rows, |tx| Some(&tx.transaction_id)),
+            strings(rows, |tx| tx.event_type.as_ref().map(|e| e.as_str())),
+            Arc::new(TimestampMicrosecondArray::from(timestamps).with_timezone("UTC")),
+            strings(rows, |tx| Some(tx.status.as_str())),
+            strings(rows, |tx| tx.decline_reason.as_ref().map(|r| r.as_str())),
+            strings(rows, |tx| Some(&tx.response_code)),
+            strings(rows, |tx| tx.auth_code.as_deref()),
+            strings(rows, |tx| tx.original_transaction_id.as_deref()),
+            strings(rows, |tx| tx.subscription_id.as_deref()),
+            strings(rows, |tx| tx.initiator.as_ref().map(|i| i.as_str())),
+            Arc::new(Int32Array::from_iter(
+                rows.iter().map(|tx| tx.installment_number.map(|n| n as i32)),
+            )),
+            Arc::new(Int32Array::from_iter(
+                rows.iter().map(|tx| tx.installment_count.map(|n| n as i32)),
+            )),
+            strings(rows, |tx| tx.order_reference.as_deref()),
+            strings(rows, |tx| tx.preauth_step.as_ref().map(|s| s.as_str())),
+            strings(rows, |tx| Some(&tx.customer_id)),
+            strings(rows, |tx| Some(&tx.cardholder_name)),
+            strings(rows, |tx| Some(&tx.email)),
+            strings(rows, |tx| Some(&tx.phone)),
+            strings(rows, |tx| Some(&tx.card_number)),
+            strings(rows, |tx| Some(&tx.card_brand)),
+            strings(rows, |tx| Some(&tx.card_expiry)),
+            strings(rows, |tx| tx.cvv.as_deref()),
+            strings(rows, |tx| tx.issuer_bank.as_deref()),
+            strings(rows, |tx| tx.card_country.as_deref()),
+            strings(rows, |tx| tx.card_type.as_ref().map(|t| t.as_str())),
+            strings(rows, |tx| Some(&tx.billing_street)),
+            strings(rows, |tx| Some(&tx.billing_city)),
+            strings(rows, |tx| Some(&tx.billing_region)),
+            strings(rows, |tx| Some(&tx.billing_postal_code)),
+            strings(rows, |tx| Some(&tx.billing_country)),
+            strings(rows, |tx| tx.shipping_street.as_deref()),
+            strings(rows, |tx| tx.shipping_city.as_deref()),
+            strings(rows, |tx| tx.shipping_region.as_deref()),
+            strings(rows, |tx| tx.shipping_postal_code.as_deref()),
+            strings(rows, |tx| tx.shipping_country.as_deref()),
+            Arc::new(Float64Array::from_iter_values(rows.iter().map(|tx| tx.amount))),
+            Arc::new(Float64Array::from_iter(rows.iter().map(|tx| tx.refund_amount))),
+            Arc::new(Float64Array::from_iter(rows.iter().map(|tx| tx.remaining_amount))),
+            strings(rows, |tx| Some(&tx.currency)),
+            Arc::new(Float64Array::from_iter(rows.iter().map(|tx| tx.original_amount))),
+            strings(rows, |tx| tx.original_currency.as_deref()),
+            Arc::new(Float64Array::from_iter(rows.iter().map(|tx| tx.billing_amount))),
+            strings(rows, |tx| tx.billing_currency.as_deref()),
+            Arc::new(Float64Array::from_iter(rows.iter().map(|tx| tx.fx_rate))),
+            Arc::new(Float64Array::from_iter(rows.iter().map(|tx| tx.fx_markup))),
+            strings(rows, |tx| Some(&tx.merchant_name)),
+            strings(rows, |tx| Some(&tx.merchant_id)),
+            strings(rows, |tx| Some(&tx.statement_descriptor)),
+            strings(rows, |tx| Some(&tx.merchant_category)),
+            strings(rows, |tx| tx.mcc.as_deref()),
+            strings(rows, |tx| Some(&tx.acquirer_id)),
+            strings(rows, |tx| Some(&tx.processor)),
+            strings(rows, |tx| tx.batch_id.as_deref()),
+            strings(rows, |tx| tx.settlement_date.as_deref()),
+            strings(rows, |tx| Some(&tx.payment_method)),
+            strings(rows, |tx| Some(tx.channel.as_str())),
+            strings(rows, |tx| Some(&tx.pos_entry_mode)),
+            strings(rows, |tx| tx.terminal_id.as_deref()),
+            strings(rows, |tx| tx.track1.as_deref()),
+            strings(rows, |tx| tx.track2.as_deref()),
+            strings(rows, |tx| tx.emv_aid.as_deref()),
+            strings(rows, |tx| tx.emv_cryptogram.as_deref()),
+            strings(rows, |tx| tx.emv_tvr.as_deref()),
+            strings(rows, |tx| tx.emv_atc.as_deref()),
+            strings(rows, |tx| tx.wallet.as_ref().map(|w| w.as_str())),
+            strings(rows, |tx| tx.wallet_id.as_deref()),
+            strings(rows, |tx| tx.token_pan.as_deref()),
+            strings(rows, |tx| tx.token_requestor_id.as_deref()),
+            strings(rows, |tx| tx.three_ds_version.as_deref()),
+            strings(rows, |tx| tx.eci.as_deref()),
+            strings(rows, |tx| tx.authentication_result.as_ref().map(|r| r.as_str())),
+            strings(rows, |tx| tx.cavv.as_deref()),
+            strings(rows, |tx| tx.avs_result.as_deref()),
+            strings(rows, |tx| tx.cvv_result.as_deref()),
+            strings(rows, |tx| tx.ip_address.as_deref()),
+            strings(rows, |tx| tx.ip_country.as_deref()),
+            strings(rows, |tx| tx.device_id.as_deref()),
+            strings(rows, |tx| tx.device_platform.as_deref()),
+            strings(rows, |tx| tx.device_os.as_deref()),
+            strings(rows, |tx| tx.device_browser.as_deref()),
+            strings(rows, |tx| tx.screen_resolution.as_deref()),
+            strings(rows, |tx| tx.device_fingerprint.as_deref()),
+            strings(rows, |tx| tx.user_agent.as_deref()),
+            Arc::new(BooleanArray::from_iter(rows.iter().map(|tx| Some(tx.is_fraud)))),
+            strings(rows, |tx| tx.fraud_pattern.as_ref().map(|p| p.as_str())),
+            strings(rows, |tx| tx.persona.as_deref()),
+        ];
+        for (name, kind) in &self.custom {
+            let values = rows.iter().map(|tx| tx.custom.get(name));
+            columns.push(match kind {
+                CustomType::Text => strings(rows, |tx| tx.custom.get(name)?.as_str()),
+                CustomType::Integer => Arc::new(Int64Array::from_iter(values.map(|v| v?.as_i64()))),
+                CustomType::Float => Arc::new(Float64Array::from_iter(values.map(|v| v?.as_f64()))),
+                CustomType::Boolean => {
+                    Arc::new(BooleanArray::from_iter(values.map(|v| v?.as_bool())))
+                }
+            });
+        }
+
+        let batch = RecordBatch::try_new(self.schema.clone(), columns).map_err(to_io)?;
+        if let Some(writer) = self.writer.as_mut() {
+            writer.write(&batch).map_err(to_io)?;
+        }
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl TransactionWriter for ParquetWriter {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        self.buffer.push(tx.clone());
+        if self.buffer.len() >= BATCH_SIZE {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.flush_batch()?;
+        if let Some(mut writer) = self.writer.take() {
+            writer.finish().map_err(to_io)?;
+            self.bytes = writer.bytes_written() as u64;
+        }
+        Ok(())
+    }
+
+    fn bytes(&self) -> u64 {
+        match &self.writer {
+            Some(writer) => writer.bytes_written() as u64,
+            None => self.bytes,
+        }
+    }
+}
+
+// Arrow schema matching the `Transaction` struct, with the custom fields
+// after the built-in ones
+pub(crate) fn transaction_schema(custom_fields: &[CustomField]) -> Schema {
+    let utf8 = |name: &str, nullable: bool| Field::new(name, DataType::Utf8, nullable);
+    let custom = custom_fields.iter().map(|field| {
+        let data_type = match field.kind() {
+            CustomType::Text => DataType::Utf8,
+            CustomType::Integer => DataType::Int64,
+            CustomType::Float => DataType::Float64,
+            CustomType::Boolean => DataType::Boolean,
+        };
+        Field::new(&field.name, data_type, true)
+    });
+    let fields = vec![
+        utf8("transaction_id", false),
+        utf8("event_type", true),
+        Field::new(
+            "transaction_date",
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            false,
+        ),
+        utf8("status", false),
+        utf8("decline_reason", true),
+        utf8("response_code", false),
+        utf8("auth_code", true),
+        utf8("original_transaction_id", true),
+        utf8("subscription_id", true),
+        utf8("initiator", true),
+        Field::new("installment_number", DataType::Int32, true),
+        Field::new("installment_count", DataType::Int32, true),
+        utf8("order_reference", true),
+        utf8("preauth_step", true),
+        utf8("customer_id", false),
+        utf8("cardholder_name", false),
+        utf8("email", false),
+        utf8("phone", false),
+        utf8("card_number", false),
+        utf8("card_brand", false),
+        utf8("card_expiry", false),
+        utf8("cvv", true),
+        utf8("issuer_bank", true),
+        utf8("card_country", true),
+        utf8("card_type", true),
+        utf8("billing_street", false),
+        utf8("billing_city", false),
+        utf8("billing_region", false),
+        utf8("billing_postal_code", false),
+        utf8("billing_country", false),
+        utf8("shipping_street", true),
+        utf8("shipping_city", true),
+        utf8("shipping_region", true),
+        utf8("shipping_postal_code", true),
+        utf8("shipping_country", true),
+        Field::new("amount", DataType::Float64, false),
+        Field::new("refund_amount", DataType::Float64, true),
+        Field::new("remaining_amount", DataType::Float64, true),
+        utf8("currency", false),
+        Field::new("original_amount", DataType::Float64, true),
+        utf8("original_currency", true),
+        Field::new("billing_amount", DataType::Float64, true),
+        utf8("billing_currency", true),
+        Field::new("fx_rate", DataType::Float64, true),
+        Field::new("fx_markup", DataType::Float64, true),
+        utf8("merchant_name", false),
+        utf8("merchant_id", false),
+        utf8("statement_descriptor", false),
+        utf8("merchant_category", false),
+        utf8("mcc", true),
+        utf8("acquirer_id", false),
+        utf8("processor", false),
+        utf8("batch_id", true),
+        utf8("settlement_date", true),
+        utf8("payment_method", false),
+        utf8("channel", false),
+        utf8("pos_entry_mode", false),
+        utf8("terminal_id", true),
+        utf8("track1", true),
+        utf8("track2", true),
+        utf8("emv_aid", true),
+        utf8("emv_cryptogram", true),
+        utf8("emv_tvr", true),
+        utf8("emv_atc", true),
+        utf8("wallet", true),
+        utf8("wallet_id", true),
+        utf8("token_pan", true),
+        utf8("token_requestor_id", true),
+        utf8("three_ds_version", true),
+        utf8("eci", true),
+        utf8("authentication_result", true),
+        utf8("cavv", true),
+        utf8("avs_result", true),
+        utf8("cvv_result", true),
+        utf8("ip_address", true),
+        utf8("ip_country", true),
+        utf8("device_id", true),
+        utf8("device_platform", true),
+        utf8("device_os", true),
+        utf8("device_browser", true),
+        utf8("screen_resolution", true),
+        utf8("device_fingerprint", true),
+        utf8("user_agent", true),
+        Field::new("is_fraud", DataType::Boolean, false),
+        utf8("fraud_pattern", true),
+        utf8("persona", true),
+    ];
+    Schema::new(fields.into_iter().chain(custom).collect::<Vec<_>>())
+}
+
+// Build a string column from one field of every buffered transaction
+fn strings<'a, F>(rows: &'a [Transaction], field: F) -> ArrayRef
+where
+    F: Fn(&'a Transaction) -> Option<&'a str>,
+{
+    Arc::new(rows.iter().map(field).collect::<StringArray>())
+}
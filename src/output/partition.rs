@@ -0,0 +1,110 @@
+use super::{ShardedWriter, TransactionWriter, create_writer, file_name};
+use crate::{
+    config::{OutputFormat, PartitionBy, Profile},
+    model::Transaction,
+};
+use chrono::DateTime;
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// Partition of rows whose date can't be read, named as Hive names the
+// partition of missing values
+const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+// Writes a dataset as a Hive-style directory with a partition per day or
+// month of the transactions' local date, `dt=2024-09-25/part-0000.csv`, for
+// query engines to prune. Partitions hold more files, `part-0001` and on,
+// when files are limited to a number of rows. Rows aren't generated in date
+// order, so each partition's file is opened when its first row comes along
+// and stays open until the dataset is finished.
+pub struct PartitionedWriter {
+    format: OutputFormat,
+    dir: PathBuf,
+    by: PartitionBy,
+    // Most rows in each file of a partition, beyond which it has more than one
+    max_rows: Option<usize>,
+    // Options the files of each partition are written with
+    profile: Profile,
+    partitions: BTreeMap<String, Box<dyn TransactionWriter>>,
+}
+
+impl PartitionedWriter {
+    pub fn new(
+        format: OutputFormat,
+        dir: &Path,
+        by: PartitionBy,
+        profile: &Profile,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Self {
+            format,
+            dir: dir.to_path_buf(),
+            by,
+            max_rows: profile.max_rows_per_file,
+            profile: Profile {
+                partition_by: None,
+                max_rows_per_file: None,
+                ..profile.clone()
+            },
+            partitions: BTreeMap::new(),
+        })
+    }
+}
+
+impl TransactionWriter for PartitionedWriter {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        let value = match DateTime::parse_from_rfc3339(&tx.transaction_date) {
+            Ok(date) => date.format(self.by.date_format()).to_string(),
+            Err(_) => DEFAULT_PARTITION.to_string(),
+        };
+        let writer = match self.partitions.entry(value) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let dir = self.dir.join(format!("dt={}", entry.key()));
+                fs::create_dir_all(&dir)?;
+                let writer: Box<dyn TransactionWriter> = match self.max_rows {
+                    Some(max_rows) => {
+                        let part = |index| format!("part-{:04}", index);
+                        Box::new(ShardedWriter::new(
+                            self.format,
+                            &dir,
+                            part,
+                            max_rows,
+                            0,
+                            &self.profile,
+                        )?)
+                    }
+                    None => {
+                        let path = dir.join(file_name("part-0000", self.format, &self.profile));
+                        create_writer(self.format, &path, &self.profile)?
+                    }
+                };
+                entry.insert(writer)
+            }
+        };
+        writer.write(tx)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        for writer in self.partitions.values_mut() {
+            writer.finish()?;
+        }
+        Ok(())
+    }
+
+    fn summary(&self) -> Option<String> {
+        let summaries: Vec<String> = self
+            .partitions
+            .values()
+            .filter_map(|w| w.summary())
+            .collect();
+        (!summaries.is_empty()).then(|| summaries.concat())
+    }
+
+    fn bytes(&self) -> u64 {
+        self.partitions.values().map(|writer| writer.bytes()).sum()
+    }
+}
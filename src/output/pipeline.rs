@@ -0,0 +1,266 @@
+use super::{Finish, TransactionWriter};
+use crate::model::Transaction;
+use std::{
+    io::{self, BufWriter, Write},
+    mem,
+    sync::{Arc, OnceLock},
+};
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    runtime::{Builder, Runtime},
+    sync::{
+        mpsc::{self, Sender},
+        oneshot,
+    },
+    task::JoinHandle,
+};
+
+// Rows handed to the serializing task at a time, so the queue isn't locked
+// for every row
+const BATCH_ROWS: usize = 256;
+
+// Bytes handed to the writing task at a time, and the number of them that
+// can wait for it
+const CHUNK_BYTES: usize = 64 * 1024;
+const QUEUED_CHUNKS: usize = 16;
+
+// Rows passed between stages, shared with the other outputs they go to
+type Batch = Vec<Arc<Transaction>>;
+
+// The runtime pipelined outputs run on, once a run has asked for them
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+// Start the runtime pipelined outputs run on. Files and stdout opened from
+// then on are written by a task of their own.
+pub fn start_pipeline() -> io::Result<&'static Runtime> {
+    if let Some(runtime) = RUNTIME.get() {
+        return Ok(runtime);
+    }
+    let runtime = Builder::new_multi_thread().thread_name("output").build()?;
+    Ok(RUNTIME.get_or_init(|| runtime))
+}
+
+// An output run as a pipeline of three stages joined by bounded queues.
+// Rows are generated on the calling thread and handed to a task that
+// serializes them, which hands the bytes to a task that writes them. Each
+// stage carries on while the next one works through its queue, so a slow
+// sink only holds up serialization once its bytes queue is full, and
+// generation once its rows queue is full too.
+pub struct PipelinedWriter {
+    batch: Batch,
+    rows: Option<Sender<Batch>>,
+    runtime: &'static Runtime,
+    worker: Option<JoinHandle<io::Result<Box<dyn TransactionWriter>>>>,
+    // The writer once its task has finished, for its summary
+    finished: Option<Box<dyn TransactionWriter>>,
+}
+
+impl PipelinedWriter {
+    // Run a writer with room for about `depth` rows queued ahead of it
+    pub fn new(mut writer: Box<dyn TransactionWriter>, depth: usize) -> io::Result<Self> {
+        let runtime = start_pipeline()?;
+        let (rows, mut queue) = mpsc::channel::<Batch>(depth.div_ceil(BATCH_ROWS).max(1));
+        // Serializing is work for the processor rather than waiting, so it's
+        // done on a thread of the runtime's blocking pool
+        let worker = runtime.spawn_blocking(move || {
+            while let Some(batch) = queue.blocking_recv() {
+                for tx in batch {
+                    writer.write(&tx)?;
+                }
+            }
+            writer.finish()?;
+            Ok(writer)
+        });
+        Ok(Self {
+            batch: Vec::with_capacity(BATCH_ROWS),
+            rows: Some(rows),
+            runtime,
+            worker: Some(worker),
+            finished: None,
+        })
+    }
+
+    // Queue the rows batched so far, waiting while the queue is full
+    fn send(&mut self) -> io::Result<()> {
+        let batch = mem::replace(&mut self.batch, Vec::with_capacity(BATCH_ROWS));
+        let Some(rows) = &self.rows else {
+            return Err(io::Error::other("pipelined output already finished"));
+        };
+        if rows.blocking_send(batch).is_err() {
+            // The writer stopped on an error, which joining its task returns
+            self.join()?;
+            return Err(io::Error::other("pipelined output stopped early"));
+        }
+        Ok(())
+    }
+
+    // Close the queue and wait for the writer to finish, keeping it for its
+    // summary
+    fn join(&mut self) -> io::Result<()> {
+        self.rows = None;
+        let Some(worker) = self.worker.take() else {
+            return Ok(());
+        };
+        let writer = self
+            .runtime
+            .block_on(worker)
+            .map_err(|_| io::Error::other("output task panicked"))??;
+        self.finished = Some(writer);
+        Ok(())
+    }
+}
+
+impl TransactionWriter for PipelinedWriter {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        self.write_shared(&Arc::new(tx.clone()))
+    }
+
+    fn write_shared(&mut self, tx: &Arc<Transaction>) -> io::Result<()> {
+        self.batch.push(tx.clone());
+        if self.batch.len() >= BATCH_ROWS {
+            self.send()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.batch.is_empty() {
+            self.send()?;
+        }
+        self.join()
+    }
+
+    fn summary(&self) -> Option<String> {
+        self.finished.as_ref()?.summary()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.finished.as_ref().map_or(0, |writer| writer.bytes())
+    }
+}
+
+// Where the bytes of a file or stdout go: straight to it through a buffer,
+// or to a task that writes them once outputs are pipelined
+pub enum Destination<W: Write> {
+    Direct(BufWriter<W>),
+    Queued(Queued),
+}
+
+impl Destination<std::fs::File> {
+    pub fn file(file: std::fs::File) -> Self {
+        match RUNTIME.get() {
+            Some(runtime) => {
+                Destination::Queued(Queued::new(tokio::fs::File::from_std(file), runtime))
+            }
+            None => Destination::Direct(BufWriter::new(file)),
+        }
+    }
+}
+
+impl Destination<io::Stdout> {
+    pub fn stdout() -> Self {
+        match RUNTIME.get() {
+            Some(runtime) => Destination::Queued(Queued::new(tokio::io::stdout(), runtime)),
+            None => Destination::Direct(BufWriter::new(io::stdout())),
+        }
+    }
+}
+
+impl<W: Write> Write for Destination<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Destination::Direct(out) => out.write(buf),
+            Destination::Queued(out) => out.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Destination::Direct(out) => out.flush(),
+            Destination::Queued(out) => out.flush(),
+        }
+    }
+}
+
+impl<W: Write + Send> Finish for Destination<W> {
+    fn finish(&mut self) -> io::Result<()> {
+        match self {
+            Destination::Direct(out) => out.flush(),
+            Destination::Queued(out) => out.finish(),
+        }
+    }
+}
+
+// The last stage of a pipelined output: bytes gathered into chunks and
+// written by a task of the runtime, which reports how writing went once the
+// last chunk is out
+pub struct Queued {
+    chunk: Vec<u8>,
+    chunks: Option<Sender<Vec<u8>>>,
+    written: Option<oneshot::Receiver<io::Result<()>>>,
+}
+
+impl Queued {
+    fn new<W: AsyncWrite + Send + Unpin + 'static>(mut out: W, runtime: &Runtime) -> Self {
+        let (chunks, mut queue) = mpsc::channel::<Vec<u8>>(QUEUED_CHUNKS);
+        let (report, written) = oneshot::channel();
+        runtime.spawn(async move {
+            let result = async {
+                while let Some(chunk) = queue.recv().await {
+                    out.write_all(&chunk).await?;
+                }
+                out.flush().await
+            };
+            // Nobody is left to tell when the output was dropped unfinished
+            let _ = report.send(result.await);
+        });
+        Self {
+            chunk: Vec::with_capacity(CHUNK_BYTES),
+            chunks: Some(chunks),
+            written: Some(written),
+        }
+    }
+
+    // Queue the bytes gathered so far, waiting while the queue is full
+    fn send(&mut self) -> io::Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        let chunk = mem::replace(&mut self.chunk, Vec::with_capacity(CHUNK_BYTES));
+        let Some(chunks) = &self.chunks else {
+            return Err(io::Error::other("pipelined output already finished"));
+        };
+        if chunks.blocking_send(chunk).is_err() {
+            // Writing stopped on an error, which the task reports
+            self.finish()?;
+            return Err(io::Error::other("pipelined output stopped early"));
+        }
+        Ok(())
+    }
+
+    // Close the queue and wait for the task to write what's left
+    fn finish(&mut self) -> io::Result<()> {
+        self.send()?;
+        self.chunks = None;
+        let Some(written) = self.written.take() else {
+            return Ok(());
+        };
+        written
+            .blocking_recv()
+            .map_err(|_| io::Error::other("output task panicked"))?
+    }
+}
+
+impl Write for Queued {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.chunk.extend_from_slice(buf);
+        if self.chunk.len() >= CHUNK_BYTES {
+            self.send()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send()
+    }
+}
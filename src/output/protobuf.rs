@@ -0,0 +1,665 @@
+use super::{Finish, TransactionWriter};
+use crate::{
+    bin::CardType,
+    custom::{CustomField, CustomType},
+    fraud::FraudPattern,
+    model::{Channel, DeclineReason, LifecycleEvent, Transaction, TransactionStatus},
+    preauth::PreauthStep,
+    subscription::Initiator,
+    three_ds::AuthenticationResult,
+    wallet::Wallet,
+};
+use chrono::DateTime;
+use std::{fmt::Write as _, io};
+
+// Protobuf field types of a transaction. Field numbers follow this order,
+// starting at 1; it is `Transaction` field order, except that fields added
+// later go at the end so existing field numbers never change.
+const FIELDS: [(&str, &str); 86] = [
+    ("transaction_id", "string"),
+    ("event_type", "LifecycleEvent"),
+    ("transaction_date", "google.protobuf.Timestamp"),
+    ("status", "TransactionStatus"),
+    ("decline_reason", "DeclineReason"),
+    ("original_transaction_id", "optional string"),
+    ("customer_id", "string"),
+    ("cardholder_name", "string"),
+    ("card_number", "string"),
+    ("card_brand", "string"),
+    ("card_expiry", "string"),
+    ("cvv", "optional string"),
+    ("amount", "double"),
+    ("currency", "string"),
+    ("merchant_name", "string"),
+    ("merchant_id", "string"),
+    ("merchant_category", "string"),
+    ("mcc", "optional string"),
+    ("payment_method", "string"),
+    ("ip_address", "optional string"),
+    ("device_id", "optional string"),
+    ("user_agent", "optional string"),
+    ("is_fraud", "bool"),
+    ("fraud_pattern", "FraudPattern"),
+    ("issuer_bank", "optional string"),
+    ("card_country", "optional string"),
+    ("card_type", "CardType"),
+    ("billing_country", "string"),
+    ("ip_country", "optional string"),
+    ("device_platform", "optional string"),
+    ("device_os", "optional string"),
+    ("device_browser", "optional string"),
+    ("screen_resolution", "optional string"),
+    ("device_fingerprint", "optional string"),
+    ("channel", "Channel"),
+    ("pos_entry_mode", "string"),
+    ("terminal_id", "optional string"),
+    ("wallet", "Wallet"),
+    ("wallet_id", "optional string"),
+    ("token_pan", "optional string"),
+    ("token_requestor_id", "optional string"),
+    ("three_ds_version", "optional string"),
+    ("eci", "optional string"),
+    ("authentication_result", "AuthenticationResult"),
+    ("cavv", "optional string"),
+    ("avs_result", "optional string"),
+    ("cvv_result", "optional string"),
+    ("response_code", "string"),
+    ("auth_code", "optional string"),
+    ("acquirer_id", "string"),
+    ("processor", "string"),
+    ("batch_id", "optional string"),
+    ("settlement_date", "optional string"),
+    ("subscription_id", "optional string"),
+    ("initiator", "Initiator"),
+    ("installment_number", "optional uint32"),
+    ("installment_count", "optional uint32"),
+    ("refund_amount", "optional double"),
+    ("remaining_amount", "optional double"),
+    ("order_reference", "optional string"),
+    ("preauth_step", "PreauthStep"),
+    ("original_amount", "optional double"),
+    ("original_currency", "optional string"),
+    ("billing_amount", "optional double"),
+    ("billing_currency", "optional string"),
+    ("fx_rate", "optional double"),
+    ("fx_markup", "optional double"),
+    ("email", "string"),
+    ("phone", "string"),
+    ("billing_street", "string"),
+    ("billing_city", "string"),
+    ("billing_region", "string"),
+    ("billing_postal_code", "string"),
+    ("shipping_street", "optional string"),
+    ("shipping_city", "optional string"),
+    ("shipping_region", "optional string"),
+    ("shipping_postal_code", "optional string"),
+    ("shipping_country", "optional string"),
+    ("statement_descriptor", "string"),
+    ("persona", "optional string"),
+    ("track1", "optional string"),
+    ("track2", "optional string"),
+    ("emv_aid", "optional string"),
+    ("emv_cryptogram", "optional string"),
+    ("emv_tvr", "optional string"),
+    ("emv_atc", "optional string"),
+];
+
+// Field number of the first custom field, the rest following in profile
+// order, clear of the numbers of fields added later
+const FIRST_CUSTOM_FIELD: u32 = 1000;
+
+// The `.proto` definition of the messages written by `ProtobufWriter`, and
+// of the gRPC service streaming them.
+// Enum value 0 is UNSPECIFIED, which also stands for an absent lifecycle
+// event, decline reason, fraud pattern, card type, wallet, authentication
+// result, initiator or pre-authorization step.
+pub fn proto_schema(custom_fields: &[CustomField]) -> String {
+    let mut proto = String::from(
+        "syntax = \"proto3\";\n\npackage luhnsynth;\n\nimport \"google/protobuf/timestamp.proto\";\n",
+    );
+    let enums: [(&str, Vec<&str>); 10] = [
+        (
+            "TransactionStatus",
+            TransactionStatus::ALL.map(|s| s.as_str()).to_vec(),
+        ),
+        (
+            "DeclineReason",
+            DeclineReason::ALL.map(|r| r.as_str()).to_vec(),
+        ),
+        (
+            "LifecycleEvent",
+            LifecycleEvent::ALL.map(|e| e.as_str()).to_vec(),
+        ),
+        (
+            "FraudPattern",
+            FraudPattern::ALL.map(|p| p.as_str()).to_vec(),
+        ),
+        ("CardType", CardType::ALL.map(|t| t.as_str()).to_vec()),
+        ("Channel", Channel::ALL.map(|c| c.as_str()).to_vec()),
+        ("Wallet", Wallet::ALL.map(|w| w.as_str()).to_vec()),
+        (
+            "AuthenticationResult",
+            AuthenticationResult::ALL.map(|r| r.as_str()).to_vec(),
+        ),
+        ("Initiator", Initiator::ALL.map(|i| i.as_str()).to_vec()),
+        ("PreauthStep", PreauthStep::ALL.map(|s| s.as_str()).to_vec()),
+    ];
+    for (name, symbols) in enums {
+        let prefix = screaming_snake_case(name);
+        writeln!(proto, "\nenum {} {{", name).unwrap();
+        writeln!(proto, "  {}_UNSPECIFIED = 0;", prefix).unwrap();
+        for (i, symbol) in symbols.iter().enumerate() {
+            let value = symbol.to_uppercase();
+            writeln!(proto, "  {}_{} = {};", prefix, value, i + 1).unwrap();
+        }
+        proto.push_str("}\n");
+    }
+    proto.push_str("\nmessage Transaction {\n");
+    for (i, (name, ty)) in FIELDS.iter().enumerate() {
+        writeln!(proto, "  {} {} = {};", ty, name, i + 1).unwrap();
+    }
+    for (field, number) in custom_fields.iter().zip(FIRST_CUSTOM_FIELD..) {
+        let ty = match field.kind() {
+            CustomType::Text => "string",
+            CustomType::Integer => "int64",
+            CustomType::Float => "double",
+            CustomType::Boolean => "bool",
+        };
+        writeln!(proto, "  optional {} {} = {};", ty, field.name, number).unwrap();
+    }
+    proto.push_str("}\n");
+    proto.push_str(SERVICE);
+    proto
+}
+
+// The gRPC service of `luhnsynth serve`, streaming transactions as they're
+// generated
+const SERVICE: &str = "
+message StreamRequest {
+  // Number of transactions; 100 when left at 0
+  uint32 count = 1;
+  // Card brand of every transaction, as in \"visa\"; any of the profile's
+  // brands when empty
+  string brand = 2;
+  // Seed of reproducible transactions, starting at row `start`
+  optional uint64 seed = 3;
+  uint64 start = 4;
+}
+
+service Transactions {
+  rpc Stream(StreamRequest) returns (stream Transaction);
+}
+";
+
+// "TransactionStatus" -> "TRANSACTION_STATUS"
+fn screaming_snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_uppercase());
+    }
+    out
+}
+
+// Writes transactions as a stream of length-delimited protobuf `Transaction`
+// messages, each prefixed with its size as a varint, the framing read by
+// `parseDelimitedFrom` and similar helpers
+pub struct ProtobufWriter<W: Finish> {
+    out: W,
+    // Reused encoding buffer for the current message
+    message: Vec<u8>,
+}
+
+impl<W: Finish> ProtobufWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            message: Vec::new(),
+        }
+    }
+}
+
+impl<W: Finish> TransactionWriter for ProtobufWriter<W> {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        self.message.clear();
+        encode_transaction(tx, &mut self.message)?;
+        let mut prefix = Vec::new();
+        write_varint(&mut prefix, self.message.len() as u64);
+        self.out.write_all(&prefix)?;
+        self.out.write_all(&self.message)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.out.bytes()
+    }
+}
+
+// Append a transaction encoded as a `Transaction` message
+pub fn encode_transaction(tx: &Transaction, out: &mut Vec<u8>) -> io::Result<()> {
+    let date = DateTime::parse_from_rfc3339(&tx.transaction_date)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    // google.protobuf.Timestamp: seconds and nanos since the Unix epoch
+    let mut timestamp = Vec::new();
+    write_varint_field(&mut timestamp, 1, date.timestamp() as u64);
+    write_varint_field(&mut timestamp, 2, date.timestamp_subsec_nanos() as u64);
+
+    write_string_field(out, 1, &tx.transaction_id);
+    if let Some(event) = &tx.event_type {
+        let symbols = LifecycleEvent::ALL.map(|e| e.as_str());
+        write_varint_field(out, 2, enum_value(&symbols, event.as_str()));
+    }
+    write_bytes_field(out, 3, &timestamp);
+    let symbols = TransactionStatus::ALL.map(|s| s.as_str());
+    write_varint_field(out, 4, enum_value(&symbols, tx.status.as_str()));
+    if let Some(reason) = &tx.decline_reason {
+        let symbols = DeclineReason::ALL.map(|r| r.as_str());
+        write_varint_field(out, 5, enum_value(&symbols, reason.as_str()));
+    }
+    // Optional fields are written whenever present, even when empty
+    if let Some(id) = &tx.original_transaction_id {
+        write_bytes_field(out, 6, id.as_bytes());
+    }
+    write_string_field(out, 7, &tx.customer_id);
+    write_string_field(out, 8, &tx.cardholder_name);
+    write_string_field(out, 9, &tx.card_number);
+    write_string_field(out, 10, &tx.card_brand);
+    write_string_field(out, 11, &tx.card_expiry);
+    write_optional_field(out, 12, tx.cvv.as_deref());
+    if tx.amount != 0.0 {
+        write_tag(out, 13, 1);
+        out.extend_from_slice(&tx.amount.to_le_bytes());
+    }
+    write_string_field(out, 14, &tx.currency);
+    write_string_field(out, 15, &tx.merchant_name);
+    write_string_field(out, 16, &tx.merchant_id);
+    write_string_field(out, 17, &tx.merchant_category);
+    if let Some(mcc) = &tx.mcc {
+        write_bytes_field(out, 18, mcc.as_bytes());
+    }
+    write_string_field(out, 19, &tx.payment_method);
+    write_optional_field(out, 20, tx.ip_address.as_deref());
+    write_optional_field(out, 21, tx.device_id.as_deref());
+    write_optional_field(out, 22, tx.user_agent.as_deref());
+    write_varint_field(out, 23, tx.is_fraud as u64);
+    if let Some(pattern) = &tx.fraud_pattern {
+        let symbols = FraudPattern::ALL.map(|p| p.as_str());
+        write_varint_field(out, 24, enum_value(&symbols, pattern.as_str()));
+    }
+    if let Some(issuer) = &tx.issuer_bank {
+        write_bytes_field(out, 25, issuer.as_bytes());
+    }
+    if let Some(country) = &tx.card_country {
+        write_bytes_field(out, 26, country.as_bytes());
+    }
+    if let Some(card_type) = &tx.card_type {
+        let symbols = CardType::ALL.map(|t| t.as_str());
+        write_varint_field(out, 27, enum_value(&symbols, card_type.as_str()));
+    }
+    write_string_field(out, 28, &tx.billing_country);
+    write_optional_field(out, 29, tx.ip_country.as_deref());
+    write_optional_field(out, 30, tx.device_platform.as_deref());
+    write_optional_field(out, 31, tx.device_os.as_deref());
+    write_optional_field(out, 32, tx.device_browser.as_deref());
+    write_optional_field(out, 33, tx.screen_resolution.as_deref());
+    write_optional_field(out, 34, tx.device_fingerprint.as_deref());
+    let symbols = Channel::ALL.map(|c| c.as_str());
+    write_varint_field(out, 35, enum_value(&symbols, tx.channel.as_str()));
+    write_string_field(out, 36, &tx.pos_entry_mode);
+    write_optional_field(out, 37, tx.terminal_id.as_deref());
+    if let Some(wallet) = &tx.wallet {
+        let symbols = Wallet::ALL.map(|w| w.as_str());
+        write_varint_field(out, 38, enum_value(&symbols, wallet.as_str()));
+    }
+    write_optional_field(out, 39, tx.wallet_id.as_deref());
+    write_optional_field(out, 40, tx.token_pan.as_deref());
+    write_optional_field(out, 41, tx.token_requestor_id.as_deref());
+    write_optional_field(out, 42, tx.three_ds_version.as_deref());
+    write_optional_field(out, 43, tx.eci.as_deref());
+    if let Some(result) = &tx.authentication_result {
+        let symbols = AuthenticationResult::ALL.map(|r| r.as_str());
+        write_varint_field(out, 44, enum_value(&symbols, result.as_str()));
+    }
+    write_optional_field(out, 45, tx.cavv.as_deref());
+    write_optional_field(out, 46, tx.avs_result.as_deref());
+    write_optional_field(out, 47, tx.cvv_result.as_deref());
+    write_string_field(out, 48, &tx.response_code);
+    write_optional_field(out, 49, tx.auth_code.as_deref());
+    write_string_field(out, 50, &tx.acquirer_id);
+    write_string_field(out, 51, &tx.processor);
+    write_optional_field(out, 52, tx.batch_id.as_deref());
+    write_optional_field(out, 53, tx.settlement_date.as_deref());
+    write_optional_field(out, 54, tx.subscription_id.as_deref());
+    if let Some(initiator) = &tx.initiator {
+        let symbols = Initiator::ALL.map(|i| i.as_str());
+        write_varint_field(out, 55, enum_value(&symbols, initiator.as_str()));
+    }
+    write_optional_varint_field(out, 56, tx.installment_number.map(u64::from));
+    write_optional_varint_field(out, 57, tx.installment_count.map(u64::from));
+    write_optional_double_field(out, 58, tx.refund_amount);
+    write_optional_double_field(out, 59, tx.remaining_amount);
+    write_optional_field(out, 60, tx.order_reference.as_deref());
+    if let Some(step) = &tx.preauth_step {
+        let symbols = PreauthStep::ALL.map(|s| s.as_str());
+        write_varint_field(out, 61, enum_value(&symbols, step.as_str()));
+    }
+    write_optional_double_field(out, 62, tx.original_amount);
+    write_optional_field(out, 63, tx.original_currency.as_deref());
+    write_optional_double_field(out, 64, tx.billing_amount);
+    write_optional_field(out, 65, tx.billing_currency.as_deref());
+    write_optional_double_field(out, 66, tx.fx_rate);
+    write_optional_double_field(out, 67, tx.fx_markup);
+    write_string_field(out, 68, &tx.email);
+    write_string_field(out, 69, &tx.phone);
+    write_string_field(out, 70, &tx.billing_street);
+    write_string_field(out, 71, &tx.billing_city);
+    write_string_field(out, 72, &tx.billing_region);
+    write_string_field(out, 73, &tx.billing_postal_code);
+    write_optional_field(out, 74, tx.shipping_street.as_deref());
+    write_optional_field(out, 75, tx.shipping_city.as_deref());
+    write_optional_field(out, 76, tx.shipping_region.as_deref());
+    write_optional_field(out, 77, tx.shipping_postal_code.as_deref());
+    write_optional_field(out, 78, tx.shipping_country.as_deref());
+    write_string_field(out, 79, &tx.statement_descriptor);
+    write_optional_field(out, 80, tx.persona.as_deref());
+    write_optional_field(out, 81, tx.track1.as_deref());
+    write_optional_field(out, 82, tx.track2.as_deref());
+    write_optional_field(out, 83, tx.emv_aid.as_deref());
+    write_optional_field(out, 84, tx.emv_cryptogram.as_deref());
+    write_optional_field(out, 85, tx.emv_tvr.as_deref());
+    write_optional_field(out, 86, tx.emv_atc.as_deref());
+    // Custom fields are numbered in profile order, which rows hold them in
+    for (value, field) in tx.custom.values().zip(FIRST_CUSTOM_FIELD..) {
+        match value {
+            serde_json::Value::String(text) => write_optional_field(out, field, Some(text)),
+            serde_json::Value::Bool(flag) => {
+                write_optional_varint_field(out, field, Some(*flag as u64))
+            }
+            serde_json::Value::Number(number) => match number.as_i64() {
+                Some(number) => write_optional_varint_field(out, field, Some(number as u64)),
+                None => write_optional_double_field(out, field, number.as_f64()),
+            },
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Enum number of a symbol; 0 is reserved for UNSPECIFIED
+fn enum_value(symbols: &[&str], value: &str) -> u64 {
+    symbols
+        .iter()
+        .position(|symbol| *symbol == value)
+        .map_or(0, |i| i as u64 + 1)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_tag(out: &mut Vec<u8>, field: u32, wire_type: u8) {
+    write_varint(out, ((field as u64) << 3) | wire_type as u64);
+}
+
+// Varint field, left out at its default of zero as proto3 does
+fn write_varint_field(out: &mut Vec<u8>, field: u32, value: u64) {
+    if value != 0 {
+        write_tag(out, field, 0);
+        write_varint(out, value);
+    }
+}
+
+// Optional varint field, written whenever present, even when zero
+fn write_optional_varint_field(out: &mut Vec<u8>, field: u32, value: Option<u64>) {
+    if let Some(value) = value {
+        write_tag(out, field, 0);
+        write_varint(out, value);
+    }
+}
+
+// Optional double field, written whenever present, even when zero
+fn write_optional_double_field(out: &mut Vec<u8>, field: u32, value: Option<f64>) {
+    if let Some(value) = value {
+        write_tag(out, field, 1);
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+// Length-delimited field, written even when empty
+fn write_bytes_field(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_tag(out, field, 2);
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+// String field, left out when empty as proto3 does
+fn write_string_field(out: &mut Vec<u8>, field: u32, value: &str) {
+    if !value.is_empty() {
+        write_bytes_field(out, field, value.as_bytes());
+    }
+}
+
+// Optional string field, written whenever present, even when empty
+fn write_optional_field(out: &mut Vec<u8>, field: u32, value: Option<&str>) {
+    if let Some(value) = value {
+        write_bytes_field(out, field, value.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Profile, custom::CustomFields, seed};
+    use rand::{SeedableRng, rngs::StdRng};
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+
+    // Field numbers of the `Transaction` message in the `.proto` definition,
+    // with each field's type and whether it's optional
+    fn message_fields(proto: &str) -> HashMap<u32, (String, String, bool)> {
+        let message = proto.split("message Transaction {\n").nth(1).unwrap();
+        let message = &message[..message.find("\n}").unwrap()];
+        message
+            .lines()
+            .map(|line| {
+                let (declaration, number) =
+                    line.trim().trim_end_matches(';').split_once(" = ").unwrap();
+                let mut words: Vec<&str> = declaration.split(' ').collect();
+                let name = words.pop().unwrap().to_string();
+                let optional = words[0] == "optional";
+                let ty = words.last().unwrap().to_string();
+                (number.parse().unwrap(), (name, ty, optional))
+            })
+            .collect()
+    }
+
+    // Wire type a field of a type is written with
+    fn wire_type(ty: &str) -> u64 {
+        match ty {
+            "double" => 1,
+            "string" | "google.protobuf.Timestamp" => 2,
+            _ => 0,
+        }
+    }
+
+    fn varint(data: &mut &[u8]) -> u64 {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = data.split_first().expect("truncated varint");
+            *data = rest;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return value;
+            }
+        }
+        panic!("varint longer than 10 bytes");
+    }
+
+    // Fields of a message in order: number, wire type and raw value
+    fn fields(mut message: &[u8]) -> Vec<(u32, u64, Vec<u8>)> {
+        let mut fields = Vec::new();
+        while !message.is_empty() {
+            let key = varint(&mut message);
+            let (number, wire) = ((key >> 3) as u32, key & 7);
+            let value = match wire {
+                0 => varint(&mut message).to_le_bytes().to_vec(),
+                1 => {
+                    let (value, rest) = message.split_at(8);
+                    message = rest;
+                    value.to_vec()
+                }
+                2 => {
+                    let length = varint(&mut message) as usize;
+                    let (value, rest) = message.split_at(length);
+                    message = rest;
+                    value.to_vec()
+                }
+                _ => panic!("wire type {} of field {}", wire, number),
+            };
+            fields.push((number, wire, value));
+        }
+        fields
+    }
+
+    #[test]
+    fn messages_match_the_schema() {
+        let profile = Profile {
+            custom_fields: vec![
+                serde_json::from_value(json!({ "name": "region", "pick": ["eu", "us"] })).unwrap(),
+                serde_json::from_value(json!({ "name": "batch", "int_range": [0, 0] })).unwrap(),
+                serde_json::from_value(json!({ "name": "score", "constant": 0.0 })).unwrap(),
+                serde_json::from_value(json!({ "name": "flagged", "constant": false })).unwrap(),
+            ],
+            ..Profile::default()
+        };
+        let schema = message_fields(&proto_schema(&profile.custom_fields));
+        assert_eq!(schema.len(), FIELDS.len() + profile.custom_fields.len());
+
+        let mut rows: Vec<Transaction> =
+            seed::SeededRows::new(&profile, 786, 0).take(500).collect();
+        let custom = CustomFields::new(&profile.custom_fields, profile.amount_format).unwrap();
+        let mut rng = StdRng::seed_from_u64(786);
+        for tx in &mut rows {
+            custom.apply(&mut rng, tx);
+        }
+        // Optional fields that are zero or empty are still set
+        rows[0].installment_number = Some(0);
+        rows[0].installment_count = Some(0);
+        rows[0].refund_amount = Some(0.0);
+        rows[0].fx_rate = Some(-0.0);
+        rows[0].cvv = Some("".into());
+        rows[0].mcc = Some("".into());
+        rows[0].amount = 0.0;
+        rows[0].is_fraud = false;
+        rows[0].transaction_date = "1970-01-01T00:00:00+00:00".into();
+
+        let mut writer = ProtobufWriter::new(Vec::new());
+        for tx in &rows {
+            writer.write(tx).unwrap();
+        }
+        let mut stream = &writer.out[..];
+        let mut messages = Vec::new();
+        while !stream.is_empty() {
+            let length = varint(&mut stream) as usize;
+            let (message, rest) = stream.split_at(length);
+            messages.push(fields(message));
+            stream = rest;
+        }
+        assert_eq!(messages.len(), rows.len());
+
+        for (tx, message) in rows.iter().zip(&messages) {
+            let json = serde_json::to_value(tx).unwrap();
+            let numbers: Vec<u32> = message.iter().map(|(number, ..)| *number).collect();
+            assert!(numbers.is_sorted_by(|a, b| a < b), "{:?}", numbers);
+            for (number, wire, value) in message {
+                let (name, ty, _) = &schema[number];
+                assert_eq!(*wire, wire_type(ty), "wire type of {}", name);
+                match ty.as_str() {
+                    "string" => assert_eq!(
+                        json[name],
+                        json!(String::from_utf8(value.clone()).unwrap()),
+                        "{}",
+                        name
+                    ),
+                    "double" => assert_eq!(
+                        json[name],
+                        json!(f64::from_le_bytes(value[..].try_into().unwrap())),
+                        "{}",
+                        name
+                    ),
+                    "google.protobuf.Timestamp" => {
+                        let date = DateTime::parse_from_rfc3339(&tx.transaction_date).unwrap();
+                        let timestamp: HashMap<u32, u64> = fields(value)
+                            .into_iter()
+                            .map(|(n, wire, v)| {
+                                assert_eq!(wire, 0);
+                                (n, u64::from_le_bytes(v.try_into().unwrap()))
+                            })
+                            .collect();
+                        assert_eq!(
+                            timestamp.get(&1).copied().unwrap_or(0),
+                            date.timestamp() as u64
+                        );
+                        assert_eq!(
+                            timestamp.get(&2).copied().unwrap_or(0),
+                            date.timestamp_subsec_nanos() as u64
+                        );
+                    }
+                    _ => {}
+                }
+            }
+            // Every field that's set is written, apart from proto3 fields
+            // at their default
+            for (number, (name, ty, optional)) in &schema {
+                let value = json.get(name).unwrap_or(&Value::Null);
+                let default = match value {
+                    Value::Null => true,
+                    Value::String(text) => text.is_empty(),
+                    Value::Bool(flag) => !flag,
+                    Value::Number(number) => number.as_f64() == Some(0.0),
+                    _ => false,
+                };
+                let expected = !value.is_null()
+                    && (*optional || !default || ty == "google.protobuf.Timestamp");
+                assert_eq!(
+                    numbers.contains(number),
+                    expected,
+                    "field {} of {}",
+                    name,
+                    tx.transaction_id
+                );
+            }
+        }
+
+        let zeros: HashMap<u32, &Vec<u8>> = messages[0].iter().map(|(n, _, v)| (*n, v)).collect();
+        for name in [
+            "installment_number",
+            "installment_count",
+            "refund_amount",
+            "fx_rate",
+            "cvv",
+            "mcc",
+            "batch",
+            "score",
+            "flagged",
+        ] {
+            let (number, _) = schema
+                .iter()
+                .find(|(_, (field, ..))| field == name)
+                .unwrap();
+            assert!(zeros.contains_key(number), "{} is left out", name);
+        }
+        assert!(!zeros.contains_key(&13), "amount of 0 is written");
+        assert!(!zeros.contains_key(&23), "is_fraud of false is written");
+        assert_eq!(zeros[&3].len(), 0);
+    }
+}
@@ -0,0 +1,484 @@
+use super::{CSV_HEADER, Finish, Sink, TransactionWriter, csv_record, csv_writer, push_custom};
+use crate::{
+    config::{Profile, RelationalSchema},
+    model::Transaction,
+    money::AmountFormat,
+};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Weekday};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io,
+    path::Path,
+};
+
+// Columns of the customers table, keyed by `customer_id`
+const CUSTOMER_COLUMNS: [&str; 10] = [
+    "customer_id",
+    "cardholder_name",
+    "email",
+    "phone",
+    "billing_street",
+    "billing_city",
+    "billing_region",
+    "billing_postal_code",
+    "billing_country",
+    "persona",
+];
+
+// Columns of the cards table after its `card_id` key. The expiry date and
+// CVV stay with the transactions, as the credentials presented with each
+// payment.
+const CARD_COLUMNS: [&str; 6] = [
+    "customer_id",
+    "card_number",
+    "card_brand",
+    "issuer_bank",
+    "card_country",
+    "card_type",
+];
+
+// Columns of the merchants table, keyed by `merchant_id`
+const MERCHANT_COLUMNS: [&str; 7] = [
+    "merchant_id",
+    "merchant_name",
+    "statement_descriptor",
+    "merchant_category",
+    "mcc",
+    "acquirer_id",
+    "processor",
+];
+
+// Columns of the date dimension of the star schema
+const DATE_COLUMNS: [&str; 10] = [
+    "date_key",
+    "date",
+    "year",
+    "quarter",
+    "month",
+    "month_name",
+    "day_of_month",
+    "day_of_week",
+    "day_name",
+    "is_weekend",
+];
+
+// A column of the transactions table
+#[derive(Clone, Copy)]
+enum Column {
+    // The column of `CSV_HEADER` at this index
+    Field(usize),
+    // The profile's custom field at this index
+    Custom(usize),
+    CustomerKey,
+    CardKey,
+    MerchantKey,
+    DateKey,
+}
+// Writes a dataset as CSV tables in a directory of its own.
+//
+// The normalized schema has `customers.csv`, `cards.csv`, `merchants.csv`
+// and `transactions.csv`, whose rows point at the others through
+// `customer_id`, `card_id` and `merchant_id`. The star schema has
+// `dim_customer.csv`, `dim_card.csv`, `dim_merchant.csv` and `dim_date.csv`
+// around `fact_transactions.csv`, joined on integer surrogate keys numbered
+// from 1, as a warehouse load would assign them; the natural IDs stay on
+// the dimensions. Customers, cards and merchants are written the first time
+// a transaction refers to them, so every foreign key joins and no entity is
+// listed without transactions. The date dimension covers every day from the
+// first to the last transaction, by the date local to each transaction.
+//
+// When customers move or merchants rebrand, their tables keep a row for
+// each version (SCD Type 2), effective from the first transaction made
+// with it until the next version took over. Those rows are written once the
+// output is finished, when every version is known.
+pub struct RelationalWriter {
+    // Taken once the output has been finished
+    tables: Option<Tables>,
+    amount_format: AmountFormat,
+    customers: Dimension,
+    cards: Dimension,
+    merchants: Dimension,
+    transaction_columns: Vec<Column>,
+    // Whether transaction times are needed, for the date dimension or the
+    // effective dates of versions
+    timed: bool,
+    // First and last transaction dates of the star schema
+    dates: Option<(NaiveDate, NaiveDate)>,
+    // Names of the profile's custom fields, kept on the transactions table
+    custom: Vec<String>,
+    record: csv::StringRecord,
+    scratch: String,
+    // Bytes of the tables, once they've been finished
+    bytes: u64,
+}
+
+struct Tables {
+    customers: csv::Writer<Sink>,
+    cards: csv::Writer<Sink>,
+    merchants: csv::Writer<Sink>,
+    transactions: csv::Writer<Sink>,
+    // The star schema's date dimension, written when the output is finished
+    dates: Option<csv::Writer<Sink>>,
+}
+
+// The customers, cards or merchants referred to by transactions
+struct Dimension {
+    // Indices into `CSV_HEADER` of the table's columns, and of those that
+    // tell its rows apart
+    columns: Vec<usize>,
+    identity: Vec<usize>,
+    // Key written before the columns, made from the number of the row
+    key: Option<fn(usize) -> String>,
+    // Number of each row by its identity, counting from 1
+    numbers: HashMap<Vec<String>, usize>,
+    // Every version of each row with the time it was first seen, in number
+    // order, when rows are versioned
+    versions: Option<Vec<Version>>,
+}
+
+struct Version {
+    // Values of the columns, the first being the natural ID
+    fields: Vec<String>,
+    since: DateTime<FixedOffset>,
+}
+
+impl Dimension {
+    // A dimension identified by the given columns, or by all of its columns
+    // when versioned
+    fn new(
+        columns: &[&str],
+        identity: &[&str],
+        key: Option<fn(usize) -> String>,
+        versioned: bool,
+    ) -> Self {
+        let indices = |names: &[&str]| -> Vec<usize> {
+            names
+                .iter()
+                .map(|name| CSV_HEADER.iter().position(|c| c == name).unwrap())
+                .collect()
+        };
+        Self {
+            columns: indices(columns),
+            identity: indices(if versioned { columns } else { identity }),
+            key,
+            numbers: HashMap::new(),
+            versions: versioned.then(Vec::new),
+        }
+    }
+
+    // Columns of the table, led by the key if it has one and followed by
+    // the effective dates of versions
+    fn header<'a>(&self, key: &'a str, columns: &[&'a str]) -> Vec<&'a str> {
+        let mut header: Vec<&str> = self.key.map(|_| key).into_iter().collect();
+        header.extend(columns);
+        if self.versions.is_some() {
+            header.extend(["effective_from", "effective_to", "is_current"]);
+        }
+        header
+    }
+
+    // Number of the row a transaction refers to. Rows are written the first
+    // time they are seen, unless versioned; versions keep the earliest
+    // transaction time they were seen at.
+    fn number(
+        &mut self,
+        record: &csv::StringRecord,
+        time: Option<DateTime<FixedOffset>>,
+        table: &mut csv::Writer<Sink>,
+    ) -> io::Result<usize> {
+        let identity: Vec<String> = self
+            .identity
+            .iter()
+            .map(|&i| record[i].to_string())
+            .collect();
+        if let Some(&number) = self.numbers.get(&identity) {
+            if let Some(versions) = self.versions.as_mut()
+                && let Some(time) = time
+            {
+                let version = &mut versions[number - 1];
+                version.since = version.since.min(time);
+            }
+            return Ok(number);
+        }
+        let number = self.numbers.len() + 1;
+        match self.versions.as_mut() {
+            Some(versions) => versions.push(Version {
+                fields: identity.clone(),
+                since: time.expect("versioned rows are timed"),
+            }),
+            None => {
+                let key = self.key.map(|key| key(number));
+                table.write_record(
+                    key.as_deref()
+                        .into_iter()
+                        .chain(self.columns.iter().map(|&i| &record[i])),
+                )?;
+            }
+        }
+        self.numbers.insert(identity, number);
+        Ok(number)
+    }
+
+    // Write every version, each effective until the next version of the
+    // same ID took over; the last one is current
+    fn write_versions(&self, table: &mut csv::Writer<Sink>) -> io::Result<()> {
+        let Some(versions) = &self.versions else {
+            return Ok(());
+        };
+        let mut order: Vec<usize> = (0..versions.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&versions[a], &versions[b]);
+            a.fields[0].cmp(&b.fields[0]).then(a.since.cmp(&b.since))
+        });
+        let mut until = vec![None; versions.len()];
+        for pair in order.windows(2) {
+            if versions[pair[0]].fields[0] == versions[pair[1]].fields[0] {
+                until[pair[0]] = Some(versions[pair[1]].since);
+            }
+        }
+        for (i, version) in versions.iter().enumerate() {
+            let key = self.key.map(|key| key(i + 1));
+            let since = version.since.to_rfc3339();
+            let until_text = until[i].map_or_else(String::new, |until| until.to_rfc3339());
+            let current = until[i].is_none().to_string();
+            table.write_record(key.iter().chain(&version.fields).chain([
+                &since,
+                &until_text,
+                &current,
+            ]))?;
+        }
+        Ok(())
+    }
+}
+
+impl RelationalWriter {
+    pub fn new(dir: &Path, profile: &Profile) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let table = |name: &str| -> io::Result<csv::Writer<Sink>> {
+            let mut file_name = format!("{}.csv", name);
+            if let Some(compression) = profile.compression {
+                file_name = format!("{}.{}", file_name, compression.extension());
+            }
+            let file = File::create(dir.join(file_name))?;
+            Ok(csv_writer(
+                Sink::new(file, profile.compression)?,
+                &profile.csv,
+            ))
+        };
+        let star = profile.relational.schema == RelationalSchema::Star;
+        let mut tables = if star {
+            Tables {
+                customers: table("dim_customer")?,
+                cards: table("dim_card")?,
+                merchants: table("dim_merchant")?,
+                transactions: table("fact_transactions")?,
+                dates: Some(table("dim_date")?),
+            }
+        } else {
+            Tables {
+                customers: table("customers")?,
+                cards: table("cards")?,
+                merchants: table("merchants")?,
+                transactions: table("transactions")?,
+                dates: None,
+            }
+        };
+
+        // Star dimensions lead with surrogate keys, and star cards hang off
+        // the fact table rather than the customer
+        let surrogate: Option<fn(usize) -> String> = star.then_some(|number| number.to_string());
+        let card_id: fn(usize) -> String = if star {
+            |number| number.to_string()
+        } else {
+            |number| format!("CRD{:09}", number)
+        };
+        let card_columns = if star {
+            &CARD_COLUMNS[1..]
+        } else {
+            &CARD_COLUMNS[..]
+        };
+        let customers = Dimension::new(
+            &CUSTOMER_COLUMNS,
+            &CUSTOMER_COLUMNS[..1],
+            surrogate,
+            profile.scd.address_change_rate > 0.0,
+        );
+        let cards = Dimension::new(card_columns, &CARD_COLUMNS[..2], Some(card_id), false);
+        let merchants = Dimension::new(
+            &MERCHANT_COLUMNS,
+            &MERCHANT_COLUMNS[..1],
+            surrogate,
+            profile.scd.merchant_rename_rate > 0.0,
+        );
+
+        // Transactions keep their own columns and the keys of the others
+        let moved: Vec<&str> = CUSTOMER_COLUMNS[1..]
+            .iter()
+            .chain(&CARD_COLUMNS[2..])
+            .chain(&MERCHANT_COLUMNS[1..])
+            .copied()
+            .collect();
+        let mut transaction_columns = Vec::new();
+        for (i, name) in CSV_HEADER.iter().enumerate() {
+            match *name {
+                "card_number" => transaction_columns.push(Column::CardKey),
+                "customer_id" if star => transaction_columns.push(Column::CustomerKey),
+                "merchant_id" if star => transaction_columns.push(Column::MerchantKey),
+                "transaction_date" if star => {
+                    transaction_columns.push(Column::Field(i));
+                    transaction_columns.push(Column::DateKey);
+                }
+                name if !moved.contains(&name) => transaction_columns.push(Column::Field(i)),
+                _ => {}
+            }
+        }
+        let custom: Vec<String> = profile
+            .custom_fields
+            .iter()
+            .map(|f| f.name.clone())
+            .collect();
+        transaction_columns.extend((0..custom.len()).map(Column::Custom));
+
+        if profile.csv.header {
+            let card_key = if star { "card_key" } else { "card_id" };
+            tables
+                .customers
+                .write_record(customers.header("customer_key", &CUSTOMER_COLUMNS))?;
+            tables
+                .cards
+                .write_record(cards.header(card_key, card_columns))?;
+            tables
+                .merchants
+                .write_record(merchants.header("merchant_key", &MERCHANT_COLUMNS))?;
+            tables
+                .transactions
+                .write_record(transaction_columns.iter().map(|column| match column {
+                    Column::Field(i) => CSV_HEADER[*i],
+                    Column::Custom(i) => custom[*i].as_str(),
+                    Column::CustomerKey => "customer_key",
+                    Column::CardKey => card_key,
+                    Column::MerchantKey => "merchant_key",
+                    Column::DateKey => "date_key",
+                }))?;
+            if let Some(dates) = tables.dates.as_mut() {
+                dates.write_record(DATE_COLUMNS)?;
+            }
+        }
+        let timed = star || customers.versions.is_some() || merchants.versions.is_some();
+        Ok(Self {
+            tables: Some(tables),
+            amount_format: profile.amount_format,
+            customers,
+            cards,
+            merchants,
+            transaction_columns,
+            timed,
+            dates: None,
+            custom,
+            record: csv::StringRecord::new(),
+            scratch: String::new(),
+            bytes: 0,
+        })
+    }
+}
+
+impl TransactionWriter for RelationalWriter {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        let Some(tables) = self.tables.as_mut() else {
+            return Err(io::Error::other("relational output already finished"));
+        };
+        csv_record(tx, self.amount_format, &mut self.record, &mut self.scratch);
+        push_custom(tx, &self.custom, &mut self.record);
+        let record = &self.record;
+        let time = if self.timed {
+            Some(
+                DateTime::parse_from_rfc3339(&tx.transaction_date)
+                    .map_err(|e| io::Error::other(format!("invalid transaction date: {}", e)))?,
+            )
+        } else {
+            None
+        };
+
+        let customer = self.customers.number(record, time, &mut tables.customers)?;
+        let card = self.cards.number(record, time, &mut tables.cards)?;
+        let merchant = self.merchants.number(record, time, &mut tables.merchants)?;
+
+        let mut date_key = String::new();
+        if let Some(dates) = tables.dates.as_ref().and(time) {
+            let date = dates.date_naive();
+            self.dates = Some(match self.dates {
+                Some((first, last)) => (first.min(date), last.max(date)),
+                None => (date, date),
+            });
+            date_key = date.format("%Y%m%d").to_string();
+        }
+        let keys = [
+            customer.to_string(),
+            self.cards.key.map_or_else(String::new, |key| key(card)),
+            merchant.to_string(),
+        ];
+        tables
+            .transactions
+            .write_record(self.transaction_columns.iter().map(|column| match column {
+                Column::Field(i) => &record[*i],
+                Column::Custom(i) => &record[CSV_HEADER.len() + *i],
+                Column::CustomerKey => keys[0].as_str(),
+                Column::CardKey => keys[1].as_str(),
+                Column::MerchantKey => keys[2].as_str(),
+                Column::DateKey => date_key.as_str(),
+            }))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let Some(mut tables) = self.tables.take() else {
+            return Ok(());
+        };
+        self.customers.write_versions(&mut tables.customers)?;
+        self.merchants.write_versions(&mut tables.merchants)?;
+        if let Some(dates) = tables.dates.as_mut()
+            && let Some((first, last)) = self.dates
+        {
+            for date in first.iter_days().take_while(|date| *date <= last) {
+                dates.write_record(date_row(date))?;
+            }
+        }
+        for table in [
+            Some(tables.customers),
+            Some(tables.cards),
+            Some(tables.merchants),
+            Some(tables.transactions),
+            tables.dates,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let mut sink = table.into_inner().map_err(|e| e.into_error())?;
+            sink.finish()?;
+            self.bytes += sink.bytes();
+        }
+        Ok(())
+    }
+
+    fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+// A row of the date dimension, with ISO weekdays numbered from Monday
+fn date_row(date: NaiveDate) -> [String; 10] {
+    let weekday = date.weekday();
+    [
+        date.format("%Y%m%d").to_string(),
+        date.format("%Y-%m-%d").to_string(),
+        date.year().to_string(),
+        ((date.month() - 1) / 3 + 1).to_string(),
+        date.month().to_string(),
+        date.format("%B").to_string(),
+        date.day().to_string(),
+        weekday.number_from_monday().to_string(),
+        date.format("%A").to_string(),
+        matches!(weekday, Weekday::Sat | Weekday::Sun).to_string(),
+    ]
+}
@@ -0,0 +1,130 @@
+use super::{Columns, proto_schema, sql, transaction_schema};
+use crate::{config::Profile, money::AmountFormat};
+use serde_json::{Map, Value, json};
+use std::io;
+
+// Schemas that `luhnsynth schema` prints, each generated from the code that
+// writes the matching output so they can't drift apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaFormat {
+    // The .proto definition of protobuf output
+    Proto,
+    // The Avro schema embedded in Avro output
+    Avro,
+    // JSON Schema of a row of JSON and JSON Lines output
+    Jsonschema,
+    // Arrow schema of Parquet output
+    Arrow,
+    // CREATE TABLE statement of SQL and SQLite output
+    Ddl,
+}
+
+// Amount fields, written as whole minor units with `--amount-format
+// minor-units`
+const AMOUNT_FIELDS: [&str; 5] = [
+    "amount",
+    "refund_amount",
+    "remaining_amount",
+    "original_amount",
+    "billing_amount",
+];
+
+// The schema in a format, for rows generated with the profile: its custom
+// fields and, where the format honours them, its amount format and columns
+pub fn schema(format: SchemaFormat, profile: &Profile) -> io::Result<String> {
+    match format {
+        SchemaFormat::Proto => Ok(proto_schema(&profile.custom_fields)),
+        SchemaFormat::Avro => {
+            let schema = transaction_schema(&profile.custom_fields);
+            Ok(format!("{}\n", serde_json::to_string_pretty(&schema)?))
+        }
+        SchemaFormat::Jsonschema => {
+            let schema = json_schema(profile);
+            Ok(format!("{}\n", serde_json::to_string_pretty(&schema)?))
+        }
+        #[cfg(feature = "parquet")]
+        SchemaFormat::Arrow => {
+            let schema = super::parquet::transaction_schema(&profile.custom_fields);
+            Ok(format!("{}\n", serde_json::to_string_pretty(&schema)?))
+        }
+        #[cfg(not(feature = "parquet"))]
+        SchemaFormat::Arrow => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the arrow schema requires building with `--features parquet`",
+        )),
+        SchemaFormat::Ddl => Ok(sql::create_table(
+            &profile.sql.table,
+            &sql::masked_types(&profile.masking),
+            &profile.custom_fields,
+            &Columns::new(profile),
+        )),
+    }
+}
+
+// JSON Schema of a row of JSON output, translated from the Avro schema. Every
+// key is present in every row, with null for absent optional fields.
+fn json_schema(profile: &Profile) -> Value {
+    let avro = transaction_schema(&profile.custom_fields);
+    let fields: Vec<(&str, &Value)> = avro["fields"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|field| Some((field["name"].as_str()?, &field["type"])))
+        .collect();
+    let columns = Columns::new(profile);
+    let mut properties = Map::new();
+    for (i, name) in columns.iter() {
+        let field = columns.field(i);
+        let Some((_, avro)) = fields.iter().find(|(f, _)| *f == field) else {
+            continue;
+        };
+        let minor_units =
+            profile.amount_format == AmountFormat::MinorUnits && AMOUNT_FIELDS.contains(&field);
+        properties.insert(name.to_string(), json_type(avro, minor_units));
+    }
+    let required: Vec<&String> = properties.keys().collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": "https://github.com/Xenith-Quantumweather/luhnsynth/transaction.schema.json",
+        "title": "Transaction",
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+// JSON Schema of a value of an Avro type, as JSON output writes it
+fn json_type(avro: &Value, minor_units: bool) -> Value {
+    match avro {
+        // Optional fields are unions of null and their type
+        Value::Array(union) => {
+            let schema = union.iter().find(|t| *t != "null").unwrap_or(&Value::Null);
+            let mut schema = json_type(schema, minor_units);
+            if let Some(kind) = schema.get("type").cloned() {
+                schema["type"] = json!([kind, "null"]);
+            }
+            if let Some(symbols) = schema.get_mut("enum").and_then(Value::as_array_mut) {
+                symbols.push(Value::Null);
+            }
+            schema
+        }
+        Value::Object(object) => match (object.get("type"), object.get("logicalType")) {
+            (_, Some(_)) => json!({ "type": "string", "format": "date-time" }),
+            (Some(kind), _) if kind == "enum" => {
+                json!({ "type": "string", "enum": object["symbols"] })
+            }
+            (Some(kind), _) => json_type(kind, minor_units),
+            (None, _) => json!({}),
+        },
+        Value::String(kind) => match kind.as_str() {
+            "string" => json!({ "type": "string" }),
+            "int" | "long" => json!({ "type": "integer" }),
+            "double" if minor_units => json!({ "type": "integer" }),
+            "double" => json!({ "type": "number" }),
+            "boolean" => json!({ "type": "boolean" }),
+            _ => json!({}),
+        },
+        _ => json!({}),
+    }
+}
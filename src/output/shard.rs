@@ -0,0 +1,108 @@
+use super::{TransactionWriter, create_writer, file_name};
+use crate::{
+    config::{OutputFormat, Profile},
+    model::Transaction,
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+// Writes a dataset as a numbered series of files of at most `max_rows` rows
+// each, starting the next file once one is full, so loaders never face one
+// giant file. A file is finished as soon as it's full, so every file before
+// the one being written is complete.
+pub struct ShardedWriter {
+    format: OutputFormat,
+    dir: PathBuf,
+    // File name of each shard, without extensions, from its index
+    stem: Box<dyn Fn(usize) -> String + Send>,
+    max_rows: usize,
+    // Options each shard is written with
+    profile: Profile,
+    // The shard being written, until it's full
+    current: Option<Box<dyn TransactionWriter>>,
+    rows: usize,
+    // Index of the next shard to start
+    shards: usize,
+    // Reports of the shards finished so far
+    summaries: Vec<String>,
+    // Bytes of the shards already finished
+    bytes: u64,
+}
+
+impl ShardedWriter {
+    // Start writing at the shard with the given index, after the shards an
+    // earlier run completed
+    pub fn new(
+        format: OutputFormat,
+        dir: &Path,
+        stem: impl Fn(usize) -> String + Send + 'static,
+        max_rows: usize,
+        first: usize,
+        profile: &Profile,
+    ) -> io::Result<Self> {
+        let profile = Profile {
+            partition_by: None,
+            max_rows_per_file: None,
+            ..profile.clone()
+        };
+        let path = dir.join(file_name(&stem(first), format, &profile));
+        Ok(Self {
+            format,
+            dir: dir.to_path_buf(),
+            current: Some(create_writer(format, &path, &profile)?),
+            stem: Box::new(stem),
+            max_rows,
+            profile,
+            rows: 0,
+            shards: first + 1,
+            summaries: Vec::new(),
+            bytes: 0,
+        })
+    }
+}
+
+impl TransactionWriter for ShardedWriter {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        let current = match &mut self.current {
+            Some(current) => current,
+            None => {
+                let name = file_name(&(self.stem)(self.shards), self.format, &self.profile);
+                self.shards += 1;
+                self.current.insert(create_writer(
+                    self.format,
+                    &self.dir.join(name),
+                    &self.profile,
+                )?)
+            }
+        };
+        current.write(tx)?;
+        self.rows += 1;
+        if self.rows == self.max_rows {
+            current.finish()?;
+            self.summaries.extend(current.summary());
+            self.bytes += current.bytes();
+            self.current = None;
+            self.rows = 0;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        match &mut self.current {
+            Some(current) => current.finish(),
+            None => Ok(()),
+        }
+    }
+
+    fn summary(&self) -> Option<String> {
+        let mut summaries = self.summaries.clone();
+        summaries.extend(self.current.as_ref().and_then(|current| current.summary()));
+        (!summaries.is_empty()).then(|| summaries.concat())
+    }
+
+    fn bytes(&self) -> u64 {
+        self.bytes + self.current.as_ref().map_or(0, |current| current.bytes())
+    }
+}
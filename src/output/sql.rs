@@ -0,0 +1,561 @@
+use super::{Columns, Finish, TransactionWriter};
+use crate::{
+    config::{SqlOptions, SqlStyle},
+    custom::{CustomField, CustomType},
+    masking::{MaskMode, MaskingConfig},
+    model::Transaction,
+    money::Money,
+};
+use std::io::{self, Write};
+
+// Columns of the transactions table with their standard SQL types, in
+// `Transaction` field order. Transaction IDs repeat across lifecycle events,
+// so there is no primary key.
+pub(crate) const COLUMNS: [(&str, &str); 86] = [
+    ("transaction_id", "VARCHAR(32) NOT NULL"),
+    ("event_type", "VARCHAR(16)"),
+    ("transaction_date", "TIMESTAMP WITH TIME ZONE NOT NULL"),
+    ("status", "VARCHAR(16) NOT NULL"),
+    ("decline_reason", "VARCHAR(32)"),
+    ("response_code", "CHAR(2) NOT NULL"),
+    ("auth_code", "CHAR(6)"),
+    ("original_transaction_id", "VARCHAR(32)"),
+    ("subscription_id", "CHAR(12)"),
+    ("initiator", "CHAR(3)"),
+    ("installment_number", "SMALLINT"),
+    ("installment_count", "SMALLINT"),
+    ("order_reference", "CHAR(12)"),
+    ("preauth_step", "VARCHAR(11)"),
+    ("customer_id", "VARCHAR(32) NOT NULL"),
+    ("cardholder_name", "VARCHAR(255) NOT NULL"),
+    ("email", "VARCHAR(254) NOT NULL"),
+    ("phone", "VARCHAR(16) NOT NULL"),
+    ("card_number", "VARCHAR(19) NOT NULL"),
+    ("card_brand", "VARCHAR(64) NOT NULL"),
+    ("card_expiry", "CHAR(5) NOT NULL"),
+    ("cvv", "VARCHAR(4)"),
+    ("issuer_bank", "VARCHAR(255)"),
+    ("card_country", "CHAR(2)"),
+    ("card_type", "VARCHAR(16)"),
+    ("billing_street", "VARCHAR(255) NOT NULL"),
+    ("billing_city", "VARCHAR(64) NOT NULL"),
+    ("billing_region", "VARCHAR(64) NOT NULL"),
+    ("billing_postal_code", "VARCHAR(16) NOT NULL"),
+    ("billing_country", "CHAR(2) NOT NULL"),
+    ("shipping_street", "VARCHAR(255)"),
+    ("shipping_city", "VARCHAR(64)"),
+    ("shipping_region", "VARCHAR(64)"),
+    ("shipping_postal_code", "VARCHAR(16)"),
+    ("shipping_country", "CHAR(2)"),
+    ("amount", "NUMERIC(15, 3) NOT NULL"),
+    ("refund_amount", "NUMERIC(15, 3)"),
+    ("remaining_amount", "NUMERIC(15, 3)"),
+    ("currency", "CHAR(3) NOT NULL"),
+    ("original_amount", "NUMERIC(15, 3)"),
+    ("original_currency", "CHAR(3)"),
+    ("billing_amount", "NUMERIC(15, 3)"),
+    ("billing_currency", "CHAR(3)"),
+    ("fx_rate", "NUMERIC(18, 6)"),
+    ("fx_markup", "NUMERIC(5, 2)"),
+    ("merchant_name", "VARCHAR(255) NOT NULL"),
+    ("merchant_id", "VARCHAR(32) NOT NULL"),
+    ("statement_descriptor", "VARCHAR(64) NOT NULL"),
+    ("merchant_category", "VARCHAR(255) NOT NULL"),
+    ("mcc", "CHAR(4)"),
+    ("acquirer_id", "VARCHAR(11) NOT NULL"),
+    ("processor", "VARCHAR(32) NOT NULL"),
+    ("batch_id", "VARCHAR(64)"),
+    ("settlement_date", "DATE"),
+    ("payment_method", "VARCHAR(32) NOT NULL"),
+    ("channel", "VARCHAR(11) NOT NULL"),
+    ("pos_entry_mode", "CHAR(2) NOT NULL"),
+    ("terminal_id", "CHAR(8)"),
+    ("track1", "VARCHAR(79)"),
+    ("track2", "VARCHAR(40)"),
+    ("emv_aid", "VARCHAR(32)"),
+    ("emv_cryptogram", "CHAR(16)"),
+    ("emv_tvr", "CHAR(10)"),
+    ("emv_atc", "CHAR(4)"),
+    ("wallet", "VARCHAR(11)"),
+    ("wallet_id", "CHAR(3)"),
+    ("token_pan", "VARCHAR(19)"),
+    ("token_requestor_id", "CHAR(11)"),
+    ("three_ds_version", "CHAR(3)"),
+    ("eci", "CHAR(2)"),
+    ("authentication_result", "VARCHAR(12)"),
+    ("cavv", "CHAR(28)"),
+    ("avs_result", "CHAR(1)"),
+    ("cvv_result", "CHAR(1)"),
+    ("ip_address", "VARCHAR(45)"),
+    ("ip_country", "CHAR(2)"),
+    ("device_id", "VARCHAR(32)"),
+    ("device_platform", "VARCHAR(16)"),
+    ("device_os", "VARCHAR(32)"),
+    ("device_browser", "VARCHAR(32)"),
+    ("screen_resolution", "VARCHAR(16)"),
+    ("device_fingerprint", "CHAR(32)"),
+    ("user_agent", "TEXT"),
+    ("is_fraud", "BOOLEAN NOT NULL"),
+    ("fraud_pattern", "VARCHAR(32)"),
+    ("persona", "VARCHAR(32)"),
+];
+
+// A column value of a transaction row
+#[derive(Clone, Copy)]
+pub(crate) enum Value<'a> {
+    Null,
+    Text(&'a str),
+    // RFC 3339 timestamp
+    Timestamp(&'a str),
+    Integer(u32),
+    // Whole number of a custom field
+    Long(i64),
+    // Number with a fraction of a custom field
+    Float(f64),
+    // Written with as many decimals as its currency has
+    Amount(Money),
+    // Exchange rate, to 6 decimals
+    Rate(f64),
+    // Percentage, to 2 decimals
+    Percent(f64),
+    Bool(bool),
+}
+
+// Width of the hex SHA-256 digests that hashed fields are written as
+const DIGEST_WIDTH: usize = 64;
+
+// Types of the columns that hashed fields outgrow, as overrides for
+// `create_table`. Digests are 64 hex characters whatever they hash, while
+// the other masking modes keep values as long as they were.
+pub(crate) fn masked_types(masking: &MaskingConfig) -> Vec<(&'static str, &'static str)> {
+    masking
+        .modes()
+        .into_iter()
+        .filter(|(_, mode)| *mode == MaskMode::Sha256)
+        // Network tokens are masked as card numbers are
+        .flat_map(|(field, _)| match field {
+            "card_number" => vec!["card_number", "token_pan"],
+            field => vec![field],
+        })
+        .filter_map(|field| {
+            let (_, ty) = COLUMNS.iter().find(|(name, _)| *name == field)?;
+            let wide = if ty.ends_with("NOT NULL") {
+                "VARCHAR(64) NOT NULL"
+            } else {
+                "VARCHAR(64)"
+            };
+            (width(ty)? < DIGEST_WIDTH).then_some((field, wide))
+        })
+        .collect()
+}
+
+// Length of a `CHAR(n)` or `VARCHAR(n)` type
+fn width(ty: &str) -> Option<usize> {
+    let (name, rest) = ty.split_once('(')?;
+    if name != "CHAR" && name != "VARCHAR" {
+        return None;
+    }
+    rest.split_once(')')?.0.parse().ok()
+}
+
+// The row of a transaction, in `COLUMNS` order
+pub(crate) fn values(tx: &Transaction) -> [Value<'_>; 86] {
+    fn text(value: Option<&str>) -> Value<'_> {
+        value.map_or(Value::Null, Value::Text)
+    }
+    let amount = |amount: f64, currency: &str| Value::Amount(Money::from_major(amount, currency));
+    let billing_currency = tx.billing_currency.as_deref().unwrap_or(&tx.currency);
+    [
+        Value::Text(&tx.transaction_id),
+        text(tx.event_type.as_ref().map(|e| e.as_str())),
+        Value::Timestamp(&tx.transaction_date),
+        Value::Text(tx.status.as_str()),
+        text(tx.decline_reason.as_ref().map(|r| r.as_str())),
+        Value::Text(&tx.response_code),
+        text(tx.auth_code.as_deref()),
+        text(tx.original_transaction_id.as_deref()),
+        text(tx.subscription_id.as_deref()),
+        text(tx.initiator.as_ref().map(|i| i.as_str())),
+        tx.installment_number.map_or(Value::Null, Value::Integer),
+        tx.installment_count.map_or(Value::Null, Value::Integer),
+        text(tx.order_reference.as_deref()),
+        text(tx.preauth_step.as_ref().map(|s| s.as_str())),
+        Value::Text(&tx.customer_id),
+        Value::Text(&tx.cardholder_name),
+        Value::Text(&tx.email),
+        Value::Text(&tx.phone),
+        Value::Text(&tx.card_number),
+        Value::Text(&tx.card_brand),
+        Value::Text(&tx.card_expiry),
+        text(tx.cvv.as_deref()),
+        text(tx.issuer_bank.as_deref()),
+        text(tx.card_country.as_deref()),
+        text(tx.card_type.as_ref().map(|t| t.as_str())),
+        Value::Text(&tx.billing_street),
+        Value::Text(&tx.billing_city),
+        Value::Text(&tx.billing_region),
+        Value::Text(&tx.billing_postal_code),
+        Value::Text(&tx.billing_country),
+        text(tx.shipping_street.as_deref()),
+        text(tx.shipping_city.as_deref()),
+        text(tx.shipping_region.as_deref()),
+        text(tx.shipping_postal_code.as_deref()),
+        text(tx.shipping_country.as_deref()),
+        amount(tx.amount, &tx.currency),
+        tx.refund_amount
+            .map_or(Value::Null, |a| amount(a, &tx.currency)),
+        tx.remaining_amount
+            .map_or(Value::Null, |a| amount(a, &tx.currency)),
+        Value::Text(&tx.currency),
+        tx.original_amount
+            .map_or(Value::Null, |a| amount(a, &tx.currency)),
+        text(tx.original_currency.as_deref()),
+        tx.billing_amount
+            .map_or(Value::Null, |a| amount(a, billing_currency)),
+        text(tx.billing_currency.as_deref()),
+        tx.fx_rate.map_or(Value::Null, Value::Rate),
+        tx.fx_markup.map_or(Value::Null, Value::Percent),
+        Value::Text(&tx.merchant_name),
+        Value::Text(&tx.merchant_id),
+        Value::Text(&tx.statement_descriptor),
+        Value::Text(&tx.merchant_category),
+        text(tx.mcc.as_deref()),
+        Value::Text(&tx.acquirer_id),
+        Value::Text(&tx.processor),
+        text(tx.batch_id.as_deref()),
+        text(tx.settlement_date.as_deref()),
+        Value::Text(&tx.payment_method),
+        Value::Text(tx.channel.as_str()),
+        Value::Text(&tx.pos_entry_mode),
+        text(tx.terminal_id.as_deref()),
+        text(tx.track1.as_deref()),
+        text(tx.track2.as_deref()),
+        text(tx.emv_aid.as_deref()),
+        text(tx.emv_cryptogram.as_deref()),
+        text(tx.emv_tvr.as_deref()),
+        text(tx.emv_atc.as_deref()),
+        text(tx.wallet.as_ref().map(|w| w.as_str())),
+        text(tx.wallet_id.as_deref()),
+        text(tx.token_pan.as_deref()),
+        text(tx.token_requestor_id.as_deref()),
+        text(tx.three_ds_version.as_deref()),
+        text(tx.eci.as_deref()),
+        text(tx.authentication_result.as_ref().map(|r| r.as_str())),
+        text(tx.cavv.as_deref()),
+        text(tx.avs_result.as_deref()),
+        text(tx.cvv_result.as_deref()),
+        text(tx.ip_address.as_deref()),
+        text(tx.ip_country.as_deref()),
+        text(tx.device_id.as_deref()),
+        text(tx.device_platform.as_deref()),
+        text(tx.device_os.as_deref()),
+        text(tx.device_browser.as_deref()),
+        text(tx.screen_resolution.as_deref()),
+        text(tx.device_fingerprint.as_deref()),
+        text(tx.user_agent.as_deref()),
+        Value::Bool(tx.is_fraud),
+        text(tx.fraud_pattern.as_ref().map(|p| p.as_str())),
+        text(tx.persona.as_deref()),
+    ]
+}
+
+// The custom fields of a row, in the order of their names
+pub(crate) fn custom_values<'a>(
+    tx: &'a Transaction,
+    names: &'a [String],
+) -> impl Iterator<Item = Value<'a>> + 'a {
+    names.iter().map(|name| match tx.custom.get(name) {
+        None | Some(serde_json::Value::Null) => Value::Null,
+        Some(serde_json::Value::String(text)) => Value::Text(text),
+        Some(serde_json::Value::Bool(flag)) => Value::Bool(*flag),
+        Some(serde_json::Value::Number(number)) => match number.as_i64() {
+            Some(number) => Value::Long(number),
+            None => Value::Float(number.as_f64().unwrap_or_default()),
+        },
+        // Custom fields only hold scalars
+        Some(_) => Value::Null,
+    })
+}
+
+// The values of a row's columns, in column order
+pub(crate) fn row_values<'a>(tx: &'a Transaction, columns: &'a Columns) -> Vec<Value<'a>> {
+    let row = values(tx)
+        .into_iter()
+        .chain(custom_values(tx, columns.custom()));
+    if columns.is_all() {
+        return row.collect();
+    }
+    let row: Vec<Value> = row.collect();
+    columns.pick(&row).copied().collect()
+}
+
+// CREATE TABLE statement for the transactions table, with column types
+// replaced for databases that lack the standard one. Overrides are keyed by
+// field name, whatever name the column is written under.
+pub(crate) fn create_table(
+    table: &str,
+    overrides: &[(&str, &str)],
+    custom_fields: &[CustomField],
+    columns: &Columns,
+) -> String {
+    let custom = custom_fields.iter().map(|field| {
+        let ty = match field.kind() {
+            CustomType::Text => "TEXT",
+            CustomType::Integer => "BIGINT",
+            CustomType::Float => "DOUBLE PRECISION",
+            CustomType::Boolean => "BOOLEAN",
+        };
+        (field.name.as_str(), ty)
+    });
+    let types: Vec<&str> = COLUMNS
+        .iter()
+        .copied()
+        .chain(custom)
+        .map(|(field, ty)| {
+            overrides
+                .iter()
+                .find(|(column, _)| *column == field)
+                .map_or(ty, |(_, ty)| *ty)
+        })
+        .collect();
+    let definitions: Vec<String> = columns
+        .iter()
+        .map(|(i, name)| format!("    {} {}", name, types[i]))
+        .collect();
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (\n{}\n);\n",
+        table,
+        definitions.join(",\n")
+    )
+}
+
+// Writes a SQL script that creates the transactions table and loads the rows
+// in a single transaction, ready for `psql -f` or similar
+pub struct SqlWriter<W: Finish> {
+    out: W,
+    options: SqlOptions,
+    columns: Columns,
+    // Rows written in the current INSERT statement or COPY block
+    pending: usize,
+}
+
+impl<W: Finish> SqlWriter<W> {
+    pub fn new(
+        mut out: W,
+        options: &SqlOptions,
+        custom_fields: &[CustomField],
+        masking: &MaskingConfig,
+        columns: Columns,
+    ) -> io::Result<Self> {
+        write!(
+            out,
+            "{}\nBEGIN;\n",
+            create_table(
+                &options.table,
+                &masked_types(masking),
+                custom_fields,
+                &columns
+            )
+        )?;
+        Ok(Self {
+            out,
+            options: options.clone(),
+            columns,
+            pending: 0,
+        })
+    }
+
+    fn column_list(&self) -> String {
+        self.columns.names().collect::<Vec<_>>().join(", ")
+    }
+
+    // Terminate the open INSERT statement or COPY block, if any
+    fn close_statement(&mut self) -> io::Result<()> {
+        if self.pending == 0 {
+            return Ok(());
+        }
+        self.pending = 0;
+        match self.options.style {
+            SqlStyle::Insert => self.out.write_all(b";\n"),
+            SqlStyle::Copy => self.out.write_all(b"\\.\n"),
+        }
+    }
+
+    fn write_insert(&mut self, tx: &Transaction) -> io::Result<()> {
+        if self.pending == 0 {
+            write!(
+                self.out,
+                "INSERT INTO {} ({}) VALUES\n  (",
+                self.options.table,
+                self.column_list()
+            )?;
+        } else {
+            self.out.write_all(b",\n  (")?;
+        }
+        for (i, value) in row_values(tx, &self.columns).into_iter().enumerate() {
+            if i > 0 {
+                self.out.write_all(b", ")?;
+            }
+            match value {
+                Value::Null => self.out.write_all(b"NULL")?,
+                Value::Text(text) | Value::Timestamp(text) => {
+                    write!(self.out, "'{}'", text.replace('\'', "''"))?
+                }
+                Value::Integer(number) => write!(self.out, "{}", number)?,
+                Value::Long(number) => write!(self.out, "{}", number)?,
+                Value::Float(number) => write!(self.out, "{}", number)?,
+                Value::Amount(amount) => write!(self.out, "{}", amount)?,
+                Value::Rate(rate) => write!(self.out, "{:.6}", rate)?,
+                Value::Percent(percent) => write!(self.out, "{:.2}", percent)?,
+                Value::Bool(flag) => self.out.write_all(if flag { b"TRUE" } else { b"FALSE" })?,
+            }
+        }
+        self.out.write_all(b")")?;
+
+        self.pending += 1;
+        if self.pending >= self.options.batch_size {
+            self.close_statement()?;
+        }
+        Ok(())
+    }
+
+    fn write_copy(&mut self, tx: &Transaction) -> io::Result<()> {
+        if self.pending == 0 {
+            writeln!(
+                self.out,
+                "COPY {} ({}) FROM stdin;",
+                self.options.table,
+                self.column_list()
+            )?;
+        }
+        write_copy_row(&mut self.out, tx, &self.columns)?;
+        self.pending += 1;
+        Ok(())
+    }
+}
+
+impl<W: Finish> TransactionWriter for SqlWriter<W> {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        match self.options.style {
+            SqlStyle::Insert => self.write_insert(tx),
+            SqlStyle::Copy => self.write_copy(tx),
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.close_statement()?;
+        self.out.write_all(b"COMMIT;\n")?;
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.out.bytes()
+    }
+}
+
+// Write a transaction as a line of the COPY text format
+pub(crate) fn write_copy_row<W: Write>(
+    out: &mut W,
+    tx: &Transaction,
+    columns: &Columns,
+) -> io::Result<()> {
+    for (i, value) in row_values(tx, columns).into_iter().enumerate() {
+        if i > 0 {
+            out.write_all(b"\t")?;
+        }
+        match value {
+            Value::Null => out.write_all(b"\\N")?,
+            Value::Text(text) | Value::Timestamp(text) => {
+                out.write_all(copy_escape(text).as_bytes())?
+            }
+            Value::Integer(number) => write!(out, "{}", number)?,
+            Value::Long(number) => write!(out, "{}", number)?,
+            Value::Float(number) => write!(out, "{}", number)?,
+            Value::Amount(amount) => write!(out, "{}", amount)?,
+            Value::Rate(rate) => write!(out, "{:.6}", rate)?,
+            Value::Percent(percent) => write!(out, "{:.2}", percent)?,
+            Value::Bool(flag) => out.write_all(if flag { b"t" } else { b"f" })?,
+        }
+    }
+    out.write_all(b"\n")
+}
+
+// Escape a value for the COPY text format
+fn copy_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Profile, generator::TransactionStream};
+    use std::collections::HashMap;
+
+    // Length of each character column of a CREATE TABLE statement
+    fn widths(ddl: &str) -> HashMap<&str, usize> {
+        ddl.lines()
+            .filter_map(|line| {
+                let (name, ty) = line.trim().trim_end_matches(',').split_once(' ')?;
+                Some((name, width(ty)?))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn masked_rows_fit_their_columns() {
+        for mode in [
+            MaskMode::Masked,
+            MaskMode::First6Last4,
+            MaskMode::Sha256,
+            MaskMode::Token,
+        ] {
+            let profile = Profile {
+                masking: MaskingConfig {
+                    card_number: mode,
+                    cvv: mode,
+                    card_expiry: mode,
+                    cardholder_name: mode,
+                    email: mode,
+                    phone: mode,
+                    token_key: "key".to_string(),
+                },
+                ..Profile::default()
+            };
+            let columns = Columns::new(&profile);
+            let ddl = create_table(
+                "transactions",
+                &masked_types(&profile.masking),
+                &[],
+                &columns,
+            );
+            let widths = widths(&ddl);
+            // Loading a row fails when a value is longer than its column
+            for mut tx in TransactionStream::new(&profile, 2_000) {
+                profile.masking.apply(&mut tx);
+                for ((name, _), value) in COLUMNS.iter().zip(values(&tx)) {
+                    if let (Value::Text(text), Some(width)) = (value, widths.get(name)) {
+                        let length = text.chars().count();
+                        assert!(
+                            length <= *width,
+                            "{:?} {} {:?} is longer than {}",
+                            mode,
+                            name,
+                            text,
+                            width
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use super::{
+    Columns, TransactionWriter,
+    sql::{self, Value},
+    to_io,
+};
+use crate::{custom::CustomField, masking::MaskingConfig, model::Transaction};
+use rusqlite::{Connection, params_from_iter, types::ToSqlOutput};
+use std::{io, path::Path};
+
+// Number of rows inserted per database transaction
+const BATCH_SIZE: usize = 10_000;
+
+// Writes transactions into a SQLite database file, with indexes for looking
+// rows up by transaction ID and card number
+pub struct SqliteWriter {
+    conn: Connection,
+    table: String,
+    insert: String,
+    columns: Columns,
+    // Rows inserted in the open database transaction
+    pending: usize,
+}
+
+impl SqliteWriter {
+    pub fn new(
+        path: &Path,
+        table: &str,
+        custom_fields: &[CustomField],
+        masking: &MaskingConfig,
+        columns: Columns,
+    ) -> io::Result<Self> {
+        let conn = Connection::open(path).map_err(to_io)?;
+        let types = sql::masked_types(masking);
+        conn.execute_batch(&sql::create_table(table, &types, custom_fields, &columns))
+            .map_err(to_io)?;
+
+        let names: Vec<&str> = columns.names().collect();
+        let placeholders = vec!["?"; names.len()].join(", ");
+        Ok(Self {
+            conn,
+            table: table.to_string(),
+            insert: format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                table,
+                names.join(", "),
+                placeholders
+            ),
+            columns,
+            pending: 0,
+        })
+    }
+
+    fn commit(&mut self) -> io::Result<()> {
+        if self.pending > 0 {
+            self.conn.execute_batch("COMMIT").map_err(to_io)?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+}
+
+impl TransactionWriter for SqliteWriter {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        if self.pending == 0 {
+            self.conn.execute_batch("BEGIN").map_err(to_io)?;
+        }
+        let values = sql::row_values(tx, &self.columns);
+        let row = values.into_iter().map(|value| match value {
+            Value::Null => ToSqlOutput::from(rusqlite::types::Null),
+            Value::Text(text) | Value::Timestamp(text) => ToSqlOutput::from(text),
+            Value::Integer(number) => ToSqlOutput::from(number),
+            Value::Long(number) => ToSqlOutput::from(number),
+            Value::Float(number) => ToSqlOutput::from(number),
+            Value::Amount(amount) => ToSqlOutput::from(amount.to_major()),
+            Value::Rate(number) | Value::Percent(number) => ToSqlOutput::from(number),
+            Value::Bool(flag) => ToSqlOutput::from(flag),
+        });
+        self.conn
+            .prepare_cached(&self.insert)
+            .and_then(|mut insert| insert.execute(params_from_iter(row)))
+            .map_err(to_io)?;
+
+        self.pending += 1;
+        if self.pending >= BATCH_SIZE {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.commit()?;
+        // Index once all rows are in, which is faster than maintaining the
+        // indexes during the load
+        let index = self.table.replace('.', "_");
+        for field in ["transaction_id", "card_number"] {
+            if let Some(column) = self.columns.name_of(field) {
+                self.conn
+                    .execute_batch(&format!(
+                        "CREATE INDEX IF NOT EXISTS {index}_{field} ON {table} ({column});",
+                        index = index,
+                        field = field,
+                        table = self.table,
+                        column = column
+                    ))
+                    .map_err(to_io)?;
+            }
+        }
+        Ok(())
+    }
+
+    // SQLite writes the database's pages itself, so its size is asked of it
+    fn bytes(&self) -> u64 {
+        self.conn
+            .query_row(
+                "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .map_or(0, |bytes| bytes as u64)
+    }
+}
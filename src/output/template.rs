@@ -0,0 +1,83 @@
+use super::{Finish, TransactionWriter, to_json};
+use crate::{custom, model::Transaction, money::AmountFormat};
+use handlebars::{Handlebars, handlebars_helper, no_escape};
+use serde_json::Value;
+use std::{fs, io, path::Path};
+
+// Name the template is registered under
+const TEMPLATE: &str = "row";
+
+// Writes each transaction as the text a Handlebars template renders from its
+// fields, for consumers no built-in format fits: log lines, EDI-style
+// records, email bodies and so on. Rows see the same fields as in JSON
+// output, and are written one after the other with nothing in between, so a
+// template ending in a newline gives a line per row.
+pub struct TemplateWriter<W: Finish> {
+    out: W,
+    registry: Handlebars<'static>,
+    amount_format: AmountFormat,
+}
+
+impl<W: Finish> TemplateWriter<W> {
+    pub fn new(out: W, path: &Path, amount_format: AmountFormat) -> io::Result<Self> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        let mut registry = Handlebars::new();
+        // Rows are rendered as text rather than HTML, and a misspelt field
+        // fails the run instead of rendering as nothing
+        registry.register_escape_fn(no_escape);
+        registry.set_strict_mode(true);
+        registry.register_helper("upper", Box::new(upper));
+        registry.register_helper("lower", Box::new(lower));
+        registry.register_helper("pad", Box::new(pad));
+        registry.register_helper("lpad", Box::new(lpad));
+        registry
+            .register_template_string(TEMPLATE, source)
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", path.display(), e),
+                )
+            })?;
+        Ok(Self {
+            out,
+            registry,
+            amount_format,
+        })
+    }
+}
+
+impl<W: Finish> TransactionWriter for TemplateWriter<W> {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        let row = to_json(tx, self.amount_format)?;
+        self.registry
+            .render_to_write(TEMPLATE, &row, &mut self.out)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("template: {}", e)))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.out.bytes()
+    }
+}
+
+// A value padded with spaces or cut to a width in characters, for
+// positional records
+fn fit(value: &Value, width: u64, right: bool) -> String {
+    let text = custom::text(Some(value));
+    let width = width as usize;
+    let text: String = text.chars().take(width).collect();
+    if right {
+        format!("{:>width$}", text, width = width)
+    } else {
+        format!("{:<width$}", text, width = width)
+    }
+}
+
+handlebars_helper!(upper: |value: Json| custom::text(Some(value)).to_uppercase());
+handlebars_helper!(lower: |value: Json| custom::text(Some(value)).to_lowercase());
+handlebars_helper!(pad: |value: Json, width: u64| fit(value, width, false));
+handlebars_helper!(lpad: |value: Json, width: u64| fit(value, width, true));
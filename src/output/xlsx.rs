@@ -0,0 +1,311 @@
+use super::{CSV_HEADER, TransactionWriter, to_io};
+use crate::{
+    currency,
+    custom::{self, CustomField, CustomType},
+    model::Transaction,
+};
+use chrono::DateTime;
+use rust_xlsxwriter::{Color, Format, FormatBorder, Workbook, XlsxError};
+use serde_json::Value;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+// Data rows per worksheet: Excel's limit of 1,048,576 rows, less the header.
+// Larger datasets continue on further worksheets.
+const SHEET_ROWS: u32 = 1_048_575;
+
+// Column widths in characters, in `Transaction` field order
+const WIDTHS: [f64; 86] = [
+    38.0, 14.0, 20.0, 11.0, 20.0, 14.0, 10.0, 38.0, 16.0, 10.0, 20.0, 19.0, 16.0, 13.0, 38.0, 24.0,
+    32.0, 16.0, 20.0, 18.0, 11.0, 6.0, 32.0, 12.0, 10.0, 32.0, 18.0, 20.0, 18.0, 15.0, 32.0, 18.0,
+    20.0, 20.0, 17.0, 12.0, 14.0, 17.0, 9.0, 16.0, 17.0, 15.0, 16.0, 12.0, 10.0, 28.0, 16.0, 40.0,
+    28.0, 6.0, 12.0, 18.0, 24.0, 16.0, 16.0, 13.0, 16.0, 12.0, 44.0, 40.0, 18.0, 18.0, 12.0, 8.0,
+    13.0, 10.0, 20.0, 20.0, 17.0, 6.0, 22.0, 32.0, 11.0, 11.0, 28.0, 11.0, 38.0, 16.0, 16.0, 20.0,
+    17.0, 34.0, 40.0, 9.0, 20.0, 18.0,
+];
+
+// Index of the columns with a non-text type
+const DATE_COLUMN: u16 = 2;
+const INSTALLMENT_NUMBER_COLUMN: u16 = 10;
+const INSTALLMENT_COUNT_COLUMN: u16 = 11;
+const AMOUNT_COLUMN: u16 = 35;
+const REFUND_AMOUNT_COLUMN: u16 = 36;
+const REMAINING_AMOUNT_COLUMN: u16 = 37;
+const ORIGINAL_AMOUNT_COLUMN: u16 = 39;
+const BILLING_AMOUNT_COLUMN: u16 = 41;
+const FX_RATE_COLUMN: u16 = 43;
+const FX_MARKUP_COLUMN: u16 = 44;
+const FRAUD_COLUMN: u16 = 83;
+
+// Column width of the profile's custom fields
+const CUSTOM_WIDTH: f64 = 16.0;
+
+// Writes transactions as an Excel workbook with a bold, frozen and filterable
+// header row. Dates, amounts, installments and fraud labels are typed cells,
+// while card
+// numbers and every other column are text, so spreadsheets never turn them
+// into scientific notation or strip leading zeros. Worksheets are streamed
+// to disk as they fill, so memory stays flat however large the dataset is.
+pub struct XlsxWriter {
+    workbook: Workbook,
+    // Taken once the workbook has been saved
+    file: Option<File>,
+    header: Format,
+    date: Format,
+    // Amount formats by the number of decimals of the currency
+    amount: [Format; 5],
+    // Worksheet being filled and the number of data rows written to it
+    sheet: usize,
+    rows: u32,
+    // Names and types of the profile's custom fields, in column order after
+    // the built-in ones
+    custom: Vec<(String, CustomType)>,
+    // Bytes of the workbook, once it's been saved
+    bytes: u64,
+}
+
+impl XlsxWriter {
+    pub fn new(file: File, custom_fields: &[CustomField]) -> io::Result<Self> {
+        let mut writer = Self {
+            workbook: Workbook::new(),
+            file: Some(file),
+            header: Format::new()
+                .set_bold()
+                .set_background_color(Color::RGB(0xD9E1F2))
+                .set_border_bottom(FormatBorder::Thin),
+            // Timestamps are shown in the timezone they were generated in
+            date: Format::new().set_num_format("yyyy-mm-dd hh:mm:ss"),
+            amount: std::array::from_fn(|decimals| match decimals {
+                0 => Format::new().set_num_format("#,##0"),
+                _ => Format::new().set_num_format(format!("#,##0.{}", "0".repeat(decimals))),
+            }),
+            sheet: 0,
+            rows: 0,
+            custom: custom_fields
+                .iter()
+                .map(|field| (field.name.clone(), field.kind()))
+                .collect(),
+            bytes: 0,
+        };
+        writer.add_sheet().map_err(to_io)?;
+        Ok(writer)
+    }
+
+    // Start a new worksheet, named "Transactions", then "Transactions 2" and
+    // so on, with its header row, column widths and frozen panes
+    fn add_sheet(&mut self) -> Result<(), XlsxError> {
+        let name = match self.workbook.worksheets().len() {
+            0 => "Transactions".to_string(),
+            n => format!("Transactions {}", n + 1),
+        };
+        let sheet = self.workbook.add_worksheet_with_constant_memory();
+        sheet.set_name(name)?;
+        for (col, (title, width)) in CSV_HEADER.iter().zip(WIDTHS).enumerate() {
+            sheet.set_column_width(col as u16, width)?;
+            sheet.write_string_with_format(0, col as u16, *title, &self.header)?;
+        }
+        for (i, (name, _)) in self.custom.iter().enumerate() {
+            let col = (CSV_HEADER.len() + i) as u16;
+            sheet.set_column_width(col, CUSTOM_WIDTH)?;
+            sheet.write_string_with_format(0, col, name, &self.header)?;
+        }
+        sheet.set_freeze_panes(1, 0)?;
+        self.sheet = self.workbook.worksheets().len() - 1;
+        self.rows = 0;
+        Ok(())
+    }
+
+    // Cover the header and the rows written to the current worksheet with an
+    // autofilter, once no more rows will be added to it
+    fn close_sheet(&mut self) -> Result<(), XlsxError> {
+        let last_col = (CSV_HEADER.len() + self.custom.len()) as u16 - 1;
+        let sheet = self.workbook.worksheet_from_index(self.sheet)?;
+        sheet.autofilter(0, 0, self.rows, last_col)?;
+        Ok(())
+    }
+
+    fn write_row(&mut self, tx: &Transaction) -> Result<(), XlsxError> {
+        if self.rows == SHEET_ROWS {
+            self.close_sheet()?;
+            self.add_sheet()?;
+        }
+        let date = DateTime::parse_from_rfc3339(&tx.transaction_date)
+            .map_err(|e| XlsxError::DateTimeParseError(e.to_string()))?
+            .naive_local();
+        self.rows += 1;
+        let row = self.rows;
+        let sheet = self.workbook.worksheet_from_index(self.sheet)?;
+        let text = [
+            Some(tx.transaction_id.as_str()),
+            tx.event_type.as_ref().map(|e| e.as_str()),
+            None,
+            Some(tx.status.as_str()),
+            tx.decline_reason.as_ref().map(|r| r.as_str()),
+            Some(&tx.response_code),
+            tx.auth_code.as_deref(),
+            tx.original_transaction_id.as_deref(),
+            tx.subscription_id.as_deref(),
+            tx.initiator.as_ref().map(|i| i.as_str()),
+            None,
+            None,
+            tx.order_reference.as_deref(),
+            tx.preauth_step.as_ref().map(|s| s.as_str()),
+            Some(&tx.customer_id),
+            Some(&tx.cardholder_name),
+            Some(&tx.email),
+            Some(&tx.phone),
+            Some(&tx.card_number),
+            Some(&tx.card_brand),
+            Some(&tx.card_expiry),
+            tx.cvv.as_deref(),
+            tx.issuer_bank.as_deref(),
+            tx.card_country.as_deref(),
+            tx.card_type.as_ref().map(|t| t.as_str()),
+            Some(&tx.billing_street),
+            Some(&tx.billing_city),
+            Some(&tx.billing_region),
+            Some(&tx.billing_postal_code),
+            Some(&tx.billing_country),
+            tx.shipping_street.as_deref(),
+            tx.shipping_city.as_deref(),
+            tx.shipping_region.as_deref(),
+            tx.shipping_postal_code.as_deref(),
+            tx.shipping_country.as_deref(),
+            None,
+            None,
+            None,
+            Some(&tx.currency),
+            None,
+            tx.original_currency.as_deref(),
+            None,
+            tx.billing_currency.as_deref(),
+            None,
+            None,
+            Some(&tx.merchant_name),
+            Some(&tx.merchant_id),
+            Some(&tx.statement_descriptor),
+            Some(&tx.merchant_category),
+            tx.mcc.as_deref(),
+            Some(&tx.acquirer_id),
+            Some(&tx.processor),
+            tx.batch_id.as_deref(),
+            tx.settlement_date.as_deref(),
+            Some(&tx.payment_method),
+            Some(tx.channel.as_str()),
+            Some(&tx.pos_entry_mode),
+            tx.terminal_id.as_deref(),
+            tx.track1.as_deref(),
+            tx.track2.as_deref(),
+            tx.emv_aid.as_deref(),
+            tx.emv_cryptogram.as_deref(),
+            tx.emv_tvr.as_deref(),
+            tx.emv_atc.as_deref(),
+            tx.wallet.as_ref().map(|w| w.as_str()),
+            tx.wallet_id.as_deref(),
+            tx.token_pan.as_deref(),
+            tx.token_requestor_id.as_deref(),
+            tx.three_ds_version.as_deref(),
+            tx.eci.as_deref(),
+            tx.authentication_result.as_ref().map(|r| r.as_str()),
+            tx.cavv.as_deref(),
+            tx.avs_result.as_deref(),
+            tx.cvv_result.as_deref(),
+            tx.ip_address.as_deref(),
+            tx.ip_country.as_deref(),
+            tx.device_id.as_deref(),
+            tx.device_platform.as_deref(),
+            tx.device_os.as_deref(),
+            tx.device_browser.as_deref(),
+            tx.screen_resolution.as_deref(),
+            tx.device_fingerprint.as_deref(),
+            tx.user_agent.as_deref(),
+            None,
+            tx.fraud_pattern.as_ref().map(|p| p.as_str()),
+            tx.persona.as_deref(),
+        ];
+        for (col, value) in text.into_iter().enumerate() {
+            // Absent optional fields are left as blank cells
+            if let Some(value) = value {
+                sheet.write_string(row, col as u16, value)?;
+            }
+        }
+        sheet.write_datetime_with_format(row, DATE_COLUMN, date, &self.date)?;
+        if let Some(number) = tx.installment_number {
+            sheet.write_number(row, INSTALLMENT_NUMBER_COLUMN, number)?;
+        }
+        if let Some(count) = tx.installment_count {
+            sheet.write_number(row, INSTALLMENT_COUNT_COLUMN, count)?;
+        }
+        let amount = &self.amount[currency::exponent(&tx.currency) as usize];
+        sheet.write_number_with_format(row, AMOUNT_COLUMN, tx.amount, amount)?;
+        if let Some(refund_amount) = tx.refund_amount {
+            sheet.write_number_with_format(row, REFUND_AMOUNT_COLUMN, refund_amount, amount)?;
+        }
+        if let Some(remaining_amount) = tx.remaining_amount {
+            sheet.write_number_with_format(
+                row,
+                REMAINING_AMOUNT_COLUMN,
+                remaining_amount,
+                amount,
+            )?;
+        }
+        if let Some(original_amount) = tx.original_amount {
+            sheet.write_number_with_format(row, ORIGINAL_AMOUNT_COLUMN, original_amount, amount)?;
+        }
+        if let (Some(billing_amount), Some(currency)) = (tx.billing_amount, &tx.billing_currency) {
+            let format = &self.amount[currency::exponent(currency) as usize];
+            sheet.write_number_with_format(row, BILLING_AMOUNT_COLUMN, billing_amount, format)?;
+        }
+        if let Some(rate) = tx.fx_rate {
+            sheet.write_number(row, FX_RATE_COLUMN, rate)?;
+        }
+        if let Some(markup) = tx.fx_markup {
+            sheet.write_number(row, FX_MARKUP_COLUMN, markup)?;
+        }
+        sheet.write_boolean(row, FRAUD_COLUMN, tx.is_fraud)?;
+        // Custom values are typed cells where the field is numeric or boolean,
+        // and nulled ones are left blank
+        for (i, (name, kind)) in self.custom.iter().enumerate() {
+            let col = (CSV_HEADER.len() + i) as u16;
+            match (tx.custom.get(name), kind) {
+                (None | Some(Value::Null), _) => {}
+                (Some(Value::Bool(value)), _) => {
+                    sheet.write_boolean(row, col, *value)?;
+                }
+                (Some(Value::Number(value)), CustomType::Integer | CustomType::Float) => {
+                    sheet.write_number(row, col, value.as_f64().unwrap_or_default())?;
+                }
+                (value, _) => {
+                    sheet.write_string(row, col, custom::text(value))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TransactionWriter for XlsxWriter {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        self.write_row(tx).map_err(to_io)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let Some(file) = self.file.take() else {
+            return Ok(());
+        };
+        self.close_sheet().map_err(to_io)?;
+        let mut out = BufWriter::new(file);
+        self.workbook.save_to_writer(&mut out).map_err(to_io)?;
+        out.flush()?;
+        // Saving seeks back over the zip entries it has written, so the
+        // workbook's size is read off the file rather than counted
+        self.bytes = out.get_ref().metadata()?.len();
+        Ok(())
+    }
+
+    fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
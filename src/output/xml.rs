@@ -0,0 +1,163 @@
+use super::{AmountFields, CSV_HEADER, Columns, Finish, TransactionWriter, iso20022::escape};
+use crate::{config::XmlOptions, custom, model::Transaction, money::AmountFormat};
+use std::{borrow::Cow, io};
+
+// Writes transactions as an XML document: a root element holding one element
+// per transaction, with a child element per field. Absent optional fields
+// are left out rather than written empty.
+pub struct XmlWriter<W: Finish> {
+    out: W,
+    root: String,
+    row: String,
+    columns: Columns,
+    // Element name of each column, in column order
+    elements: Vec<String>,
+    amount_format: AmountFormat,
+}
+
+impl<W: Finish> XmlWriter<W> {
+    pub fn new(
+        mut out: W,
+        options: &XmlOptions,
+        amount_format: AmountFormat,
+        columns: Columns,
+    ) -> io::Result<Self> {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(out, "<{}>", options.root)?;
+        Ok(Self {
+            out,
+            root: options.root.clone(),
+            row: options.row.clone(),
+            elements: columns
+                .iter()
+                .map(|(i, name)| options.element(columns.field(i), name).to_string())
+                .collect(),
+            columns,
+            amount_format,
+        })
+    }
+}
+
+impl<W: Finish> TransactionWriter for XmlWriter<W> {
+    fn write(&mut self, tx: &Transaction) -> io::Result<()> {
+        let amounts = AmountFields::new(tx, self.amount_format);
+        let fx_rate = tx.fx_rate.map(|r| format!("{:.6}", r));
+        let fx_markup = tx.fx_markup.map(|m| format!("{:.2}", m));
+        let installment_number = tx.installment_number.map(|n| n.to_string());
+        let installment_count = tx.installment_count.map(|n| n.to_string());
+        let is_fraud = tx.is_fraud.to_string();
+        let values = [
+            Some(tx.transaction_id.as_str()),
+            tx.event_type.as_ref().map(|e| e.as_str()),
+            Some(&tx.transaction_date),
+            Some(tx.status.as_str()),
+            tx.decline_reason.as_ref().map(|r| r.as_str()),
+            Some(&tx.response_code),
+            tx.auth_code.as_deref(),
+            tx.original_transaction_id.as_deref(),
+            tx.subscription_id.as_deref(),
+            tx.initiator.as_ref().map(|i| i.as_str()),
+            installment_number.as_deref(),
+            installment_count.as_deref(),
+            tx.order_reference.as_deref(),
+            tx.preauth_step.as_ref().map(|s| s.as_str()),
+            Some(&tx.customer_id),
+            Some(&tx.cardholder_name),
+            Some(&tx.email),
+            Some(&tx.phone),
+            Some(&tx.card_number),
+            Some(&tx.card_brand),
+            Some(&tx.card_expiry),
+            tx.cvv.as_deref(),
+            tx.issuer_bank.as_deref(),
+            tx.card_country.as_deref(),
+            tx.card_type.as_ref().map(|t| t.as_str()),
+            Some(&tx.billing_street),
+            Some(&tx.billing_city),
+            Some(&tx.billing_region),
+            Some(&tx.billing_postal_code),
+            Some(&tx.billing_country),
+            tx.shipping_street.as_deref(),
+            tx.shipping_city.as_deref(),
+            tx.shipping_region.as_deref(),
+            tx.shipping_postal_code.as_deref(),
+            tx.shipping_country.as_deref(),
+            Some(&amounts.amount),
+            amounts.refund_amount.as_deref(),
+            amounts.remaining_amount.as_deref(),
+            Some(&tx.currency),
+            amounts.original_amount.as_deref(),
+            tx.original_currency.as_deref(),
+            amounts.billing_amount.as_deref(),
+            tx.billing_currency.as_deref(),
+            fx_rate.as_deref(),
+            fx_markup.as_deref(),
+            Some(&tx.merchant_name),
+            Some(&tx.merchant_id),
+            Some(&tx.statement_descriptor),
+            Some(&tx.merchant_category),
+            tx.mcc.as_deref(),
+            Some(&tx.acquirer_id),
+            Some(&tx.processor),
+            tx.batch_id.as_deref(),
+            tx.settlement_date.as_deref(),
+            Some(&tx.payment_method),
+            Some(tx.channel.as_str()),
+            Some(&tx.pos_entry_mode),
+            tx.terminal_id.as_deref(),
+            tx.track1.as_deref(),
+            tx.track2.as_deref(),
+            tx.emv_aid.as_deref(),
+            tx.emv_cryptogram.as_deref(),
+            tx.emv_tvr.as_deref(),
+            tx.emv_atc.as_deref(),
+            tx.wallet.as_ref().map(|w| w.as_str()),
+            tx.wallet_id.as_deref(),
+            tx.token_pan.as_deref(),
+            tx.token_requestor_id.as_deref(),
+            tx.three_ds_version.as_deref(),
+            tx.eci.as_deref(),
+            tx.authentication_result.as_ref().map(|r| r.as_str()),
+            tx.cavv.as_deref(),
+            tx.avs_result.as_deref(),
+            tx.cvv_result.as_deref(),
+            tx.ip_address.as_deref(),
+            tx.ip_country.as_deref(),
+            tx.device_id.as_deref(),
+            tx.device_platform.as_deref(),
+            tx.device_os.as_deref(),
+            tx.device_browser.as_deref(),
+            tx.screen_resolution.as_deref(),
+            tx.device_fingerprint.as_deref(),
+            tx.user_agent.as_deref(),
+            Some(&is_fraud),
+            tx.fraud_pattern.as_ref().map(|p| p.as_str()),
+            tx.persona.as_deref(),
+        ];
+
+        writeln!(self.out, "  <{}>", self.row)?;
+        for ((i, _), element) in self.columns.iter().zip(&self.elements) {
+            let value = match values.get(i) {
+                Some(value) => value.map(Cow::Borrowed),
+                None => {
+                    let name = &self.columns.custom()[i - CSV_HEADER.len()];
+                    let value = tx.custom.get(name).filter(|value| !value.is_null());
+                    value.map(|value| custom::text(Some(value)))
+                }
+            };
+            if let Some(value) = value {
+                writeln!(self.out, "    <{0}>{1}</{0}>", element, escape(&value))?;
+            }
+        }
+        writeln!(self.out, "  </{}>", self.row)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        writeln!(self.out, "</{}>", self.root)?;
+        self.out.finish()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.out.bytes()
+    }
+}
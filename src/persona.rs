@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// A kind of cardholder with spending habits of their own, given to a share
+// of the customer pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Persona {
+    pub name: String,
+    // Share of customers with the persona, relative to the other personas
+    pub weight: f64,
+    // How likely the persona's customers are to shop at each merchant
+    // category, relative to 1 for categories left out
+    #[serde(default)]
+    pub categories: BTreeMap<String, f64>,
+    // Factor the amounts drawn for the merchant's category are scaled by
+    #[serde(default = "one")]
+    pub amount_scale: f64,
+    // How often the persona's customers transact, relative to 1 for a
+    // customer without a persona
+    #[serde(default = "one")]
+    pub frequency: f64,
+    // Number of devices each customer shops online from
+    #[serde(default = "one_device")]
+    pub devices: usize,
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+fn one_device() -> usize {
+    1
+}
+
+impl Persona {
+    // Weight of each merchant category for the persona's customers
+    pub fn category_weight(&self, category: &str) -> f64 {
+        self.categories.get(category).copied().unwrap_or(1.0)
+    }
+
+    // Reasons the persona can't be generated from, if any
+    pub fn check(&self) -> Result<(), String> {
+        if !self.weight.is_finite() || self.weight < 0.0 {
+            return Err("weight must be a non-negative number".to_string());
+        }
+        if let Some((category, _)) = self
+            .categories
+            .iter()
+            .find(|(_, weight)| !weight.is_finite() || **weight < 0.0)
+        {
+            return Err(format!(
+                "categories.\"{}\" must be a non-negative number",
+                category
+            ));
+        }
+        if !self.amount_scale.is_finite() || self.amount_scale <= 0.0 {
+            return Err("amount_scale must be above 0".to_string());
+        }
+        if !self.frequency.is_finite() || self.frequency <= 0.0 {
+            return Err("frequency must be above 0".to_string());
+        }
+        if self.devices == 0 {
+            return Err("devices must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Personas spread over the pool with `--personas`, made for the built-in
+// merchants: students buying coffee, books and snacks, travelers spending
+// big on trips from several devices, and small businesses buying equipment
+// and travel often and in large amounts
+pub fn builtin_personas() -> Vec<Persona> {
+    let persona =
+        |name: &str, weight, categories: &[(&str, f64)], amount_scale, frequency, devices| {
+            Persona {
+                name: name.to_string(),
+                weight,
+                categories: categories
+                    .iter()
+                    .map(|(category, weight)| (category.to_string(), *weight))
+                    .collect(),
+                amount_scale,
+                frequency,
+                devices,
+            }
+        };
+    vec![
+        persona(
+            "student",
+            0.4,
+            &[
+                ("Food & Beverage", 4.0),
+                ("Books & Media", 3.0),
+                ("Convenience Store", 3.0),
+                ("Restaurant", 1.5),
+                ("Electronics", 0.5),
+                ("Travel", 0.2),
+                ("Lodging", 0.1),
+                ("Car Rental", 0.1),
+            ],
+            0.5,
+            1.5,
+            2,
+        ),
+        persona(
+            "frequent_traveler",
+            0.25,
+            &[
+                ("Travel", 5.0),
+                ("Lodging", 5.0),
+                ("Car Rental", 4.0),
+                ("Restaurant", 2.0),
+                ("Food & Beverage", 1.5),
+                ("Grocery", 0.3),
+                ("Convenience Store", 0.5),
+            ],
+            1.8,
+            1.2,
+            3,
+        ),
+        persona(
+            "small_business",
+            0.35,
+            &[
+                ("Electronics", 4.0),
+                ("Retail", 3.0),
+                ("Travel", 1.5),
+                ("Lodging", 1.5),
+                ("Restaurant", 1.5),
+                ("Grocery", 0.3),
+                ("Clothing", 0.3),
+                ("Health & Fitness", 0.2),
+            ],
+            3.0,
+            2.0,
+            2,
+        ),
+    ]
+}
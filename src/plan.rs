@@ -0,0 +1,213 @@
+use crate::{
+    config::{OutputFormat, Profile},
+    custom::CustomFields,
+    generator::TransactionStream,
+    lifecycle, metrics,
+    nulls::Nulls,
+    output,
+};
+use rand::{SeedableRng, rngs::StdRng};
+use std::{env, fmt::Write as _, fs, io, path::Path, process};
+
+// Transactions generated to measure the formats on
+const SAMPLE_ROWS: usize = 1000;
+
+// Rows and bytes a run writes per transaction generated, measured on a
+// sample written to a scratch directory and removed again
+pub struct RowSizes {
+    rows_per_transaction: f64,
+    // Bytes per row of each format, and the files a dataset of it takes
+    bytes_per_row: Vec<(OutputFormat, f64, u64)>,
+}
+
+impl RowSizes {
+    pub fn measure(profile: &Profile) -> io::Result<Self> {
+        let profile = Profile {
+            partition_by: None,
+            max_rows_per_file: None,
+            ..profile.clone()
+        };
+        let dir = env::temp_dir().join(format!("luhnsynth-dry-run-{}", process::id()));
+        fs::create_dir_all(&dir)?;
+        let measured = Self::write_sample(&profile, &dir);
+        fs::remove_dir_all(&dir)?;
+        measured
+    }
+
+    fn write_sample(profile: &Profile, dir: &Path) -> io::Result<Self> {
+        let mut writers = Vec::new();
+        for &format in &profile.formats {
+            let path = dir.join(output::file_name("sample", format, profile));
+            writers.push((
+                format,
+                path.clone(),
+                output::create_writer(format, &path, profile)?,
+            ));
+        }
+        let masking = profile.masking.is_enabled().then_some(&profile.masking);
+        let custom = CustomFields::new(&profile.custom_fields, profile.amount_format);
+        let nulls = Nulls::new(&profile.nulls);
+        let mut rows = 0;
+        let mut rng = StdRng::from_entropy();
+        for tx in TransactionStream::new(profile, SAMPLE_ROWS) {
+            let events = if profile.lifecycle {
+                lifecycle::events(&mut rng, &tx)
+            } else {
+                vec![tx]
+            };
+            for mut tx in events {
+                if let Some(masking) = masking {
+                    masking.apply(&mut tx);
+                }
+                if let Some(custom) = custom.as_ref() {
+                    custom.apply(&mut rng, &mut tx);
+                }
+                if let Some(nulls) = nulls.as_ref() {
+                    nulls.apply(&mut rng, &mut tx);
+                }
+                for (_, _, writer) in &mut writers {
+                    writer.write(&tx)?;
+                }
+                rows += 1;
+            }
+        }
+        let mut bytes_per_row = Vec::new();
+        for (format, path, mut writer) in writers {
+            writer.finish()?;
+            let bytes = metrics::disk_usage(&path) as f64;
+            let files = metrics::files(&path).len() as u64;
+            bytes_per_row.push((format, bytes / rows.max(1) as f64, files));
+        }
+        Ok(Self {
+            rows_per_transaction: rows as f64 / SAMPLE_ROWS as f64,
+            bytes_per_row,
+        })
+    }
+
+    // Rows written for a number of transactions, lifecycle events included
+    pub fn rows(&self, transactions: f64) -> u64 {
+        (transactions * self.rows_per_transaction).round() as u64
+    }
+
+    // Bytes a number of rows take in a format
+    pub fn bytes(&self, format: OutputFormat, rows: u64) -> u64 {
+        self.bytes_per_row
+            .iter()
+            .find(|(f, _, _)| *f == format)
+            .map_or(0, |(_, bytes, _)| (bytes * rows as f64).round() as u64)
+    }
+
+    // Files a dataset of a format takes, as relational output is several
+    pub fn files(&self, format: OutputFormat) -> u64 {
+        self.bytes_per_row
+            .iter()
+            .find(|(f, _, _)| *f == format)
+            .map_or(1, |(_, _, files)| *files)
+    }
+}
+
+// What a run would write, printed by --dry-run instead of writing it
+#[derive(Default)]
+pub struct Plan {
+    datasets: Vec<Dataset>,
+}
+
+struct Dataset {
+    // Rows of the dataset, unless it goes on until a live run is up
+    rows: Option<u64>,
+    outputs: Vec<Planned>,
+}
+
+// An output with its rows and estimated size, for those that are files
+struct Planned {
+    name: String,
+    rows: Option<u64>,
+    files: Option<u64>,
+    bytes: Option<u64>,
+}
+
+impl Plan {
+    pub fn dataset(&mut self, rows: Option<u64>) {
+        self.datasets.push(Dataset {
+            rows,
+            outputs: Vec::new(),
+        });
+    }
+
+    // Add an output of the last dataset: its name as the run would log it,
+    // the rows written to it, and the files and bytes they take
+    pub fn output(
+        &mut self,
+        name: &str,
+        rows: Option<u64>,
+        files: Option<u64>,
+        bytes: Option<u64>,
+    ) {
+        if let Some(dataset) = self.datasets.last_mut() {
+            dataset.outputs.push(Planned {
+                name: name.to_string(),
+                rows,
+                files,
+                bytes,
+            });
+        }
+    }
+
+    pub fn report(&self) -> String {
+        let mut report = String::from("Dry run, nothing written. Plan:\n");
+        let (mut rows, mut files, mut bytes) = (0, Some(0), 0);
+        for (i, dataset) in self.datasets.iter().enumerate() {
+            match dataset.rows {
+                Some(count) => {
+                    let _ = writeln!(report, "  dataset {}: {} rows", i + 1, count);
+                    rows += count;
+                }
+                None => {
+                    let _ = writeln!(report, "  dataset {}: rows until --duration is up", i + 1);
+                }
+            }
+            for output in &dataset.outputs {
+                let mut line = format!("    {}", output.name);
+                if let Some(count) = output.rows {
+                    let _ = write!(line, ": {} rows", count);
+                }
+                if let Some(count) = output.files.filter(|count| *count > 1) {
+                    let _ = write!(line, " in {} files", count);
+                }
+                if let Some(size) = output.bytes {
+                    let _ = write!(line, ", ~{}", human(size));
+                    bytes += size;
+                }
+                // Partitions are made as rows come, so their files aren't counted
+                files = files.zip(output.files).map(|(files, count)| files + count);
+                let _ = writeln!(report, "{}", line);
+            }
+        }
+        let _ = match files {
+            Some(files) => writeln!(
+                report,
+                "  total: {} rows in {} files, ~{}",
+                rows,
+                files,
+                human(bytes)
+            ),
+            None => writeln!(report, "  total: {} rows, ~{}", rows, human(bytes)),
+        };
+        report
+    }
+}
+
+// Bytes in the largest unit that keeps a whole number in front
+fn human(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", size, UNITS[unit]),
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    fx,
+    id::{self, IdStrategy},
+    model::{Transaction, TransactionStatus},
+    money::Money,
+    settlement, temporal,
+};
+use chrono::{DateTime, Duration};
+use chrono_tz::Tz;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Pre-authorized payments at hotels and car rentals: an estimate is held on
+// the card at check-in or pick-up, topped up while the stay or rental goes
+// on, and a different final amount is captured at the end. Pre-authorizations
+// are only generated when `rate` is above 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreauthConfig {
+    // Share of purchases at lodging and car rental merchants that are
+    // pre-authorized
+    pub rate: f64,
+    // Chance of each further incremental authorization, up to
+    // `max_incrementals` of them
+    pub incremental_rate: f64,
+    pub max_incrementals: u32,
+}
+
+impl Default for PreauthConfig {
+    fn default() -> Self {
+        Self {
+            rate: 0.0,
+            incremental_rate: 0.4,
+            max_incrementals: 3,
+        }
+    }
+}
+
+impl PreauthConfig {
+    // Each rate with the name it is configured under
+    pub fn rates(&self) -> [(&'static str, f64); 2] {
+        [
+            ("rate", self.rate),
+            ("incremental_rate", self.incremental_rate),
+        ]
+    }
+}
+
+// Step of a pre-authorized payment: the initial hold, a top-up of it, or the
+// final capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreauthStep {
+    Preauth,
+    Incremental,
+    Capture,
+}
+
+impl PreauthStep {
+    pub const ALL: [PreauthStep; 3] = [
+        PreauthStep::Preauth,
+        PreauthStep::Incremental,
+        PreauthStep::Capture,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PreauthStep::Preauth => "preauth",
+            PreauthStep::Incremental => "incremental",
+            PreauthStep::Capture => "capture",
+        }
+    }
+}
+
+// Whether a merchant category code belongs to a car rental agency or a
+// hotel, the merchants that pre-authorize payments: the car rental and
+// lodging chains (3351-3999), hotels (7011) and rental agencies (7512, 7513
+// and 7519)
+pub fn is_lodging_or_rental(mcc: Option<&str>) -> bool {
+    mcc.and_then(|mcc| mcc.parse::<u16>().ok())
+        .is_some_and(|code| matches!(code, 3351..=3999 | 7011 | 7512 | 7513 | 7519))
+}
+
+// Number of orders pre-authorized so far in this run, scrambled into their
+// references
+pub(crate) static ORDERS: AtomicU64 = AtomicU64::new(0);
+
+// Generate an order reference, distinct for the first billion orders of a run
+fn gen_order_reference() -> String {
+    let sequence = ORDERS.fetch_add(1, Ordering::Relaxed);
+    format!("ORD{:09}", id::scramble(sequence, 1_000_000_000, 5))
+}
+
+// Turn a purchase into the pre-authorization of an order, returning its
+// incremental authorizations and final capture up to `end`. Incremental
+// authorizations add 10-50% of the estimate each, a day or so apart, and
+// the capture follows a few days later for 85-115% of the total authorized,
+// never exactly that total, as card networks tolerate for hotels and car
+// rentals. Holds are never settled; only the capture is, under the
+// authorization code of the pre-authorization. Declined pre-authorizations
+// end the order.
+pub fn authorize<R: Rng + ?Sized>(
+    rng: &mut R,
+    preauth: &mut Transaction,
+    config: &PreauthConfig,
+    end: &DateTime<Tz>,
+    ids: IdStrategy,
+) -> Vec<Transaction> {
+    preauth.order_reference = Some(gen_order_reference().into());
+    preauth.preauth_step = Some(PreauthStep::Preauth);
+    settlement::settle(preauth);
+    if preauth.status != TransactionStatus::Approved {
+        return Vec::new();
+    }
+
+    let estimate = Money::from_major(preauth.amount, &preauth.currency);
+    let mut authorized = estimate;
+    let mut at = DateTime::parse_from_rfc3339(&preauth.transaction_date)
+        .expect("generated dates are RFC 3339")
+        .with_timezone(&end.timezone());
+
+    let mut steps = Vec::new();
+    for _ in 0..config.max_incrementals {
+        if !rng.gen_bool(config.incremental_rate) {
+            break;
+        }
+        at += Duration::minutes(rng.gen_range(12 * 60..=36 * 60));
+        if at > *end {
+            break;
+        }
+        let amount = estimate.scale(rng.gen_range(0.1..0.5));
+        let amount = Money {
+            minor_units: amount.minor_units.max(1),
+            ..amount
+        };
+        let mut incremental = Transaction {
+            transaction_id: ids.generate(rng, at.timestamp_millis()),
+            transaction_date: temporal::rfc3339(&at),
+            original_transaction_id: Some(preauth.transaction_id.clone()),
+            preauth_step: Some(PreauthStep::Incremental),
+            amount: amount.to_major(),
+            ..preauth.clone()
+        };
+        fx::rebill(&mut incremental);
+        incremental.respond(rng);
+        settlement::settle(&mut incremental);
+        authorized = authorized + amount;
+        steps.push(incremental);
+    }
+
+    at += Duration::minutes(rng.gen_range(24 * 60..=96 * 60));
+    if at > *end {
+        return steps;
+    }
+    let mut captured = authorized.scale(rng.gen_range(0.85..1.15));
+    captured.minor_units = captured.minor_units.max(1);
+    if captured == authorized {
+        captured.minor_units += 1;
+    }
+    let mut capture = Transaction {
+        transaction_id: ids.generate(rng, at.timestamp_millis()),
+        transaction_date: temporal::rfc3339(&at),
+        original_transaction_id: Some(preauth.transaction_id.clone()),
+        preauth_step: Some(PreauthStep::Capture),
+        amount: captured.to_major(),
+        ..preauth.clone()
+    };
+    fx::rebill(&mut capture);
+    settlement::settle(&mut capture);
+    steps.push(capture);
+    steps
+}
@@ -0,0 +1,340 @@
+use crate::{
+    config::Profile,
+    model::{Transaction, TransactionStatus},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+// Formats the profile report of a dataset is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    // Machine-readable summary, for checks in CI
+    Json,
+    // A page of tables and bars, for reading
+    Html,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+// Distributions of the rows written to a dataset, set beside the shares the
+// profile asked for, so generated data can be checked without loading it
+// elsewhere
+pub struct DataProfile {
+    rows: u64,
+    fraud_rows: u64,
+    brands: BTreeMap<String, u64>,
+    statuses: BTreeMap<String, u64>,
+    amounts: BTreeMap<String, Amounts>,
+    days: BTreeMap<String, u64>,
+    // Shares of each card brand and status in the profile
+    expected_brands: BTreeMap<String, f64>,
+    expected_statuses: BTreeMap<String, f64>,
+}
+
+// Amounts of one currency, with a histogram of bins on a 1-2-5 scale of
+// major units
+#[derive(Default)]
+struct Amounts {
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+    // Rows by the signed lower bound of their bin's magnitude; 0 holds
+    // amounts between -1 and 1
+    bins: BTreeMap<i64, u64>,
+}
+
+impl DataProfile {
+    pub fn new(profile: &Profile) -> Self {
+        let expected_brands = shares(
+            profile.card_brands.iter().map(|brand| brand.name.clone()),
+            profile.brand_weights(),
+        );
+        let expected_statuses = shares(
+            TransactionStatus::ALL
+                .iter()
+                .map(|s| s.as_str().to_string()),
+            profile.status_weights(),
+        );
+        Self {
+            rows: 0,
+            fraud_rows: 0,
+            brands: BTreeMap::new(),
+            statuses: BTreeMap::new(),
+            amounts: BTreeMap::new(),
+            days: BTreeMap::new(),
+            expected_brands,
+            expected_statuses,
+        }
+    }
+
+    pub fn record(&mut self, tx: &Transaction) {
+        self.rows += 1;
+        self.fraud_rows += u64::from(tx.is_fraud);
+        *self.brands.entry(tx.card_brand.to_string()).or_default() += 1;
+        *self
+            .statuses
+            .entry(tx.status.as_str().to_string())
+            .or_default() += 1;
+        let amounts = self.amounts.entry(tx.currency.to_string()).or_default();
+        if amounts.count == 0 || tx.amount < amounts.min {
+            amounts.min = tx.amount;
+        }
+        if amounts.count == 0 || tx.amount > amounts.max {
+            amounts.max = tx.amount;
+        }
+        amounts.count += 1;
+        amounts.sum += tx.amount;
+        *amounts.bins.entry(bin(tx.amount)).or_default() += 1;
+        // Rows are stamped with their local time, so the day is the date part
+        let day = tx
+            .transaction_date
+            .get(..10)
+            .unwrap_or(&tx.transaction_date);
+        *self.days.entry(day.to_string()).or_default() += 1;
+    }
+
+    pub fn to_json(&self) -> Value {
+        let counts = |counts: &BTreeMap<String, u64>, expected: &BTreeMap<String, f64>| {
+            names(counts, expected)
+                .map(|name| {
+                    let count = counts.get(name).copied().unwrap_or(0);
+                    let entry = json!({
+                        "count": count,
+                        "share": share(count, self.rows),
+                        "expected": expected.get(name),
+                    });
+                    (name.clone(), entry)
+                })
+                .collect::<serde_json::Map<_, _>>()
+        };
+        let amounts: serde_json::Map<_, _> = self
+            .amounts
+            .iter()
+            .map(|(currency, amounts)| {
+                let histogram: Vec<Value> = amounts
+                    .bins
+                    .iter()
+                    .map(|(&bin, &count)| {
+                        let (from, to) = bounds(bin);
+                        json!({ "from": from, "to": to, "count": count })
+                    })
+                    .collect();
+                let summary = json!({
+                    "count": amounts.count,
+                    "min": amounts.min,
+                    "max": amounts.max,
+                    "mean": amounts.sum / amounts.count as f64,
+                    "histogram": histogram,
+                });
+                (currency.clone(), summary)
+            })
+            .collect();
+        json!({
+            "rows": self.rows,
+            "fraud_rows": self.fraud_rows,
+            "fraud_share": share(self.fraud_rows, self.rows),
+            "card_brands": counts(&self.brands, &self.expected_brands),
+            "statuses": counts(&self.statuses, &self.expected_statuses),
+            "amounts": amounts,
+            "days": self.days,
+        })
+    }
+
+    pub fn write(&self, path: &Path, format: ReportFormat) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        match format {
+            ReportFormat::Json => {
+                serde_json::to_writer_pretty(&mut out, &self.to_json())?;
+                writeln!(out)?;
+            }
+            ReportFormat::Html => out.write_all(self.to_html().as_bytes())?,
+        }
+        out.flush()
+    }
+
+    // A standalone page with a table for each distribution, and bars drawn
+    // with plain CSS so it opens anywhere
+    fn to_html(&self) -> String {
+        let mut html = String::from(HTML_HEAD);
+        let _ = writeln!(
+            html,
+            "<h1>Data profile</h1>\n<p>{} rows, {} of them fraud ({:.2}%)</p>",
+            self.rows,
+            self.fraud_rows,
+            share(self.fraud_rows, self.rows) * 100.0
+        );
+        for (title, counts, expected) in [
+            ("Card brands", &self.brands, &self.expected_brands),
+            ("Statuses", &self.statuses, &self.expected_statuses),
+        ] {
+            let _ = writeln!(html, "<h2>{}</h2>\n<table>", title);
+            html.push_str(
+                "<tr><th></th><th>rows</th><th>share</th><th>expected</th><th></th></tr>\n",
+            );
+            let most = counts.values().copied().max().unwrap_or(1);
+            for name in names(counts, expected) {
+                let count = counts.get(name).copied().unwrap_or(0);
+                let share = share(count, self.rows);
+                let expected = expected
+                    .get(name)
+                    .map_or(String::new(), |e| format!("{:.2}%", e * 100.0));
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td><td>{:.2}%</td><td>{}</td><td>{}</td></tr>",
+                    escape(name),
+                    count,
+                    share * 100.0,
+                    expected,
+                    bar(count as f64 / most as f64)
+                );
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str("<h2>Amounts</h2>\n");
+        for (currency, amounts) in &self.amounts {
+            let _ = writeln!(
+                html,
+                "<h3>{}</h3>\n<p>{} rows, from {} to {}, mean {:.2}</p>",
+                escape(currency),
+                amounts.count,
+                amounts.min,
+                amounts.max,
+                amounts.sum / amounts.count as f64
+            );
+            html.push_str("<table>\n<tr><th>from</th><th>to</th><th>rows</th><th></th></tr>\n");
+            let most = amounts.bins.values().copied().max().unwrap_or(1);
+            for (&bin, &count) in &amounts.bins {
+                let (from, to) = bounds(bin);
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    from,
+                    to,
+                    count,
+                    bar(share(count, most))
+                );
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str(
+            "<h2>Rows per day</h2>\n<table>\n<tr><th>day</th><th>rows</th><th></th></tr>\n",
+        );
+        let most = self.days.values().copied().max().unwrap_or(1);
+        for (day, &count) in &self.days {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(day),
+                count,
+                bar(share(count, most))
+            );
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+const HTML_HEAD: &str = "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Data profile</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { padding: 2px 8px; text-align: right; }
+td:first-child { text-align: left; }
+.bar { background: #4a7bd0; height: 0.8em; }
+</style>
+</head>
+<body>
+";
+
+// A bar as wide as a fraction of 200 pixels
+fn bar(share: f64) -> String {
+    format!(
+        "<div class=\"bar\" style=\"width: {:.0}px\"></div>",
+        share * 200.0
+    )
+}
+
+// Names the profile gives a share, then any others seen
+fn names<'a>(
+    counts: &'a BTreeMap<String, u64>,
+    expected: &'a BTreeMap<String, f64>,
+) -> impl Iterator<Item = &'a String> {
+    let others = counts.keys().filter(|name| !expected.contains_key(*name));
+    expected.keys().chain(others)
+}
+
+fn share(count: u64, total: u64) -> f64 {
+    count as f64 / total.max(1) as f64
+}
+
+// Weights as shares of their total, by name
+fn shares(names: impl Iterator<Item = String>, weights: Vec<f64>) -> BTreeMap<String, f64> {
+    let total: f64 = weights.iter().sum();
+    names
+        .zip(weights)
+        .map(|(name, weight)| (name, weight / total))
+        .collect()
+}
+
+// Bin of an amount: the lower bound of its magnitude on a 1-2-5 scale, with
+// the amount's sign
+fn bin(amount: f64) -> i64 {
+    let magnitude = amount.abs();
+    if magnitude < 1.0 {
+        return 0;
+    }
+    let mut power = 10f64.powi(magnitude.log10().floor() as i32);
+    // log10 can fall just short on exact powers of ten
+    if magnitude >= power * 10.0 {
+        power *= 10.0;
+    }
+    let lower = match magnitude / power {
+        m if m >= 5.0 => 5.0 * power,
+        m if m >= 2.0 => 2.0 * power,
+        _ => power,
+    };
+    (lower as i64) * amount.signum() as i64
+}
+
+// The amounts a bin holds, from and up to
+fn bounds(bin: i64) -> (i64, i64) {
+    let next = |lower: i64| {
+        let power = 10i64.pow(lower.ilog10());
+        match lower / power {
+            1 => 2 * power,
+            2 => 5 * power,
+            _ => 10 * power,
+        }
+    };
+    match bin {
+        0 => (-1, 1),
+        bin if bin > 0 => (bin, next(bin)),
+        bin => (-next(-bin), bin),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,120 @@
+use crate::{address::Address, model::Transaction, temporal::DateRange};
+use chrono::{DateTime, Duration, FixedOffset};
+use chrono_tz::Tz;
+use rand::{Rng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+
+// Slowly changing customers and merchants: over the date range some
+// customers move house and some merchants rebrand, so rows after the change
+// carry the new billing address or merchant name, and relational output
+// keeps each version with the times it was in effect (SCD Type 2). Nothing
+// changes unless a rate is above 0.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScdConfig {
+    // Share of pooled customers who move to a new billing address in their
+    // country, and chance of each moving again after that
+    pub address_change_rate: f64,
+    // Share of merchants that rebrand under a new name, and chance of each
+    // rebranding again after that
+    pub merchant_rename_rate: f64,
+}
+
+impl ScdConfig {
+    // Each rate with its name, for validation
+    pub fn rates(&self) -> [(&'static str, f64); 2] {
+        [
+            ("address_change_rate", self.address_change_rate),
+            ("merchant_rename_rate", self.merchant_rename_rate),
+        ]
+    }
+}
+
+// Values something changes to, each with the time it takes effect, in order
+pub type Changes<T> = Vec<(DateTime<Tz>, T)>;
+
+// Most times a customer or merchant changes over a dataset
+const MAX_CHANGES: usize = 3;
+
+// Words merchants add to or put before their name when they rebrand
+const SUFFIXES: [&str; 8] = [
+    "& Co",
+    "Group",
+    "Collective",
+    "Studio",
+    "Express",
+    "Market",
+    "Direct",
+    "Online",
+];
+const PREFIXES: [&str; 3] = ["The", "New", "My"];
+
+// Times within the date range that something changes, in order: the first
+// with the given chance, and each further one with that chance again
+pub fn change_times<R: Rng + ?Sized>(
+    rng: &mut R,
+    rate: f64,
+    range: &DateRange,
+) -> Vec<DateTime<Tz>> {
+    let span = (range.end - range.start).num_seconds().max(1);
+    let mut times = Vec::new();
+    while times.len() < MAX_CHANGES && rng.gen_bool(rate) {
+        times.push(range.start + Duration::seconds(rng.gen_range(1..=span)));
+    }
+    times.sort();
+    times
+}
+
+// The last of the values changed to by a time, if any took effect by then
+pub fn latest<'a, T>(changes: &'a Changes<T>, time: &DateTime<FixedOffset>) -> Option<&'a T> {
+    changes
+        .iter()
+        .rev()
+        .find(|(at, _)| at <= time)
+        .map(|(_, value)| value)
+}
+
+// Bill a transaction to the address its customer moved to. Goods shipped to
+// the old billing address go to the new one instead.
+pub fn relocate(tx: &mut Transaction, address: &Address) {
+    let shipped_home = tx.shipping_street.as_ref() == Some(&tx.billing_street)
+        && tx.shipping_postal_code.as_ref() == Some(&tx.billing_postal_code);
+    tx.billing_street = address.street.clone();
+    tx.billing_city = address.city.clone();
+    tx.billing_region = address.region.clone();
+    tx.billing_postal_code = address.postal_code.clone();
+    tx.billing_country = address.country.clone();
+    if shipped_home {
+        address.assign_shipping(tx);
+    }
+}
+
+// A name a merchant rebrands to: its name with a word added before or after
+// it, in place of any word an earlier rebrand added
+pub fn rename<R: Rng + ?Sized>(rng: &mut R, current: &str) -> String {
+    let base = base_name(current);
+    loop {
+        let name = if rng.gen_bool(0.7) {
+            format!("{} {}", base, SUFFIXES.choose(rng).unwrap())
+        } else {
+            format!("{} {}", PREFIXES.choose(rng).unwrap(), base)
+        };
+        if name != current {
+            return name;
+        }
+    }
+}
+
+// A merchant's name without the word a rebrand added to it
+fn base_name(name: &str) -> &str {
+    let base = PREFIXES
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix)?.strip_prefix(' '))
+        .or_else(|| {
+            SUFFIXES
+                .iter()
+                .find_map(|suffix| name.strip_suffix(suffix)?.strip_suffix(' '))
+        })
+        .unwrap_or(name);
+    if base.is_empty() { name } else { base }
+}
@@ -0,0 +1,368 @@
+use crate::{
+    config::Profile,
+    currency,
+    dispute::{self, ChargebackReason, DisputeEvent, DisputeStatus},
+    fraud::FraudPattern,
+    generator::{Generator, Order},
+    model::{Channel, Customer, DeclineReason, Transaction, TransactionStatus},
+    money::Money,
+    throttle,
+};
+use chrono::{DateTime, Duration, FixedOffset};
+use chrono_tz::Tz;
+use rand::Rng;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io, path::Path};
+
+// Time between rows of a scenario that doesn't give one
+const DEFAULT_GAP: &str = "1h";
+
+// A script of what named customers do, step by step, which is played out
+// with synthesized cardholders, cards and merchants:
+//
+//   start: 2025-03-01T09:00:00Z
+//   steps:
+//     - { customer: alice, merchant: MER001, count: 3, gap: 2h }
+//     - { customer: alice, merchant: MER001, amount: 900, status: declined,
+//         decline_reason: insufficient_funds }
+//     - { customer: alice, action: chargeback, reason: not_received }
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    // Time of the first row; the start of the date range by default
+    #[serde(default)]
+    pub start: Option<DateTime<FixedOffset>>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    // Rows paid at a merchant
+    #[default]
+    Purchase,
+    // A refund of the customer's last approved purchase, of what's left of
+    // it unless an amount is given
+    Refund,
+    // A chargeback of the customer's last approved purchase, written to the
+    // disputes file
+    Chargeback,
+}
+
+// One behavior of a customer. Values that aren't given are drawn from the
+// profile, except that purchases are approved unless scripted otherwise.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    // Name the customer is referred to by in the scenario; the same name is
+    // the same cardholder and card throughout
+    pub customer: String,
+    #[serde(default)]
+    pub action: Action,
+    // Merchant ID or name from the profile; the merchant of the customer's
+    // previous purchase by default
+    #[serde(default)]
+    pub merchant: Option<String>,
+    // Number of purchases made
+    #[serde(default = "one")]
+    pub count: usize,
+    // Time before each row, such as 10m or 2d
+    #[serde(default)]
+    pub gap: Option<String>,
+    #[serde(default)]
+    pub status: Option<TransactionStatus>,
+    #[serde(default)]
+    pub decline_reason: Option<DeclineReason>,
+    // In major units of the currency
+    #[serde(default)]
+    pub amount: Option<f64>,
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub channel: Option<Channel>,
+    // Labels the purchases as fraud of this pattern
+    #[serde(default)]
+    pub fraud: Option<FraudPattern>,
+    // Chargebacks only: why and how far the dispute goes
+    #[serde(default)]
+    pub reason: Option<ChargebackReason>,
+    #[serde(default)]
+    pub outcome: Option<DisputeStatus>,
+}
+
+fn one() -> usize {
+    1
+}
+
+// The rows a scenario played out to, in order, and the disputes its
+// chargebacks opened
+pub struct Script {
+    pub transactions: Vec<Transaction>,
+    pub disputes: Vec<DisputeEvent>,
+}
+
+// What the scenario has made of one of its customers so far
+struct Actor {
+    customer: Customer,
+    merchant_index: Option<usize>,
+    // Last approved purchase, with what's left to refund of it
+    purchase: Option<(Transaction, Money)>,
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let scenario: Scenario = serde_yaml::from_str(&contents).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid scenario {}: {}", path.display(), e),
+            )
+        })?;
+        Ok(scenario)
+    }
+
+    // Check every step against the profile, so a scenario fails before
+    // anything is written
+    pub fn validate(&self, profile: &Profile) -> io::Result<()> {
+        let invalid = |step: usize, message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("scenario step {}: {}", step + 1, message),
+            )
+        };
+        if self.steps.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "scenario has no steps",
+            ));
+        }
+        let mut purchased = Vec::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            if let Some(merchant) = &step.merchant
+                && merchant_index(profile, merchant).is_none()
+            {
+                return Err(invalid(
+                    i,
+                    format!("no merchant {:?} in the profile", merchant),
+                ));
+            }
+            if let Some(gap) = &step.gap {
+                throttle::parse_duration(gap).map_err(|e| invalid(i, e))?;
+            }
+            if step
+                .amount
+                .is_some_and(|amount| !amount.is_finite() || amount <= 0.0)
+            {
+                return Err(invalid(i, "amount must be above 0".to_string()));
+            }
+            if let Some(currency) = &step.currency
+                && !profile.currency_codes().contains(currency)
+            {
+                return Err(invalid(
+                    i,
+                    format!("no currency {:?} in the profile", currency),
+                ));
+            }
+            match step.action {
+                Action::Purchase => {
+                    if step.count == 0 {
+                        return Err(invalid(i, "count must be at least 1".to_string()));
+                    }
+                    let status = step.status.unwrap_or(TransactionStatus::Approved);
+                    if status == TransactionStatus::Refunded {
+                        return Err(invalid(
+                            i,
+                            "refunds are scripted with `action: refund`".to_string(),
+                        ));
+                    }
+                    if step.decline_reason.is_some() && status != TransactionStatus::Declined {
+                        return Err(invalid(
+                            i,
+                            "decline_reason is for declined purchases".to_string(),
+                        ));
+                    }
+                    if step.reason.is_some() || step.outcome.is_some() {
+                        return Err(invalid(
+                            i,
+                            "reason and outcome are for chargebacks".to_string(),
+                        ));
+                    }
+                    if status == TransactionStatus::Approved {
+                        purchased.push(&step.customer);
+                    }
+                }
+                Action::Refund | Action::Chargeback => {
+                    if !purchased.contains(&&step.customer) {
+                        return Err(invalid(
+                            i,
+                            format!("{:?} has no approved purchase before it", step.customer),
+                        ));
+                    }
+                    let purchase_only = [
+                        ("merchant", step.merchant.is_some()),
+                        ("count", step.count != 1),
+                        ("status", step.status.is_some()),
+                        ("decline_reason", step.decline_reason.is_some()),
+                        ("currency", step.currency.is_some()),
+                        ("channel", step.channel.is_some()),
+                        ("fraud", step.fraud.is_some()),
+                    ];
+                    if let Some((name, _)) = purchase_only.iter().find(|(_, given)| *given) {
+                        return Err(invalid(i, format!("{} is for purchases", name)));
+                    }
+                    if step.action == Action::Refund
+                        && (step.reason.is_some() || step.outcome.is_some())
+                    {
+                        return Err(invalid(
+                            i,
+                            "reason and outcome are for chargebacks".to_string(),
+                        ));
+                    }
+                    if step.action == Action::Chargeback && step.amount.is_some() {
+                        return Err(invalid(
+                            i,
+                            "chargebacks are for the whole purchase".to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Play the scenario out, drawing what it leaves open from the profile
+    pub fn run<R: Rng + ?Sized>(&self, rng: &mut R, profile: &Profile) -> io::Result<Script> {
+        let generator = Generator::new(profile, rng);
+        let date_range = profile.date_range();
+        let tz: Tz = date_range.end.timezone();
+        let mut at = match self.start {
+            Some(start) => start.with_timezone(&tz),
+            None => date_range.start,
+        };
+        let mut actors: HashMap<&str, Actor> = HashMap::new();
+        let mut script = Script {
+            transactions: Vec::new(),
+            disputes: Vec::new(),
+        };
+        for (i, step) in self.steps.iter().enumerate() {
+            let gap = throttle::parse_duration(step.gap.as_deref().unwrap_or(DEFAULT_GAP))
+                .ok()
+                .and_then(|gap| Duration::from_std(gap).ok())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("scenario step {}: gap is too long", i + 1),
+                    )
+                })?;
+            let actor = actors.entry(&step.customer).or_insert_with(|| Actor {
+                customer: generator.customer(rng),
+                merchant_index: None,
+                purchase: None,
+            });
+            match step.action {
+                Action::Purchase => {
+                    let merchant_index = match &step.merchant {
+                        Some(merchant) => {
+                            merchant_index(profile, merchant).expect("merchants are validated")
+                        }
+                        None => actor
+                            .merchant_index
+                            .unwrap_or_else(|| rng.gen_range(0..profile.merchants.len())),
+                    };
+                    actor.merchant_index = Some(merchant_index);
+                    let status = step.status.unwrap_or(TransactionStatus::Approved);
+                    for _ in 0..step.count {
+                        at += gap;
+                        let currency = match &step.currency {
+                            Some(currency) => currency.into(),
+                            None => generator.currency(rng),
+                        };
+                        let amount = match step.amount {
+                            Some(amount) => amount,
+                            None => {
+                                let category = &profile.merchants[merchant_index].category;
+                                profile.amounts.for_category(category).sample(rng)
+                                    * currency::amount_scale(&currency)
+                            }
+                        };
+                        let amount = Money::from_major(amount, &currency).to_major();
+                        let channel = step.channel.unwrap_or_else(|| generator.channel(rng));
+                        let mut tx = generator.scripted(
+                            rng,
+                            Order {
+                                customer: &actor.customer,
+                                merchant_index,
+                                channel,
+                                currency,
+                                amount,
+                                transaction_date: at,
+                                status,
+                                decline_reason: step.decline_reason.clone(),
+                            },
+                        );
+                        if let Some(pattern) = step.fraud {
+                            tx.is_fraud = true;
+                            tx.fraud_pattern = Some(pattern);
+                        }
+                        if tx.status == TransactionStatus::Approved {
+                            let amount = Money::from_major(tx.amount, &tx.currency);
+                            actor.purchase = Some((tx.clone(), amount));
+                        }
+                        script.transactions.push(tx);
+                    }
+                }
+                Action::Refund => {
+                    at += gap;
+                    let (original, remaining) =
+                        actor.purchase.as_mut().expect("refunds are validated");
+                    let refunded = match step.amount {
+                        Some(amount) => Money::from_major(amount, &original.currency),
+                        None => *remaining,
+                    };
+                    if refunded.minor_units > remaining.minor_units || refunded.minor_units == 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "scenario step {}: {} left to refund of {}'s last purchase",
+                                i + 1,
+                                remaining.to_major(),
+                                step.customer
+                            ),
+                        ));
+                    }
+                    *remaining = *remaining - refunded;
+                    let refund = generator.refund_of(rng, original, refunded, *remaining, at);
+                    script.transactions.push(refund);
+                }
+                Action::Chargeback => {
+                    at += gap;
+                    let (original, _) = actor.purchase.as_ref().expect("chargebacks are validated");
+                    script.disputes.extend(dispute::chargeback(
+                        rng,
+                        original,
+                        step.reason.unwrap_or_default(),
+                        step.outcome.unwrap_or(DisputeStatus::Opened),
+                        at.fixed_offset(),
+                    ));
+                }
+            }
+        }
+        Ok(script)
+    }
+
+    // Whether any step opens a dispute, which needs a disputes file
+    pub fn has_chargebacks(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|step| step.action == Action::Chargeback)
+    }
+}
+
+// Index of the profile's merchant with this ID, or else this name
+fn merchant_index(profile: &Profile, merchant: &str) -> Option<usize> {
+    let merchants = &profile.merchants;
+    merchants
+        .iter()
+        .position(|m| m.id == merchant)
+        .or_else(|| merchants.iter().position(|m| m.name == merchant))
+}
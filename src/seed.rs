@@ -0,0 +1,120 @@
+use crate::{
+    config::Profile,
+    drift::Drift,
+    fraud::FraudInjector,
+    generator::{Counters, Generator},
+    id,
+    model::Transaction,
+};
+use rand::{SeedableRng, rngs::StdRng};
+use std::{collections::VecDeque, iter};
+
+// Rows generated from each seed of a seeded run. Rows are addressed by
+// block, so row N only needs its block generated, while refunds, renewals
+// and fraud bursts still have the rest of the block to point back to.
+pub const BLOCK_ROWS: u64 = 1000;
+
+// Transaction IDs set aside for each block: its rows, with room for the
+// renewals, installments and pre-authorization steps they schedule
+const BLOCK_IDS: u64 = BLOCK_ROWS * 64;
+
+// Customers, devices and card numbers set aside for each block
+const BLOCK_NUMBERS: u64 = BLOCK_ROWS * 4;
+
+// Streams of randomness drawn from a seed besides its blocks
+const POOL_STREAM: u64 = u64::MAX;
+const KEY_STREAM: u64 = u64::MAX - 1;
+
+// Run `f` with a generator seeded from `seed` to draw from and IDs,
+// customers and cards numbered from the start, as scenarios are played out
+pub fn with_seed<T>(seed: u64, f: impl FnOnce(&mut StdRng) -> T) -> T {
+    id::restore_sequence(mix(seed, KEY_STREAM), 0);
+    Counters::default().restore();
+    f(&mut StdRng::seed_from_u64(mix(seed, POOL_STREAM)))
+}
+
+// The seed of one stream of a run's seed (SplitMix64's finalizer), so
+// neighbouring blocks draw unrelated values
+fn mix(seed: u64, stream: u64) -> u64 {
+    let mut z = seed ^ stream.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// The rows of a seeded run from a given row on. Row N is the same whichever
+// row the stream starts from, so separate jobs can each generate a range of
+// rows that joins up with the others' into the rows of a single run.
+pub struct SeededRows<'a> {
+    profile: &'a Profile,
+    generator: Generator<'a>,
+    seed: u64,
+    // Counters once the customer pool has been generated, which every
+    // block numbers on from
+    pool: Counters,
+    // Next block to generate
+    block: u64,
+    rows: VecDeque<Transaction>,
+}
+
+impl<'a> SeededRows<'a> {
+    pub fn new(profile: &'a Profile, seed: u64, first: u64) -> Self {
+        id::restore_sequence(mix(seed, KEY_STREAM), 0);
+        Counters::default().restore();
+        let generator = Generator::new(profile, &mut StdRng::seed_from_u64(mix(seed, POOL_STREAM)));
+        let mut rows = Self {
+            profile,
+            generator,
+            seed,
+            pool: Counters::current(),
+            block: first / BLOCK_ROWS,
+            rows: VecDeque::new(),
+        };
+        rows.fill();
+        rows.rows.drain(..(first % BLOCK_ROWS) as usize);
+        rows
+    }
+
+    // Generate the next block, from where its seed and numbering put it
+    fn fill(&mut self) {
+        let profile = self.profile;
+        let generator = &mut self.generator;
+        id::restore_sequence(mix(self.seed, KEY_STREAM), self.block * BLOCK_IDS);
+        self.pool.offset(self.block * BLOCK_NUMBERS).restore();
+        generator.reset();
+        // The block's rows and the fraud injected into them each draw from a
+        // generator of their own, seeded from the block's seed
+        let mut rng = StdRng::seed_from_u64(mix(self.seed, self.block));
+        let fraud_rng = StdRng::from_rng(&mut rng).expect("StdRng seeds from another");
+        let rows = FraudInjector::new(
+            iter::from_fn(|| Some(generator.transaction(&mut rng))),
+            &profile.fraud,
+            profile.id_strategy,
+        )
+        .with_rng(fraud_rng)
+        .with_target_ratio(profile.ml.label_ratio)
+        .with_drift(Drift::of(profile))
+        .with_user_agents(&profile.user_agents, &profile.user_agent_weights())
+        .take(BLOCK_ROWS as usize);
+        self.rows.extend(rows);
+        self.block += 1;
+    }
+}
+
+impl Iterator for SeededRows<'_> {
+    type Item = Transaction;
+
+    fn next(&mut self) -> Option<Transaction> {
+        if self.rows.is_empty() {
+            self.fill();
+        }
+        self.rows.pop_front()
+    }
+}
+
+// Row `n` of the run seeded with `seed`
+pub fn generate_nth(profile: &Profile, seed: u64, n: u64) -> Transaction {
+    SeededRows::new(profile, seed, n)
+        .next()
+        .expect("seeded rows never run out")
+}
@@ -0,0 +1,201 @@
+use crate::{config::Merchant, throttle};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
+
+// Merchant sequences: a pooled customer's next purchase follows their
+// previous one some time later, at a merchant whose category is drawn from
+// the row of a transition matrix for the previous category, so rows of a
+// customer read like a day out (groceries, then a stop at the convenience
+// store, then coffee) rather than independent draws. Nothing chains unless
+// `transitions` has a row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SequenceConfig {
+    // Weights of the next purchase's category after a purchase in each
+    // category. Categories left out of a row are never next, and purchases
+    // in a category without a row are followed by an independent draw.
+    pub transitions: BTreeMap<String, BTreeMap<String, f64>>,
+    // Mean time between a customer's consecutive purchases, such as 6h or
+    // 2d. A chain that would run past the end of the date range starts
+    // over at a time and merchant drawn as usual.
+    pub gap: String,
+}
+
+impl Default for SequenceConfig {
+    fn default() -> Self {
+        Self {
+            transitions: BTreeMap::new(),
+            gap: "1d".to_string(),
+        }
+    }
+}
+
+impl SequenceConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.transitions.is_empty()
+    }
+
+    // Mean gap between consecutive purchases; validated on load
+    pub fn mean_gap(&self) -> Duration {
+        throttle::parse_duration(&self.gap).expect("sequence gap is validated")
+    }
+
+    // Reasons the matrix can't be drawn from with these merchants, if any
+    pub fn check(&self, merchants: &[Merchant]) -> Result<(), String> {
+        let gap = throttle::parse_duration(&self.gap).map_err(|e| format!("gap: {}", e))?;
+        if gap.is_zero() {
+            return Err("gap must be above 0".to_string());
+        }
+        if chrono::Duration::from_std(gap).is_err() {
+            return Err("gap is too long".to_string());
+        }
+        let known = |category: &str| merchants.iter().any(|m| m.category == category);
+        for (from, row) in &self.transitions {
+            if !known(from) {
+                return Err(format!(
+                    "transitions.\"{}\": no merchant in the category",
+                    from
+                ));
+            }
+            for (to, weight) in row {
+                if !known(to) {
+                    return Err(format!(
+                        "transitions.\"{}\".\"{}\": no merchant in the category",
+                        from, to
+                    ));
+                }
+                if !weight.is_finite() || *weight < 0.0 {
+                    return Err(format!(
+                        "transitions.\"{}\".\"{}\" must be a non-negative number",
+                        from, to
+                    ));
+                }
+            }
+            if !row.values().any(|weight| *weight > 0.0) {
+                return Err(format!("transitions.\"{}\" needs a weight above 0", from));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Transitions between the built-in merchants' categories used by
+// `--merchant-sequences`: errands lead on to one another, eating out
+// follows shopping, and trips go from the booking to the car and hotel.
+// Categories none of the merchants are in are left out.
+pub fn builtin_transitions(merchants: &[Merchant]) -> BTreeMap<String, BTreeMap<String, f64>> {
+    let known = |category: &str| merchants.iter().any(|m| m.category == category);
+    let rows: [(&str, &[(&str, f64)]); 12] = [
+        (
+            "Grocery",
+            &[
+                ("Convenience Store", 4.0),
+                ("Food & Beverage", 3.0),
+                ("Health & Fitness", 1.0),
+                ("Retail", 1.0),
+                ("Grocery", 1.0),
+            ],
+        ),
+        (
+            "Convenience Store",
+            &[
+                ("Food & Beverage", 4.0),
+                ("Grocery", 2.0),
+                ("Restaurant", 1.0),
+                ("Retail", 1.0),
+            ],
+        ),
+        (
+            "Food & Beverage",
+            &[
+                ("Books & Media", 2.0),
+                ("Retail", 2.0),
+                ("Clothing", 2.0),
+                ("Grocery", 2.0),
+                ("Food & Beverage", 1.0),
+            ],
+        ),
+        (
+            "Retail",
+            &[
+                ("Clothing", 3.0),
+                ("Food & Beverage", 3.0),
+                ("Electronics", 1.0),
+                ("Restaurant", 1.0),
+                ("Grocery", 1.0),
+            ],
+        ),
+        (
+            "Clothing",
+            &[
+                ("Retail", 3.0),
+                ("Food & Beverage", 3.0),
+                ("Restaurant", 2.0),
+                ("Clothing", 1.0),
+            ],
+        ),
+        (
+            "Electronics",
+            &[
+                ("Retail", 2.0),
+                ("Food & Beverage", 2.0),
+                ("Books & Media", 1.0),
+                ("Grocery", 1.0),
+            ],
+        ),
+        (
+            "Books & Media",
+            &[("Food & Beverage", 4.0), ("Retail", 1.0), ("Grocery", 1.0)],
+        ),
+        (
+            "Health & Fitness",
+            &[
+                ("Food & Beverage", 3.0),
+                ("Grocery", 3.0),
+                ("Convenience Store", 1.0),
+            ],
+        ),
+        (
+            "Restaurant",
+            &[
+                ("Convenience Store", 2.0),
+                ("Grocery", 2.0),
+                ("Food & Beverage", 1.0),
+                ("Retail", 1.0),
+            ],
+        ),
+        (
+            "Travel",
+            &[("Car Rental", 4.0), ("Lodging", 4.0), ("Restaurant", 1.0)],
+        ),
+        (
+            "Car Rental",
+            &[
+                ("Lodging", 4.0),
+                ("Convenience Store", 2.0),
+                ("Restaurant", 2.0),
+            ],
+        ),
+        (
+            "Lodging",
+            &[
+                ("Restaurant", 4.0),
+                ("Food & Beverage", 2.0),
+                ("Travel", 1.0),
+                ("Car Rental", 1.0),
+            ],
+        ),
+    ];
+    rows.iter()
+        .filter(|(from, _)| known(from))
+        .map(|(from, row)| {
+            let row: BTreeMap<String, f64> = row
+                .iter()
+                .filter(|(to, _)| known(to))
+                .map(|(to, weight)| (to.to_string(), *weight))
+                .collect();
+            (from.to_string(), row)
+        })
+        .filter(|(_, row)| !row.is_empty())
+        .collect()
+}
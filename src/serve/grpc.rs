@@ -0,0 +1,193 @@
+use super::{Body, DEFAULT_COUNT, Feed, Query};
+use crate::{config::Profile, model::Transaction, output};
+use bytes::{Buf, BufMut};
+use http::{Request, Response};
+use http_body_util::BodyExt;
+use hyper::body::Incoming;
+use std::{future, sync::Arc};
+use tokio::task;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{
+    Status,
+    codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
+    server::{Grpc, ServerStreamingService},
+};
+
+// The one method of the `Transactions` service in `luhnsynth schema --proto`
+const STREAM_METHOD: &str = "/luhnsynth.Transactions/Stream";
+
+// Largest request message accepted
+const MAX_REQUEST: usize = 64 * 1024;
+
+// Rows queued for a call ahead of the connection sending them
+const QUEUED_ROWS: usize = 256;
+
+// Answer a gRPC call
+pub async fn call(request: Request<Incoming>, profile: Arc<Profile>) -> Response<Body> {
+    if request.uri().path() != STREAM_METHOD {
+        let message = format!("unknown method {}", request.uri().path());
+        return Status::unimplemented(message).into_http();
+    }
+    let mut grpc = Grpc::new(TransactionCodec).max_decoding_message_size(MAX_REQUEST);
+    let response = grpc.server_streaming(Stream(profile), request).await;
+    response.map(|body| body.map_err(Into::into).boxed_unsync())
+}
+
+// The `Stream` method: each transaction the query asks for, as a message
+// of its own
+struct Stream(Arc<Profile>);
+
+impl ServerStreamingService<Query> for Stream {
+    type Response = Transaction;
+    type ResponseStream = ReceiverStream<Result<Transaction, Status>>;
+    type Future = future::Ready<Result<tonic::Response<Self::ResponseStream>, Status>>;
+
+    fn call(&mut self, request: tonic::Request<Query>) -> Self::Future {
+        let query = request.into_inner();
+        let profile = match query.profile(&self.0) {
+            Ok(profile) => profile,
+            Err(message) => return future::ready(Err(Status::invalid_argument(message))),
+        };
+        let (feed, rows) = Feed::new(QUEUED_ROWS);
+        task::spawn_blocking(move || {
+            for tx in super::rows(&profile, &query) {
+                if feed.send(Ok(tx)).is_err() {
+                    break;
+                }
+            }
+        });
+        future::ready(Ok(tonic::Response::new(ReceiverStream::new(rows))))
+    }
+}
+
+// Messages of the `Transactions` service: a `StreamRequest` read into a
+// query, and `Transaction` messages written as protobuf output writes them
+struct TransactionCodec;
+
+impl Codec for TransactionCodec {
+    type Encode = Transaction;
+    type Decode = Query;
+    type Encoder = TransactionCodec;
+    type Decoder = TransactionCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        TransactionCodec
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        TransactionCodec
+    }
+}
+
+impl Encoder for TransactionCodec {
+    type Item = Transaction;
+    type Error = Status;
+
+    fn encode(&mut self, tx: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        let mut message = Vec::new();
+        output::encode_transaction(&tx, &mut message)?;
+        dst.put_slice(&message);
+        Ok(())
+    }
+}
+
+impl Decoder for TransactionCodec {
+    type Item = Query;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Query>, Status> {
+        let message = src.copy_to_bytes(src.remaining());
+        decode_request(&message).map(Some).map_err(Status::invalid_argument)
+    }
+}
+
+// The query in a `StreamRequest` message
+fn decode_request(mut message: &[u8]) -> Result<Query, String> {
+    let mut query = Query {
+        count: Some(DEFAULT_COUNT),
+        ..Query::default()
+    };
+    while !message.is_empty() {
+        let key = varint(&mut message)?;
+        match (key >> 3, key & 7) {
+            (1, 0) => match varint(&mut message)? {
+                0 => {}
+                count => query.count = Some(count as usize),
+            },
+            (2, 2) => {
+                let brand = bytes(&mut message)?;
+                let brand = String::from_utf8(brand.to_vec()).map_err(|_| "brand isn't UTF-8")?;
+                query.brand = Some(brand).filter(|brand| !brand.is_empty());
+            }
+            (3, 0) => query.seed = Some(varint(&mut message)?),
+            (4, 0) => query.start = varint(&mut message)?,
+            // Fields of later versions of the message are skipped
+            (_, 0) => {
+                varint(&mut message)?;
+            }
+            (_, 1) => message = message.get(8..).ok_or("truncated request message")?,
+            (_, 2) => {
+                bytes(&mut message)?;
+            }
+            (_, 5) => message = message.get(4..).ok_or("truncated request message")?,
+            _ => return Err("malformed request message".to_string()),
+        }
+    }
+    Ok(query)
+}
+
+fn varint(message: &mut &[u8]) -> Result<u64, String> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = message.split_first().ok_or("truncated request message")?;
+        *message = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("malformed request message".to_string())
+}
+
+fn bytes<'a>(message: &mut &'a [u8]) -> Result<&'a [u8], String> {
+    let length = varint(message)? as usize;
+    let (bytes, rest) = message
+        .split_at_checked(length)
+        .ok_or("truncated request message")?;
+    *message = rest;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_decoded() {
+        let default = Query {
+            count: Some(DEFAULT_COUNT),
+            ..Query::default()
+        };
+        assert_eq!(decode_request(&[]).unwrap(), default);
+        // count 300, brand "visa", seed 7, start 2, then a string field 9
+        // from a later version
+        let message = [
+            0x08, 0xac, 0x02, 0x12, 4, b'v', b'i', b's', b'a', 0x18, 7, 0x20, 2, 0x4a, 1, b'x',
+        ];
+        assert_eq!(
+            decode_request(&message).unwrap(),
+            Query {
+                count: Some(300),
+                brand: Some("visa".to_string()),
+                seed: Some(7),
+                start: 2,
+            }
+        );
+        // A count of 0 is an unset field
+        assert_eq!(decode_request(&[0x08, 0]).unwrap(), default);
+
+        assert!(decode_request(&[0x08]).is_err());
+        assert!(decode_request(&[0x12, 5, b'v']).is_err());
+        assert!(decode_request(&[0x0b]).is_err());
+    }
+}
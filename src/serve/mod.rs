@@ -0,0 +1,492 @@
+mod grpc;
+mod rest;
+mod websocket;
+
+use crate::{
+    config::Profile,
+    custom::CustomFields,
+    drift::Drift,
+    fraud::FraudInjector,
+    generator::{Counters, TransactionStream},
+    id, lifecycle,
+    model::Transaction,
+    nulls::Nulls,
+    seed::SeededRows,
+};
+use bytes::Bytes;
+use http::{Request, Response, header::CONTENT_TYPE};
+use http_body_util::combinators::UnsyncBoxBody;
+use hyper::{body::Incoming, service::service_fn};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::conn::auto,
+};
+use rand::{SeedableRng, rngs::StdRng};
+use std::{
+    convert::Infallible,
+    error::Error,
+    io, iter,
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    runtime::{Builder, Handle},
+    sync::mpsc::{self, error::SendTimeoutError},
+    time,
+};
+use tracing::{debug, info, warn};
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+// Body of every response, whether a whole message or rows streamed out
+pub type Body = UnsyncBoxBody<Bytes, BoxError>;
+
+// Rows a request gets when it doesn't ask for a number, and the most it can
+pub const DEFAULT_COUNT: usize = 100;
+pub const MAX_COUNT: usize = 1_000_000;
+
+// Rows generated at a time while the generation lock is held, so requests
+// served side by side take turns
+const BATCH_ROWS: usize = 256;
+
+// Connections served at once, each on a task of its own. Connections past
+// it are closed as they come in.
+const MAX_CONNECTIONS: usize = 256;
+
+// Largest request head (request line and headers, or an HTTP/2 header
+// list) accepted, and most headers
+const MAX_HEAD: usize = 16 * 1024;
+const MAX_HEADERS: usize = 100;
+
+// Streams an HTTP/2 client may have open at once; more are refused
+const MAX_STREAMS: u32 = 100;
+
+// How long a connection waits for the client to send a request or take
+// what's sent to it before giving up on it
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// Generators number IDs, customers and cards from counters shared by the
+// whole process, which seeded rows set to the numbers of their block
+static GENERATION: Mutex<()> = Mutex::new(());
+
+// What a request asks for, whether in a REST query string or a gRPC message
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    // Rows to send; a stream without a count goes on until the client stops
+    pub count: Option<usize>,
+    // Only cards of this brand, matched regardless of case and spacing
+    pub brand: Option<String>,
+    // Rows of the run with this seed, from row `start` on
+    pub seed: Option<u64>,
+    pub start: u64,
+}
+
+impl Query {
+    // The profile rows for this query are generated with, or why there's
+    // none, to send back to the client
+    pub fn profile(&self, base: &Profile) -> Result<Profile, String> {
+        if self.count.is_some_and(|count| count > MAX_COUNT) {
+            return Err(format!("count can be at most {}", MAX_COUNT));
+        }
+        if self.seed.is_none() && self.start > 0 {
+            return Err("start only applies to seeded requests".to_string());
+        }
+        if self.seed.is_some() && base.date_to.is_none() {
+            return Err(
+                "seeded requests need the server started with --date-to (or date_to in the \
+                 config)"
+                    .to_string(),
+            );
+        }
+        if self.seed.is_some() && base.lifecycle {
+            return Err("seeded requests can't be served with lifecycle events".to_string());
+        }
+        let mut profile = base.clone();
+        if let Some(brand) = &self.brand {
+            let key = |name: &str| -> String {
+                name.chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .flat_map(char::to_lowercase)
+                    .collect()
+            };
+            profile.card_brands.retain(|b| key(&b.name) == key(brand));
+            if profile.card_brands.is_empty() {
+                let names: Vec<_> = base.card_brands.iter().map(|b| b.name.as_str()).collect();
+                return Err(format!(
+                    "unknown brand {:?}; expected one of {}",
+                    brand,
+                    names.join(", ")
+                ));
+            }
+            profile.weights.card_brands.clear();
+            profile.validate().map_err(|e| e.to_string())?;
+        }
+        Ok(profile)
+    }
+}
+
+// Serve transactions over HTTP until the process is stopped: REST requests
+// and WebSockets over HTTP/1.1, and gRPC over HTTP/2 without TLS, on the
+// same port
+pub fn run(profile: Profile, addr: SocketAddr) -> io::Result<()> {
+    let runtime = Builder::new_multi_thread().enable_all().build()?;
+    runtime.block_on(async {
+        let listener = TcpListener::bind(addr).await?;
+        info!("Serving transactions on http://{}", listener.local_addr()?);
+        serve(listener, Arc::new(profile)).await
+    })
+}
+
+async fn serve(listener: TcpListener, profile: Arc<Profile>) -> io::Result<()> {
+    let open = Arc::new(AtomicUsize::new(0));
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!(error = %e, "failed to accept a connection");
+                continue;
+            }
+        };
+        let Some(slot) = Slot::take(&open) else {
+            warn!(?peer, "too many connections; closing a new one");
+            continue;
+        };
+        let profile = Arc::clone(&profile);
+        tokio::spawn(async move {
+            let _slot = slot;
+            if let Err(e) = connection(stream, profile).await {
+                debug!(?peer, error = %e, "connection closed");
+            }
+        });
+    }
+}
+
+// One of the connections served at once, given back when its task ends,
+// however it ends
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    fn take(open: &Arc<AtomicUsize>) -> Option<Self> {
+        if open.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            open.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+        Some(Self(Arc::clone(open)))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// Serve the requests of a connection, telling HTTP/2 clients without TLS
+// from HTTP/1.1 ones by the preface they open with. HTTP/1.1 connections
+// are closed after a request, and HTTP/2 ones that stop answering pings.
+async fn connection(stream: TcpStream, profile: Arc<Profile>) -> Result<(), BoxError> {
+    // Once a client starts on a request, the server gives it as long again
+    // to finish its headers
+    time::timeout(TIMEOUT, stream.peek(&mut [0]))
+        .await
+        .map_err(|_| "no request sent")??;
+    let service = service_fn(move |request| {
+        let profile = Arc::clone(&profile);
+        async move { Ok::<_, Infallible>(respond(request, profile).await) }
+    });
+    let mut builder = auto::Builder::new(TokioExecutor::new());
+    builder
+        .http1()
+        .timer(TokioTimer::new())
+        .keep_alive(false)
+        .header_read_timeout(TIMEOUT)
+        .max_buf_size(MAX_HEAD)
+        .max_headers(MAX_HEADERS);
+    builder
+        .http2()
+        .timer(TokioTimer::new())
+        .max_concurrent_streams(MAX_STREAMS)
+        .max_header_list_size(MAX_HEAD as u32)
+        .keep_alive_interval(TIMEOUT)
+        .keep_alive_timeout(TIMEOUT);
+    builder
+        .serve_connection_with_upgrades(TokioIo::new(stream), service)
+        .await
+}
+
+// gRPC calls are told from REST requests by their content type, as they
+// may be made on any path
+async fn respond(request: Request<Incoming>, profile: Arc<Profile>) -> Response<Body> {
+    let grpc = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/grpc"));
+    match grpc {
+        true => grpc::call(request, profile).await,
+        false => rest::respond(request, profile).await,
+    }
+}
+
+// The sending end of a bounded queue from a thread of the runtime's
+// blocking pool, where rows are generated, to a task sending them to a
+// client. A client that takes nothing for `TIMEOUT` is given up on.
+pub struct Feed<T> {
+    sender: mpsc::Sender<T>,
+    runtime: Handle,
+}
+
+// Cloned by hand, as items needn't be
+impl<T> Clone for Feed<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+impl<T> Feed<T> {
+    // A feed and the queue it fills, made on the runtime
+    pub fn new(capacity: usize) -> (Self, mpsc::Receiver<T>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let runtime = Handle::current();
+        (Self { sender, runtime }, receiver)
+    }
+
+    pub fn send(&self, item: T) -> io::Result<()> {
+        self.runtime
+            .block_on(self.sender.send_timeout(item, TIMEOUT))
+            .map_err(|e| match e {
+                SendTimeoutError::Timeout(_) => {
+                    io::Error::new(io::ErrorKind::TimedOut, "client took nothing for too long")
+                }
+                SendTimeoutError::Closed(_) => {
+                    io::Error::new(io::ErrorKind::BrokenPipe, "client went away")
+                }
+            })
+    }
+}
+
+// The rows a query asks for, generated a batch at a time under the lock.
+// Lifecycle events, masking, custom fields and nulls apply as they do to
+// files.
+pub fn rows<'a>(profile: &'a Profile, query: &Query) -> impl Iterator<Item = Transaction> + 'a {
+    let seeded = query.seed.is_some();
+    let count = query.count.unwrap_or(usize::MAX);
+    let mut generated = locked(seeded, || -> Box<dyn Iterator<Item = Transaction>> {
+        match query.seed {
+            Some(seed) => Box::new(SeededRows::new(profile, seed, query.start)),
+            None => Box::new(
+                FraudInjector::new(
+                    TransactionStream::new(profile, count),
+                    &profile.fraud,
+                    profile.id_strategy,
+                )
+                .with_target_ratio(profile.ml.label_ratio)
+                .with_drift(Drift::of(profile))
+                .with_user_agents(&profile.user_agents, &profile.user_agent_weights()),
+            ),
+        }
+    });
+    // Live rows are stamped with the time they're generated at, so they're
+    // generated as they go out
+    let batch_rows = match profile.live_until {
+        Some(_) => 1,
+        None => BATCH_ROWS,
+    };
+    let mut left = count;
+    let mut batch = Vec::new().into_iter();
+    let masking = profile.masking.is_enabled().then_some(&profile.masking);
+    let custom = CustomFields::new(&profile.custom_fields, profile.amount_format);
+    let nulls = Nulls::new(&profile.nulls);
+    let (mut lifecycle_rng, mut rng) = (StdRng::from_entropy(), StdRng::from_entropy());
+    iter::from_fn(move || {
+        if batch.len() == 0 && left > 0 {
+            let rows: Vec<_> = locked(seeded, || {
+                generated.by_ref().take(left.min(batch_rows)).collect()
+            });
+            left -= rows.len();
+            batch = rows.into_iter();
+        }
+        batch.next()
+    })
+    .flat_map(move |tx| match profile.lifecycle {
+        true => lifecycle::events(&mut lifecycle_rng, &tx),
+        false => vec![tx],
+    })
+    .map(move |mut tx| {
+        if let Some(masking) = masking {
+            masking.apply(&mut tx);
+        }
+        if let Some(custom) = custom.as_ref() {
+            custom.apply(&mut rng, &mut tx);
+        }
+        if let Some(nulls) = nulls.as_ref() {
+            nulls.apply(&mut rng, &mut tx);
+        }
+        tx
+    })
+}
+
+// Run `f` holding the generation lock. Seeded rows number from their block,
+// so the counters are put back afterwards for unseeded rows to carry on from.
+fn locked<T>(seeded: bool, f: impl FnOnce() -> T) -> T {
+    let _lock = GENERATION.lock().unwrap_or_else(|e| e.into_inner());
+    if !seeded {
+        return f();
+    }
+    let (key, sequence) = id::sequence();
+    let counters = Counters::current();
+    let result = f();
+    id::restore_sequence(key, sequence);
+    counters.restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        runtime::Runtime,
+    };
+    use tokio_tungstenite::{
+        WebSocketStream,
+        tungstenite::{Message, protocol::Role},
+    };
+
+    // A runtime serving the default profile, and the address it listens on
+    fn server() -> (Runtime, SocketAddr) {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let addr = runtime.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(serve(listener, Arc::new(Profile::default())));
+            addr
+        });
+        (runtime, addr)
+    }
+
+    // What the server answers a request with, head and body
+    async fn get(addr: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn rest_requests_are_answered() {
+        let (runtime, addr) = server();
+        runtime.block_on(async {
+            let request = |target: &str| {
+                format!("GET {} HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n", target)
+            };
+            let response = get(addr, &request("/transactions?count=3&format=jsonl")).await;
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+            assert!(response.contains("content-type: application/x-ndjson\r\n"));
+            assert_eq!(response.matches("\"transaction_id\"").count(), 3);
+
+            let response = get(addr, &request("/health")).await;
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+            assert!(response.ends_with("\r\n\r\nok\n"));
+            let response = get(addr, &request("/transactions?count=x")).await;
+            assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+            let response = get(addr, &request("/nowhere")).await;
+            assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+            let response = get(addr, &request("/stream")).await;
+            assert!(response.starts_with("HTTP/1.1 426 Upgrade Required\r\n"));
+        });
+    }
+
+    #[test]
+    fn rows_are_pushed_over_websockets() {
+        let (runtime, addr) = server();
+        runtime.block_on(async {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            // The key and answer from RFC 6455
+            let request = "GET /stream?rate=100&count=3 HTTP/1.1\r\nHost: test\r\n\
+                           Connection: Upgrade\r\nUpgrade: websocket\r\n\
+                           Sec-WebSocket-Version: 13\r\n\
+                           Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                head.push(stream.read_u8().await.unwrap());
+            }
+            let head = String::from_utf8(head).unwrap();
+            assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+            assert!(head.contains("sec-websocket-accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+            let mut socket = WebSocketStream::from_raw_socket(stream, Role::Client, None).await;
+            let mut rows = 0;
+            while let Some(message) = socket.next().await {
+                match message.unwrap() {
+                    Message::Text(text) => {
+                        assert!(text.starts_with("{\"transaction_id\":"));
+                        rows += 1;
+                    }
+                    Message::Close(_) => break,
+                    _ => {}
+                }
+            }
+            assert_eq!(rows, 3);
+            let _ = socket.close(None).await;
+        });
+    }
+
+    #[test]
+    fn grpc_calls_stream_rows() {
+        let (runtime, addr) = server();
+        runtime.block_on(async {
+            let stream = TcpStream::connect(addr).await.unwrap();
+            let (client, connection) = h2::client::handshake(stream).await.unwrap();
+            tokio::spawn(connection);
+            let mut client = client.ready().await.unwrap();
+            let call = |path: &str| {
+                Request::post(format!("http://test{}", path))
+                    .header(CONTENT_TYPE, "application/grpc")
+                    .header("te", "trailers")
+                    .body(())
+                    .unwrap()
+            };
+
+            // A StreamRequest with a count of 2
+            let (response, mut request) =
+                client.send_request(call("/luhnsynth.Transactions/Stream"), false).unwrap();
+            request
+                .send_data(Bytes::from_static(&[0, 0, 0, 0, 2, 0x08, 2]), true)
+                .unwrap();
+            let mut body = response.await.unwrap().into_body();
+            let mut data = Vec::new();
+            while let Some(chunk) = body.data().await {
+                let chunk = chunk.unwrap();
+                body.flow_control().release_capacity(chunk.len()).unwrap();
+                data.extend_from_slice(&chunk);
+            }
+            let trailers = body.trailers().await.unwrap().unwrap();
+            assert_eq!(trailers["grpc-status"], "0");
+            let mut messages = 0;
+            let mut rest = &data[..];
+            while let Some((prefix, message)) = rest.split_at_checked(5) {
+                let length = u32::from_be_bytes(prefix[1..].try_into().unwrap()) as usize;
+                assert_eq!(prefix[0], 0);
+                rest = &message[length..];
+                messages += 1;
+            }
+            assert_eq!(messages, 2);
+
+            let (response, _) = client.send_request(call("/nowhere"), true).unwrap();
+            let response = response.await.unwrap();
+            assert_eq!(response.headers()["grpc-status"], "12");
+        });
+    }
+}
@@ -0,0 +1,257 @@
+use super::{Body, DEFAULT_COUNT, Feed, Query, websocket};
+use crate::{
+    config::{OutputFormat, Profile},
+    output::{self, Finish},
+    throttle,
+};
+use bytes::Bytes;
+use chrono::Utc;
+use clap::ValueEnum;
+use futures_util::StreamExt;
+use http::{
+    HeaderMap, HeaderValue, Method, Request, Response, StatusCode,
+    header::{CONNECTION, CONTENT_TYPE, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, UPGRADE},
+};
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::{Frame, Incoming};
+use std::{
+    io::{self, Write},
+    mem,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::task;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
+
+// Bytes of a streamed response handed to the connection at a time, and the
+// number of them that can wait for it
+const CHUNK_BYTES: usize = 16 * 1024;
+const QUEUED_CHUNKS: usize = 4;
+
+// Answer a REST request, or upgrade it to a WebSocket on /stream
+pub async fn respond(request: Request<Incoming>, profile: Arc<Profile>) -> Response<Body> {
+    let path = request.uri().path();
+    if !matches!(path, "/transactions" | "/stream" | "/health" | "/schema.proto") {
+        return text(StatusCode::NOT_FOUND, "no such endpoint\n");
+    }
+    if request.method() != Method::GET {
+        return text(StatusCode::METHOD_NOT_ALLOWED, "only GET is allowed\n");
+    }
+    match path {
+        "/health" => text(StatusCode::OK, "ok\n"),
+        "/schema.proto" => text(StatusCode::OK, output::proto_schema(&profile.custom_fields)),
+        "/stream" => stream_rows(request, profile),
+        _ => transactions(&request, profile),
+    }
+}
+
+// GET /transactions?count=&brand=&seed=&start=&format=, streamed out as
+// rows are generated
+fn transactions(request: &Request<Incoming>, profile: Arc<Profile>) -> Response<Body> {
+    let names = ["count", "brand", "seed", "start", "format"];
+    let mut params = match parse(request.uri().query().unwrap_or(""), &names) {
+        Ok(params) => params,
+        Err(message) => return text(StatusCode::BAD_REQUEST, format!("{}\n", message)),
+    };
+    params.query.count.get_or_insert(DEFAULT_COUNT);
+    let profile = match params.query.profile(&profile) {
+        Ok(profile) => profile,
+        Err(message) => return text(StatusCode::BAD_REQUEST, format!("{}\n", message)),
+    };
+    let format = params.format.unwrap_or(OutputFormat::Json);
+    let (feed, chunks) = Feed::new(QUEUED_CHUNKS);
+    task::spawn_blocking(move || {
+        let out = Chunks {
+            buffer: Vec::with_capacity(CHUNK_BYTES),
+            feed: feed.clone(),
+        };
+        let result = output::stream_writer(format, out, &profile).and_then(|mut writer| {
+            for tx in super::rows(&profile, &params.query) {
+                writer.write(&tx)?;
+            }
+            writer.finish()
+        });
+        // A response that fails partway through ends in an error rather
+        // than looking complete
+        if let Err(e) = result {
+            debug!(error = %e, "stopped sending rows");
+            let _ = feed.send(Err(e));
+        }
+    });
+    let body = StreamBody::new(ReceiverStream::new(chunks).map(|chunk| chunk.map(Frame::data)));
+    let mut response = Response::new(body.map_err(Into::into).boxed_unsync());
+    let media_type = HeaderValue::from_static(format.media_type());
+    response.headers_mut().insert(CONTENT_TYPE, media_type);
+    response
+}
+
+// A response body as a byte stream for writers of a format, handed to the
+// connection a chunk at a time. Writing fails once the client goes away.
+struct Chunks {
+    buffer: Vec<u8>,
+    feed: Feed<io::Result<Bytes>>,
+}
+
+impl Write for Chunks {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= CHUNK_BYTES {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_BYTES));
+        self.feed.send(Ok(Bytes::from(chunk)))
+    }
+}
+
+impl Finish for Chunks {}
+
+// GET /stream?rate=&duration=&count=&brand=&seed=&start=, upgraded to a
+// WebSocket that rows are pushed down at the rate asked for
+fn stream_rows(mut request: Request<Incoming>, profile: Arc<Profile>) -> Response<Body> {
+    let names = ["rate", "duration", "count", "brand", "seed", "start"];
+    let params = match parse(request.uri().query().unwrap_or(""), &names) {
+        Ok(params) => params,
+        Err(message) => return text(StatusCode::BAD_REQUEST, format!("{}\n", message)),
+    };
+    let profile = match params.query.profile(&profile) {
+        Ok(profile) => profile,
+        Err(message) => return text(StatusCode::BAD_REQUEST, format!("{}\n", message)),
+    };
+    let duration = params.duration.unwrap_or(websocket::DEFAULT_DURATION);
+    let Some(end) = chrono::Duration::from_std(duration)
+        .ok()
+        .and_then(|duration| Utc::now().checked_add_signed(duration))
+    else {
+        return text(StatusCode::BAD_REQUEST, "duration is too long\n");
+    };
+    let headers = request.headers();
+    let upgrade = has_token(headers, CONNECTION, "upgrade")
+        && has_token(headers, UPGRADE, "websocket")
+        && headers.get("sec-websocket-version").is_some_and(|v| v == websocket::VERSION);
+    let Some(key) = headers.get(SEC_WEBSOCKET_KEY).filter(|_| upgrade) else {
+        let mut response = text(
+            StatusCode::UPGRADE_REQUIRED,
+            "/stream is a WebSocket; connect with a WebSocket client\n",
+        );
+        let headers = response.headers_mut();
+        headers.insert(UPGRADE, HeaderValue::from_static("websocket"));
+        headers.insert("sec-websocket-version", HeaderValue::from_static(websocket::VERSION));
+        return response;
+    };
+    let accept = websocket::accept_key(key.as_bytes());
+    let rate = params.rate.unwrap_or(websocket::DEFAULT_RATE);
+    let upgraded = hyper::upgrade::on(&mut request);
+    tokio::spawn(async move {
+        let result = match upgraded.await {
+            Ok(upgraded) => websocket::serve(upgraded, profile, params.query, rate, end).await,
+            Err(e) => Err(io::Error::other(e)),
+        };
+        if let Err(e) = result {
+            debug!(error = %e, "WebSocket closed");
+        }
+    });
+    let mut response = Response::new(Body::default());
+    *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+    let headers = response.headers_mut();
+    headers.insert(UPGRADE, HeaderValue::from_static("websocket"));
+    headers.insert(CONNECTION, HeaderValue::from_static("Upgrade"));
+    headers.insert(SEC_WEBSOCKET_ACCEPT, accept);
+    response
+}
+
+// Whether a comma-separated header lists a token, as `Connection` lists
+// `Upgrade`
+fn has_token(headers: &HeaderMap, name: http::header::HeaderName, token: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|item| item.trim().eq_ignore_ascii_case(token))
+}
+
+// Parameters of a query string, of which each endpoint takes those it names
+#[derive(Default)]
+struct Params {
+    query: Query,
+    format: Option<OutputFormat>,
+    rate: Option<f64>,
+    duration: Option<Duration>,
+}
+
+fn parse(query: &str, names: &[&str]) -> Result<Params, String> {
+    let mut params = Params::default();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        if !names.contains(&name) {
+            return Err(format!("unknown parameter {:?}", name));
+        }
+        let value = decode(value);
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("{} must be a whole number, got {:?}", name, value))
+        };
+        match name {
+            "count" => params.query.count = Some(number()? as usize),
+            "brand" => params.query.brand = Some(value.clone()),
+            "seed" => params.query.seed = Some(number()?),
+            "start" => params.query.start = number()?,
+            "format" => {
+                let format = OutputFormat::from_str(&value, true)
+                    .ok()
+                    .filter(OutputFormat::is_streamed)
+                    .ok_or_else(|| format!("format {:?} can't be streamed", value))?;
+                params.format = Some(format);
+            }
+            "rate" => params.rate = Some(throttle::parse_rate(&value)?),
+            "duration" => params.duration = Some(throttle::parse_duration(&value)?),
+            _ => return Err(format!("unknown parameter {:?}", name)),
+        }
+    }
+    Ok(params)
+}
+
+// A query string value with its `+` and percent escapes decoded
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// A plain text response
+fn text(status: StatusCode, body: impl Into<Bytes>) -> Response<Body> {
+    let body = Full::new(body.into()).map_err(|never| match never {}).boxed_unsync();
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+    response
+}
@@ -0,0 +1,137 @@
+use super::{Feed, Query};
+use crate::{config::Profile, live::LiveStream, model::Transaction, output, throttle::Throttle};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::{DateTime, Utc};
+use futures_util::{SinkExt, StreamExt};
+use http::HeaderValue;
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use sha1::{Digest, Sha1};
+use std::{io, time::Duration};
+use tokio::{
+    sync::mpsc,
+    task,
+    time::{self, Instant},
+};
+use tokio_tungstenite::{
+    WebSocketStream,
+    tungstenite::{
+        self, Message,
+        protocol::{CloseFrame, Role, WebSocketConfig, frame::coding::CloseCode},
+    },
+};
+
+// The version of the protocol (RFC 6455) and the GUID every server appends
+// to the client's key to accept it
+pub const VERSION: &str = "13";
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+// Rate and length of a stream that doesn't ask for others
+pub const DEFAULT_RATE: f64 = 1.0;
+pub const DEFAULT_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Largest message accepted from clients, which only need to send control
+// frames
+const MAX_MESSAGE: usize = 64 * 1024;
+
+// How long to wait for the client to answer a close before hanging up
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub fn accept_key(key: &[u8]) -> HeaderValue {
+    let mut digest = Sha1::new();
+    digest.update(key);
+    digest.update(GUID);
+    HeaderValue::from_str(&BASE64.encode(digest.finalize())).expect("base64 is a valid header")
+}
+
+// Push rows down an upgraded connection as text messages of JSON, one row
+// each, at `rate` a second until `end`. Unseeded rows are live, stamped
+// with the time they're sent at, as in a live run.
+pub async fn serve(
+    upgraded: Upgraded,
+    mut profile: Profile,
+    query: Query,
+    rate: f64,
+    end: DateTime<Utc>,
+) -> io::Result<()> {
+    let config = WebSocketConfig::default()
+        .max_message_size(Some(MAX_MESSAGE))
+        .max_frame_size(Some(MAX_MESSAGE));
+    let mut socket =
+        WebSocketStream::from_raw_socket(TokioIo::new(upgraded), Role::Server, Some(config)).await;
+    let live = query.seed.is_none();
+    if live {
+        profile.live_until = Some(end);
+    }
+    // Rows are generated on a thread of the blocking pool one at a time as
+    // they're due, so live rows are stamped with the time they go out
+    let (want, mut wanted) = mpsc::channel::<()>(1);
+    let (feed, mut messages) = Feed::new(1);
+    let amount_format = profile.amount_format;
+    task::spawn_blocking(move || {
+        let rows = super::rows(&profile, &query);
+        let mut rows: Box<dyn Iterator<Item = Transaction>> = match live {
+            true => Box::new(LiveStream::new(rows, end)),
+            false => Box::new(rows),
+        };
+        while wanted.blocking_recv().is_some() {
+            let message = rows
+                .next()
+                .map(|tx| output::json_message(&tx, amount_format))
+                .transpose();
+            if feed.send(message).is_err() {
+                break;
+            }
+        }
+    });
+    let mut throttle = Throttle::new(rate);
+    loop {
+        // Answer the client while waiting for the next row's turn
+        let due = Instant::from_std(throttle.reserve());
+        loop {
+            tokio::select! {
+                _ = time::sleep_until(due) => break,
+                message = socket.next() => match message {
+                    // Pongs and the answer to a close are queued as pings
+                    // and closes come in, and go out as the socket flushes
+                    Some(Ok(Message::Close(_))) | None => return flush(&mut socket).await,
+                    Some(Ok(_)) => flush(&mut socket).await?,
+                    Some(Err(e)) => return Err(io::Error::other(e)),
+                },
+            }
+        }
+        if Utc::now() >= end {
+            break;
+        }
+        let _ = want.send(()).await;
+        let Some(message) = messages.recv().await.transpose()?.flatten() else {
+            break;
+        };
+        time::timeout(super::TIMEOUT, socket.send(Message::text(message)))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "client took nothing for too long"))?
+            .map_err(io::Error::other)?;
+    }
+    let close = CloseFrame {
+        code: CloseCode::Normal,
+        reason: "".into(),
+    };
+    socket
+        .close(Some(close))
+        .await
+        .map_err(io::Error::other)?;
+    // Wait for the client to answer the close, whatever it sends before it
+    let _ = time::timeout(CLOSE_TIMEOUT, async {
+        while let Some(Ok(_)) = socket.next().await {}
+    })
+    .await;
+    Ok(())
+}
+
+// Send what's queued for the client, which may already be gone
+async fn flush(socket: &mut WebSocketStream<TokioIo<Upgraded>>) -> io::Result<()> {
+    match socket.flush().await {
+        Ok(()) | Err(tungstenite::Error::ConnectionClosed) => Ok(()),
+        Err(e) => Err(io::Error::other(e)),
+    }
+}
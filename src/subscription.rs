@@ -0,0 +1,185 @@
+use crate::{
+    id::{self, IdStrategy},
+    model::{DeclineReason, Transaction, TransactionStatus},
+    settlement, temporal,
+    text::Text,
+    verification,
+};
+use chrono::{DateTime, Duration, Months};
+use chrono_tz::Tz;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Monthly subscriptions that customers sign up to online. Subscriptions are
+// only generated when `rate` is above 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubscriptionConfig {
+    // Share of purchases that are subscription sign-ups
+    pub rate: f64,
+    // Chance that a subscriber cancels before each renewal
+    pub churn_rate: f64,
+    // Share of renewals declined for insufficient funds
+    pub decline_rate: f64,
+    // Days after a declined renewal that the merchant retries it, in order.
+    // Subscriptions whose retries all fail are cancelled.
+    pub retry_days: Vec<u32>,
+    // Chance that each retry goes through
+    pub retry_success_rate: f64,
+}
+
+impl Default for SubscriptionConfig {
+    fn default() -> Self {
+        Self {
+            rate: 0.0,
+            churn_rate: 0.05,
+            decline_rate: 0.08,
+            retry_days: vec![1, 3, 7],
+            retry_success_rate: 0.5,
+        }
+    }
+}
+
+impl SubscriptionConfig {
+    // Each rate with the name it is configured under
+    pub fn rates(&self) -> [(&'static str, f64); 4] {
+        [
+            ("rate", self.rate),
+            ("churn_rate", self.churn_rate),
+            ("decline_rate", self.decline_rate),
+            ("retry_success_rate", self.retry_success_rate),
+        ]
+    }
+}
+
+// Who started a payment made with stored card details: the cardholder
+// signing up (customer-initiated), or the merchant charging them later
+// (merchant-initiated)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Initiator {
+    Cit,
+    Mit,
+}
+
+impl Initiator {
+    pub const ALL: [Initiator; 2] = [Initiator::Cit, Initiator::Mit];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Initiator::Cit => "cit",
+            Initiator::Mit => "mit",
+        }
+    }
+}
+
+// PAN entry mode of merchant-initiated charges, which use the card details
+// on file
+const CREDENTIAL_ON_FILE: &str = "10";
+
+// Number of subscriptions started so far in this run, scrambled into their IDs
+pub(crate) static SUBSCRIPTIONS: AtomicU64 = AtomicU64::new(0);
+
+// Generate a subscription ID, distinct for the first billion subscriptions
+// of a run
+pub fn gen_subscription_id() -> String {
+    let sequence = SUBSCRIPTIONS.fetch_add(1, Ordering::Relaxed);
+    format!("SUB{:09}", id::scramble(sequence, 1_000_000_000, 4))
+}
+
+// The renewals of a subscription signed up to with the given purchase, up to
+// `end`. Each month on the sign-up's day (or the month's last day) the
+// merchant charges the same amount. Declined renewals are retried on the
+// configured days, with retries pointing back to the declined charge.
+pub fn renewals<R: Rng + ?Sized>(
+    rng: &mut R,
+    signup: &Transaction,
+    config: &SubscriptionConfig,
+    end: &DateTime<Tz>,
+    ids: IdStrategy,
+) -> Vec<Transaction> {
+    let start = DateTime::parse_from_rfc3339(&signup.transaction_date)
+        .expect("generated dates are RFC 3339")
+        .with_timezone(&end.timezone());
+
+    let mut charges = Vec::new();
+    for month in 1.. {
+        let Some(due) = start.checked_add_months(Months::new(month)) else {
+            break;
+        };
+        if due > *end || rng.gen_bool(config.churn_rate) {
+            break;
+        }
+        let declined = rng.gen_bool(config.decline_rate);
+        let renewal = charge(rng, signup, &due, declined, None, ids);
+        let renewal_id = renewal.transaction_id.clone();
+        charges.push(renewal);
+        if !declined {
+            continue;
+        }
+
+        // Dunning: retry the declined renewal until one attempt goes through
+        let mut recovered = false;
+        for days in &config.retry_days {
+            let at = due + Duration::days(*days as i64);
+            if at > *end {
+                break;
+            }
+            recovered = rng.gen_bool(config.retry_success_rate);
+            charges.push(charge(rng, signup, &at, !recovered, Some(&renewal_id), ids));
+            if recovered {
+                break;
+            }
+        }
+        if !recovered {
+            break;
+        }
+    }
+    charges
+}
+
+// A merchant-initiated charge of the subscription at the given time. The
+// cardholder isn't there, so it carries no CVV, device or 3-D Secure
+// authentication.
+fn charge<R: Rng + ?Sized>(
+    rng: &mut R,
+    signup: &Transaction,
+    at: &DateTime<Tz>,
+    declined: bool,
+    retry_of: Option<&str>,
+    ids: IdStrategy,
+) -> Transaction {
+    let mut tx = Transaction {
+        transaction_id: ids.generate(rng, at.timestamp_millis()),
+        transaction_date: temporal::rfc3339(at),
+        status: if declined {
+            TransactionStatus::Declined
+        } else {
+            TransactionStatus::Approved
+        },
+        decline_reason: declined.then_some(DeclineReason::InsufficientFunds),
+        original_transaction_id: retry_of.map(Text::from),
+        initiator: Some(Initiator::Mit),
+        cvv: None,
+        pos_entry_mode: Text::from_static(CREDENTIAL_ON_FILE),
+        three_ds_version: None,
+        eci: None,
+        authentication_result: None,
+        cavv: None,
+        ip_address: None,
+        ip_country: None,
+        device_id: None,
+        device_platform: None,
+        device_os: None,
+        device_browser: None,
+        screen_resolution: None,
+        device_fingerprint: None,
+        user_agent: None,
+        ..signup.clone()
+    };
+    verification::verify(rng, &mut tx);
+    tx.respond(rng);
+    settlement::settle(&mut tx);
+    tx
+}
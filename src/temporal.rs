@@ -0,0 +1,345 @@
+use crate::text::{Text, TextBuf};
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Timelike,
+    Utc,
+};
+use chrono_tz::Tz;
+use rand::{
+    Rng,
+    distributions::{Distribution, WeightedIndex},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Relative transaction volume by hour of day, day of week and month.
+// Weights are relative, so only their proportions matter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemporalPattern {
+    // Hours 0-23
+    pub hourly: [f64; 24],
+    // Monday through Sunday
+    pub weekday: [f64; 7],
+    // January through December
+    pub monthly: [f64; 12],
+}
+
+impl Default for TemporalPattern {
+    fn default() -> Self {
+        Self {
+            hourly: [1.0; 24],
+            weekday: [1.0; 7],
+            monthly: [1.0; 12],
+        }
+    }
+}
+
+impl TemporalPattern {
+    // Describe the first invalid weight list, if any
+    pub fn check(&self) -> Result<(), String> {
+        let lists: [(&str, &[f64]); 3] = [
+            ("hourly", &self.hourly),
+            ("weekday", &self.weekday),
+            ("monthly", &self.monthly),
+        ];
+        for (name, weights) in lists {
+            if weights.iter().any(|w| !w.is_finite() || *w < 0.0)
+                || weights.iter().sum::<f64>() <= 0.0
+            {
+                return Err(format!(
+                    "{} weights must be non-negative with a positive total",
+                    name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Timestamp settings of a profile: a default pattern plus overrides keyed by
+// merchant category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemporalConfig {
+    pub default: TemporalPattern,
+    pub categories: BTreeMap<String, TemporalPattern>,
+}
+
+impl TemporalConfig {
+    // Pattern to draw timestamps from for a merchant category
+    pub fn for_category(&self, category: &str) -> &TemporalPattern {
+        self.categories.get(category).unwrap_or(&self.default)
+    }
+}
+
+impl Default for TemporalConfig {
+    fn default() -> Self {
+        // Shopping peaks in the late morning and early evening, dips overnight
+        let retail_hours = [
+            0.2, 0.1, 0.1, 0.1, 0.1, 0.2, 0.4, 0.7, 1.0, 1.3, 1.6, 1.8, 1.9, 1.8, 1.7, 1.7, 1.8,
+            2.0, 2.0, 1.8, 1.5, 1.1, 0.7, 0.4,
+        ];
+        // Busier towards the weekend, with holiday spikes in November and December
+        let retail_days = [0.9, 0.9, 0.95, 1.0, 1.2, 1.3, 1.0];
+        let retail_months = [
+            0.85, 0.8, 0.9, 0.95, 1.0, 1.0, 1.0, 1.05, 0.95, 1.0, 1.3, 1.6,
+        ];
+        let pattern = |hourly, weekday| TemporalPattern {
+            hourly,
+            weekday,
+            monthly: retail_months,
+        };
+        Self {
+            default: pattern(retail_hours, retail_days),
+            categories: BTreeMap::from([
+                // Coffee: a sharp morning rush and a smaller mid-afternoon bump
+                (
+                    "Food & Beverage".to_string(),
+                    pattern(
+                        [
+                            0.05, 0.05, 0.05, 0.05, 0.1, 0.4, 1.5, 3.0, 3.5, 2.5, 1.8, 1.5, 1.6,
+                            1.4, 1.5, 1.4, 1.0, 0.7, 0.4, 0.3, 0.2, 0.1, 0.05, 0.05,
+                        ],
+                        [1.1, 1.1, 1.1, 1.1, 1.1, 0.8, 0.7],
+                    ),
+                ),
+                // Restaurants: lunch and dinner services, busiest on weekends
+                (
+                    "Restaurant".to_string(),
+                    pattern(
+                        [
+                            0.1, 0.05, 0.05, 0.05, 0.05, 0.05, 0.1, 0.2, 0.3, 0.3, 0.5, 1.5, 2.5,
+                            2.0, 0.8, 0.5, 0.6, 1.2, 2.5, 3.0, 2.5, 1.5, 0.7, 0.3,
+                        ],
+                        [0.7, 0.8, 0.9, 1.0, 1.4, 1.6, 1.2],
+                    ),
+                ),
+                // Travel: booked around the clock, mostly on weekdays, ahead of summer
+                (
+                    "Travel".to_string(),
+                    TemporalPattern {
+                        hourly: [
+                            0.4, 0.3, 0.2, 0.2, 0.2, 0.3, 0.5, 0.8, 1.0, 1.2, 1.3, 1.3, 1.3, 1.3,
+                            1.3, 1.2, 1.2, 1.2, 1.3, 1.4, 1.4, 1.2, 0.9, 0.6,
+                        ],
+                        weekday: [1.2, 1.2, 1.1, 1.1, 1.0, 0.7, 0.7],
+                        monthly: [1.3, 1.2, 1.2, 1.1, 1.1, 1.0, 0.9, 0.8, 0.9, 0.9, 0.8, 0.9],
+                    },
+                ),
+            ]),
+        }
+    }
+}
+
+// Inclusive bounds that transaction timestamps are drawn between
+#[derive(Debug, Clone, Copy)]
+pub struct DateRange {
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+    // Timestamps are the time they're drawn at, as in a live run, rather
+    // than drawn from the range
+    pub live: bool,
+}
+
+impl DateRange {
+    // Whole days from `from` to `to` in the given timezone. Without a start
+    // the range covers the 3 years before the end, and without an end it
+    // runs up to now.
+    pub fn new(from: Option<NaiveDate>, to: Option<NaiveDate>, tz: Tz) -> Self {
+        let end = match to {
+            Some(day) => {
+                local(tz, day.and_time(NaiveTime::MIN)) + Duration::days(1) - Duration::seconds(1)
+            }
+            None => Utc::now().with_timezone(&tz),
+        };
+        let start = match from {
+            Some(day) => local(tz, day.and_time(NaiveTime::MIN)),
+            None => end - Duration::days(365 * 3),
+        };
+        Self {
+            start,
+            end,
+            live: false,
+        }
+    }
+
+    // A live run from now until `until`
+    pub fn live(until: DateTime<Utc>, tz: Tz) -> Self {
+        Self {
+            start: Utc::now().with_timezone(&tz),
+            end: until.with_timezone(&tz),
+            live: true,
+        }
+    }
+
+    // The current time to the second, as live timestamps are written
+    pub fn now(&self) -> DateTime<Tz> {
+        let now = Utc::now().with_timezone(&self.end.timezone());
+        now.with_nanosecond(0).unwrap_or(now)
+    }
+
+    // Every calendar day the range touches, in its timezone
+    fn days(&self) -> impl Iterator<Item = NaiveDate> {
+        self.start
+            .date_naive()
+            .iter_days()
+            .take_while(|day| *day <= self.end.date_naive())
+    }
+}
+
+// Resolve a local time, moving past a daylight saving gap if it falls in one
+pub(crate) fn local(tz: Tz, time: NaiveDateTime) -> DateTime<Tz> {
+    (0..=2)
+        .find_map(|hours| {
+            tz.from_local_datetime(&(time + Duration::hours(hours)))
+                .earliest()
+        })
+        .expect("daylight saving gaps are shorter than two hours")
+}
+
+// A timestamp in RFC 3339, exactly as `DateTime::to_rfc3339` writes it but
+// without going through chrono's formatter, since every row carries one.
+// Leap seconds, years past four digits and offsets with seconds are left to
+// chrono.
+pub fn rfc3339<Z: TimeZone>(time: &DateTime<Z>) -> Text {
+    let local = time.naive_local();
+    let offset = time.offset().fix().local_minus_utc();
+    let nanos = local.nanosecond();
+    if nanos >= 1_000_000_000 || !(0..=9999).contains(&local.year()) || offset % 60 != 0 {
+        return time.to_rfc3339().into();
+    }
+    let mut text = TextBuf::new();
+    push_date(&mut text, local.date(), "-");
+    text.push('T');
+    text.push_digits(local.hour().into(), 2);
+    text.push(':');
+    text.push_digits(local.minute().into(), 2);
+    text.push(':');
+    text.push_digits(local.second().into(), 2);
+    // Fractions of a second in as few groups of three digits as they need
+    let (fraction, digits) = match nanos {
+        0 => (0, 0),
+        n if n % 1_000_000 == 0 => (n / 1_000_000, 3),
+        n if n % 1_000 == 0 => (n / 1_000, 6),
+        n => (n, 9),
+    };
+    if digits > 0 {
+        text.push('.');
+        text.push_digits(fraction.into(), digits);
+    }
+    text.push(if offset < 0 { '-' } else { '+' });
+    let minutes = offset.unsigned_abs() / 60;
+    text.push_digits((minutes / 60).into(), 2);
+    text.push(':');
+    text.push_digits((minutes % 60).into(), 2);
+    text.into()
+}
+
+// Write a date as its year, month and day with a separator between them
+pub(crate) fn push_date(text: &mut TextBuf, date: NaiveDate, separator: &str) {
+    text.push_digits(date.year().max(0) as u64, 4);
+    text.push_str(separator);
+    text.push_digits(date.month().into(), 2);
+    text.push_str(separator);
+    text.push_digits(date.day().into(), 2);
+}
+
+// The local date an RFC 3339 timestamp falls on, read from its leading
+// `YYYY-MM-DD`
+pub(crate) fn local_date(rfc3339: &str) -> Option<NaiveDate> {
+    let bytes = rfc3339.as_bytes();
+    if bytes.len() < 11 || bytes[4] != b'-' || bytes[7] != b'-' || bytes[10] != b'T' {
+        return None;
+    }
+    let number = |range: std::ops::Range<usize>| -> Option<u32> {
+        bytes[range].iter().try_fold(0, |n, byte| {
+            byte.is_ascii_digit()
+                .then(|| n * 10 + u32::from(byte - b'0'))
+        })
+    };
+    NaiveDate::from_ymd_opt(number(0..4)? as i32, number(5..7)?, number(8..10)?)
+}
+
+// Draws timestamps following a temporal pattern within a date range
+pub struct TimestampSampler {
+    range: DateRange,
+    days: Vec<NaiveDate>,
+    day_index: WeightedIndex<f64>,
+    hour_index: WeightedIndex<f64>,
+}
+
+impl TimestampSampler {
+    // Fails when the pattern gives no weight to any day in the range
+    pub fn new(pattern: &TemporalPattern, range: &DateRange) -> Result<Self, String> {
+        let days: Vec<NaiveDate> = range.days().collect();
+        // Live runs take whatever day it is
+        let day_index = WeightedIndex::new(days.iter().map(|day| {
+            if range.live {
+                1.0
+            } else {
+                pattern.weekday[day.weekday().num_days_from_monday() as usize]
+                    * pattern.monthly[day.month0() as usize]
+            }
+        }))
+        .map_err(|_| "no day in the date range has a positive weight".to_string())?;
+        let hour_index = WeightedIndex::new(pattern.hourly)
+            .map_err(|_| "hourly weights must have a positive total".to_string())?;
+        Ok(Self {
+            range: *range,
+            days,
+            day_index,
+            hour_index,
+        })
+    }
+
+    // Draw a timestamp in the range. The day follows the weekday and month
+    // weights and the local hour follows the hourly weights.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> DateTime<Tz> {
+        if self.range.live {
+            return self.range.now();
+        }
+        let tz = self.range.start.timezone();
+        loop {
+            let day = self.days[self.day_index.sample(rng)];
+            let hour = self.hour_index.sample(rng) as u32;
+            let time = NaiveTime::from_hms_opt(hour, rng.gen_range(0..60), rng.gen_range(0..60))
+                .expect("hour, minute and second are in range");
+
+            // Skip times inside a daylight saving gap, and times outside a
+            // partially covered first or last day
+            if let Some(at) = tz.from_local_datetime(&day.and_time(time)).earliest()
+                && at >= self.range.start
+                && at <= self.range.end
+            {
+                return at;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn rfc3339_matches_chrono() {
+        let base = NaiveDate::from_ymd_opt(2024, 3, 9)
+            .unwrap()
+            .and_hms_opt(7, 5, 3)
+            .unwrap();
+        for nanos in [0, 120_000_000, 123_456_000, 123_456_789] {
+            let time = base + Duration::nanoseconds(nanos);
+            for tz in ["UTC", "America/New_York", "Asia/Kolkata"] {
+                let tz: Tz = tz.parse().unwrap();
+                let at = tz.from_utc_datetime(&time);
+                assert_eq!(rfc3339(&at), at.to_rfc3339());
+                assert_eq!(local_date(&rfc3339(&at)), Some(at.date_naive()));
+            }
+            let west = FixedOffset::west_opt(9 * 3600 + 30 * 60).unwrap();
+            let at = west.from_utc_datetime(&time);
+            assert_eq!(rfc3339(&at), at.to_rfc3339());
+        }
+        assert_eq!(local_date("not a date"), None);
+    }
+}
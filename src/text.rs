@@ -0,0 +1,365 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::Arc,
+};
+
+// Longest text held in place rather than behind a pointer. Card numbers,
+// CVVs, IDs, dates and most names and addresses fit.
+const INLINE: usize = 30;
+
+// Text of a transaction or customer, held so that copying it from row to
+// row costs no allocation: short text in place, like card numbers in a
+// fixed-size array, and longer text behind a reference count. Making a
+// `Text` of longer text allocates every time; text that many rows carry,
+// such as merchants, user agents and issuers, is made once by the generator
+// or the profile, and rows clone it to share it.
+pub struct Text(Repr);
+
+enum Repr {
+    Inline { len: u8, bytes: [u8; INLINE] },
+    Static(&'static str),
+    Shared(Arc<str>),
+}
+
+impl Text {
+    #[inline]
+    pub fn new(text: &str) -> Self {
+        if text.len() > INLINE {
+            return Self(Repr::Shared(text.into()));
+        }
+        let mut bytes = [0; INLINE];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+        Self(Repr::Inline {
+            len: text.len() as u8,
+            bytes,
+        })
+    }
+
+    // Text of the program itself, which is never copied
+    #[inline]
+    pub const fn from_static(text: &'static str) -> Self {
+        Self(Repr::Static(text))
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            // SAFETY: inline bytes are only ever copied from a `str` or a
+            // `TextBuf`, whole characters at a time, so they are UTF-8
+            Repr::Inline { len, bytes } => unsafe {
+                std::str::from_utf8_unchecked(&bytes[..*len as usize])
+            },
+            Repr::Static(text) => text,
+            Repr::Shared(text) => text,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+// Text written a piece at a time, such as an ID or a date, in place until it
+// outgrows what a `Text` holds in place, so most of it never allocates
+pub struct TextBuf {
+    len: usize,
+    bytes: [u8; INLINE],
+    // Everything written, once it no longer fits in place
+    spilled: String,
+}
+
+impl TextBuf {
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            bytes: [0; INLINE],
+            spilled: String::new(),
+        }
+    }
+
+    #[inline]
+    pub fn push_str(&mut self, text: &str) {
+        let end = self.len + text.len();
+        if end <= INLINE {
+            self.bytes[self.len..end].copy_from_slice(text.as_bytes());
+        } else {
+            if self.len <= INLINE {
+                // SAFETY: only whole `str`s are copied in
+                let inline = unsafe { std::str::from_utf8_unchecked(&self.bytes[..self.len]) };
+                self.spilled.push_str(inline);
+            }
+            self.spilled.push_str(text);
+        }
+        self.len = end;
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    // A number in decimal, zero-padded to at least `width` digits
+    pub fn push_digits(&mut self, mut n: u64, width: usize) {
+        let count = width.max(n.checked_ilog10().unwrap_or(0) as usize + 1);
+        let end = self.len + count;
+        if end <= INLINE {
+            // Last digit first, with zeros once the number runs out
+            for byte in self.bytes[self.len..end].iter_mut().rev() {
+                *byte = b'0' + (n % 10) as u8;
+                n /= 10;
+            }
+            self.len = end;
+            return;
+        }
+        // Zeros are already in place for the padding
+        let mut digits = [b'0'; 20];
+        let mut start = digits.len();
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (n % 10) as u8;
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+        let start = start.min(digits.len() - width.min(digits.len()));
+        // SAFETY: ASCII digits are UTF-8
+        self.push_str(unsafe { std::str::from_utf8_unchecked(&digits[start..]) });
+    }
+
+    pub fn as_str(&self) -> &str {
+        if self.len > INLINE {
+            return &self.spilled;
+        }
+        // SAFETY: only whole `str`s are copied in
+        unsafe { std::str::from_utf8_unchecked(&self.bytes[..self.len]) }
+    }
+}
+
+impl Default for TextBuf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Write for TextBuf {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.push_str(text);
+        Ok(())
+    }
+}
+
+impl From<TextBuf> for Text {
+    fn from(buf: TextBuf) -> Self {
+        if buf.len > INLINE {
+            return Self(Repr::Shared(buf.spilled.into()));
+        }
+        Self(Repr::Inline {
+            len: buf.len as u8,
+            bytes: buf.bytes,
+        })
+    }
+}
+
+impl Clone for Text {
+    #[inline]
+    fn clone(&self) -> Self {
+        match &self.0 {
+            Repr::Shared(text) => Self(Repr::Shared(Arc::clone(text))),
+            // SAFETY: text in place and static text own nothing, so copying
+            // their bits copies them
+            _ => unsafe { std::ptr::read(self) },
+        }
+    }
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Self::from_static("")
+    }
+}
+
+impl Deref for Text {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Text {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl Borrow<str> for Text {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl From<&str> for Text {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<&String> for Text {
+    fn from(text: &String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for Text {
+    fn from(text: String) -> Self {
+        Self::new(&text)
+    }
+}
+
+impl From<Text> for String {
+    fn from(text: Text) -> Self {
+        text.as_str().to_string()
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Debug for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl PartialEq for Text {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Text {}
+
+impl PartialEq<str> for Text {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Text {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Text {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<Text> for str {
+    fn eq(&self, other: &Text) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<Text> for &str {
+    fn eq(&self, other: &Text) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<Text> for String {
+    fn eq(&self, other: &Text) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialOrd for Text {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Text {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for Text {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl Serialize for Text {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Text {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn text_reads_back_whether_in_place_or_shared() {
+        for text in [
+            "",
+            "4111111111111111",
+            "Zoë 12",
+            &"x".repeat(30),
+            &"y".repeat(31),
+        ] {
+            let held = Text::new(text);
+            assert_eq!(held.as_str(), text);
+            assert_eq!(held.clone(), *text);
+            assert_eq!(String::from(held), text);
+        }
+        assert!(matches!(
+            Text::new(&"x".repeat(30)).0,
+            Repr::Inline { len: 30, .. }
+        ));
+        assert!(matches!(Text::new(&"y".repeat(31)).0, Repr::Shared(_)));
+    }
+
+    #[test]
+    fn text_compares_and_hashes_by_what_it_says() {
+        let texts = [
+            Text::new("USD"),
+            Text::from_static("USD"),
+            Text::from("USD".to_string()),
+        ];
+        assert!(texts.iter().all(|text| *text == texts[0]));
+        assert_eq!(texts.iter().collect::<HashSet<_>>().len(), 1);
+        assert!(Text::new("EUR") < Text::from_static("USD"));
+        let mut cleared = Text::new("4111");
+        cleared.clear();
+        assert_eq!(cleared, "");
+    }
+
+    #[test]
+    fn text_serializes_as_a_string() {
+        let text = Text::new(&"Mozilla/5.0 ".repeat(4));
+        let json = serde_json::to_string(&text).unwrap();
+        assert_eq!(json, serde_json::to_string(text.as_str()).unwrap());
+        assert_eq!(serde_json::from_str::<Text>(&json).unwrap(), text);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{model::Transaction, text::Text};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// 3-D Secure protocol versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ThreeDsVersion {
+    #[serde(rename = "1.0")]
+    V1_0,
+    #[serde(rename = "2.1")]
+    V2_1,
+    #[serde(rename = "2.2")]
+    V2_2,
+}
+
+impl ThreeDsVersion {
+    pub const ALL: [ThreeDsVersion; 3] = [
+        ThreeDsVersion::V1_0,
+        ThreeDsVersion::V2_1,
+        ThreeDsVersion::V2_2,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThreeDsVersion::V1_0 => "1.0",
+            ThreeDsVersion::V2_1 => "2.1",
+            ThreeDsVersion::V2_2 => "2.2",
+        }
+    }
+}
+
+// How the issuer authenticated the cardholder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthenticationResult {
+    // Authenticated from the data the merchant sent, without asking the
+    // cardholder for anything
+    Frictionless,
+    // Authenticated after the cardholder completed a challenge
+    Challenge,
+    // The cardholder failed or abandoned the challenge
+    Failed,
+}
+
+impl AuthenticationResult {
+    pub const ALL: [AuthenticationResult; 3] = [
+        AuthenticationResult::Frictionless,
+        AuthenticationResult::Challenge,
+        AuthenticationResult::Failed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthenticationResult::Frictionless => "frictionless",
+            AuthenticationResult::Challenge => "challenge",
+            AuthenticationResult::Failed => "failed",
+        }
+    }
+}
+
+// 3-D Secure authentication of online card payments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThreeDsConfig {
+    // Share of online card payments authenticated with 3-D Secure
+    pub rate: f64,
+    // Relative weights of the protocol versions
+    pub versions: BTreeMap<ThreeDsVersion, f64>,
+    // Share of authentications of each version that challenge the
+    // cardholder; the rest are frictionless
+    pub challenge_rates: BTreeMap<ThreeDsVersion, f64>,
+    // Share of challenges the cardholder fails
+    pub failure_rate: f64,
+}
+
+impl Default for ThreeDsConfig {
+    fn default() -> Self {
+        Self {
+            rate: 0.8,
+            versions: BTreeMap::from([
+                (ThreeDsVersion::V1_0, 0.05),
+                (ThreeDsVersion::V2_1, 0.35),
+                (ThreeDsVersion::V2_2, 0.6),
+            ]),
+            // 3-D Secure 1.0 has no frictionless flow
+            challenge_rates: BTreeMap::from([
+                (ThreeDsVersion::V1_0, 1.0),
+                (ThreeDsVersion::V2_1, 0.2),
+                (ThreeDsVersion::V2_2, 0.1),
+            ]),
+            failure_rate: 0.1,
+        }
+    }
+}
+
+impl ThreeDsConfig {
+    // Sampling weights for each version, in `ThreeDsVersion::ALL` order
+    pub fn version_weights(&self) -> Vec<f64> {
+        ThreeDsVersion::ALL
+            .iter()
+            .map(|v| self.versions.get(v).copied().unwrap_or(0.0))
+            .collect()
+    }
+
+    // Share of authentications of a version that are challenged; versions
+    // left out of `challenge_rates` are always frictionless
+    pub fn challenge_rate(&self, version: ThreeDsVersion) -> f64 {
+        self.challenge_rates.get(&version).copied().unwrap_or(0.0)
+    }
+
+    // Authenticate a payment with the given card brand over a version
+    pub fn authenticate<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        version: ThreeDsVersion,
+        card_brand: &str,
+    ) -> Authentication {
+        let result = if !rng.gen_bool(self.challenge_rate(version)) {
+            AuthenticationResult::Frictionless
+        } else if rng.gen_bool(self.failure_rate) {
+            AuthenticationResult::Failed
+        } else {
+            AuthenticationResult::Challenge
+        };
+        Authentication::new(rng, version, result, card_brand)
+    }
+}
+
+// Outcome of a 3-D Secure authentication as the merchant passes it on to
+// authorization
+#[derive(Debug, Clone)]
+pub struct Authentication {
+    pub version: ThreeDsVersion,
+    pub result: AuthenticationResult,
+    // Electronic commerce indicator
+    pub eci: &'static str,
+    // Cardholder authentication verification value, only issued for a
+    // successful authentication
+    pub cavv: Option<Text>,
+}
+
+impl Authentication {
+    pub fn new<R: Rng + ?Sized>(
+        rng: &mut R,
+        version: ThreeDsVersion,
+        result: AuthenticationResult,
+        card_brand: &str,
+    ) -> Self {
+        let authenticated = result != AuthenticationResult::Failed;
+        Self {
+            version,
+            result,
+            eci: eci(card_brand, authenticated),
+            cavv: authenticated.then(|| gen_cavv(rng).into()),
+        }
+    }
+
+    // Put the authentication's attributes on the transaction it authenticated
+    pub fn assign(&self, tx: &mut Transaction) {
+        tx.three_ds_version = Some(Text::from_static(self.version.as_str()));
+        tx.eci = Some(Text::from_static(self.eci));
+        tx.authentication_result = Some(self.result);
+        tx.cavv = self.cavv.clone();
+    }
+}
+
+// ECI of an authentication. Mastercard and Maestro use 02 for an
+// authenticated cardholder and 00 otherwise; the other networks follow
+// Visa's 05 and 07.
+fn eci(card_brand: &str, authenticated: bool) -> &'static str {
+    match (card_brand, authenticated) {
+        ("Mastercard" | "Maestro", true) => "02",
+        ("Mastercard" | "Maestro", false) => "00",
+        (_, true) => "05",
+        (_, false) => "07",
+    }
+}
+
+// A CAVV: 20 bytes, base64-encoded into 28 characters
+fn gen_cavv<R: Rng + ?Sized>(rng: &mut R) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let bytes: [u8; 20] = rng.r#gen();
+    let mut cavv = String::with_capacity(28);
+    for chunk in bytes.chunks(3) {
+        let n =
+            chunk.iter().fold(0u32, |n, byte| (n << 8) | *byte as u32) << (8 * (3 - chunk.len()));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                cavv.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                cavv.push('=');
+            }
+        }
+    }
+    cavv
+}
@@ -0,0 +1,175 @@
+use rand::Rng;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+// Paces a loop to a steady number of iterations per second. Falling behind
+// doesn't cause a burst afterwards; the schedule restarts from the late call.
+pub struct Throttle {
+    per_second: f64,
+    start: Instant,
+    next: Instant,
+    jitter: bool,
+    bursts: Vec<Burst>,
+}
+
+impl Throttle {
+    pub fn new(per_second: f64) -> Self {
+        let now = Instant::now();
+        Self {
+            per_second,
+            start: now,
+            next: now,
+            jitter: false,
+            bursts: Vec::new(),
+        }
+    }
+
+    // Space iterations as a Poisson process at the same average rate, with
+    // exponentially distributed gaps instead of even ones
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    // Speed up while any of these bursts is on, by the largest factor of
+    // those that are
+    pub fn with_bursts(mut self, bursts: Vec<Burst>) -> Self {
+        self.bursts = bursts;
+        self
+    }
+
+    // Block until the next iteration is due
+    pub fn wait(&mut self) {
+        let due = self.reserve();
+        let now = Instant::now();
+        if due > now {
+            thread::sleep(due - now);
+        }
+    }
+
+    // Schedule the next iteration, returning when it's due, for callers with
+    // something else to do while they wait
+    pub fn reserve(&mut self) -> Instant {
+        self.next = self.next.max(Instant::now());
+        let due = self.next;
+        self.next += self.interval();
+        due
+    }
+
+    // Time until the iteration after the one due now
+    fn interval(&self) -> Duration {
+        let elapsed = self.next.saturating_duration_since(self.start);
+        let factor = self
+            .bursts
+            .iter()
+            .filter(|burst| burst.is_on(elapsed))
+            .map(|burst| burst.factor)
+            .fold(1.0, f64::max);
+        let mean = 1.0 / (self.per_second * factor);
+        if self.jitter {
+            // Exponential gaps, drawn by inverting the distribution
+            let uniform: f64 = rand::thread_rng().r#gen();
+            Duration::from_secs_f64(-mean * (1.0 - uniform).ln())
+        } else {
+            Duration::from_secs_f64(mean)
+        }
+    }
+}
+
+// A burst of traffic that comes round at a fixed period: the rate times
+// `factor` for the last `length` of every `every`, as in `10x30s/5m`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Burst {
+    pub factor: f64,
+    pub length: Duration,
+    pub every: Duration,
+}
+
+impl Burst {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "expected FACTORxLENGTH/PERIOD, as in 10x30s/5m, got {:?}",
+                value
+            )
+        };
+        let (factor, rest) = value.split_once('x').ok_or_else(invalid)?;
+        let (length, every) = rest.split_once('/').ok_or_else(invalid)?;
+        let factor: f64 = factor.parse().map_err(|_| invalid())?;
+        let burst = Self {
+            factor,
+            length: parse_duration(length)?,
+            every: parse_duration(every)?,
+        };
+        if !(factor > 0.0 && factor.is_finite()) {
+            return Err(format!(
+                "burst factor must be a positive number, got {}",
+                factor
+            ));
+        }
+        if burst.length > burst.every {
+            return Err(format!("burst {:?} lasts longer than its period", value));
+        }
+        Ok(burst)
+    }
+
+    fn is_on(&self, elapsed: Duration) -> bool {
+        let phase = elapsed.as_nanos() % self.every.as_nanos();
+        phase >= (self.every - self.length).as_nanos()
+    }
+}
+
+// A rate per second, or per minute or hour given as in `500/s`, `3000/m`
+// or `100/h`
+pub fn parse_rate(value: &str) -> Result<f64, String> {
+    let (count, per) = value.split_once('/').unwrap_or((value, "s"));
+    let seconds = match per {
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        _ => return Err(format!("expected a rate per s, m or h, got {:?}", value)),
+    };
+    match count.parse::<f64>() {
+        Ok(count) if count > 0.0 && count.is_finite() => Ok(count / seconds),
+        _ => Err(format!("expected a positive rate, got {:?}", value)),
+    }
+}
+
+// A length of time in milliseconds, seconds, minutes, hours or days, as in
+// `500ms`, `90s`, `10m` or `1h30m`
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "expected a duration such as 90s, 10m or 1h30m, got {:?}",
+            value
+        )
+    };
+    let mut total = Duration::ZERO;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or_else(invalid)?;
+        let amount: f64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit] {
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            "d" => 86400.0,
+            _ => return Err(invalid()),
+        };
+        rest = &rest[unit..];
+        total += Duration::from_secs_f64(amount * seconds);
+    }
+    if total.is_zero() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
@@ -0,0 +1,140 @@
+use crate::model::Transaction;
+use sha2::{Digest, Sha256};
+
+// Service code written on the cards: international card with a chip,
+// authorized as normal, without restrictions
+const SERVICE_CODE: &str = "201";
+
+// Characters Track 2 may hold, sentinels and LRC included (ISO/IEC 7813)
+const TRACK2_LENGTH: usize = 40;
+
+// Longest name Track 1 holds
+const NAME_LENGTH: usize = 26;
+
+// Give a card-present row the Track 1 and Track 2 data the terminal read
+// from the card's stripe, or from the chip's copy of it. Wallet payments
+// carry the device PAN instead of the card number.
+pub fn assign(tx: &mut Transaction) {
+    let pan = tx.token_pan.as_deref().unwrap_or(&tx.card_number);
+    let Some(data) = data(pan, &tx.card_expiry) else {
+        return;
+    };
+    tx.track1 = Some(track1(pan, &name(&tx.cardholder_name), &data).into());
+    tx.track2 = Some(track2(pan, &data).into());
+}
+
+// Track 1 (IATA): `%B`, the PAN, `^`, the name, `^`, the expiry date,
+// service code and discretionary data, `?` and the LRC
+pub fn track1(pan: &str, name: &str, data: &str) -> String {
+    with_lrc(format!("%B{}^{}^{}?", pan, name, data), 0x20, 0x3f)
+}
+
+// Track 2 (ABA): `;`, the PAN, `=`, the expiry date, service code and
+// discretionary data, `?` and the LRC
+pub fn track2(pan: &str, data: &str) -> String {
+    with_lrc(format!(";{}={}?", pan, data), 0x30, 0x0f)
+}
+
+// A cardholder name as embossed on Track 1: `SURNAME/GIVEN NAMES` in
+// capitals, without the letters the track's character set lacks
+pub fn name(full: &str) -> String {
+    let words: Vec<String> = full
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(char::is_ascii_alphabetic)
+                .map(|c| c.to_ascii_uppercase())
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+    let name = match words.split_last() {
+        Some((surname, [])) => surname.clone(),
+        Some((surname, given)) => format!("{}/{}", surname, given.join(" ")),
+        None => "CARDHOLDER".to_string(),
+    };
+    name.chars().take(NAME_LENGTH).collect()
+}
+
+// What follows the PAN on both tracks: the expiry date as YYMM, the
+// service code, and discretionary data filling Track 2 to its full length:
+// a PIN verification key index and value and a card verification value of
+// their own, then zeros. They're derived from the card, so a card's tracks
+// are the same on every row.
+fn data(pan: &str, expiry: &str) -> Option<String> {
+    if pan.is_empty() || !pan.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (month, year) = expiry.split_once('/')?;
+    let digest = Sha256::digest(format!("{}{}", pan, expiry).as_bytes());
+    let verification: String = digest
+        .iter()
+        .take(7)
+        .map(|byte| char::from(b'0' + byte % 10))
+        .collect();
+    // Sentinels, separator, LRC, PAN, expiry and service code take the rest
+    let length = TRACK2_LENGTH.checked_sub(11 + pan.len())?;
+    let discretionary = format!("1{:0<width$}", verification, width = length.saturating_sub(1));
+    Some(format!(
+        "{}{}{}{}",
+        year,
+        month,
+        SERVICE_CODE,
+        &discretionary[..length.min(discretionary.len())]
+    ))
+}
+
+// Append the longitudinal redundancy check: the XOR of the characters'
+// values in the track's character set, which start at `offset` and use the
+// bits of `mask`
+fn with_lrc(mut track: String, offset: u8, mask: u8) -> String {
+    let lrc = track
+        .bytes()
+        .fold(0, |lrc, byte| lrc ^ (byte.wrapping_sub(offset) & mask));
+    track.push(char::from(offset + lrc));
+    track
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAN: &str = "4111111111111111";
+
+    #[test]
+    fn tracks_are_encoded() {
+        let data = data(PAN, "12/28").unwrap();
+        assert_eq!(data, "28122011155473100000");
+        let track2 = track2(PAN, &data);
+        assert_eq!(track2, ";4111111111111111=28122011155473100000?7");
+        assert_eq!(track2.len(), TRACK2_LENGTH);
+        assert_eq!(
+            track1(PAN, &name("Jane Q. Doe"), &data),
+            "%B4111111111111111^DOE/JANE Q^28122011155473100000?F"
+        );
+    }
+
+    #[test]
+    fn lrc_is_the_xor_of_the_characters() {
+        // ";1=?" in the Track 2 set is 0xb, 0x1, 0xd and 0xf
+        assert_eq!(with_lrc(";1=?".to_string(), 0x30, 0x0f), ";1=?8");
+        // "%B^?" in the Track 1 set is 0x05, 0x22, 0x3e and 0x1f
+        assert_eq!(with_lrc("%B^?".to_string(), 0x20, 0x3f), "%B^?&");
+    }
+
+    #[test]
+    fn names_are_embossed() {
+        assert_eq!(name("María José García-López"), "GARCALPEZ/MARA JOS");
+        assert_eq!(name("Cher"), "CHER");
+        assert_eq!(name(""), "CARDHOLDER");
+        assert_eq!(name(&"a".repeat(40)).len(), NAME_LENGTH);
+    }
+
+    #[test]
+    fn bad_cards_have_no_tracks() {
+        assert_eq!(data("", "12/28"), None);
+        assert_eq!(data("4111-1111", "12/28"), None);
+        assert_eq!(data(PAN, "1228"), None);
+        assert_eq!(data(&"4".repeat(30), "12/28"), None);
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+// Built-in user agents with their weights. Each group of user agents shares
+// a weight of roughly its browser and platform's percentage of payment
+// traffic, with newer versions weighted more heavily than older ones. The
+// strings follow the formats browsers send today, including the frozen
+// platform and version details of Chrome's reduced user agent.
+pub fn builtin_user_agents() -> Vec<(String, f64)> {
+    let chrome = 120..=131u32;
+    let firefox = 115..=132;
+    let iphone_os = [
+        "16_6_1", "16_7_10", "17_0_3", "17_1_2", "17_2_1", "17_3_1", "17_4_1", "17_5_1", "17_6_1",
+        "18_0", "18_0_1", "18_1", "18_1_1",
+    ];
+    let ipad_os = [
+        "16_7_10", "17_4_1", "17_5_1", "17_6_1", "18_0_1", "18_1", "18_1_1",
+    ];
+    let mac_safari = [
+        "16.6", "17.0", "17.1", "17.2.1", "17.3.1", "17.4.1", "17.5", "17.6", "18.0", "18.1",
+    ];
+    let android_phones = [
+        ("12", "SM-A525F"),
+        ("13", "SM-G991B"),
+        ("13", "2201117TG"),
+        ("14", "SM-A546B"),
+        ("14", "SM-S918B"),
+        ("14", "Pixel 7"),
+        ("14", "CPH2449"),
+        ("14", "moto g54 5G"),
+        ("15", "Pixel 8 Pro"),
+    ];
+    let windows_chrome =
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko)";
+    let mac_chrome =
+        "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko)";
+
+    let mut agents = Vec::new();
+    let mut group = |share: f64, variants: Vec<String>| {
+        // Weights rise linearly through the variants, listed oldest first
+        let total = (variants.len() * (variants.len() + 1) / 2) as f64;
+        for (i, variant) in variants.into_iter().enumerate() {
+            agents.push((variant, share * (i + 1) as f64 / total));
+        }
+    };
+
+    // Desktop
+    group(
+        21.0,
+        chrome
+            .clone()
+            .map(|v| format!("{} Chrome/{}.0.0.0 Safari/537.36", windows_chrome, v))
+            .collect(),
+    );
+    group(
+        0.4,
+        ["6.1", "6.3"]
+            .iter()
+            .map(|nt| {
+                format!(
+                    "Mozilla/5.0 (Windows NT {}; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/109.0.0.0 Safari/537.36",
+                    nt
+                )
+            })
+            .collect(),
+    );
+    group(
+        5.0,
+        chrome
+            .clone()
+            .map(|v| {
+                format!(
+                    "{} Chrome/{v}.0.0.0 Safari/537.36 Edg/{v}.0.0.0",
+                    windows_chrome
+                )
+            })
+            .collect(),
+    );
+    group(
+        3.0,
+        firefox
+            .clone()
+            .map(|v| {
+                format!(
+                    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:{v}.0) Gecko/20100101 Firefox/{v}.0"
+                )
+            })
+            .collect(),
+    );
+    group(
+        0.7,
+        [
+            (124, 110),
+            (125, 111),
+            (126, 112),
+            (127, 113),
+            (128, 114),
+            (130, 115),
+        ]
+        .iter()
+        .map(|(c, o)| {
+            format!(
+                "{} Chrome/{}.0.0.0 Safari/537.36 OPR/{}.0.0.0",
+                windows_chrome, c, o
+            )
+        })
+        .collect(),
+    );
+    group(
+        5.0,
+        chrome
+            .clone()
+            .map(|v| format!("{} Chrome/{}.0.0.0 Safari/537.36", mac_chrome, v))
+            .collect(),
+    );
+    group(
+        4.0,
+        mac_safari
+            .iter()
+            .map(|s| {
+                format!(
+                    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/{} Safari/605.1.15",
+                    s
+                )
+            })
+            .collect(),
+    );
+    group(
+        1.0,
+        firefox
+            .clone()
+            .map(|v| {
+                format!(
+                    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:{v}.0) Gecko/20100101 Firefox/{v}.0"
+                )
+            })
+            .collect(),
+    );
+    group(
+        0.5,
+        chrome
+            .clone()
+            .map(|v| {
+                format!(
+                    "{} Chrome/{v}.0.0.0 Safari/537.36 Edg/{v}.0.0.0",
+                    mac_chrome
+                )
+            })
+            .collect(),
+    );
+    group(
+        1.0,
+        chrome
+            .clone()
+            .map(|v| {
+                format!(
+                    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{}.0.0.0 Safari/537.36",
+                    v
+                )
+            })
+            .collect(),
+    );
+    group(
+        0.7,
+        firefox
+            .clone()
+            .step_by(2)
+            .flat_map(|v| {
+                [
+                    format!("Mozilla/5.0 (X11; Linux x86_64; rv:{v}.0) Gecko/20100101 Firefox/{v}.0"),
+                    format!("Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:{v}.0) Gecko/20100101 Firefox/{v}.0"),
+                ]
+            })
+            .collect(),
+    );
+    group(
+        0.5,
+        chrome
+            .clone()
+            .map(|v| {
+                format!(
+                    "Mozilla/5.0 (X11; CrOS x86_64 14541.0.0) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{}.0.0.0 Safari/537.36",
+                    v
+                )
+            })
+            .collect(),
+    );
+
+    // Mobile
+    group(
+        20.0,
+        iphone_os
+            .iter()
+            .map(|os| {
+                format!(
+                    "Mozilla/5.0 (iPhone; CPU iPhone OS {} like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/{} Mobile/15E148 Safari/604.1",
+                    os,
+                    os.replace('_', ".")
+                )
+            })
+            .collect(),
+    );
+    group(
+        3.0,
+        iphone_os[5..]
+            .iter()
+            .step_by(2)
+            .flat_map(|os| {
+                chrome.clone().step_by(2).map(move |v| {
+                    format!(
+                        "Mozilla/5.0 (iPhone; CPU iPhone OS {} like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) CriOS/{}.0.{}.{} Mobile/15E148 Safari/604.1",
+                        os,
+                        v,
+                        chrome_build(v),
+                        (v % 7) * 11 + 40
+                    )
+                })
+            })
+            .collect(),
+    );
+    group(
+        3.0,
+        ipad_os
+            .iter()
+            .map(|os| {
+                format!(
+                    "Mozilla/5.0 (iPad; CPU OS {} like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/{} Mobile/15E148 Safari/604.1",
+                    os,
+                    os.replace('_', ".")
+                )
+            })
+            .collect(),
+    );
+    group(
+        12.0,
+        chrome
+            .clone()
+            .map(|v| {
+                format!(
+                    "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{}.0.0.0 Mobile Safari/537.36",
+                    v
+                )
+            })
+            .collect(),
+    );
+    group(
+        5.0,
+        chrome
+            .clone()
+            .step_by(3)
+            .flat_map(|v| {
+                android_phones.iter().map(move |(android, model)| {
+                    format!(
+                        "Mozilla/5.0 (Linux; Android {}; {}) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{}.0.{}.{} Mobile Safari/537.36",
+                        android,
+                        model,
+                        v,
+                        chrome_build(v),
+                        (v % 5) * 17 + 60
+                    )
+                })
+            })
+            .collect(),
+    );
+    group(
+        3.0,
+        [(23, 115), (24, 117), (25, 121), (26, 122)]
+            .iter()
+            .flat_map(|(samsung, chrome)| {
+                android_phones[..5].iter().map(move |(android, model)| {
+                    format!(
+                        "Mozilla/5.0 (Linux; Android {}; {}) AppleWebKit/537.36 (KHTML, like Gecko) SamsungBrowser/{}.0 Chrome/{}.0.0.0 Mobile Safari/537.36",
+                        android, model, samsung, chrome
+                    )
+                })
+            })
+            .collect(),
+    );
+    group(
+        1.0,
+        chrome
+            .clone()
+            .map(|v| {
+                format!(
+                    "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{}.0.0.0 Safari/537.36",
+                    v
+                )
+            })
+            .collect(),
+    );
+    group(
+        0.5,
+        firefox
+            .clone()
+            .step_by(2)
+            .map(|v| {
+                format!("Mozilla/5.0 (Android 14; Mobile; rv:{v}.0) Gecko/{v}.0 Firefox/{v}.0")
+            })
+            .collect(),
+    );
+
+    // Shopping and wallet apps making requests with their own user agent
+    group(
+        5.0,
+        [
+            "ShopMate/4.9.2",
+            "ShopMate/4.10.0",
+            "PayWallet/7.3.1",
+            "PayWallet/7.4.0",
+        ]
+        .iter()
+        .flat_map(|app| {
+            let ios = iphone_os[6..].iter().step_by(2).map(move |os| {
+                format!("{} (iPhone; iOS {}; Scale/3.00)", app, os.replace('_', "."))
+            });
+            let android = android_phones[3..].iter().map(move |(android, model)| {
+                format!("{} (Linux; Android {}; {}; Mobile)", app, android, model)
+            });
+            ios.chain(android)
+        })
+        .collect(),
+    );
+
+    agents
+}
+
+// Build number of each Chrome major version, which full version strings
+// carry after the major version
+fn chrome_build(major: u32) -> u32 {
+    const BUILDS: [u32; 12] = [
+        6099, 6167, 6261, 6312, 6367, 6422, 6478, 6533, 6613, 6668, 6723, 6778,
+    ];
+    BUILDS[(major - 120) as usize]
+}
+
+// Weight of each built-in user agent, for looking up user agents a profile
+// lists without weights
+pub fn builtin_weights() -> HashMap<String, f64> {
+    builtin_user_agents().into_iter().collect()
+}
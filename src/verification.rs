@@ -0,0 +1,58 @@
+use crate::{
+    model::{DeclineReason, Transaction},
+    text::Text,
+};
+use rand::Rng;
+
+// AVS result codes: street address and postal code match (Y), address only
+// (A), postal code only (Z), neither (N)
+const AVS_CODES: [&str; 4] = ["Y", "A", "Z", "N"];
+// CVV result codes: match (M), no match (N), not processed (P)
+const CVV_CODES: [&str; 3] = ["M", "N", "P"];
+
+// Result code weights, in code order, for genuine payments, for payments
+// declined as an invalid card or as suspicious, and for fraud
+const AVS_GENUINE: [f64; 4] = [0.82, 0.04, 0.09, 0.05];
+const AVS_INVALID_CARD: [f64; 4] = [0.25, 0.1, 0.2, 0.45];
+const AVS_SUSPICIOUS: [f64; 4] = [0.3, 0.15, 0.2, 0.35];
+const AVS_FRAUD: [f64; 4] = [0.4, 0.1, 0.2, 0.3];
+const CVV_GENUINE: [f64; 3] = [0.96, 0.01, 0.03];
+const CVV_INVALID_CARD: [f64; 3] = [0.25, 0.7, 0.05];
+const CVV_SUSPICIOUS: [f64; 3] = [0.6, 0.3, 0.1];
+const CVV_FRAUD: [f64; 3] = [0.7, 0.2, 0.1];
+
+// Set the AVS and CVV results a card-not-present transaction got back from
+// the issuer. Invalid cards mostly fail the CVV check and suspicious or
+// fraudulent payments the address check, while genuine ones mostly match.
+// The CVV result is only set when a CVV was sent, and card-present
+// transactions have neither.
+pub fn verify<R: Rng + ?Sized>(rng: &mut R, tx: &mut Transaction) {
+    if tx.channel.is_card_present() {
+        tx.avs_result = None;
+        tx.cvv_result = None;
+        return;
+    }
+    let (avs, cvv) = match tx.decline_reason {
+        Some(DeclineReason::InvalidCard) => (AVS_INVALID_CARD, CVV_INVALID_CARD),
+        Some(DeclineReason::SuspiciousActivity) => (AVS_SUSPICIOUS, CVV_SUSPICIOUS),
+        _ if tx.is_fraud => (AVS_FRAUD, CVV_FRAUD),
+        _ => (AVS_GENUINE, CVV_GENUINE),
+    };
+    tx.avs_result = Some(Text::from_static(sample(rng, &AVS_CODES, &avs)));
+    tx.cvv_result = tx
+        .cvv
+        .is_some()
+        .then(|| Text::from_static(sample(rng, &CVV_CODES, &cvv)));
+}
+
+// A code drawn by its weight, which together add up to one
+fn sample<'a, R: Rng + ?Sized>(rng: &mut R, codes: &[&'a str], weights: &[f64]) -> &'a str {
+    let mut draw = rng.r#gen::<f64>();
+    for (code, weight) in codes.iter().zip(weights) {
+        if draw < *weight {
+            return code;
+        }
+        draw -= weight;
+    }
+    codes[codes.len() - 1]
+}
@@ -0,0 +1,80 @@
+use crate::{device::Device, text::Text};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Digital wallets that pay with a network token instead of the card number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Wallet {
+    ApplePay,
+    GooglePay,
+    SamsungPay,
+}
+
+impl Wallet {
+    pub const ALL: [Wallet; 3] = [Wallet::ApplePay, Wallet::GooglePay, Wallet::SamsungPay];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Wallet::ApplePay => "apple_pay",
+            Wallet::GooglePay => "google_pay",
+            Wallet::SamsungPay => "samsung_pay",
+        }
+    }
+
+    // Wallet identifier that networks carry with wallet transactions, as in
+    // Mastercard's DE48 subelement 26
+    pub fn wallet_id(&self) -> &'static str {
+        match self {
+            Wallet::ApplePay => "103",
+            Wallet::GooglePay => "216",
+            Wallet::SamsungPay => "217",
+        }
+    }
+
+    // 11-digit ID the token service provider assigned to the wallet as a
+    // token requestor
+    pub fn token_requestor_id(&self) -> &'static str {
+        match self {
+            Wallet::ApplePay => "40010030273",
+            Wallet::GooglePay => "40010075001",
+            Wallet::SamsungPay => "40010043095",
+        }
+    }
+
+    // The wallet a customer would provision their card into on the given
+    // device: Apple Pay on Apple devices, Samsung Pay or Google Pay on
+    // Samsung phones, and Google Pay everywhere else
+    pub fn for_device<R: Rng + ?Sized>(rng: &mut R, device: &Device) -> Wallet {
+        match device.platform.as_str() {
+            "iOS" | "macOS" => Wallet::ApplePay,
+            "Android" if device.user_agent.contains("SM-") && rng.gen_bool(0.5) => {
+                Wallet::SamsungPay
+            }
+            _ => Wallet::GooglePay,
+        }
+    }
+}
+
+// Wallet payments of a profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WalletConfig {
+    // Share of online and contactless purchases paid with the customer's
+    // wallet
+    pub rate: f64,
+}
+
+impl Default for WalletConfig {
+    fn default() -> Self {
+        Self { rate: 0.3 }
+    }
+}
+
+// A card provisioned into a wallet, with the device PAN that stands in for
+// the card number
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletToken {
+    pub wallet: Wallet,
+    pub token_pan: Text,
+}